@@ -0,0 +1,146 @@
+//! Compute unit comparisons across multiple program implementations.
+//!
+//! The comparison bencher runs the same set of benches against several
+//! Mollusk instances - each configured with a different ELF for the same
+//! program ID - and writes a single markdown table comparing compute unit
+//! usage per bench. The first implementation is treated as the baseline, and
+//! every other implementation's column includes its percentage delta against
+//! it.
+//!
+//! ```rust,ignore
+//! use {
+//!     mollusk_svm_bencher::MolluskComputeUnitComparisonBencher,
+//!     mollusk_svm::Mollusk,
+//!     /* ... */
+//! };
+//!
+//! let mollusk_a = Mollusk::new(&program_id, "my_program");
+//! let mollusk_b = Mollusk::new(&program_id, "my_program_optimized");
+//!
+//! MolluskComputeUnitComparisonBencher::new()
+//!     .implementation("baseline", mollusk_a)
+//!     .implementation("optimized", mollusk_b)
+//!     .bench(("bench0", &instruction0, &accounts0))
+//!     .bench(("bench1", &instruction1, &accounts1))
+//!     .must_pass(true)
+//!     .out_dir("../target/benches")
+//!     .execute();
+//! ```
+//!
+//! The resulting `compute_units_comparison.md` file is overwritten on each
+//! run, and contains a single table:
+//!
+//! ```markdown
+//! | Name   | baseline | optimized       |
+//! |--------|----------|-----------------|
+//! | bench0 | 450      | 400 (-11.11%)   |
+//! | bench1 | 1,204    | 1,204 (--)      |
+//! ```
+
+use {
+    crate::{get_trezoa_version, result::write_comparison_results, Bench},
+    chrono::Utc,
+    mollusk_svm::{result::ProgramResult, Mollusk},
+    std::path::PathBuf,
+};
+
+/// Mollusk's compute unit comparison bencher.
+///
+/// Allows developers to compare compute unit usage across multiple
+/// implementations of the same program.
+pub struct MolluskComputeUnitComparisonBencher<'a> {
+    benches: Vec<Bench<'a>>,
+    implementations: Vec<(&'a str, Mollusk)>,
+    must_pass: bool,
+    out_dir: PathBuf,
+}
+
+impl Default for MolluskComputeUnitComparisonBencher<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> MolluskComputeUnitComparisonBencher<'a> {
+    /// Create a new comparison bencher, to which implementations, benches,
+    /// and configurations can be added.
+    pub fn new() -> Self {
+        let mut out_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        out_dir.push("benches");
+        Self {
+            benches: Vec::new(),
+            implementations: Vec::new(),
+            must_pass: false,
+            out_dir,
+        }
+    }
+
+    /// Add an implementation to compare. The first implementation added is
+    /// used as the baseline.
+    pub fn implementation(mut self, name: &'a str, mollusk: Mollusk) -> Self {
+        self.implementations.push((name, mollusk));
+        self
+    }
+
+    /// Add a bench to the bencher. Each bench is run against every
+    /// implementation.
+    pub fn bench(mut self, bench: Bench<'a>) -> Self {
+        self.benches.push(bench);
+        self
+    }
+
+    /// Set whether the bencher should panic if a program execution fails.
+    pub const fn must_pass(mut self, must_pass: bool) -> Self {
+        self.must_pass = must_pass;
+        self
+    }
+
+    /// Set the output directory for the results.
+    pub fn out_dir(mut self, out_dir: &str) -> Self {
+        self.out_dir = PathBuf::from(out_dir);
+        self
+    }
+
+    /// Execute the benches against all implementations.
+    pub fn execute(&mut self) {
+        if self.implementations.is_empty() {
+            panic!("At least one implementation is required to run a comparison");
+        }
+
+        let table_header = Utc::now().to_string();
+        let trezoa_version = get_trezoa_version();
+        let implementation_names = self
+            .implementations
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        let bench_results = std::mem::take(&mut self.benches)
+            .into_iter()
+            .map(|(name, instruction, accounts)| {
+                let cus = self
+                    .implementations
+                    .iter()
+                    .map(|(implementation, mollusk)| {
+                        let result = mollusk.process_instruction(instruction, accounts);
+                        if self.must_pass && result.program_result != ProgramResult::Success {
+                            panic!(
+                                "Program execution failed for implementation `{}`, but \
+                                 `must_pass` was set. Error: {:?}",
+                                implementation, result.program_result
+                            );
+                        }
+                        result.compute_units_consumed
+                    })
+                    .collect::<Vec<_>>();
+                (name, cus)
+            })
+            .collect::<Vec<_>>();
+        write_comparison_results(
+            &self.out_dir,
+            &table_header,
+            &trezoa_version,
+            &implementation_names,
+            &bench_results,
+        );
+    }
+}
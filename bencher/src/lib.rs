@@ -55,16 +55,32 @@
 //! | bench2 | 1,204 | +754   |
 //! | bench3 | 2,811 | +2,361 |
 //! ```
+//!
+//! New tables are prepended, so the file accumulates a history of compute
+//! unit usage. To keep a compact rolling history, `max_sections(n)` drops all
+//! but the `n` most recent tables. See [`result::append_results`].
+//!
+//! For dashboards and bots, `json(true)` additionally writes the results to
+//! `compute_units.json`, using a stable schema. See
+//! [`result::write_json_results`].
+//!
+//! To skip building the bencher by hand, declare the benches with the
+//! [`mollusk_bench!`] macro, which runs them and writes the same report.
+//!
+//! To compare compute unit usage across multiple implementations of the same
+//! program, see [`MolluskComputeUnitComparisonBencher`].
 
+pub mod comparison;
+mod macros;
 pub mod result;
 
+pub use comparison::MolluskComputeUnitComparisonBencher;
 use {
     chrono::Utc,
-    mollusk_svm::{result::ProgramResult, Mollusk},
-    result::{write_results, MolluskComputeUnitBenchResult},
+    mollusk_svm::{prelude::Pubkey, result::ProgramResult, Mollusk},
+    result::{append_results, write_json_results, MolluskComputeUnitBenchResult},
     trezoa_account::Account,
     trezoa_instruction::Instruction,
-    trezoa_pubkey::Pubkey,
     std::{path::PathBuf, process::Command},
 };
 
@@ -76,6 +92,9 @@ pub type Bench<'a> = (&'a str, &'a Instruction, &'a [(Pubkey, Account)]);
 /// Allows developers to bench test compute unit usage on their programs.
 pub struct MolluskComputeUnitBencher<'a> {
     benches: Vec<Bench<'a>>,
+    git_sha: Option<String>,
+    json: bool,
+    max_sections: Option<usize>,
     mollusk: Mollusk,
     must_pass: bool,
     out_dir: PathBuf,
@@ -88,6 +107,9 @@ impl<'a> MolluskComputeUnitBencher<'a> {
         out_dir.push("benches");
         Self {
             benches: Vec::new(),
+            git_sha: None,
+            json: false,
+            max_sections: None,
             mollusk,
             must_pass: false,
             out_dir,
@@ -112,9 +134,32 @@ impl<'a> MolluskComputeUnitBencher<'a> {
         self
     }
 
+    /// Set whether the bencher should also write a JSON report
+    /// (`compute_units.json`) alongside the markdown file.
+    pub const fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// Set the maximum number of tables to keep in the markdown report.
+    ///
+    /// When a new table is added, the oldest tables beyond this limit are
+    /// dropped. By default, every table is kept.
+    pub const fn max_sections(mut self, max_sections: usize) -> Self {
+        self.max_sections = Some(max_sections);
+        self
+    }
+
+    /// Set the git commit SHA to record in the JSON report.
+    pub fn git_sha(mut self, git_sha: &str) -> Self {
+        self.git_sha = Some(git_sha.to_string());
+        self
+    }
+
     /// Execute the benches.
     pub fn execute(&mut self) {
-        let table_header = Utc::now().to_string();
+        let now = Utc::now();
+        let table_header = now.to_string();
         let trezoa_version = get_trezoa_version();
         let bench_results = std::mem::take(&mut self.benches)
             .into_iter()
@@ -134,7 +179,22 @@ impl<'a> MolluskComputeUnitBencher<'a> {
                 MolluskComputeUnitBenchResult::new(name, result)
             })
             .collect::<Vec<_>>();
-        write_results(&self.out_dir, &table_header, &trezoa_version, bench_results);
+        if self.json {
+            write_json_results(
+                &self.out_dir,
+                &now.to_rfc3339(),
+                &trezoa_version,
+                self.git_sha.as_deref(),
+                &bench_results,
+            );
+        }
+        append_results(
+            &self.out_dir,
+            &table_header,
+            &trezoa_version,
+            bench_results,
+            self.max_sections,
+        );
     }
 }
 
@@ -0,0 +1,45 @@
+/// Declare and run a set of compute unit benches.
+///
+/// Expands to a `MolluskComputeUnitBencher` loaded with the provided benches,
+/// and executes it. Each bench is a tuple of a name, an `Instruction`, and
+/// the accounts, as anything `Vec::from` accepts (a `Vec`, an array, or a
+/// slice). Any trailing `method: value` pairs are applied as bencher
+/// configurations, such as `must_pass`, `out_dir`, or `json`.
+///
+/// ```rust,ignore
+/// use {mollusk_svm::Mollusk, mollusk_svm_bencher::mollusk_bench};
+///
+/// fn main() {
+///     /* Instruction & accounts setup ... */
+///
+///     mollusk_bench! {
+///         mollusk: Mollusk::new(&program_id, "my_program"),
+///         benches: [
+///             ("bench0", instruction0, accounts0),
+///             ("bench1", instruction1, [(key, account.clone())]),
+///         ],
+///         must_pass: true,
+///         out_dir: "../target/benches",
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! mollusk_bench {
+    (
+        mollusk: $mollusk:expr,
+        benches: [
+            $( ($name:expr, $instruction:expr, $accounts:expr $(,)?) ),* $(,)?
+        ]
+        $(, $option:ident : $value:expr )* $(,)?
+    ) => {{
+        let benches = ::std::vec![
+            $( ($name, $instruction, ::std::vec::Vec::from($accounts)) ),*
+        ];
+        let mut bencher = $crate::MolluskComputeUnitBencher::new($mollusk)
+            $( .$option($value) )*;
+        for (name, instruction, accounts) in &benches {
+            bencher = bencher.bench((*name, instruction, accounts.as_slice()));
+        }
+        bencher.execute();
+    }};
+}
@@ -3,7 +3,7 @@
 use {
     mollusk_svm::result::InstructionResult,
     num_format::{Locale, ToFormattedString},
-    std::path::Path,
+    std::{fmt, path::Path},
 };
 
 pub struct MolluskComputeUnitBenchResult<'a> {
@@ -18,11 +18,33 @@ impl<'a> MolluskComputeUnitBenchResult<'a> {
     }
 }
 
+/// Write the results to the markdown report (`compute_units.md`).
+///
+/// If any compute unit values changed since the most recent table in the
+/// report, a new table is prepended. Otherwise the report is left untouched.
+/// Equivalent to `append_results` without a retention limit.
 pub fn write_results(
     out_dir: &Path,
     table_header: &str,
     trezoa_version: &str,
     results: Vec<MolluskComputeUnitBenchResult>,
+) {
+    append_results(out_dir, table_header, trezoa_version, results, None);
+}
+
+/// Append the results to the markdown report (`compute_units.md`) as a new
+/// section, keeping at most `max_sections` sections.
+///
+/// Each section is a table headed by `table_header`, and the newest section
+/// comes first. A section is only added if any compute unit values changed
+/// since the previous one, but the report is trimmed to `max_sections`
+/// either way, so older history rolls off as new sections are added.
+pub fn append_results(
+    out_dir: &Path,
+    table_header: &str,
+    trezoa_version: &str,
+    results: Vec<MolluskComputeUnitBenchResult>,
+    max_sections: Option<usize>,
 ) {
     let path = out_dir.join("compute_units.md");
 
@@ -74,10 +96,205 @@ pub fn write_results(
     }
 
     // Only create a new table if there were changes.
+    let existing_content = existing_content.unwrap_or_default();
+    let mut contents = String::new();
     if !no_changes {
         md_table.push('\n');
-        prepend_to_md_file(&path, &md_table);
+        contents.push_str(&md_table);
+    }
+    contents.push_str(&existing_content);
+
+    if let Some(max_sections) = max_sections {
+        contents.truncate(md_sections_len(&contents, max_sections));
+    }
+
+    if contents != existing_content {
+        write_md_file(&path, &contents);
+    }
+}
+
+/// The change in compute units consumed by a bench, against a baseline
+/// report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComputeUnitDelta {
+    /// The name of the bench.
+    pub name: String,
+    /// The compute units consumed in the baseline report.
+    pub baseline_cus: u64,
+    /// The compute units consumed in this run.
+    pub cus_consumed: u64,
+}
+
+impl ComputeUnitDelta {
+    /// The change in compute units consumed. Positive for regressions.
+    pub fn delta(&self) -> i64 {
+        self.cus_consumed as i64 - self.baseline_cus as i64
+    }
+}
+
+impl fmt::Display for ComputeUnitDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let delta = self.delta();
+        let sign = if delta > 0 { "+" } else { "" };
+        write!(
+            f,
+            "{}: {} -> {} ({}{})",
+            self.name,
+            self.baseline_cus.to_formatted_string(&Locale::en),
+            self.cus_consumed.to_formatted_string(&Locale::en),
+            sign,
+            delta.to_formatted_string(&Locale::en),
+        )
+    }
+}
+
+/// Compare the results against the most recent table of a markdown report,
+/// as written by `append_results`.
+///
+/// `path` is the report, or a directory containing `compute_units.md`.
+/// Benches missing from the baseline are skipped.
+pub fn compare_with_baseline(
+    path: &Path,
+    results: &[MolluskComputeUnitBenchResult],
+) -> std::io::Result<Vec<ComputeUnitDelta>> {
+    let path = if path.is_dir() {
+        path.join("compute_units.md")
+    } else {
+        path.to_path_buf()
+    };
+    let content = std::fs::read_to_string(path)?;
+    let baseline = parse_last_md_table(&content);
+
+    Ok(results
+        .iter()
+        .filter_map(|result| {
+            let prev = baseline
+                .iter()
+                .find(|prev_result| prev_result.name == result.name)?;
+            Some(ComputeUnitDelta {
+                name: result.name.to_string(),
+                baseline_cus: prev.cus_consumed,
+                cus_consumed: result.cus_consumed,
+            })
+        })
+        .collect())
+}
+
+/// Version of the JSON report schema, incremented on breaking changes.
+pub const JSON_SCHEMA_VERSION: u64 = 1;
+
+/// Write the results as JSON, for consumption by dashboards and bots.
+///
+/// Unlike the markdown report, which keeps a history of tables, the JSON
+/// report is overwritten with the latest run:
+///
+/// ```json
+/// {
+///   "schema_version": 1,
+///   "timestamp": "2025-01-01T00:00:00+00:00",
+///   "trezoa_version": "trezoa-cli 3.0.0",
+///   "git_sha": "0123abc",
+///   "benches": [
+///     { "name": "bench0", "cus": 450 }
+///   ]
+/// }
+/// ```
+///
+/// `git_sha` is `null` unless provided.
+pub fn write_json_results(
+    out_dir: &Path,
+    timestamp: &str,
+    trezoa_version: &str,
+    git_sha: Option<&str>,
+    results: &[MolluskComputeUnitBenchResult],
+) {
+    let path = out_dir.join("compute_units.json");
+
+    let benches = results
+        .iter()
+        .map(|result| {
+            serde_json::json!({
+                "name": result.name,
+                "cus": result.cus_consumed,
+            })
+        })
+        .collect::<Vec<_>>();
+    let report = serde_json::json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "timestamp": timestamp,
+        "trezoa_version": trezoa_version,
+        "git_sha": git_sha,
+        "benches": benches,
+    });
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    let mut contents = serde_json::to_string_pretty(&report).unwrap();
+    contents.push('\n');
+    std::fs::write(path, contents).unwrap();
+}
+
+/// Write a comparison table of compute unit usage across implementations.
+///
+/// `results` holds, per bench, the compute units consumed by each
+/// implementation, in the same order as `implementations`. The first
+/// implementation is the baseline for percentage deltas.
+pub fn write_comparison_results(
+    out_dir: &Path,
+    table_header: &str,
+    trezoa_version: &str,
+    implementations: &[&str],
+    results: &[(&str, Vec<u64>)],
+) {
+    let path = out_dir.join("compute_units_comparison.md");
+
+    let mut md_table = format!(
+        "#### {}\n\nTrezoa CLI Version: {}\n\n| Name |",
+        table_header, trezoa_version,
+    );
+    for implementation in implementations {
+        md_table.push_str(&format!(" {} |", implementation));
+    }
+    md_table.push_str("\n|------|");
+    for _ in implementations {
+        md_table.push_str("------|");
+    }
+    md_table.push('\n');
+
+    for (name, cus) in results {
+        md_table.push_str(&format!("| {} |", name));
+        let baseline = cus[0];
+        for (i, cus_consumed) in cus.iter().enumerate() {
+            let formatted = cus_consumed.to_formatted_string(&Locale::en);
+            if i == 0 {
+                md_table.push_str(&format!(" {} |", formatted));
+            } else {
+                md_table.push_str(&format!(
+                    " {} ({}) |",
+                    formatted,
+                    percent_delta(baseline, *cus_consumed)
+                ));
+            }
+        }
+        md_table.push('\n');
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(path, md_table).unwrap();
+}
+
+fn percent_delta(baseline: u64, cus_consumed: u64) -> String {
+    if cus_consumed == baseline {
+        return "--".to_string();
     }
+    if baseline == 0 {
+        return "n/a".to_string();
+    }
+    let delta = (cus_consumed as f64 - baseline as f64) / baseline as f64 * 100.0;
+    format!("{:+.2}%", delta)
 }
 
 fn md_header(table_header: &str, trezoa_version: &str) -> String {
@@ -111,19 +328,19 @@ fn parse_last_md_table(content: &str) -> Vec<MolluskComputeUnitBenchResult<'_>>
     results
 }
 
-fn prepend_to_md_file(path: &Path, content: &str) {
+/// The length of the first `max_sections` sections of a markdown report.
+fn md_sections_len(content: &str, max_sections: usize) -> usize {
+    content
+        .match_indices("####")
+        .map(|(index, _)| index)
+        .filter(|index| *index == 0 || content[..*index].ends_with('\n'))
+        .nth(max_sections)
+        .unwrap_or(content.len())
+}
+
+fn write_md_file(path: &Path, contents: &str) {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent).unwrap();
     }
-
-    let contents = if path.exists() {
-        std::fs::read_to_string(path).unwrap()
-    } else {
-        String::new()
-    };
-
-    let mut new_contents = content.to_string();
-    new_contents.push_str(&contents);
-
-    std::fs::write(path, new_contents).unwrap();
+    std::fs::write(path, contents).unwrap();
 }
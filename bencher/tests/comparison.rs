@@ -0,0 +1,88 @@
+use {
+    mollusk_svm::{prelude::Pubkey, Mollusk},
+    mollusk_svm_bencher::MolluskComputeUnitComparisonBencher,
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+};
+
+#[test]
+fn test_comparison_markdown() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+    trezoa_logger::setup_with("");
+
+    let program_id = Pubkey::new_unique();
+
+    let instruction = Instruction::new_with_bytes(program_id, &[0], vec![]);
+    let accounts = vec![];
+
+    let mollusk_a = Mollusk::new(&program_id, "test_program_primary");
+    let mollusk_b = Mollusk::new(&program_id, "test_program_primary");
+
+    let out_dir = "../target/benches/comparison";
+
+    MolluskComputeUnitComparisonBencher::new()
+        .implementation("impl_a", mollusk_a)
+        .implementation("impl_b", mollusk_b)
+        .bench(("bench0", &instruction, &accounts))
+        .bench(("bench1", &instruction, &accounts))
+        .must_pass(true)
+        .out_dir(out_dir)
+        .execute();
+
+    let content =
+        std::fs::read_to_string(format!("{}/compute_units_comparison.md", out_dir)).unwrap();
+    let mut lines = content.lines().skip(4);
+    assert_eq!(lines.next().unwrap(), "| Name | impl_a | impl_b |");
+    assert_eq!(lines.next().unwrap(), "|------|------|------|");
+    // Identical ELFs consume identical compute units.
+    assert!(lines.next().unwrap().ends_with("(--) |"));
+    assert!(lines.next().unwrap().ends_with("(--) |"));
+    assert!(lines.next().is_none());
+}
+
+#[test]
+fn test_comparison_markdown_different_programs() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+    trezoa_logger::setup_with("");
+
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+
+    // The primary program treats this as a no-op, while the CPI target
+    // program writes it to the account.
+    let instruction =
+        Instruction::new_with_bytes(program_id, &[0], vec![AccountMeta::new(key, true)]);
+    let accounts = vec![(key, Account::new(1_000_000, 1, &program_id))];
+
+    let mollusk_a = Mollusk::new(&program_id, "test_program_primary");
+    let mollusk_b = Mollusk::new(&program_id, "test_program_cpi_target");
+
+    let baseline = mollusk_a
+        .process_instruction(&instruction, &accounts)
+        .compute_units_consumed;
+    let other = mollusk_b
+        .process_instruction(&instruction, &accounts)
+        .compute_units_consumed;
+    assert_ne!(baseline, other);
+
+    let out_dir = "../target/benches/comparison_different_programs";
+
+    MolluskComputeUnitComparisonBencher::new()
+        .implementation("primary", mollusk_a)
+        .implementation("cpi_target", mollusk_b)
+        .bench(("bench0", &instruction, &accounts))
+        .must_pass(true)
+        .out_dir(out_dir)
+        .execute();
+
+    let content =
+        std::fs::read_to_string(format!("{}/compute_units_comparison.md", out_dir)).unwrap();
+    let mut lines = content.lines().skip(4);
+    assert_eq!(lines.next().unwrap(), "| Name | primary | cpi_target |");
+    assert_eq!(lines.next().unwrap(), "|------|------|------|");
+    let row = lines.next().unwrap();
+    assert!(row.starts_with("| bench0 |"));
+    let delta = (other as f64 - baseline as f64) / baseline as f64 * 100.0;
+    assert!(row.ends_with(&format!(" ({:+.2}%) |", delta)));
+    assert!(lines.next().is_none());
+}
@@ -0,0 +1,41 @@
+use {
+    mollusk_svm::{prelude::Pubkey, Mollusk},
+    mollusk_svm_bencher::MolluskComputeUnitBencher,
+    trezoa_instruction::Instruction,
+};
+
+#[test]
+fn test_json() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+    trezoa_logger::setup_with("");
+
+    let program_id = Pubkey::new_unique();
+
+    let instruction = Instruction::new_with_bytes(program_id, &[0], vec![]);
+    let accounts = vec![];
+
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    MolluskComputeUnitBencher::new(mollusk)
+        .bench(("bench0", &instruction, &accounts))
+        .bench(("bench1", &instruction, &accounts))
+        .must_pass(true)
+        .json(true)
+        .git_sha("0123abc")
+        .out_dir("../target/benches-json")
+        .execute();
+
+    let contents = std::fs::read_to_string("../target/benches-json/compute_units.json").unwrap();
+    let report: serde_json::Value = serde_json::from_str(&contents).unwrap();
+
+    assert_eq!(report["schema_version"], 1);
+    assert_eq!(report["git_sha"], "0123abc");
+    assert!(report["timestamp"].is_string());
+    assert!(report["trezoa_version"].is_string());
+
+    let benches = report["benches"].as_array().unwrap();
+    assert_eq!(benches.len(), 2);
+    assert_eq!(benches[0]["name"], "bench0");
+    assert_eq!(benches[1]["name"], "bench1");
+    assert!(benches[0]["cus"].as_u64().unwrap() > 0);
+}
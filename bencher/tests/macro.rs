@@ -0,0 +1,33 @@
+use {
+    mollusk_svm::{prelude::Pubkey, Mollusk},
+    mollusk_svm_bencher::mollusk_bench,
+    trezoa_account::Account,
+    trezoa_instruction::Instruction,
+};
+
+#[test]
+fn test_macro() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+    trezoa_logger::setup_with("");
+
+    let program_id = Pubkey::new_unique();
+
+    let instruction = Instruction::new_with_bytes(program_id, &[0], vec![]);
+    let account = (Pubkey::new_unique(), Account::default());
+
+    mollusk_bench! {
+        mollusk: Mollusk::new(&program_id, "test_program_primary"),
+        benches: [
+            ("bench0", instruction.clone(), vec![account.clone()]),
+            ("bench1", instruction.clone(), [account.clone()]),
+            ("bench2", instruction, &[account][..]),
+        ],
+        must_pass: true,
+        out_dir: "../target/benches-macro",
+    }
+
+    let markdown = std::fs::read_to_string("../target/benches-macro/compute_units.md").unwrap();
+    assert!(markdown.contains("bench0"));
+    assert!(markdown.contains("bench1"));
+    assert!(markdown.contains("bench2"));
+}
@@ -1,6 +1,11 @@
 use {
-    mollusk_svm::Mollusk, mollusk_svm_bencher::MolluskComputeUnitBencher,
-    trezoa_instruction::Instruction, trezoa_pubkey::Pubkey,
+    mollusk_svm::{prelude::Pubkey, result::InstructionResult, Mollusk},
+    mollusk_svm_bencher::{
+        result::{append_results, compare_with_baseline, MolluskComputeUnitBenchResult},
+        MolluskComputeUnitBencher,
+    },
+    std::path::Path,
+    trezoa_instruction::Instruction,
 };
 
 #[test]
@@ -27,3 +32,75 @@ fn test_markdown() {
         .out_dir("../target/benches")
         .execute();
 }
+
+#[test]
+fn test_markdown_max_sections() {
+    let out_dir = Path::new("../target/benches-max-sections");
+    let _ = std::fs::remove_dir_all(out_dir);
+
+    let bench = |compute_units_consumed| {
+        let result = InstructionResult {
+            compute_units_consumed,
+            ..Default::default()
+        };
+        vec![MolluskComputeUnitBenchResult::new("bench0", result)]
+    };
+
+    for (i, cus) in [100, 200, 300, 400].into_iter().enumerate() {
+        let header = format!("run{}", i);
+        append_results(out_dir, &header, "Unknown", bench(cus), Some(2));
+    }
+    // No changes, so no new section.
+    append_results(out_dir, "run4", "Unknown", bench(400), Some(2));
+
+    let contents = std::fs::read_to_string(out_dir.join("compute_units.md")).unwrap();
+    let headers = contents
+        .lines()
+        .filter(|line| line.starts_with("####"))
+        .collect::<Vec<_>>();
+    assert_eq!(headers, vec!["#### run3", "#### run2"]);
+    assert!(contents.contains("| bench0 | 400 | +100 |"));
+}
+
+#[test]
+fn test_compare_with_baseline() {
+    let out_dir = Path::new("../target/benches-baseline");
+    let _ = std::fs::remove_dir_all(out_dir);
+
+    let result = |compute_units_consumed| InstructionResult {
+        compute_units_consumed,
+        ..Default::default()
+    };
+
+    append_results(
+        out_dir,
+        "baseline",
+        "Unknown",
+        vec![
+            MolluskComputeUnitBenchResult::new("bench0", result(1_000)),
+            MolluskComputeUnitBenchResult::new("bench1", result(2_000)),
+        ],
+        None,
+    );
+
+    let deltas = compare_with_baseline(
+        out_dir,
+        &[
+            MolluskComputeUnitBenchResult::new("bench0", result(1_250)),
+            MolluskComputeUnitBenchResult::new("bench1", result(1_900)),
+            // Not in the baseline.
+            MolluskComputeUnitBenchResult::new("bench2", result(500)),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        deltas.iter().map(|delta| delta.delta()).collect::<Vec<_>>(),
+        vec![250, -100]
+    );
+    assert_eq!(deltas[0].to_string(), "bench0: 1,000 -> 1,250 (+250)");
+
+    // The report itself can be passed, rather than its directory.
+    let same = compare_with_baseline(&out_dir.join("compute_units.md"), &[]).unwrap();
+    assert!(same.is_empty());
+    assert!(compare_with_baseline(&out_dir.join("missing.md"), &[]).is_err());
+}
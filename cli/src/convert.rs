@@ -0,0 +1,155 @@
+//! Fixture layout conversion.
+//!
+//! Converts instruction fixtures between the Mollusk and Firedancer protobuf
+//! layouts, so corpora can be shared between tooling built on either one.
+//!
+//! The layouts don't carry the same information, so a conversion may lose
+//! some of it:
+//!
+//! * Mollusk fixtures record every sysvar, while Firedancer fixtures only
+//!   record the slot. Sysvars which don't match the defaults at that slot are
+//!   lost when converting to Firedancer, and sysvars are rebuilt from the
+//!   defaults when converting to Mollusk.
+//! * Firedancer fixtures only record the compute unit limit, not the rest of
+//!   the compute budget.
+//! * Firedancer fixtures don't record execution time, and Mollusk fixtures
+//!   don't record account seed addresses.
+//!
+//! Each loss is reported as a warning.
+
+use {
+    crate::runner::ProtoLayout,
+    mollusk_svm::{
+        fuzz::{firedancer, mollusk},
+        sysvar::Sysvars,
+        Mollusk,
+    },
+    mollusk_svm_fuzz_fs::FsHandler,
+};
+
+/// Convert the fixture at `fixture_path` from the `from` layout to the other
+/// layout, writing it to `out_dir`. Returns a warning for each piece of
+/// information lost in the conversion.
+pub fn convert_fixture(from: &ProtoLayout, fixture_path: &str, out_dir: &str) -> Vec<String> {
+    match from {
+        ProtoLayout::Mollusk => {
+            let fixture = mollusk_svm_fuzz_fixture::Fixture::load_from_blob_file(fixture_path);
+            let (context, result) = mollusk::load_fixture(&fixture);
+
+            let mut warnings = lost_sysvars(&context.sysvars)
+                .into_iter()
+                .map(|name| {
+                    format!(
+                        "{} sysvar differs from the default at slot {}, and is dropped",
+                        name, context.sysvars.clock.slot,
+                    )
+                })
+                .collect::<Vec<_>>();
+            if result.execution_time != 0 {
+                warnings.push("Execution time is dropped".to_string());
+            }
+
+            let mut mollusk = Mollusk::default();
+            mollusk.compute_budget = context.compute_budget;
+            mollusk.feature_set = context.feature_set;
+            mollusk.slot = context.sysvars.clock.slot;
+            mollusk.sysvars = context.sysvars;
+
+            let converted = firedancer::build_fixture_from_mollusk_test(
+                &mollusk,
+                &context.instruction,
+                &context.accounts,
+                &result,
+            );
+
+            // Read the converted fixture back to catch anything it can't
+            // represent.
+            let (parsed, _) = firedancer::load_firedancer_fixture(&converted);
+            if parsed.compute_budget != mollusk.compute_budget {
+                warnings.push(
+                    "Compute budget differs from the default, and only the compute unit limit is \
+                     kept"
+                        .to_string(),
+                );
+            }
+            for feature_id in mollusk.feature_set.active().keys() {
+                if !parsed.feature_set.is_active(feature_id) {
+                    warnings.push(format!(
+                        "Feature {} is deactivated, so the fixture targets the builtin program",
+                        feature_id,
+                    ));
+                }
+            }
+
+            FsHandler::new(converted).dump_to_blob_file(out_dir);
+            warnings
+        }
+        ProtoLayout::Firedancer => {
+            let fixture =
+                mollusk_svm_fuzz_fixture_firedancer::Fixture::load_from_blob_file(fixture_path);
+            let (context, result) = firedancer::load_firedancer_fixture(&fixture);
+
+            let mut warnings = vec![format!(
+                "Sysvars aren't recorded, so the defaults at slot {} are used",
+                context.slot,
+            )];
+            let has_seed_addresses = fixture
+                .input
+                .accounts
+                .iter()
+                .any(|(_, _, seed_address)| seed_address.is_some())
+                || fixture
+                    .output
+                    .modified_accounts
+                    .iter()
+                    .any(|(_, _, seed_address)| seed_address.is_some());
+            if has_seed_addresses {
+                warnings.push("Account seed addresses are dropped".to_string());
+            }
+
+            let mut mollusk = Mollusk::default();
+            mollusk.compute_budget = context.compute_budget;
+            mollusk.feature_set = context.feature_set;
+            mollusk.slot = context.slot;
+            mollusk.warp_to_slot(context.slot);
+
+            let converted = mollusk::build_fixture_from_mollusk_test(
+                &mollusk,
+                &context.instruction,
+                &context.accounts,
+                &result,
+            );
+
+            FsHandler::new(converted).dump_to_blob_file(out_dir);
+            warnings
+        }
+    }
+}
+
+/// The names of the sysvars which differ from the defaults at the clock's
+/// slot, and so can't be represented by a Firedancer fixture.
+fn lost_sysvars(sysvars: &Sysvars) -> Vec<&'static str> {
+    let mut defaults = Sysvars::default();
+    defaults.warp_to_slot(sysvars.clock.slot);
+
+    let mut lost = Vec::new();
+    if sysvars.clock != defaults.clock {
+        lost.push("Clock");
+    }
+    if sysvars.epoch_rewards != defaults.epoch_rewards {
+        lost.push("EpochRewards");
+    }
+    if sysvars.epoch_schedule != defaults.epoch_schedule {
+        lost.push("EpochSchedule");
+    }
+    if sysvars.rent != defaults.rent {
+        lost.push("Rent");
+    }
+    if sysvars.slot_hashes != defaults.slot_hashes {
+        lost.push("SlotHashes");
+    }
+    if sysvars.stake_history != defaults.stake_history {
+        lost.push("StakeHistory");
+    }
+    lost
+}
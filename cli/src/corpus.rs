@@ -0,0 +1,262 @@
+//! Fixture corpus statistics and management.
+
+use {
+    crate::runner::ProtoLayout,
+    mollusk_svm_fuzz_fixture::proto::InstrFixture as MolluskFixture,
+    mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture as FiredancerFixture,
+    mollusk_svm_fuzz_fs::{corpus::Corpus, SerializableFixture},
+    trezoa_pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap},
+        path::Path,
+    },
+};
+
+// Upper bounds (inclusive) of the instruction data size histogram buckets.
+const DATA_SIZE_BUCKETS: &[usize] = &[0, 8, 32, 128, 512, 1024];
+
+/// The inputs of a single fixture relevant to corpus statistics, extracted
+/// from either protobuf layout.
+struct FixtureSummary {
+    program_id: Pubkey,
+    instruction_data_len: usize,
+    account_count: usize,
+    active_features: Vec<Pubkey>,
+    // The compute budget's limits, by name. Firedancer fixtures only record
+    // the compute units available.
+    compute_budget: Vec<(&'static str, u64)>,
+}
+
+impl FixtureSummary {
+    fn load(proto: &ProtoLayout, fixture_path: &str) -> Self {
+        match proto {
+            ProtoLayout::Mollusk => {
+                let fixture = mollusk_svm_fuzz_fixture::Fixture::load_from_blob_file(fixture_path);
+                let input = fixture.input;
+                let budget = &input.compute_budget;
+                Self {
+                    program_id: input.program_id,
+                    instruction_data_len: input.instruction_data.len(),
+                    account_count: input.accounts.len(),
+                    active_features: sorted_keys(input.feature_set.active().keys()),
+                    compute_budget: vec![
+                        ("Compute unit limit", budget.compute_unit_limit),
+                        ("Heap size", u64::from(budget.heap_size)),
+                        (
+                            "Max instruction stack depth",
+                            budget.max_instruction_stack_depth as u64,
+                        ),
+                        (
+                            "Max instruction trace length",
+                            budget.max_instruction_trace_length as u64,
+                        ),
+                        ("Max call depth", budget.max_call_depth as u64),
+                        ("Stack frame size", budget.stack_frame_size as u64),
+                    ],
+                }
+            }
+            ProtoLayout::Firedancer => {
+                let fixture =
+                    mollusk_svm_fuzz_fixture_firedancer::Fixture::load_from_blob_file(fixture_path);
+                let input = fixture.input;
+                Self {
+                    program_id: input.program_id,
+                    instruction_data_len: input.instruction_data.len(),
+                    account_count: input.accounts.len(),
+                    active_features: sorted_keys(input.epoch_context.feature_set.active().keys()),
+                    compute_budget: vec![("Compute unit limit", input.compute_units_available)],
+                }
+            }
+        }
+    }
+}
+
+fn sorted_keys<'a>(keys: impl Iterator<Item = &'a Pubkey>) -> Vec<Pubkey> {
+    let mut keys = keys.copied().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// Aggregated statistics over a fixture corpus.
+#[derive(Default)]
+pub struct CorpusStats {
+    fixture_count: usize,
+    by_program_id: BTreeMap<Pubkey, usize>,
+    data_size_histogram: BTreeMap<usize, usize>,
+    account_count_distribution: BTreeMap<usize, usize>,
+    feature_set_variants: HashMap<Vec<Pubkey>, usize>,
+    // The range of each compute budget limit, in the order they're first
+    // seen.
+    compute_budget_ranges: Vec<(&'static str, (u64, u64))>,
+}
+
+impl CorpusStats {
+    /// Load every fixture and aggregate its statistics.
+    pub fn collect(proto: &ProtoLayout, fixtures: &[String]) -> Self {
+        let mut stats = Self::default();
+        for fixture_path in fixtures {
+            stats.add(FixtureSummary::load(proto, fixture_path));
+        }
+        stats
+    }
+
+    fn add(&mut self, summary: FixtureSummary) {
+        self.fixture_count += 1;
+
+        *self.by_program_id.entry(summary.program_id).or_default() += 1;
+
+        // Bucket index; the last bucket holds everything above the largest
+        // bound.
+        let bucket = DATA_SIZE_BUCKETS
+            .iter()
+            .position(|bound| summary.instruction_data_len <= *bound)
+            .unwrap_or(DATA_SIZE_BUCKETS.len());
+        *self.data_size_histogram.entry(bucket).or_default() += 1;
+
+        *self
+            .account_count_distribution
+            .entry(summary.account_count)
+            .or_default() += 1;
+
+        *self
+            .feature_set_variants
+            .entry(summary.active_features)
+            .or_default() += 1;
+
+        for (name, value) in summary.compute_budget {
+            match self
+                .compute_budget_ranges
+                .iter_mut()
+                .find(|(range_name, _)| *range_name == name)
+            {
+                Some((_, (min, max))) => {
+                    *min = (*min).min(value);
+                    *max = (*max).max(value);
+                }
+                None => self.compute_budget_ranges.push((name, (value, value))),
+            }
+        }
+    }
+
+    /// Print a summary of the statistics to stdout.
+    pub fn print(&self) {
+        println!("Fixtures: {}", self.fixture_count);
+        if self.fixture_count == 0 {
+            return;
+        }
+
+        println!();
+        println!("Fixtures by program ID:");
+        for (program_id, count) in &self.by_program_id {
+            println!("    {}: {}", program_id, count);
+        }
+
+        println!();
+        println!("Instruction data size (bytes):");
+        for (bucket, count) in &self.data_size_histogram {
+            let label = match *bucket {
+                0 => "0".to_string(),
+                i if i == DATA_SIZE_BUCKETS.len() => {
+                    format!("> {}", DATA_SIZE_BUCKETS[i - 1])
+                }
+                i => format!("{}-{}", DATA_SIZE_BUCKETS[i - 1] + 1, DATA_SIZE_BUCKETS[i]),
+            };
+            println!("    {:>11}: {}", label, count);
+        }
+
+        println!();
+        println!("Account count:");
+        for (account_count, count) in &self.account_count_distribution {
+            println!("    {:>3}: {}", account_count, count);
+        }
+
+        println!();
+        println!("Feature set variants: {}", self.feature_set_variants.len());
+        let mut variants = self
+            .feature_set_variants
+            .iter()
+            .map(|(features, count)| (features.len(), *count))
+            .collect::<Vec<_>>();
+        variants.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (active_features, count) in variants {
+            println!(
+                "    {} active features: {} fixtures",
+                active_features, count
+            );
+        }
+
+        println!();
+        println!("Compute budget:");
+        for (name, (min, max)) in &self.compute_budget_ranges {
+            println!("    {}: {} - {}", name, min, max);
+        }
+    }
+}
+
+/// Remove fixtures whose context duplicates another fixture's. With
+/// `dry_run`, the duplicates are only reported.
+pub fn dedupe(proto: &ProtoLayout, path: &str, dry_run: bool) {
+    match proto {
+        ProtoLayout::Mollusk => dedupe_corpus(Corpus::<MolluskFixture>::load(path), dry_run),
+        ProtoLayout::Firedancer => dedupe_corpus(Corpus::<FiredancerFixture>::load(path), dry_run),
+    }
+}
+
+fn dedupe_corpus<SF: SerializableFixture>(mut corpus: Corpus<SF>, dry_run: bool) {
+    let fixture_count = corpus.len();
+    let duplicates = if dry_run {
+        corpus
+            .duplicates()
+            .into_iter()
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>()
+    } else {
+        corpus.dedupe()
+    };
+    for path in &duplicates {
+        println!("[DUPLICATE]: {}", path.display());
+    }
+    println!(
+        "{} of {} fixtures are duplicates{}",
+        duplicates.len(),
+        fixture_count,
+        if dry_run { "" } else { ", and were removed" }
+    );
+}
+
+/// Split a corpus into `parts` shards under `out_dir`, by context hash.
+pub fn split(proto: &ProtoLayout, path: &str, out_dir: &str, parts: usize) {
+    let shards = match proto {
+        ProtoLayout::Mollusk => Corpus::<MolluskFixture>::load(path).split(out_dir, parts),
+        ProtoLayout::Firedancer => Corpus::<FiredancerFixture>::load(path).split(out_dir, parts),
+    };
+    for shard in shards {
+        println!("{}: {} fixtures", shard.display(), count_fixtures(&shard));
+    }
+}
+
+/// Merge several corpora into `out_dir`, dropping duplicates.
+pub fn merge(proto: &ProtoLayout, paths: &[String], out_dir: &str) {
+    let (fixture_count, written) = match proto {
+        ProtoLayout::Mollusk => {
+            let corpus = Corpus::<MolluskFixture>::load_all(paths);
+            (corpus.len(), corpus.merge_into(out_dir))
+        }
+        ProtoLayout::Firedancer => {
+            let corpus = Corpus::<FiredancerFixture>::load_all(paths);
+            (corpus.len(), corpus.merge_into(out_dir))
+        }
+    };
+    println!(
+        "Merged {} fixtures into {} ({} duplicates dropped)",
+        fixture_count,
+        out_dir,
+        fixture_count - written
+    );
+}
+
+fn count_fixtures(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.count())
+        .unwrap_or(0)
+}
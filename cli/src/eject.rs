@@ -0,0 +1,106 @@
+//! Fixture generation from a test suite run.
+
+use {
+    crate::runner::ProtoLayout,
+    mollusk_svm_fuzz_fixture::proto::InstrFixture as MolluskFixture,
+    mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture as FiredancerFixture,
+    mollusk_svm_fuzz_fs::{corpus::Corpus, SerializableFixture},
+    std::{collections::HashSet, fs, path::Path, process::Command},
+};
+
+/// The fixtures collected from a test suite run.
+pub struct EjectSummary {
+    /// The context hash and file name of each fixture collected.
+    pub collected: Vec<(String, String)>,
+    /// The number of fixtures skipped for duplicating the context of a
+    /// fixture already collected, or already in the target directory.
+    pub duplicates: usize,
+}
+
+impl EjectSummary {
+    /// Print a summary of the collected fixtures to stdout.
+    pub fn print(&self, out_dir: &str) {
+        for (context_hash, file_name) in &self.collected {
+            println!("    {}  {}", context_hash, file_name);
+        }
+        println!(
+            "Collected {} fixtures into {} ({} duplicates skipped)",
+            self.collected.len(),
+            out_dir,
+            self.duplicates
+        );
+    }
+}
+
+/// Run `cargo <cargo_command> <cargo_args>` with fixture ejection enabled,
+/// then collect the ejected fixtures into `out_dir`, skipping any whose
+/// context duplicates a fixture already collected.
+///
+/// Fixtures are collected even if the tests fail, in which case an error is
+/// returned afterwards.
+pub fn eject_fixtures(
+    proto: &ProtoLayout,
+    cargo_command: &str,
+    cargo_args: &[String],
+    out_dir: &str,
+) -> Result<EjectSummary, Box<dyn std::error::Error>> {
+    // Fixtures are ejected into a staging directory first, so they can be
+    // deduplicated against those already in `out_dir`.
+    let staging_dir =
+        std::env::temp_dir().join(format!("mollusk-eject-fixtures-{}", std::process::id()));
+    fs::create_dir_all(&staging_dir)?;
+
+    let env_var = match proto {
+        ProtoLayout::Mollusk => "EJECT_FUZZ_FIXTURES",
+        ProtoLayout::Firedancer => "EJECT_FUZZ_FIXTURES_FD",
+    };
+    let status = Command::new("cargo")
+        .arg(cargo_command)
+        .args(cargo_args)
+        .env(env_var, &staging_dir)
+        .status()?;
+
+    let summary = match proto {
+        ProtoLayout::Mollusk => collect::<MolluskFixture>(&staging_dir, Path::new(out_dir)),
+        ProtoLayout::Firedancer => collect::<FiredancerFixture>(&staging_dir, Path::new(out_dir)),
+    };
+    fs::remove_dir_all(&staging_dir)?;
+
+    if !status.success() {
+        summary.print(out_dir);
+        return Err(format!("`cargo {}` failed with {}", cargo_command, status).into());
+    }
+    Ok(summary)
+}
+
+fn collect<SF: SerializableFixture>(staging_dir: &Path, out_dir: &Path) -> EjectSummary {
+    fs::create_dir_all(out_dir).expect("Failed to create directory");
+
+    let mut seen = Corpus::<SF>::load(out_dir)
+        .entries()
+        .iter()
+        .map(|entry| entry.context_hash)
+        .collect::<HashSet<_>>();
+
+    let mut summary = EjectSummary {
+        collected: Vec::new(),
+        duplicates: 0,
+    };
+    for entry in Corpus::<SF>::load(staging_dir).entries() {
+        if !seen.insert(entry.context_hash) {
+            summary.duplicates += 1;
+            continue;
+        }
+        let file_name = entry
+            .path
+            .file_name()
+            .expect("Fixture path has no file name");
+        fs::copy(&entry.path, out_dir.join(file_name))
+            .unwrap_or_else(|err| panic!("Failed to copy {}: {}", entry.path.display(), err));
+        summary.collected.push((
+            entry.context_hash.to_string(),
+            file_name.to_string_lossy().into_owned(),
+        ));
+    }
+    summary
+}
@@ -0,0 +1,150 @@
+//! Raw instruction spec files, for executing a single instruction without
+//! a fixture or a Rust test.
+//!
+//! A spec describes the instruction and the state of its accounts, in JSON
+//! or YAML:
+//!
+//! ```yaml
+//! programId: 11111111111111111111111111111111
+//! data:
+//!   hex: "0200000040420f0000000000"
+//! accounts:
+//!   - pubkey: 9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM
+//!     isSigner: true
+//!     isWritable: true
+//!   - pubkey: 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T
+//!     isWritable: true
+//! accountStates:
+//!   - pubkey: 9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM
+//!     lamports: 1000000000
+//!     owner: 11111111111111111111111111111111
+//! ```
+//!
+//! Data may be given as `hex` or `base58`. Accounts without a state are
+//! left for Mollusk to provide, such as programs and sysvars.
+
+use {
+    serde::Deserialize,
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    std::str::FromStr,
+};
+
+/// Raw bytes, in one of the supported encodings.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Bytes {
+    Hex(String),
+    Base58(String),
+}
+
+impl Default for Bytes {
+    fn default() -> Self {
+        Self::Hex(String::new())
+    }
+}
+
+impl Bytes {
+    fn decode(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Hex(data) => hex::decode(data).map_err(|e| format!("Invalid hex data: {}", e)),
+            Self::Base58(data) => bs58::decode(data)
+                .into_vec()
+                .map_err(|e| format!("Invalid base58 data: {}", e)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountMetaSpec {
+    pub pubkey: String,
+    #[serde(default)]
+    pub is_signer: bool,
+    #[serde(default)]
+    pub is_writable: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStateSpec {
+    pub pubkey: String,
+    pub lamports: u64,
+    #[serde(default)]
+    pub data: Bytes,
+    pub owner: String,
+    #[serde(default)]
+    pub executable: bool,
+    #[serde(default)]
+    pub rent_epoch: u64,
+}
+
+/// A single instruction, along with the state of its accounts.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionSpec {
+    pub program_id: String,
+    #[serde(default)]
+    pub data: Bytes,
+    #[serde(default)]
+    pub accounts: Vec<AccountMetaSpec>,
+    #[serde(default)]
+    pub account_states: Vec<AccountStateSpec>,
+}
+
+fn parse_pubkey(pubkey: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(pubkey).map_err(|e| format!("Invalid pubkey {}: {}", pubkey, e))
+}
+
+impl InstructionSpec {
+    pub fn try_load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let ext = std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        let file = std::fs::read_to_string(path)?;
+        match ext {
+            "json" => Ok(serde_json::from_str(&file)?),
+            "yaml" | "yml" => Ok(serde_yaml::from_str(&file)?),
+            _ => Err(format!("Unsupported instruction spec format: {}", ext).into()),
+        }
+    }
+
+    /// Parse the spec into an instruction and its accounts.
+    pub fn parse(&self) -> Result<(Instruction, Vec<(Pubkey, Account)>), String> {
+        let program_id = parse_pubkey(&self.program_id)?;
+        let data = self.data.decode()?;
+        let metas = self
+            .accounts
+            .iter()
+            .map(|meta| {
+                Ok(AccountMeta {
+                    pubkey: parse_pubkey(&meta.pubkey)?,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let accounts = self
+            .account_states
+            .iter()
+            .map(|state| {
+                Ok((
+                    parse_pubkey(&state.pubkey)?,
+                    Account {
+                        lamports: state.lamports,
+                        data: state.data.decode()?,
+                        owner: parse_pubkey(&state.owner)?,
+                        executable: state.executable,
+                        rent_epoch: state.rent_epoch,
+                    },
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((
+            Instruction::new_with_bytes(program_id, &data, metas),
+            accounts,
+        ))
+    }
+}
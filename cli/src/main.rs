@@ -1,14 +1,23 @@
 //! Mollusk CLI.
 
 mod config;
+mod convert;
+mod corpus;
+mod eject;
+mod instruction;
 mod runner;
+mod scaffold;
+mod soak;
 
 use {
     crate::runner::{ProtoLayout, Runner},
     clap::{Parser, Subcommand},
     config::ConfigFile,
-    mollusk_svm::{result::Compare, Mollusk},
-    runner::CusReport,
+    corpus::CorpusStats,
+    instruction::InstructionSpec,
+    mollusk_svm::{fuzz, result::Compare, Mollusk},
+    mollusk_svm_fuzz_fs::FsHandler,
+    runner::{CusBaseline, CusReport},
     trezoa_pubkey::Pubkey,
     std::{fs, path::Path, str::FromStr},
 };
@@ -28,6 +37,10 @@ enum SubCommand {
         #[arg(value_parser = Pubkey::from_str)]
         program_id: Pubkey,
 
+        /// Execute each fixture twice and compare the two results against
+        /// each other, flagging nondeterministic program behavior.
+        #[arg(long)]
+        audit_determinism: bool,
         /// Path to the config file for validation checks.
         #[arg(short, long)]
         config: Option<String>,
@@ -39,6 +52,23 @@ enum SubCommand {
         /// Note this flag is ignored if `cus_report` is not set.
         #[arg(long)]
         cus_report_table_header: Option<String>,
+        /// Maximum number of tables to keep in the compute unit consumption
+        /// report. Older tables are dropped when a new one is added.
+        ///
+        /// Note this flag is ignored if `cus_report` is not set.
+        #[arg(long)]
+        max_sections: Option<usize>,
+        /// Path to a previous compute unit consumption report, or the
+        /// directory containing it, to compare against. Fixtures whose
+        /// compute unit consumption regressed by more than
+        /// `cus_regression_threshold` are printed, and fail the run.
+        #[arg(long)]
+        cus_baseline: Option<String>,
+        /// Compute units a fixture may regress by against the baseline.
+        ///
+        /// Note this flag is ignored if `cus_baseline` is not set.
+        #[arg(long, default_value_t = 0)]
+        cus_regression_threshold: u64,
         /// Skip comparing compute unit consumption, but compare everything
         /// else.
         ///
@@ -55,6 +85,17 @@ enum SubCommand {
         /// Protobuf layout to use when executing the fixture.
         #[arg(long, default_value = "mollusk")]
         proto: ProtoLayout,
+        /// Execute the fixtures this many times over, tracking memory usage
+        /// and program cache size, and fail if either grows after the first
+        /// iteration.
+        ///
+        /// Note the compute unit report is not written, nor compared against
+        /// the baseline, in this mode.
+        #[arg(long)]
+        soak: Option<usize>,
+        /// Print verification and load timings for each program ELF.
+        #[arg(long)]
+        time_loads: bool,
         /// Enable verbose mode for fixture effects. Does not enable program
         /// logs. Disabled by default.
         #[arg(short, long)]
@@ -78,6 +119,10 @@ enum SubCommand {
         #[arg(value_parser = Pubkey::from_str)]
         program_id: Pubkey,
 
+        /// Execute each fixture twice and compare the two results against
+        /// each other, flagging nondeterministic program behavior.
+        #[arg(long)]
+        audit_determinism: bool,
         /// Path to the config file for validation checks.
         #[arg(short, long)]
         config: Option<String>,
@@ -89,6 +134,23 @@ enum SubCommand {
         /// Note this flag is ignored if `cus_report` is not set.
         #[arg(long)]
         cus_report_table_header: Option<String>,
+        /// Maximum number of tables to keep in the compute unit consumption
+        /// report. Older tables are dropped when a new one is added.
+        ///
+        /// Note this flag is ignored if `cus_report` is not set.
+        #[arg(long)]
+        max_sections: Option<usize>,
+        /// Path to a previous compute unit consumption report, or the
+        /// directory containing it, to compare against. Fixtures whose
+        /// compute unit consumption regressed by more than
+        /// `cus_regression_threshold` are printed, and fail the run.
+        #[arg(long)]
+        cus_baseline: Option<String>,
+        /// Compute units a fixture may regress by against the baseline.
+        ///
+        /// Note this flag is ignored if `cus_baseline` is not set.
+        #[arg(long, default_value_t = 0)]
+        cus_regression_threshold: u64,
         /// Skip comparing compute unit consumption, but compare everything
         /// else.
         ///
@@ -102,11 +164,133 @@ enum SubCommand {
         /// Protobuf layout to use when executing the fixture.
         #[arg(long, default_value = "mollusk")]
         proto: ProtoLayout,
+        /// Print verification and load timings for each program ELF.
+        #[arg(long)]
+        time_loads: bool,
         /// Enable verbose mode for fixture effects. Does not enable program
         /// logs. Disabled by default.
         #[arg(short, long)]
         verbose: bool,
     },
+    /// Execute a single instruction described by a JSON or YAML spec file,
+    /// and print the result.
+    RunInstruction {
+        /// The path to the ELF file of the program.
+        #[arg(required = true)]
+        elf_path: String,
+        /// Path to the instruction spec file (`.json` or `.yaml`).
+        #[arg(required = true)]
+        spec: String,
+
+        /// Directory to write a fixture of the instruction and its result.
+        #[arg(long)]
+        fixture_dir: Option<String>,
+        /// Enable emission of program logs to stdout. Disabled by default.
+        #[arg(long)]
+        program_logs: bool,
+        /// Protobuf layout of the fixture written to `fixture_dir`.
+        ///
+        /// Note this flag is ignored if `fixture_dir` is not set.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Summarize a fixture corpus: fixtures by program ID, instruction data
+    /// sizes, account counts, feature set variants, and compute budgets.
+    CorpusStats {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Remove fixtures from a corpus which exercise the same context as
+    /// another fixture, keeping the first by path.
+    CorpusDedupe {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Only report the duplicates, without removing them.
+        #[arg(long)]
+        dry_run: bool,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Split a corpus into shards, assigning fixtures by context hash.
+    CorpusSplit {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Directory to write the shards to, as numbered subdirectories.
+        #[arg(required = true)]
+        out_dir: String,
+        /// The number of shards.
+        #[arg(long, default_value_t = 2)]
+        parts: usize,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Merge several corpora into one directory, dropping fixtures which
+    /// exercise the same context.
+    CorpusMerge {
+        /// Directory to write the merged corpus to.
+        #[arg(required = true)]
+        out_dir: String,
+        /// Paths to instruction fixtures (`.fix` files) or directories
+        /// containing them.
+        #[arg(required = true)]
+        fixtures: Vec<String>,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Convert fixtures from one protobuf layout to the other, warning about
+    /// any information the target layout can't represent.
+    ConvertFixture {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Directory to write the converted fixtures to.
+        #[arg(required = true)]
+        out_dir: String,
+        /// Protobuf layout of the fixtures. They're converted to the other
+        /// layout.
+        #[arg(long, default_value = "mollusk")]
+        from: ProtoLayout,
+    },
+    /// Run a test suite with fixture ejection enabled, and collect the
+    /// generated fixtures into a directory, skipping duplicates.
+    EjectFixtures {
+        /// Directory to collect the fixtures into.
+        #[arg(required = true)]
+        out_dir: String,
+        /// The cargo subcommand which runs the tests.
+        #[arg(long, default_value = "test-sbf")]
+        cargo_command: String,
+        /// Protobuf layout of the fixtures to eject.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+        /// Arguments passed through to the cargo subcommand, after `--`.
+        #[arg(last = true)]
+        cargo_args: Vec<String>,
+    },
+    /// Scaffold a new SBF test program, along with a Mollusk integration
+    /// test.
+    NewTestProgram {
+        /// The name of the program (ie. `my-program`). The program ELF will
+        /// be named after the crate (ie. `my_program.so`).
+        #[arg(required = true)]
+        name: String,
+        /// Directory in which to create the program.
+        #[arg(long, default_value = ".")]
+        dir: String,
+    },
 }
 
 #[derive(Parser)]
@@ -136,13 +320,34 @@ fn search_paths(path: &str, extension: &str) -> Result<Vec<String>, Box<dyn std:
     Ok(result)
 }
 
-fn add_elf_to_mollusk(mollusk: &mut Mollusk, elf_path: &str, program_id: &Pubkey) {
+fn add_elf_to_mollusk(
+    mollusk: &mut Mollusk,
+    elf_path: &str,
+    program_id: &Pubkey,
+    time_loads: bool,
+) {
     let elf = mollusk_svm::file::read_file(elf_path);
     mollusk.add_program_with_loader_and_elf(
         program_id,
         &trezoa_sdk_ids::bpf_loader_upgradeable::id(),
         &elf,
     );
+    if time_loads {
+        let timings = mollusk
+            .program_cache
+            .get_program_load_timings(program_id)
+            .unwrap();
+        println!(
+            "[LOAD]: {}: {}us total (register syscalls: {}us, load ELF: {}us, verify: {}us, JIT \
+             compile: {}us)",
+            elf_path,
+            timings.total_us,
+            timings.register_syscalls_us,
+            timings.load_elf_us,
+            timings.verify_code_us,
+            timings.jit_compile_us,
+        );
+    }
 }
 
 #[tokio::main]
@@ -152,17 +357,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             elf_path,
             fixture,
             program_id,
+            audit_determinism,
             config,
             cus_report,
             cus_report_table_header,
+            max_sections,
+            cus_baseline,
+            cus_regression_threshold,
             ignore_compute_units,
             inputs_only,
             program_logs,
             proto,
+            soak,
+            time_loads,
             verbose,
         } => {
             let mut mollusk = Mollusk::default();
-            add_elf_to_mollusk(&mut mollusk, &elf_path, &program_id);
+            add_elf_to_mollusk(&mut mollusk, &elf_path, &program_id, time_loads);
 
             let checks = if let Some(config_path) = config {
                 ConfigFile::try_load(&config_path)?.checks
@@ -175,36 +386,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             let fixtures = search_paths(&fixture, "fix")?;
 
-            Runner::new(
+            let runner = Runner::new(
+                audit_determinism,
                 checks,
-                cus_report.map(|path| CusReport::new(path, cus_report_table_header)),
+                cus_report.map(|path| CusReport::new(path, cus_report_table_header, max_sections)),
+                cus_baseline.map(|path| CusBaseline {
+                    path,
+                    threshold: cus_regression_threshold,
+                }),
                 inputs_only,
                 program_logs,
                 proto,
                 verbose,
-            )
-            .run_all(None, &mut mollusk, &fixtures)?
+            );
+
+            if let Some(iterations) = soak {
+                runner.run_soak(&mut mollusk, &fixtures, iterations)?
+            } else {
+                runner.run_all(None, &mut mollusk, &fixtures)?
+            }
         }
         SubCommand::RunTest {
             elf_path_source,
             elf_path_target,
             fixture,
             program_id,
+            audit_determinism,
             config,
             cus_report,
             cus_report_table_header,
+            max_sections,
+            cus_baseline,
+            cus_regression_threshold,
             ignore_compute_units,
             program_logs,
             proto,
+            time_loads,
             verbose,
         } => {
             // First, set up a Mollusk instance with the ground truth program.
             let mut mollusk_ground = Mollusk::default();
-            add_elf_to_mollusk(&mut mollusk_ground, &elf_path_source, &program_id);
+            add_elf_to_mollusk(
+                &mut mollusk_ground,
+                &elf_path_source,
+                &program_id,
+                time_loads,
+            );
 
             // Next, set up a Mollusk instance with the test program.
             let mut mollusk_test = Mollusk::default();
-            add_elf_to_mollusk(&mut mollusk_test, &elf_path_target, &program_id);
+            add_elf_to_mollusk(&mut mollusk_test, &elf_path_target, &program_id, time_loads);
 
             let checks = if let Some(config_path) = config {
                 ConfigFile::try_load(&config_path)?.checks
@@ -218,8 +449,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let fixtures = search_paths(&fixture, "fix")?;
 
             Runner::new(
+                audit_determinism,
                 checks,
-                cus_report.map(|path| CusReport::new(path, cus_report_table_header)),
+                cus_report.map(|path| CusReport::new(path, cus_report_table_header, max_sections)),
+                cus_baseline.map(|path| CusBaseline {
+                    path,
+                    threshold: cus_regression_threshold,
+                }),
                 /* inputs_only */ true,
                 program_logs,
                 proto,
@@ -227,6 +463,105 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             )
             .run_all(Some(&mut mollusk_ground), &mut mollusk_test, &fixtures)?
         }
+        SubCommand::RunInstruction {
+            elf_path,
+            spec,
+            fixture_dir,
+            program_logs,
+            proto,
+        } => {
+            let (instruction, accounts) = InstructionSpec::try_load(&spec)?.parse()?;
+
+            let mut mollusk = Mollusk::default();
+            add_elf_to_mollusk(&mut mollusk, &elf_path, &instruction.program_id, false);
+
+            // Disable stdout logging of program logs if not specified.
+            if !program_logs {
+                trezoa_logger::setup_with("");
+            }
+
+            let result = mollusk.process_instruction(&instruction, &accounts);
+
+            println!("Program result: {:?}", result.program_result);
+            println!("Compute units consumed: {}", result.compute_units_consumed);
+            println!("Return data: {:?}", result.return_data);
+            println!("Resulting accounts:");
+            for (pubkey, account) in &result.resulting_accounts {
+                println!("    {}: {:?}", pubkey, account);
+            }
+
+            if let Some(fixture_dir) = fixture_dir {
+                match proto {
+                    ProtoLayout::Mollusk => {
+                        FsHandler::new(fuzz::mollusk::build_fixture_from_mollusk_test(
+                            &mollusk,
+                            &instruction,
+                            &accounts,
+                            &result,
+                        ))
+                        .dump_to_blob_file(&fixture_dir)
+                    }
+                    ProtoLayout::Firedancer => {
+                        FsHandler::new(fuzz::firedancer::build_fixture_from_mollusk_test(
+                            &mollusk,
+                            &instruction,
+                            &accounts,
+                            &result,
+                        ))
+                        .dump_to_blob_file(&fixture_dir)
+                    }
+                }
+                println!("Wrote fixture to {}", fixture_dir);
+            }
+        }
+        SubCommand::CorpusStats { fixture, proto } => {
+            let fixtures = search_paths(&fixture, "fix")?;
+            CorpusStats::collect(&proto, &fixtures).print();
+        }
+        SubCommand::CorpusDedupe {
+            fixture,
+            dry_run,
+            proto,
+        } => corpus::dedupe(&proto, &fixture, dry_run),
+        SubCommand::CorpusSplit {
+            fixture,
+            out_dir,
+            parts,
+            proto,
+        } => corpus::split(&proto, &fixture, &out_dir, parts),
+        SubCommand::CorpusMerge {
+            out_dir,
+            fixtures,
+            proto,
+        } => corpus::merge(&proto, &fixtures, &out_dir),
+        SubCommand::ConvertFixture {
+            fixture,
+            out_dir,
+            from,
+        } => {
+            let fixtures = search_paths(&fixture, "fix")?;
+            for fixture_path in &fixtures {
+                for warning in convert::convert_fixture(&from, fixture_path, &out_dir) {
+                    println!("[WARN]: {}: {}", fixture_path, warning);
+                }
+            }
+            println!("Converted {} fixtures to {}", fixtures.len(), out_dir);
+        }
+        SubCommand::EjectFixtures {
+            out_dir,
+            cargo_command,
+            proto,
+            cargo_args,
+        } => eject::eject_fixtures(&proto, &cargo_command, &cargo_args, &out_dir)?.print(&out_dir),
+        SubCommand::NewTestProgram { name, dir } => {
+            let root = scaffold::new_test_program(&name, &dir)?;
+            println!("Created test program `{}` at {}", name, root.display());
+            println!();
+            println!("Build and test it with:");
+            println!("    cd {}", root.display());
+            println!("    cargo build-sbf");
+            println!("    cargo test-sbf");
+        }
     }
     Ok(())
 }
@@ -1,14 +1,18 @@
 //! CLI runner. Many jobs share the same pattern but do different core actions.
 
 use {
+    crate::soak::{Sample, SoakReport},
     chrono::Utc,
     clap::ValueEnum,
     mollusk_svm::{
         result::{Compare, Config, InstructionResult},
         Mollusk,
     },
-    mollusk_svm_bencher::{get_trezoa_version, result::MolluskComputeUnitBenchResult},
-    std::path::PathBuf,
+    mollusk_svm_bencher::{
+        get_trezoa_version,
+        result::{compare_with_baseline, MolluskComputeUnitBenchResult},
+    },
+    std::path::{Path, PathBuf},
 };
 
 #[derive(Clone, Debug, Default, ValueEnum)]
@@ -23,23 +27,38 @@ pub enum ProtoLayout {
 pub struct CusReport {
     pub path: String,
     pub table_header: String,
+    pub max_sections: Option<usize>,
 }
 
 impl CusReport {
-    pub fn new(path: String, table_header: Option<String>) -> Self {
+    pub fn new(path: String, table_header: Option<String>, max_sections: Option<usize>) -> Self {
         let table_header = table_header.unwrap_or_else(|| Utc::now().to_string());
-        Self { path, table_header }
+        Self {
+            path,
+            table_header,
+            max_sections,
+        }
     }
 }
 
+/// A previous compute unit report to compare compute unit consumption
+/// against.
+pub struct CusBaseline {
+    pub path: String,
+    /// Compute units a fixture may regress by before failing the run.
+    pub threshold: u64,
+}
+
 pub struct RunResult<'a> {
     pub pass: bool,
     pub bench_result: Option<MolluskComputeUnitBenchResult<'a>>,
 }
 
 pub struct Runner {
+    audit_determinism: bool,
     checks: Vec<Compare>,
     cus_report: Option<CusReport>,
+    cus_baseline: Option<CusBaseline>,
     inputs_only: bool,
     program_logs: bool,
     proto: ProtoLayout,
@@ -48,16 +67,20 @@ pub struct Runner {
 
 impl Runner {
     pub const fn new(
+        audit_determinism: bool,
         checks: Vec<Compare>,
         cus_report: Option<CusReport>,
+        cus_baseline: Option<CusBaseline>,
         inputs_only: bool,
         program_logs: bool,
         proto: ProtoLayout,
         verbose: bool,
     ) -> Self {
         Self {
+            audit_determinism,
             checks,
             cus_report,
+            cus_baseline,
             inputs_only,
             program_logs,
             proto,
@@ -141,10 +164,7 @@ impl Runner {
                 pass &= ground_result.compare_with_config(
                     &effects,
                     &self.checks,
-                    &Config {
-                        panic: false,
-                        verbose: self.verbose,
-                    },
+                    &Config::default().panic(false).verbose(self.verbose),
                 );
             }
 
@@ -164,8 +184,8 @@ impl Runner {
 
         let (target_result, effects) = self.run_fixture(target, fixture_path);
 
-        // Record a bench result for the CU report, if specified.
-        let bench_result = if self.cus_report.is_some() {
+        // Record a bench result for the CU report or baseline, if specified.
+        let bench_result = if self.cus_report.is_some() || self.cus_baseline.is_some() {
             Some(MolluskComputeUnitBenchResult::new(
                 parse_fixture_name(fixture_path),
                 target_result.clone(),
@@ -195,11 +215,41 @@ impl Runner {
             pass &= target_result.compare_with_config(
                 &effects,
                 &self.checks,
-                &Config {
-                    panic: false,
-                    verbose: self.verbose,
-                },
+                &Config::default().panic(false).verbose(self.verbose),
+            );
+        }
+
+        if self.audit_determinism {
+            // Execute the fixture a second time and compare the two results
+            // against each other, rather than against the fixture effects.
+            // Any difference points to nondeterminism in the target program.
+            if self.verbose {
+                println!("[AUDIT]: Re-executing fixture...");
+                println!();
+            }
+
+            if self.program_logs {
+                println!("[AUDIT]: Program logs:");
+                println!();
+            }
+
+            let (rerun_result, _) = self.run_fixture(target, fixture_path);
+
+            if self.program_logs {
+                println!();
+            }
+
+            let deterministic = target_result.compare_with_config(
+                &rerun_result,
+                &Compare::everything(),
+                &Config::default().panic(false).verbose(self.verbose),
             );
+
+            if !deterministic {
+                println!("[AUDIT]: Nondeterministic execution: {}", &fixture_path);
+            }
+
+            pass &= deterministic;
         }
 
         if let Some(ground_result) = ground_result {
@@ -212,10 +262,7 @@ impl Runner {
             pass &= ground_result.compare_with_config(
                 &target_result,
                 &self.checks,
-                &Config {
-                    panic: false,
-                    verbose: self.verbose,
-                },
+                &Config::default().panic(false).verbose(self.verbose),
             );
         }
 
@@ -265,16 +312,75 @@ impl Runner {
             std::process::exit(1);
         }
 
+        // Compare against the baseline before writing the report, which may
+        // be the baseline itself.
+        let mut regressions = Vec::new();
+        if let Some(cus_baseline) = &self.cus_baseline {
+            let deltas = compare_with_baseline(Path::new(&cus_baseline.path), &bench_results)?;
+            regressions = deltas
+                .into_iter()
+                .filter(|delta| delta.delta() > cus_baseline.threshold as i64)
+                .collect();
+            println!(
+                "[DONE][CUS BASELINE]: {} regressions above {} CUs",
+                regressions.len(),
+                cus_baseline.threshold
+            );
+            for regression in &regressions {
+                println!("    {}", regression);
+            }
+        }
+
         if let Some(cus_report) = &self.cus_report {
             let trezoa_version = get_trezoa_version();
-            mollusk_svm_bencher::result::write_results(
+            mollusk_svm_bencher::result::append_results(
                 &PathBuf::from(&cus_report.path),
                 &cus_report.table_header,
                 &trezoa_version,
                 bench_results,
+                cus_report.max_sections,
             );
         }
 
+        if !regressions.is_empty() {
+            std::process::exit(1);
+        }
+
+        Ok(())
+    }
+
+    /// Execute the fixtures `iterations` times over, sampling resource usage
+    /// after each pass, and report any growth. See the `soak` module.
+    ///
+    /// The compute unit report is not written, nor compared against the
+    /// baseline, in this mode.
+    pub fn run_soak(
+        &self,
+        target: &mut Mollusk,
+        fixtures: &[String],
+        iterations: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut failures = 0;
+        let mut report = SoakReport::default();
+
+        for iteration in 1..=iterations {
+            for fixture_path in fixtures {
+                if !self.run(None, target, fixture_path)?.pass {
+                    failures += 1;
+                }
+            }
+            report.record(iteration, Sample::take(target));
+        }
+
+        let growth = report.finish();
+
+        println!();
+        println!("[DONE][TEST RESULT]: {} failures", failures);
+
+        if failures > 0 || growth {
+            std::process::exit(1);
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,129 @@
+//! Test program scaffolding.
+//!
+//! The template lives in `cli/templates/test-program` and uses
+//! `cargo-generate` placeholders, so it can also be used directly:
+//!
+//! ```ignore
+//! cargo generate --path <mollusk>/cli/templates/test-program
+//! ```
+
+use std::path::{Path, PathBuf};
+
+/// Template files, as `(destination, contents)`. The `.liquid` suffix used by
+/// `cargo-generate` is stripped from the destination.
+const TEMPLATE_FILES: &[(&str, &str)] = &[
+    (
+        "Cargo.toml",
+        include_str!("../templates/test-program/Cargo.toml.liquid"),
+    ),
+    (
+        "src/lib.rs",
+        include_str!("../templates/test-program/src/lib.rs"),
+    ),
+    (
+        "tests/integration.rs",
+        include_str!("../templates/test-program/tests/integration.rs.liquid"),
+    ),
+];
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Program name cannot be empty".to_string());
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "Invalid program name `{}`: must start with a letter",
+            name
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid program name `{}`: only ASCII letters, digits, `-` and `_` are allowed",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn render(template: &str, name: &str) -> String {
+    template
+        .replace("{{project-name}}", name)
+        .replace("{{crate_name}}", &name.replace('-', "_"))
+        .replace("{{mollusk_version}}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Scaffold a new SBF test program named `name` in `<dir>/<name>`.
+///
+/// Returns the path to the created project.
+pub fn new_test_program(name: &str, dir: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    validate_name(name)?;
+
+    let root = Path::new(dir).join(name);
+    if root.exists() {
+        return Err(format!("Destination `{}` already exists", root.display()).into());
+    }
+
+    for (path, template) in TEMPLATE_FILES {
+        let dest = root.join(path);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(dest, render(template, name))?;
+    }
+
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "mollusk-scaffold-{}",
+            trezoa_pubkey::Pubkey::new_unique()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_new_test_program() {
+        let dir = temp_dir();
+        let root = new_test_program("my-program", dir.to_str().unwrap()).unwrap();
+        assert_eq!(root, dir.join("my-program"));
+
+        for (path, _) in TEMPLATE_FILES {
+            let contents = std::fs::read_to_string(root.join(path)).unwrap();
+            assert!(!contents.contains("{{"), "unrendered placeholder in {path}");
+        }
+
+        let manifest = std::fs::read_to_string(root.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"my-program\""));
+        assert!(manifest.contains(&format!("mollusk-svm = \"{}\"", env!("CARGO_PKG_VERSION"))));
+        assert!(manifest.contains("\n[workspace]\n"));
+        assert!(manifest.contains("test-sbf = []"));
+
+        let test = std::fs::read_to_string(root.join("tests/integration.rs")).unwrap();
+        assert!(test.starts_with("#![cfg(feature = \"test-sbf\")]"));
+        assert!(test.contains("Mollusk::new(&program_id, \"my_program\")"));
+
+        // Scaffolding over an existing project fails.
+        assert!(new_test_program("my-program", dir.to_str().unwrap()).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        let dir = temp_dir();
+        for name in ["", "1program", "-program", "my program", "my/program"] {
+            assert!(new_test_program(name, dir.to_str().unwrap()).is_err());
+        }
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
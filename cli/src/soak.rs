@@ -0,0 +1,151 @@
+//! Soak testing. Replays a corpus many times over, sampling resource usage
+//! after each pass, to catch state which grows with every invocation.
+//!
+//! The first pass warms up the program cache and allocator, so it serves as
+//! the baseline. Replaying the same corpus afterwards shouldn't grow the
+//! program cache at all, and shouldn't grow the resident set size beyond
+//! allocator noise.
+
+use {mollusk_svm::Mollusk, std::fs};
+
+// Resident set size growth beyond the baseline tolerated before it's
+// reported, to absorb allocator noise.
+const RSS_GROWTH_TOLERANCE_KB: u64 = 1024;
+
+/// Resource usage sampled after a pass over the corpus.
+#[derive(Clone, Copy)]
+pub struct Sample {
+    /// Resident set size, in kilobytes. `None` where `/proc` is unavailable.
+    rss_kb: Option<u64>,
+    /// The number of programs in the program cache.
+    cached_programs: usize,
+}
+
+impl Sample {
+    pub fn take(mollusk: &Mollusk) -> Self {
+        Self {
+            rss_kb: resident_set_size_kb(),
+            cached_programs: mollusk.program_cache.num_programs(),
+        }
+    }
+}
+
+fn resident_set_size_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[derive(Default)]
+pub struct SoakReport {
+    samples: Vec<Sample>,
+}
+
+impl SoakReport {
+    /// Record and print the sample taken after a pass.
+    pub fn record(&mut self, iteration: usize, sample: Sample) {
+        println!(
+            "[SOAK]: Iteration {}: RSS: {}, cached programs: {}",
+            iteration,
+            sample
+                .rss_kb
+                .map_or_else(|| "unavailable".to_string(), |kb| format!("{} kB", kb)),
+            sample.cached_programs,
+        );
+        self.samples.push(sample);
+    }
+
+    /// Compare the last sample against the baseline and print any growth.
+    /// Returns `true` if growth was detected.
+    pub fn finish(&self) -> bool {
+        let (Some(baseline), Some(last)) = (self.samples.first(), self.samples.last()) else {
+            return false;
+        };
+        if self.samples.len() < 2 {
+            println!("[SOAK]: At least two iterations are needed to detect growth.");
+            return false;
+        }
+
+        let mut growth = false;
+
+        if last.cached_programs > baseline.cached_programs {
+            println!(
+                "[SOAK]: Program cache grew from {} to {} programs",
+                baseline.cached_programs, last.cached_programs,
+            );
+            growth = true;
+        }
+        if let (Some(baseline_kb), Some(last_kb)) = (baseline.rss_kb, last.rss_kb) {
+            if last_kb > baseline_kb.saturating_add(RSS_GROWTH_TOLERANCE_KB) {
+                println!(
+                    "[SOAK]: Resident set size grew from {} kB to {} kB",
+                    baseline_kb, last_kb,
+                );
+                growth = true;
+            }
+        }
+
+        if !growth {
+            println!(
+                "[SOAK]: No growth detected over {} iterations",
+                self.samples.len()
+            );
+        }
+        growth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(rss_kb: Option<u64>, cached_programs: usize) -> Sample {
+        Sample {
+            rss_kb,
+            cached_programs,
+        }
+    }
+
+    #[test]
+    fn test_program_cache_growth() {
+        let mut mollusk = Mollusk::default();
+        let mut report = SoakReport::default();
+
+        report.record(1, Sample::take(&mollusk));
+        report.record(2, Sample::take(&mollusk));
+        assert!(!report.finish());
+
+        std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+        mollusk.add_program(&trezoa_pubkey::Pubkey::new_unique(), "test_program_primary");
+        report.record(3, Sample::take(&mollusk));
+        assert!(report.finish());
+    }
+
+    #[test]
+    fn test_rss_growth_tolerance() {
+        let mut report = SoakReport::default();
+        report.record(1, sample(Some(10_000), 1));
+        report.record(2, sample(Some(10_000 + RSS_GROWTH_TOLERANCE_KB), 1));
+        assert!(!report.finish());
+
+        report.record(3, sample(Some(10_001 + RSS_GROWTH_TOLERANCE_KB), 1));
+        assert!(report.finish());
+
+        // Growth can't be measured without `/proc`.
+        let mut report = SoakReport::default();
+        report.record(1, sample(None, 1));
+        report.record(2, sample(Some(1_000_000), 1));
+        assert!(!report.finish());
+    }
+
+    #[test]
+    fn test_single_iteration() {
+        let mut report = SoakReport::default();
+        assert!(!report.finish());
+
+        report.record(1, sample(None, 1));
+        assert!(!report.finish());
+    }
+}
@@ -0,0 +1,34 @@
+use {
+    trezoa_account_info::{next_account_info, AccountInfo},
+    trezoa_program_error::{ProgramError, ProgramResult},
+    trezoa_pubkey::Pubkey,
+};
+
+trezoa_program_entrypoint::entrypoint!(process_instruction);
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    // Simply write the input data to the first account.
+    let accounts_iter = &mut accounts.iter();
+
+    let account_info = next_account_info(accounts_iter)?;
+
+    if !account_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if input.len() > account_info.data_len() {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    account_info.try_borrow_mut_data()?[..input.len()].copy_from_slice(input);
+
+    Ok(())
+}
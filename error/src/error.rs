@@ -15,6 +15,15 @@ pub enum MolluskError<'a> {
     /// Failed to read file.
     #[error("    [MOLLUSK]: Failed to read file: {0}")]
     FileReadError(&'a Path),
+    /// Failed to write file.
+    #[error("    [MOLLUSK]: Failed to write file: {0}")]
+    FileWriteError(&'a Path),
+    /// A program dump is neither an ELF nor loader-v3 program data.
+    #[error("    [MOLLUSK]: Program dump is neither an ELF nor loader-v3 program data: {0}")]
+    InvalidProgramDump(&'a Path),
+    /// A program keypair file or program ID manifest couldn't be parsed.
+    #[error("    [MOLLUSK]: Invalid program ID file: {0}")]
+    InvalidProgramIdFile(&'a Path),
     /// Program file not found.
     #[error("    [MOLLUSK]: Program file not found: {0}")]
     FileNotFound(&'a str),
@@ -30,6 +39,19 @@ pub enum MolluskError<'a> {
     /// Account index exceeds maximum (255).
     #[error("    [MOLLUSK]: Account index exceeds maximum of 255: {0}")]
     AccountIndexOverflow(usize),
+    /// A v0 message, or a transaction charged fees, requires a signer to act
+    /// as the fee payer.
+    #[error("    [MOLLUSK]: A fee payer is required, but no instruction account is a signer")]
+    FeePayerMissing,
+    /// Failed to compile a v0 message with the provided address lookup tables.
+    #[error("    [MOLLUSK]: Failed to compile v0 message")]
+    MessageCompileError,
+    /// An instruction doesn't match the interface registered for its program.
+    #[error("    [MOLLUSK]: Instruction doesn't match the interface of program {0}")]
+    InterfaceMismatch(&'a Pubkey),
+    /// A request to an RPC endpoint failed.
+    #[error("    [MOLLUSK]: RPC request to {0} failed")]
+    RpcRequestError(&'a str),
 }
 
 pub trait MolluskPanic<T> {
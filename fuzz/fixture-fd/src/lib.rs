@@ -8,6 +8,9 @@
 //! which means developers can fuzz programs using the Mollusk harness.
 //! However, these fixtures (and this library) do not depend on the harness.
 //! They can be used to fuzz a custom entrypoint of the developer's choice.
+//!
+//! Both instruction-level (`Fixture`) and transaction-level (`TxnFixture`)
+//! layouts are supported.
 
 pub mod account;
 pub mod context;
@@ -15,10 +18,12 @@ pub mod effects;
 pub mod feature_set;
 pub mod instr_account;
 pub mod metadata;
+pub mod txn;
 pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/org.trezoa.sealevel.v1.rs"));
 }
 
+pub use txn::TxnFixture;
 use {
     crate::{
         context::Context, effects::Effects, metadata::Metadata, proto::InstrFixture as ProtoFixture,
@@ -92,6 +97,17 @@ impl SerializableFixture for ProtoFixture {
         }
         hasher.result()
     }
+
+    fn context_hash(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        if let Some(metadata) = &self.metadata {
+            crate::metadata::hash_proto_metadata(&mut hasher, metadata);
+        }
+        if let Some(input) = &self.input {
+            crate::context::hash_proto_context(&mut hasher, input);
+        }
+        hasher.result()
+    }
 }
 
 impl IntoSerializableFixture for Fixture {
@@ -0,0 +1,517 @@
+//! Transaction-level fixtures.
+//!
+//! Where `Fixture` invokes a single instruction, a `TxnFixture` executes an
+//! entire (sanitized) transaction message: multiple instructions, a fee
+//! payer, and optionally address lookup tables.
+
+use {
+    super::proto::{
+        CompiledInstruction as ProtoCompiledInstruction, FeeDetails as ProtoFeeDetails,
+        MessageAddressTableLookup as ProtoAddressTableLookup, MessageHeader as ProtoMessageHeader,
+        RentDebits as ProtoRentDebits, ResultingState as ProtoResultingState,
+        SanitizedTransaction as ProtoSanitizedTransaction,
+        TransactionMessage as ProtoTransactionMessage, TxnContext as ProtoTxnContext,
+        TxnFixture as ProtoTxnFixture, TxnResult as ProtoTxnResult,
+    },
+    crate::{
+        account::SeedAddress,
+        context::{EpochContext, SlotContext},
+        metadata::Metadata,
+    },
+    mollusk_svm_fuzz_fs::{FsHandler, IntoSerializableFixture, SerializableFixture},
+    trezoa_account::Account,
+    trezoa_keccak_hasher::{Hash, Hasher},
+    trezoa_pubkey::Pubkey,
+};
+
+fn pubkey_from_bytes(bytes: Vec<u8>) -> Pubkey {
+    let pubkey_bytes: [u8; 32] = bytes.try_into().expect("Invalid bytes for pubkey");
+    Pubkey::new_from_array(pubkey_bytes)
+}
+
+/// The message header, describing which account keys are signers and which
+/// are writable.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageHeader {
+    pub num_required_signatures: u32,
+    pub num_readonly_signed_accounts: u32,
+    pub num_readonly_unsigned_accounts: u32,
+}
+
+impl From<ProtoMessageHeader> for MessageHeader {
+    fn from(value: ProtoMessageHeader) -> Self {
+        let ProtoMessageHeader {
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        } = value;
+        Self {
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        }
+    }
+}
+
+impl From<MessageHeader> for ProtoMessageHeader {
+    fn from(value: MessageHeader) -> Self {
+        let MessageHeader {
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        } = value;
+        Self {
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        }
+    }
+}
+
+/// An instruction, with its program and accounts referenced by index into
+/// the message's account keys, followed by any loaded addresses.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompiledInstruction {
+    pub program_id_index: u32,
+    pub accounts: Vec<u32>,
+    pub data: Vec<u8>,
+}
+
+impl From<ProtoCompiledInstruction> for CompiledInstruction {
+    fn from(value: ProtoCompiledInstruction) -> Self {
+        let ProtoCompiledInstruction {
+            program_id_index,
+            accounts,
+            data,
+        } = value;
+        Self {
+            program_id_index,
+            accounts,
+            data,
+        }
+    }
+}
+
+impl From<CompiledInstruction> for ProtoCompiledInstruction {
+    fn from(value: CompiledInstruction) -> Self {
+        let CompiledInstruction {
+            program_id_index,
+            accounts,
+            data,
+        } = value;
+        Self {
+            program_id_index,
+            accounts,
+            data,
+        }
+    }
+}
+
+/// Addresses loaded from an address lookup table.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AddressTableLookup {
+    /// The address of the lookup table account.
+    pub account_key: Pubkey,
+    pub writable_indexes: Vec<u32>,
+    pub readonly_indexes: Vec<u32>,
+}
+
+impl From<ProtoAddressTableLookup> for AddressTableLookup {
+    fn from(value: ProtoAddressTableLookup) -> Self {
+        let ProtoAddressTableLookup {
+            account_key,
+            writable_indexes,
+            readonly_indexes,
+        } = value;
+        Self {
+            account_key: pubkey_from_bytes(account_key),
+            writable_indexes,
+            readonly_indexes,
+        }
+    }
+}
+
+impl From<AddressTableLookup> for ProtoAddressTableLookup {
+    fn from(value: AddressTableLookup) -> Self {
+        let AddressTableLookup {
+            account_key,
+            writable_indexes,
+            readonly_indexes,
+        } = value;
+        Self {
+            account_key: account_key.to_bytes().to_vec(),
+            writable_indexes,
+            readonly_indexes,
+        }
+    }
+}
+
+/// Transaction context fixture.
+#[derive(Clone, Debug, Default)]
+pub struct TxnContext {
+    /// Whether the message is a legacy message. Otherwise, a v0 message.
+    pub is_legacy: bool,
+    /// The message header.
+    pub header: MessageHeader,
+    /// The static account keys. The first key is the fee payer.
+    pub account_keys: Vec<Pubkey>,
+    /// Input accounts with state, including any address lookup tables.
+    pub accounts: Vec<(Pubkey, Account, Option<SeedAddress>)>,
+    /// The message's recent blockhash.
+    pub recent_blockhash: Vec<u8>,
+    /// The instructions to execute.
+    pub instructions: Vec<CompiledInstruction>,
+    /// Address table lookups (v0 messages only).
+    pub address_table_lookups: Vec<AddressTableLookup>,
+    /// The message hash.
+    pub message_hash: Vec<u8>,
+    /// The transaction signatures.
+    pub signatures: Vec<Vec<u8>>,
+    /// Recent blockhashes, with the most recent last.
+    pub blockhash_queue: Vec<Vec<u8>>,
+    /// Slot context.
+    pub slot_context: SlotContext,
+    /// Epoch context.
+    pub epoch_context: EpochContext,
+}
+
+impl From<ProtoTxnContext> for TxnContext {
+    fn from(value: ProtoTxnContext) -> Self {
+        // All transaction contexts should have a message.
+        let ProtoSanitizedTransaction {
+            message,
+            message_hash,
+            signatures,
+        } = value.tx.unwrap();
+        let ProtoTransactionMessage {
+            is_legacy,
+            header,
+            account_keys,
+            account_shared_data,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+        } = message.unwrap();
+
+        Self {
+            is_legacy,
+            header: header.map(Into::into).unwrap_or_default(),
+            account_keys: account_keys.into_iter().map(pubkey_from_bytes).collect(),
+            accounts: account_shared_data.into_iter().map(Into::into).collect(),
+            recent_blockhash,
+            instructions: instructions.into_iter().map(Into::into).collect(),
+            address_table_lookups: address_table_lookups.into_iter().map(Into::into).collect(),
+            message_hash,
+            signatures,
+            blockhash_queue: value.blockhash_queue,
+            slot_context: value.slot_ctx.map(Into::into).unwrap_or_default(),
+            epoch_context: value.epoch_ctx.map(Into::into).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<TxnContext> for ProtoTxnContext {
+    fn from(value: TxnContext) -> Self {
+        let message = ProtoTransactionMessage {
+            is_legacy: value.is_legacy,
+            header: Some(value.header.into()),
+            account_keys: value
+                .account_keys
+                .iter()
+                .map(|key| key.to_bytes().to_vec())
+                .collect(),
+            account_shared_data: value.accounts.into_iter().map(Into::into).collect(),
+            recent_blockhash: value.recent_blockhash,
+            instructions: value.instructions.into_iter().map(Into::into).collect(),
+            address_table_lookups: value
+                .address_table_lookups
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        };
+
+        Self {
+            tx: Some(ProtoSanitizedTransaction {
+                message: Some(message),
+                message_hash: value.message_hash,
+                signatures: value.signatures,
+            }),
+            blockhash_queue: value.blockhash_queue,
+            epoch_ctx: Some(value.epoch_context.into()),
+            slot_ctx: Some(value.slot_context.into()),
+        }
+    }
+}
+
+/// Represents the effects of a transaction.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TxnEffects {
+    /// Whether the transaction was executed. False for transactions rejected
+    /// before execution, such as sanitization failures.
+    pub executed: bool,
+    /// Whether the transaction failed sanitization.
+    pub sanitization_error: bool,
+    /// Account states after executing the transaction.
+    pub resulting_accounts: Vec<(Pubkey, Account, Option<SeedAddress>)>,
+    /// Rent collected from each account.
+    pub rent_debits: Vec<(Pubkey, i64)>,
+    /// Total rent collected by the transaction.
+    pub transaction_rent: u64,
+    pub rent: u64,
+    /// Whether the transaction succeeded.
+    pub is_ok: bool,
+    // Transaction error code. Zero is success, errors are non-zero.
+    pub status: u32,
+    // Instruction error code, also non-zero if any.
+    pub instruction_error: u32,
+    /// The index of the instruction which failed, if any.
+    pub instruction_error_index: u32,
+    // Custom error code, also non-zero if any.
+    pub custom_error: u32,
+    /// Transaction return data.
+    pub return_data: Vec<u8>,
+    /// Compute units consumed by the transaction.
+    pub executed_units: u64,
+    /// The base transaction fee charged to the fee payer.
+    pub transaction_fee: u64,
+    /// The prioritization fee charged to the fee payer.
+    pub prioritization_fee: u64,
+    /// The total size of the loaded accounts' data.
+    pub loaded_accounts_data_size: u64,
+}
+
+impl From<ProtoTxnResult> for TxnEffects {
+    fn from(value: ProtoTxnResult) -> Self {
+        let ProtoResultingState {
+            acct_states,
+            rent_debits,
+            transaction_rent,
+        } = value.resulting_state.unwrap_or_default();
+        let ProtoFeeDetails {
+            transaction_fee,
+            prioritization_fee,
+        } = value.fee_details.unwrap_or_default();
+
+        Self {
+            executed: value.executed,
+            sanitization_error: value.sanitization_error,
+            resulting_accounts: acct_states.into_iter().map(Into::into).collect(),
+            rent_debits: rent_debits
+                .into_iter()
+                .map(|debit| (pubkey_from_bytes(debit.pubkey), debit.rent_collected))
+                .collect(),
+            transaction_rent,
+            rent: value.rent,
+            is_ok: value.is_ok,
+            status: value.status,
+            instruction_error: value.instruction_error,
+            instruction_error_index: value.instruction_error_index,
+            custom_error: value.custom_error,
+            return_data: value.return_data,
+            executed_units: value.executed_units,
+            transaction_fee,
+            prioritization_fee,
+            loaded_accounts_data_size: value.loaded_accounts_data_size,
+        }
+    }
+}
+
+impl From<TxnEffects> for ProtoTxnResult {
+    fn from(value: TxnEffects) -> Self {
+        let resulting_state = ProtoResultingState {
+            acct_states: value
+                .resulting_accounts
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            rent_debits: value
+                .rent_debits
+                .into_iter()
+                .map(|(pubkey, rent_collected)| ProtoRentDebits {
+                    pubkey: pubkey.to_bytes().to_vec(),
+                    rent_collected,
+                })
+                .collect(),
+            transaction_rent: value.transaction_rent,
+        };
+
+        Self {
+            executed: value.executed,
+            sanitization_error: value.sanitization_error,
+            resulting_state: Some(resulting_state),
+            rent: value.rent,
+            is_ok: value.is_ok,
+            status: value.status,
+            instruction_error: value.instruction_error,
+            instruction_error_index: value.instruction_error_index,
+            custom_error: value.custom_error,
+            return_data: value.return_data,
+            executed_units: value.executed_units,
+            fee_details: Some(ProtoFeeDetails {
+                transaction_fee: value.transaction_fee,
+                prioritization_fee: value.prioritization_fee,
+            }),
+            loaded_accounts_data_size: value.loaded_accounts_data_size,
+        }
+    }
+}
+
+/// A fixture for executing a transaction against a simulated SVM program
+/// runtime environment.
+#[derive(Clone, Debug, Default)]
+pub struct TxnFixture {
+    /// The fixture metadata.
+    pub metadata: Option<Metadata>,
+    /// The fixture inputs.
+    pub input: TxnContext,
+    /// The fixture outputs.
+    pub output: TxnEffects,
+}
+
+impl TxnFixture {
+    pub fn decode(blob: &[u8]) -> Self {
+        let proto_fixture = <ProtoTxnFixture as SerializableFixture>::decode(blob);
+        proto_fixture.into()
+    }
+
+    pub fn load_from_blob_file(file_path: &str) -> Self {
+        let proto_fixture: ProtoTxnFixture = FsHandler::load_from_blob_file(file_path);
+        proto_fixture.into()
+    }
+
+    pub fn load_from_json_file(file_path: &str) -> Self {
+        let proto_fixture: ProtoTxnFixture = FsHandler::load_from_json_file(file_path);
+        proto_fixture.into()
+    }
+}
+
+impl From<ProtoTxnFixture> for TxnFixture {
+    fn from(value: ProtoTxnFixture) -> Self {
+        // All blobs should have an input and output.
+        Self {
+            metadata: value.metadata.map(Into::into),
+            input: value.input.unwrap().into(),
+            output: value.output.unwrap().into(),
+        }
+    }
+}
+
+impl From<TxnFixture> for ProtoTxnFixture {
+    fn from(value: TxnFixture) -> Self {
+        Self {
+            metadata: value.metadata.map(Into::into),
+            input: Some(value.input.into()),
+            output: Some(value.output.into()),
+        }
+    }
+}
+
+fn hash_proto_txn_context(hasher: &mut Hasher, context: &ProtoTxnContext) {
+    if let Some(tx) = &context.tx {
+        if let Some(message) = &tx.message {
+            hasher.hash(&[message.is_legacy as u8]);
+            if let Some(header) = &message.header {
+                hasher.hash(&header.num_required_signatures.to_le_bytes());
+                hasher.hash(&header.num_readonly_signed_accounts.to_le_bytes());
+                hasher.hash(&header.num_readonly_unsigned_accounts.to_le_bytes());
+            }
+            for key in &message.account_keys {
+                hasher.hash(key);
+            }
+            crate::account::hash_proto_accounts(hasher, &message.account_shared_data);
+            hasher.hash(&message.recent_blockhash);
+            for instruction in &message.instructions {
+                hasher.hash(&instruction.program_id_index.to_le_bytes());
+                for account in &instruction.accounts {
+                    hasher.hash(&account.to_le_bytes());
+                }
+                hasher.hash(&instruction.data);
+            }
+            for lookup in &message.address_table_lookups {
+                hasher.hash(&lookup.account_key);
+                for index in &lookup.writable_indexes {
+                    hasher.hash(&index.to_le_bytes());
+                }
+                for index in &lookup.readonly_indexes {
+                    hasher.hash(&index.to_le_bytes());
+                }
+            }
+        }
+        hasher.hash(&tx.message_hash);
+        for signature in &tx.signatures {
+            hasher.hash(signature);
+        }
+    }
+    for blockhash in &context.blockhash_queue {
+        hasher.hash(blockhash);
+    }
+    if let Some(slot_context) = &context.slot_ctx {
+        hasher.hash(&slot_context.slot.to_le_bytes());
+    }
+    if let Some(epoch_context) = &context.epoch_ctx {
+        if let Some(features) = &epoch_context.features {
+            crate::feature_set::hash_proto_feature_set(hasher, features);
+        }
+    }
+}
+
+fn hash_proto_txn_result(hasher: &mut Hasher, result: &ProtoTxnResult) {
+    hasher.hash(&[result.executed as u8, result.sanitization_error as u8]);
+    if let Some(resulting_state) = &result.resulting_state {
+        crate::account::hash_proto_accounts(hasher, &resulting_state.acct_states);
+        for debit in &resulting_state.rent_debits {
+            hasher.hash(&debit.pubkey);
+            hasher.hash(&debit.rent_collected.to_le_bytes());
+        }
+        hasher.hash(&resulting_state.transaction_rent.to_le_bytes());
+    }
+    hasher.hash(&result.rent.to_le_bytes());
+    hasher.hash(&[result.is_ok as u8]);
+    hasher.hash(&result.status.to_le_bytes());
+    hasher.hash(&result.instruction_error.to_le_bytes());
+    hasher.hash(&result.instruction_error_index.to_le_bytes());
+    hasher.hash(&result.custom_error.to_le_bytes());
+    hasher.hash(&result.return_data);
+    hasher.hash(&result.executed_units.to_le_bytes());
+    if let Some(fee_details) = &result.fee_details {
+        hasher.hash(&fee_details.transaction_fee.to_le_bytes());
+        hasher.hash(&fee_details.prioritization_fee.to_le_bytes());
+    }
+    hasher.hash(&result.loaded_accounts_data_size.to_le_bytes());
+}
+
+impl SerializableFixture for ProtoTxnFixture {
+    // Manually implemented for deterministic hashes.
+    fn hash(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        if let Some(metadata) = &self.metadata {
+            crate::metadata::hash_proto_metadata(&mut hasher, metadata);
+        }
+        if let Some(input) = &self.input {
+            hash_proto_txn_context(&mut hasher, input);
+        }
+        if let Some(output) = &self.output {
+            hash_proto_txn_result(&mut hasher, output);
+        }
+        hasher.result()
+    }
+
+    fn context_hash(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        if let Some(metadata) = &self.metadata {
+            crate::metadata::hash_proto_metadata(&mut hasher, metadata);
+        }
+        if let Some(input) = &self.input {
+            hash_proto_txn_context(&mut hasher, input);
+        }
+        hasher.result()
+    }
+}
+
+impl IntoSerializableFixture for TxnFixture {
+    type Fixture = ProtoTxnFixture;
+
+    fn into(self) -> Self::Fixture {
+        Into::into(self)
+    }
+}
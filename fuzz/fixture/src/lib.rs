@@ -79,6 +79,14 @@ impl SerializableFixture for ProtoFixture {
         }
         hasher.result()
     }
+
+    fn context_hash(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        if let Some(input) = &self.input {
+            crate::context::hash_proto_context(&mut hasher, input);
+        }
+        hasher.result()
+    }
 }
 
 impl IntoSerializableFixture for Fixture {
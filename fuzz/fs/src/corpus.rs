@@ -0,0 +1,198 @@
+//! Fixture corpus management.
+//!
+//! A corpus is a directory of `.fix` blob files, searched recursively.
+//! Fixtures are identified by their context hash (see
+//! `SerializableFixture::context_hash`), so two fixtures exercising the same
+//! inputs are duplicates, even if their recorded effects differ.
+//!
+//! ```rust,ignore
+//! use {mollusk_svm_fuzz_fixture::proto::InstrFixture, mollusk_svm_fuzz_fs::corpus::Corpus};
+//!
+//! let mut corpus = Corpus::<InstrFixture>::load("./fixtures");
+//! let removed = corpus.dedupe();
+//! corpus.split("./shards", 4);
+//! ```
+//!
+//! Whenever a choice is made between duplicates, the fixture with the first
+//! path in lexicographic order is kept, so results don't depend on the order
+//! files were written in.
+
+use {
+    crate::{FsHandler, SerializableFixture},
+    std::{
+        collections::HashSet,
+        fs,
+        path::{Path, PathBuf},
+    },
+    trezoa_keccak_hasher::{Hash, Hasher},
+};
+
+const FIXTURE_EXTENSION: &str = "fix";
+
+/// A fixture in a corpus.
+pub struct CorpusEntry<SF: SerializableFixture> {
+    /// The path of the fixture's blob file.
+    pub path: PathBuf,
+    /// The hash of the fixture's inputs.
+    pub context_hash: Hash,
+    /// The decoded fixture.
+    pub fixture: SF,
+}
+
+/// A set of fixture blob files, loaded from disk.
+pub struct Corpus<SF: SerializableFixture> {
+    entries: Vec<CorpusEntry<SF>>,
+}
+
+impl<SF: SerializableFixture> Corpus<SF> {
+    /// Load every `.fix` file under the provided path, which may also be a
+    /// single fixture file. Entries are sorted by path.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let mut paths = Vec::new();
+        find_fixture_files(path.as_ref(), &mut paths);
+        paths.sort();
+
+        let entries = paths
+            .into_iter()
+            .map(|path| {
+                let fixture: SF = FsHandler::load_from_blob_file(
+                    path.to_str().expect("Fixture path isn't valid UTF-8"),
+                );
+                CorpusEntry {
+                    context_hash: fixture.context_hash(),
+                    path,
+                    fixture,
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Load and combine several corpora. Entries are sorted by path.
+    pub fn load_all<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Self {
+        let mut entries = paths
+            .into_iter()
+            .flat_map(|path| Self::load(path).entries)
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Self { entries }
+    }
+
+    /// The fixtures in the corpus.
+    pub fn entries(&self) -> &[CorpusEntry<SF>] {
+        &self.entries
+    }
+
+    /// The number of fixtures in the corpus.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the corpus has no fixtures.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The paths of fixtures whose context hash matches an earlier fixture.
+    pub fn duplicates(&self) -> Vec<&Path> {
+        let mut seen = HashSet::new();
+        self.entries
+            .iter()
+            .filter(|entry| !seen.insert(entry.context_hash))
+            .map(|entry| entry.path.as_path())
+            .collect()
+    }
+
+    /// Delete duplicate fixture files from disk, and drop them from the
+    /// corpus. Returns the paths of the deleted files.
+    pub fn dedupe(&mut self) -> Vec<PathBuf> {
+        let mut seen = HashSet::new();
+        let (unique, duplicates): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| seen.insert(entry.context_hash));
+        self.entries = unique;
+
+        duplicates
+            .into_iter()
+            .map(|entry| {
+                fs::remove_file(&entry.path).unwrap_or_else(|err| {
+                    panic!("Failed to remove {}: {}", entry.path.display(), err)
+                });
+                entry.path
+            })
+            .collect()
+    }
+
+    /// Copy the corpus into `parts` subdirectories of `out_dir`, named `0`
+    /// through `parts - 1`. Copies are named after the hash of their
+    /// contents.
+    ///
+    /// Fixtures are assigned by context hash, so a fixture always lands in
+    /// the same shard, and duplicates land together. Returns the shard
+    /// directories.
+    pub fn split(&self, out_dir: impl AsRef<Path>, parts: usize) -> Vec<PathBuf> {
+        assert!(parts > 0, "A corpus must be split into at least one part");
+
+        let shards = (0..parts)
+            .map(|index| out_dir.as_ref().join(index.to_string()))
+            .collect::<Vec<_>>();
+        for shard in &shards {
+            fs::create_dir_all(shard).expect("Failed to create directory");
+        }
+
+        for entry in &self.entries {
+            let index = shard_index(&entry.context_hash, parts);
+            copy_fixture(&entry.path, &shards[index]);
+        }
+        shards
+    }
+
+    /// Copy one fixture per context hash into `out_dir`. Use with
+    /// `load_all` to merge several corpora. Returns the number of fixtures
+    /// written.
+    pub fn merge_into(&self, out_dir: impl AsRef<Path>) -> usize {
+        fs::create_dir_all(out_dir.as_ref()).expect("Failed to create directory");
+
+        let mut seen = HashSet::new();
+        let mut written = 0;
+        for entry in &self.entries {
+            if seen.insert(entry.context_hash) {
+                copy_fixture(&entry.path, out_dir.as_ref());
+                written += 1;
+            }
+        }
+        written
+    }
+}
+
+fn find_fixture_files(path: &Path, result: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        for entry in fs::read_dir(path).expect("Failed to read directory") {
+            find_fixture_files(&entry.expect("Failed to read entry").path(), result);
+        }
+    } else if path.extension().is_some_and(|ext| ext == FIXTURE_EXTENSION) {
+        result.push(path.to_path_buf());
+    }
+}
+
+fn shard_index(context_hash: &Hash, parts: usize) -> usize {
+    let prefix = u64::from_le_bytes(context_hash.as_ref()[..8].try_into().unwrap());
+    (prefix % parts as u64) as usize
+}
+
+/// Copy a fixture file into a directory, named after the hash of its
+/// contents, so fixtures sharing a file name in different corpora don't
+/// overwrite each other.
+fn copy_fixture(path: &Path, dir: &Path) {
+    let blob =
+        fs::read(path).unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err));
+    let mut hasher = Hasher::default();
+    hasher.hash(&blob);
+    let file_name = format!(
+        "{}.{}",
+        bs58::encode(hasher.result()).into_string(),
+        FIXTURE_EXTENSION
+    );
+    fs::write(dir.join(file_name), blob)
+        .unwrap_or_else(|err| panic!("Failed to copy {}: {}", path.display(), err));
+}
@@ -1,3 +1,5 @@
+pub mod corpus;
+
 use {
     prost::Message,
     serde::{de::DeserializeOwned, Serialize},
@@ -6,6 +8,7 @@ use {
         io::{Read, Write},
         path::Path,
     },
+    trezoa_keccak_hasher::{Hash, Hasher},
 };
 
 /// Represents a serializable fuzz fixture.
@@ -25,6 +28,14 @@ pub trait SerializableFixture: Default + DeserializeOwned + Message + Serialize
 
     /// Hash the fixture's contents into a Keccak hash.
     fn hash(&self) -> trezoa_keccak_hasher::Hash;
+
+    /// Hash only the fixture's inputs into a Keccak hash, so fixtures which
+    /// exercise the same context share a hash regardless of their effects.
+    ///
+    /// Defaults to `hash`.
+    fn context_hash(&self) -> trezoa_keccak_hasher::Hash {
+        self.hash()
+    }
 }
 
 /// Represents a fixture that can be converted into a serializable fixture.
@@ -39,6 +50,8 @@ where
     SF: SerializableFixture,
 {
     serializable_fixture: SF,
+    /// Overrides the fixture's own hash in file names. See `paired_with`.
+    file_hash: Option<Hash>,
 }
 
 impl<SF> FsHandler<SF>
@@ -49,15 +62,36 @@ where
         let serializable_fixture = fix.into();
         Self {
             serializable_fixture,
+            file_hash: None,
         }
     }
 
+    /// Name this fixture and `other` after a single hash of both fixtures.
+    ///
+    /// Use this when one invocation is dumped in several fixture layouts, so
+    /// the files for the same invocation share a name and can be paired.
+    pub fn paired_with<OF>(self, other: FsHandler<OF>) -> (Self, FsHandler<OF>)
+    where
+        OF: SerializableFixture,
+    {
+        let mut hasher = Hasher::default();
+        hasher.hash(self.serializable_fixture.hash().as_ref());
+        hasher.hash(other.serializable_fixture.hash().as_ref());
+        let file_hash = Some(hasher.result());
+        (Self { file_hash, ..self }, FsHandler { file_hash, ..other })
+    }
+
+    fn file_hash(&self) -> Hash {
+        self.file_hash
+            .unwrap_or_else(|| self.serializable_fixture.hash())
+    }
+
     /// Dumps the fixture to a protobuf binary blob file.
     /// The file name is a hash of the fixture with the `.fix` extension.
     pub fn dump_to_blob_file(&self, dir: &str) {
         let blob = SerializableFixture::encode(&self.serializable_fixture);
 
-        let hash = self.serializable_fixture.hash();
+        let hash = self.file_hash();
         let file_name = format!("instr-{}.fix", bs58::encode(hash).into_string());
 
         write_file(Path::new(dir), &file_name, &blob);
@@ -69,7 +103,7 @@ where
         let json = serde_json::to_string_pretty(&self.serializable_fixture)
             .expect("Failed to serialize fixture to JSON");
 
-        let hash = self.serializable_fixture.hash();
+        let hash = self.file_hash();
         let file_name = format!("instr-{}.json", bs58::encode(hash).into_string());
 
         write_file(Path::new(dir_path), &file_name, json.as_bytes());
@@ -0,0 +1,148 @@
+//! Builder for seeding test accounts.
+//!
+//! Computing PDAs and funding accounts with rent-exempt lamports is common
+//! boilerplate when setting up instruction accounts. `AccountBuilder` takes
+//! care of both, using the harness's configured `Rent` sysvar, and returns
+//! `(Pubkey, Account)` pairs ready for the accounts slice.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::Mollusk;
+//!
+//! let mollusk = Mollusk::new(&program_id, "my_program");
+//!
+//! // A system-owned account, funded with 1 TRZ.
+//! let payer = mollusk
+//!     .account_builder()
+//!     .system()
+//!     .lamports(1_000_000_000)
+//!     .build();
+//!
+//! // A rent-exempt PDA owned by the program, with 64 bytes of space.
+//! let vault = mollusk
+//!     .account_builder()
+//!     .pda(&[b"vault", payer.0.as_ref()], &program_id)
+//!     .space(64)
+//!     .build();
+//!
+//! let accounts = [payer, vault];
+//! ```
+
+use {trezoa_account::Account, trezoa_pubkey::Pubkey, trezoa_rent::Rent};
+
+/// Builder for a single `(Pubkey, Account)` pair.
+///
+/// Unless overridden, the account is assigned a unique address, owned by the
+/// system program, and funded with the rent-exempt minimum for its data.
+pub struct AccountBuilder<'a> {
+    rent: &'a Rent,
+    address: Option<Pubkey>,
+    bump: Option<u8>,
+    data: Vec<u8>,
+    executable: bool,
+    lamports: Option<u64>,
+    owner: Pubkey,
+    rent_epoch: u64,
+}
+
+impl<'a> AccountBuilder<'a> {
+    /// Create a new builder, using the provided rent configuration to compute
+    /// rent-exempt balances.
+    pub fn new(rent: &'a Rent) -> Self {
+        Self {
+            rent,
+            address: None,
+            bump: None,
+            data: Vec::new(),
+            executable: false,
+            lamports: None,
+            owner: trezoa_sdk_ids::system_program::id(),
+            rent_epoch: 0,
+        }
+    }
+
+    /// Set the account's address.
+    pub fn address(mut self, address: &Pubkey) -> Self {
+        self.address = Some(*address);
+        self.bump = None;
+        self
+    }
+
+    /// Derive the account's address as a PDA of `program_id` from the
+    /// provided seeds, and assign the account to `program_id`.
+    ///
+    /// The canonical bump is available via `bump`.
+    pub fn pda(mut self, seeds: &[&[u8]], program_id: &Pubkey) -> Self {
+        let (address, bump) = Pubkey::find_program_address(seeds, program_id);
+        self.address = Some(address);
+        self.bump = Some(bump);
+        self.owner = *program_id;
+        self
+    }
+
+    /// Set the account's owner.
+    pub fn owner(mut self, owner: &Pubkey) -> Self {
+        self.owner = *owner;
+        self
+    }
+
+    /// Assign the account to the system program.
+    pub fn system(self) -> Self {
+        self.owner(&trezoa_sdk_ids::system_program::id())
+    }
+
+    /// Set the account's data.
+    pub fn data(mut self, data: &[u8]) -> Self {
+        self.data = data.to_vec();
+        self
+    }
+
+    /// Set the account's data to `space` zeroed bytes.
+    pub fn space(mut self, space: usize) -> Self {
+        self.data = vec![0; space];
+        self
+    }
+
+    /// Set whether the account is executable.
+    pub fn executable(mut self, executable: bool) -> Self {
+        self.executable = executable;
+        self
+    }
+
+    /// Set the account's lamports, overriding the rent-exempt minimum.
+    pub fn lamports(mut self, lamports: u64) -> Self {
+        self.lamports = Some(lamports);
+        self
+    }
+
+    /// Set the account's rent epoch. Defaults to `0`.
+    ///
+    /// Mollusk preserves the rent epoch through execution, so historical
+    /// accounts can be reproduced exactly.
+    pub fn rent_epoch(mut self, rent_epoch: u64) -> Self {
+        self.rent_epoch = rent_epoch;
+        self
+    }
+
+    /// The canonical bump of the PDA, if the address was set with `pda`.
+    pub fn bump(&self) -> Option<u8> {
+        self.bump
+    }
+
+    /// Build the account.
+    pub fn build(self) -> (Pubkey, Account) {
+        let address = self.address.unwrap_or_else(Pubkey::new_unique);
+        let lamports = self
+            .lamports
+            .unwrap_or_else(|| self.rent.minimum_balance(self.data.len()));
+        (
+            address,
+            Account {
+                lamports,
+                data: self.data,
+                owner: self.owner,
+                executable: self.executable,
+                rent_epoch: self.rent_epoch,
+            },
+        )
+    }
+}
@@ -1,6 +1,52 @@
 //! A trait for implementing an account store, to be used with the
 /// `MolluskContext`.
-use {trezoa_account::Account, trezoa_pubkey::Pubkey, std::collections::HashMap};
+#[cfg(feature = "fs")]
+mod file;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+#[cfg(feature = "fs")]
+pub use file::FileAccountStore;
+use {
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::{
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, HashMap},
+        fmt,
+    },
+};
+
+/// Returned by store-wide queries, such as `MolluskContext::store_view`, when
+/// the account store can't enumerate its accounts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IterationUnsupported;
+
+impl fmt::Display for IterationUnsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the account store does not support iterating over its accounts"
+        )
+    }
+}
+
+impl std::error::Error for IterationUnsupported {}
+
+/// How a missing account was referenced by the instructions being
+/// processed. See `AccountStore::default_account_for`.
+///
+/// References are merged across all instructions, so an account which is
+/// writable in one instruction and readonly in another is writable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MissingAccountHint {
+    /// The account was referenced as a signer.
+    pub is_signer: bool,
+    /// The account was referenced as writable.
+    pub is_writable: bool,
+    /// The account is also the program ID of an instruction.
+    pub is_program_id: bool,
+}
 
 /// A trait for implementing an account store, to be used with the
 /// `MolluskContext`.
@@ -10,11 +56,47 @@ pub trait AccountStore {
         Account::default()
     }
 
+    /// Returns the default account to be used when an account is not found,
+    /// given how the account was referenced.
+    ///
+    /// Stores can override this to construct more realistic defaults, such
+    /// as funded system accounts for writable signers. Defaults to
+    /// `default_account`, ignoring the hint.
+    fn default_account_for(&self, pubkey: &Pubkey, _hint: MissingAccountHint) -> Account {
+        self.default_account(pubkey)
+    }
+
     /// Get an account at the given public key.
     fn get_account(&self, pubkey: &Pubkey) -> Option<Account>;
 
     /// Store an account at the given public key.
     fn store_account(&mut self, pubkey: Pubkey, account: Account);
+
+    /// Store a batch of accounts, such as the resulting accounts of an
+    /// instruction.
+    ///
+    /// Defaults to storing each account with `store_account`. Stores can
+    /// override this to apply bookkeeping, such as eviction, once per batch.
+    fn store_accounts<I>(&mut self, accounts: I)
+    where
+        I: IntoIterator<Item = (Pubkey, Account)>,
+        Self: Sized,
+    {
+        for (pubkey, account) in accounts {
+            self.store_account(pubkey, account);
+        }
+    }
+
+    /// Visit every account in the store, returning whether the store
+    /// supports iteration.
+    ///
+    /// Required for store-wide queries, such as
+    /// `MolluskContext::store_view`. Stores that cannot enumerate their
+    /// contents can keep the default implementation, which visits nothing
+    /// and returns `false`, so those queries return `IterationUnsupported`.
+    fn for_each_account(&self, _f: &mut dyn FnMut(&Pubkey, &Account)) -> bool {
+        false
+    }
 }
 
 impl AccountStore for HashMap<Pubkey, Account> {
@@ -25,4 +107,191 @@ impl AccountStore for HashMap<Pubkey, Account> {
     fn store_account(&mut self, pubkey: Pubkey, account: Account) {
         self.insert(pubkey, account);
     }
+
+    fn store_accounts<I>(&mut self, accounts: I)
+    where
+        I: IntoIterator<Item = (Pubkey, Account)>,
+    {
+        self.extend(accounts);
+    }
+
+    fn for_each_account(&self, f: &mut dyn FnMut(&Pubkey, &Account)) -> bool {
+        self.iter().for_each(|(pubkey, account)| f(pubkey, account));
+        true
+    }
+}
+
+/// A callback receiving accounts evicted from an `LruAccountStore`.
+pub type EvictionCallback = dyn FnMut(Pubkey, Account);
+
+/// A size-bounded account store, which evicts the least recently used
+/// accounts once its accounts exceed a byte budget.
+///
+/// Useful for fuzzing campaigns which stream large state sets through a
+/// `MolluskContext`. Accounts are sized with `LruAccountStore::account_size`.
+/// Both reads and writes count as uses.
+///
+/// Evicted accounts are dropped from the store, so a later instruction
+/// referencing one gets `default_account_for`. To keep them, for example by
+/// spilling them to disk, register an eviction callback.
+///
+/// ```rust,ignore
+/// use mollusk_svm::{account_store::LruAccountStore, Mollusk};
+///
+/// let store = LruAccountStore::new(64 * 1024 * 1024)
+///     .with_eviction_callback(|pubkey, account| spill(pubkey, account));
+/// let context = Mollusk::default().with_context(store);
+/// ```
+pub struct LruAccountStore {
+    accounts: HashMap<Pubkey, LruEntry>,
+    // Account addresses, keyed by the tick of their last use. Reads update
+    // it too, hence the `RefCell`.
+    recency: RefCell<BTreeMap<u64, Pubkey>>,
+    next_tick: Cell<u64>,
+    bytes: usize,
+    max_bytes: usize,
+    evicted: usize,
+    on_evict: Option<Box<EvictionCallback>>,
+}
+
+struct LruEntry {
+    account: Account,
+    last_used: Cell<u64>,
+}
+
+impl LruAccountStore {
+    /// The bytes accounted for each account on top of its data: its address
+    /// and its fields.
+    pub const ACCOUNT_OVERHEAD: usize =
+        std::mem::size_of::<Pubkey>() + std::mem::size_of::<Account>();
+
+    /// Create an empty store holding up to `max_bytes` of accounts.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            accounts: HashMap::new(),
+            recency: RefCell::new(BTreeMap::new()),
+            next_tick: Cell::new(0),
+            bytes: 0,
+            max_bytes,
+            evicted: 0,
+            on_evict: None,
+        }
+    }
+
+    /// Call `on_evict` with every account evicted from the store.
+    pub fn with_eviction_callback(
+        mut self,
+        on_evict: impl FnMut(Pubkey, Account) + 'static,
+    ) -> Self {
+        self.on_evict = Some(Box::new(on_evict));
+        self
+    }
+
+    /// The bytes accounted for an account: `ACCOUNT_OVERHEAD` plus the
+    /// length of its data.
+    pub fn account_size(account: &Account) -> usize {
+        Self::ACCOUNT_OVERHEAD.saturating_add(account.data.len())
+    }
+
+    /// The bytes accounted for every account in the store.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// The maximum bytes of accounts the store holds before evicting.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// The number of accounts in the store.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Whether the store holds no accounts.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// The number of accounts evicted so far.
+    pub fn evicted(&self) -> usize {
+        self.evicted
+    }
+
+    /// Whether the store holds an account at the given public key, without
+    /// counting as a use.
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.accounts.contains_key(pubkey)
+    }
+
+    fn touch(&self, pubkey: &Pubkey, entry: &LruEntry) {
+        let tick = self.next_tick.get();
+        self.next_tick.set(tick + 1);
+        let mut recency = self.recency.borrow_mut();
+        recency.remove(&entry.last_used.get());
+        recency.insert(tick, *pubkey);
+        entry.last_used.set(tick);
+    }
+
+    fn insert(&mut self, pubkey: Pubkey, account: Account) {
+        self.bytes += Self::account_size(&account);
+        let entry = LruEntry {
+            account,
+            last_used: Cell::new(u64::MAX),
+        };
+        self.touch(&pubkey, &entry);
+        if let Some(previous) = self.accounts.insert(pubkey, entry) {
+            self.bytes -= Self::account_size(&previous.account);
+            self.recency.get_mut().remove(&previous.last_used.get());
+        }
+    }
+
+    /// Evict the least recently used accounts until the store fits its
+    /// budget. The most recently used account is never evicted, even if it
+    /// exceeds the budget alone.
+    fn evict(&mut self) {
+        while self.bytes > self.max_bytes && self.accounts.len() > 1 {
+            let Some((_, pubkey)) = self.recency.get_mut().pop_first() else {
+                break;
+            };
+            let entry = self.accounts.remove(&pubkey).unwrap();
+            self.bytes -= Self::account_size(&entry.account);
+            self.evicted += 1;
+            if let Some(on_evict) = self.on_evict.as_mut() {
+                on_evict(pubkey, entry.account);
+            }
+        }
+    }
+}
+
+impl AccountStore for LruAccountStore {
+    fn get_account(&self, pubkey: &Pubkey) -> Option<Account> {
+        let entry = self.accounts.get(pubkey)?;
+        self.touch(pubkey, entry);
+        Some(entry.account.clone())
+    }
+
+    fn store_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.insert(pubkey, account);
+        self.evict();
+    }
+
+    fn store_accounts<I>(&mut self, accounts: I)
+    where
+        I: IntoIterator<Item = (Pubkey, Account)>,
+    {
+        // Evict once the whole batch is stored, rather than after each
+        // account.
+        for (pubkey, account) in accounts {
+            self.insert(pubkey, account);
+        }
+        self.evict();
+    }
+
+    fn for_each_account(&self, f: &mut dyn FnMut(&Pubkey, &Account)) -> bool {
+        self.accounts
+            .iter()
+            .for_each(|(pubkey, entry)| f(pubkey, &entry.account));
+        true
+    }
 }
@@ -0,0 +1,166 @@
+//! A persistent account store, backed by a file on disk.
+//!
+//! Only available when the `fs` feature is enabled.
+
+use {
+    super::AccountStore,
+    mollusk_svm_error::error::{MolluskError, MolluskPanic},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Magic bytes at the start of a `FileAccountStore` file, followed by a
+/// version byte.
+const FILE_STORE_MAGIC: &[u8; 8] = b"MOLLUSKA";
+const FILE_STORE_VERSION: u8 = 1;
+
+/// A persistent account store, backed by a file on disk.
+///
+/// Accounts are held in memory while in use, and written to disk with
+/// `save`. Opening a store at an existing path loads the previously saved
+/// accounts, so long-running suites can resume from a previous state, and
+/// separate test binaries can share the same fixtures.
+///
+/// ```rust,ignore
+/// use mollusk_svm::{account_store::FileAccountStore, Mollusk};
+///
+/// let context = Mollusk::default().with_context(FileAccountStore::open("state.bin"));
+///
+/// // Execute instructions...
+///
+/// context.account_store.borrow().save();
+/// ```
+#[derive(Debug)]
+pub struct FileAccountStore {
+    path: PathBuf,
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl FileAccountStore {
+    /// Open the store at `path`, loading any accounts previously saved
+    /// there. If the file doesn't exist, the store starts empty.
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let accounts = if path.exists() {
+            let data = crate::file::read_file(&path);
+            decode_accounts(&data).or_panic_with(MolluskError::FileReadError(&path))
+        } else {
+            HashMap::new()
+        };
+        Self { path, accounts }
+    }
+
+    /// The path the store is saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write every account in the store to disk, replacing the file's
+    /// previous contents.
+    ///
+    /// Accounts are written sorted by address, so saving the same state
+    /// always produces the same file.
+    pub fn save(&self) {
+        let path = &self.path;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).or_panic_with(MolluskError::FileWriteError(path));
+        }
+        // Write to a temporary file first, so a failed save never leaves a
+        // truncated store behind.
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, encode_accounts(&self.accounts))
+            .or_panic_with(MolluskError::FileWriteError(path));
+        std::fs::rename(&tmp_path, path).or_panic_with(MolluskError::FileWriteError(path));
+    }
+}
+
+impl AccountStore for FileAccountStore {
+    fn get_account(&self, pubkey: &Pubkey) -> Option<Account> {
+        self.accounts.get_account(pubkey)
+    }
+
+    fn store_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.accounts.store_account(pubkey, account);
+    }
+
+    fn for_each_account(&self, f: &mut dyn FnMut(&Pubkey, &Account)) -> bool {
+        self.accounts.for_each_account(f)
+    }
+}
+
+fn encode_accounts(accounts: &HashMap<Pubkey, Account>) -> Vec<u8> {
+    let mut sorted = accounts.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|(pubkey, _)| **pubkey);
+
+    let mut data = Vec::new();
+    data.extend_from_slice(FILE_STORE_MAGIC);
+    data.push(FILE_STORE_VERSION);
+    data.extend_from_slice(&(sorted.len() as u64).to_le_bytes());
+    for (pubkey, account) in sorted {
+        data.extend_from_slice(pubkey.as_ref());
+        data.extend_from_slice(&account.lamports.to_le_bytes());
+        data.extend_from_slice(account.owner.as_ref());
+        data.push(u8::from(account.executable));
+        data.extend_from_slice(&account.rent_epoch.to_le_bytes());
+        data.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+        data.extend_from_slice(&account.data);
+    }
+    data
+}
+
+fn decode_accounts(data: &[u8]) -> Result<HashMap<Pubkey, Account>, &'static str> {
+    const MALFORMED: &str = "malformed account store";
+
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], &'static str> {
+        if data.len() < len {
+            return Err(MALFORMED);
+        }
+        let (taken, rest) = data.split_at(len);
+        *data = rest;
+        Ok(taken)
+    }
+    fn take_u64(data: &mut &[u8]) -> Result<u64, &'static str> {
+        Ok(u64::from_le_bytes(take(data, 8)?.try_into().unwrap()))
+    }
+    fn take_pubkey(data: &mut &[u8]) -> Result<Pubkey, &'static str> {
+        Ok(Pubkey::new_from_array(take(data, 32)?.try_into().unwrap()))
+    }
+
+    let mut data = data;
+    if take(&mut data, FILE_STORE_MAGIC.len())? != FILE_STORE_MAGIC {
+        return Err(MALFORMED);
+    }
+    if take(&mut data, 1)?[0] != FILE_STORE_VERSION {
+        return Err("unsupported account store version");
+    }
+
+    let count = take_u64(&mut data)?;
+    let mut accounts = HashMap::new();
+    for _ in 0..count {
+        let pubkey = take_pubkey(&mut data)?;
+        let lamports = take_u64(&mut data)?;
+        let owner = take_pubkey(&mut data)?;
+        let executable = take(&mut data, 1)?[0] != 0;
+        let rent_epoch = take_u64(&mut data)?;
+        let data_len = usize::try_from(take_u64(&mut data)?).map_err(|_| MALFORMED)?;
+        let account_data = take(&mut data, data_len)?.to_vec();
+        accounts.insert(
+            pubkey,
+            Account {
+                lamports,
+                data: account_data,
+                owner,
+                executable,
+                rent_epoch,
+            },
+        );
+    }
+    if !data.is_empty() {
+        return Err(MALFORMED);
+    }
+    Ok(accounts)
+}
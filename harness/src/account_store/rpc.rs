@@ -0,0 +1,191 @@
+//! An account store which loads accounts from a JSON-RPC endpoint.
+//!
+//! Only available when the `rpc` feature is enabled.
+//!
+//! `RpcAccountStore` fetches any account it hasn't seen yet from the
+//! endpoint, and caches the result locally. Accounts stored by the context -
+//! ie. the results of executed instructions - are only written to the local
+//! cache, so the endpoint is never modified, and later reads see the local
+//! state.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{account_store::rpc::RpcAccountStore, Mollusk};
+//!
+//! let store = RpcAccountStore::new("https://api.mainnet-beta.trezoa.xyz");
+//! // Optionally, load everything the test needs in a single request.
+//! store.prefetch(&[mint, oracle]);
+//!
+//! let context = Mollusk::new(&program_id, "my_program").with_context(store);
+//! context.process_instruction(&instruction);
+//! ```
+
+use {
+    super::AccountStore,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    mollusk_svm_error::error::{MolluskError, MolluskPanic},
+    serde_json::{json, Value},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::{cell::RefCell, collections::HashMap},
+};
+
+/// The maximum number of accounts `getMultipleAccounts` accepts.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// An account store backed by a JSON-RPC endpoint, with local caching.
+pub struct RpcAccountStore {
+    url: String,
+    commitment: String,
+    /// Every account fetched or stored so far. `None` marks an account which
+    /// doesn't exist on the endpoint, so it isn't requested again.
+    cache: RefCell<HashMap<Pubkey, Option<Account>>>,
+}
+
+impl RpcAccountStore {
+    /// Create a new store for the provided endpoint URL, using `confirmed`
+    /// commitment.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            commitment: String::from("confirmed"),
+            cache: RefCell::default(),
+        }
+    }
+
+    /// Set the commitment level to fetch accounts with.
+    pub fn commitment(mut self, commitment: &str) -> Self {
+        self.commitment = commitment.to_string();
+        self
+    }
+
+    /// Fetch any of the provided accounts which aren't cached yet, batching
+    /// them into as few requests as possible.
+    pub fn prefetch(&self, pubkeys: &[Pubkey]) {
+        let missing = {
+            let cache = self.cache.borrow();
+            let mut missing = pubkeys
+                .iter()
+                .filter(|pubkey| !cache.contains_key(pubkey))
+                .copied()
+                .collect::<Vec<_>>();
+            missing.sort();
+            missing.dedup();
+            missing
+        };
+        for chunk in missing.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses = chunk.iter().map(Pubkey::to_string).collect::<Vec<_>>();
+            let result = self.request("getMultipleAccounts", json!([addresses, self.config()]));
+            let values = result["value"]
+                .as_array()
+                .or_panic_with(MolluskError::RpcRequestError(&self.url));
+            let mut cache = self.cache.borrow_mut();
+            for (pubkey, value) in chunk.iter().zip(values) {
+                cache.insert(*pubkey, parse_account(value));
+            }
+        }
+    }
+
+    /// Visit every account cached so far: those fetched from the endpoint,
+    /// and those stored locally. Accounts which haven't been fetched aren't
+    /// visited.
+    pub fn for_each_cached_account(&self, mut f: impl FnMut(&Pubkey, &Account)) {
+        self.cache
+            .borrow()
+            .iter()
+            .filter_map(|(pubkey, account)| Some((pubkey, account.as_ref()?)))
+            .for_each(|(pubkey, account)| f(pubkey, account));
+    }
+
+    fn config(&self) -> Value {
+        json!({
+            "encoding": "base64",
+            "commitment": self.commitment,
+        })
+    }
+
+    fn request(&self, method: &str, params: Value) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let mut response: Value = ureq::post(&self.url)
+            .send_json(request)
+            .or_panic_with(MolluskError::RpcRequestError(&self.url))
+            .into_json()
+            .or_panic_with(MolluskError::RpcRequestError(&self.url));
+        if let Some(error) = response.get("error") {
+            panic!("{}: {}", MolluskError::RpcRequestError(&self.url), error);
+        }
+        response["result"].take()
+    }
+
+    fn fetch_account(&self, pubkey: &Pubkey) -> Option<Account> {
+        let result = self.request("getAccountInfo", json!([pubkey.to_string(), self.config()]));
+        parse_account(&result["value"])
+    }
+}
+
+impl AccountStore for RpcAccountStore {
+    fn get_account(&self, pubkey: &Pubkey) -> Option<Account> {
+        if let Some(account) = self.cache.borrow().get(pubkey) {
+            return account.clone();
+        }
+        let account = self.fetch_account(pubkey);
+        self.cache.borrow_mut().insert(*pubkey, account.clone());
+        account
+    }
+
+    fn store_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.cache.get_mut().insert(pubkey, Some(account));
+    }
+
+    // The endpoint can't be enumerated, so `for_each_account` keeps the
+    // default implementation, and store-wide queries report
+    // `IterationUnsupported` rather than partial results. See
+    // `RpcAccountStore::for_each_cached_account`.
+}
+
+/// Parse an account from a base64-encoded JSON-RPC account value, or `None`
+/// if the account doesn't exist.
+fn parse_account(value: &Value) -> Option<Account> {
+    if value.is_null() {
+        return None;
+    }
+    let parse = || -> Option<Account> {
+        Some(Account {
+            lamports: value["lamports"].as_u64()?,
+            data: BASE64_STANDARD.decode(value["data"][0].as_str()?).ok()?,
+            owner: value["owner"].as_str()?.parse().ok()?,
+            executable: value["executable"].as_bool()?,
+            rent_epoch: value["rentEpoch"].as_u64()?,
+        })
+    };
+    let account = parse().unwrap_or_else(|| panic!("Malformed account in RPC response: {}", value));
+    Some(account)
+}
+
+#[test]
+fn test_parse_account() {
+    let owner = Pubkey::new_unique();
+    let value = json!({
+        "data": [BASE64_STANDARD.encode([1, 2, 3]), "base64"],
+        "executable": false,
+        "lamports": 1_000_000,
+        "owner": owner.to_string(),
+        "rentEpoch": u64::MAX,
+        "space": 3,
+    });
+    assert_eq!(
+        parse_account(&value),
+        Some(Account {
+            lamports: 1_000_000,
+            data: vec![1, 2, 3],
+            owner,
+            executable: false,
+            rent_epoch: u64::MAX,
+        }),
+    );
+    assert_eq!(parse_account(&Value::Null), None);
+}
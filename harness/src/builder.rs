@@ -0,0 +1,136 @@
+//! Builder for a configured Mollusk harness.
+//!
+//! The program cache's runtime environment is derived from the feature set
+//! and compute budget, so changing either after programs are loaded is easy
+//! to get wrong. `MolluskBuilder` applies the configuration first, then loads
+//! the programs, returning a fully configured harness in one expression.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::Mollusk;
+//!
+//! let mollusk = Mollusk::builder()
+//!     .compute_budget(compute_budget)
+//!     .feature_set(feature_set)
+//!     .programs(&[(program_id, "my_program"), (other_id, "other_program")])
+//!     .build();
+//! ```
+
+#[cfg(feature = "fs")]
+use trezoa_pubkey::Pubkey;
+use {
+    crate::{epoch_stake::EpochStake, program::Syscall, sysvar::Sysvars, Mollusk},
+    trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_feature_set::FeatureSet,
+    trezoa_svm_log_collector::LogCollector,
+    std::{cell::RefCell, rc::Rc},
+};
+
+/// Builder for a `Mollusk` instance.
+///
+/// Anything left unset keeps the value from `Mollusk::default`.
+#[derive(Default)]
+pub struct MolluskBuilder {
+    compute_budget: Option<ComputeBudget>,
+    epoch_stake: Option<EpochStake>,
+    feature_set: Option<FeatureSet>,
+    logger: Option<Rc<RefCell<LogCollector>>>,
+    #[cfg(feature = "fs")]
+    programs: Vec<(Pubkey, String)>,
+    syscalls: Vec<(String, Syscall)>,
+    sysvars: Option<Sysvars>,
+}
+
+impl MolluskBuilder {
+    /// Create a new builder, with nothing configured.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the compute budget.
+    pub fn compute_budget(mut self, compute_budget: ComputeBudget) -> Self {
+        self.compute_budget = Some(compute_budget);
+        self
+    }
+
+    /// Set the epoch stake.
+    pub fn epoch_stake(mut self, epoch_stake: EpochStake) -> Self {
+        self.epoch_stake = Some(epoch_stake);
+        self
+    }
+
+    /// Set the feature set.
+    pub fn feature_set(mut self, feature_set: FeatureSet) -> Self {
+        self.feature_set = Some(feature_set);
+        self
+    }
+
+    /// Set the log collector.
+    pub fn logger(mut self, logger: Rc<RefCell<LogCollector>>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Add programs to load, by program ID and ELF file name, under the
+    /// default loader. See `Mollusk::add_program`.
+    #[cfg(feature = "fs")]
+    pub fn programs(mut self, programs: &[(Pubkey, &str)]) -> Self {
+        self.programs.extend(
+            programs
+                .iter()
+                .map(|(program_id, program_name)| (*program_id, program_name.to_string())),
+        );
+        self
+    }
+
+    /// Register a custom syscall, before any programs are loaded. See
+    /// `Mollusk::register_syscall`.
+    pub fn syscall(mut self, name: &str, function: Syscall) -> Self {
+        self.syscalls.push((name.to_string(), function));
+        self
+    }
+
+    /// Set the sysvars.
+    pub fn sysvars(mut self, sysvars: Sysvars) -> Self {
+        self.sysvars = Some(sysvars);
+        self
+    }
+
+    /// Build the `Mollusk` instance.
+    ///
+    /// The program cache is rebuilt for the configured feature set and
+    /// compute budget before any programs are loaded.
+    pub fn build(self) -> Mollusk {
+        let mut mollusk = Mollusk::default();
+
+        if self.compute_budget.is_some() || self.feature_set.is_some() {
+            if let Some(compute_budget) = self.compute_budget {
+                mollusk.compute_budget = compute_budget;
+            }
+            if let Some(feature_set) = self.feature_set {
+                mollusk.feature_set = feature_set;
+            }
+            mollusk
+                .program_cache
+                .set_feature_set(&mollusk.feature_set, &mollusk.compute_budget);
+        }
+        if let Some(epoch_stake) = self.epoch_stake {
+            mollusk.epoch_stake = epoch_stake;
+        }
+        if let Some(logger) = self.logger {
+            mollusk.logger = Some(logger);
+        }
+        if let Some(sysvars) = self.sysvars {
+            mollusk.sysvars = sysvars;
+        }
+
+        for (name, function) in self.syscalls {
+            mollusk.register_syscall(&name, function);
+        }
+        #[cfg(feature = "fs")]
+        for (program_id, program_name) in &self.programs {
+            mollusk.add_program(program_id, program_name);
+        }
+
+        mollusk
+    }
+}
@@ -0,0 +1,65 @@
+//! Elements of an instruction chain with per-instruction overrides.
+//!
+//! `Mollusk::process_and_validate_instruction_chain` runs every instruction
+//! with the harness's compute budget. To model transactions which request
+//! different limits, build the chain from `ChainElement`s instead, and pass
+//! them to `Mollusk::process_and_validate_chain_elements`.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{chain::ChainElement, result::Check, Mollusk};
+//!
+//! let mollusk = Mollusk::new(&program_id, "my_program");
+//!
+//! let small_budget = ComputeBudget {
+//!     compute_unit_limit: 5_000,
+//!     ..mollusk.compute_budget.clone()
+//! };
+//!
+//! mollusk.process_and_validate_chain_elements(
+//!     &[
+//!         ChainElement::new(&instruction0, &[Check::success()]),
+//!         ChainElement::new(&instruction1, &[Check::success()])
+//!             .compute_budget(small_budget),
+//!     ],
+//!     &accounts,
+//! );
+//! ```
+
+use {
+    crate::result::Check, trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_instruction::Instruction,
+};
+
+/// An instruction in a chain, with the checks to run on its result.
+pub struct ChainElement<'a> {
+    /// The instruction to process.
+    pub instruction: &'a Instruction,
+    /// The checks to run on the instruction's result.
+    pub checks: &'a [Check<'a>],
+    /// The compute budget (including heap size) to process the instruction
+    /// with. Defaults to the harness's compute budget.
+    pub compute_budget: Option<ComputeBudget>,
+}
+
+impl<'a> ChainElement<'a> {
+    /// Create a new chain element, using the harness's compute budget.
+    pub fn new(instruction: &'a Instruction, checks: &'a [Check<'a>]) -> Self {
+        Self {
+            instruction,
+            checks,
+            compute_budget: None,
+        }
+    }
+
+    /// Override the compute budget for this instruction only.
+    pub fn compute_budget(mut self, compute_budget: ComputeBudget) -> Self {
+        self.compute_budget = Some(compute_budget);
+        self
+    }
+}
+
+impl<'a> From<(&'a Instruction, &'a [Check<'a>])> for ChainElement<'a> {
+    fn from((instruction, checks): (&'a Instruction, &'a [Check<'a>])) -> Self {
+        Self::new(instruction, checks)
+    }
+}
@@ -4,18 +4,28 @@
 use {
     mollusk_svm_error::error::{MolluskError, MolluskPanic},
     trezoa_account::{Account, AccountSharedData, WritableAccount},
+    trezoa_hash::Hash,
     trezoa_instruction::Instruction,
-    trezoa_message::{LegacyMessage, Message, SanitizedMessage},
+    trezoa_message::{
+        v0::{self, LoadedAddresses, LoadedMessage},
+        AddressLookupTableAccount, LegacyMessage, Message, SanitizedMessage,
+    },
     trezoa_pubkey::Pubkey,
     std::collections::{HashMap, HashSet},
 };
 
+/// Compile a legacy message, with the provided recent blockhash.
+///
+/// If a payer is provided, it's placed first in the message as a writable
+/// signer.
 pub fn compile_accounts<'a>(
     instructions: &[Instruction],
     accounts: impl Iterator<Item = &'a (Pubkey, Account)>,
     fallback_accounts: &HashMap<Pubkey, Account>,
+    payer: Option<&Pubkey>,
+    recent_blockhash: &Hash,
 ) -> (SanitizedMessage, Vec<(Pubkey, AccountSharedData)>) {
-    let message = Message::new(instructions, None);
+    let message = Message::new_with_blockhash(instructions, payer, recent_blockhash);
     let sanitized_message = SanitizedMessage::Legacy(LegacyMessage::new(message, &HashSet::new()));
 
     let accounts: Vec<_> = accounts.collect();
@@ -29,6 +39,77 @@ pub fn compile_accounts<'a>(
     (sanitized_message, transaction_accounts)
 }
 
+/// Compile a v0 message, with the provided recent blockhash, resolving any
+/// accounts found in the provided address lookup tables as loaded addresses.
+///
+/// Unless a payer is provided, the first signer across all instructions is
+/// used as the fee payer.
+pub fn compile_accounts_with_lookup_tables<'a>(
+    instructions: &[Instruction],
+    accounts: impl Iterator<Item = &'a (Pubkey, Account)>,
+    fallback_accounts: &HashMap<Pubkey, Account>,
+    address_lookup_tables: &[AddressLookupTableAccount],
+    payer: Option<&Pubkey>,
+    recent_blockhash: &Hash,
+) -> (SanitizedMessage, Vec<(Pubkey, AccountSharedData)>) {
+    let payer = payer
+        .copied()
+        .or_else(|| {
+            instructions
+                .iter()
+                .flat_map(|ix| ix.accounts.iter())
+                .find(|meta| meta.is_signer)
+                .map(|meta| meta.pubkey)
+        })
+        .or_panic_with(MolluskError::FeePayerMissing);
+
+    let message = v0::Message::try_compile(
+        &payer,
+        instructions,
+        address_lookup_tables,
+        *recent_blockhash,
+    )
+    .or_panic_with(MolluskError::MessageCompileError);
+
+    // Resolve the loaded addresses the same way a validator does: all
+    // writable lookups in table order, followed by all readonly lookups.
+    let mut loaded_addresses = LoadedAddresses::default();
+    for lookup in &message.address_table_lookups {
+        let table = address_lookup_tables
+            .iter()
+            .find(|table| table.key == lookup.account_key)
+            .or_panic_with(MolluskError::AccountMissing(&lookup.account_key));
+        loaded_addresses.writable.extend(
+            lookup
+                .writable_indexes
+                .iter()
+                .map(|index| table.addresses[*index as usize]),
+        );
+        loaded_addresses.readonly.extend(
+            lookup
+                .readonly_indexes
+                .iter()
+                .map(|index| table.addresses[*index as usize]),
+        );
+    }
+
+    let sanitized_message = SanitizedMessage::V0(LoadedMessage::new(
+        message,
+        loaded_addresses,
+        &HashSet::new(),
+    ));
+
+    let accounts: Vec<_> = accounts.collect();
+    let transaction_accounts = build_transaction_accounts(
+        &sanitized_message,
+        &accounts,
+        instructions,
+        fallback_accounts,
+    );
+
+    (sanitized_message, transaction_accounts)
+}
+
 fn build_transaction_accounts(
     message: &SanitizedMessage,
     accounts: &[&(Pubkey, Account)],
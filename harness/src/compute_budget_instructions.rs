@@ -0,0 +1,138 @@
+//! Compute budget program instruction processing.
+//!
+//! Like a validator, Mollusk applies compute budget program instructions to
+//! the transaction's budget up front, rather than executing them as programs.
+
+use {
+    trezoa_compute_budget::compute_budget::ComputeBudget, trezoa_instruction::Instruction,
+    trezoa_instruction_error::InstructionError, trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
+};
+
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+const MIN_HEAP_FRAME_BYTES: u32 = 32 * 1024;
+const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+const HEAP_FRAME_BYTES_ALIGNMENT: u32 = 1024;
+
+// `ComputeBudgetInstruction` discriminators (Borsh).
+const REQUEST_HEAP_FRAME: u8 = 1;
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+const SET_LOADED_ACCOUNTS_DATA_SIZE_LIMIT: u8 = 4;
+
+pub(crate) fn is_compute_budget_program(program_id: &Pubkey) -> bool {
+    *program_id == trezoa_sdk_ids::compute_budget::id()
+}
+
+/// Compute budget settings requested by a transaction.
+#[derive(Default)]
+struct RequestedComputeBudget {
+    compute_unit_limit: Option<u32>,
+    heap_size: Option<u32>,
+    compute_unit_price: Option<u64>,
+    loaded_accounts_data_size_limit: Option<u32>,
+}
+
+fn parse_u32(data: &[u8]) -> Option<u32> {
+    data.try_into().ok().map(u32::from_le_bytes)
+}
+
+fn parse_u64(data: &[u8]) -> Option<u64> {
+    data.try_into().ok().map(u64::from_le_bytes)
+}
+
+/// Apply any compute budget program instructions to the provided budget,
+/// returning it along with the requested compute unit price, in
+/// micro-lamports, or `0` if none is requested.
+///
+/// Only the compute unit limit and heap size affect execution. The compute
+/// unit price is only charged when a fee model is configured, and the loaded
+/// accounts data size limit only applies when transaction limits are
+/// enforced. See `loaded_accounts_data_size_limit`.
+pub(crate) fn apply_compute_budget_instructions(
+    compute_budget: &ComputeBudget,
+    instructions: &[Instruction],
+) -> Result<(ComputeBudget, u64), TransactionError> {
+    let mut requested = RequestedComputeBudget::default();
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        if !is_compute_budget_program(&instruction.program_id) {
+            continue;
+        }
+
+        let invalid = || {
+            TransactionError::InstructionError(
+                index as u8,
+                InstructionError::InvalidInstructionData,
+            )
+        };
+        let duplicate = || TransactionError::DuplicateInstruction(index as u8);
+
+        let Some((discriminator, rest)) = instruction.data.split_first() else {
+            return Err(invalid());
+        };
+        match *discriminator {
+            REQUEST_HEAP_FRAME => {
+                let bytes = parse_u32(rest).ok_or_else(invalid)?;
+                if requested.heap_size.replace(bytes).is_some() {
+                    return Err(duplicate());
+                }
+                if !(MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes)
+                    || bytes % HEAP_FRAME_BYTES_ALIGNMENT != 0
+                {
+                    return Err(invalid());
+                }
+            }
+            SET_COMPUTE_UNIT_LIMIT => {
+                let units = parse_u32(rest).ok_or_else(invalid)?;
+                if requested.compute_unit_limit.replace(units).is_some() {
+                    return Err(duplicate());
+                }
+            }
+            SET_COMPUTE_UNIT_PRICE => {
+                let price = parse_u64(rest).ok_or_else(invalid)?;
+                if requested.compute_unit_price.replace(price).is_some() {
+                    return Err(duplicate());
+                }
+            }
+            SET_LOADED_ACCOUNTS_DATA_SIZE_LIMIT => {
+                let bytes = parse_u32(rest).ok_or_else(invalid)?;
+                if requested
+                    .loaded_accounts_data_size_limit
+                    .replace(bytes)
+                    .is_some()
+                {
+                    return Err(duplicate());
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    let mut compute_budget = compute_budget.clone();
+    if let Some(units) = requested.compute_unit_limit {
+        compute_budget.compute_unit_limit = u64::from(units.min(MAX_COMPUTE_UNIT_LIMIT));
+    }
+    if let Some(bytes) = requested.heap_size {
+        compute_budget.heap_size = bytes;
+    }
+    Ok((
+        compute_budget,
+        requested.compute_unit_price.unwrap_or_default(),
+    ))
+}
+
+/// The loaded accounts data size limit, in bytes, requested by the provided
+/// instructions, if any.
+///
+/// Assumes the instructions have already passed
+/// `apply_compute_budget_instructions`.
+pub(crate) fn loaded_accounts_data_size_limit(instructions: &[Instruction]) -> Option<u32> {
+    instructions
+        .iter()
+        .filter(|instruction| is_compute_budget_program(&instruction.program_id))
+        .find_map(|instruction| match instruction.data.split_first() {
+            Some((&SET_LOADED_ACCOUNTS_DATA_SIZE_LIMIT, rest)) => parse_u32(rest),
+            _ => None,
+        })
+}
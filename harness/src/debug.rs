@@ -0,0 +1,312 @@
+//! Step-through debugging of instructions and their CPIs.
+//!
+//! A `DebugSession` records every program invocation made while it's
+//! recording, top-level instructions and CPIs alike, so a test can inspect
+//! how state changed at each level of a nested CPI once execution is over.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::debug::DebugSession;
+//!
+//! let (result, session) =
+//!     DebugSession::record(|| mollusk.process_instruction(&instruction, &accounts));
+//!
+//! // The accounts passed to the first CPI, as the callee saw them.
+//! let before = session.state_before_cpi(0).unwrap();
+//! // The same accounts, once the callee returned.
+//! let after = session.state_after_cpi(0).unwrap();
+//! ```
+//!
+//! Invocations are recorded by wrapping the default builtin programs,
+//! including the loaders which execute SBF programs. Builtins added with
+//! `ProgramCache::add_builtin`, such as stubs, aren't recorded, nor are
+//! precompiles.
+
+use {
+    crate::program::{loader_keys, Builtin},
+    trezoa_account::{Account, ReadableAccount},
+    trezoa_instruction_error::InstructionError,
+    trezoa_program_runtime::{
+        invoke_context::{BuiltinFunctionWithContext, InvokeContext},
+        trezoa_sbpf::{declare_builtin_function, memory_region::MemoryMapping},
+    },
+    trezoa_pubkey::Pubkey,
+    std::cell::RefCell,
+};
+
+/// A single program invocation, at any stack height.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DebugFrame {
+    /// The invoked program.
+    pub program_id: Pubkey,
+    /// The stack height of the invocation. Top-level instructions have a
+    /// stack height of 1, and CPIs anything greater.
+    pub stack_height: usize,
+    /// The instruction data.
+    pub instruction_data: Vec<u8>,
+    /// The instruction's accounts when the program was invoked, in
+    /// instruction order. For a CPI, these include the caller's changes up to
+    /// the CPI.
+    pub accounts_before: Vec<(Pubkey, Account)>,
+    /// The instruction's accounts when the program returned, in instruction
+    /// order.
+    pub accounts_after: Vec<(Pubkey, Account)>,
+    /// The compute units remaining when the program was invoked.
+    pub compute_units_remaining_before: u64,
+    /// The compute units remaining when the program returned.
+    pub compute_units_remaining_after: u64,
+    /// The result of the invocation.
+    pub result: Result<(), InstructionError>,
+}
+
+impl DebugFrame {
+    /// The compute units consumed by the invocation, including any CPIs it
+    /// made.
+    pub fn compute_units_consumed(&self) -> u64 {
+        self.compute_units_remaining_before
+            .saturating_sub(self.compute_units_remaining_after)
+    }
+
+    /// Whether the invocation is a CPI.
+    pub fn is_cpi(&self) -> bool {
+        self.stack_height > 1
+    }
+}
+
+/// The program invocations recorded by `DebugSession::record`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugSession {
+    frames: Vec<DebugFrame>,
+}
+
+#[derive(Default)]
+struct Recorder {
+    frames: Vec<DebugFrame>,
+    /// Indices of the frames which haven't returned yet.
+    open: Vec<usize>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+impl DebugSession {
+    /// Record every program invocation made on this thread while running
+    /// `f`.
+    pub fn record<R>(f: impl FnOnce() -> R) -> (R, Self) {
+        let previous = RECORDER.with(|recorder| recorder.replace(Some(Recorder::default())));
+        let result = f();
+        let recorder = RECORDER.with(|recorder| recorder.replace(previous));
+        let frames = recorder.map(|recorder| recorder.frames).unwrap_or_default();
+        (result, Self { frames })
+    }
+
+    /// Every recorded invocation, in the order the programs were invoked.
+    pub fn frames(&self) -> &[DebugFrame] {
+        &self.frames
+    }
+
+    /// The recorded invocations at the provided stack height.
+    pub fn frames_at_height(&self, stack_height: usize) -> impl Iterator<Item = &DebugFrame> {
+        self.frames
+            .iter()
+            .filter(move |frame| frame.stack_height == stack_height)
+    }
+
+    /// The recorded CPIs, in the order they were made.
+    pub fn cpis(&self) -> impl Iterator<Item = &DebugFrame> {
+        self.frames.iter().filter(|frame| frame.is_cpi())
+    }
+
+    /// The CPI at the provided index, counting from zero in the order CPIs
+    /// were made.
+    pub fn cpi(&self, index: usize) -> Option<&DebugFrame> {
+        self.cpis().nth(index)
+    }
+
+    /// The accounts passed to a CPI, when the callee was invoked. See
+    /// `cpi`.
+    pub fn state_before_cpi(&self, index: usize) -> Option<&[(Pubkey, Account)]> {
+        self.cpi(index)
+            .map(|frame| frame.accounts_before.as_slice())
+    }
+
+    /// The accounts passed to a CPI, when the callee returned. See `cpi`.
+    pub fn state_after_cpi(&self, index: usize) -> Option<&[(Pubkey, Account)]> {
+        self.cpi(index).map(|frame| frame.accounts_after.as_slice())
+    }
+}
+
+fn instruction_accounts(invoke_context: &InvokeContext) -> Vec<(Pubkey, Account)> {
+    let transaction_context = &invoke_context.transaction_context;
+    let Ok(instruction_context) = transaction_context.get_current_instruction_context() else {
+        return Vec::new();
+    };
+    (0..instruction_context.get_number_of_instruction_accounts())
+        .filter_map(|index| {
+            let index = instruction_context
+                .get_index_of_instruction_account_in_transaction(index)
+                .ok()?;
+            let key = *transaction_context
+                .get_key_of_account_at_index(index)
+                .ok()?;
+            let account = transaction_context.accounts().try_borrow(index).ok()?;
+            let account = Account {
+                lamports: account.lamports(),
+                data: account.data().to_vec(),
+                owner: *account.owner(),
+                executable: account.executable(),
+                rent_epoch: account.rent_epoch(),
+            };
+            Some((key, account))
+        })
+        .collect()
+}
+
+fn is_recording() -> bool {
+    RECORDER.with(|recorder| recorder.borrow().is_some())
+}
+
+/// Invoke a builtin's entrypoint, recording the invocation if a session is
+/// recording.
+fn record_invocation(
+    invoke_context: &mut InvokeContext,
+    invoke: impl FnOnce(&mut InvokeContext) -> Result<u64, Box<dyn std::error::Error>>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    if !is_recording() {
+        return invoke(invoke_context);
+    }
+
+    let (program_id, instruction_data) = invoke_context
+        .transaction_context
+        .get_current_instruction_context()
+        .and_then(|instruction_context| {
+            Ok((
+                *instruction_context.get_program_key()?,
+                instruction_context.get_instruction_data().to_vec(),
+            ))
+        })
+        .unwrap_or_default();
+    let frame = DebugFrame {
+        program_id,
+        stack_height: invoke_context.get_stack_height(),
+        instruction_data,
+        accounts_before: instruction_accounts(invoke_context),
+        accounts_after: Vec::new(),
+        compute_units_remaining_before: invoke_context.get_remaining(),
+        compute_units_remaining_after: 0,
+        result: Ok(()),
+    };
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.open.push(recorder.frames.len());
+            recorder.frames.push(frame);
+        }
+    });
+
+    let result = invoke(invoke_context);
+
+    let accounts_after = instruction_accounts(invoke_context);
+    let compute_units_remaining_after = invoke_context.get_remaining();
+    // The runtime reports errors other than an `InstructionError` as
+    // `ProgramFailedToComplete`.
+    let frame_result = match &result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err
+            .downcast_ref::<InstructionError>()
+            .cloned()
+            .unwrap_or(InstructionError::ProgramFailedToComplete)),
+    };
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            if let Some(index) = recorder.open.pop() {
+                let frame = &mut recorder.frames[index];
+                frame.accounts_after = accounts_after;
+                frame.compute_units_remaining_after = compute_units_remaining_after;
+                frame.result = frame_result;
+            }
+        }
+    });
+
+    result
+}
+
+macro_rules! recorded_entrypoint {
+    ($name:ident, $entrypoint:path) => {
+        declare_builtin_function!(
+            $name,
+            fn rust(
+                invoke_context: &mut InvokeContext,
+                arg0: u64,
+                arg1: u64,
+                arg2: u64,
+                arg3: u64,
+                arg4: u64,
+                memory_mapping: &mut MemoryMapping,
+            ) -> Result<u64, Box<dyn std::error::Error>> {
+                record_invocation(invoke_context, |invoke_context| {
+                    <$entrypoint>::rust(
+                        invoke_context,
+                        arg0,
+                        arg1,
+                        arg2,
+                        arg3,
+                        arg4,
+                        memory_mapping,
+                    )
+                })
+            }
+        );
+    };
+}
+
+recorded_entrypoint!(
+    SystemProgram,
+    trezoa_system_program::system_processor::Entrypoint
+);
+recorded_entrypoint!(BpfLoader, trezoa_bpf_loader_program::Entrypoint);
+#[cfg(feature = "all-builtins")]
+recorded_entrypoint!(LoaderV4, trezoa_loader_v4_program::Entrypoint);
+#[cfg(feature = "all-builtins")]
+recorded_entrypoint!(
+    StakeProgram,
+    trezoa_stake_program::stake_instruction::Entrypoint
+);
+#[cfg(feature = "all-builtins")]
+recorded_entrypoint!(VoteProgram, trezoa_vote_program::vote_processor::Entrypoint);
+#[cfg(feature = "all-builtins")]
+recorded_entrypoint!(
+    ZkElGamalProofProgram,
+    trezoa_zk_elgamal_proof_program::Entrypoint
+);
+
+fn recorded_entrypoints() -> Vec<(Pubkey, BuiltinFunctionWithContext)> {
+    vec![
+        (trezoa_system_program::id(), SystemProgram::vm),
+        (loader_keys::LOADER_V2, BpfLoader::vm),
+        (loader_keys::LOADER_V3, BpfLoader::vm),
+        #[cfg(feature = "all-builtins")]
+        (loader_keys::LOADER_V1, BpfLoader::vm),
+        #[cfg(feature = "all-builtins")]
+        (loader_keys::LOADER_V4, LoaderV4::vm),
+        #[cfg(feature = "all-builtins")]
+        (trezoa_sdk_ids::stake::id(), StakeProgram::vm),
+        #[cfg(feature = "all-builtins")]
+        (trezoa_sdk_ids::vote::id(), VoteProgram::vm),
+        #[cfg(feature = "all-builtins")]
+        (
+            trezoa_sdk_ids::zk_elgamal_proof_program::id(),
+            ZkElGamalProofProgram::vm,
+        ),
+    ]
+}
+
+/// Wrap a default builtin so its invocations are recorded, if it's
+/// supported.
+pub(crate) fn wrap_builtin(builtin: &Builtin) -> Option<Builtin> {
+    recorded_entrypoints()
+        .into_iter()
+        .find(|(program_id, _)| program_id == builtin.program_id())
+        .map(|(program_id, entrypoint)| {
+            Builtin::new(program_id, builtin.name().to_string(), entrypoint)
+        })
+}
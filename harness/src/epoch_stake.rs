@@ -1,4 +1,15 @@
-use {trezoa_pubkey::Pubkey, std::collections::HashMap};
+use {
+    crate::stake::create_delegated_stake_account,
+    trezoa_account::{Account, ReadableAccount},
+    trezoa_clock::Epoch,
+    trezoa_pubkey::Pubkey,
+    trezoa_rent::Rent,
+    trezoa_stake_interface::{
+        stake_history::{StakeHistory, StakeHistoryEntry},
+        state::StakeStateV2,
+    },
+    std::collections::HashMap,
+};
 
 /// A simple map of vote accounts to their epoch stake.
 ///
@@ -33,6 +44,268 @@ pub fn create_mock_epoch_stake(target_total: u64) -> EpochStake {
     epoch_stake
 }
 
+/// The size of a vote account (`VoteStateV3`).
+pub const VOTE_STATE_SIZE: usize = 3762;
+
+// Serialize a fresh `VoteStateVersions::V3` with the provided authorities and
+// no voting history, using the vote program's bincode layout.
+fn serialize_vote_state(
+    node_pubkey: &Pubkey,
+    authorized_voter: &Pubkey,
+    authorized_withdrawer: &Pubkey,
+    commission: u8,
+    epoch: Epoch,
+) -> Vec<u8> {
+    const VOTE_STATE_V3_TAG: u32 = 2;
+    const PRIOR_VOTERS_MAX_ITEMS: usize = 32;
+
+    let mut data = Vec::with_capacity(VOTE_STATE_SIZE);
+    data.extend_from_slice(&VOTE_STATE_V3_TAG.to_le_bytes());
+    // node_pubkey, authorized_withdrawer, commission.
+    data.extend_from_slice(node_pubkey.as_ref());
+    data.extend_from_slice(authorized_withdrawer.as_ref());
+    data.push(commission);
+    // votes: empty.
+    data.extend_from_slice(&0u64.to_le_bytes());
+    // root_slot: none.
+    data.push(0);
+    // authorized_voters: a single entry for the current epoch.
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&epoch.to_le_bytes());
+    data.extend_from_slice(authorized_voter.as_ref());
+    // prior_voters: an empty circular buffer of `(Pubkey, Epoch, Epoch)`.
+    data.resize(data.len() + PRIOR_VOTERS_MAX_ITEMS * (32 + 8 + 8), 0);
+    data.extend_from_slice(&(PRIOR_VOTERS_MAX_ITEMS as u64 - 1).to_le_bytes());
+    data.push(1);
+    // epoch_credits: empty.
+    data.extend_from_slice(&0u64.to_le_bytes());
+    // last_timestamp: `(slot, timestamp)`, both zero.
+    data.extend_from_slice(&[0; 16]);
+
+    data.resize(VOTE_STATE_SIZE, 0);
+    data
+}
+
+/// Create a vote account for the provided vote address and stake.
+///
+/// The account holds an initialized `VoteState` whose node identity and
+/// authorities are all set to the vote address itself. Its balance is the
+/// rent-exempt minimum plus `stake`, so programs which cross-check epoch stake
+/// syscall values against vote account balances see consistent data.
+pub fn create_vote_account(
+    vote_address: &Pubkey,
+    stake: u64,
+    rent: &Rent,
+    epoch: Epoch,
+) -> Account {
+    Account {
+        lamports: rent.minimum_balance(VOTE_STATE_SIZE).saturating_add(stake),
+        data: serialize_vote_state(vote_address, vote_address, vote_address, 0, epoch),
+        owner: trezoa_sdk_ids::vote::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Create a rent-exempt vote account with a distinct node identity and
+/// authorities, as created by the vote program's `InitializeAccount`.
+///
+/// The authorized voter is set for the provided epoch, and the account has
+/// no voting history.
+pub fn create_vote_account_with_authorities(
+    node_pubkey: &Pubkey,
+    authorized_voter: &Pubkey,
+    authorized_withdrawer: &Pubkey,
+    commission: u8,
+    rent: &Rent,
+    epoch: Epoch,
+) -> Account {
+    Account {
+        lamports: rent.minimum_balance(VOTE_STATE_SIZE),
+        data: serialize_vote_state(
+            node_pubkey,
+            authorized_voter,
+            authorized_withdrawer,
+            commission,
+            epoch,
+        ),
+        owner: trezoa_sdk_ids::vote::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Materialize a vote account for every entry in the provided `EpochStake`,
+/// sorted by address.
+///
+/// See [`create_vote_account`].
+pub fn create_vote_accounts(
+    epoch_stake: &EpochStake,
+    rent: &Rent,
+    epoch: Epoch,
+) -> Vec<(Pubkey, Account)> {
+    let mut accounts = epoch_stake
+        .iter()
+        .map(|(vote_address, stake)| {
+            (
+                *vote_address,
+                create_vote_account(vote_address, *stake, rent, epoch),
+            )
+        })
+        .collect::<Vec<_>>();
+    accounts.sort_by(|(a, _), (b, _)| a.cmp(b));
+    accounts
+}
+
+/// The activation epoch of bootstrap delegations, which are fully effective
+/// in every epoch, like those created at genesis.
+pub const BOOTSTRAP_ACTIVATION_EPOCH: Epoch = Epoch::MAX;
+
+/// A builder for a consistent set of vote accounts, the stake accounts
+/// delegated to them, and the resulting `EpochStake`.
+///
+/// Delegations are bootstrap delegations, so they're fully effective
+/// regardless of the current epoch or stake history.
+///
+/// ```rust,ignore
+/// use mollusk_svm::epoch_stake::EpochStakeBuilder;
+///
+/// let accounts = mollusk.set_epoch_stake(
+///     EpochStakeBuilder::default()
+///         .vote_account(vote_a, 1_000_000_000)
+///         .vote_account(vote_b, 2_000_000_000)
+///         .delegation(vote_b, 500_000_000),
+/// );
+/// ```
+#[derive(Default)]
+pub struct EpochStakeBuilder {
+    stake_authority: Option<Pubkey>,
+    vote_accounts: Vec<Pubkey>,
+    delegations: Vec<(Pubkey, u64)>,
+}
+
+impl EpochStakeBuilder {
+    /// Set the staker and withdrawer of every generated stake account.
+    /// Defaults to a unique address.
+    pub fn stake_authority(mut self, stake_authority: Pubkey) -> Self {
+        self.stake_authority = Some(stake_authority);
+        self
+    }
+
+    /// Add a vote account with a single stake account delegating `stake`
+    /// lamports to it. A `stake` of zero adds the vote account alone.
+    pub fn vote_account(mut self, vote_address: Pubkey, stake: u64) -> Self {
+        if !self.vote_accounts.contains(&vote_address) {
+            self.vote_accounts.push(vote_address);
+        }
+        if stake > 0 {
+            self.delegations.push((vote_address, stake));
+        }
+        self
+    }
+
+    /// Add a stake account delegating `stake` lamports to the provided vote
+    /// account, adding the vote account if it hasn't been added already.
+    pub fn delegation(self, vote_address: Pubkey, stake: u64) -> Self {
+        self.vote_account(vote_address, stake)
+    }
+
+    /// Generate the accounts, as of the provided epoch.
+    pub fn build(self, rent: &Rent, epoch: Epoch) -> EpochStakeAccounts {
+        let stake_authority = self.stake_authority.unwrap_or_else(Pubkey::new_unique);
+
+        let mut epoch_stake: EpochStake = self
+            .vote_accounts
+            .iter()
+            .map(|vote_address| (*vote_address, 0))
+            .collect();
+        let stake_accounts = self
+            .delegations
+            .into_iter()
+            .map(|(vote_address, stake)| {
+                *epoch_stake.entry(vote_address).or_default() += stake;
+                let account = create_delegated_stake_account(
+                    &stake_authority,
+                    &vote_address,
+                    stake,
+                    rent,
+                    BOOTSTRAP_ACTIVATION_EPOCH,
+                );
+                (Pubkey::new_unique(), account)
+            })
+            .collect();
+        let vote_accounts = self
+            .vote_accounts
+            .iter()
+            .map(|vote_address| {
+                let account =
+                    create_vote_account(vote_address, epoch_stake[vote_address], rent, epoch);
+                (*vote_address, account)
+            })
+            .collect();
+
+        EpochStakeAccounts {
+            stake_history_entry: StakeHistoryEntry::with_effective(epoch_stake.values().sum()),
+            epoch_stake,
+            stake_authority,
+            vote_accounts,
+            stake_accounts,
+        }
+    }
+}
+
+/// The output of an [`EpochStakeBuilder`].
+pub struct EpochStakeAccounts {
+    /// The stake of each vote account.
+    pub epoch_stake: EpochStake,
+    /// The stake history entry for the current epoch, with the total stake
+    /// fully effective.
+    pub stake_history_entry: StakeHistoryEntry,
+    /// The staker and withdrawer of every stake account.
+    pub stake_authority: Pubkey,
+    /// The vote accounts, in the order they were added.
+    pub vote_accounts: Vec<(Pubkey, Account)>,
+    /// The stake accounts, in the order they were added.
+    pub stake_accounts: Vec<(Pubkey, Account)>,
+}
+
+impl EpochStakeAccounts {
+    /// All vote and stake accounts, vote accounts first.
+    pub fn accounts(&self) -> Vec<(Pubkey, Account)> {
+        self.vote_accounts
+            .iter()
+            .chain(&self.stake_accounts)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Update the stake of every vote account delegated to by the provided
+/// stake accounts, to the sum of their effective delegations in `epoch`.
+///
+/// Vote accounts without any delegations among the provided accounts keep
+/// their stake. Accounts which aren't delegated stake accounts are ignored.
+pub fn sync_epoch_stake<'a, A: ReadableAccount + 'a>(
+    epoch_stake: &mut EpochStake,
+    accounts: impl IntoIterator<Item = &'a A>,
+    epoch: Epoch,
+    stake_history: &StakeHistory,
+    new_rate_activation_epoch: Option<Epoch>,
+) {
+    let mut synced = EpochStake::new();
+    for account in accounts {
+        if account.owner() != &trezoa_sdk_ids::stake::id() {
+            continue;
+        }
+        if let Ok(StakeStateV2::Stake(_, stake, _)) = bincode::deserialize(account.data()) {
+            let delegation = stake.delegation;
+            *synced.entry(delegation.voter_pubkey).or_default() +=
+                delegation.stake(epoch, stake_history, new_rate_activation_epoch);
+        }
+    }
+    epoch_stake.extend(synced);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,4 +424,27 @@ mod tests {
         assert_eq!(stakes.iter().filter(|&&s| s == 100_000_000_000).count(), 11);
         assert_eq!(stakes.iter().filter(|&&s| s == 134_567_890_123).count(), 1);
     }
+
+    #[test]
+    fn test_create_vote_accounts() {
+        let rent = Rent::default();
+        let epoch_stake = create_mock_epoch_stake(250_000_000_000);
+
+        let accounts = create_vote_accounts(&epoch_stake, &rent, 5);
+        assert_eq!(accounts.len(), 2);
+        assert!(accounts.windows(2).all(|w| w[0].0 < w[1].0));
+
+        for (vote_address, account) in accounts {
+            let stake = epoch_stake[&vote_address];
+            assert_eq!(account.owner, trezoa_sdk_ids::vote::id());
+            assert_eq!(account.data.len(), VOTE_STATE_SIZE);
+            assert_eq!(
+                account.lamports,
+                rent.minimum_balance(VOTE_STATE_SIZE) + stake
+            );
+            // Variant tag, then the node identity.
+            assert_eq!(&account.data[..4], &2u32.to_le_bytes());
+            assert_eq!(&account.data[4..36], vote_address.as_ref());
+        }
+    }
 }
@@ -0,0 +1,157 @@
+//! Opt-in transaction fee simulation.
+//!
+//! By default, Mollusk doesn't charge fees. Set `Mollusk::fee_model` to have
+//! `process_transaction_instructions` charge the fee payer before execution,
+//! as a validator would, so end-to-end balances match.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{fees::FeeModel, Mollusk};
+//!
+//! let mut mollusk = Mollusk::default();
+//! mollusk.fee_model = Some(FeeModel::default().fee_payer(payer));
+//!
+//! let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+//! ```
+//!
+//! The fee is charged even if execution fails. If the fee payer can't pay,
+//! no instruction is executed, and the transaction fails with the same
+//! `TransactionError` a validator would return.
+
+use {
+    crate::DEFAULT_LAMPORTS_PER_SIGNATURE,
+    trezoa_account::{AccountSharedData, ReadableAccount, WritableAccount},
+    trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_instruction::Instruction,
+    trezoa_message::SanitizedMessage,
+    trezoa_pubkey::Pubkey,
+    trezoa_rent::Rent,
+    trezoa_transaction_error::TransactionError,
+};
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// The fee structure used to charge transactions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeModel {
+    /// The account which pays the fee. It's placed first in the compiled
+    /// message, as a writable signer. If `None`, the first signer across all
+    /// instructions pays.
+    pub fee_payer: Option<Pubkey>,
+    /// Lamports charged per signature, including the signatures verified by
+    /// precompile instructions. Defaults to `5000`.
+    pub lamports_per_signature: u64,
+    /// Whether to charge the prioritization fee requested by a compute unit
+    /// price instruction. Defaults to `true`.
+    pub prioritization_fee: bool,
+}
+
+impl Default for FeeModel {
+    fn default() -> Self {
+        Self {
+            fee_payer: None,
+            lamports_per_signature: DEFAULT_LAMPORTS_PER_SIGNATURE,
+            prioritization_fee: true,
+        }
+    }
+}
+
+impl FeeModel {
+    /// Designate the account which pays the fee.
+    pub fn fee_payer(mut self, fee_payer: Pubkey) -> Self {
+        self.fee_payer = Some(fee_payer);
+        self
+    }
+
+    /// Set the lamports charged per signature.
+    pub fn lamports_per_signature(mut self, lamports_per_signature: u64) -> Self {
+        self.lamports_per_signature = lamports_per_signature;
+        self
+    }
+
+    /// Set whether to charge the prioritization fee.
+    pub fn prioritization_fee(mut self, prioritization_fee: bool) -> Self {
+        self.prioritization_fee = prioritization_fee;
+        self
+    }
+
+    /// Calculate the fee for a compiled message.
+    ///
+    /// The compute unit price, in micro-lamports, is charged against the
+    /// transaction's compute unit limit, rounded up to the nearest lamport.
+    pub fn calculate_fee(
+        &self,
+        message: &SanitizedMessage,
+        instructions: &[Instruction],
+        compute_budget: &ComputeBudget,
+        compute_unit_price: u64,
+    ) -> u64 {
+        let signature_fee = self
+            .lamports_per_signature
+            .saturating_mul(num_signatures(message, instructions));
+        let prioritization_fee = if self.prioritization_fee {
+            let micro_lamports = u128::from(compute_unit_price)
+                .saturating_mul(u128::from(compute_budget.compute_unit_limit))
+                .div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+            u64::try_from(micro_lamports).unwrap_or(u64::MAX)
+        } else {
+            0
+        };
+        signature_fee.saturating_add(prioritization_fee)
+    }
+}
+
+/// Count the transaction's signatures, plus those verified by precompiles.
+fn num_signatures(message: &SanitizedMessage, instructions: &[Instruction]) -> u64 {
+    let precompile_signatures = instructions
+        .iter()
+        .filter(|ix| {
+            ix.program_id == trezoa_sdk_ids::ed25519_program::id()
+                || ix.program_id == trezoa_sdk_ids::secp256k1_program::id()
+                || ix.program_id == trezoa_sdk_ids::secp256r1_program::id()
+        })
+        .map(|ix| u64::from(ix.data.first().copied().unwrap_or(0)))
+        .sum::<u64>();
+    u64::from(message.header().num_required_signatures).saturating_add(precompile_signatures)
+}
+
+/// Validate the fee payer and deduct the fee from it, like a validator does
+/// before executing a transaction.
+///
+/// The fee payer is always the first transaction account.
+pub(crate) fn charge_fee(
+    fee: u64,
+    rent: &Rent,
+    transaction_accounts: &mut [(Pubkey, AccountSharedData)],
+) -> Result<(), TransactionError> {
+    let (_, fee_payer) = transaction_accounts
+        .first_mut()
+        .ok_or(TransactionError::AccountNotFound)?;
+
+    if fee_payer.lamports() == 0 {
+        return Err(TransactionError::AccountNotFound);
+    }
+    if *fee_payer.owner() != trezoa_sdk_ids::system_program::id() {
+        return Err(TransactionError::InvalidAccountForFee);
+    }
+
+    // Accounts with data (ie. nonce accounts) must stay rent-exempt.
+    let min_balance = if fee_payer.data().is_empty() {
+        0
+    } else {
+        rent.minimum_balance(fee_payer.data().len())
+    };
+    let post_balance = fee_payer
+        .lamports()
+        .checked_sub(min_balance)
+        .and_then(|spendable| spendable.checked_sub(fee))
+        .map(|remaining| remaining + min_balance)
+        .ok_or(TransactionError::InsufficientFundsForFee)?;
+
+    // The fee can't leave the fee payer paying rent.
+    if post_balance != 0 && !rent.is_exempt(post_balance, fee_payer.data().len()) {
+        return Err(TransactionError::InsufficientFundsForRent { account_index: 0 });
+    }
+
+    fee_payer.set_lamports(post_balance);
+    Ok(())
+}
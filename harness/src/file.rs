@@ -17,10 +17,16 @@
 //! Since these functions are intended for the local filesystem and for testing
 //! purposes, most of them will panic if the file is not found or if there is an
 //! error reading the file.
+//!
+//! For workspaces with many programs, `discover_programs` finds every program
+//! ELF in the search paths, along with its program ID.
 
 use {
     mollusk_svm_error::error::{MolluskError, MolluskPanic},
+    trezoa_loader_v3_interface::state::UpgradeableLoaderState,
+    trezoa_pubkey::Pubkey,
     std::{
+        collections::HashMap,
         fs::File,
         io::Read,
         path::{Path, PathBuf},
@@ -82,3 +88,179 @@ pub fn load_program_elf(program_name: &str) -> Vec<u8> {
     let program_file = find_file(&file_name).or_panic_with(MolluskError::FileNotFound(&file_name));
     read_file(program_file)
 }
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// A program dumped from a cluster, such as with `trezoa program dump` or
+/// `trezoa account`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgramDump {
+    /// A raw program ELF.
+    Elf(Vec<u8>),
+    /// The full data of a loader-v3 (Upgradeable) program data account,
+    /// split into its header and ELF.
+    ProgramData {
+        slot: u64,
+        upgrade_authority_address: Option<Pubkey>,
+        elf: Vec<u8>,
+    },
+}
+
+impl ProgramDump {
+    /// The program ELF.
+    pub fn elf(&self) -> &[u8] {
+        match self {
+            Self::Elf(elf) | Self::ProgramData { elf, .. } => elf,
+        }
+    }
+}
+
+/// Load a program dumped from a cluster from the local filesystem.
+///
+/// The dump may be either a raw ELF, or the full data of a loader-v3 program
+/// data account, whose header is detected and parsed. Panics if it's
+/// neither.
+pub fn load_program_dump<P: AsRef<Path>>(path: P) -> ProgramDump {
+    let path = path.as_ref();
+    let data = read_file(path);
+
+    if data.starts_with(ELF_MAGIC) {
+        return ProgramDump::Elf(data);
+    }
+
+    let elf_offset = UpgradeableLoaderState::size_of_programdata_metadata();
+    match data
+        .get(..elf_offset)
+        .and_then(|header| bincode::deserialize(header).ok())
+    {
+        Some(UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address,
+        }) if data[elf_offset..].starts_with(ELF_MAGIC) => ProgramDump::ProgramData {
+            slot,
+            upgrade_authority_address,
+            elf: data[elf_offset..].to_vec(),
+        },
+        _ => panic!("{}", MolluskError::InvalidProgramDump(path)),
+    }
+}
+
+/// The name of the manifest mapping program names to program IDs, read by
+/// `discover_programs`.
+///
+/// The manifest is a JSON object of program names to base58-encoded program
+/// IDs:
+///
+/// ```json
+/// { "my_program": "239vxAL9Q7e3uLoinJpJ873r3bvT9sPFxH7yekwPppNF" }
+/// ```
+pub const PROGRAM_IDS_MANIFEST: &str = "program-ids.json";
+
+/// A program ELF found by `discover_programs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveredProgram {
+    /// The program's name, which is its ELF file name without `.so`.
+    pub name: String,
+    /// The program's ID.
+    pub program_id: Pubkey,
+    /// The path of the program's ELF file.
+    pub path: PathBuf,
+}
+
+/// Find every program ELF in the default search paths, along with its
+/// program ID:
+///
+/// * `tests/fixtures`
+/// * `BPF_OUT_DIR`
+/// * `SBF_OUT_DIR`
+///
+/// See `discover_programs_in`.
+pub fn discover_programs() -> Vec<DiscoveredProgram> {
+    let mut dirs = vec![PathBuf::from("tests/fixtures")];
+    dirs.extend(std::env::var("BPF_OUT_DIR").map(PathBuf::from));
+    dirs.extend(std::env::var("SBF_OUT_DIR").map(PathBuf::from));
+    discover_programs_in(dirs)
+}
+
+/// Find every program ELF (`{program_name}.so`) in the provided directories,
+/// along with its program ID. Missing directories are skipped.
+///
+/// A program's ID is read from the first of these files, in the ELF's
+/// directory, which exists:
+///
+/// * A `program-ids.json` manifest listing the program. See
+///   [`PROGRAM_IDS_MANIFEST`].
+/// * The `{program_name}-keypair.json` file written by `cargo build-sbf`.
+///
+/// ELFs with neither are skipped. If a program is found in several
+/// directories, the first directory takes precedence. Programs are sorted by
+/// name.
+pub fn discover_programs_in<P: AsRef<Path>>(
+    dirs: impl IntoIterator<Item = P>,
+) -> Vec<DiscoveredProgram> {
+    let mut programs: Vec<DiscoveredProgram> = Vec::new();
+    for dir in dirs {
+        let dir = dir.as_ref();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        let manifest_path = dir.join(PROGRAM_IDS_MANIFEST);
+        let manifest = if manifest_path.exists() {
+            read_program_ids_manifest(&manifest_path)
+        } else {
+            HashMap::new()
+        };
+
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "so"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if programs.iter().any(|program| program.name == name) {
+                continue;
+            }
+            let keypair_path = dir.join(format!("{name}-keypair.json"));
+            let program_id = match manifest.get(name) {
+                Some(program_id) => *program_id,
+                None if keypair_path.exists() => read_keypair_pubkey(&keypair_path),
+                None => continue,
+            };
+            programs.push(DiscoveredProgram {
+                name: name.to_string(),
+                program_id,
+                path,
+            });
+        }
+    }
+    programs.sort_by(|a, b| a.name.cmp(&b.name));
+    programs
+}
+
+/// Read a program ID manifest, a JSON object mapping program names to IDs.
+fn read_program_ids_manifest(path: &Path) -> HashMap<String, Pubkey> {
+    serde_json::from_slice::<HashMap<String, String>>(&read_file(path))
+        .ok()
+        .and_then(|manifest| {
+            manifest
+                .into_iter()
+                .map(|(name, program_id)| Some((name, program_id.parse().ok()?)))
+                .collect()
+        })
+        .or_panic_with(MolluskError::InvalidProgramIdFile(path))
+}
+
+/// Read the public key of a keypair file, a JSON array of the keypair's 64
+/// bytes, whose last 32 bytes are the public key.
+fn read_keypair_pubkey(path: &Path) -> Pubkey {
+    let bytes = serde_json::from_slice::<Vec<u8>>(&read_file(path))
+        .ok()
+        .filter(|bytes| bytes.len() == 64)
+        .or_panic_with(MolluskError::InvalidProgramIdFile(path));
+    Pubkey::try_from(&bytes[32..]).unwrap()
+}
@@ -0,0 +1,351 @@
+//! Property-based fuzzing driver.
+//!
+//! Rather than only replaying fixtures, the driver generates them: a
+//! `ContextStrategy` describes the space of inputs for a program (how many
+//! accounts, how much data, which owners, compute budgets within bounds),
+//! and random contexts drawn from it are processed by the harness and
+//! returned as fixtures.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::fuzz::driver::{run_random_instructions, ContextStrategy};
+//!
+//! let mollusk = Mollusk::new(&program_id, "my_program");
+//! let strategy = ContextStrategy::new(&program_id);
+//!
+//! // Differential fuzzing: replay the fixtures against a reference build of
+//! // the program, comparing every effect.
+//! let mut reference = Mollusk::new(&program_id, "my_program_reference");
+//! for fixture in run_random_instructions(&mollusk, &strategy, 42, 1_000) {
+//!     reference.process_and_validate_fixture(&fixture);
+//! }
+//! ```
+//!
+//! Randomness comes from an `Entropy` source, which is either a seeded
+//! `testgen::KeyGen`, so runs are reproducible from their seed, or the raw
+//! input of a coverage-guided fuzzer. With the `arbitrary` feature, `Entropy`
+//! implements `arbitrary::Arbitrary`, so a `cargo fuzz` target can take it
+//! as its input and drive the strategy directly:
+//!
+//! ```rust,ignore
+//! fuzz_target!(|entropy: Entropy| {
+//!     let mut entropy = entropy;
+//!     let context = strategy.generate(&mollusk, &mut entropy);
+//!     run_context(&mollusk, context);
+//! });
+//! ```
+//!
+//! With the `proptest` feature, a strategy bound to a harness with
+//! `ContextStrategy::with_harness` is a `proptest` strategy, and `Entropy`
+//! implements `proptest::arbitrary::Arbitrary`:
+//!
+//! ```rust,ignore
+//! proptest!(|(context in strategy.with_harness(&mollusk))| {
+//!     run_context(&mollusk, context);
+//! });
+//! ```
+
+use {
+    crate::{chain::ChainElement, testgen::KeyGen, Mollusk},
+    mollusk_svm_fuzz_fixture::{context::Context, effects::Effects, Fixture},
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    std::ops::RangeInclusive,
+};
+
+/// A source of randomness for generating contexts.
+#[derive(Clone, Debug)]
+pub struct Entropy<'a> {
+    source: EntropySource<'a>,
+}
+
+#[derive(Clone, Debug)]
+enum EntropySource<'a> {
+    Seeded(KeyGen),
+    Bytes(&'a [u8]),
+}
+
+impl Entropy<'static> {
+    /// Create a seeded source, producing the same values for the same seed,
+    /// every run.
+    pub const fn seeded(seed: u64) -> Self {
+        Self {
+            source: EntropySource::Seeded(KeyGen::seeded(seed)),
+        }
+    }
+}
+
+impl<'a> Entropy<'a> {
+    /// Create a source reading from the provided bytes, such as the input of
+    /// a coverage-guided fuzzer. Once the bytes run out, every value is zero,
+    /// so generation always completes.
+    pub const fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self {
+            source: EntropySource::Bytes(bytes),
+        }
+    }
+
+    /// The next random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        match &mut self.source {
+            EntropySource::Seeded(keys) => keys.next_u64(),
+            EntropySource::Bytes(bytes) => {
+                let len = bytes.len().min(8);
+                let mut buf = [0u8; 8];
+                buf[..len].copy_from_slice(&bytes[..len]);
+                *bytes = &bytes[len..];
+                u64::from_le_bytes(buf)
+            }
+        }
+    }
+
+    /// A random value within the provided range.
+    pub fn range(&mut self, range: &RangeInclusive<u64>) -> u64 {
+        let (start, end) = (*range.start(), *range.end());
+        if start >= end {
+            return start;
+        }
+        match (end - start).checked_add(1) {
+            Some(span) => start + self.next_u64() % span,
+            None => self.next_u64(),
+        }
+    }
+
+    /// A random length within the provided range.
+    pub fn len(&mut self, range: &RangeInclusive<usize>) -> usize {
+        self.range(&(*range.start() as u64..=*range.end() as u64)) as usize
+    }
+
+    /// A random boolean.
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// Random bytes.
+    pub fn bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len + 8);
+        while bytes.len() < len {
+            bytes.extend_from_slice(&self.next_u64().to_le_bytes());
+        }
+        bytes.truncate(len);
+        bytes
+    }
+
+    /// A random pubkey.
+    pub fn pubkey(&mut self) -> Pubkey {
+        Pubkey::new_from_array(self.bytes(32).try_into().unwrap())
+    }
+
+    /// A random element of the provided slice, or `None` if it's empty.
+    pub fn choose<'b, T>(&mut self, items: &'b [T]) -> Option<&'b T> {
+        if items.is_empty() {
+            return None;
+        }
+        let index = self.len(&(0..=items.len() - 1));
+        items.get(index)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Entropy<'a> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.len();
+        Ok(Self::from_bytes(u.bytes(len)?))
+    }
+
+    fn arbitrary_take_rest(u: arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::from_bytes(u.take_rest()))
+    }
+}
+
+/// Seeded sources, drawn from proptest's random seeds.
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Entropy<'static> {
+    type Parameters = ();
+    type Strategy = proptest::strategy::Map<proptest::num::u64::Any, fn(u64) -> Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        proptest::strategy::Strategy::prop_map(
+            proptest::num::u64::ANY,
+            Self::seeded as fn(u64) -> Self,
+        )
+    }
+}
+
+/// The space of contexts to generate for a program.
+///
+/// Contexts use the harness's feature set and sysvars, so the generated
+/// fixtures replay against the same environment.
+#[derive(Clone, Debug)]
+pub struct ContextStrategy {
+    /// The program to invoke.
+    pub program_id: Pubkey,
+    /// The number of accounts passed to the instruction.
+    pub num_accounts: RangeInclusive<usize>,
+    /// The length of each account's data.
+    pub account_data_len: RangeInclusive<usize>,
+    /// The lamports held by each account.
+    pub lamports: RangeInclusive<u64>,
+    /// The owners accounts are assigned to, at random. Defaults to the
+    /// program and the System program.
+    pub owners: Vec<Pubkey>,
+    /// The length of the instruction data, excluding any discriminator.
+    pub instruction_data_len: RangeInclusive<usize>,
+    /// Prefixes to start the instruction data with, one chosen at random per
+    /// context, such as the program's instruction discriminators. Defaults to
+    /// empty, for fully random data.
+    pub discriminators: Vec<Vec<u8>>,
+    /// The compute unit limit of the compute budget.
+    pub compute_unit_limit: RangeInclusive<u64>,
+}
+
+impl ContextStrategy {
+    /// Create a strategy for the provided program, with defaults suitable
+    /// for small instructions.
+    pub fn new(program_id: &Pubkey) -> Self {
+        Self {
+            program_id: *program_id,
+            num_accounts: 0..=4,
+            account_data_len: 0..=128,
+            lamports: 0..=1_000_000_000,
+            owners: vec![*program_id, trezoa_sdk_ids::system_program::id()],
+            instruction_data_len: 0..=64,
+            discriminators: Vec::new(),
+            compute_unit_limit: 1_000..=200_000,
+        }
+    }
+
+    /// Generate a context, drawing from the provided entropy.
+    pub fn generate(&self, mollusk: &Mollusk, entropy: &mut Entropy) -> Context {
+        let num_accounts = entropy.len(&self.num_accounts);
+        let mut accounts = Vec::with_capacity(num_accounts);
+        let mut instruction_accounts = Vec::with_capacity(num_accounts);
+        for _ in 0..num_accounts {
+            let key = entropy.pubkey();
+            let data_len = entropy.len(&self.account_data_len);
+            let account = Account {
+                lamports: entropy.range(&self.lamports),
+                data: entropy.bytes(data_len),
+                owner: entropy
+                    .choose(&self.owners)
+                    .copied()
+                    .unwrap_or(self.program_id),
+                ..Default::default()
+            };
+            let is_signer = entropy.bool();
+            let meta = if entropy.bool() {
+                AccountMeta::new(key, is_signer)
+            } else {
+                AccountMeta::new_readonly(key, is_signer)
+            };
+            accounts.push((key, account));
+            instruction_accounts.push(meta);
+        }
+
+        let mut instruction_data = entropy
+            .choose(&self.discriminators)
+            .cloned()
+            .unwrap_or_default();
+        let data_len = entropy.len(&self.instruction_data_len);
+        instruction_data.extend(entropy.bytes(data_len));
+
+        let mut compute_budget = mollusk.compute_budget;
+        compute_budget.compute_unit_limit = entropy.range(&self.compute_unit_limit);
+
+        Context {
+            compute_budget,
+            feature_set: mollusk.feature_set.clone(),
+            sysvars: (&mollusk.sysvars).into(),
+            program_id: self.program_id,
+            instruction_accounts,
+            instruction_data,
+            accounts,
+        }
+    }
+
+    /// Bind the strategy to the harness whose environment the contexts use,
+    /// as a `proptest` strategy.
+    #[cfg(feature = "proptest")]
+    pub fn with_harness<'a>(&'a self, mollusk: &'a Mollusk) -> HarnessContextStrategy<'a> {
+        HarnessContextStrategy {
+            strategy: self,
+            mollusk,
+        }
+    }
+}
+
+/// A `ContextStrategy` bound to a harness, as a `proptest` strategy. See
+/// `ContextStrategy::with_harness`.
+///
+/// Each context is generated from a seed drawn from the test runner, so
+/// contexts aren't shrunk, but failures reproduce from proptest's seed.
+#[cfg(feature = "proptest")]
+pub struct HarnessContextStrategy<'a> {
+    strategy: &'a ContextStrategy,
+    mollusk: &'a Mollusk,
+}
+
+#[cfg(feature = "proptest")]
+impl std::fmt::Debug for HarnessContextStrategy<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HarnessContextStrategy")
+            .field("strategy", self.strategy)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::strategy::Strategy for HarnessContextStrategy<'_> {
+    type Tree = proptest::strategy::Just<Context>;
+    type Value = Context;
+
+    fn new_tree(
+        &self,
+        runner: &mut proptest::test_runner::TestRunner,
+    ) -> proptest::strategy::NewTree<Self> {
+        use proptest::prelude::RngCore;
+
+        let mut entropy = Entropy::seeded(runner.rng().next_u64());
+        Ok(proptest::strategy::Just(
+            self.strategy.generate(self.mollusk, &mut entropy),
+        ))
+    }
+}
+
+/// Process a context, returning it as a fixture along with the effects
+/// observed.
+///
+/// The context's compute budget is used, but its feature set and sysvars
+/// are expected to match the harness's, as they do for generated contexts.
+pub fn run_context(mollusk: &Mollusk, context: Context) -> Fixture {
+    let instruction = Instruction::new_with_bytes(
+        context.program_id,
+        &context.instruction_data,
+        context.instruction_accounts.clone(),
+    );
+    let result = mollusk.process_and_validate_chain_elements(
+        &[ChainElement::new(&instruction, &[]).compute_budget(context.compute_budget)],
+        &context.accounts,
+    );
+    Fixture {
+        output: Effects::from(&result),
+        input: context,
+    }
+}
+
+/// Generate `n` contexts from the strategy, seeded with `seed`, and process
+/// each one, returning the fixtures in order.
+///
+/// The same seed yields the same fixtures, so a failing run can be
+/// reproduced from its seed alone.
+pub fn run_random_instructions(
+    mollusk: &Mollusk,
+    strategy: &ContextStrategy,
+    seed: u64,
+    n: usize,
+) -> Vec<Fixture> {
+    let mut entropy = Entropy::seeded(seed);
+    (0..n)
+        .map(|_| run_context(mollusk, strategy.generate(mollusk, &mut entropy)))
+        .collect()
+}
@@ -2,10 +2,20 @@
 //! Mollusk types. These conversions allow Mollusk to eject Firedancer fuzzing
 //! fixtures from tests, amongst other things.
 //!
+//! Both instruction-level (`sol_compat_instr_execute_v1`) and
+//! transaction-level (`sol_compat_txn_execute_v1`) fixtures can be loaded.
+//!
 //! Only available when the `fuzz-fd` feature is enabled.
 
 use {
-    crate::{compile_accounts::compile_accounts, Mollusk, DEFAULT_LOADER_KEY},
+    crate::{
+        compile_accounts::compile_accounts,
+        program::{
+            create_program_account_loader_v3, create_program_data_account_loader_v3, loader_keys,
+            ProgramCache,
+        },
+        MessageResult, Mollusk, DEFAULT_LOADER_KEY,
+    },
     trezoa_feature_set::FeatureSet,
     mollusk_svm_fuzz_fixture_firedancer::{
         context::{
@@ -14,32 +24,79 @@ use {
         },
         effects::Effects as FuzzEffects,
         metadata::Metadata as FuzzMetadata,
-        Fixture as FuzzFixture,
+        txn::{TxnContext as FuzzTxnContext, TxnEffects as FuzzTxnEffects},
+        Fixture as FuzzFixture, TxnFixture as FuzzTxnFixture,
     },
-    mollusk_svm_result::InstructionResult,
+    mollusk_svm_result::{InstructionResult, TransactionResult},
     trezoa_account::Account,
     trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_hash::Hash,
     trezoa_instruction::{error::InstructionError, AccountMeta, Instruction},
+    trezoa_message::AddressLookupTableAccount,
     trezoa_pubkey::Pubkey,
     trezoa_transaction_context::InstructionAccount,
+    trezoa_transaction_error::TransactionError,
     std::collections::HashMap,
 };
 
-static BUILTIN_PROGRAM_IDS: &[Pubkey] = &[
-    trezoa_sdk_ids::system_program::id(),
-    trezoa_sdk_ids::vote::id(),
-    trezoa_sdk_ids::stake::id(),
-    trezoa_sdk_ids::config::id(),
-    trezoa_sdk_ids::bpf_loader_deprecated::id(),
-    trezoa_sdk_ids::bpf_loader::id(),
-    trezoa_sdk_ids::bpf_loader_upgradeable::id(),
-    trezoa_sdk_ids::compute_budget::id(),
-    trezoa_sdk_ids::address_lookup_table::id(),
-    trezoa_sdk_ids::zk_token_proof_program::id(),
-    trezoa_sdk_ids::loader_v4::id(),
-    trezoa_sdk_ids::zk_elgamal_proof_program::id(),
+/// Builtins which are migrated to Core BPF programs, and the features gating
+/// their migrations.
+static CORE_BPF_MIGRATIONS: &[(Pubkey, Pubkey)] = &[
+    (
+        trezoa_sdk_ids::address_lookup_table::id(),
+        trezoa_feature_set::migrate_address_lookup_table_program_to_core_bpf::id(),
+    ),
+    (
+        trezoa_sdk_ids::config::id(),
+        trezoa_feature_set::migrate_config_program_to_core_bpf::id(),
+    ),
+    (
+        trezoa_sdk_ids::stake::id(),
+        trezoa_feature_set::migrate_stake_program_to_core_bpf::id(),
+    ),
 ];
 
+/// Determine the loader to stub the program account with, and the feature set
+/// to record, for a fixture targeting the provided program.
+///
+/// Programs in the cache keep the loader they were added with, so builtins
+/// are owned by the native loader. If the harness executed a builtin whose
+/// Core BPF migration is active, the migration feature is deactivated in the
+/// recorded feature set, so the fixture's target executes the builtin too.
+/// Migratable builtins which aren't in the cache are stubbed as Core BPF
+/// (upgradeable) programs once their migration is active, and as builtins
+/// otherwise.
+///
+/// Upgradeable programs are stubbed along with their program data account,
+/// holding the ELF from the cache if there is one.
+fn fixture_program_loader(
+    program_cache: &ProgramCache,
+    feature_set: &FeatureSet,
+    program_id: &Pubkey,
+) -> (Pubkey, FeatureSet) {
+    let mut feature_set = feature_set.clone();
+    let migration = CORE_BPF_MIGRATIONS
+        .iter()
+        .find(|(builtin_id, _)| builtin_id == program_id)
+        .map(|(_, feature_id)| feature_id);
+
+    let loader_key = match program_cache.get_program_loader_key(program_id) {
+        Some(loader_keys::NATIVE_LOADER) => {
+            if let Some(feature_id) = migration {
+                feature_set.deactivate(feature_id);
+            }
+            loader_keys::NATIVE_LOADER
+        }
+        Some(loader_key) => loader_key,
+        None => match migration {
+            Some(feature_id) if feature_set.is_active(feature_id) => loader_keys::LOADER_V3,
+            Some(_) => loader_keys::NATIVE_LOADER,
+            None => DEFAULT_LOADER_KEY,
+        },
+    };
+    (loader_key, feature_set)
+}
+
 fn instr_err_to_num(error: &InstructionError) -> i32 {
     let serialized_err = bincode::serialize(error).unwrap();
     i32::from_le_bytes((&serialized_err[0..4]).try_into().unwrap()) + 1
@@ -55,34 +112,45 @@ fn num_to_instr_err(num: i32, custom_code: u32) -> InstructionError {
     deser
 }
 
+fn num_to_txn_err(num: u32, index: u8) -> TransactionError {
+    // Variants carrying an index, such as `DuplicateInstruction`, read it from
+    // the byte following the discriminant. Unit variants ignore it.
+    let mut bytes = (num - 1).to_le_bytes().to_vec();
+    bytes.push(index);
+    bincode::deserialize(&bytes)
+        .unwrap_or_else(|_| panic!("Invalid transaction error status: {}", num))
+}
+
 fn build_fixture_context(
     accounts: &[(Pubkey, Account)],
     compute_budget: &ComputeBudget,
     feature_set: &FeatureSet,
+    program_cache: &ProgramCache,
     instruction: &Instruction,
     slot: u64,
 ) -> FuzzContext {
-    let loader_key = if BUILTIN_PROGRAM_IDS.contains(&instruction.program_id) {
-        trezoa_sdk_ids::native_loader::id()
-    } else {
-        DEFAULT_LOADER_KEY
-    };
+    let (loader_key, feature_set) =
+        fixture_program_loader(program_cache, feature_set, &instruction.program_id);
 
-    let fallbacks: HashMap<Pubkey, Account> = [(
-        instruction.program_id,
+    let program_account = if loader_key == loader_keys::LOADER_V3 {
+        create_program_account_loader_v3(&instruction.program_id)
+    } else {
         Account {
             owner: loader_key,
             executable: true,
             ..Default::default()
-        },
-    )]
-    .into_iter()
-    .collect();
+        }
+    };
+    let fallbacks: HashMap<Pubkey, Account> = [(instruction.program_id, program_account)]
+        .into_iter()
+        .collect();
 
     let (sanitized_message, transaction_accounts) = compile_accounts(
         std::slice::from_ref(instruction),
         accounts.iter(),
         &fallbacks,
+        None,
+        &Hash::default(),
     );
 
     let compiled_ix = sanitized_message.instructions().first().unwrap();
@@ -99,11 +167,29 @@ fn build_fixture_context(
         })
         .collect();
 
-    let accounts = transaction_accounts
+    let mut accounts = transaction_accounts
         .into_iter()
         .map(|(key, account)| (key, account.into(), None))
         .collect::<Vec<_>>();
 
+    if loader_key == loader_keys::LOADER_V3 {
+        let programdata_address =
+            Pubkey::find_program_address(&[instruction.program_id.as_ref()], &loader_key).0;
+        if !accounts
+            .iter()
+            .any(|(key, _, _)| *key == programdata_address)
+        {
+            let elf = program_cache
+                .get_program_elf_bytes(&instruction.program_id)
+                .unwrap_or_default();
+            accounts.push((
+                programdata_address,
+                create_program_data_account_loader_v3(&elf).into(),
+                None,
+            ));
+        }
+    }
+
     FuzzContext {
         program_id: instruction.program_id,
         accounts,
@@ -111,9 +197,7 @@ fn build_fixture_context(
         instruction_data: instruction.data.clone(),
         compute_units_available: compute_budget.compute_unit_limit,
         slot_context: FuzzSlotContext { slot },
-        epoch_context: FuzzEpochContext {
-            feature_set: feature_set.clone(),
-        },
+        epoch_context: FuzzEpochContext { feature_set },
     }
 }
 
@@ -248,11 +332,23 @@ pub(crate) fn parse_fixture_effects(
         execution_time: 0, // TODO: Omitted for now.
         compute_units_consumed: compute_unit_limit.saturating_sub(effects.compute_units_available),
         return_data,
+        inner_return_data: vec![],
         resulting_accounts,
+        instruction_trace_length: 0,
+        max_stack_height: 0,
+        accounts_resize_delta: 0,
+        failing_program_id: None,
+        precompile_error: None,
+        declared_writable: vec![],
+        actually_modified: vec![],
+        referenced_accounts: vec![],
+        profile: Default::default(),
+        execution_mode: None,
+        logs: vec![],
         #[cfg(feature = "inner-instructions")]
         inner_instructions: vec![],
-        #[cfg(feature = "inner-instructions")]
         message: None,
+        failure_context: None,
     }
 }
 
@@ -273,6 +369,7 @@ pub fn build_fixture_from_mollusk_test(
         accounts,
         &mollusk.compute_budget,
         &mollusk.feature_set,
+        &mollusk.program_cache,
         instruction,
         mollusk.slot, // FD-fuzz feature only.
     );
@@ -298,6 +395,222 @@ pub fn load_firedancer_fixture(
     (parsed, result)
 }
 
+/// The length of an address lookup table's metadata, which precedes its
+/// addresses.
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+pub struct ParsedTxnFixtureContext {
+    pub accounts: Vec<(Pubkey, Account)>,
+    pub address_lookup_tables: Vec<AddressLookupTableAccount>,
+    pub compute_budget: ComputeBudget,
+    pub fee_payer: Option<Pubkey>,
+    pub feature_set: FeatureSet,
+    pub instructions: Vec<Instruction>,
+    pub slot: u64,
+}
+
+fn parse_lookup_table_addresses(key: &Pubkey, accounts: &[(Pubkey, Account)]) -> Vec<Pubkey> {
+    let (_, table) = accounts
+        .iter()
+        .find(|(k, _)| k == key)
+        .unwrap_or_else(|| panic!("Address lookup table {} missing from fixture accounts", key));
+    table
+        .data
+        .get(LOOKUP_TABLE_META_SIZE..)
+        .unwrap_or_default()
+        .chunks_exact(32)
+        .map(|chunk| Pubkey::new_from_array(chunk.try_into().unwrap()))
+        .collect()
+}
+
+pub(crate) fn parse_txn_fixture_context(context: &FuzzTxnContext) -> ParsedTxnFixtureContext {
+    let FuzzTxnContext {
+        header,
+        account_keys,
+        accounts,
+        instructions,
+        address_table_lookups,
+        slot_context,
+        epoch_context,
+        ..
+    } = context;
+
+    // Compute budget instructions are applied when the transaction is
+    // processed.
+    let compute_budget = ComputeBudget::new_with_defaults(true, true);
+
+    let accounts = accounts
+        .iter()
+        .map(|(key, acct, _)| (*key, acct.clone()))
+        .collect::<Vec<_>>();
+
+    let address_lookup_tables = address_table_lookups
+        .iter()
+        .map(|lookup| AddressLookupTableAccount {
+            key: lookup.account_key,
+            addresses: parse_lookup_table_addresses(&lookup.account_key, &accounts),
+        })
+        .collect::<Vec<_>>();
+
+    // Loaded addresses follow the static keys: all writable lookups in table
+    // order, followed by all readonly lookups.
+    let mut loaded_writable = Vec::new();
+    let mut loaded_readonly = Vec::new();
+    for (lookup, table) in address_table_lookups.iter().zip(&address_lookup_tables) {
+        loaded_writable.extend(
+            lookup
+                .writable_indexes
+                .iter()
+                .map(|index| table.addresses[*index as usize]),
+        );
+        loaded_readonly.extend(
+            lookup
+                .readonly_indexes
+                .iter()
+                .map(|index| table.addresses[*index as usize]),
+        );
+    }
+
+    let num_static = account_keys.len();
+    let num_signed = header.num_required_signatures as usize;
+    let num_writable_signed =
+        num_signed.saturating_sub(header.num_readonly_signed_accounts as usize);
+    let num_writable_unsigned = num_static
+        .saturating_sub(num_signed)
+        .saturating_sub(header.num_readonly_unsigned_accounts as usize);
+    let num_loaded_writable = loaded_writable.len();
+
+    let is_writable = |index: usize| {
+        if index < num_signed {
+            index < num_writable_signed
+        } else if index < num_static {
+            index - num_signed < num_writable_unsigned
+        } else {
+            index - num_static < num_loaded_writable
+        }
+    };
+
+    let keys = account_keys
+        .iter()
+        .copied()
+        .chain(loaded_writable)
+        .chain(loaded_readonly)
+        .collect::<Vec<_>>();
+
+    let instructions = instructions
+        .iter()
+        .map(|compiled_ix| {
+            let metas = compiled_ix
+                .accounts
+                .iter()
+                .map(|&index| {
+                    let index = index as usize;
+                    AccountMeta {
+                        pubkey: *keys.get(index).expect("Index out of bounds"),
+                        is_signer: index < num_signed,
+                        is_writable: is_writable(index),
+                    }
+                })
+                .collect::<Vec<_>>();
+            let program_id = *keys
+                .get(compiled_ix.program_id_index as usize)
+                .expect("Index out of bounds");
+            Instruction::new_with_bytes(program_id, &compiled_ix.data, metas)
+        })
+        .collect();
+
+    ParsedTxnFixtureContext {
+        accounts,
+        address_lookup_tables,
+        compute_budget,
+        fee_payer: account_keys.first().copied(),
+        feature_set: epoch_context.feature_set.clone(),
+        instructions,
+        slot: slot_context.slot,
+    }
+}
+
+pub(crate) fn parse_txn_fixture_effects(
+    context: &FuzzTxnContext,
+    accounts: &[(Pubkey, Account)],
+    effects: &FuzzTxnEffects,
+) -> TransactionResult {
+    // Transactions which fail before execution, such as sanitization or fee
+    // payer failures, only record their transaction error status.
+    let raw_result = if effects.is_ok {
+        Ok(())
+    } else if effects.instruction_error != 0 {
+        Err(TransactionError::InstructionError(
+            effects.instruction_error_index as u8,
+            num_to_instr_err(effects.instruction_error as i32, effects.custom_error),
+        ))
+    } else {
+        Err(num_to_txn_err(
+            effects.status,
+            effects.instruction_error_index as u8,
+        ))
+    };
+
+    let program_result = MessageResult::extract_txn_program_result(&raw_result);
+    let return_data = effects.return_data.clone();
+
+    // Fees aren't charged when processing the fixture, so they're refunded
+    // to the fee payer's expected balance.
+    let fee_payer = context.account_keys.first();
+    let fees = effects
+        .transaction_fee
+        .saturating_add(effects.prioritization_fee);
+
+    let resulting_accounts = accounts
+        .iter()
+        .map(|(key, acct)| {
+            let resulting_account =
+                match effects.resulting_accounts.iter().find(|(k, _, _)| k == key) {
+                    Some((_, acct, _)) if Some(key) == fee_payer => Account {
+                        lamports: acct.lamports.saturating_add(fees),
+                        ..acct.clone()
+                    },
+                    Some((_, acct, _)) => acct.clone(),
+                    None => acct.clone(),
+                };
+            (*key, resulting_account)
+        })
+        .collect();
+
+    TransactionResult {
+        compute_units_consumed: effects.executed_units,
+        execution_time: 0, // TODO: Omitted for now.
+        program_result,
+        raw_result,
+        return_data,
+        inner_return_data: vec![],
+        resulting_accounts,
+        instruction_trace_length: 0,
+        max_stack_height: 0,
+        accounts_resize_delta: 0,
+        failing_program_id: None,
+        precompile_error: None,
+        declared_writable: vec![],
+        actually_modified: vec![],
+        referenced_accounts: vec![],
+        profile: Default::default(),
+        execution_mode: None,
+        logs: vec![],
+        per_instruction: vec![],
+        #[cfg(feature = "inner-instructions")]
+        inner_instructions: vec![],
+        message: None,
+    }
+}
+
+pub fn load_firedancer_txn_fixture(
+    fixture: &FuzzTxnFixture,
+) -> (ParsedTxnFixtureContext, TransactionResult) {
+    let parsed = parse_txn_fixture_context(&fixture.input);
+    let result = parse_txn_fixture_effects(&fixture.input, &parsed.accounts, &fixture.output);
+    (parsed, result)
+}
+
 #[test]
 fn test_num_to_instr_err() {
     [
@@ -331,3 +644,26 @@ fn test_num_to_instr_err() {
         assert_eq!(ie, err);
     })
 }
+
+#[test]
+fn test_num_to_txn_err() {
+    [
+        TransactionError::AccountNotFound,
+        TransactionError::ProgramAccountNotFound,
+        TransactionError::InsufficientFundsForFee,
+        TransactionError::InvalidAccountForFee,
+        TransactionError::BlockhashNotFound,
+        TransactionError::SanitizeFailure,
+        TransactionError::TooManyAccountLocks,
+        TransactionError::DuplicateInstruction(2),
+        TransactionError::InsufficientFundsForRent { account_index: 1 },
+        TransactionError::MaxLoadedAccountsDataSizeExceeded,
+    ]
+    .into_iter()
+    .for_each(|te| {
+        let serialized_err = bincode::serialize(&te).unwrap();
+        let num = u32::from_le_bytes((&serialized_err[0..4]).try_into().unwrap()) + 1;
+        let index = serialized_err.get(4).copied().unwrap_or_default();
+        assert_eq!(te, num_to_txn_err(num, index));
+    })
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "fuzz")]
+pub mod driver;
 #[cfg(feature = "fuzz-fd")]
 pub mod firedancer;
 #[cfg(feature = "fuzz")]
@@ -15,37 +17,57 @@ pub fn generate_fixtures_from_mollusk_test(
     result: &InstructionResult,
 ) {
     #[cfg(feature = "fuzz")]
-    {
-        if std::env::var("EJECT_FUZZ_FIXTURES").is_ok()
-            || std::env::var("EJECT_FUZZ_FIXTURES_JSON").is_ok()
-        {
-            let fixture =
-                mollusk::build_fixture_from_mollusk_test(mollusk, instruction, accounts, result);
-            let handler = FsHandler::new(fixture);
-            if let Ok(blob_dir) = std::env::var("EJECT_FUZZ_FIXTURES") {
-                handler.dump_to_blob_file(&blob_dir);
-            }
+    let handler = (std::env::var("EJECT_FUZZ_FIXTURES").is_ok()
+        || std::env::var("EJECT_FUZZ_FIXTURES_JSON").is_ok())
+    .then(|| {
+        FsHandler::new(mollusk::build_fixture_from_mollusk_test(
+            mollusk,
+            instruction,
+            accounts,
+            result,
+        ))
+    });
+    #[cfg(feature = "fuzz-fd")]
+    let fd_handler = (std::env::var("EJECT_FUZZ_FIXTURES_FD").is_ok()
+        || std::env::var("EJECT_FUZZ_FIXTURES_JSON_FD").is_ok())
+    .then(|| {
+        FsHandler::new(firedancer::build_fixture_from_mollusk_test(
+            mollusk,
+            instruction,
+            accounts,
+            result,
+        ))
+    });
+
+    // When both layouts are ejected, name them after the same hash, so the
+    // Mollusk and Firedancer fixtures for an invocation can be paired.
+    #[cfg(all(feature = "fuzz", feature = "fuzz-fd"))]
+    let (handler, fd_handler) = match (handler, fd_handler) {
+        (Some(handler), Some(fd_handler)) => {
+            let (handler, fd_handler) = handler.paired_with(fd_handler);
+            (Some(handler), Some(fd_handler))
+        }
+        handlers => handlers,
+    };
+
+    #[cfg(feature = "fuzz")]
+    if let Some(handler) = handler {
+        if let Ok(blob_dir) = std::env::var("EJECT_FUZZ_FIXTURES") {
+            handler.dump_to_blob_file(&blob_dir);
+        }
 
-            if let Ok(json_dir) = std::env::var("EJECT_FUZZ_FIXTURES_JSON") {
-                handler.dump_to_json_file(&json_dir);
-            }
+        if let Ok(json_dir) = std::env::var("EJECT_FUZZ_FIXTURES_JSON") {
+            handler.dump_to_json_file(&json_dir);
         }
     }
     #[cfg(feature = "fuzz-fd")]
-    {
-        if std::env::var("EJECT_FUZZ_FIXTURES_FD").is_ok()
-            || std::env::var("EJECT_FUZZ_FIXTURES_JSON_FD").is_ok()
-        {
-            let fixture =
-                firedancer::build_fixture_from_mollusk_test(mollusk, instruction, accounts, result);
-            let handler = FsHandler::new(fixture);
-            if let Ok(blob_dir) = std::env::var("EJECT_FUZZ_FIXTURES_FD") {
-                handler.dump_to_blob_file(&blob_dir);
-            }
+    if let Some(handler) = fd_handler {
+        if let Ok(blob_dir) = std::env::var("EJECT_FUZZ_FIXTURES_FD") {
+            handler.dump_to_blob_file(&blob_dir);
+        }
 
-            if let Ok(json_dir) = std::env::var("EJECT_FUZZ_FIXTURES_JSON_FD") {
-                handler.dump_to_json_file(&json_dir);
-            }
+        if let Ok(json_dir) = std::env::var("EJECT_FUZZ_FIXTURES_JSON_FD") {
+            handler.dump_to_json_file(&json_dir);
         }
     }
 }
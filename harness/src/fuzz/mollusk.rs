@@ -129,3 +129,61 @@ pub fn load_fixture(
         InstructionResult::from(&fixture.output),
     )
 }
+
+/// The aggregate outcome of processing a batch of fixtures with
+/// `Mollusk::process_fixture_batch`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FixtureBatchReport {
+    /// The number of fixtures processed.
+    pub total: usize,
+    /// The number of fixtures whose effects matched the result.
+    pub passed: usize,
+    /// The indices of the fixtures whose effects didn't match the result, in
+    /// the order they were provided.
+    pub failed: Vec<usize>,
+    /// The number of distinct contexts - compute budget, feature set and
+    /// sysvars - the harness was configured with.
+    pub contexts: usize,
+}
+
+impl FixtureBatchReport {
+    /// Whether every fixture passed.
+    pub fn all_passed(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl std::fmt::Display for FixtureBatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} fixtures passed across {} contexts",
+            self.passed, self.total, self.contexts
+        )?;
+        if !self.failed.is_empty() {
+            write!(f, ", failed: {:?}", self.failed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Group fixtures sharing a context, in order of first appearance, keeping
+/// the index of each fixture.
+pub(crate) fn group_fixtures_by_context(
+    fixtures: impl IntoIterator<Item = FuzzFixture>,
+) -> Vec<Vec<(usize, FuzzFixture)>> {
+    let mut groups: Vec<Vec<(usize, FuzzFixture)>> = Vec::new();
+    for (index, fixture) in fixtures.into_iter().enumerate() {
+        let group = groups.iter_mut().find(|group| {
+            let input = &group[0].1.input;
+            input.compute_budget == fixture.input.compute_budget
+                && input.feature_set == fixture.input.feature_set
+                && input.sysvars == fixture.input.sysvars
+        });
+        match group {
+            Some(group) => group.push((index, fixture)),
+            None => groups.push(vec![(index, fixture)]),
+        }
+    }
+    groups
+}
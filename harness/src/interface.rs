@@ -0,0 +1,379 @@
+//! Dry-run validation of instructions against program interfaces.
+//!
+//! A program which receives the wrong accounts usually fails with an opaque
+//! error, such as `NotEnoughAccountKeys` or a custom error code. Registering
+//! a `ProgramInterface` for a program has Mollusk check every instruction
+//! sent to it before execution, and panic with a descriptive error instead:
+//!
+//! ```text
+//! initialize: expected 5 accounts, got 4: missing rent
+//! ```
+//!
+//! An interface lists the program's instructions, each with its data
+//! discriminator and the accounts it expects, in order.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{
+//!     interface::{AccountInterface, InstructionInterface, ProgramInterface},
+//!     Mollusk,
+//! };
+//!
+//! let mut mollusk = Mollusk::new(&program_id, "my_program");
+//! mollusk.interfaces.insert(
+//!     program_id,
+//!     ProgramInterface::new(vec![InstructionInterface::new(
+//!         "initialize",
+//!         &[0],
+//!         vec![
+//!             AccountInterface::new("payer").signer().writable(),
+//!             AccountInterface::new("state").writable(),
+//!             AccountInterface::new("rent").address(trezoa_sdk_ids::sysvar::rent::id()),
+//!         ],
+//!     )]),
+//! );
+//! ```
+//!
+//! With the `idl` feature, an interface can be loaded from an Anchor IDL
+//! with `ProgramInterface::from_anchor_idl`.
+//!
+//! Instructions may provide accounts beyond the ones in the interface, which
+//! are left unchecked. Optional accounts may be replaced by the program ID,
+//! as Anchor does when one is omitted.
+
+use {
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    std::fmt,
+};
+
+/// An account expected by an instruction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountInterface {
+    pub name: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    /// Whether the account may be omitted, by passing the program ID in its
+    /// place.
+    pub is_optional: bool,
+    /// The address the account must have, if any.
+    pub address: Option<Pubkey>,
+}
+
+impl AccountInterface {
+    /// A read-only, non-signer account.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Require the account to be a signer.
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    /// Require the account to be writable.
+    pub fn writable(mut self) -> Self {
+        self.is_writable = true;
+        self
+    }
+
+    /// Allow the account to be omitted.
+    pub fn optional(mut self) -> Self {
+        self.is_optional = true;
+        self
+    }
+
+    /// Require the account to have the provided address.
+    pub fn address(mut self, address: Pubkey) -> Self {
+        self.address = Some(address);
+        self
+    }
+}
+
+/// An instruction of a program.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstructionInterface {
+    pub name: String,
+    /// The prefix of the instruction data identifying the instruction.
+    pub discriminator: Vec<u8>,
+    pub accounts: Vec<AccountInterface>,
+}
+
+impl InstructionInterface {
+    pub fn new(name: &str, discriminator: &[u8], accounts: Vec<AccountInterface>) -> Self {
+        Self {
+            name: name.to_string(),
+            discriminator: discriminator.to_vec(),
+            accounts,
+        }
+    }
+}
+
+/// The instructions of a program.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgramInterface {
+    pub instructions: Vec<InstructionInterface>,
+}
+
+/// An instruction which doesn't match its program's interface.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InterfaceError {
+    /// No instruction in the interface matches the instruction data.
+    UnknownDiscriminator { data: Vec<u8> },
+    /// The instruction has fewer accounts than the interface expects.
+    MissingAccounts {
+        instruction: String,
+        expected: usize,
+        got: usize,
+        missing: Vec<String>,
+    },
+    /// An account which must sign doesn't.
+    NotSigner {
+        instruction: String,
+        index: usize,
+        account: String,
+    },
+    /// An account which must be writable isn't.
+    NotWritable {
+        instruction: String,
+        index: usize,
+        account: String,
+    },
+    /// An account with a fixed address has another address.
+    WrongAddress {
+        instruction: String,
+        index: usize,
+        account: String,
+        expected: Pubkey,
+        got: Pubkey,
+    },
+}
+
+impl fmt::Display for InterfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownDiscriminator { data } => {
+                write!(f, "no instruction matches the instruction data: {:?}", data)
+            }
+            Self::MissingAccounts {
+                instruction,
+                expected,
+                got,
+                missing,
+            } => write!(
+                f,
+                "{}: expected {} accounts, got {}: missing {}",
+                instruction,
+                expected,
+                got,
+                missing.join(", "),
+            ),
+            Self::NotSigner {
+                instruction,
+                index,
+                account,
+            } => write!(
+                f,
+                "{}: account {} ({}) must be a signer",
+                instruction, index, account,
+            ),
+            Self::NotWritable {
+                instruction,
+                index,
+                account,
+            } => write!(
+                f,
+                "{}: account {} ({}) must be writable",
+                instruction, index, account,
+            ),
+            Self::WrongAddress {
+                instruction,
+                index,
+                account,
+                expected,
+                got,
+            } => write!(
+                f,
+                "{}: account {} ({}) must be {}, got {}",
+                instruction, index, account, expected, got,
+            ),
+        }
+    }
+}
+
+impl ProgramInterface {
+    pub fn new(instructions: Vec<InstructionInterface>) -> Self {
+        Self { instructions }
+    }
+
+    /// Find the instruction matching the instruction data. When several
+    /// match, the first one listed wins.
+    pub fn find_instruction(&self, data: &[u8]) -> Option<&InstructionInterface> {
+        self.instructions
+            .iter()
+            .find(|instruction| data.starts_with(&instruction.discriminator))
+    }
+
+    /// Check an instruction's data discriminator and accounts against the
+    /// interface.
+    pub fn validate(&self, instruction: &Instruction) -> Result<(), InterfaceError> {
+        let interface = self.find_instruction(&instruction.data).ok_or_else(|| {
+            InterfaceError::UnknownDiscriminator {
+                data: instruction.data.clone(),
+            }
+        })?;
+
+        if instruction.accounts.len() < interface.accounts.len() {
+            return Err(InterfaceError::MissingAccounts {
+                instruction: interface.name.clone(),
+                expected: interface.accounts.len(),
+                got: instruction.accounts.len(),
+                missing: interface.accounts[instruction.accounts.len()..]
+                    .iter()
+                    .map(|account| account.name.clone())
+                    .collect(),
+            });
+        }
+
+        for (index, (account, meta)) in interface
+            .accounts
+            .iter()
+            .zip(&instruction.accounts)
+            .enumerate()
+        {
+            validate_account(interface, index, account, meta, &instruction.program_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_account(
+    interface: &InstructionInterface,
+    index: usize,
+    account: &AccountInterface,
+    meta: &AccountMeta,
+    program_id: &Pubkey,
+) -> Result<(), InterfaceError> {
+    if account.is_optional && meta.pubkey == *program_id {
+        return Ok(());
+    }
+    if let Some(address) = account.address {
+        if meta.pubkey != address {
+            return Err(InterfaceError::WrongAddress {
+                instruction: interface.name.clone(),
+                index,
+                account: account.name.clone(),
+                expected: address,
+                got: meta.pubkey,
+            });
+        }
+    }
+    if account.is_signer && !meta.is_signer {
+        return Err(InterfaceError::NotSigner {
+            instruction: interface.name.clone(),
+            index,
+            account: account.name.clone(),
+        });
+    }
+    if account.is_writable && !meta.is_writable {
+        return Err(InterfaceError::NotWritable {
+            instruction: interface.name.clone(),
+            index,
+            account: account.name.clone(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "idl")]
+mod anchor {
+    use {
+        super::{AccountInterface, InstructionInterface, ProgramInterface},
+        serde::Deserialize,
+    };
+
+    #[derive(Deserialize)]
+    pub struct Idl {
+        instructions: Vec<IdlInstruction>,
+    }
+
+    #[derive(Deserialize)]
+    struct IdlInstruction {
+        name: String,
+        discriminator: Vec<u8>,
+        accounts: Vec<IdlAccountItem>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum IdlAccountItem {
+        // Composite accounts, nesting their own accounts.
+        Composite {
+            accounts: Vec<IdlAccountItem>,
+        },
+        Single {
+            name: String,
+            #[serde(default)]
+            writable: bool,
+            #[serde(default)]
+            signer: bool,
+            #[serde(default)]
+            optional: bool,
+            address: Option<String>,
+        },
+    }
+
+    fn flatten(items: Vec<IdlAccountItem>, accounts: &mut Vec<AccountInterface>) {
+        for item in items {
+            match item {
+                IdlAccountItem::Composite { accounts: items } => flatten(items, accounts),
+                IdlAccountItem::Single {
+                    name,
+                    writable,
+                    signer,
+                    optional,
+                    address,
+                } => accounts.push(AccountInterface {
+                    name,
+                    is_signer: signer,
+                    is_writable: writable,
+                    is_optional: optional,
+                    address: address.and_then(|address| address.parse().ok()),
+                }),
+            }
+        }
+    }
+
+    impl From<Idl> for ProgramInterface {
+        fn from(idl: Idl) -> Self {
+            let instructions = idl
+                .instructions
+                .into_iter()
+                .map(|instruction| {
+                    let mut accounts = Vec::new();
+                    flatten(instruction.accounts, &mut accounts);
+                    InstructionInterface {
+                        name: instruction.name,
+                        discriminator: instruction.discriminator,
+                        accounts,
+                    }
+                })
+                .collect();
+            Self { instructions }
+        }
+    }
+}
+
+#[cfg(feature = "idl")]
+impl ProgramInterface {
+    /// Load an interface from an Anchor IDL, in the JSON format emitted by
+    /// Anchor 0.30 and later. Composite accounts are flattened into their
+    /// accounts, and addresses which can't be parsed are left unchecked.
+    pub fn from_anchor_idl(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str::<anchor::Idl>(json).map(Into::into)
+    }
+}
@@ -0,0 +1,164 @@
+//! Interop with other versions of the Trezoa SDK.
+//!
+//! Mollusk's API takes the `Pubkey`, `Account`, and `Instruction` types of
+//! the SDK version it's built against. A crate built against another minor
+//! version has distinct types with the same layout, which Rust won't accept
+//! in their place.
+//!
+//! `mollusk_interop!` bridges the two. Invoked once, in the crate with the
+//! other SDK version, it defines a `ToMollusk` trait for converting that
+//! version's types into Mollusk's, and a `FromMollusk` trait for converting
+//! results back. The conversions are field-by-field, so they work across any
+//! versions which agree on the types' fields.
+//!
+//! ```rust,ignore
+//! mollusk_svm::mollusk_interop! {
+//!     Pubkey = solana_pubkey::Pubkey,
+//!     Account = solana_account::Account,
+//!     AccountMeta = solana_instruction::AccountMeta,
+//!     Instruction = solana_instruction::Instruction,
+//! }
+//!
+//! let result = mollusk.process_instruction(&instruction.to_mollusk(), &accounts.to_mollusk());
+//! let resulting_accounts: Vec<(solana_pubkey::Pubkey, solana_account::Account)> =
+//!     FromMollusk::from_mollusk(&result.resulting_accounts);
+//! ```
+
+#[doc(hidden)]
+pub mod __private {
+    pub use {
+        trezoa_account::Account,
+        trezoa_instruction::{AccountMeta, Instruction},
+        trezoa_pubkey::Pubkey,
+    };
+}
+
+/// Define `ToMollusk` and `FromMollusk` conversions between another SDK
+/// version's types and Mollusk's. See the `interop` module.
+#[macro_export]
+macro_rules! mollusk_interop {
+    (
+        Pubkey = $pubkey:ty,
+        Account = $account:ty,
+        AccountMeta = $account_meta:ty,
+        Instruction = $instruction:ty $(,)?
+    ) => {
+        /// Convert a type into its Mollusk SDK version equivalent.
+        pub trait ToMollusk<T> {
+            fn to_mollusk(&self) -> T;
+        }
+
+        /// Convert a type from its Mollusk SDK version equivalent.
+        pub trait FromMollusk<T> {
+            fn from_mollusk(value: &T) -> Self;
+        }
+
+        impl ToMollusk<$crate::interop::__private::Pubkey> for $pubkey {
+            fn to_mollusk(&self) -> $crate::interop::__private::Pubkey {
+                $crate::interop::__private::Pubkey::new_from_array(self.to_bytes())
+            }
+        }
+
+        impl FromMollusk<$crate::interop::__private::Pubkey> for $pubkey {
+            fn from_mollusk(value: &$crate::interop::__private::Pubkey) -> Self {
+                <$pubkey>::new_from_array(value.to_bytes())
+            }
+        }
+
+        impl ToMollusk<$crate::interop::__private::Account> for $account {
+            fn to_mollusk(&self) -> $crate::interop::__private::Account {
+                $crate::interop::__private::Account {
+                    lamports: self.lamports,
+                    data: self.data.clone(),
+                    owner: self.owner.to_mollusk(),
+                    executable: self.executable,
+                    rent_epoch: self.rent_epoch,
+                }
+            }
+        }
+
+        impl FromMollusk<$crate::interop::__private::Account> for $account {
+            fn from_mollusk(value: &$crate::interop::__private::Account) -> Self {
+                Self {
+                    lamports: value.lamports,
+                    data: value.data.clone(),
+                    owner: FromMollusk::from_mollusk(&value.owner),
+                    executable: value.executable,
+                    rent_epoch: value.rent_epoch,
+                }
+            }
+        }
+
+        impl ToMollusk<$crate::interop::__private::AccountMeta> for $account_meta {
+            fn to_mollusk(&self) -> $crate::interop::__private::AccountMeta {
+                $crate::interop::__private::AccountMeta {
+                    pubkey: self.pubkey.to_mollusk(),
+                    is_signer: self.is_signer,
+                    is_writable: self.is_writable,
+                }
+            }
+        }
+
+        impl ToMollusk<$crate::interop::__private::Instruction> for $instruction {
+            fn to_mollusk(&self) -> $crate::interop::__private::Instruction {
+                $crate::interop::__private::Instruction {
+                    program_id: self.program_id.to_mollusk(),
+                    accounts: self.accounts.iter().map(ToMollusk::to_mollusk).collect(),
+                    data: self.data.clone(),
+                }
+            }
+        }
+
+        impl ToMollusk<Vec<$crate::interop::__private::Instruction>> for [$instruction] {
+            fn to_mollusk(&self) -> Vec<$crate::interop::__private::Instruction> {
+                self.iter().map(ToMollusk::to_mollusk).collect()
+            }
+        }
+
+        impl
+            ToMollusk<
+                Vec<(
+                    $crate::interop::__private::Pubkey,
+                    $crate::interop::__private::Account,
+                )>,
+            > for [($pubkey, $account)]
+        {
+            fn to_mollusk(
+                &self,
+            ) -> Vec<(
+                $crate::interop::__private::Pubkey,
+                $crate::interop::__private::Account,
+            )> {
+                self.iter()
+                    .map(|(pubkey, account)| (pubkey.to_mollusk(), account.to_mollusk()))
+                    .collect()
+            }
+        }
+
+        impl
+            FromMollusk<
+                Vec<(
+                    $crate::interop::__private::Pubkey,
+                    $crate::interop::__private::Account,
+                )>,
+            > for Vec<($pubkey, $account)>
+        {
+            fn from_mollusk(
+                value: &Vec<(
+                    $crate::interop::__private::Pubkey,
+                    $crate::interop::__private::Account,
+                )>,
+            ) -> Self {
+                value
+                    .iter()
+                    .map(|(pubkey, account)| {
+                        (
+                            FromMollusk::from_mollusk(pubkey),
+                            FromMollusk::from_mollusk(account),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    };
+}
@@ -21,7 +21,7 @@
 //! The test environment can be further configured by adjusting the compute
 //! budget, feature set, or sysvars. These configurations are stored directly
 //! on the test harness (the `Mollusk` struct), but can be manipulated through
-//! a handful of helpers.
+//! a handful of helpers, or configured up front with `Mollusk::builder`.
 //!
 //! Four main API methods are offered:
 //!
@@ -131,6 +131,16 @@
 //! default builtin programs. For more builtin programs, you can add them
 //! yourself or use the `all-builtins` feature.
 //!
+//! The `all-builtins` feature includes the stake and vote programs, for
+//! programs which CPI into them. The `stake` and `epoch_stake` modules provide
+//! helpers for creating realistic stake and vote accounts.
+//!
+//! `Mollusk::set_epoch_stake` configures the epoch stake from an
+//! `EpochStakeBuilder`, generating the matching vote and stake accounts and
+//! `StakeHistory` entry. With `Mollusk::sync_epoch_stake` set, the stake of
+//! any vote account delegated to by a provided stake account is derived from
+//! the provided delegations, so the epoch stake syscalls agree with them.
+//!
 //! ## Instruction Chains
 //!
 //! Both `process_instruction_chain` and
@@ -276,6 +286,9 @@
 //! Developers should recognize that instruction chains are primarily used for
 //! testing program execution.
 //!
+//! To process individual instructions in a chain with a different compute
+//! budget, use `process_and_validate_chain_elements`. See the `chain` module.
+//!
 //! ## Stateful Testing with MolluskContext
 //!
 //! For complex testing scenarios that involve multiple instructions or require
@@ -334,14 +347,33 @@
 //! * `process_and_validate_instruction_chain`: Process and validate an
 //!   instruction chain
 //!
-//! All methods return `ContextResult` instead of `InstructionResult`, which
-//! omits the `resulting_accounts` field since accounts are managed by the
-//! context's account store.
+//! All methods return `InstructionResult`, just like the base `Mollusk`
+//! methods. The `resulting_accounts` field reflects the final state after
+//! execution, and these accounts are automatically stored in the context's
+//! account store, so they can be asserted on without borrowing the store.
 //!
 //! Note that `HashMap<Pubkey, Account>` implements `AccountStore` directly,
 //! so you can use it as a simple in-memory account store without needing
 //! to implement your own.
 //!
+//! For state that outlives a single test binary, `FileAccountStore` persists
+//! its accounts to a file with `save`, and reloads them with `open`. It's
+//! gated behind the `fs` feature, which is enabled by default.
+//!
+//! For huge state sets, such as in fuzzing campaigns, `LruAccountStore`
+//! bounds the bytes held by its accounts, evicting the least recently used
+//! ones and handing them to an optional eviction callback.
+//!
+//! To test against live cluster state, enable the `rpc` feature and use
+//! `account_store::rpc::RpcAccountStore`, which fetches accounts such as
+//! mints and oracles from a JSON-RPC endpoint on first use, and caches them.
+//!
+//! Accounts missing from the store are created with `default_account`. To
+//! create them based on how the instruction uses them - for example, funding
+//! a missing fee payer - override `default_account_for`, which receives a
+//! `MissingAccountHint` describing whether the account is a signer, writable,
+//! or a program ID.
+//!
 //! ## Fixtures
 //!
 //! Mollusk also supports working with multiple kinds of fixtures, which can
@@ -372,9 +404,15 @@
 //! EJECT_FUZZ_FIXTURES="./fuzz-fixtures" cargo test-sbf ...
 //! ```
 //!
+//! When both the `fuzz` and `fuzz-fd` features are enabled, the
+//! `_FD`-suffixed variables eject Firedancer fixtures alongside Mollusk's own.
+//! If both layouts are ejected, the two fixtures for an invocation are named
+//! after a hash of both, so they can be paired across corpora.
+//!
 //! Note that Mollusk currently supports two types of fixtures: Mollusk's own
 //! fixture layout and the fixture layout used by the Firedancer team. Both of
-//! these layouts stem from Protobuf definitions.
+//! these layouts stem from Protobuf definitions. Firedancer's transaction-level
+//! fixtures can also be processed, using `process_firedancer_txn_fixture`.
 //!
 //! These layouts live in separate crates, but a snippet of the Mollusk input
 //! data for a fixture can be found below:
@@ -439,52 +477,95 @@
 //! Fixtures can be loaded from files or decoded from raw blobs. These
 //! capabilities are provided by the respective fixture crates.
 
+pub mod account_builder;
 pub mod account_store;
+pub mod builder;
+pub mod chain;
 mod compile_accounts;
+mod compute_budget_instructions;
+#[cfg(feature = "invocation-inspect-callback")]
+pub mod debug;
 pub mod epoch_stake;
+pub mod fees;
+#[cfg(feature = "fs")]
 pub mod file;
 #[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
 pub mod fuzz;
 pub mod instructions_sysvar;
+pub mod interface;
+pub mod interop;
+pub mod nonce;
+#[cfg(feature = "precompile-fixtures")]
+pub mod precompile;
+pub mod prelude;
 pub mod program;
 #[cfg(feature = "register-tracing")]
 pub mod register_tracing;
+pub mod rent_collection;
+pub mod runtime_environment;
+pub mod scenario;
+#[cfg(feature = "fs")]
+pub mod snapshot;
+pub mod stake;
+pub mod stub;
 pub mod sysvar;
+pub mod testgen;
+pub mod time_travel;
+pub mod tx_limits;
 
 #[cfg(feature = "register-tracing")]
 use crate::register_tracing::DefaultRegisterTracingCallback;
 // Re-export result module from mollusk-svm-result crate
 pub use mollusk_svm_result as result;
-#[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
-use mollusk_svm_result::Compare;
+#[cfg(feature = "all-builtins")]
+use trezoa_loader_v4_interface::{
+    instruction::{deploy, retract, set_program_length, write},
+    state::{LoaderV4State, LoaderV4Status},
+    DEPLOYMENT_COOLDOWN_IN_SLOTS,
+};
 #[cfg(feature = "precompiles")]
 use trezoa_precompile_error::PrecompileError;
 #[cfg(feature = "invocation-inspect-callback")]
 use trezoa_transaction_context::InstructionAccount;
 use {
     crate::{
-        account_store::AccountStore, epoch_stake::EpochStake, program::ProgramCache,
+        account_builder::AccountBuilder,
+        account_store::{AccountStore, IterationUnsupported, MissingAccountHint},
+        builder::MolluskBuilder,
+        chain::ChainElement,
+        compute_budget_instructions::apply_compute_budget_instructions,
+        epoch_stake::{EpochStake, EpochStakeAccounts, EpochStakeBuilder},
+        fees::FeeModel,
+        interface::ProgramInterface,
+        program::ProgramCache,
+        runtime_environment::RuntimeEnvironmentCache,
+        stub::StubHandler,
         sysvar::Sysvars,
+        time_travel::TimeTravel,
+        tx_limits::TransactionLimits,
     },
     trezoa_feature_set::FeatureSet,
-    trezoa_syscalls::{
-        create_program_runtime_environment_v1, create_program_runtime_environment_v2,
-    },
     mollusk_svm_error::error::{MolluskError, MolluskPanic},
     mollusk_svm_result::{
-        types::{TransactionProgramResult, TransactionResult},
-        Check, CheckContext, Config, InstructionResult,
+        diff::{format_diffs, AccountDiff},
+        profile::{ExecutionProfile, InstructionProfile},
+        return_data::InnerReturnData,
+        types::{
+            ExecutionMode, FailureContext, InstructionExecutionSummary, ProgramResult,
+            TransactionProgramResult, TransactionResult,
+        },
+        Check, CheckContext, Compare, Config, InstructionResult,
     },
     trezoa_account::{Account, AccountSharedData, ReadableAccount},
+    trezoa_account_info::AccountInfo,
     trezoa_compute_budget::compute_budget::ComputeBudget,
     trezoa_hash::Hash,
     trezoa_instruction::{AccountMeta, Instruction},
     trezoa_instruction_error::InstructionError,
-    trezoa_message::SanitizedMessage,
+    trezoa_message::{AddressLookupTableAccount, SanitizedMessage},
     trezoa_program_error::ProgramError,
     trezoa_program_runtime::{
         invoke_context::{EnvironmentConfig, InvokeContext},
-        loaded_programs::ProgramRuntimeEnvironments,
         sysvar_cache::SysvarCache,
     },
     trezoa_pubkey::Pubkey,
@@ -492,16 +573,19 @@ use {
     trezoa_svm_log_collector::LogCollector,
     trezoa_svm_timings::ExecuteTimings,
     trezoa_svm_transaction::instruction::SVMInstruction,
+    trezoa_system_interface::MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION,
     trezoa_transaction_context::{IndexOfAccount, TransactionContext},
     trezoa_transaction_error::TransactionError,
     std::{
-        cell::RefCell,
+        borrow::Cow,
+        cell::{Cell, RefCell},
         collections::{HashMap, HashSet},
         iter::once,
         rc::Rc,
-        sync::Arc,
     },
 };
+#[cfg(feature = "fs")]
+use std::path::Path;
 #[cfg(feature = "inner-instructions")]
 use {
     trezoa_message::compiled_instruction::CompiledInstruction,
@@ -509,6 +593,32 @@ use {
 };
 
 pub(crate) const DEFAULT_LOADER_KEY: Pubkey = trezoa_sdk_ids::bpf_loader_upgradeable::id();
+pub(crate) const DEFAULT_LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// A transform applied to the compiled transaction accounts before
+/// execution.
+pub type PreExecutionTransform = dyn Fn(&mut Vec<(Pubkey, AccountSharedData)>);
+
+/// How to handle a program targeted by an instruction which is neither
+/// provided in the accounts nor loaded in the program cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingProgramBehavior {
+    /// Panic with `MolluskError::ProgramNotCached`.
+    #[default]
+    Panic,
+    /// Stub the program account as an executable account owned by the native
+    /// loader, so the runtime fails the invocation with
+    /// `UnsupportedProgramId`.
+    Stub,
+    /// Fail the instruction with `UnsupportedProgramId` without executing
+    /// it, leaving its accounts unchanged. The elements of a chain before it
+    /// are still executed.
+    ///
+    /// Like the runtime, a transaction fails to load with
+    /// `ProgramAccountNotFound` instead, so nothing is executed, but the fee
+    /// is still charged and a durable nonce still advanced.
+    Error,
+}
 
 /// The Mollusk API, providing a simple interface for testing Trezoa programs.
 ///
@@ -523,6 +633,90 @@ pub struct Mollusk {
     pub program_cache: ProgramCache,
     pub sysvars: Sysvars,
 
+    /// Whether program logs are collected when no `logger` is set, with a
+    /// collector capped at the runtime's default log limit. Results only
+    /// carry logs, and the events extracted from them, when they're
+    /// collected. Defaults to `true`.
+    pub collect_logs: bool,
+
+    /// The blockhash of the execution environment, used by the System
+    /// program's durable nonce instructions. Defaults to `Hash::default()`.
+    pub blockhash: Hash,
+    /// The lamports per signature of the execution environment, recorded in
+    /// durable nonce accounts. Defaults to `5000`. If `fee_model` is set, its
+    /// `lamports_per_signature` is recorded instead, matching the fee charged.
+    pub lamports_per_signature: u64,
+    /// The recent blockhash of transactions processed with
+    /// `process_transaction_instructions`. A durable nonce transaction must
+    /// use the nonce stored in its nonce account. Defaults to
+    /// `Hash::default()`.
+    pub recent_blockhash: Hash,
+
+    /// The fee model used to charge transactions processed with
+    /// `process_transaction_instructions`. Defaults to `None`, which charges
+    /// no fees. See the `fees` module.
+    pub fee_model: Option<FeeModel>,
+
+    /// Whether `process_transaction_instructions` treats a transaction
+    /// beginning with `AdvanceNonceAccount` as a durable nonce transaction,
+    /// validating the nonce before execution and advancing it even if
+    /// execution fails. Defaults to `false`. See the `nonce` module.
+    pub durable_nonce: bool,
+
+    /// The limits enforced on transactions processed with
+    /// `process_transaction_instructions`. Defaults to `None`, which enforces
+    /// no limits. See the `tx_limits` module.
+    pub enforce_tx_limits: Option<TransactionLimits>,
+
+    /// Whether to collect rent from writable accounts which aren't
+    /// rent-exempt before execution, as the runtime did. Defaults to `false`.
+    /// See the `rent_collection` module.
+    pub collect_rent: bool,
+
+    /// Whether the epoch stake seen by programs is synced with the stake
+    /// accounts provided to each instruction or transaction. Defaults to
+    /// `false`. See `epoch_stake::sync_epoch_stake`.
+    pub sync_epoch_stake: bool,
+
+    /// Interfaces to validate instructions against before execution, by
+    /// program ID. Defaults to empty. See the `interface` module.
+    pub interfaces: HashMap<Pubkey, ProgramInterface>,
+
+    /// A transform applied to the compiled transaction accounts before
+    /// execution. See `set_pre_execution_transform`.
+    pub pre_execution_transform: Option<Box<PreExecutionTransform>>,
+
+    /// How to handle a program targeted by an instruction which is neither
+    /// provided in the accounts nor loaded in the program cache. Defaults to
+    /// `MissingProgramBehavior::Panic`.
+    pub missing_program_behavior: MissingProgramBehavior,
+
+    /// The number of slots to advance between the elements of an instruction
+    /// chain, simulating time passing between transactions. Each element sees
+    /// the sysvars moved forward as with `TimeTravel::advance_slots`, including
+    /// any sysvar accounts passed to it, though `sysvars` itself is left
+    /// untouched. Defaults to `None`, which keeps the whole chain in the
+    /// current slot.
+    pub slot_advance_per_instruction: Option<u64>,
+
+    /// The maximum net growth of account data, in bytes, across a
+    /// transaction, CPIs included. Checked when each CPI returns and when
+    /// each top-level instruction completes, failing it with
+    /// `MaxAccountsDataAllocationsExceeded`, so programs which reallocate can
+    /// be tested against a tighter budget. The runtime's own limit still
+    /// applies, so larger values have no effect. Defaults to the runtime's
+    /// `MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION` (20 MiB).
+    pub accounts_resize_delta_limit: i64,
+
+    /// The program runtime environments, built once per feature set and
+    /// compute budget and reused across invocations. Defaults to a new cache
+    /// per harness, but the handle can be cloned into other harnesses to
+    /// share it. See the `runtime_environment` module.
+    pub runtime_environments: RuntimeEnvironmentCache,
+
+    /// Handlers for programs stubbed with `stub_program`, by program ID.
+    stubs: HashMap<Pubkey, Rc<StubHandler>>,
+
     /// The callback which can be used to inspect invoke_context
     /// and extract low-level information such as bpf traces, transaction
     /// context, detailed timings, etc.
@@ -601,10 +795,32 @@ impl CheckContext for Mollusk {
     }
 }
 
+/// The context checks run with when Mollusk validates a result itself,
+/// supplying the input accounts for delta checks.
+struct InputAccountsContext<'a> {
+    mollusk: &'a Mollusk,
+    accounts: &'a [(Pubkey, Account)],
+}
+
+impl CheckContext for InputAccountsContext<'_> {
+    fn is_rent_exempt(&self, lamports: u64, space: usize, owner: Pubkey) -> bool {
+        self.mollusk.is_rent_exempt(lamports, space, owner)
+    }
+
+    fn input_account(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts
+            .iter()
+            .find(|(key, _)| key == pubkey)
+            .map(|(_, account)| account)
+    }
+}
+
 struct MolluskInvokeContextCallback<'a> {
     #[cfg_attr(not(feature = "precompiles"), allow(dead_code))]
     feature_set: &'a FeatureSet,
     epoch_stake: &'a EpochStake,
+    /// The error returned by the last precompile verified, if it failed.
+    precompile_error: Cell<Option<trezoa_precompile_error::PrecompileError>>,
 }
 
 impl InvokeContextCallback for MolluskInvokeContextCallback<'_> {
@@ -635,13 +851,16 @@ impl InvokeContextCallback for MolluskInvokeContextCallback<'_> {
         data: &[u8],
         instruction_datas: Vec<&[u8]>,
     ) -> Result<(), PrecompileError> {
-        if let Some(precompile) = trezoa_precompiles::get_precompile(program_id, |feature_id| {
-            self.feature_set.is_active(feature_id)
-        }) {
+        let result = if let Some(precompile) =
+            trezoa_precompiles::get_precompile(program_id, |feature_id| {
+                self.feature_set.is_active(feature_id)
+            }) {
             precompile.verify(data, &instruction_datas, self.feature_set)
         } else {
             Err(PrecompileError::InvalidPublicKey)
-        }
+        };
+        self.precompile_error.set(result.clone().err());
+        result
     }
 
     #[cfg(not(feature = "precompiles"))]
@@ -664,6 +883,30 @@ struct MessageResult {
     pub raw_result: Result<(), TransactionError>,
     /// The return data produced by the transaction, if any.
     pub return_data: Vec<u8>,
+    /// The return data set by each program invocation, in the order it was
+    /// set.
+    pub inner_return_data: Vec<InnerReturnData>,
+    /// The number of instructions in the instruction trace, including CPIs.
+    pub instruction_trace_length: usize,
+    /// The maximum stack height reached during execution.
+    pub max_stack_height: usize,
+    /// The net change in account data size accumulated during execution.
+    pub accounts_resize_delta: i64,
+    /// The deepest program which failed, if any. See `failing_program_id`
+    /// on `InstructionResult`.
+    pub failing_program_id: Option<Pubkey>,
+    /// The error returned by a failing precompile, if any.
+    pub precompile_error: Option<trezoa_precompile_error::PrecompileError>,
+    /// The program logs emitted during execution.
+    pub logs: Vec<String>,
+    /// A summary of each top-level instruction processed.
+    pub per_instruction: Vec<InstructionExecutionSummary>,
+    /// The accounts the message declared writable.
+    pub declared_writable: Vec<Pubkey>,
+    /// The accounts referenced by the instruction trace.
+    pub referenced_accounts: Vec<Pubkey>,
+    /// A deterministic profile of the execution.
+    pub profile: ExecutionProfile,
     /// Inner instructions (CPIs) invoked during the transaction execution.
     ///
     /// Each entry represents a cross-program invocation made by the program,
@@ -678,10 +921,25 @@ struct MessageResult {
     ///
     /// This is `None` when the result is loaded from a fuzz fixture, since
     /// fixtures don't contain the compiled message.
-    #[cfg(feature = "inner-instructions")]
     pub message: Option<SanitizedMessage>,
 }
 
+/// The current return data and the program which set it.
+fn owned_return_data(invoke_context: &InvokeContext) -> (Pubkey, Vec<u8>) {
+    let (program_id, data) = invoke_context.transaction_context.get_return_data();
+    (*program_id, data.to_vec())
+}
+
+/// Metrics and inner instructions extracted from the instruction trace.
+struct DeconstructedInstructionTrace {
+    instruction_trace_length: usize,
+    max_stack_height: usize,
+    referenced_accounts: Vec<Pubkey>,
+    instructions: Vec<InstructionProfile>,
+    #[cfg(feature = "inner-instructions")]
+    inner_instructions: Vec<Vec<InnerInstruction>>,
+}
+
 impl MessageResult {
     fn extract_ix_err(txn_err: TransactionError) -> InstructionError {
         match txn_err {
@@ -703,13 +961,17 @@ impl MessageResult {
                     TransactionProgramResult::UnknownError(index, ix_err.clone())
                 }
             }
-            _ => unreachable!(), // Mollusk only uses `InstructionError` variant.
+            // Transactions can also fail before any program is invoked, such
+            // as with duplicate compute budget instructions, or a fee payer
+            // which can't pay.
+            Err(err) => TransactionProgramResult::TransactionError(err.clone()),
         }
     }
 }
 
 impl Mollusk {
     fn new_inner(#[allow(unused)] enable_register_tracing: bool) -> Self {
+        #[cfg(feature = "logger")]
         #[rustfmt::skip]
         trezoa_logger::setup_with_default(
             "trezoa_rbpf::vm=debug,\
@@ -743,6 +1005,22 @@ impl Mollusk {
             logger: None,
             program_cache,
             sysvars: Sysvars::default(),
+            collect_logs: true,
+            blockhash: Hash::default(),
+            lamports_per_signature: DEFAULT_LAMPORTS_PER_SIGNATURE,
+            recent_blockhash: Hash::default(),
+            fee_model: None,
+            durable_nonce: false,
+            enforce_tx_limits: None,
+            collect_rent: false,
+            sync_epoch_stake: false,
+            interfaces: HashMap::new(),
+            pre_execution_transform: None,
+            missing_program_behavior: MissingProgramBehavior::default(),
+            slot_advance_per_instruction: None,
+            accounts_resize_delta_limit: MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION,
+            runtime_environments: RuntimeEnvironmentCache::default(),
+            stubs: HashMap::new(),
 
             #[cfg(feature = "invocation-inspect-callback")]
             invocation_inspect_callback: Box::new(EmptyInvocationInspectCallback {}),
@@ -778,12 +1056,28 @@ impl Mollusk {
     /// - The directory specified by the `BPF_OUT_DIR` environment variable
     /// - The directory specified by the `SBF_OUT_DIR` environment variable
     /// - The current working directory
+    #[cfg(feature = "fs")]
     pub fn new(program_id: &Pubkey, program_name: &str) -> Self {
         let mut mollusk = Self::default();
         mollusk.add_program(program_id, program_name);
         mollusk
     }
 
+    /// Create a new Mollusk instance with every program found by
+    /// `file::discover_programs` added under the default loader.
+    #[cfg(feature = "fs")]
+    pub fn with_all_workspace_programs() -> Self {
+        let mut mollusk = Self::default();
+        mollusk.add_workspace_programs();
+        mollusk
+    }
+
+    /// Create a `MolluskBuilder`, to configure a Mollusk instance in one
+    /// expression. See the `builder` module.
+    pub fn builder() -> MolluskBuilder {
+        MolluskBuilder::new()
+    }
+
     /// Create a new Mollusk instance with configurable debugging features.
     ///
     /// This constructor allows enabling low-level VM debugging capabilities,
@@ -809,6 +1103,7 @@ impl Mollusk {
     /// Add a program to the test environment.
     ///
     /// If you intend to CPI to a program, this is likely what you want to use.
+    #[cfg(feature = "fs")]
     pub fn add_program(&mut self, program_id: &Pubkey, program_name: &str) {
         self.add_program_with_loader(program_id, program_name, &DEFAULT_LOADER_KEY);
     }
@@ -816,6 +1111,7 @@ impl Mollusk {
     /// Add a program to the test environment under the specified loader.
     ///
     /// If you intend to CPI to a program, this is likely what you want to use.
+    #[cfg(feature = "fs")]
     pub fn add_program_with_loader(
         &mut self,
         program_id: &Pubkey,
@@ -839,19 +1135,415 @@ impl Mollusk {
         self.program_cache.add_program(program_id, loader_key, elf);
     }
 
+    /// Register a custom syscall, available to every program in the test
+    /// environment, including programs deployed by a loader while
+    /// processing instructions. See `ProgramCache::register_syscall`.
+    pub fn register_syscall(&mut self, name: &str, function: program::Syscall) {
+        self.program_cache.register_syscall(name, function);
+    }
+
+    /// Add a program to the test environment under the specified loader,
+    /// loaded with the provided SBPF version rather than the versions
+    /// enabled by the feature set.
+    ///
+    /// Useful for testing mixed deployments, such as legacy v0 programs
+    /// alongside v3 programs. See
+    /// `ProgramCache::add_program_with_sbpf_version`.
+    #[cfg(feature = "fs")]
+    pub fn add_program_with_sbpf_version(
+        &mut self,
+        program_id: &Pubkey,
+        program_name: &str,
+        loader_key: &Pubkey,
+        sbpf_version: program::SBPFVersion,
+    ) {
+        let elf = file::load_program_elf(program_name);
+        self.program_cache.add_program_with_sbpf_version(
+            program_id,
+            loader_key,
+            &elf,
+            sbpf_version,
+        );
+    }
+
+    /// Add every program found by `file::discover_programs` to the test
+    /// environment under the default loader, returning the programs added.
+    ///
+    /// Program IDs are read from a `program-ids.json` manifest, or the
+    /// keypair files written by `cargo build-sbf`.
+    #[cfg(feature = "fs")]
+    pub fn add_workspace_programs(&mut self) -> Vec<file::DiscoveredProgram> {
+        let programs = file::discover_programs();
+        for program in &programs {
+            let elf = file::read_file(&program.path);
+            self.add_program_with_loader_and_elf(&program.program_id, &DEFAULT_LOADER_KEY, &elf);
+        }
+        programs
+    }
+
+    /// Add a program dumped from a cluster, such as with `trezoa program dump`,
+    /// to the test environment under loader-v3 (Upgradeable).
+    ///
+    /// The dump may be a raw ELF, or the full data of the program's program
+    /// data account, in which case its deployment slot and upgrade authority
+    /// are preserved. See `file::load_program_dump`.
+    ///
+    /// Returns the keyed program and program data accounts, which can be
+    /// provided to instructions that inspect them, such as those checking
+    /// the program's upgrade authority.
+    #[cfg(feature = "fs")]
+    pub fn add_program_from_dump<P: AsRef<Path>>(
+        &mut self,
+        program_id: &Pubkey,
+        path: P,
+    ) -> [(Pubkey, Account); 2] {
+        let dump = file::load_program_dump(path);
+        self.add_program_with_loader_and_elf(
+            program_id,
+            &program::loader_keys::LOADER_V3,
+            dump.elf(),
+        );
+
+        let programdata_address =
+            Pubkey::find_program_address(&[program_id.as_ref()], &program::loader_keys::LOADER_V3)
+                .0;
+        let programdata_account = match dump {
+            file::ProgramDump::Elf(elf) => program::create_program_data_account_loader_v3(&elf),
+            file::ProgramDump::ProgramData {
+                slot,
+                upgrade_authority_address,
+                elf,
+            } => program::create_program_data_account_loader_v3_with_header(
+                &elf,
+                slot,
+                upgrade_authority_address,
+            ),
+        };
+        [
+            (
+                *program_id,
+                program::create_program_account_loader_v3(program_id),
+            ),
+            (programdata_address, programdata_account),
+        ]
+    }
+
+    /// Deploy a program through the loader-v4 builtin, as a deployment tool
+    /// would.
+    ///
+    /// The program is retracted if it's deployed, resized to fit the ELF,
+    /// written, and deployed again, in a single instruction chain. The
+    /// program account is topped up to the rent-exempt minimum for its new
+    /// size. Finalized programs can't be redeployed, so the chain fails for
+    /// them.
+    ///
+    /// The loader rejects deploying or retracting a program until the
+    /// deployment cooldown has passed since its last deployment, so when
+    /// redeploying within the cooldown, the clock is warped to the end of it
+    /// first, with `time_travel`. The sysvars are left at that slot, so
+    /// instructions processed afterwards see the later clock.
+    ///
+    /// Panics if the ELF is too large for a loader-v4 program.
+    ///
+    /// `accounts` must contain the program account, which can be built in
+    /// any state with `LoaderV4AccountBuilder`, and the authority account.
+    /// Like every instruction Mollusk processes, the program cache is synced
+    /// with the deployed program, so it can be invoked by the instructions
+    /// which follow. See `ProgramCache::sync_loader_v4_program`.
+    ///
+    /// ```rust,ignore
+    /// let program_account = LoaderV4AccountBuilder::new(&[])
+    ///     .authority(&authority)
+    ///     .status(LoaderV4Status::Retracted)
+    ///     .build();
+    ///
+    /// let result = mollusk.deploy_loader_v4_program(
+    ///     &program_id,
+    ///     &authority,
+    ///     &elf,
+    ///     &[(program_id, program_account), (authority, authority_account)],
+    /// );
+    ///
+    /// // The deployed program can now be invoked, or redeployed.
+    /// mollusk.process_instruction(&instruction, &result.resulting_accounts);
+    /// mollusk.deploy_loader_v4_program(
+    ///     &program_id,
+    ///     &authority,
+    ///     &new_elf,
+    ///     &result.resulting_accounts,
+    /// );
+    /// ```
+    #[cfg(feature = "all-builtins")]
+    pub fn deploy_loader_v4_program(
+        &mut self,
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        elf: &[u8],
+        accounts: &[(Pubkey, Account)],
+    ) -> InstructionResult {
+        let mut accounts = accounts.to_vec();
+        let (_, program_account) = accounts
+            .iter_mut()
+            .find(|(key, _)| key == program_id)
+            .expect("the program account must be provided");
+        let state = program::get_loader_v4_state(program_account)
+            .expect("the program account must be owned by the loader-v4 program");
+
+        let elf_length = u32::try_from(elf.len()).expect("the ELF must fit in a loader-v4 program");
+        let program_size = LoaderV4State::program_data_offset().saturating_add(elf.len());
+        program_account.lamports = program_account
+            .lamports
+            .max(self.sysvars.rent.minimum_balance(program_size));
+
+        let mut instructions = Vec::with_capacity(4);
+        if state.status == LoaderV4Status::Deployed {
+            instructions.push(retract(program_id, authority));
+        }
+        instructions.push(set_program_length(
+            program_id, authority, elf_length, authority,
+        ));
+        instructions.push(write(program_id, authority, 0, elf.to_vec()));
+        instructions.push(deploy(program_id, authority));
+
+        // A slot of `0` means the program was never deployed, so there's no
+        // cooldown to wait for. Retracting doesn't reset the slot, so one
+        // wait covers both the retraction and the deployment.
+        let cooldown_end = state.slot.saturating_add(DEPLOYMENT_COOLDOWN_IN_SLOTS);
+        if state.slot != 0 && self.sysvars.clock.slot < cooldown_end {
+            self.time_travel().warp_to_slot(cooldown_end);
+        }
+
+        self.process_instruction_chain(&instructions, &accounts)
+    }
+
+    /// Stub a program with a Rust closure, receiving the instruction data and
+    /// the instruction's accounts. See the `stub` module.
+    ///
+    /// The stub is registered in the program cache as a builtin, replacing
+    /// any program with the same ID. Returns the keyed program account, which
+    /// must be provided to instructions invoking the stub through CPI.
+    pub fn stub_program<F>(&mut self, program_id: &Pubkey, handler: F) -> (Pubkey, Account)
+    where
+        F: Fn(&[u8], &[AccountInfo]) -> trezoa_program_error::ProgramResult + 'static,
+    {
+        self.program_cache.add_builtin(program::Builtin::new(
+            *program_id,
+            stub::STUB_PROGRAM_NAME,
+            stub::Entrypoint::vm,
+        ));
+        self.stubs.insert(*program_id, Rc::new(handler));
+        program::create_keyed_account_for_builtin_program(program_id, stub::STUB_PROGRAM_NAME)
+    }
+
     /// Warp the test environment to a slot by updating sysvars.
     pub fn warp_to_slot(&mut self, slot: u64) {
         self.sysvars.warp_to_slot(slot)
     }
 
+    /// Move the test environment through time, updating the clock, slot
+    /// hashes, stake history, and epoch rewards sysvars together.
+    ///
+    /// See the `time_travel` module.
+    pub fn time_travel(&mut self) -> TimeTravel<'_> {
+        TimeTravel::new(&mut self.sysvars)
+    }
+
+    /// Create an `AccountBuilder` for seeding test accounts, using the
+    /// configured `Rent` sysvar for rent-exempt balances.
+    pub fn account_builder(&self) -> AccountBuilder<'_> {
+        AccountBuilder::new(&self.sysvars.rent)
+    }
+
+    /// Get the mode in which SBF programs are executed: JIT-compiled or
+    /// interpreted.
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.program_cache.execution_mode()
+    }
+
+    /// The lamports per signature recorded in durable nonce accounts: the
+    /// fee model's, if set, so it matches the fee charged.
+    pub(crate) fn nonce_lamports_per_signature(&self) -> u64 {
+        self.fee_model
+            .as_ref()
+            .map_or(self.lamports_per_signature, |fee_model| {
+                fee_model.lamports_per_signature
+            })
+    }
+
+    /// Set a transform to apply to the compiled transaction accounts before
+    /// every execution.
+    ///
+    /// The transform runs after the accounts are compiled - including any
+    /// fallback accounts, such as program stubs and sysvars - but before the
+    /// transaction context is created. This lets tests inject corrupted or
+    /// partially-written state without changing the accounts they pass in
+    /// and assert against.
+    ///
+    /// ```rust,ignore
+    /// mollusk.set_pre_execution_transform(move |accounts| {
+    ///     for (pubkey, account) in accounts.iter_mut() {
+    ///         if *pubkey == target {
+    ///             account.set_rent_epoch(0);
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn set_pre_execution_transform<F>(&mut self, transform: F)
+    where
+        F: Fn(&mut Vec<(Pubkey, AccountSharedData)>) + 'static,
+    {
+        self.pre_execution_transform = Some(Box::new(transform));
+    }
+
+    /// Set whether SBF programs are JIT-compiled or interpreted.
+    ///
+    /// Interpreted mode is useful for precise tracing and coverage, while JIT
+    /// mode gives more realistic execution time measurements. Programs already
+    /// loaded are reloaded in the new mode.
+    ///
+    /// JIT mode is only available on supported platforms (x86_64, excluding
+    /// Windows). Elsewhere, programs are always interpreted.
+    pub fn set_execution_mode(&mut self, execution_mode: ExecutionMode) {
+        self.program_cache.set_execution_mode(execution_mode);
+    }
+
+    /// Create a vote account for every entry in the configured `EpochStake`.
+    ///
+    /// Programs which read vote accounts alongside the epoch stake syscalls
+    /// will see consistent data if these accounts are provided to the
+    /// instruction. See [`epoch_stake::create_vote_account`].
+    pub fn epoch_stake_vote_accounts(&self) -> Vec<(Pubkey, Account)> {
+        crate::epoch_stake::create_vote_accounts(
+            &self.epoch_stake,
+            &self.sysvars.rent,
+            self.sysvars.clock.epoch,
+        )
+    }
+
+    /// Replace the configured `EpochStake` with the stake generated by the
+    /// provided builder, and record it in the current epoch's `StakeHistory`
+    /// entry.
+    ///
+    /// Returns the generated vote and stake accounts, which should be
+    /// provided to instructions reading them.
+    pub fn set_epoch_stake(&mut self, builder: EpochStakeBuilder) -> EpochStakeAccounts {
+        let accounts = builder.build(&self.sysvars.rent, self.sysvars.clock.epoch);
+        self.epoch_stake = accounts.epoch_stake.clone();
+        self.sysvars.stake_history.add(
+            self.sysvars.clock.epoch,
+            accounts.stake_history_entry.clone(),
+        );
+        accounts
+    }
+
+    fn synced_epoch_stake(&self, transaction_context: &TransactionContext) -> Cow<'_, EpochStake> {
+        if !self.sync_epoch_stake {
+            return Cow::Borrowed(&self.epoch_stake);
+        }
+        let accounts = (0..transaction_context.get_number_of_accounts())
+            .filter_map(|index| transaction_context.accounts().try_borrow(index).ok())
+            .collect::<Vec<_>>();
+        let mut epoch_stake = self.epoch_stake.clone();
+        crate::epoch_stake::sync_epoch_stake(
+            &mut epoch_stake,
+            accounts.iter().map(|account| &**account),
+            self.sysvars.clock.epoch,
+            &self.sysvars.stake_history,
+            self.feature_set
+                .new_warmup_cooldown_rate_epoch(&self.sysvars.epoch_schedule),
+        );
+        Cow::Owned(epoch_stake)
+    }
+
     fn get_loader_key(&self, program_id: &Pubkey) -> Pubkey {
-        if crate::program::precompile_keys::is_precompile(program_id) {
-            crate::program::loader_keys::NATIVE_LOADER
-        } else {
-            self.program_cache
-                .load_program(program_id)
-                .or_panic_with(MolluskError::ProgramNotCached(program_id))
-                .account_owner()
+        if crate::program::precompile_keys::is_precompile(program_id)
+            || crate::compute_budget_instructions::is_compute_budget_program(program_id)
+        {
+            return crate::program::loader_keys::NATIVE_LOADER;
+        }
+        match self.program_cache.load_program(program_id) {
+            Some(entry) => entry.account_owner(),
+            None if self.missing_program_behavior == MissingProgramBehavior::Panic => {
+                panic!("{}", MolluskError::ProgramNotCached(program_id))
+            }
+            // The runtime can't find a builtin for the stub, so it fails the
+            // invocation with `UnsupportedProgramId`.
+            None => crate::program::loader_keys::NATIVE_LOADER,
+        }
+    }
+
+    // With `MissingProgramBehavior::Error`, find the index of the first
+    // instruction whose program is neither provided nor cached.
+    fn find_missing_program(
+        &self,
+        instructions: &[Instruction],
+        accounts: &[(Pubkey, Account)],
+    ) -> Option<usize> {
+        if self.missing_program_behavior != MissingProgramBehavior::Error {
+            return None;
+        }
+        instructions.iter().position(|instruction| {
+            let program_id = &instruction.program_id;
+            !accounts.iter().any(|(key, _)| key == program_id)
+                && !crate::program::precompile_keys::is_precompile(program_id)
+                && !crate::compute_budget_instructions::is_compute_budget_program(program_id)
+                && self.program_cache.load_program(program_id).is_none()
+        })
+    }
+
+    // The result of an instruction whose program is missing, under
+    // `MissingProgramBehavior::Error`.
+    fn missing_program_result(
+        &self,
+        program_id: &Pubkey,
+        accounts: &[(Pubkey, Account)],
+    ) -> InstructionResult {
+        let raw_result = Err(InstructionError::UnsupportedProgramId);
+        InstructionResult {
+            program_result: raw_result.clone().into(),
+            raw_result,
+            resulting_accounts: accounts.to_vec(),
+            failing_program_id: Some(*program_id),
+            execution_mode: Some(self.execution_mode()),
+            ..Default::default()
+        }
+    }
+
+    fn input_accounts_context<'a>(
+        &'a self,
+        accounts: &'a [(Pubkey, Account)],
+    ) -> InputAccountsContext<'a> {
+        InputAccountsContext {
+            mollusk: self,
+            accounts,
+        }
+    }
+
+    // Run checks according to the config. With `show_diffs`, failures are
+    // reported with the diff of the accounts appended, unless they're routed
+    // to an `on_failure` sink.
+    fn run_checks_with_diffs(
+        &self,
+        run_checks: impl FnOnce(&Config) -> bool,
+        diff_accounts: impl FnOnce() -> Vec<AccountDiff>,
+    ) {
+        if !self.config.show_diffs || self.config.on_failure.is_some() {
+            run_checks(&self.config);
+            return;
+        }
+        let config = Config::default()
+            .panic(false)
+            .verbose(self.config.panic || self.config.verbose)
+            .show_diffs(true);
+        if run_checks(&config) {
+            return;
+        }
+        let diffs = format_diffs(&diff_accounts());
+        if self.config.panic {
+            panic!("CHECKS FAILED\nAccount diffs:\n{}", diffs);
+        }
+        if self.config.verbose {
+            eprintln!("Account diffs:\n{}", diffs);
         }
     }
 
@@ -895,26 +1587,77 @@ impl Mollusk {
 
     fn create_transaction_context(
         &self,
-        transaction_accounts: Vec<(Pubkey, AccountSharedData)>,
+        sanitized_message: &SanitizedMessage,
+        mut transaction_accounts: Vec<(Pubkey, AccountSharedData)>,
+        compute_budget: &ComputeBudget,
     ) -> TransactionContext<'_> {
+        if self.collect_rent {
+            // Like the runtime, only collect rent from writable accounts.
+            for (_, (pubkey, account)) in transaction_accounts
+                .iter_mut()
+                .enumerate()
+                .filter(|(index, _)| sanitized_message.is_writable(*index))
+            {
+                rent_collection::collect_rent(
+                    &self.sysvars.rent,
+                    &self.sysvars.epoch_schedule,
+                    self.sysvars.clock.epoch,
+                    pubkey,
+                    account,
+                );
+            }
+        }
+        if let Some(transform) = &self.pre_execution_transform {
+            transform(&mut transaction_accounts);
+        }
         TransactionContext::new(
             transaction_accounts,
             self.sysvars.rent.clone(),
-            self.compute_budget.max_instruction_stack_depth,
-            self.compute_budget.max_instruction_trace_length,
+            compute_budget.max_instruction_stack_depth,
+            compute_budget.max_instruction_trace_length,
         )
     }
 
-    #[cfg(feature = "inner-instructions")]
-    fn deconstruct_inner_instructions(
+    fn deconstruct_instruction_trace(
         transaction_context: &mut TransactionContext,
-    ) -> Vec<Vec<InnerInstruction>> {
+    ) -> DeconstructedInstructionTrace {
         let ix_trace = transaction_context.take_instruction_trace();
+        let instruction_trace_length = ix_trace.len();
+        let mut max_stack_height = 0;
+        let mut referenced_accounts = Vec::new();
+        let mut instructions = Vec::new();
+        #[cfg(feature = "inner-instructions")]
         let mut all_inner_instructions: Vec<Vec<InnerInstruction>> = Vec::new();
 
         for ix_in_trace in ix_trace {
             let stack_height = ix_in_trace.nesting_level.saturating_add(1);
+            max_stack_height = max_stack_height.max(stack_height);
+
+            let indices = once(ix_in_trace.program_account_index_in_tx).chain(
+                ix_in_trace
+                    .instruction_accounts
+                    .iter()
+                    .map(|acc| acc.index_in_transaction),
+            );
+            for index in indices {
+                if let Ok(key) = transaction_context.get_key_of_account_at_index(index) {
+                    if !referenced_accounts.contains(key) {
+                        referenced_accounts.push(*key);
+                    }
+                }
+            }
+
+            if let Ok(program_id) = transaction_context
+                .get_key_of_account_at_index(ix_in_trace.program_account_index_in_tx)
+            {
+                instructions.push(InstructionProfile {
+                    program_id: *program_id,
+                    stack_height,
+                    compute_units_consumed: None,
+                });
+            }
 
+            #[cfg(feature = "inner-instructions")]
             if stack_height == 1 {
                 // Top-level instruction: start a new empty group for its inner instructions.
                 all_inner_instructions.push(Vec::new());
@@ -936,7 +1679,14 @@ impl Mollusk {
             }
         }
 
-        all_inner_instructions
+        DeconstructedInstructionTrace {
+            instruction_trace_length,
+            max_stack_height,
+            referenced_accounts,
+            instructions,
+            #[cfg(feature = "inner-instructions")]
+            inner_instructions: all_inner_instructions,
+        }
     }
 
     fn deconstruct_resulting_accounts(
@@ -964,65 +1714,152 @@ impl Mollusk {
             .collect()
     }
 
+    // The state of execution when a message failed, taken from the
+    // transaction context before its changes are discarded.
+    fn failure_context(
+        message_result: &MessageResult,
+        transaction_context: &TransactionContext,
+        accounts: &[(Pubkey, Account)],
+    ) -> Option<FailureContext> {
+        message_result.raw_result.is_err().then(|| FailureContext {
+            instruction_trace: message_result.profile.instructions.clone(),
+            logs: message_result.logs.clone(),
+            compute_units_consumed: message_result.compute_units_consumed,
+            accounts: Self::deconstruct_resulting_accounts(transaction_context, accounts),
+        })
+    }
+
+    fn modified_account_keys(
+        original_accounts: &[(Pubkey, Account)],
+        resulting_accounts: &[(Pubkey, Account)],
+    ) -> Vec<Pubkey> {
+        original_accounts
+            .iter()
+            .zip(resulting_accounts)
+            .filter(|((_, original), (_, resulting))| original != resulting)
+            .map(|((pubkey, _), _)| *pubkey)
+            .collect()
+    }
+
+    // Programs deployed, upgraded, or closed by a successful message are
+    // synced to the program cache, so the instructions which follow invoke
+    // them in their new state, rather than waiting for the next slot as the
+    // loaders would.
+    fn sync_modified_programs(
+        &self,
+        message_result: &MessageResult,
+        resulting_accounts: &[(Pubkey, Account)],
+        actually_modified: &[Pubkey],
+    ) {
+        if message_result.raw_result.is_ok() {
+            self.program_cache
+                .sync_modified_programs(resulting_accounts, actually_modified);
+        }
+    }
+
     fn process_transaction_message<'a>(
         &self,
         sanitized_message: &'a SanitizedMessage,
         transaction_context: &mut TransactionContext<'a>,
         sysvar_cache: &SysvarCache,
+        compute_budget: &ComputeBudget,
     ) -> MessageResult {
         let mut compute_units_consumed = 0;
         let mut timings = ExecuteTimings::default();
 
+        let declared_writable = sanitized_message
+            .account_keys()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| sanitized_message.is_writable(*index))
+            .map(|(_, key)| *key)
+            .collect();
+
+        let _active_stubs = stub::activate(&self.stubs);
+        program::set_accounts_resize_delta_limit(self.accounts_resize_delta_limit);
+        // Discard anything recorded by an execution which panicked.
+        program::take_inner_return_data();
+        let epoch_stake = self.synced_epoch_stake(transaction_context);
         let mut program_cache = self.program_cache.cache();
         let callback = MolluskInvokeContextCallback {
-            epoch_stake: &self.epoch_stake,
+            epoch_stake: &epoch_stake,
             feature_set: &self.feature_set,
+            precompile_error: Cell::new(None),
         };
-        let execution_budget = self.compute_budget.to_budget();
         let runtime_features = self.feature_set.runtime_features();
 
         let _enable_register_tracing = false;
         #[cfg(feature = "register-tracing")]
         let _enable_register_tracing = self.enable_register_tracing;
 
-        let program_runtime_environments: ProgramRuntimeEnvironments = ProgramRuntimeEnvironments {
-            program_runtime_v1: Arc::new(
-                create_program_runtime_environment_v1(
-                    &runtime_features,
-                    &execution_budget,
-                    /* reject_deployment_of_broken_elfs */ false,
-                    /* debugging_features */ _enable_register_tracing,
-                )
-                .unwrap(),
-            ),
-            program_runtime_v2: Arc::new(create_program_runtime_environment_v2(
-                &execution_budget,
-                /* debugging_features */ _enable_register_tracing,
-            )),
+        let program_runtime_environments = self.runtime_environments.get(
+            &self.feature_set,
+            compute_budget,
+            _enable_register_tracing,
+            self.program_cache.syscalls(),
+        );
+
+        let logger = self
+            .logger
+            .clone()
+            .or_else(|| self.collect_logs.then(LogCollector::new_ref));
+        let log_count = || {
+            logger
+                .as_ref()
+                .map_or(0, |logger| logger.borrow().get_recorded_content().len())
         };
+        let logs_since = |start: usize| {
+            logger.as_ref().map_or_else(Vec::new, |logger| {
+                logger.borrow().get_recorded_content()[start..].to_vec()
+            })
+        };
+        let log_start = log_count();
 
         let mut invoke_context = InvokeContext::new(
             transaction_context,
             &mut program_cache,
             EnvironmentConfig::new(
-                Hash::default(),
-                /* blockhash_lamports_per_signature */ 5000, // The default value
+                self.blockhash,
+                self.nonce_lamports_per_signature(),
                 &callback,
                 &runtime_features,
                 &program_runtime_environments,
                 &program_runtime_environments,
                 sysvar_cache,
             ),
-            self.logger.clone(),
-            self.compute_budget.to_budget(),
-            self.compute_budget.to_cost(),
+            logger.clone(),
+            compute_budget.to_budget(),
+            compute_budget.to_cost(),
         );
 
         let mut raw_result = Ok(());
+        let mut top_level_compute_units = Vec::new();
+        let mut per_instruction = Vec::new();
+        let mut inner_return_data = Vec::new();
+        let mut failing_program_id = None;
+        program::take_failing_cpi_program_id();
+        #[cfg(feature = "inner-instructions")]
+        let mut skipped_instructions = Vec::new();
 
         for (instruction_index, (program_id, compiled_ix)) in
             sanitized_message.program_instructions_iter().enumerate()
         {
+            // Compute budget instructions are applied to the budget before
+            // execution, not invoked.
+            if crate::compute_budget_instructions::is_compute_budget_program(program_id) {
+                #[cfg(feature = "inner-instructions")]
+                skipped_instructions.push(instruction_index);
+                per_instruction.push(InstructionExecutionSummary {
+                    program_id: *program_id,
+                    program_result: ProgramResult::Success,
+                    raw_result: Ok(()),
+                    compute_units_consumed: 0,
+                    return_data: Vec::new(),
+                    logs: Vec::new(),
+                });
+                continue;
+            }
+
             let program_id_index = compiled_ix.program_id_index as IndexOfAccount;
 
             invoke_context
@@ -1050,6 +1887,9 @@ impl Mollusk {
                 );
             }
 
+            let compute_units_before = compute_units_consumed;
+            let logs_before = log_count();
+            let return_data_before = owned_return_data(&invoke_context);
             let invoke_result = if invoke_context.is_precompile(program_id) {
                 invoke_context.process_precompile(
                     program_id,
@@ -1059,6 +1899,40 @@ impl Mollusk {
             } else {
                 invoke_context.process_instruction(&mut compute_units_consumed, &mut timings)
             };
+            top_level_compute_units.push(compute_units_consumed - compute_units_before);
+            // The runtime only enforces its own limit, so a tighter one is
+            // checked when each CPI returns, and once the instruction
+            // completes.
+            let invoke_result = invoke_result.and_then(|()| {
+                let accounts_resize_delta =
+                    invoke_context.transaction_context.accounts_resize_delta()?;
+                if accounts_resize_delta > self.accounts_resize_delta_limit {
+                    return Err(InstructionError::MaxAccountsDataAllocationsExceeded);
+                }
+                Ok(())
+            });
+
+            // Return data persists across instructions, so it's only
+            // attributed to the instruction which set it. Builtins set it
+            // without a syscall, so they're only noticed if they changed it.
+            let instruction_return_data = program::take_inner_return_data();
+            let return_data_after = owned_return_data(&invoke_context);
+            let set_return_data =
+                !instruction_return_data.is_empty() || return_data_after != return_data_before;
+            let return_data = if set_return_data {
+                return_data_after.1
+            } else {
+                Vec::new()
+            };
+            inner_return_data.extend(instruction_return_data);
+            per_instruction.push(InstructionExecutionSummary {
+                program_id: *program_id,
+                program_result: invoke_result.clone().into(),
+                raw_result: invoke_result.clone(),
+                compute_units_consumed: compute_units_consumed - compute_units_before,
+                return_data,
+                logs: logs_since(logs_before),
+            });
 
             #[cfg(feature = "invocation-inspect-callback")]
             self.invocation_inspect_callback.after_invocation(
@@ -1068,6 +1942,9 @@ impl Mollusk {
             );
 
             if let Err(err) = invoke_result {
+                // Unless one of its CPIs failed, the instruction's own program
+                // is the deepest failing program.
+                failing_program_id = program::take_failing_cpi_program_id().or(Some(*program_id));
                 raw_result = Err(TransactionError::InstructionError(
                     instruction_index as u8,
                     err,
@@ -1076,23 +1953,114 @@ impl Mollusk {
             }
         }
 
+        #[allow(unused_mut)]
+        let mut profile = ExecutionProfile::default();
+        #[cfg(feature = "register-tracing")]
+        if self.enable_register_tracing {
+            register_tracing::profile_vm_traces(&invoke_context, &mut profile);
+        }
+
         let return_data = transaction_context.get_return_data().1.to_vec();
+        let accounts_resize_delta = transaction_context
+            .accounts_resize_delta()
+            .unwrap_or_default();
+
+        let logs = logs_since(log_start);
+
+        // Successful precompiles reset the error, so it belongs to the
+        // instruction which failed.
+        let precompile_error = callback.precompile_error.take();
+
+        let instruction_trace = Self::deconstruct_instruction_trace(transaction_context);
+
+        // Top-level consumption is measured directly. CPIs only report
+        // theirs through the logs.
+        profile.instructions = instruction_trace.instructions;
+        for (instruction, compute_units) in profile
+            .top_level_instructions_mut()
+            .zip(top_level_compute_units)
+        {
+            instruction.compute_units_consumed = Some(compute_units);
+        }
+        profile.record_logged_compute_units(&logs);
 
         #[cfg(feature = "inner-instructions")]
-        let inner_instructions = Self::deconstruct_inner_instructions(transaction_context);
+        let inner_instructions = {
+            let mut inner_instructions = instruction_trace.inner_instructions;
+            // Skipped instructions have no trace entry, so give them empty
+            // groups to keep the indices aligned with the message.
+            for index in skipped_instructions {
+                inner_instructions.insert(index, Vec::new());
+            }
+            inner_instructions
+        };
 
         MessageResult {
             compute_units_consumed,
             execution_time: timings.details.execute_us.0,
             raw_result,
             return_data,
+            inner_return_data,
+            instruction_trace_length: instruction_trace.instruction_trace_length,
+            max_stack_height: instruction_trace.max_stack_height,
+            accounts_resize_delta,
+            failing_program_id,
+            precompile_error,
+            logs,
+            per_instruction,
+            declared_writable,
+            referenced_accounts: instruction_trace.referenced_accounts,
+            profile,
             #[cfg(feature = "inner-instructions")]
             inner_instructions,
-            #[cfg(feature = "inner-instructions")]
             message: Some(sanitized_message.clone()),
         }
     }
 
+    // The sysvars seen by the element of an instruction chain at `index`, if
+    // `slot_advance_per_instruction` moves them away from `sysvars`.
+    fn chain_element_sysvars(&self, index: usize) -> Option<Sysvars> {
+        let slots = self
+            .slot_advance_per_instruction?
+            .saturating_mul(index as u64);
+        if slots == 0 {
+            return None;
+        }
+        let mut sysvars = self.sysvars.clone();
+        TimeTravel::new(&mut sysvars).advance_slots(slots);
+        Some(sysvars)
+    }
+
+    // The sysvar cache and fallback accounts for the element of an
+    // instruction chain at `index`, if `slot_advance_per_instruction` moves
+    // its sysvars away from `sysvars`. The sysvar accounts passed to the
+    // element are moved forward too, so they agree with the cache.
+    fn advance_chain_element(
+        &self,
+        index: usize,
+        accounts: &mut [(Pubkey, Account)],
+        fallback_accounts: &HashMap<Pubkey, Account>,
+    ) -> Option<(SysvarCache, HashMap<Pubkey, Account>)> {
+        let sysvars = self.chain_element_sysvars(index)?;
+        // Only the time-derived sysvars are replaced, so a custom `Rent`, for
+        // example, is kept.
+        let advance = |key: &Pubkey, account: &mut Account| {
+            if let Some(advanced) = sysvars.maybe_create_sysvar_account(key) {
+                if self.sysvars.maybe_create_sysvar_account(key).as_ref() != Some(&advanced) {
+                    account.data = advanced.data;
+                }
+            }
+        };
+        for (key, account) in accounts.iter_mut() {
+            advance(key, account);
+        }
+        let mut fallback_accounts = fallback_accounts.clone();
+        for (key, account) in fallback_accounts.iter_mut() {
+            advance(key, account);
+        }
+        Some((sysvars.setup_sysvar_cache(accounts), fallback_accounts))
+    }
+
     fn process_instruction_chain_element(
         &self,
         index: usize,
@@ -1100,20 +2068,35 @@ impl Mollusk {
         accounts: &[(Pubkey, Account)],
         fallback_accounts: &HashMap<Pubkey, Account>,
         sysvar_cache: &SysvarCache,
+        compute_budget: &ComputeBudget,
     ) -> InstructionResult {
+        if self
+            .find_missing_program(std::slice::from_ref(instruction), accounts)
+            .is_some()
+        {
+            return self.missing_program_result(&instruction.program_id, accounts);
+        }
+
         let (sanitized_message, transaction_accounts) = crate::compile_accounts::compile_accounts(
             std::slice::from_ref(instruction),
             accounts.iter(),
             fallback_accounts,
+            None,
+            &Hash::default(),
         );
 
-        let mut transaction_context = self.create_transaction_context(transaction_accounts);
+        let mut transaction_context = self.create_transaction_context(
+            &sanitized_message,
+            transaction_accounts,
+            compute_budget,
+        );
         transaction_context.set_top_level_instruction_index(index);
 
         let message_result = self.process_transaction_message(
             &sanitized_message,
             &mut transaction_context,
             sysvar_cache,
+            compute_budget,
         );
 
         let resulting_accounts = if message_result.raw_result.is_ok() {
@@ -1121,6 +2104,10 @@ impl Mollusk {
         } else {
             accounts.to_vec()
         };
+        let actually_modified = Self::modified_account_keys(accounts, &resulting_accounts);
+        self.sync_modified_programs(&message_result, &resulting_accounts, &actually_modified);
+        let failure_context =
+            Self::failure_context(&message_result, &transaction_context, accounts);
 
         let raw_result = message_result
             .raw_result
@@ -1132,15 +2119,27 @@ impl Mollusk {
             program_result: raw_result.clone().into(),
             raw_result,
             return_data: message_result.return_data,
+            inner_return_data: message_result.inner_return_data,
             resulting_accounts,
+            instruction_trace_length: message_result.instruction_trace_length,
+            max_stack_height: message_result.max_stack_height,
+            accounts_resize_delta: message_result.accounts_resize_delta,
+            failing_program_id: message_result.failing_program_id,
+            precompile_error: message_result.precompile_error,
+            declared_writable: message_result.declared_writable,
+            actually_modified,
+            referenced_accounts: message_result.referenced_accounts,
+            profile: message_result.profile,
+            execution_mode: Some(self.execution_mode()),
+            logs: message_result.logs,
             #[cfg(feature = "inner-instructions")]
             inner_instructions: message_result
                 .inner_instructions
                 .into_iter()
                 .nth(index)
                 .unwrap_or_default(),
-            #[cfg(feature = "inner-instructions")]
             message: message_result.message,
+            failure_context,
         };
 
         #[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
@@ -1175,6 +2174,15 @@ impl Mollusk {
         instruction: &Instruction,
         accounts: &[(Pubkey, Account)],
     ) -> InstructionResult {
+        self.validate_interfaces(std::iter::once(instruction));
+
+        if self
+            .find_missing_program(std::slice::from_ref(instruction), accounts)
+            .is_some()
+        {
+            return self.missing_program_result(&instruction.program_id, accounts);
+        }
+
         let fallback_accounts = self.get_account_fallbacks(
             std::iter::once(&instruction.program_id),
             std::iter::once(instruction),
@@ -1185,15 +2193,22 @@ impl Mollusk {
             std::slice::from_ref(instruction),
             accounts.iter(),
             &fallback_accounts,
+            None,
+            &Hash::default(),
         );
 
-        let mut transaction_context = self.create_transaction_context(transaction_accounts);
+        let mut transaction_context = self.create_transaction_context(
+            &sanitized_message,
+            transaction_accounts,
+            &self.compute_budget,
+        );
         let sysvar_cache = self.sysvars.setup_sysvar_cache(accounts);
 
         let message_result = self.process_transaction_message(
             &sanitized_message,
             &mut transaction_context,
             &sysvar_cache,
+            &self.compute_budget,
         );
 
         let resulting_accounts = if message_result.raw_result.is_ok() {
@@ -1201,6 +2216,10 @@ impl Mollusk {
         } else {
             accounts.to_vec()
         };
+        let actually_modified = Self::modified_account_keys(accounts, &resulting_accounts);
+        self.sync_modified_programs(&message_result, &resulting_accounts, &actually_modified);
+        let failure_context =
+            Self::failure_context(&message_result, &transaction_context, accounts);
 
         let raw_result = message_result
             .raw_result
@@ -1212,15 +2231,27 @@ impl Mollusk {
             program_result: raw_result.clone().into(),
             raw_result,
             return_data: message_result.return_data,
+            inner_return_data: message_result.inner_return_data,
             resulting_accounts,
+            instruction_trace_length: message_result.instruction_trace_length,
+            max_stack_height: message_result.max_stack_height,
+            accounts_resize_delta: message_result.accounts_resize_delta,
+            failing_program_id: message_result.failing_program_id,
+            precompile_error: message_result.precompile_error,
+            declared_writable: message_result.declared_writable,
+            actually_modified,
+            referenced_accounts: message_result.referenced_accounts,
+            profile: message_result.profile,
+            execution_mode: Some(self.execution_mode()),
+            logs: message_result.logs,
             #[cfg(feature = "inner-instructions")]
             inner_instructions: message_result
                 .inner_instructions
                 .into_iter()
                 .next()
                 .unwrap_or_default(),
-            #[cfg(feature = "inner-instructions")]
             message: message_result.message,
+            failure_context,
         };
 
         #[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
@@ -1270,6 +2301,42 @@ impl Mollusk {
         instructions: &[Instruction],
         accounts: &[(Pubkey, Account)],
     ) -> InstructionResult {
+        self.process_instruction_chain_with_hook(instructions, accounts, |_, _| {})
+    }
+
+    /// Process a chain of instructions like `process_instruction_chain`,
+    /// invoking `hook` between elements.
+    ///
+    /// After each instruction except the last succeeds, `hook` is called with
+    /// the index of that instruction and the resulting accounts, which it may
+    /// modify before they're passed to the next instruction. This can be used
+    /// to simulate an external actor, such as a crank or an oracle update,
+    /// acting between instructions.
+    ///
+    /// ```rust,ignore
+    /// mollusk.process_instruction_chain_with_hook(
+    ///     &[deposit, settle],
+    ///     &accounts,
+    ///     |index, accounts| {
+    ///         if index == 0 {
+    ///             // Publish a new price before settling.
+    ///             let (_, oracle) = accounts
+    ///                 .iter_mut()
+    ///                 .find(|(key, _)| key == &oracle_key)
+    ///                 .unwrap();
+    ///             oracle.data = new_price.to_le_bytes().to_vec();
+    ///         }
+    ///     },
+    /// );
+    /// ```
+    pub fn process_instruction_chain_with_hook(
+        &self,
+        instructions: &[Instruction],
+        accounts: &[(Pubkey, Account)],
+        mut hook: impl FnMut(usize, &mut Vec<(Pubkey, Account)>),
+    ) -> InstructionResult {
+        self.validate_interfaces(instructions);
+
         let mut composite_result = InstructionResult {
             resulting_accounts: accounts.to_vec(),
             ..Default::default()
@@ -1284,12 +2351,22 @@ impl Mollusk {
         let sysvar_cache = self.sysvars.setup_sysvar_cache(accounts);
 
         for (index, instruction) in instructions.iter().enumerate() {
+            let advanced = self.advance_chain_element(
+                index,
+                &mut composite_result.resulting_accounts,
+                &fallback_accounts,
+            );
+            let (element_sysvar_cache, element_fallback_accounts) = match &advanced {
+                Some((sysvar_cache, fallback_accounts)) => (sysvar_cache, fallback_accounts),
+                None => (&sysvar_cache, &fallback_accounts),
+            };
             let this_result = self.process_instruction_chain_element(
                 index,
                 instruction,
                 &composite_result.resulting_accounts,
-                &fallback_accounts,
-                &sysvar_cache,
+                element_fallback_accounts,
+                element_sysvar_cache,
+                &self.compute_budget,
             );
 
             composite_result.absorb(this_result);
@@ -1297,6 +2374,10 @@ impl Mollusk {
             if composite_result.program_result.is_err() {
                 break;
             }
+
+            if index + 1 < instructions.len() {
+                hook(index, &mut composite_result.resulting_accounts);
+            }
         }
 
         composite_result
@@ -1318,37 +2399,256 @@ impl Mollusk {
     /// * `program_result`: The result code of the last program's execution and
     ///   its index.
     /// * `resulting_accounts`: The resulting accounts after all instructions.
+    /// * `per_instruction`: The status, compute units, return data, and logs of
+    ///   each top-level instruction processed.
+    ///
+    /// Compute budget program instructions are handled as a validator would:
+    /// the requested compute unit limit and heap size are applied to the
+    /// transaction's budget, and the instructions themselves are not executed.
+    ///
+    /// If `fee_model` is set, the fee payer is charged before execution. See
+    /// the `fees` module.
+    ///
+    /// If `durable_nonce` is set, a transaction beginning with
+    /// `AdvanceNonceAccount` is handled as a durable nonce transaction. See
+    /// the `nonce` module.
+    ///
+    /// If `enforce_tx_limits` is set, a transaction exceeding the size,
+    /// account, or loaded account data limits fails without executing. See
+    /// the `tx_limits` module.
     pub fn process_transaction_instructions(
         &self,
         instructions: &[Instruction],
         accounts: &[(Pubkey, Account)],
     ) -> TransactionResult {
+        self.process_transaction_instructions_inner(instructions, accounts, None, None)
+    }
+
+    /// Process multiple instructions as a v0 transaction, using the provided
+    /// address lookup tables.
+    ///
+    /// Works like `process_transaction_instructions`, but compiles a v0
+    /// message. Any instruction accounts found in the lookup tables are
+    /// loaded through them, as writable or readonly loaded addresses, rather
+    /// than included as static account keys. Unless `fee_model` designates
+    /// one, the first signer across all instructions acts as the fee payer.
+    ///
+    /// As with any other account, the accounts referenced through lookup
+    /// tables must be provided in `accounts`. The lookup table accounts
+    /// themselves do not need to be.
+    pub fn process_transaction_instructions_with_lookup_tables(
+        &self,
+        instructions: &[Instruction],
+        accounts: &[(Pubkey, Account)],
+        address_lookup_tables: &[AddressLookupTableAccount],
+    ) -> TransactionResult {
+        self.process_transaction_instructions_inner(
+            instructions,
+            accounts,
+            Some(address_lookup_tables),
+            None,
+        )
+    }
+
+    /// Validate instructions against the interfaces registered for their
+    /// programs, if any. See the `interface` module.
+    fn validate_interfaces<'a>(&self, instructions: impl IntoIterator<Item = &'a Instruction>) {
+        for instruction in instructions {
+            if let Some(interface) = self.interfaces.get(&instruction.program_id) {
+                interface
+                    .validate(instruction)
+                    .or_panic_with(MolluskError::InterfaceMismatch(&instruction.program_id));
+            }
+        }
+    }
+
+    /// The result of a transaction which failed before any instruction was
+    /// executed.
+    fn unprocessed_transaction_result(
+        &self,
+        err: TransactionError,
+        accounts: &[(Pubkey, Account)],
+    ) -> TransactionResult {
+        let raw_result = Err(err);
+        TransactionResult {
+            compute_units_consumed: 0,
+            execution_time: 0,
+            program_result: MessageResult::extract_txn_program_result(&raw_result),
+            raw_result,
+            return_data: Vec::new(),
+            inner_return_data: Vec::new(),
+            resulting_accounts: accounts.to_vec(),
+            instruction_trace_length: 0,
+            max_stack_height: 0,
+            accounts_resize_delta: 0,
+            failing_program_id: None,
+            precompile_error: None,
+            declared_writable: Vec::new(),
+            actually_modified: Vec::new(),
+            referenced_accounts: Vec::new(),
+            profile: ExecutionProfile::default(),
+            execution_mode: Some(self.execution_mode()),
+            logs: Vec::new(),
+            per_instruction: Vec::new(),
+            #[cfg(feature = "inner-instructions")]
+            inner_instructions: Vec::new(),
+            message: None,
+        }
+    }
+
+    /// Process a transaction. The fee payer defaults to the one designated
+    /// by `fee_model`, if any.
+    fn process_transaction_instructions_inner(
+        &self,
+        instructions: &[Instruction],
+        accounts: &[(Pubkey, Account)],
+        address_lookup_tables: Option<&[AddressLookupTableAccount]>,
+        fee_payer: Option<&Pubkey>,
+    ) -> TransactionResult {
+        self.validate_interfaces(instructions);
+
+        let (compute_budget, compute_unit_price) =
+            match apply_compute_budget_instructions(&self.compute_budget, instructions) {
+                Ok(requested) => requested,
+                Err(err) => return self.unprocessed_transaction_result(err, accounts),
+            };
+
         let fallback_accounts = self.get_account_fallbacks(
             instructions.iter().map(|ix| &ix.program_id),
             instructions.iter(),
             accounts,
         );
 
-        let (sanitized_message, transaction_accounts) = crate::compile_accounts::compile_accounts(
-            instructions,
-            accounts.iter(),
-            &fallback_accounts,
-        );
+        let fee_payer = fee_payer.or_else(|| {
+            self.fee_model
+                .as_ref()
+                .and_then(|fee_model| fee_model.fee_payer.as_ref())
+        });
 
-        let mut transaction_context = self.create_transaction_context(transaction_accounts);
+        let (sanitized_message, mut transaction_accounts) = match address_lookup_tables {
+            Some(address_lookup_tables) => {
+                crate::compile_accounts::compile_accounts_with_lookup_tables(
+                    instructions,
+                    accounts.iter(),
+                    &fallback_accounts,
+                    address_lookup_tables,
+                    fee_payer,
+                    &self.recent_blockhash,
+                )
+            }
+            None => crate::compile_accounts::compile_accounts(
+                instructions,
+                accounts.iter(),
+                &fallback_accounts,
+                fee_payer,
+                &self.recent_blockhash,
+            ),
+        };
+
+        if let Some(limits) = &self.enforce_tx_limits {
+            let requested_limit =
+                crate::compute_budget_instructions::loaded_accounts_data_size_limit(instructions);
+            if let Err(err) =
+                limits.check(&sanitized_message, &transaction_accounts, requested_limit)
+            {
+                return self.unprocessed_transaction_result(err, accounts);
+            }
+        }
+
+        // Like a validator, check the nonce before charging the fee, and
+        // advance it even if execution fails.
+        let advanced_nonce = if self.durable_nonce {
+            match crate::nonce::check_durable_nonce(
+                &sanitized_message,
+                &transaction_accounts,
+                &self.blockhash,
+                self.nonce_lamports_per_signature(),
+            ) {
+                Ok(advanced_nonce) => advanced_nonce,
+                Err(err) => return self.unprocessed_transaction_result(err, accounts),
+            }
+        } else {
+            None
+        };
+
+        // Like a validator, charge the fee before execution, and keep it even
+        // if execution fails.
+        let fee = match &self.fee_model {
+            Some(fee_model) => {
+                if sanitized_message.header().num_required_signatures == 0 {
+                    panic!("{}", MolluskError::FeePayerMissing);
+                }
+                let fee = fee_model.calculate_fee(
+                    &sanitized_message,
+                    instructions,
+                    &compute_budget,
+                    compute_unit_price,
+                );
+                if let Err(err) =
+                    crate::fees::charge_fee(fee, &self.sysvars.rent, &mut transaction_accounts)
+                {
+                    return self.unprocessed_transaction_result(err, accounts);
+                }
+                fee
+            }
+            None => 0,
+        };
+
+        // The accounts of a transaction which failed after the fee was
+        // charged, which keep the fee and the advanced nonce.
+        let fee_only_accounts = || {
+            let fee_payer = sanitized_message.fee_payer();
+            accounts
+                .iter()
+                .map(|(pubkey, account)| {
+                    let mut account = account.clone();
+                    if pubkey == fee_payer {
+                        account.lamports -= fee;
+                    }
+                    if let Some((nonce_address, nonce_account)) = &advanced_nonce {
+                        if pubkey == nonce_address {
+                            account.data = nonce_account.data.clone();
+                        }
+                    }
+                    (*pubkey, account)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Like a validator, fail to load a transaction whose program is
+        // missing only once the fee is charged.
+        if let Some(index) = self.find_missing_program(instructions, accounts) {
+            return TransactionResult {
+                resulting_accounts: fee_only_accounts(),
+                failing_program_id: Some(instructions[index].program_id),
+                ..self.unprocessed_transaction_result(
+                    TransactionError::ProgramAccountNotFound,
+                    accounts,
+                )
+            };
+        }
+
+        let mut transaction_context = self.create_transaction_context(
+            &sanitized_message,
+            transaction_accounts,
+            &compute_budget,
+        );
         let sysvar_cache = self.sysvars.setup_sysvar_cache(accounts);
 
         let message_result = self.process_transaction_message(
             &sanitized_message,
             &mut transaction_context,
             &sysvar_cache,
+            &compute_budget,
         );
 
         let resulting_accounts = if message_result.raw_result.is_ok() {
             Self::deconstruct_resulting_accounts(&transaction_context, accounts)
         } else {
-            accounts.to_vec()
+            fee_only_accounts()
         };
+        let actually_modified = Self::modified_account_keys(accounts, &resulting_accounts);
+        self.sync_modified_programs(&message_result, &resulting_accounts, &actually_modified);
 
         let program_result = MessageResult::extract_txn_program_result(&message_result.raw_result);
 
@@ -1358,10 +2658,22 @@ impl Mollusk {
             program_result,
             raw_result: message_result.raw_result,
             return_data: message_result.return_data,
+            inner_return_data: message_result.inner_return_data,
             resulting_accounts,
+            instruction_trace_length: message_result.instruction_trace_length,
+            max_stack_height: message_result.max_stack_height,
+            accounts_resize_delta: message_result.accounts_resize_delta,
+            failing_program_id: message_result.failing_program_id,
+            precompile_error: message_result.precompile_error,
+            declared_writable: message_result.declared_writable,
+            actually_modified,
+            referenced_accounts: message_result.referenced_accounts,
+            profile: message_result.profile,
+            execution_mode: Some(self.execution_mode()),
+            logs: message_result.logs,
+            per_instruction: message_result.per_instruction,
             #[cfg(feature = "inner-instructions")]
             inner_instructions: message_result.inner_instructions,
-            #[cfg(feature = "inner-instructions")]
             message: message_result.message,
         }
     }
@@ -1395,7 +2707,79 @@ impl Mollusk {
         checks: &[Check],
     ) -> InstructionResult {
         let result = self.process_instruction(instruction, accounts);
-        result.run_checks(checks, &self.config, self);
+        let context = self.input_accounts_context(accounts);
+        self.run_checks_with_diffs(
+            |config| result.run_checks(checks, config, &context),
+            || result.diff_accounts(accounts),
+        );
+        result
+    }
+
+    /// Process an instruction with account data direct mapping disabled,
+    /// then enabled, and compare the two results. Returns both results, in
+    /// that order.
+    ///
+    /// Under direct mapping, account data is mapped into the VM directly, so
+    /// invalid writes fault during execution rather than being caught after
+    /// it. Use this to certify that a program behaves the same either way,
+    /// ahead of the feature's activation. Mismatches are reported according
+    /// to the harness's config.
+    ///
+    /// The feature set and program cache are restored afterwards, but
+    /// programs are reloaded, so this is relatively expensive.
+    pub fn process_and_compare_direct_mapping(
+        &mut self,
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+        checks: &[Compare],
+    ) -> (InstructionResult, InstructionResult) {
+        let original_feature_set = self.feature_set.clone();
+        let [without, with] = [false, true].map(|enabled| {
+            for feature_id in [
+                trezoa_feature_set::stricter_abi_and_runtime_constraints::id(),
+                trezoa_feature_set::account_data_direct_mapping::id(),
+            ] {
+                if enabled {
+                    self.feature_set.activate(&feature_id, 0);
+                } else {
+                    self.feature_set.deactivate(&feature_id);
+                }
+            }
+            self.program_cache
+                .set_feature_set(&self.feature_set, &self.compute_budget);
+            self.process_instruction(instruction, accounts)
+        });
+        self.feature_set = original_feature_set;
+        self.program_cache
+            .set_feature_set(&self.feature_set, &self.compute_budget);
+
+        without.compare_with_config(&with, checks, &self.config);
+        (without, with)
+    }
+
+    /// Process an instruction, then compare the result against the snapshot
+    /// file at `path`. Panics if the result doesn't match.
+    ///
+    /// Unlike `Check::matches_snapshot`, a missing snapshot is created rather
+    /// than failing. This is what `mollusk_snapshot!` uses under the hood.
+    #[cfg(feature = "fs")]
+    pub fn process_and_validate_snapshot(
+        &self,
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+        path: &Path,
+    ) -> InstructionResult {
+        if path.exists() {
+            return self.process_and_validate_instruction(
+                instruction,
+                accounts,
+                &[Check::matches_snapshot(path)],
+            );
+        }
+        let result = self.process_instruction(instruction, accounts);
+        result
+            .save_snapshot(path)
+            .unwrap_or_else(|err| panic!("Failed to write snapshot {}: {}", path.display(), err));
         result
     }
 
@@ -1429,43 +2813,100 @@ impl Mollusk {
     /// transaction-level restrictions and treating each instruction in the
     /// chain as its own standalone invocation. However, account changes are
     /// persisted between invocations.
+    ///
+    /// The chain stops at the first failing instruction, unless that
+    /// instruction's checks include `Check::expect_failure_and_continue`.
     pub fn process_and_validate_instruction_chain(
         &self,
         instructions: &[(&Instruction, &[Check])],
         accounts: &[(Pubkey, Account)],
     ) -> InstructionResult {
+        let elements = instructions
+            .iter()
+            .map(|(instruction, checks)| ChainElement::new(instruction, checks))
+            .collect::<Vec<_>>();
+        self.process_and_validate_chain_elements(&elements, accounts)
+    }
+
+    /// Process a chain of instructions, then perform checks on each result,
+    /// like `process_and_validate_instruction_chain`.
+    ///
+    /// Each element may override the compute budget its instruction is
+    /// processed with. See the `chain` module.
+    pub fn process_and_validate_chain_elements(
+        &self,
+        elements: &[ChainElement],
+        accounts: &[(Pubkey, Account)],
+    ) -> InstructionResult {
+        self.process_and_validate_chain_elements_inner(elements, accounts)
+            .0
+    }
+
+    // Process and validate a chain, also returning whether it ran to
+    // completion, rather than stopping at an unexpected failure.
+    fn process_and_validate_chain_elements_inner(
+        &self,
+        elements: &[ChainElement],
+        accounts: &[(Pubkey, Account)],
+    ) -> (InstructionResult, bool) {
+        self.validate_interfaces(elements.iter().map(|element| element.instruction));
+
         let mut composite_result = InstructionResult {
             resulting_accounts: accounts.to_vec(),
             ..Default::default()
         };
 
         let fallback_accounts = self.get_account_fallbacks(
-            instructions.iter().map(|(ix, _)| &ix.program_id),
-            instructions.iter().map(|(ix, _)| *ix),
+            elements
+                .iter()
+                .map(|element| &element.instruction.program_id),
+            elements.iter().map(|element| element.instruction),
             accounts,
         );
 
         let sysvar_cache = self.sysvars.setup_sysvar_cache(accounts);
 
-        for (index, (instruction, checks)) in instructions.iter().enumerate() {
+        for (index, element) in elements.iter().enumerate() {
+            let advanced = self.advance_chain_element(
+                index,
+                &mut composite_result.resulting_accounts,
+                &fallback_accounts,
+            );
+            let (element_sysvar_cache, element_fallback_accounts) = match &advanced {
+                Some((sysvar_cache, fallback_accounts)) => (sysvar_cache, fallback_accounts),
+                None => (&sysvar_cache, &fallback_accounts),
+            };
             let this_result = self.process_instruction_chain_element(
                 index,
-                instruction,
+                element.instruction,
                 &composite_result.resulting_accounts,
-                &fallback_accounts,
-                &sysvar_cache,
+                element_fallback_accounts,
+                element_sysvar_cache,
+                element
+                    .compute_budget
+                    .as_ref()
+                    .unwrap_or(&self.compute_budget),
             );
 
-            this_result.run_checks(checks, &self.config, self);
+            let context = self.input_accounts_context(&composite_result.resulting_accounts);
+            self.run_checks_with_diffs(
+                |config| this_result.run_checks(element.checks, config, &context),
+                || this_result.diff_accounts(&composite_result.resulting_accounts),
+            );
 
             composite_result.absorb(this_result);
 
-            if composite_result.program_result.is_err() {
-                break;
+            if composite_result.program_result.is_err()
+                && !element
+                    .checks
+                    .iter()
+                    .any(Check::is_expect_failure_and_continue)
+            {
+                return (composite_result, false);
             }
         }
 
-        composite_result
+        (composite_result, true)
     }
 
     /// Process multiple instructions using a single shared transaction context,
@@ -1485,6 +2926,8 @@ impl Mollusk {
     /// * `program_result`: The result code of the last program's execution and
     ///   its index.
     /// * `resulting_accounts`: The resulting accounts after all instructions.
+    /// * `per_instruction`: The status, compute units, return data, and logs of
+    ///   each top-level instruction processed.
     pub fn process_and_validate_transaction_instructions(
         &self,
         instructions: &[Instruction],
@@ -1492,7 +2935,36 @@ impl Mollusk {
         checks: &[Check],
     ) -> TransactionResult {
         let result = self.process_transaction_instructions(instructions, accounts);
-        result.run_checks(checks, &self.config, self);
+        let context = self.input_accounts_context(accounts);
+        self.run_checks_with_diffs(
+            |config| result.run_checks(checks, config, &context),
+            || result.diff_accounts(accounts),
+        );
+        result
+    }
+
+    /// Process multiple instructions as a v0 transaction, using the provided
+    /// address lookup tables, then perform checks on the result. Panics if
+    /// any checks fail.
+    ///
+    /// See `process_transaction_instructions_with_lookup_tables`.
+    pub fn process_and_validate_transaction_instructions_with_lookup_tables(
+        &self,
+        instructions: &[Instruction],
+        accounts: &[(Pubkey, Account)],
+        address_lookup_tables: &[AddressLookupTableAccount],
+        checks: &[Check],
+    ) -> TransactionResult {
+        let result = self.process_transaction_instructions_with_lookup_tables(
+            instructions,
+            accounts,
+            address_lookup_tables,
+        );
+        let context = self.input_accounts_context(accounts);
+        self.run_checks_with_diffs(
+            |config| result.run_checks(checks, config, &context),
+            || result.diff_accounts(accounts),
+        );
         result
     }
 
@@ -1598,6 +3070,64 @@ impl Mollusk {
         result
     }
 
+    #[cfg(feature = "fuzz")]
+    /// Process a batch of fuzz fixtures, comparing each result against the
+    /// fixture's effects, and return the aggregate outcome.
+    ///
+    /// Fixtures are grouped by their context - compute budget, feature set
+    /// and sysvars - so the harness is configured once per group rather than
+    /// once per fixture. Mismatches are reported according to `config`, but
+    /// never panic, so every fixture is processed.
+    ///
+    /// Note: Like `process_fixture`, this leaves the harness configured with
+    /// the context of the last group processed.
+    pub fn process_fixture_batch(
+        &mut self,
+        fixtures: impl IntoIterator<Item = mollusk_svm_fuzz_fixture::Fixture>,
+    ) -> fuzz::mollusk::FixtureBatchReport {
+        let groups = fuzz::mollusk::group_fixtures_by_context(fixtures);
+        let mut report = fuzz::mollusk::FixtureBatchReport {
+            contexts: groups.len(),
+            ..Default::default()
+        };
+
+        let panic = std::mem::replace(&mut self.config.panic, false);
+        for group in groups {
+            let fuzz::mollusk::ParsedFixtureContext {
+                compute_budget,
+                feature_set,
+                sysvars,
+                ..
+            } = fuzz::mollusk::parse_fixture_context(&group[0].1.input);
+            self.compute_budget = compute_budget;
+            self.feature_set = feature_set;
+            self.sysvars = sysvars;
+
+            for (index, fixture) in group {
+                let input = &fixture.input;
+                let instruction = Instruction::new_with_bytes(
+                    input.program_id,
+                    &input.instruction_data,
+                    input.instruction_accounts.clone(),
+                );
+                let result = self.process_instruction(&instruction, &input.accounts);
+                report.total += 1;
+                if InstructionResult::from(&fixture.output).compare_with_config(
+                    &result,
+                    &Compare::everything(),
+                    &self.config,
+                ) {
+                    report.passed += 1;
+                } else {
+                    report.failed.push(index);
+                }
+            }
+        }
+        self.config.panic = panic;
+
+        report
+    }
+
     #[cfg(feature = "fuzz-fd")]
     /// Process a Firedancer fuzz fixture using the minified Trezoa Virtual
     /// Machine (SVM) environment.
@@ -1726,6 +3256,109 @@ impl Mollusk {
         result
     }
 
+    #[cfg(feature = "fuzz-fd")]
+    fn process_firedancer_txn_context(
+        &mut self,
+        context: fuzz::firedancer::ParsedTxnFixtureContext,
+    ) -> TransactionResult {
+        let fuzz::firedancer::ParsedTxnFixtureContext {
+            accounts,
+            address_lookup_tables,
+            compute_budget,
+            fee_payer,
+            feature_set,
+            instructions,
+            slot,
+        } = context;
+        self.compute_budget = compute_budget;
+        self.feature_set = feature_set;
+        self.slot = slot;
+
+        // The fees recorded in the fixture are refunded to the expected
+        // balance of the fee payer instead, so none are charged here.
+        let fee_model = self.fee_model.take();
+        let address_lookup_tables =
+            (!address_lookup_tables.is_empty()).then_some(address_lookup_tables.as_slice());
+        let result = self.process_transaction_instructions_inner(
+            &instructions,
+            &accounts,
+            address_lookup_tables,
+            fee_payer.as_ref(),
+        );
+        self.fee_model = fee_model;
+        result
+    }
+
+    #[cfg(feature = "fuzz-fd")]
+    /// Process a Firedancer transaction fuzz fixture
+    /// (`sol_compat_txn_execute_v1`) using the minified Trezoa Virtual
+    /// Machine (SVM) environment.
+    ///
+    /// The fixture's compiled message is decompiled into instructions, which
+    /// are processed with `process_transaction_instructions`, resolving any
+    /// address table lookups through the lookup table accounts provided in
+    /// the fixture.
+    ///
+    /// Note: Transaction fees aren't charged, even if `fee_model` is set, and
+    /// the fixture's first account key is used as the fee payer. Fixtures
+    /// for transactions which fail before execution, such as sanitization
+    /// failures, are not supported.
+    ///
+    /// Note: This is a mutable method on `Mollusk`, since loading a fixture
+    /// into the test environment will alter `Mollusk` values, such as compute
+    /// budget and sysvars. However, the program cache remains unchanged.
+    pub fn process_firedancer_txn_fixture(
+        &mut self,
+        fixture: &mollusk_svm_fuzz_fixture_firedancer::TxnFixture,
+    ) -> TransactionResult {
+        let context = fuzz::firedancer::parse_txn_fixture_context(&fixture.input);
+        self.process_firedancer_txn_context(context)
+    }
+
+    #[cfg(feature = "fuzz-fd")]
+    /// Process a Firedancer transaction fuzz fixture using the minified Trezoa
+    /// Virtual Machine (SVM) environment and compare the result against the
+    /// fixture's effects.
+    ///
+    /// Since transaction fees aren't charged when processing the fixture, the
+    /// fees recorded in the fixture are refunded to the fee payer's expected
+    /// balance before comparing.
+    ///
+    /// See `process_firedancer_txn_fixture` for more details.
+    pub fn process_and_validate_firedancer_txn_fixture(
+        &mut self,
+        fixture: &mollusk_svm_fuzz_fixture_firedancer::TxnFixture,
+    ) -> TransactionResult {
+        self.process_and_partially_validate_firedancer_txn_fixture(fixture, &Compare::everything())
+    }
+
+    #[cfg(feature = "fuzz-fd")]
+    /// Process a Firedancer transaction fuzz fixture using the minified Trezoa
+    /// Virtual Machine (SVM) environment and compare the result against the
+    /// fixture's effects using a specific set of checks.
+    ///
+    /// This is useful for when you may not want to compare the entire effects,
+    /// such as omitting comparisons of compute units consumed.
+    ///
+    /// See `process_firedancer_txn_fixture` for more details.
+    pub fn process_and_partially_validate_firedancer_txn_fixture(
+        &mut self,
+        fixture: &mollusk_svm_fuzz_fixture_firedancer::TxnFixture,
+        checks: &[Compare],
+    ) -> TransactionResult {
+        let context = fuzz::firedancer::parse_txn_fixture_context(&fixture.input);
+        let expected = fuzz::firedancer::parse_txn_fixture_effects(
+            &fixture.input,
+            &context.accounts,
+            &fixture.output,
+        );
+
+        let result = self.process_firedancer_txn_context(context);
+
+        result.compare_with_config(&expected, checks, &self.config);
+        result
+    }
+
     /// Convert this `Mollusk` instance into a `MolluskContext` for stateful
     /// testing.
     ///
@@ -1748,6 +3381,8 @@ impl Mollusk {
             mollusk: self,
             account_store: Rc::new(RefCell::new(account_store)),
             hydrate_store: true, // <-- Default
+            hydrate_referenced_only: false,
+            referenced_accounts: RefCell::new(HashSet::new()),
         }
     }
 }
@@ -1767,12 +3402,19 @@ impl Mollusk {
 ///
 /// The API is functionally identical to `Mollusk` but with enhanced state
 /// management and a streamlined interface. Namely, the input `accounts` slice
-/// is no longer required, and the returned result does not contain a
-/// `resulting_accounts` field.
+/// is no longer required. The returned result still contains the
+/// `resulting_accounts`, which are also written to the account store.
 pub struct MolluskContext<AS: AccountStore> {
     pub mollusk: Mollusk,
     pub account_store: Rc<RefCell<AS>>,
     pub hydrate_store: bool,
+    /// When hydrating the store, skip program and sysvar accounts which
+    /// weren't referenced by any instruction processed so far, unless the
+    /// instructions being processed reference them. Defaults to `false`.
+    pub hydrate_referenced_only: bool,
+    /// The accounts referenced by every instruction processed so far. See
+    /// `InstructionResult::referenced_accounts`.
+    pub referenced_accounts: RefCell<HashSet<Pubkey>>,
 }
 
 impl<AS: AccountStore> MolluskContext<AS> {
@@ -1781,25 +3423,52 @@ impl<AS: AccountStore> MolluskContext<AS> {
         instructions: impl Iterator<Item = &'a Instruction>,
     ) -> Vec<(Pubkey, Account)> {
         let mut accounts = Vec::new();
+        let instructions = instructions.collect::<Vec<_>>();
 
         // If hydration is enabled, add sysvars and program accounts regardless
         // of whether or not they exist already.
         if self.hydrate_store {
+            let referenced_accounts = self.referenced_accounts.borrow();
+            let is_referenced = |pubkey: &Pubkey| {
+                instructions.iter().any(|ix| {
+                    ix.program_id == *pubkey
+                        || ix.accounts.iter().any(|meta| meta.pubkey == *pubkey)
+                })
+            };
             self.mollusk
                 .program_cache
                 .get_all_keyed_program_accounts()
                 .into_iter()
                 .chain(self.mollusk.sysvars.get_all_keyed_sysvar_accounts())
+                .filter(|(pubkey, _)| {
+                    !self.hydrate_referenced_only
+                        || referenced_accounts.contains(pubkey)
+                        || is_referenced(pubkey)
+                })
                 .for_each(|(pubkey, account)| {
                     accounts.push((pubkey, account));
                 });
         }
 
+        // Merge every reference to each account, in case it's missing from
+        // the store and the store builds a default from the hint.
+        let mut hints: HashMap<Pubkey, MissingAccountHint> = HashMap::new();
+        for meta in instructions.iter().flat_map(|ix| ix.accounts.iter()) {
+            let hint = hints.entry(meta.pubkey).or_default();
+            hint.is_signer |= meta.is_signer;
+            hint.is_writable |= meta.is_writable;
+        }
+        for instruction in &instructions {
+            if let Some(hint) = hints.get_mut(&instruction.program_id) {
+                hint.is_program_id = true;
+            }
+        }
+
         // Regardless of hydration, only add an account if the caller hasn't
         // already loaded it into the store.
         let mut seen = HashSet::new();
         let store = self.account_store.borrow();
-        instructions.for_each(|instruction| {
+        instructions.iter().for_each(|instruction| {
             instruction
                 .accounts
                 .iter()
@@ -1816,7 +3485,9 @@ impl<AS: AccountStore> MolluskContext<AS> {
                                     self.mollusk
                                         .program_cache
                                         .maybe_create_program_account(pubkey)
-                                        .unwrap_or_else(|| store.default_account(pubkey))
+                                        .unwrap_or_else(|| {
+                                            store.default_account_for(pubkey, hints[pubkey])
+                                        })
                                 })
                         });
                         accounts.push((*pubkey, account));
@@ -1827,13 +3498,115 @@ impl<AS: AccountStore> MolluskContext<AS> {
     }
 
     fn consume_mollusk_result(&self, result: &InstructionResult) {
-        if result.program_result.is_ok() {
-            // Only store resulting accounts if the result was success.
-            let mut store = self.account_store.borrow_mut();
-            for (pubkey, account) in result.resulting_accounts.iter() {
-                store.store_account(*pubkey, account.clone());
-            }
+        // Only store resulting accounts if the result was success.
+        self.consume_result(result, result.program_result.is_ok());
+    }
+
+    // A validated chain whose failures were all expected ran to completion,
+    // even if its last element failed. Failed elements leave the accounts
+    // unchanged, so the resulting accounts are stored either way.
+    fn consume_chain_result(
+        &self,
+        (result, completed): (InstructionResult, bool),
+    ) -> InstructionResult {
+        self.consume_result(&result, completed);
+        result
+    }
+
+    fn consume_result(&self, result: &InstructionResult, store_accounts: bool) {
+        self.referenced_accounts
+            .borrow_mut()
+            .extend(result.referenced_accounts.iter().copied());
+        if store_accounts {
+            self.account_store
+                .borrow_mut()
+                .store_accounts(result.resulting_accounts.iter().cloned());
+        }
+    }
+
+    /// Get a snapshot of all accounts in the store owned by the provided
+    /// program, sorted by address.
+    ///
+    /// Returns `IterationUnsupported` unless the store implements
+    /// `AccountStore::for_each_account`.
+    pub fn store_view(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<Vec<(Pubkey, Account)>, IterationUnsupported> {
+        let mut accounts = Vec::new();
+        let supported = self
+            .account_store
+            .borrow()
+            .for_each_account(&mut |pubkey, account| {
+                if account.owner == *owner {
+                    accounts.push((*pubkey, account.clone()));
+                }
+            });
+        if !supported {
+            return Err(IterationUnsupported);
         }
+        accounts.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(accounts)
+    }
+
+    /// Store a vote account for every entry in the configured `EpochStake`.
+    ///
+    /// See [`Mollusk::epoch_stake_vote_accounts`].
+    pub fn store_epoch_stake_vote_accounts(&self) {
+        let mut store = self.account_store.borrow_mut();
+        for (pubkey, account) in self.mollusk.epoch_stake_vote_accounts() {
+            store.store_account(pubkey, account);
+        }
+    }
+
+    /// Credit lamports to an account in the store, without processing an
+    /// instruction.
+    ///
+    /// If the account doesn't exist yet, it's created as an empty account
+    /// owned by the System program, like a wallet receiving an airdrop.
+    pub fn airdrop(&self, pubkey: &Pubkey, lamports: u64) {
+        let mut store = self.account_store.borrow_mut();
+        let mut account = store
+            .get_account(pubkey)
+            .unwrap_or_else(|| Account::new(0, 0, &trezoa_sdk_ids::system_program::id()));
+        account.lamports = account.lamports.saturating_add(lamports);
+        store.store_account(*pubkey, account);
+    }
+
+    /// Store a new account with zeroed data of the provided size, without
+    /// processing an instruction, replacing any existing account.
+    ///
+    /// The account is given exactly `lamports`, so it must cover the rent
+    /// exemption minimum for `space` if the program expects that. See
+    /// `Mollusk::sysvars.rent.minimum_balance`.
+    pub fn create_system_account(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+        space: usize,
+        owner: &Pubkey,
+    ) {
+        self.account_store
+            .borrow_mut()
+            .store_account(*pubkey, Account::new(lamports, space, owner));
+    }
+
+    /// Count the accounts in the store, grouped by owner.
+    ///
+    /// Returns `IterationUnsupported` unless the store implements
+    /// `AccountStore::for_each_account`.
+    pub fn count_by_owner(&self) -> Result<HashMap<Pubkey, usize>, IterationUnsupported> {
+        let mut counts = HashMap::new();
+        let supported = self
+            .account_store
+            .borrow()
+            .for_each_account(&mut |_, account| {
+                *counts.entry(account.owner).or_insert(0) += 1;
+            });
+        if !supported {
+            return Err(IterationUnsupported);
+        }
+        Ok(counts)
     }
 
     /// Process an instruction using the minified Trezoa Virtual Machine (SVM)
@@ -1877,13 +3650,24 @@ impl<AS: AccountStore> MolluskContext<AS> {
         &self,
         instructions: &[(&Instruction, &[Check])],
     ) -> InstructionResult {
-        let accounts = self.load_accounts_for_instructions(
-            instructions.iter().map(|(instruction, _)| *instruction),
-        );
-        let result = self
-            .mollusk
-            .process_and_validate_instruction_chain(instructions, &accounts);
-        self.consume_mollusk_result(&result);
-        result
+        let elements = instructions
+            .iter()
+            .map(|(instruction, checks)| ChainElement::new(instruction, checks))
+            .collect::<Vec<_>>();
+        self.process_and_validate_chain_elements(&elements)
+    }
+
+    /// Process a chain of instructions, each with its own checks and optional
+    /// compute budget override, then perform checks on the result.
+    pub fn process_and_validate_chain_elements(
+        &self,
+        elements: &[ChainElement],
+    ) -> InstructionResult {
+        let accounts =
+            self.load_accounts_for_instructions(elements.iter().map(|element| element.instruction));
+        self.consume_chain_result(
+            self.mollusk
+                .process_and_validate_chain_elements_inner(elements, &accounts),
+        )
     }
 }
@@ -0,0 +1,158 @@
+//! Opt-in durable nonce transaction handling.
+//!
+//! The System program already implements the durable nonce instructions,
+//! but a validator also treats a transaction beginning with
+//! `AdvanceNonceAccount` specially: the nonce account is validated before
+//! execution, and it's advanced even if execution fails. Set
+//! `Mollusk::durable_nonce` to have `process_transaction_instructions` do
+//! the same.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{
+//!     nonce::{create_nonce_account, nonce_data},
+//!     Mollusk,
+//! };
+//!
+//! let mut mollusk = Mollusk::default();
+//! mollusk.durable_nonce = true;
+//!
+//! // The nonce must have been stored under a previous blockhash to be
+//! // advanced, and the transaction must use it as its recent blockhash.
+//! let nonce_account = create_nonce_account(&mollusk, &authority, &previous_blockhash);
+//! mollusk.recent_blockhash = *nonce_data(&nonce_account).unwrap().blockhash();
+//!
+//! let instructions = [
+//!     advance_nonce_account(&nonce, &authority),
+//!     /* ... */
+//! ];
+//! let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+//! ```
+//!
+//! A transaction is only treated as a durable nonce transaction if its first
+//! instruction is `AdvanceNonceAccount`. Such a transaction fails with
+//! `TransactionError::BlockhashNotFound`, without executing, if:
+//!
+//! * The nonce account isn't an initialized nonce account owned by the System
+//!   program.
+//! * The transaction's recent blockhash, `Mollusk::recent_blockhash`, isn't the
+//!   stored nonce.
+//! * The nonce was already advanced under `Mollusk::blockhash`.
+//! * The nonce authority didn't sign the transaction.
+//!
+//! The nonce is advanced with the same lamports per signature as the fee
+//! charged: `FeeModel::lamports_per_signature` if a fee model is set, and
+//! `Mollusk::lamports_per_signature` otherwise.
+//!
+//! The error is surfaced as `TransactionProgramResult::TransactionError`, and
+//! can be asserted on with `Check::transaction_err`.
+
+use {
+    crate::Mollusk,
+    trezoa_account::{Account, AccountSharedData, ReadableAccount},
+    trezoa_hash::Hash,
+    trezoa_message::SanitizedMessage,
+    trezoa_nonce::{
+        state::{Data, DurableNonce, State},
+        versions::Versions,
+    },
+    trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
+};
+
+/// The discriminator of `SystemInstruction::AdvanceNonceAccount`.
+const ADVANCE_NONCE_ACCOUNT: [u8; 4] = 4u32.to_le_bytes();
+
+/// Create an initialized nonce account, storing the durable nonce derived
+/// from the provided blockhash.
+///
+/// The account is rent-exempt under the harness's `Rent` sysvar, and records
+/// the lamports per signature the harness advances nonces with.
+pub fn create_nonce_account(mollusk: &Mollusk, authority: &Pubkey, blockhash: &Hash) -> Account {
+    let state = State::Initialized(Data::new(
+        *authority,
+        DurableNonce::from_blockhash(blockhash),
+        mollusk.nonce_lamports_per_signature(),
+    ));
+    let data = bincode::serialize(&Versions::new(state)).unwrap();
+    Account {
+        lamports: mollusk.sysvars.rent.minimum_balance(data.len()),
+        data,
+        owner: trezoa_sdk_ids::system_program::id(),
+        ..Default::default()
+    }
+}
+
+/// Read the state of an initialized nonce account, if it is one.
+pub fn nonce_data(account: &impl ReadableAccount) -> Option<Data> {
+    if *account.owner() != trezoa_sdk_ids::system_program::id() {
+        return None;
+    }
+    match bincode::deserialize(account.data()).ok()? {
+        Versions::Current(state) => match *state {
+            State::Initialized(data) => Some(data),
+            State::Uninitialized => None,
+        },
+        // Legacy nonces can't be used for durable transactions.
+        Versions::Legacy(_) => None,
+    }
+}
+
+/// Validate a durable nonce transaction, like a validator does before
+/// executing it.
+///
+/// Returns `None` if the message isn't a durable nonce transaction.
+/// Otherwise, returns the nonce account as it's stored if execution fails:
+/// advanced to the durable nonce derived from `blockhash`.
+pub(crate) fn check_durable_nonce(
+    message: &SanitizedMessage,
+    transaction_accounts: &[(Pubkey, AccountSharedData)],
+    blockhash: &Hash,
+    lamports_per_signature: u64,
+) -> Result<Option<(Pubkey, Account)>, TransactionError> {
+    let Some(nonce_index) = message
+        .program_instructions_iter()
+        .next()
+        .filter(|(program_id, ix)| {
+            **program_id == trezoa_sdk_ids::system_program::id()
+                && ix.data.starts_with(&ADVANCE_NONCE_ACCOUNT)
+        })
+        .and_then(|(_, ix)| ix.accounts.first())
+        .map(|index| usize::from(*index))
+        .filter(|index| message.is_writable(*index))
+    else {
+        return Ok(None);
+    };
+
+    let (nonce_address, nonce_account) = &transaction_accounts[nonce_index];
+    let data = nonce_data(nonce_account).ok_or(TransactionError::BlockhashNotFound)?;
+
+    // The transaction is signed over the stored nonce, in place of a recent
+    // blockhash.
+    if message.recent_blockhash() != data.blockhash() {
+        return Err(TransactionError::BlockhashNotFound);
+    }
+
+    // The nonce can only be advanced once per blockhash.
+    let next_durable_nonce = DurableNonce::from_blockhash(blockhash);
+    if data.durable_nonce == next_durable_nonce {
+        return Err(TransactionError::BlockhashNotFound);
+    }
+
+    let authority_signed = message
+        .account_keys()
+        .iter()
+        .enumerate()
+        .any(|(index, key)| *key == data.authority && message.is_signer(index));
+    if !authority_signed {
+        return Err(TransactionError::BlockhashNotFound);
+    }
+
+    let advanced = State::Initialized(Data::new(
+        data.authority,
+        next_durable_nonce,
+        lamports_per_signature,
+    ));
+    let mut account = Account::from(nonce_account.clone());
+    account.data = bincode::serialize(&Versions::new(advanced)).unwrap();
+    Ok(Some((*nonce_address, account)))
+}
@@ -0,0 +1,249 @@
+//! Signature fixture generators for the Ed25519 and Secp256r1 precompiles.
+//!
+//! Each generator signs a message with a key drawn from a `KeyGen` and
+//! builds the precompile instruction verifying it, either valid or corrupted
+//! in a way the precompile should reject. Tests and fuzz corpora covering
+//! signature verification paths can use them without depending on the
+//! signing libraries themselves. The same seed always yields the same
+//! instructions, so fixtures can be ejected and hashed.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{precompile::{self, Corruption}, testgen::KeyGen};
+//!
+//! let mut keys = KeyGen::seeded(42);
+//! let fixture =
+//!     precompile::ed25519_with_corruption(&mut keys, b"hello", Corruption::BadSignature);
+//! mollusk.process_and_validate_instruction(
+//!     &fixture.instruction,
+//!     &[fixture.keyed_account()],
+//!     &[fixture.check()],
+//! );
+//! ```
+//!
+//! Both precompiles lay out their instruction data the same way: the
+//! signature count and padding, the offsets of each signature, then the
+//! public key, the signature and the message.
+
+use {
+    crate::{program::create_keyed_account_for_builtin_program, result::Check, testgen::KeyGen},
+    openssl::{
+        bn::{BigNum, BigNumContext, BigNumRef},
+        ec::{EcGroup, EcPoint, PointConversionForm},
+        nid::Nid,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::Instruction,
+    trezoa_precompile_error::PrecompileError,
+    trezoa_pubkey::Pubkey,
+    std::cmp::Ordering,
+};
+
+/// The offset of the first signature's offsets in the instruction data.
+const SIGNATURE_OFFSETS_START: usize = 2;
+/// The offset of the public key, when a single signature is verified.
+const DATA_START: usize = 16;
+
+/// A way to corrupt a precompile instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corruption {
+    /// Flip a bit of the signature, so it doesn't verify.
+    BadSignature,
+    /// Truncate the instruction data in the middle of the public key, so
+    /// the data its offsets point at is out of bounds.
+    TruncatedPubkey,
+    /// Point the signature offset past the end of the instruction data.
+    WrongOffsets,
+}
+
+impl Corruption {
+    /// Every corruption, for iterating over negative cases.
+    pub const ALL: [Corruption; 3] = [
+        Corruption::BadSignature,
+        Corruption::TruncatedPubkey,
+        Corruption::WrongOffsets,
+    ];
+
+    /// The error the precompile returns for an instruction with this
+    /// corruption.
+    pub fn expected_error(&self) -> PrecompileError {
+        match self {
+            Corruption::BadSignature => PrecompileError::InvalidSignature,
+            Corruption::TruncatedPubkey | Corruption::WrongOffsets => {
+                PrecompileError::InvalidDataOffsets
+            }
+        }
+    }
+
+    fn apply(&self, data: &mut Vec<u8>, pubkey_len: usize) {
+        let signature_offset = DATA_START + pubkey_len;
+        match self {
+            Corruption::BadSignature => data[signature_offset] ^= 1,
+            Corruption::TruncatedPubkey => data.truncate(DATA_START + pubkey_len / 2),
+            Corruption::WrongOffsets => {
+                let past_end = u16::try_from(data.len()).unwrap_or(u16::MAX);
+                data[SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + 2]
+                    .copy_from_slice(&past_end.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// A precompile instruction, along with the outcome of verifying it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrecompileFixture {
+    /// The precompile verifying the signature.
+    pub program_id: Pubkey,
+    /// The precompile instruction.
+    pub instruction: Instruction,
+    /// The error the precompile returns, or `None` if the signature
+    /// verifies.
+    pub expected_error: Option<PrecompileError>,
+}
+
+impl PrecompileFixture {
+    /// The key and account of the precompile, to pass along with the
+    /// instruction.
+    pub fn keyed_account(&self) -> (Pubkey, Account) {
+        let name = if self.program_id == trezoa_sdk_ids::ed25519_program::id() {
+            "ed25519_program"
+        } else {
+            "secp256r1_program"
+        };
+        create_keyed_account_for_builtin_program(&self.program_id, name)
+    }
+
+    /// A check asserting the expected outcome of verifying the instruction.
+    pub fn check(&self) -> Check<'static> {
+        match &self.expected_error {
+            Some(error) => Check::precompile_err(error.clone()),
+            None => Check::success(),
+        }
+    }
+
+    fn corrupt(mut self, corruption: Corruption, pubkey_len: usize) -> Self {
+        corruption.apply(&mut self.instruction.data, pubkey_len);
+        self.expected_error = Some(corruption.expected_error());
+        self
+    }
+}
+
+/// Build a valid Ed25519 precompile instruction verifying a signature over
+/// `message`, with a key drawn from `keys`.
+pub fn ed25519(keys: &mut KeyGen, message: &[u8]) -> PrecompileFixture {
+    use ed25519_dalek::Signer;
+
+    let secret = ed25519_dalek::SecretKey::from_bytes(&keys.pubkey().to_bytes()).unwrap();
+    let public = ed25519_dalek::PublicKey::from(&secret);
+    let keypair = ed25519_dalek::Keypair { secret, public };
+
+    let signature = keypair.sign(message).to_bytes();
+    let instruction = trezoa_ed25519_program::new_ed25519_instruction_with_signature(
+        message,
+        &signature,
+        &keypair.public.to_bytes(),
+    );
+    PrecompileFixture {
+        program_id: trezoa_sdk_ids::ed25519_program::id(),
+        instruction,
+        expected_error: None,
+    }
+}
+
+/// Build an Ed25519 precompile instruction verifying a signature over
+/// `message`, with the provided corruption.
+pub fn ed25519_with_corruption(
+    keys: &mut KeyGen,
+    message: &[u8],
+    corruption: Corruption,
+) -> PrecompileFixture {
+    ed25519(keys, message).corrupt(corruption, trezoa_ed25519_program::PUBKEY_SERIALIZED_SIZE)
+}
+
+/// Draw a scalar in `[1, order)` from `keys`.
+fn scalar(keys: &mut KeyGen, order: &BigNumRef) -> BigNum {
+    loop {
+        let candidate = BigNum::from_slice(keys.pubkey().as_ref()).unwrap();
+        if candidate.num_bits() > 0 && candidate.ucmp(order) == Ordering::Less {
+            return candidate;
+        }
+    }
+}
+
+/// Build a valid Secp256r1 precompile instruction verifying a signature
+/// over `message`, with a key drawn from `keys`.
+pub fn secp256r1(keys: &mut KeyGen, message: &[u8]) -> PrecompileFixture {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let mut ctx = BigNumContext::new().unwrap();
+    let mut order = BigNum::new().unwrap();
+    group.order(&mut order, &mut ctx).unwrap();
+
+    let secret = scalar(keys, &order);
+    let mut public = EcPoint::new(&group).unwrap();
+    public.mul_generator(&group, &secret, &ctx).unwrap();
+    let public_key_bytes = public
+        .to_bytes(&group, PointConversionForm::COMPRESSED, &mut ctx)
+        .unwrap();
+    let mut public_key = [0; trezoa_secp256r1_program::COMPRESSED_PUBKEY_SERIALIZED_SIZE];
+    public_key.copy_from_slice(&public_key_bytes);
+
+    // ECDSA over the message's SHA-256 digest. The nonce is drawn from
+    // `keys` too, rather than at random, so the signature is reproducible.
+    let nonce = scalar(keys, &order);
+    let mut nonce_point = EcPoint::new(&group).unwrap();
+    nonce_point.mul_generator(&group, &nonce, &ctx).unwrap();
+    let mut x = BigNum::new().unwrap();
+    let mut y = BigNum::new().unwrap();
+    nonce_point
+        .affine_coordinates(&group, &mut x, &mut y, &mut ctx)
+        .unwrap();
+    let mut r = BigNum::new().unwrap();
+    r.nnmod(&x, &order, &mut ctx).unwrap();
+
+    let digest = BigNum::from_slice(&openssl::sha::sha256(message)).unwrap();
+    let mut r_secret = BigNum::new().unwrap();
+    r_secret.mod_mul(&r, &secret, &order, &mut ctx).unwrap();
+    let mut sum = BigNum::new().unwrap();
+    sum.mod_add(&digest, &r_secret, &order, &mut ctx).unwrap();
+    let mut nonce_inverse = BigNum::new().unwrap();
+    nonce_inverse.mod_inverse(&nonce, &order, &mut ctx).unwrap();
+    let mut s = BigNum::new().unwrap();
+    s.mod_mul(&nonce_inverse, &sum, &order, &mut ctx).unwrap();
+
+    // The precompile only accepts low-S signatures.
+    let mut half_order = BigNum::new().unwrap();
+    half_order.rshift1(&order).unwrap();
+    if s.ucmp(&half_order) == Ordering::Greater {
+        let mut low_s = BigNum::new().unwrap();
+        low_s.checked_sub(&order, &s).unwrap();
+        s = low_s;
+    }
+
+    let mut signature = [0; trezoa_secp256r1_program::SIGNATURE_SERIALIZED_SIZE];
+    let (r_bytes, s_bytes) = signature.split_at_mut(32);
+    r_bytes.copy_from_slice(&r.to_vec_padded(32).unwrap());
+    s_bytes.copy_from_slice(&s.to_vec_padded(32).unwrap());
+
+    let instruction = trezoa_secp256r1_program::new_secp256r1_instruction_with_signature(
+        message,
+        &signature,
+        &public_key,
+    );
+    PrecompileFixture {
+        program_id: trezoa_sdk_ids::secp256r1_program::id(),
+        instruction,
+        expected_error: None,
+    }
+}
+
+/// Build a Secp256r1 precompile instruction verifying a signature over
+/// `message`, with the provided corruption.
+pub fn secp256r1_with_corruption(
+    keys: &mut KeyGen,
+    message: &[u8],
+    corruption: Corruption,
+) -> PrecompileFixture {
+    secp256r1(keys, message).corrupt(
+        corruption,
+        trezoa_secp256r1_program::COMPRESSED_PUBKEY_SERIALIZED_SIZE,
+    )
+}
@@ -0,0 +1,47 @@
+//! Commonly used types, re-exported for convenience.
+//!
+//! Most tests need the same handful of Mollusk and Trezoa types. Rather than
+//! importing each from its own crate, a test can glob-import the prelude:
+//!
+//! ```rust,ignore
+//! use mollusk_svm::prelude::*;
+//!
+//! let program_id = Pubkey::new_unique();
+//! let mollusk = Mollusk::new(&program_id, "my_program");
+//!
+//! mollusk.process_and_validate_instruction(
+//!     &Instruction::new_with_bytes(program_id, &[], vec![]),
+//!     &[],
+//!     &[Check::success()],
+//! );
+//! ```
+//!
+//! Token program helpers live in the `mollusk-svm-programs-token` crate,
+//! since it depends on this one.
+
+#[cfg(feature = "register-tracing")]
+pub use crate::register_tracing::DefaultRegisterTracingCallback;
+#[cfg(feature = "inner-instructions")]
+pub use trezoa_transaction_status_client_types::InnerInstruction;
+pub use {
+    crate::{
+        account_builder::AccountBuilder,
+        account_store::AccountStore,
+        program::{
+            create_keyed_account_for_builtin_program, create_program_account_loader_v3,
+            create_program_account_pair_loader_v3, keyed_account_for_system_program, loader_keys,
+        },
+        result::{
+            types::{TransactionProgramResult, TransactionResult},
+            Check, CheckContext, Compare, Config, InstructionResult, ProgramResult,
+        },
+        sysvar::Sysvars,
+        testgen::KeyGen,
+        Mollusk, MolluskContext,
+    },
+    trezoa_account::{Account, ReadableAccount, WritableAccount},
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_instruction_error::InstructionError,
+    trezoa_program_error::ProgramError,
+    trezoa_pubkey::Pubkey,
+};
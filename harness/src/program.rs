@@ -1,24 +1,38 @@
 //! Module for working with Trezoa programs.
 
 use {
+    mollusk_svm_result::{return_data::InnerReturnData, types::ExecutionMode},
     trezoa_feature_set::FeatureSet,
     trezoa_syscalls::create_program_runtime_environment_v1,
     trezoa_account::Account,
     trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_instruction_error::InstructionError,
     trezoa_loader_v3_interface::state::UpgradeableLoaderState,
-    trezoa_loader_v4_interface::state::{LoaderV4State, LoaderV4Status},
+    trezoa_loader_v4_interface::state::LoaderV4State,
     trezoa_program_runtime::{
         invoke_context::{BuiltinFunctionWithContext, InvokeContext},
-        loaded_programs::{LoadProgramMetrics, ProgramCacheEntry, ProgramCacheForTxBatch},
-        trezoa_sbpf::program::BuiltinProgram,
+        loaded_programs::{
+            LoadProgramMetrics, ProgramCacheEntry, ProgramCacheEntryOwner, ProgramCacheEntryType,
+            ProgramCacheForTxBatch,
+        },
+        trezoa_sbpf::{
+            elf::Executable,
+            error::{EbpfError, ProgramResult},
+            program::{BuiltinFunction, BuiltinProgram},
+            verifier::RequisiteVerifier,
+            vm::{get_runtime_environment_key, EbpfVm},
+        },
     },
     trezoa_pubkey::Pubkey,
     trezoa_rent::Rent,
+    trezoa_system_interface::MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION,
     std::{
-        cell::{RefCell, RefMut},
+        borrow::Cow,
+        cell::{Cell, RefCell, RefMut},
         collections::HashMap,
         rc::Rc,
-        sync::Arc,
+        sync::{Arc, OnceLock},
+        time::Instant,
     },
 };
 
@@ -31,6 +45,145 @@ pub mod loader_keys {
     };
 }
 
+/// The SBPF version targeted by a program, re-exported for
+/// `ProgramCache::add_program_with_sbpf_version`.
+pub use trezoa_program_runtime::trezoa_sbpf::program::SBPFVersion;
+
+/// A syscall function, as registered with `ProgramCache::register_syscall`.
+pub type Syscall = BuiltinFunction<InvokeContext<'static, 'static>>;
+
+// The syscalls the harness hooks into: the CPI syscalls, which apply the
+// caller's and callee's account resizes to the transaction context and
+// record failing invocations, and the syscall setting return data.
+const HOOKED_SYSCALLS: [&str; 3] = [
+    "sol_invoke_signed_c",
+    "sol_invoke_signed_rust",
+    "sol_set_return_data",
+];
+const SET_RETURN_DATA: usize = 2;
+
+static HOOKED_SYSCALL_FUNCTIONS: [OnceLock<Syscall>; 3] =
+    [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+
+thread_local! {
+    static ACCOUNTS_RESIZE_DELTA_LIMIT: Cell<i64> =
+        const { Cell::new(MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION) };
+    static INNER_RETURN_DATA: RefCell<Vec<InnerReturnData>> = const { RefCell::new(Vec::new()) };
+    static FAILING_CPI_PROGRAM_ID: Cell<Option<Pubkey>> = const { Cell::new(None) };
+}
+
+/// Set the `accounts_resize_delta_limit` enforced when each CPI returns on
+/// this thread.
+pub(crate) fn set_accounts_resize_delta_limit(limit: i64) {
+    ACCOUNTS_RESIZE_DELTA_LIMIT.with(|cell| cell.set(limit));
+}
+
+/// Take the return data set by programs on this thread since it was last
+/// taken, in the order it was set.
+pub(crate) fn take_inner_return_data() -> Vec<InnerReturnData> {
+    INNER_RETURN_DATA.with(|inner| std::mem::take(&mut *inner.borrow_mut()))
+}
+
+/// Take the deepest program which failed in a CPI on this thread since it
+/// was last taken, if any.
+pub(crate) fn take_failing_cpi_program_id() -> Option<Pubkey> {
+    FAILING_CPI_PROGRAM_ID.with(Cell::take)
+}
+
+/// Wrap the syscalls in `HOOKED_SYSCALLS`, so
+/// `Mollusk::accounts_resize_delta_limit` is enforced when each CPI returns,
+/// like the runtime's own limit, failing CPIs are recorded, and return data
+/// is recorded as it's set. Other syscalls are returned as is.
+fn hook_syscall(name: &str, function: Syscall) -> Syscall {
+    let Some(index) = HOOKED_SYSCALLS.iter().position(|hooked| *hooked == name) else {
+        return function;
+    };
+    HOOKED_SYSCALL_FUNCTIONS[index].get_or_init(|| function);
+    [
+        hooked_syscall::<0>,
+        hooked_syscall::<1>,
+        hooked_syscall::<2>,
+    ][index]
+}
+
+fn hooked_syscall<const N: usize>(
+    vm: *mut EbpfVm<InvokeContext<'static, 'static>>,
+    arg0: u64,
+    arg1: u64,
+    arg2: u64,
+    arg3: u64,
+    arg4: u64,
+) {
+    // Syscalls receive a pointer to the VM offset by the runtime environment
+    // key.
+    let runtime_vm = || unsafe {
+        &mut *vm
+            .cast::<u64>()
+            .offset(-(get_runtime_environment_key() as isize))
+            .cast::<EbpfVm<InvokeContext<'static, 'static>>>()
+    };
+    let trace_length_before = runtime_vm()
+        .context_object_pointer
+        .transaction_context
+        .get_instruction_trace_length();
+
+    let function = HOOKED_SYSCALL_FUNCTIONS[N].get().unwrap();
+    function(vm, arg0, arg1, arg2, arg3, arg4);
+
+    let vm = runtime_vm();
+    let invoke_context = &vm.context_object_pointer;
+    if !matches!(vm.program_result, ProgramResult::Ok(_)) {
+        if N != SET_RETURN_DATA {
+            record_failing_cpi(invoke_context, trace_length_before);
+        }
+        return;
+    }
+
+    if N == SET_RETURN_DATA {
+        let (program_id, data) = invoke_context.transaction_context.get_return_data();
+        let returned = InnerReturnData {
+            program_id: *program_id,
+            stack_height: invoke_context.get_stack_height(),
+            data: data.to_vec(),
+        };
+        INNER_RETURN_DATA.with(|inner| inner.borrow_mut().push(returned));
+        return;
+    }
+
+    let limit = ACCOUNTS_RESIZE_DELTA_LIMIT.with(Cell::get);
+    let exceeded = invoke_context
+        .transaction_context
+        .accounts_resize_delta()
+        .is_ok_and(|accounts_resize_delta| accounts_resize_delta > limit);
+    if exceeded {
+        vm.program_result = ProgramResult::Err(EbpfError::SyscallError(Box::new(
+            InstructionError::MaxAccountsDataAllocationsExceeded,
+        )));
+    }
+}
+
+// A failing CPI aborts its caller, so the innermost failure is the first
+// recorded as the error unwinds. If the callee was never invoked, such as
+// when the CPI itself is invalid, the caller failed.
+fn record_failing_cpi(invoke_context: &InvokeContext, trace_length_before: usize) {
+    if FAILING_CPI_PROGRAM_ID.with(Cell::get).is_some() {
+        return;
+    }
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context =
+        if transaction_context.get_instruction_trace_length() > trace_length_before {
+            transaction_context.get_instruction_context_at_index_in_trace(trace_length_before)
+        } else {
+            transaction_context.get_current_instruction_context()
+        };
+    let Ok(instruction_context) = instruction_context else {
+        return;
+    };
+    if let Ok(program_id) = instruction_context.get_program_key() {
+        FAILING_CPI_PROGRAM_ID.with(|cell| cell.set(Some(*program_id)));
+    }
+}
+
 #[cfg(feature = "precompiles")]
 pub mod precompile_keys {
     use trezoa_pubkey::Pubkey;
@@ -56,9 +209,49 @@ pub mod precompile_keys {
     }
 }
 
+/// Whether JIT compilation of SBF programs is supported on this platform.
+pub const JIT_SUPPORTED: bool = cfg!(all(not(target_os = "windows"), target_arch = "x86_64"));
+
 pub struct CacheEntry {
     pub loader_key: Pubkey,
     pub elf_bytes: Option<Vec<u8>>,
+    pub load_timings: Option<ProgramLoadTimings>,
+    /// The SBPF version the program was loaded with, if it was pinned with
+    /// `ProgramCache::add_program_with_sbpf_version`.
+    pub sbpf_version: Option<SBPFVersion>,
+}
+
+/// Timings recorded while verifying and loading a program ELF into the
+/// cache, in microseconds. The wall-clock timings are zero on
+/// `wasm32-unknown-unknown`, which has no clock.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgramLoadTimings {
+    /// Time spent building the loader and registering syscalls.
+    pub register_syscalls_us: u64,
+    /// Time spent parsing and relocating the ELF.
+    pub load_elf_us: u64,
+    /// Time spent verifying the program's bytecode.
+    pub verify_code_us: u64,
+    /// Time spent JIT-compiling the program. Zero if JIT is not enabled.
+    pub jit_compile_us: u64,
+    /// Total wall-clock time for the program's addition to the cache.
+    pub total_us: u64,
+}
+
+// `Instant::now` panics on `wasm32-unknown-unknown`, so load timings are
+// reported as zero there.
+#[cfg(not(target_arch = "wasm32"))]
+fn start_timer() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn start_timer() -> Option<Instant> {
+    None
+}
+
+fn elapsed_us(start: Option<Instant>) -> u64 {
+    start.map_or(0, |start| start.elapsed().as_micros() as u64)
 }
 
 pub struct ProgramCache {
@@ -73,9 +266,16 @@ pub struct ProgramCache {
     //
     // K: program ID, V: cache entry
     entries_cache: Rc<RefCell<HashMap<Pubkey, CacheEntry>>>,
+    // Whether programs are JIT-compiled or interpreted.
+    execution_mode: ExecutionMode,
+    // Whether the runtime environment enables register tracing.
+    enable_register_tracing: bool,
     // The function registry (syscalls) to use for verifying and loading
     // program ELFs.
     pub program_runtime_environment: BuiltinProgram<InvokeContext<'static, 'static>>,
+    // Custom syscalls registered with `register_syscall`, in registration
+    // order.
+    syscalls: Vec<(String, Syscall)>,
 }
 
 impl ProgramCache {
@@ -87,6 +287,12 @@ impl ProgramCache {
         let me = Self {
             cache: Rc::new(RefCell::new(ProgramCacheForTxBatch::default())),
             entries_cache: Rc::new(RefCell::new(HashMap::new())),
+            execution_mode: if JIT_SUPPORTED {
+                ExecutionMode::Jit
+            } else {
+                ExecutionMode::Interpreted
+            },
+            enable_register_tracing,
             program_runtime_environment: create_program_runtime_environment_v1(
                 &feature_set.runtime_features(),
                 &compute_budget.to_budget(),
@@ -94,11 +300,19 @@ impl ProgramCache {
                 /* debugging_features */ enable_register_tracing,
             )
             .unwrap(),
+            syscalls: Vec::new(),
         };
         BUILTINS.iter().for_each(|builtin| {
             let program_id = builtin.program_id;
+            // Builtins are wrapped so a `DebugSession` can record their
+            // invocations.
+            #[cfg(feature = "invocation-inspect-callback")]
+            if let Some(builtin) = crate::debug::wrap_builtin(builtin) {
+                me.replenish(program_id, builtin.program_cache_entry(), None, None, None);
+                return;
+            }
             let entry = builtin.program_cache_entry();
-            me.replenish(program_id, entry, None);
+            me.replenish(program_id, entry, None, None, None);
         });
         me
     }
@@ -112,61 +326,424 @@ impl ProgramCache {
         program_id: Pubkey,
         entry: Arc<ProgramCacheEntry>,
         elf_bytes: Option<&[u8]>,
+        load_timings: Option<ProgramLoadTimings>,
+        sbpf_version: Option<SBPFVersion>,
     ) {
         self.entries_cache.borrow_mut().insert(
             program_id,
             CacheEntry {
                 loader_key: entry.account_owner(),
                 elf_bytes: elf_bytes.map(|s| s.to_vec()),
+                load_timings,
+                sbpf_version,
             },
         );
         self.cache.borrow_mut().replenish(program_id, entry);
     }
 
-    /// Add a builtin program to the cache.
+    /// Add a builtin program to the cache. A builtin added under the ID of a
+    /// program already in the cache replaces it.
     pub fn add_builtin(&mut self, builtin: Builtin) {
         let program_id = builtin.program_id;
         let entry = builtin.program_cache_entry();
-        self.replenish(program_id, entry, None);
+        self.replenish(program_id, entry, None, None, None);
     }
 
     /// Add a program to the cache.
+    ///
+    /// Verification and load timings are recorded, and can be retrieved with
+    /// `get_program_load_timings`.
     pub fn add_program(&mut self, program_id: &Pubkey, loader_key: &Pubkey, elf: &[u8]) {
+        self.load_program_entry(program_id, loader_key, elf, None);
+    }
+
+    /// Add a program to the cache, loaded with the provided SBPF version
+    /// rather than the versions enabled by the feature set.
+    ///
+    /// Each program gets its own runtime environment, so programs targeting
+    /// different SBPF versions, such as legacy v0 programs alongside v3
+    /// programs, can be loaded into the same cache. The program's ELF must
+    /// target the provided version.
+    pub fn add_program_with_sbpf_version(
+        &mut self,
+        program_id: &Pubkey,
+        loader_key: &Pubkey,
+        elf: &[u8],
+        sbpf_version: SBPFVersion,
+    ) {
+        self.load_program_entry(program_id, loader_key, elf, Some(sbpf_version));
+    }
+
+    fn load_program_entry(
+        &self,
+        program_id: &Pubkey,
+        loader_key: &Pubkey,
+        elf: &[u8],
+        sbpf_version: Option<SBPFVersion>,
+    ) {
+        self.try_load_program_entry(program_id, loader_key, elf, sbpf_version)
+            .unwrap();
+    }
+
+    fn program_environment(
+        &self,
+        sbpf_version: Option<SBPFVersion>,
+    ) -> Arc<BuiltinProgram<InvokeContext<'static, 'static>>> {
         // This might look rough, but it's actually functionally the same as
         // calling `create_program_runtime_environment_v1` on every addition.
-        let environment = {
-            let config = self.program_runtime_environment.get_config().clone();
-            let mut loader = BuiltinProgram::new_loader(config);
+        let mut config = self.program_runtime_environment.get_config().clone();
+        if let Some(sbpf_version) = sbpf_version {
+            config.enabled_sbpf_versions = sbpf_version..=sbpf_version;
+        }
+        let mut loader = BuiltinProgram::new_loader(config);
 
-            for (_key, (name, value)) in self
-                .program_runtime_environment
-                .get_function_registry()
-                .iter()
-            {
-                let name = std::str::from_utf8(name).unwrap();
-                loader.register_function(name, value).unwrap();
+        for (_key, (name, value)) in self
+            .program_runtime_environment
+            .get_function_registry()
+            .iter()
+        {
+            let name = std::str::from_utf8(name).unwrap();
+            let value = hook_syscall(name, value);
+            // Syscalls are metered for `SyscallProfileCallback`, which relies
+            // on register tracing.
+            #[cfg(feature = "register-tracing")]
+            let value = if self.enable_register_tracing {
+                crate::register_tracing::meter_syscall(name, value)
+            } else {
+                value
+            };
+            loader.register_function(name, value).unwrap();
+        }
+
+        Arc::new(loader)
+    }
+
+    fn try_load_program_entry(
+        &self,
+        program_id: &Pubkey,
+        loader_key: &Pubkey,
+        elf: &[u8],
+        sbpf_version: Option<SBPFVersion>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let start = start_timer();
+
+        let environment = self.program_environment(sbpf_version);
+        let register_syscalls_us = elapsed_us(start);
+
+        let mut metrics = LoadProgramMetrics::default();
+        let entry = if self.execution_mode == ExecutionMode::Interpreted {
+            // `ProgramCacheEntry::new` JIT-compiles the program wherever JIT is
+            // supported, while the loader only interprets executables without
+            // a compiled program. Load and verify the executable without
+            // compiling it instead.
+            let load_start = start_timer();
+            let executable = Executable::load(elf, environment)?;
+            metrics.load_elf_us = elapsed_us(load_start);
+            let verify_start = start_timer();
+            executable.verify::<RequisiteVerifier>()?;
+            metrics.verify_code_us = elapsed_us(verify_start);
+            ProgramCacheEntry {
+                program: ProgramCacheEntryType::Loaded(executable),
+                account_owner: ProgramCacheEntryOwner::try_from(loader_key).unwrap(),
+                account_size: elf.len(),
+                ..ProgramCacheEntry::default()
             }
+        } else {
+            ProgramCacheEntry::new(loader_key, environment, 0, 0, elf, elf.len(), &mut metrics)?
+        };
 
-            Arc::new(loader)
+        let load_timings = ProgramLoadTimings {
+            register_syscalls_us,
+            load_elf_us: metrics.load_elf_us,
+            verify_code_us: metrics.verify_code_us,
+            jit_compile_us: metrics.jit_compile_us,
+            total_us: elapsed_us(start),
         };
+
         self.replenish(
             *program_id,
-            Arc::new(
-                ProgramCacheEntry::new(
-                    loader_key,
-                    environment,
-                    0,
-                    0,
-                    elf,
-                    elf.len(),
-                    &mut LoadProgramMetrics::default(),
-                )
-                .unwrap(),
-            ),
+            Arc::new(entry),
             Some(elf),
+            Some(load_timings),
+            sbpf_version,
+        );
+        Ok(())
+    }
+
+    /// Register a custom syscall, which programs can invoke by name.
+    ///
+    /// Unlike registering the syscall on `program_runtime_environment`
+    /// directly, the syscall is registered again on every runtime
+    /// environment Mollusk builds: when the feature set changes, and for
+    /// the environments used while processing messages, such as when a
+    /// loader deploys a program. Programs already in the cache are
+    /// reloaded, so they can invoke it too.
+    ///
+    /// Panics if a syscall with the same name is already registered.
+    pub fn register_syscall(&mut self, name: &str, function: Syscall) {
+        self.program_runtime_environment
+            .register_function(name, function)
+            .unwrap_or_else(|err| panic!("Failed to register syscall {}: {}", name, err));
+        self.syscalls.push((name.to_string(), function));
+        self.reload_programs();
+    }
+
+    /// The custom syscalls registered with `register_syscall`, in
+    /// registration order.
+    pub fn syscalls(&self) -> &[(String, Syscall)] {
+        &self.syscalls
+    }
+
+    /// Get the mode in which SBF programs are executed.
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.execution_mode
+    }
+
+    /// Set whether SBF programs are JIT-compiled or interpreted.
+    ///
+    /// Programs already in the cache are reloaded. JIT mode is only
+    /// available where supported (see `JIT_SUPPORTED`); elsewhere, programs
+    /// are always interpreted.
+    pub fn set_execution_mode(&mut self, execution_mode: ExecutionMode) {
+        let execution_mode = if JIT_SUPPORTED {
+            execution_mode
+        } else {
+            ExecutionMode::Interpreted
+        };
+        if self.execution_mode == execution_mode {
+            return;
+        }
+        self.execution_mode = execution_mode;
+        self.reload_programs();
+    }
+
+    /// Rebuild the runtime environment for the provided feature set.
+    ///
+    /// Programs are verified and loaded against the environment of the
+    /// feature set the cache was created with, so features which change how
+    /// programs are loaded or executed, such as account data direct mapping,
+    /// only take effect once the environment is rebuilt. Programs already in
+    /// the cache are reloaded, and custom syscalls registered with
+    /// `register_syscall` are carried over. Syscalls registered on the
+    /// previous environment directly are not.
+    pub fn set_feature_set(&mut self, feature_set: &FeatureSet, compute_budget: &ComputeBudget) {
+        let mut environment = create_program_runtime_environment_v1(
+            &feature_set.runtime_features(),
+            &compute_budget.to_budget(),
+            /* reject_deployment_of_broken_elfs */ false,
+            /* debugging_features */ self.enable_register_tracing,
+        )
+        .unwrap();
+        // Only the custom syscalls are carried over, so syscalls gated off
+        // by the new feature set are dropped.
+        for (name, function) in &self.syscalls {
+            if environment
+                .get_function_registry()
+                .lookup_by_name(name.as_bytes())
+                .is_none()
+            {
+                environment.register_function(name, *function).unwrap();
+            }
+        }
+        self.program_runtime_environment = environment;
+        self.reload_programs();
+    }
+
+    fn reload_programs(&mut self) {
+        let programs = self
+            .entries_cache
+            .borrow()
+            .iter()
+            .filter_map(|(program_id, cache_entry)| {
+                cache_entry.elf_bytes.clone().map(|elf| {
+                    (
+                        *program_id,
+                        cache_entry.loader_key,
+                        elf,
+                        cache_entry.sbpf_version,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        for (program_id, loader_key, elf, sbpf_version) in programs {
+            self.load_program_entry(&program_id, &loader_key, &elf, sbpf_version);
+        }
+    }
+
+    /// Sync the cache with a BPF Loader 4 program account, such as one
+    /// resulting from a `deploy` or `retract` instruction.
+    ///
+    /// Deployed and finalized programs are loaded from the account's ELF,
+    /// while retracted programs are closed, so invoking them fails. Accounts
+    /// which aren't BPF Loader 4 programs are ignored.
+    ///
+    /// The program is only reloaded if its ELF changed, and stays pinned to
+    /// the SBPF version it was added with, if any. A program which fails
+    /// verification is marked as such, so invoking it fails.
+    pub fn sync_loader_v4_program(&self, program_id: &Pubkey, account: &Account) {
+        let Some(state) = get_loader_v4_state(account) else {
+            return;
+        };
+        self.drain_modified_entry(program_id);
+        match state.status {
+            LoaderV4Status::Retracted => {
+                self.close_program(program_id, state.slot, ProgramCacheEntryOwner::LoaderV4);
+            }
+            LoaderV4Status::Deployed | LoaderV4Status::Finalized => {
+                let elf = &account.data[LoaderV4State::program_data_offset()..];
+                self.sync_program(program_id, &loader_keys::LOADER_V4, elf, state.slot);
+            }
+        }
+    }
+
+    /// Sync the cache with the programdata account of a BPF Loader
+    /// Upgradeable program, such as one resulting from a `deploy`,
+    /// `upgrade`, or `close` instruction.
+    ///
+    /// The program is loaded from the programdata's ELF, as with
+    /// `sync_loader_v4_program`, so instructions which don't change the ELF,
+    /// such as `set_upgrade_authority`, don't reload it. If the programdata
+    /// was closed, the program is closed too, so invoking it fails.
+    pub fn sync_loader_v3_program(&self, program_id: &Pubkey, programdata_account: &Account) {
+        self.drain_modified_entry(program_id);
+        match bincode::deserialize(&programdata_account.data) {
+            Ok(UpgradeableLoaderState::ProgramData { slot, .. }) => {
+                let elf = &programdata_account.data
+                    [UpgradeableLoaderState::size_of_programdata_metadata()..];
+                self.sync_program(program_id, &loader_keys::LOADER_V3, elf, slot);
+            }
+            _ => self.close_program(program_id, 0, ProgramCacheEntryOwner::LoaderV3),
+        }
+    }
+
+    // The loader stores the programs it deploys as modified entries, which
+    // only become visible from the next slot, and would otherwise shadow the
+    // entry synced for the program. Other programs' entries are kept.
+    fn drain_modified_entry(&self, program_id: &Pubkey) {
+        let mut cache = self.cache.borrow_mut();
+        for (key, entry) in cache.drain_modified_entries() {
+            if key != *program_id {
+                cache.store_modified_entry(key, entry);
+            }
+        }
+    }
+
+    // Load a program modified by an instruction, unless its ELF is the one
+    // already cached, as it is after instructions which only change the
+    // program's authority. A program pinned to an SBPF version stays pinned.
+    //
+    // As in the runtime, a program which fails verification is stored as a
+    // tombstone, so invoking it fails rather than the sync panicking.
+    fn sync_program(&self, program_id: &Pubkey, loader_key: &Pubkey, elf: &[u8], slot: u64) {
+        let sbpf_version = {
+            let entries_cache = self.entries_cache.borrow();
+            let cache_entry = entries_cache.get(program_id);
+            if cache_entry.is_some_and(|cache_entry| {
+                cache_entry.loader_key == *loader_key
+                    && cache_entry.elf_bytes.as_deref() == Some(elf)
+            }) {
+                return;
+            }
+            cache_entry.and_then(|cache_entry| cache_entry.sbpf_version)
+        };
+        if self
+            .try_load_program_entry(program_id, loader_key, elf, sbpf_version)
+            .is_err()
+        {
+            self.entries_cache.borrow_mut().remove(program_id);
+            self.cache.borrow_mut().replenish(
+                *program_id,
+                Arc::new(ProgramCacheEntry::new_tombstone(
+                    slot,
+                    entry_owner(loader_key),
+                    ProgramCacheEntryType::FailedVerification(
+                        self.program_environment(sbpf_version),
+                    ),
+                )),
+            );
+        }
+    }
+
+    // Sync the cache with the BPF Loader Upgradeable and BPF Loader 4
+    // programs among the provided accounts whose program or programdata
+    // accounts were modified.
+    pub(crate) fn sync_modified_programs(
+        &self,
+        accounts: &[(Pubkey, Account)],
+        modified: &[Pubkey],
+    ) {
+        for (program_id, account) in accounts {
+            match account.owner {
+                loader_keys::LOADER_V3 => {
+                    let Ok(UpgradeableLoaderState::Program {
+                        programdata_address,
+                    }) = bincode::deserialize(&account.data)
+                    else {
+                        continue;
+                    };
+                    if !modified.contains(program_id) && !modified.contains(&programdata_address) {
+                        continue;
+                    }
+                    if let Some((_, programdata_account)) =
+                        accounts.iter().find(|(key, _)| key == &programdata_address)
+                    {
+                        self.sync_loader_v3_program(program_id, programdata_account);
+                    }
+                }
+                loader_keys::LOADER_V4 if modified.contains(program_id) => {
+                    self.sync_loader_v4_program(program_id, account);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn close_program(&self, program_id: &Pubkey, slot: u64, owner: ProgramCacheEntryOwner) {
+        self.entries_cache.borrow_mut().remove(program_id);
+        self.cache.borrow_mut().replenish(
+            *program_id,
+            Arc::new(ProgramCacheEntry::new_tombstone(
+                slot,
+                owner,
+                ProgramCacheEntryType::Closed,
+            )),
         );
     }
 
+    /// Remove a program from the cache, returning its entry, or `None` if it
+    /// wasn't in the cache.
+    ///
+    /// The program is closed, as it would be on chain, so invoking it fails,
+    /// and it's no longer listed by `iter_entries`.
+    pub fn remove_program(&mut self, program_id: &Pubkey) -> Option<CacheEntry> {
+        let cache_entry = self.entries_cache.borrow_mut().remove(program_id)?;
+        // Entries the loaders stored while processing would otherwise shadow
+        // the tombstone.
+        self.cache.borrow_mut().drain_modified_entries();
+        self.close_program(program_id, 0, entry_owner(&cache_entry.loader_key));
+        Some(cache_entry)
+    }
+
+    /// Replace a program in the cache with the provided ELF, as an upgrade
+    /// would, returning the previous entry, or `None` if the program wasn't
+    /// in the cache.
+    ///
+    /// A program pinned to an SBPF version with
+    /// `add_program_with_sbpf_version` stays pinned to it.
+    pub fn replace_program(
+        &mut self,
+        program_id: &Pubkey,
+        loader_key: &Pubkey,
+        elf: &[u8],
+    ) -> Option<CacheEntry> {
+        let previous = self.remove_program(program_id);
+        let sbpf_version = previous
+            .as_ref()
+            .and_then(|cache_entry| cache_entry.sbpf_version);
+        self.load_program_entry(program_id, loader_key, elf, sbpf_version);
+        previous
+    }
+
     /// Load a program from the cache.
     pub fn load_program(&self, program_id: &Pubkey) -> Option<Arc<ProgramCacheEntry>> {
         self.cache.borrow().find(program_id)
@@ -212,6 +789,62 @@ impl ProgramCache {
             })
     }
 
+    /// The number of programs in the cache, including builtins.
+    pub fn num_programs(&self) -> usize {
+        self.entries_cache.borrow().len()
+    }
+
+    /// List every program in the cache, including builtins, as its program
+    /// ID, loader key, and ELF size. Builtins have no ELF, so their size is
+    /// `0`.
+    ///
+    /// Entries are sorted by program ID. Use `num_programs` to count them
+    /// without building the list.
+    pub fn iter_entries(&self) -> Vec<(Pubkey, Pubkey, usize)> {
+        let mut entries = self
+            .entries_cache
+            .borrow()
+            .iter()
+            .map(|(program_id, cache_entry)| {
+                (
+                    *program_id,
+                    cache_entry.loader_key,
+                    cache_entry.elf_bytes.as_ref().map_or(0, Vec::len),
+                )
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(program_id, _, _)| *program_id);
+        entries
+    }
+
+    /// Get the key of the loader which owns the provided program, or `None`
+    /// if the program isn't in the cache. Builtins are owned by the native
+    /// loader.
+    pub fn get_program_loader_key(&self, program_id: &Pubkey) -> Option<Pubkey> {
+        self.entries_cache
+            .borrow()
+            .get(program_id)
+            .map(|cache_entry| cache_entry.loader_key)
+    }
+
+    /// Get the verification and load timings recorded when the program was
+    /// added to the cache. Returns `None` for builtins.
+    pub fn get_program_load_timings(&self, program_id: &Pubkey) -> Option<ProgramLoadTimings> {
+        self.entries_cache
+            .borrow()
+            .get(program_id)
+            .and_then(|cache_entry| cache_entry.load_timings.clone())
+    }
+
+    /// Get the SBPF version of a loaded program. Returns `None` for builtins
+    /// and programs which failed to load.
+    pub fn get_program_sbpf_version(&self, program_id: &Pubkey) -> Option<SBPFVersion> {
+        match &self.load_program(program_id)?.program {
+            ProgramCacheEntryType::Loaded(executable) => Some(executable.get_sbpf_version()),
+            _ => None,
+        }
+    }
+
     pub fn get_program_elf_bytes(&self, program_id: &Pubkey) -> Option<Vec<u8>> {
         match self.entries_cache.borrow().get(program_id) {
             None => None,
@@ -220,13 +853,78 @@ impl ProgramCache {
     }
 }
 
+fn entry_owner(loader_key: &Pubkey) -> ProgramCacheEntryOwner {
+    match *loader_key {
+        loader_keys::NATIVE_LOADER => ProgramCacheEntryOwner::NativeLoader,
+        loader_keys::LOADER_V1 => ProgramCacheEntryOwner::LoaderV1,
+        loader_keys::LOADER_V2 => ProgramCacheEntryOwner::LoaderV2,
+        loader_keys::LOADER_V3 => ProgramCacheEntryOwner::LoaderV3,
+        loader_keys::LOADER_V4 => ProgramCacheEntryOwner::LoaderV4,
+        _ => panic!("Invalid loader key: {}", loader_key),
+    }
+}
+
+/// A builtin program, implemented natively rather than loaded from an ELF.
+///
+/// The runtime invokes builtins through a plain function pointer, so an
+/// entrypoint can't be a capturing closure. Declare one with
+/// `trezoa_program_runtime::declare_process_instruction!`, and pass its `vm`
+/// function:
+///
+/// ```rust,ignore
+/// use {
+///     mollusk_svm::{program::Builtin, Mollusk},
+///     trezoa_program_runtime::declare_process_instruction,
+/// };
+///
+/// declare_process_instruction!(Entrypoint, 150, |invoke_context| {
+///     /* ... */
+///     Ok(())
+/// });
+///
+/// let mut mollusk = Mollusk::default();
+/// mollusk
+///     .program_cache
+///     .add_builtin(Builtin::new(program_id, "my_builtin", Entrypoint::vm));
+/// ```
 pub struct Builtin {
     program_id: Pubkey,
-    name: &'static str,
+    name: Cow<'static, str>,
     entrypoint: BuiltinFunctionWithContext,
 }
 
 impl Builtin {
+    /// Create a builtin program from its ID, name, and VM entrypoint.
+    ///
+    /// The name is stored as the data of the builtin's program account. See
+    /// `keyed_account`.
+    pub fn new(
+        program_id: Pubkey,
+        name: impl Into<Cow<'static, str>>,
+        entrypoint: BuiltinFunctionWithContext,
+    ) -> Self {
+        Self {
+            program_id,
+            name: name.into(),
+            entrypoint,
+        }
+    }
+
+    /// The builtin's program ID.
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    /// The builtin's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the key and account for the builtin program.
+    pub fn keyed_account(&self) -> (Pubkey, Account) {
+        create_keyed_account_for_builtin_program(&self.program_id, &self.name)
+    }
+
     fn program_cache_entry(&self) -> Arc<ProgramCacheEntry> {
         Arc::new(ProgramCacheEntry::new_builtin(
             0,
@@ -239,35 +937,47 @@ impl Builtin {
 static BUILTINS: &[Builtin] = &[
     Builtin {
         program_id: trezoa_system_program::id(),
-        name: "system_program",
+        name: Cow::Borrowed("system_program"),
         entrypoint: trezoa_system_program::system_processor::Entrypoint::vm,
     },
     Builtin {
         program_id: loader_keys::LOADER_V2,
-        name: "trezoa_bpf_loader_program",
+        name: Cow::Borrowed("trezoa_bpf_loader_program"),
         entrypoint: trezoa_bpf_loader_program::Entrypoint::vm,
     },
     Builtin {
         program_id: loader_keys::LOADER_V3,
-        name: "trezoa_bpf_loader_upgradeable_program",
+        name: Cow::Borrowed("trezoa_bpf_loader_upgradeable_program"),
         entrypoint: trezoa_bpf_loader_program::Entrypoint::vm,
     },
     #[cfg(feature = "all-builtins")]
     Builtin {
         program_id: loader_keys::LOADER_V1,
-        name: "trezoa_bpf_loader_deprecated_program",
+        name: Cow::Borrowed("trezoa_bpf_loader_deprecated_program"),
         entrypoint: trezoa_bpf_loader_program::Entrypoint::vm,
     },
     #[cfg(feature = "all-builtins")]
     Builtin {
         program_id: loader_keys::LOADER_V4,
-        name: "trezoa_loader_v4_program",
+        name: Cow::Borrowed("trezoa_loader_v4_program"),
         entrypoint: trezoa_loader_v4_program::Entrypoint::vm,
     },
     #[cfg(feature = "all-builtins")]
+    Builtin {
+        program_id: trezoa_sdk_ids::stake::id(),
+        name: Cow::Borrowed("stake_program"),
+        entrypoint: trezoa_stake_program::stake_instruction::Entrypoint::vm,
+    },
+    #[cfg(feature = "all-builtins")]
+    Builtin {
+        program_id: trezoa_sdk_ids::vote::id(),
+        name: Cow::Borrowed("vote_program"),
+        entrypoint: trezoa_vote_program::vote_processor::Entrypoint::vm,
+    },
+    #[cfg(feature = "all-builtins")]
     Builtin {
         program_id: trezoa_sdk_ids::zk_elgamal_proof_program::id(),
-        name: "zk_elgamal_proof_program",
+        name: Cow::Borrowed("zk_elgamal_proof_program"),
         entrypoint: trezoa_zk_elgamal_proof_program::Entrypoint::vm,
     },
 ];
@@ -291,17 +1001,29 @@ pub fn create_keyed_account_for_builtin_program(
 
 /// Get the key and account for the system program.
 pub fn keyed_account_for_system_program() -> (Pubkey, Account) {
-    create_keyed_account_for_builtin_program(&BUILTINS[0].program_id, BUILTINS[0].name)
+    BUILTINS[0].keyed_account()
 }
 
 /// Get the key and account for the BPF Loader v2 program.
 pub fn keyed_account_for_bpf_loader_v2_program() -> (Pubkey, Account) {
-    create_keyed_account_for_builtin_program(&BUILTINS[1].program_id, BUILTINS[1].name)
+    BUILTINS[1].keyed_account()
 }
 
 /// Get the key and account for the BPF Loader v3 (Upgradeable) program.
 pub fn keyed_account_for_bpf_loader_v3_program() -> (Pubkey, Account) {
-    create_keyed_account_for_builtin_program(&BUILTINS[2].program_id, BUILTINS[2].name)
+    BUILTINS[2].keyed_account()
+}
+
+/// Get the key and account for the stake program.
+#[cfg(feature = "all-builtins")]
+pub fn keyed_account_for_stake_program() -> (Pubkey, Account) {
+    create_keyed_account_for_builtin_program(&trezoa_sdk_ids::stake::id(), "stake_program")
+}
+
+/// Get the key and account for the vote program.
+#[cfg(feature = "all-builtins")]
+pub fn keyed_account_for_vote_program() -> (Pubkey, Account) {
+    create_keyed_account_for_builtin_program(&trezoa_sdk_ids::vote::id(), "vote_program")
 }
 
 /* ... */
@@ -350,6 +1072,16 @@ pub fn create_program_account_loader_v3(program_id: &Pubkey) -> Account {
 
 /// Create a BPF Loader v3 (Upgradeable) program data account.
 pub fn create_program_data_account_loader_v3(elf: &[u8]) -> Account {
+    create_program_data_account_loader_v3_with_header(elf, 0, None)
+}
+
+/// Create a BPF Loader v3 (Upgradeable) program data account with the
+/// provided deployment slot and upgrade authority.
+pub fn create_program_data_account_loader_v3_with_header(
+    elf: &[u8],
+    slot: u64,
+    upgrade_authority_address: Option<Pubkey>,
+) -> Account {
     let data = {
         let elf_offset = UpgradeableLoaderState::size_of_programdata_metadata();
         let data_len = elf_offset + elf.len();
@@ -357,8 +1089,8 @@ pub fn create_program_data_account_loader_v3(elf: &[u8]) -> Account {
         bincode::serialize_into(
             &mut data[0..elf_offset],
             &UpgradeableLoaderState::ProgramData {
-                slot: 0,
-                upgrade_authority_address: None,
+                slot,
+                upgrade_authority_address,
             },
         )
         .unwrap();
@@ -390,27 +1122,127 @@ pub fn create_program_account_pair_loader_v3(
 }
 
 /// Create a BPF Loader 4 program account.
+///
+/// The program is deployed at slot `0`, under a placeholder authority. See
+/// `LoaderV4AccountBuilder` for control over the program's state.
 pub fn create_program_account_loader_v4(elf: &[u8]) -> Account {
-    let data = unsafe {
-        let elf_offset = LoaderV4State::program_data_offset();
-        let data_len = elf_offset + elf.len();
-        let mut data = vec![0u8; data_len];
-        *std::mem::transmute::<&mut [u8; LoaderV4State::program_data_offset()], &mut LoaderV4State>(
-            (&mut data[0..elf_offset]).try_into().unwrap(),
-        ) = LoaderV4State {
+    LoaderV4AccountBuilder::new(elf).build()
+}
+
+/// The status of a BPF Loader 4 program, re-exported for
+/// `LoaderV4AccountBuilder`.
+pub use trezoa_loader_v4_interface::state::LoaderV4Status;
+
+// Offsets of the fields of `LoaderV4State` within the account data.
+const LOADER_V4_SLOT_OFFSET: usize = 0;
+const LOADER_V4_AUTHORITY_OFFSET: usize = 8;
+const LOADER_V4_STATUS_OFFSET: usize = 40;
+
+/// Builder for a BPF Loader 4 program account.
+///
+/// Unless overridden, the program is deployed at slot `0`, under a
+/// placeholder authority.
+///
+/// ```rust,ignore
+/// use mollusk_svm::program::{LoaderV4AccountBuilder, LoaderV4Status};
+///
+/// let program_account = LoaderV4AccountBuilder::new(&elf)
+///     .authority(&authority)
+///     .status(LoaderV4Status::Retracted)
+///     .slot(10)
+///     .build();
+/// ```
+pub struct LoaderV4AccountBuilder {
+    elf: Vec<u8>,
+    slot: u64,
+    authority_address_or_next_version: Pubkey,
+    status: LoaderV4Status,
+}
+
+impl LoaderV4AccountBuilder {
+    /// Create a new builder for a program with the provided ELF.
+    pub fn new(elf: &[u8]) -> Self {
+        Self {
+            elf: elf.to_vec(),
             slot: 0,
             authority_address_or_next_version: Pubkey::new_from_array([2; 32]),
             status: LoaderV4Status::Deployed,
-        };
-        data[elf_offset..].copy_from_slice(elf);
-        data
-    };
-    let lamports = Rent::default().minimum_balance(data.len());
-    Account {
-        lamports,
-        data,
-        owner: loader_keys::LOADER_V4,
-        executable: true,
-        ..Default::default()
+        }
+    }
+
+    /// Set the program's authority. For a finalized program, this is the
+    /// address of the program's next version instead.
+    pub fn authority(mut self, authority: &Pubkey) -> Self {
+        self.authority_address_or_next_version = *authority;
+        self
+    }
+
+    /// Set the slot in which the program was last deployed or retracted.
+    ///
+    /// The loader rejects deploying or retracting a program again until the
+    /// deployment cooldown has passed since this slot.
+    pub fn slot(mut self, slot: u64) -> Self {
+        self.slot = slot;
+        self
     }
+
+    /// Set the program's status.
+    ///
+    /// Retracted programs can be written to and deployed, deployed programs
+    /// can be invoked and retracted, and finalized programs can be invoked
+    /// but never changed again.
+    pub fn status(mut self, status: LoaderV4Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Build the account, funded with the rent-exempt minimum for its data.
+    ///
+    /// Deployed and finalized programs are marked executable, while retracted
+    /// programs aren't, so the loader can modify them.
+    pub fn build(self) -> Account {
+        let elf_offset = LoaderV4State::program_data_offset();
+        let mut data = vec![0u8; elf_offset + self.elf.len()];
+        data[LOADER_V4_SLOT_OFFSET..LOADER_V4_AUTHORITY_OFFSET]
+            .copy_from_slice(&self.slot.to_le_bytes());
+        data[LOADER_V4_AUTHORITY_OFFSET..LOADER_V4_STATUS_OFFSET]
+            .copy_from_slice(self.authority_address_or_next_version.as_ref());
+        data[LOADER_V4_STATUS_OFFSET..elf_offset]
+            .copy_from_slice(&(self.status as u64).to_le_bytes());
+        data[elf_offset..].copy_from_slice(&self.elf);
+
+        let lamports = Rent::default().minimum_balance(data.len());
+        Account {
+            lamports,
+            data,
+            owner: loader_keys::LOADER_V4,
+            executable: self.status != LoaderV4Status::Retracted,
+            ..Default::default()
+        }
+    }
+}
+
+/// Get the state of a BPF Loader 4 program account, or `None` if the
+/// account isn't one.
+pub fn get_loader_v4_state(account: &Account) -> Option<LoaderV4State> {
+    let data = &account.data;
+    if account.owner != loader_keys::LOADER_V4 || data.len() < LoaderV4State::program_data_offset()
+    {
+        return None;
+    }
+    let read_u64 = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let status = match read_u64(LOADER_V4_STATUS_OFFSET) {
+        0 => LoaderV4Status::Retracted,
+        1 => LoaderV4Status::Deployed,
+        2 => LoaderV4Status::Finalized,
+        _ => return None,
+    };
+    Some(LoaderV4State {
+        slot: read_u64(LOADER_V4_SLOT_OFFSET),
+        authority_address_or_next_version: Pubkey::try_from(
+            &data[LOADER_V4_AUTHORITY_OFFSET..LOADER_V4_STATUS_OFFSET],
+        )
+        .unwrap(),
+        status,
+    })
 }
@@ -1,14 +1,38 @@
 use {
-    crate::{InvocationInspectCallback, Mollusk},
+    crate::{
+        program::Syscall, result::profile::ExecutionProfile, InvocationInspectCallback, Mollusk,
+    },
+    serde::Serialize,
     sha2::{Digest, Sha256},
-    trezoa_program_runtime::invoke_context::{Executable, InvokeContext, RegisterTrace},
+    trezoa_program_runtime::{
+        invoke_context::{Executable, InvokeContext, RegisterTrace},
+        trezoa_sbpf::{
+            ebpf,
+            vm::{get_runtime_environment_key, ContextObject, EbpfVm},
+        },
+    },
     trezoa_pubkey::Pubkey,
     trezoa_transaction_context::{InstructionAccount, InstructionContext},
-    std::{fs::File, io::Write},
+    std::{
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, VecDeque},
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+        rc::Rc,
+        sync::Mutex,
+    },
 };
 
 const DEFAULT_PATH: &str = "target/sbf/trace";
 
+/// The version of the JSON trace schema written by `JsonTraceCallback`.
+///
+/// The version is bumped whenever a field is removed or its meaning
+/// changes. Fields may be added without bumping it, so consumers should
+/// ignore fields they don't recognize.
+pub const JSON_TRACE_SCHEMA_VERSION: u32 = 2;
+
 pub struct DefaultRegisterTracingCallback {
     pub sbf_trace_dir: String,
 }
@@ -109,6 +133,742 @@ impl InvocationInspectCallback for DefaultRegisterTracingCallback {
     }
 }
 
+/// An invocation callback which exports register traces as JSON Lines, for
+/// external analysis tools and coverage dashboards.
+///
+/// Each line is a JSON object describing a single executed VM instruction:
+///
+/// ```json
+/// {
+///   "version": 2,
+///   "trace": 0,
+///   "program_id": "11111111111111111111111111111111",
+///   "step": 0,
+///   "pc": 0,
+///   "opcode": 183,
+///   "instruction": "b701000000000000",
+///   "registers": ["0x0000000000000000", "..."],
+///   "cu": 1399999
+/// }
+/// ```
+///
+/// * `version`: The schema version. See `JSON_TRACE_SCHEMA_VERSION`.
+/// * `trace`: The index of the register trace, counting from zero across every
+///   invocation of an SBF program (including CPIs) exported by the callback.
+/// * `program_id`: The base58-encoded ID of the traced program.
+/// * `step`: The index of the instruction within its trace.
+/// * `pc`: The program counter, in instructions from the start of the text
+///   section.
+/// * `opcode`: The instruction's opcode.
+/// * `instruction`: The instruction's eight bytes, hex-encoded.
+/// * `registers`: Registers `r0` through `r10` before the instruction was
+///   executed, as zero-padded hex strings, since JSON numbers can't hold every
+///   `u64`.
+/// * `cu`: The compute units remaining before the instruction was executed,
+///   from the transaction's compute meter, so syscalls and CPIs are accounted
+///   for. `null` if it's unknown, such as when too many syscalls were called to
+///   meter them all.
+///
+/// Register tracing must be enabled, such as with `Mollusk::new_debuggable`.
+///
+/// ```rust,ignore
+/// let mut mollusk = Mollusk::new_debuggable(&program_id, "my_program", true);
+/// mollusk.invocation_inspect_callback =
+///     Box::new(JsonTraceCallback::create("target/sbf/trace.jsonl").unwrap());
+/// ```
+pub struct JsonTraceCallback {
+    writer: RefCell<Box<dyn Write>>,
+    next_trace: Cell<u64>,
+    // The number of traces recorded by the invoke context before the
+    // current instruction, which were already exported.
+    exported_traces: Cell<usize>,
+}
+
+impl JsonTraceCallback {
+    /// Create a callback which writes JSON Lines to the provided writer.
+    pub fn new(writer: impl Write + 'static) -> Self {
+        Self {
+            writer: RefCell::new(Box::new(writer)),
+            next_trace: Cell::new(0),
+            exported_traces: Cell::new(0),
+        }
+    }
+
+    /// Create a callback which writes JSON Lines to a file at the provided
+    /// path, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    fn export(
+        &self,
+        instruction_context: InstructionContext,
+        executable: &Executable,
+        register_trace: RegisterTrace,
+        remaining_compute_units: &[Option<u64>],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let program_id = instruction_context.get_program_key()?;
+        let trace = self.next_trace.get();
+        self.next_trace.set(trace + 1);
+
+        let mut writer = self.writer.try_borrow_mut()?;
+        write_json_trace(
+            &mut *writer,
+            trace,
+            program_id,
+            executable,
+            register_trace,
+            remaining_compute_units,
+        )?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl InvocationInspectCallback for JsonTraceCallback {
+    fn before_invocation(
+        &self,
+        _: &Mollusk,
+        _: &Pubkey,
+        _: &[u8],
+        _: &[InstructionAccount],
+        invoke_context: &InvokeContext,
+    ) {
+        // The invoke context is shared by every instruction in a
+        // transaction, and keeps the traces of instructions already
+        // processed.
+        let exported_traces = Cell::new(0);
+        invoke_context.iterate_vm_traces(
+            &|_: InstructionContext, _: &Executable, _: RegisterTrace| {
+                exported_traces.set(exported_traces.get() + 1);
+            },
+        );
+        self.exported_traces.set(exported_traces.get());
+        SYSCALL_METER_READINGS.with(|readings| readings.replace(Some(Vec::new())));
+    }
+
+    fn after_invocation(
+        &self,
+        _: &Mollusk,
+        invoke_context: &InvokeContext,
+        register_tracing_enabled: bool,
+    ) {
+        let readings = SYSCALL_METER_READINGS
+            .with(|readings| readings.take())
+            .unwrap_or_default();
+        if !register_tracing_enabled {
+            return;
+        }
+
+        // Programs at the same stack height run one after another, so their
+        // syscalls are matched to their traces in order.
+        let mut readings_by_height = BTreeMap::<usize, VecDeque<&SyscallMeterReading>>::new();
+        for reading in &readings {
+            readings_by_height
+                .entry(reading.stack_height)
+                .or_default()
+                .push_back(reading);
+        }
+        let readings_by_height = RefCell::new(readings_by_height);
+
+        let index = Cell::new(0);
+        invoke_context.iterate_vm_traces(
+            &|instruction_context: InstructionContext,
+              executable: &Executable,
+              register_trace: RegisterTrace| {
+                let trace_index = index.get();
+                index.set(trace_index + 1);
+                if trace_index < self.exported_traces.get() || register_trace.is_empty() {
+                    return;
+                }
+
+                let stack_height = instruction_context.get_stack_height();
+                // A program invoked through a CPI returns to the syscall
+                // which invoked it.
+                let remaining_at_return = if stack_height == 1 {
+                    Some(invoke_context.get_remaining())
+                } else {
+                    readings
+                        .iter()
+                        .find(|reading| reading.traces_after == trace_index + 1)
+                        .map(|reading| reading.remaining_after)
+                };
+                let remaining_compute_units = remaining_compute_units(
+                    executable,
+                    register_trace,
+                    readings_by_height
+                        .borrow_mut()
+                        .entry(stack_height)
+                        .or_default(),
+                    remaining_at_return,
+                );
+
+                if let Err(e) = self.export(
+                    instruction_context,
+                    executable,
+                    register_trace,
+                    &remaining_compute_units,
+                ) {
+                    eprintln!("Error exporting the register tracing: {}", e);
+                }
+            },
+        );
+    }
+}
+
+/// Whether an instruction calls a syscall, rather than a function of the
+/// program.
+fn is_syscall(executable: &Executable, insn: &ebpf::Insn) -> bool {
+    if executable.get_sbpf_version().static_syscalls() {
+        insn.opc == ebpf::SYSCALL
+    } else {
+        insn.opc == ebpf::CALL_IMM
+            && executable
+                .get_loader()
+                .get_function_registry()
+                .lookup_by_key(insn.imm as u32)
+                .is_some()
+    }
+}
+
+/// The compute units remaining before each instruction of a register trace
+/// executed, counting one per instruction from the compute meter readings
+/// around the trace's syscalls, which are taken from `syscall_readings`, or
+/// else from the compute units remaining when the program returned.
+fn remaining_compute_units(
+    executable: &Executable,
+    register_trace: RegisterTrace,
+    syscall_readings: &mut VecDeque<&SyscallMeterReading>,
+    remaining_at_return: Option<u64>,
+) -> Vec<Option<u64>> {
+    let (_, program) = executable.get_text_bytes();
+    let syscalls = register_trace
+        .iter()
+        .enumerate()
+        .filter(|(_, regs)| {
+            // The program counter is stored in r11.
+            is_syscall(
+                executable,
+                &ebpf::get_insn_unchecked(program, regs[11] as usize),
+            )
+        })
+        .map_while(|(step, _)| Some((step, syscall_readings.pop_front()?)))
+        .collect::<Vec<_>>();
+
+    let length = register_trace.len();
+    let mut next_syscall = 0;
+    (0..length)
+        .map(|step| {
+            while syscalls
+                .get(next_syscall)
+                .is_some_and(|(syscall_step, _)| *syscall_step < step)
+            {
+                next_syscall += 1;
+            }
+            // Before a syscall, the compute units consumed by the VM
+            // instructions up to and including the call are already
+            // deducted when it's entered.
+            if let Some((syscall_step, reading)) = syscalls.get(next_syscall) {
+                return Some(reading.remaining_before + (syscall_step + 1 - step) as u64);
+            }
+            if let Some((syscall_step, reading)) = syscalls.last() {
+                return Some(
+                    reading
+                        .remaining_after
+                        .saturating_sub((step - syscall_step - 1) as u64),
+                );
+            }
+            remaining_at_return.map(|remaining| remaining + (length - step) as u64)
+        })
+        .collect()
+}
+
+/// A record written by `JsonTraceCallback`. See `JsonTraceCallback` for the
+/// schema.
+#[derive(Serialize)]
+struct JsonTraceRecord<'a> {
+    version: u32,
+    trace: u64,
+    program_id: String,
+    step: usize,
+    pc: u64,
+    opcode: u8,
+    instruction: String,
+    registers: &'a [String],
+    cu: Option<u64>,
+}
+
+/// Write a register trace as JSON Lines, one record per executed
+/// instruction, with the compute units remaining before each instruction
+/// executed, if known. See `JsonTraceCallback` for the schema.
+pub fn write_json_trace<W: Write + ?Sized>(
+    writer: &mut W,
+    trace: u64,
+    program_id: &Pubkey,
+    executable: &Executable,
+    register_trace: RegisterTrace,
+    remaining_compute_units: &[Option<u64>],
+) -> std::io::Result<()> {
+    let (_, program) = executable.get_text_bytes();
+    let program_id = program_id.to_string();
+    for (step, regs) in register_trace.iter().enumerate() {
+        // The program counter is stored in r11.
+        let pc = regs[11];
+        let insn = ebpf::get_insn_unchecked(program, pc as usize);
+        let registers = regs[..11]
+            .iter()
+            .map(|reg| format!("0x{:016x}", reg))
+            .collect::<Vec<_>>();
+        let record = JsonTraceRecord {
+            version: JSON_TRACE_SCHEMA_VERSION,
+            trace,
+            program_id: program_id.clone(),
+            step,
+            pc,
+            opcode: insn.opc,
+            instruction: hex::encode(insn.to_array()),
+            registers: &registers,
+            cu: remaining_compute_units.get(step).copied().flatten(),
+        };
+        serde_json::to_writer(&mut *writer, &record)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// The calls to a syscall, and the compute units they consumed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyscallStats {
+    /// The number of times the syscall was invoked.
+    pub calls: u64,
+    /// The compute units consumed by the syscall, beyond the instruction
+    /// calling it. For CPIs, this includes the compute units consumed by
+    /// invoked builtin programs, but not the VM instructions executed or
+    /// syscalls made by invoked SBF programs, which are reported on their
+    /// own.
+    pub compute_units: u64,
+}
+
+/// Compute unit consumption attributed to syscalls, collected by
+/// `SyscallProfileCallback`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyscallReport {
+    /// The calls to each syscall, by name.
+    pub syscalls: BTreeMap<String, SyscallStats>,
+    /// The number of VM instructions executed, each consuming one compute
+    /// unit.
+    pub vm_instruction_count: u64,
+    /// The compute units consumed by the profiled instructions, as measured
+    /// by the compute meter.
+    pub compute_units_consumed: u64,
+}
+
+impl SyscallReport {
+    /// The compute units which couldn't be attributed to VM instructions or
+    /// syscalls, such as those consumed by builtin programs invoked by
+    /// top-level instructions.
+    pub fn unattributed_compute_units(&self) -> u64 {
+        let attributed = self
+            .syscalls
+            .values()
+            .map(|stats| stats.compute_units)
+            .sum::<u64>()
+            .saturating_add(self.vm_instruction_count);
+        self.compute_units_consumed.saturating_sub(attributed)
+    }
+
+    /// The syscalls, from the most compute units consumed to the least.
+    pub fn by_compute_units(&self) -> Vec<(&str, &SyscallStats)> {
+        let mut syscalls = self
+            .syscalls
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+            .collect::<Vec<_>>();
+        syscalls.sort_by(|a, b| b.1.compute_units.cmp(&a.1.compute_units));
+        syscalls
+    }
+}
+
+impl std::fmt::Display for SyscallReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<36} {:>8} {:>12}", "syscall", "calls", "CUs")?;
+        for (name, stats) in self.by_compute_units() {
+            writeln!(
+                f,
+                "{:<36} {:>8} {:>12}",
+                name, stats.calls, stats.compute_units
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<36} {:>8} {:>12}",
+            "(vm instructions)", self.vm_instruction_count, self.vm_instruction_count
+        )?;
+        writeln!(
+            f,
+            "{:<36} {:>8} {:>12}",
+            "(unattributed)",
+            "",
+            self.unattributed_compute_units()
+        )?;
+        write!(
+            f,
+            "{:<36} {:>8} {:>12}",
+            "total", "", self.compute_units_consumed
+        )
+    }
+}
+
+/// An invocation callback which attributes compute units to the syscalls
+/// invoked by SBF programs, CPIs included.
+///
+/// When register tracing is enabled, the syscalls of programs loaded into the
+/// cache are wrapped, and the compute meter is read before and after each
+/// call, so the compute units reported are the ones the runtime actually
+/// charged, whatever the compute budget or the call's arguments. VM
+/// instructions are counted from the register traces.
+///
+/// Register tracing must be enabled, such as with `Mollusk::new_debuggable`.
+/// The report is shared, so keep a handle to it before installing the
+/// callback:
+///
+/// ```rust,ignore
+/// let mut mollusk = Mollusk::new_debuggable(&program_id, "my_program", true);
+/// let profiler = SyscallProfileCallback::new();
+/// let report = profiler.report();
+/// mollusk.invocation_inspect_callback = Box::new(profiler);
+///
+/// mollusk.process_instruction(&instruction, &accounts);
+/// println!("{}", report.borrow());
+/// ```
+#[derive(Default)]
+pub struct SyscallProfileCallback {
+    report: Rc<RefCell<SyscallReport>>,
+    remaining_before: Cell<u64>,
+    // The number of traces recorded by the invoke context before the
+    // current instruction, which were already profiled.
+    profiled_traces: Cell<usize>,
+}
+
+impl SyscallProfileCallback {
+    /// Create a callback with an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A handle to the report, updated after every instruction.
+    pub fn report(&self) -> Rc<RefCell<SyscallReport>> {
+        Rc::clone(&self.report)
+    }
+}
+
+impl InvocationInspectCallback for SyscallProfileCallback {
+    fn before_invocation(
+        &self,
+        _: &Mollusk,
+        _: &Pubkey,
+        _: &[u8],
+        _: &[InstructionAccount],
+        invoke_context: &InvokeContext,
+    ) {
+        self.remaining_before.set(invoke_context.get_remaining());
+        MEASURED_SYSCALLS.with(|measured| measured.replace(Some(Vec::new())));
+        let profiled_traces = Cell::new(0);
+        invoke_context.iterate_vm_traces(
+            &|_: InstructionContext, _: &Executable, _: RegisterTrace| {
+                profiled_traces.set(profiled_traces.get() + 1);
+            },
+        );
+        self.profiled_traces.set(profiled_traces.get());
+    }
+
+    fn after_invocation(
+        &self,
+        _: &Mollusk,
+        invoke_context: &InvokeContext,
+        register_tracing_enabled: bool,
+    ) {
+        let measured = MEASURED_SYSCALLS
+            .with(|measured| measured.take())
+            .unwrap_or_default();
+        if !register_tracing_enabled {
+            return;
+        }
+        let mut report = self.report.borrow_mut();
+        report.compute_units_consumed += self
+            .remaining_before
+            .get()
+            .saturating_sub(invoke_context.get_remaining());
+        for (name, compute_units) in measured {
+            let stats = report.syscalls.entry(name).or_default();
+            stats.calls += 1;
+            stats.compute_units += compute_units;
+        }
+
+        let report = RefCell::new(&mut *report);
+        let index = Cell::new(0);
+        invoke_context.iterate_vm_traces(
+            &|_: InstructionContext, _: &Executable, register_trace: RegisterTrace| {
+                let skip = index.get() < self.profiled_traces.get();
+                index.set(index.get() + 1);
+                if !skip {
+                    report.borrow_mut().vm_instruction_count += register_trace.len() as u64;
+                }
+            },
+        );
+    }
+}
+
+/// The most syscalls which can be metered, since each needs a wrapper of its
+/// own.
+const MAX_METERED_SYSCALLS: usize = 256;
+
+// The syscalls wrapped by `meter_syscall`, by wrapper index.
+static METERED_SYSCALLS: Mutex<Vec<(String, Syscall)>> = Mutex::new(Vec::new());
+
+thread_local! {
+    // The syscalls measured on this thread, and the compute units each
+    // consumed, while a `SyscallProfileCallback` is profiling.
+    static MEASURED_SYSCALLS: RefCell<Option<Vec<(String, u64)>>> = const { RefCell::new(None) };
+    // For each metered syscall in progress, the compute units consumed by
+    // the syscalls nested in it, such as those made by a CPI's callee.
+    static NESTED_SYSCALL_UNITS: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    // The compute meter readings around the syscalls made on this thread,
+    // in the order they returned, while a `JsonTraceCallback` is tracing.
+    static SYSCALL_METER_READINGS: RefCell<Option<Vec<SyscallMeterReading>>> =
+        const { RefCell::new(None) };
+}
+
+/// The compute meter read around a syscall.
+struct SyscallMeterReading {
+    /// The stack height of the program which made the syscall.
+    stack_height: usize,
+    /// The compute units remaining when the syscall was entered, once the VM
+    /// instructions executed so far were consumed.
+    remaining_before: u64,
+    /// The compute units remaining when the syscall returned.
+    remaining_after: u64,
+    /// The number of register traces recorded when the syscall returned, so
+    /// the trace of a program invoked through a CPI is the last of them.
+    traces_after: usize,
+}
+
+/// Wrap a syscall so the compute units it consumes are measured while a
+/// `SyscallProfileCallback` is profiling. Syscalls beyond
+/// `MAX_METERED_SYSCALLS` are returned as is.
+pub(crate) fn meter_syscall(name: &str, function: Syscall) -> Syscall {
+    let mut metered = METERED_SYSCALLS.lock().unwrap();
+    let index = match metered
+        .iter()
+        .position(|(n, f)| n == name && *f as usize == function as usize)
+    {
+        Some(index) => index,
+        None if metered.len() < MAX_METERED_SYSCALLS => {
+            metered.push((name.to_string(), function));
+            metered.len() - 1
+        }
+        None => return function,
+    };
+    METERED_SYSCALL_WRAPPERS[index / 16][index % 16]
+}
+
+/// The VM a syscall is invoked with. Syscalls receive a pointer to the VM
+/// offset by the runtime environment key.
+///
+/// # Safety
+///
+/// The pointer must be the one the VM passed to the syscall.
+unsafe fn syscall_vm<'a>(
+    vm: *mut EbpfVm<'a, InvokeContext<'static, 'static>>,
+) -> &'a EbpfVm<'a, InvokeContext<'static, 'static>> {
+    &*vm.cast::<u64>()
+        .offset(-(get_runtime_environment_key() as isize))
+        .cast::<EbpfVm<'a, InvokeContext<'static, 'static>>>()
+}
+
+/// The number of register traces recorded so far, and the number of VM
+/// instructions in them.
+fn recorded_traces(invoke_context: &InvokeContext) -> (usize, u64) {
+    let traces = Cell::new(0);
+    let instructions = Cell::new(0);
+    invoke_context.iterate_vm_traces(
+        &|_: InstructionContext, _: &Executable, register_trace: RegisterTrace| {
+            traces.set(traces.get() + 1);
+            instructions.set(instructions.get() + register_trace.len() as u64);
+        },
+    );
+    (traces.get(), instructions.get())
+}
+
+fn metered_syscall<const N: usize>(
+    vm: *mut EbpfVm<InvokeContext<'static, 'static>>,
+    arg0: u64,
+    arg1: u64,
+    arg2: u64,
+    arg3: u64,
+    arg4: u64,
+) {
+    let (name, function) = METERED_SYSCALLS.lock().unwrap()[N].clone();
+    if MEASURED_SYSCALLS.with(|measured| measured.borrow().is_none())
+        && SYSCALL_METER_READINGS.with(|readings| readings.borrow().is_none())
+    {
+        return function(vm, arg0, arg1, arg2, arg3, arg4);
+    }
+
+    let (stack_height, remaining_before, (_, traced_before)) = {
+        let vm = unsafe { syscall_vm(vm) };
+        // The VM instructions executed since the compute meter was last
+        // updated are consumed when the syscall is entered, but aren't part
+        // of its cost.
+        let pending = if vm.loader.get_config().enable_instruction_meter {
+            vm.previous_instruction_meter
+                .saturating_sub(vm.due_insn_count)
+        } else {
+            0
+        };
+        (
+            vm.context_object_pointer.get_stack_height(),
+            vm.context_object_pointer
+                .get_remaining()
+                .saturating_sub(pending),
+            recorded_traces(vm.context_object_pointer),
+        )
+    };
+
+    NESTED_SYSCALL_UNITS.with(|nested| nested.borrow_mut().push(0));
+    function(vm, arg0, arg1, arg2, arg3, arg4);
+    let nested_units = NESTED_SYSCALL_UNITS
+        .with(|nested| nested.borrow_mut().pop())
+        .unwrap_or_default();
+
+    let (remaining_after, (traces_after, traced_after)) = {
+        let vm = unsafe { syscall_vm(vm) };
+        (
+            vm.context_object_pointer.get_remaining(),
+            recorded_traces(vm.context_object_pointer),
+        )
+    };
+    SYSCALL_METER_READINGS.with(|readings| {
+        if let Some(readings) = readings.borrow_mut().as_mut() {
+            readings.push(SyscallMeterReading {
+                stack_height,
+                remaining_before,
+                remaining_after,
+                traces_after,
+            });
+        }
+    });
+
+    // The VM instructions executed and syscalls made by programs invoked
+    // through a CPI are reported on their own.
+    let compute_units = remaining_before
+        .saturating_sub(remaining_after)
+        .saturating_sub(nested_units)
+        .saturating_sub(traced_after.saturating_sub(traced_before));
+    NESTED_SYSCALL_UNITS.with(|nested| {
+        if let Some(parent) = nested.borrow_mut().last_mut() {
+            *parent += compute_units + nested_units;
+        }
+    });
+    MEASURED_SYSCALLS.with(|measured| {
+        if let Some(measured) = measured.borrow_mut().as_mut() {
+            measured.push((name, compute_units));
+        }
+    });
+}
+
+macro_rules! metered_syscall_row {
+    ($row:literal) => {
+        [
+            metered_syscall::<{ $row * 16 }>,
+            metered_syscall::<{ $row * 16 + 1 }>,
+            metered_syscall::<{ $row * 16 + 2 }>,
+            metered_syscall::<{ $row * 16 + 3 }>,
+            metered_syscall::<{ $row * 16 + 4 }>,
+            metered_syscall::<{ $row * 16 + 5 }>,
+            metered_syscall::<{ $row * 16 + 6 }>,
+            metered_syscall::<{ $row * 16 + 7 }>,
+            metered_syscall::<{ $row * 16 + 8 }>,
+            metered_syscall::<{ $row * 16 + 9 }>,
+            metered_syscall::<{ $row * 16 + 10 }>,
+            metered_syscall::<{ $row * 16 + 11 }>,
+            metered_syscall::<{ $row * 16 + 12 }>,
+            metered_syscall::<{ $row * 16 + 13 }>,
+            metered_syscall::<{ $row * 16 + 14 }>,
+            metered_syscall::<{ $row * 16 + 15 }>,
+        ]
+    };
+}
+
+// Syscalls are plain function pointers, so each metered syscall is wrapped
+// by a function of its own, which looks the syscall up by index.
+static METERED_SYSCALL_WRAPPERS: [[Syscall; 16]; MAX_METERED_SYSCALLS / 16] = [
+    metered_syscall_row!(0),
+    metered_syscall_row!(1),
+    metered_syscall_row!(2),
+    metered_syscall_row!(3),
+    metered_syscall_row!(4),
+    metered_syscall_row!(5),
+    metered_syscall_row!(6),
+    metered_syscall_row!(7),
+    metered_syscall_row!(8),
+    metered_syscall_row!(9),
+    metered_syscall_row!(10),
+    metered_syscall_row!(11),
+    metered_syscall_row!(12),
+    metered_syscall_row!(13),
+    metered_syscall_row!(14),
+    metered_syscall_row!(15),
+];
+
+/// Count the VM instructions and syscalls in every register trace recorded
+/// so far, adding them to the profile.
+pub(crate) fn profile_vm_traces(invoke_context: &InvokeContext, profile: &mut ExecutionProfile) {
+    let profile = RefCell::new(profile);
+    invoke_context.iterate_vm_traces(
+        &|_: InstructionContext, executable: &Executable, register_trace: RegisterTrace| {
+            let mut profile = profile.borrow_mut();
+            *profile.vm_instruction_count.get_or_insert(0) += register_trace.len() as u64;
+
+            let (_, program) = executable.get_text_bytes();
+            for regs in register_trace.iter() {
+                // The program counter is stored in r11.
+                let insn = ebpf::get_insn_unchecked(program, regs[11] as usize);
+                if let Some(name) = invoked_syscall(executable, &insn) {
+                    *profile
+                        .syscall_counts
+                        .entry(String::from_utf8_lossy(name).into_owned())
+                        .or_default() += 1;
+                }
+            }
+        },
+    );
+}
+
+/// The name of the syscall an instruction invokes, if any.
+///
+/// SBPF v3 programs invoke syscalls with the dedicated `syscall`
+/// instruction, while earlier versions use `call imm`, which invokes a
+/// syscall when its key is registered with the loader.
+fn invoked_syscall<'a>(executable: &'a Executable, insn: &ebpf::Insn) -> Option<&'a [u8]> {
+    let syscall_opc = if executable.get_sbpf_version().static_syscalls() {
+        ebpf::SYSCALL
+    } else {
+        ebpf::CALL_IMM
+    };
+    if insn.opc != syscall_opc {
+        return None;
+    }
+    executable
+        .get_loader()
+        .get_function_registry()
+        .lookup_by_key(insn.imm as u32)
+        .map(|(name, _)| name)
+}
+
 pub(crate) fn as_bytes<T>(slice: &[T]) -> &[u8] {
     unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const u8, std::mem::size_of_val(slice)) }
 }
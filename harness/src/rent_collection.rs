@@ -0,0 +1,85 @@
+//! Opt-in rent collection simulation.
+//!
+//! By default, Mollusk never collects rent, nor touches an account's
+//! `rent_epoch`. Set `Mollusk::collect_rent` to have every instruction or
+//! transaction collect rent from its writable accounts before execution, the
+//! way the runtime did for rent-paying accounts, using the harness's `Rent`
+//! sysvar and the current epoch. Readonly accounts are never charged.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::Mollusk;
+//!
+//! let mut mollusk = Mollusk::default();
+//! mollusk.collect_rent = true;
+//!
+//! // Accounts which aren't rent-exempt are debited the rent due since their
+//! // `rent_epoch`, or deleted if they can't pay it.
+//! mollusk.warp_to_slot(1_000_000);
+//! let result = mollusk.process_instruction(&instruction, &accounts);
+//! ```
+//!
+//! Rent-exempt accounts are marked as such by setting their `rent_epoch` to
+//! `RENT_EXEMPT_RENT_EPOCH`. Executable accounts and the incinerator are
+//! never charged.
+
+use {
+    trezoa_account::{AccountSharedData, ReadableAccount, WritableAccount},
+    trezoa_clock::Epoch,
+    trezoa_epoch_schedule::EpochSchedule,
+    trezoa_pubkey::Pubkey,
+    trezoa_rent::{Rent, ACCOUNT_STORAGE_OVERHEAD},
+};
+
+/// The `rent_epoch` of rent-exempt accounts, which are never charged rent.
+pub const RENT_EXEMPT_RENT_EPOCH: Epoch = Epoch::MAX;
+
+// The number of slots in a year, at the default 160 ticks per second and 64
+// ticks per slot.
+const SLOTS_PER_YEAR: f64 = 365.242_199 * 24.0 * 60.0 * 60.0 * 160.0 / 64.0;
+
+/// Collect the rent an account owes as of `epoch`, returning the lamports
+/// collected.
+///
+/// Rent is due for every epoch from the account's `rent_epoch` through
+/// `epoch`, after which the account's `rent_epoch` is advanced to the next
+/// epoch. An account which can't pay the rent due is reset to an empty
+/// account, which the runtime treats as deleted.
+pub fn collect_rent(
+    rent: &Rent,
+    epoch_schedule: &EpochSchedule,
+    epoch: Epoch,
+    pubkey: &Pubkey,
+    account: &mut AccountSharedData,
+) -> u64 {
+    if account.executable() || *pubkey == trezoa_sdk_ids::incinerator::id() {
+        return 0;
+    }
+    if rent.is_exempt(account.lamports(), account.data().len()) {
+        account.set_rent_epoch(RENT_EXEMPT_RENT_EPOCH);
+        return 0;
+    }
+    if account.rent_epoch() > epoch {
+        // Already paid up.
+        return 0;
+    }
+
+    // Like the runtime, each epoch is charged for the slots in the epoch
+    // which follows it.
+    let slots_elapsed = (account.rent_epoch()..=epoch)
+        .map(|epoch| epoch_schedule.get_slots_in_epoch(epoch.saturating_add(1)))
+        .fold(0u64, u64::saturating_add);
+    let years_elapsed = slots_elapsed as f64 / SLOTS_PER_YEAR;
+    let bytes = ACCOUNT_STORAGE_OVERHEAD.saturating_add(account.data().len() as u64);
+    let rent_due =
+        (bytes.saturating_mul(rent.lamports_per_byte_year) as f64 * years_elapsed) as u64;
+
+    if rent_due >= account.lamports() {
+        let collected = account.lamports();
+        *account = AccountSharedData::default();
+        return collected;
+    }
+
+    account.set_lamports(account.lamports() - rent_due);
+    account.set_rent_epoch(epoch.saturating_add(1));
+    rent_due
+}
@@ -0,0 +1,197 @@
+//! Caching of the program runtime environments.
+//!
+//! Every message Mollusk processes needs the v1 and v2 program runtime
+//! environments, which register every syscall and are costly to build. They
+//! only depend on the feature set, the call depth and stack frame size of the
+//! compute budget, whether register tracing is enabled, and the custom
+//! syscalls registered with `Mollusk::register_syscall`, so they're built
+//! once per configuration and reused. Other compute budget fields, such as
+//! the compute unit limit, don't affect them.
+//!
+//! Only the most recently used configurations are kept, so harnesses whose
+//! configuration keeps changing, such as fuzzing campaigns, don't accumulate
+//! environments. See `RuntimeEnvironmentCache::with_capacity`.
+//!
+//! The cache is a shared handle, so harnesses with the same configuration
+//! can reuse each other's environments:
+//!
+//! ```rust,ignore
+//! let first = Mollusk::new(&program_id, "my_program");
+//! let mut second = Mollusk::new(&program_id, "my_program");
+//! second.runtime_environments = first.runtime_environments.clone();
+//! ```
+
+use {
+    crate::program::Syscall,
+    trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_feature_set::FeatureSet,
+    trezoa_program_runtime::loaded_programs::ProgramRuntimeEnvironments,
+    trezoa_syscalls::{
+        create_program_runtime_environment_v1, create_program_runtime_environment_v2,
+    },
+    std::{cell::RefCell, rc::Rc, sync::Arc},
+};
+
+struct CachedEnvironments {
+    max_call_depth: usize,
+    stack_frame_size: usize,
+    enable_register_tracing: bool,
+    syscalls: Vec<(String, Syscall)>,
+    feature_set: FeatureSet,
+    environments: ProgramRuntimeEnvironments,
+}
+
+impl CachedEnvironments {
+    fn matches(
+        &self,
+        feature_set: &FeatureSet,
+        compute_budget: &ComputeBudget,
+        enable_register_tracing: bool,
+        syscalls: &[(String, Syscall)],
+    ) -> bool {
+        // The cheap fields are compared first, and the feature set last.
+        self.max_call_depth == compute_budget.max_call_depth
+            && self.stack_frame_size == compute_budget.stack_frame_size
+            && self.enable_register_tracing == enable_register_tracing
+            && same_syscalls(&self.syscalls, syscalls)
+            && self.feature_set == *feature_set
+    }
+}
+
+fn same_syscalls(a: &[(String, Syscall)], b: &[(String, Syscall)]) -> bool {
+    // Syscalls are compared by address, since harnesses sharing the cache
+    // may register different functions under the same name.
+    a.len() == b.len()
+        && a.iter().zip(b).all(|((a_name, a_fn), (b_name, b_fn))| {
+            a_name == b_name && *a_fn as usize == *b_fn as usize
+        })
+}
+
+/// A shared cache of program runtime environments, keyed by the
+/// configuration they were built for.
+///
+/// Cloning the cache yields a handle to the same environments.
+#[derive(Clone)]
+pub struct RuntimeEnvironmentCache {
+    // Most recently used first.
+    entries: Rc<RefCell<Vec<CachedEnvironments>>>,
+    capacity: usize,
+}
+
+impl Default for RuntimeEnvironmentCache {
+    fn default() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl RuntimeEnvironmentCache {
+    /// The number of configurations a cache keeps environments for by
+    /// default.
+    pub const DEFAULT_CAPACITY: usize = 4;
+
+    /// Create a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new, empty cache, which keeps the environments of up to
+    /// `capacity` configurations. Once full, the environments of the least
+    /// recently used configuration are dropped.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Rc::new(RefCell::new(Vec::with_capacity(capacity))),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Get the environments for the provided configuration, building them if
+    /// they aren't cached yet. Changing the feature set, call depth or stack
+    /// frame size yields different environments, so there's no need to
+    /// invalidate the cache. Building environments for a new configuration
+    /// evicts the least recently used one once the cache is full.
+    ///
+    /// The custom syscalls are registered on the v1 environment, unless the
+    /// feature set already provides a syscall with the same name.
+    pub fn get(
+        &self,
+        feature_set: &FeatureSet,
+        compute_budget: &ComputeBudget,
+        enable_register_tracing: bool,
+        syscalls: &[(String, Syscall)],
+    ) -> ProgramRuntimeEnvironments {
+        let mut entries = self.entries.borrow_mut();
+        if let Some(index) = entries.iter().position(|cached| {
+            cached.matches(
+                feature_set,
+                compute_budget,
+                enable_register_tracing,
+                syscalls,
+            )
+        }) {
+            let cached = entries.remove(index);
+            let environments = cached.environments.clone();
+            entries.insert(0, cached);
+            return environments;
+        }
+
+        let execution_budget = compute_budget.to_budget();
+        let mut program_runtime_v1 = create_program_runtime_environment_v1(
+            &feature_set.runtime_features(),
+            &execution_budget,
+            /* reject_deployment_of_broken_elfs */ false,
+            /* debugging_features */ enable_register_tracing,
+        )
+        .unwrap();
+        for (name, function) in syscalls {
+            if program_runtime_v1
+                .get_function_registry()
+                .lookup_by_name(name.as_bytes())
+                .is_none()
+            {
+                program_runtime_v1
+                    .register_function(name, *function)
+                    .unwrap();
+            }
+        }
+        let environments = ProgramRuntimeEnvironments {
+            program_runtime_v1: Arc::new(program_runtime_v1),
+            program_runtime_v2: Arc::new(create_program_runtime_environment_v2(
+                &execution_budget,
+                /* debugging_features */ enable_register_tracing,
+            )),
+        };
+        entries.truncate(self.capacity - 1);
+        entries.insert(
+            0,
+            CachedEnvironments {
+                max_call_depth: compute_budget.max_call_depth,
+                stack_frame_size: compute_budget.stack_frame_size,
+                enable_register_tracing,
+                syscalls: syscalls.to_vec(),
+                feature_set: feature_set.clone(),
+                environments: environments.clone(),
+            },
+        );
+        environments
+    }
+
+    /// The maximum number of configurations with cached environments.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of configurations with cached environments.
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    /// Whether no environments are cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    /// Drop every cached environment.
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
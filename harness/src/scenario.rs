@@ -0,0 +1,254 @@
+//! Reusable account-set templates.
+//!
+//! Test suites tend to repeat the same account setup across tests, such as a
+//! funded payer, an initialized mint and an associated token account. A
+//! `Scenario` captures that setup once, as a named bundle of accounts, each
+//! built from the keys of the bundle. Scenarios can be merged into larger
+//! ones, and every instantiation assigns fresh keys, so tests don't share
+//! addresses.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::scenario::Scenario;
+//!
+//! let payer = Scenario::new("payer").account("payer", |_| {
+//!     Account::new(1_000_000_000, 0, &system_program::id())
+//! });
+//! let mint = Scenario::new("mint")
+//!     .account("mint", |keys| mint_account(&keys["payer"]))
+//!     .derived_account(
+//!         "ata",
+//!         |keys| get_associated_token_address(&keys["payer"], &keys["mint"]),
+//!         |keys| token_account(&keys["payer"], &keys["mint"]),
+//!     );
+//!
+//! let funded_mint = payer.merge(mint);
+//!
+//! let instance = funded_mint.instantiate();
+//! let instruction = transfer(&instance.key("ata"), &instance.key("payer"));
+//! mollusk.process_instruction(&instruction, &instance);
+//! ```
+//!
+//! Account builders run once every key is known, so an account can refer to
+//! any key in the scenario. Derived addresses are computed in order, so they
+//! can only refer to the keys of accounts declared before them.
+
+use {
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::{
+        ops::{Deref, Index},
+        rc::Rc,
+    },
+};
+
+type AddressFn = dyn Fn(&ScenarioKeys) -> Pubkey;
+type AccountFn = dyn Fn(&ScenarioKeys) -> Account;
+
+#[derive(Clone)]
+struct ScenarioAccount {
+    name: String,
+    address: Option<Rc<AddressFn>>,
+    account: Rc<AccountFn>,
+}
+
+/// A named, reusable bundle of accounts.
+#[derive(Clone)]
+pub struct Scenario {
+    name: String,
+    accounts: Vec<ScenarioAccount>,
+}
+
+impl Scenario {
+    /// Create a new, empty scenario.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            accounts: Vec::new(),
+        }
+    }
+
+    /// The name of the scenario.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The names of the scenario's accounts, in order.
+    pub fn account_names(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().map(|account| account.name.as_str())
+    }
+
+    /// Add an account, assigned a fresh key on every instantiation.
+    ///
+    /// If the scenario already has an account with the same name, it's
+    /// replaced.
+    pub fn account(self, name: &str, account: impl Fn(&ScenarioKeys) -> Account + 'static) -> Self {
+        self.push(ScenarioAccount {
+            name: name.to_string(),
+            address: None,
+            account: Rc::new(account),
+        })
+    }
+
+    /// Add an account whose address is derived from the keys of the accounts
+    /// declared before it, such as a PDA or an associated token account.
+    ///
+    /// If the scenario already has an account with the same name, it's
+    /// replaced.
+    pub fn derived_account(
+        self,
+        name: &str,
+        address: impl Fn(&ScenarioKeys) -> Pubkey + 'static,
+        account: impl Fn(&ScenarioKeys) -> Account + 'static,
+    ) -> Self {
+        self.push(ScenarioAccount {
+            name: name.to_string(),
+            address: Some(Rc::new(address)),
+            account: Rc::new(account),
+        })
+    }
+
+    /// Compose two scenarios. The accounts of `other` are appended to those
+    /// of `self`, except for accounts sharing a name, which `other` replaces
+    /// in place.
+    ///
+    /// Accounts of `other` can refer to the keys of `self` by name, which
+    /// allows a scenario to build on accounts it doesn't declare itself.
+    pub fn merge(mut self, other: Scenario) -> Self {
+        self.name = format!("{} + {}", self.name, other.name);
+        other
+            .accounts
+            .into_iter()
+            .fold(self, |scenario, account| scenario.push(account))
+    }
+
+    /// Instantiate the scenario, assigning every account a key from
+    /// `Pubkey::new_unique()`.
+    pub fn instantiate(&self) -> ScenarioInstance {
+        self.instantiate_with(&mut std::iter::repeat_with(Pubkey::new_unique))
+    }
+
+    /// Instantiate the scenario, assigning every account a key from the
+    /// provided generator, such as a seeded `KeyGen`.
+    pub fn instantiate_with(&self, keys: &mut impl Iterator<Item = Pubkey>) -> ScenarioInstance {
+        let mut scenario_keys = ScenarioKeys { keys: Vec::new() };
+        for account in &self.accounts {
+            let key = match &account.address {
+                Some(address) => address(&scenario_keys),
+                None => keys.next().expect("Key generator exhausted"),
+            };
+            scenario_keys.keys.push((account.name.clone(), key));
+        }
+
+        let accounts = self
+            .accounts
+            .iter()
+            .zip(&scenario_keys.keys)
+            .map(|(account, (_, key))| (*key, (account.account)(&scenario_keys)))
+            .collect();
+
+        ScenarioInstance {
+            keys: scenario_keys,
+            accounts,
+        }
+    }
+
+    fn push(mut self, account: ScenarioAccount) -> Self {
+        match self
+            .accounts
+            .iter_mut()
+            .find(|existing| existing.name == account.name)
+        {
+            Some(existing) => *existing = account,
+            None => self.accounts.push(account),
+        }
+        self
+    }
+}
+
+/// The keys assigned to a scenario's accounts, by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScenarioKeys {
+    keys: Vec<(String, Pubkey)>,
+}
+
+impl ScenarioKeys {
+    /// The key of the named account, if it's been assigned.
+    pub fn get(&self, name: &str) -> Option<&Pubkey> {
+        self.keys
+            .iter()
+            .find(|(account_name, _)| account_name == name)
+            .map(|(_, key)| key)
+    }
+}
+
+impl Index<&str> for ScenarioKeys {
+    type Output = Pubkey;
+
+    fn index(&self, name: &str) -> &Pubkey {
+        self.get(name)
+            .unwrap_or_else(|| panic!("Scenario has no account named `{}`", name))
+    }
+}
+
+/// An instantiated scenario, with keyed accounts ready for the process
+/// methods.
+///
+/// Dereferences to the `(Pubkey, Account)` slice, in the order the accounts
+/// were declared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioInstance {
+    keys: ScenarioKeys,
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+impl ScenarioInstance {
+    /// The keys assigned to the accounts, by name.
+    pub fn keys(&self) -> &ScenarioKeys {
+        &self.keys
+    }
+
+    /// The key of the named account.
+    ///
+    /// Panics if the scenario has no such account.
+    pub fn key(&self, name: &str) -> Pubkey {
+        self.keys[name]
+    }
+
+    /// The named account.
+    ///
+    /// Panics if the scenario has no such account.
+    pub fn account(&self, name: &str) -> &Account {
+        &self.keyed_account(name).1
+    }
+
+    /// The named account, along with its key.
+    ///
+    /// Panics if the scenario has no such account.
+    pub fn keyed_account(&self, name: &str) -> &(Pubkey, Account) {
+        let index = self
+            .keys
+            .keys
+            .iter()
+            .position(|(account_name, _)| account_name == name)
+            .unwrap_or_else(|| panic!("Scenario has no account named `{}`", name));
+        &self.accounts[index]
+    }
+
+    /// The keyed accounts, in the order they were declared.
+    pub fn accounts(&self) -> &[(Pubkey, Account)] {
+        &self.accounts
+    }
+
+    /// Consume the instance, returning the keyed accounts.
+    pub fn into_accounts(self) -> Vec<(Pubkey, Account)> {
+        self.accounts
+    }
+}
+
+impl Deref for ScenarioInstance {
+    type Target = [(Pubkey, Account)];
+
+    fn deref(&self) -> &Self::Target {
+        &self.accounts
+    }
+}
@@ -0,0 +1,81 @@
+//! Zero-setup snapshot tests.
+//!
+//! `mollusk_snapshot!` processes an instruction and compares the full result
+//! against a snapshot file, without any checks to write. The snapshot lives
+//! at `tests/snapshots/<module>__<test>.snap`, relative to the calling
+//! crate's manifest directory. It's created on the first run, and can be
+//! regenerated by setting `MOLLUSK_UPDATE_SNAPSHOTS`.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::mollusk_snapshot;
+//!
+//! #[test]
+//! fn test_transfer() {
+//!     let mollusk = Mollusk::default();
+//!     mollusk_snapshot!(mollusk, &instruction, &accounts);
+//!
+//!     // Several snapshots in the same test need a name.
+//!     mollusk_snapshot!(mollusk, &other_instruction, &accounts, "other");
+//! }
+//! ```
+//!
+//! See the result crate's `snapshot` module for the snapshot format.
+
+use std::path::PathBuf;
+
+/// Derive the path of an automatically managed snapshot, from the path of
+/// the function `mollusk_snapshot!` was invoked in.
+#[doc(hidden)]
+pub fn snapshot_path(manifest_dir: &str, function_path: &str, name: Option<&str>) -> PathBuf {
+    // The macro passes the path of a function nested in the test, so drop it,
+    // along with any closures the test was invoked from.
+    let mut segments = function_path.split("::").collect::<Vec<_>>();
+    segments.pop();
+    while segments.last() == Some(&"{{closure}}") {
+        segments.pop();
+    }
+
+    let mut file_name = segments.join("__");
+    if let Some(name) = name {
+        file_name.push_str("__");
+        file_name.push_str(name);
+    }
+    file_name.push_str(".snap");
+
+    PathBuf::from(manifest_dir)
+        .join("tests")
+        .join("snapshots")
+        .join(file_name)
+}
+
+#[doc(hidden)]
+pub fn type_name_of<T>(_: T) -> &'static str {
+    std::any::type_name::<T>()
+}
+
+/// Process an instruction, then compare the result against an automatically
+/// managed snapshot file. Panics if the result doesn't match. See the
+/// `snapshot` module.
+///
+/// ```rust,ignore
+/// mollusk_snapshot!(mollusk, &instruction, &accounts);
+/// mollusk_snapshot!(mollusk, &instruction, &accounts, "named");
+/// ```
+#[macro_export]
+macro_rules! mollusk_snapshot {
+    ($mollusk:expr, $instruction:expr, $accounts:expr $(,)?) => {
+        $crate::mollusk_snapshot!(@inner $mollusk, $instruction, $accounts, None)
+    };
+    ($mollusk:expr, $instruction:expr, $accounts:expr, $name:expr $(,)?) => {
+        $crate::mollusk_snapshot!(@inner $mollusk, $instruction, $accounts, Some($name))
+    };
+    (@inner $mollusk:expr, $instruction:expr, $accounts:expr, $name:expr) => {{
+        fn f() {}
+        let path = $crate::snapshot::snapshot_path(
+            env!("CARGO_MANIFEST_DIR"),
+            $crate::snapshot::type_name_of(f),
+            $name,
+        );
+        $mollusk.process_and_validate_snapshot($instruction, $accounts, &path)
+    }};
+}
@@ -0,0 +1,84 @@
+//! Helpers for creating stake accounts.
+//!
+//! Programs which CPI into the stake program, or read stake accounts, need
+//! accounts holding a valid `StakeStateV2`. These helpers build rent-exempt
+//! stake accounts in the states the stake program itself would produce.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{epoch_stake::create_vote_account_with_authorities, stake};
+//!
+//! let rent = &mollusk.sysvars.rent;
+//! let epoch = mollusk.sysvars.clock.epoch;
+//!
+//! let vote_account =
+//!     create_vote_account_with_authorities(&node, &voter, &withdrawer, 10, rent, epoch);
+//! let stake_account =
+//!     stake::create_delegated_stake_account(&authority, &vote_address, 1_000_000_000, rent, epoch);
+//! ```
+//!
+//! To process stake and vote instructions, enable the `all-builtins` feature.
+
+use {
+    trezoa_account::Account,
+    trezoa_clock::Epoch,
+    trezoa_pubkey::Pubkey,
+    trezoa_rent::Rent,
+    trezoa_stake_interface::{
+        stake_flags::StakeFlags,
+        state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2},
+    },
+};
+
+fn create_stake_account(lamports: u64, state: &StakeStateV2) -> Account {
+    let mut data = vec![0; StakeStateV2::size_of()];
+    bincode::serialize_into(&mut data[..], state).unwrap();
+    Account {
+        lamports,
+        data,
+        owner: trezoa_sdk_ids::stake::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn meta(authority: &Pubkey, rent: &Rent) -> Meta {
+    Meta {
+        rent_exempt_reserve: rent.minimum_balance(StakeStateV2::size_of()),
+        authorized: Authorized {
+            staker: *authority,
+            withdrawer: *authority,
+        },
+        lockup: Lockup::default(),
+    }
+}
+
+/// Create a rent-exempt, initialized stake account which isn't delegated,
+/// with `authority` as both the staker and withdrawer.
+pub fn create_initialized_stake_account(authority: &Pubkey, rent: &Rent) -> Account {
+    let meta = meta(authority, rent);
+    create_stake_account(meta.rent_exempt_reserve, &StakeStateV2::Initialized(meta))
+}
+
+/// Create a stake account delegating `stake` lamports to the provided vote
+/// account, activated at `activation_epoch`, with `authority` as both the
+/// staker and withdrawer.
+///
+/// The account's balance is the rent-exempt reserve plus `stake`.
+pub fn create_delegated_stake_account(
+    authority: &Pubkey,
+    vote_address: &Pubkey,
+    stake: u64,
+    rent: &Rent,
+    activation_epoch: Epoch,
+) -> Account {
+    let meta = meta(authority, rent);
+    let lamports = meta.rent_exempt_reserve.saturating_add(stake);
+    let stake = Stake {
+        delegation: Delegation::new(vote_address, stake, activation_epoch),
+        credits_observed: 0,
+    };
+    create_stake_account(
+        lamports,
+        &StakeStateV2::Stake(meta, stake, StakeFlags::empty()),
+    )
+}
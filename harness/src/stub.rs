@@ -0,0 +1,168 @@
+//! Programs stubbed with Rust closures.
+//!
+//! A stub stands in for a program without an ELF, so a program's CPI
+//! handling can be tested against a mock callee. Register one with
+//! `Mollusk::stub_program`:
+//!
+//! ```rust,ignore
+//! let stub_account = mollusk.stub_program(&callee_id, |data, accounts| {
+//!     accounts[0].try_borrow_mut_data()?.copy_from_slice(data);
+//!     Ok(())
+//! });
+//! ```
+//!
+//! Stubs are registered in the program cache as builtins, so they can be
+//! invoked directly or through CPI. Like any builtin invoked through CPI,
+//! the stub's program account must be provided. `stub_program` returns it.
+//!
+//! The handler receives the instruction's accounts serialized the way the
+//! loaders serialize them for SBF programs, so its `AccountInfo`s behave as
+//! they would on chain: accounts passed more than once share their state,
+//! and account data can grow by up to `MAX_PERMITTED_DATA_INCREASE` bytes
+//! with `AccountInfo::resize`. Once the handler returns successfully,
+//! changes to lamports, data, and owners are written back, subject to the
+//! runtime's usual rules.
+
+use {
+    trezoa_account_info::{AccountInfo, MAX_PERMITTED_DATA_INCREASE},
+    trezoa_instruction_error::InstructionError,
+    trezoa_program_entrypoint::{deserialize, BPF_ALIGN_OF_U128, NON_DUP_MARKER},
+    trezoa_program_error::ProgramResult,
+    trezoa_program_runtime::{declare_process_instruction, invoke_context::InvokeContext},
+    trezoa_pubkey::Pubkey,
+    trezoa_transaction_context::{IndexOfAccount, InstructionContext},
+    std::{cell::RefCell, collections::HashMap, rc::Rc},
+};
+
+/// The name stored in the program account of a stub.
+pub const STUB_PROGRAM_NAME: &str = "mollusk_stub_program";
+
+/// The compute units consumed by each invocation of a stub.
+pub const STUB_COMPUTE_UNITS: u64 = 150;
+
+/// A handler for a stubbed program, receiving the instruction data and the
+/// instruction's accounts.
+pub type StubHandler = dyn Fn(&[u8], &[AccountInfo]) -> ProgramResult;
+
+thread_local! {
+    /// The stubs of the Mollusk instance currently executing on this thread.
+    static ACTIVE_STUBS: RefCell<HashMap<Pubkey, Rc<StubHandler>>> = RefCell::default();
+}
+
+/// Restores the previously active stubs when dropped.
+pub(crate) struct ActiveStubs {
+    previous: Option<HashMap<Pubkey, Rc<StubHandler>>>,
+}
+
+impl Drop for ActiveStubs {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            ACTIVE_STUBS.with(|stubs| *stubs.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Make a Mollusk instance's stubs available to the stub entrypoint for
+/// the lifetime of the returned guard.
+pub(crate) fn activate(stubs: &HashMap<Pubkey, Rc<StubHandler>>) -> ActiveStubs {
+    if stubs.is_empty() {
+        return ActiveStubs { previous: None };
+    }
+    let previous = ACTIVE_STUBS.with(|active| active.replace(stubs.clone()));
+    ActiveStubs {
+        previous: Some(previous),
+    }
+}
+
+/// Serialize the instruction's accounts, data, and program ID in the
+/// aligned input format of SBF programs. The buffer is backed by `u64`s, so
+/// it's aligned as the entrypoint expects.
+fn serialize_input(instruction_context: &InstructionContext) -> Result<Vec<u64>, InstructionError> {
+    let mut input = Vec::new();
+    let num_accounts = instruction_context.get_number_of_instruction_accounts();
+    input.extend_from_slice(&u64::from(num_accounts).to_le_bytes());
+
+    for index in 0..num_accounts {
+        if let Some(original) = instruction_context.is_instruction_account_duplicate(index)? {
+            input.push(original as u8);
+            input.extend_from_slice(&[0; 7]);
+            continue;
+        }
+        let account = instruction_context.try_borrow_instruction_account(index)?;
+        input.push(NON_DUP_MARKER);
+        input.push(u8::from(account.is_signer()));
+        input.push(u8::from(account.is_writable()));
+        input.push(u8::from(account.is_executable()));
+        // The entrypoint stores the original data length here.
+        input.extend_from_slice(&[0; 4]);
+        input.extend_from_slice(account.get_key().as_ref());
+        input.extend_from_slice(account.get_owner().as_ref());
+        input.extend_from_slice(&account.get_lamports().to_le_bytes());
+        input.extend_from_slice(&(account.get_data().len() as u64).to_le_bytes());
+        input.extend_from_slice(account.get_data());
+        // Room to grow the data, padded to the next aligned offset.
+        let padded_len =
+            (input.len() + MAX_PERMITTED_DATA_INCREASE).next_multiple_of(BPF_ALIGN_OF_U128);
+        input.resize(padded_len, 0);
+        input.extend_from_slice(&account.get_rent_epoch().to_le_bytes());
+    }
+
+    let instruction_data = instruction_context.get_instruction_data();
+    input.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+    input.extend_from_slice(instruction_data);
+    input.extend_from_slice(instruction_context.get_program_key()?.as_ref());
+
+    let mut aligned = vec![0u64; input.len().div_ceil(8)];
+    // SAFETY: `aligned` holds at least `input.len()` bytes.
+    unsafe {
+        std::ptr::copy_nonoverlapping(input.as_ptr(), aligned.as_mut_ptr() as *mut u8, input.len());
+    }
+    Ok(aligned)
+}
+
+declare_process_instruction!(Entrypoint, STUB_COMPUTE_UNITS, |invoke_context| {
+    process_stub(invoke_context)
+});
+
+fn process_stub(invoke_context: &mut InvokeContext) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let program_id = *instruction_context.get_program_key()?;
+    let handler = ACTIVE_STUBS
+        .with(|stubs| stubs.borrow().get(&program_id).cloned())
+        .ok_or(InstructionError::UnsupportedProgramId)?;
+
+    let mut input = serialize_input(&instruction_context)?;
+    // SAFETY: The input was serialized in the format the entrypoint expects,
+    // and outlives the account infos, which only borrow it.
+    let (_, account_infos, instruction_data) =
+        unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+    handler(instruction_data, &account_infos)
+        .map_err(|err| InstructionError::from(u64::from(err)))?;
+
+    // Data can only be changed by the owner, so the owner is written last.
+    for (index, account_info) in account_infos.iter().enumerate() {
+        let index = index as IndexOfAccount;
+        if instruction_context
+            .is_instruction_account_duplicate(index)?
+            .is_some()
+        {
+            continue;
+        }
+        let mut account = instruction_context.try_borrow_instruction_account(index)?;
+        if account.get_lamports() != account_info.lamports() {
+            account.set_lamports(account_info.lamports())?;
+        }
+        let data = account_info
+            .try_borrow_data()
+            .map_err(|_| InstructionError::AccountBorrowFailed)?;
+        if account.get_data() != *data {
+            account.set_data_from_slice(&data)?;
+        }
+        if account.get_owner() != account_info.owner {
+            account.set_owner(account_info.owner.as_ref())?;
+        }
+    }
+    Ok(())
+}
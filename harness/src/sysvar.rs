@@ -1,4 +1,29 @@
 //! Module for working with Trezoa sysvars.
+//!
+//! Programs can read sysvars either from an account passed to the
+//! instruction, or through syscalls such as `sol_get_clock_sysvar` and the
+//! generic `sol_get_sysvar`. Both paths are served by the values stored on
+//! `Sysvars`, unless the caller provides an account for the sysvar, in which
+//! case the provided account's data takes precedence.
+//!
+//! The following sysvars are supported, including partial (offset/length)
+//! reads through `sol_get_sysvar`:
+//!
+//! * `Clock`
+//! * `EpochRewards`
+//! * `EpochSchedule`
+//! * `LastRestartSlot`
+//! * `Rent`
+//! * `SlotHashes`
+//! * `StakeHistory`
+//!
+//! The following sysvars are _not_ served by the sysvar cache. Calling
+//! `sol_get_sysvar` for any of them returns `SYSVAR_NOT_FOUND`, and they must
+//! be provided as accounts if a program reads them:
+//!
+//! * `Fees` and `RecentBlockhashes` (deprecated)
+//! * `Instructions` (account-only; Mollusk creates it automatically)
+//! * `SlotHistory`
 
 use {
     trezoa_account::{Account, ReadableAccount},
@@ -18,6 +43,7 @@ use {
 // Trezoa-team's sysvar cache is difficult to work with, so Mollusk offers a wrapper
 // around it for modifying its contents.
 /// Mollusk sysvars.
+#[derive(Clone)]
 pub struct Sysvars {
     pub clock: Clock,
     pub epoch_rewards: EpochRewards,
@@ -72,7 +98,18 @@ impl Sysvars {
         (T::id(), account)
     }
 
-    pub(crate) fn maybe_create_sysvar_account(&self, pubkey: &Pubkey) -> Option<Account> {
+    /// Get the key and account for a sysvar, by type.
+    ///
+    /// ```rust,ignore
+    /// let (clock_id, clock_account) = mollusk.sysvars.keyed_account::<Clock>();
+    /// ```
+    pub fn keyed_account<T: StoredSysvar>(&self) -> (Pubkey, Account) {
+        self.sysvar_account(T::get(self))
+    }
+
+    /// Create the account for the sysvar with the provided ID, or `None` if
+    /// it isn't one of the sysvars stored on `Sysvars`.
+    pub fn maybe_create_sysvar_account(&self, pubkey: &Pubkey) -> Option<Account> {
         if pubkey.eq(&Clock::id()) {
             Some(self.sysvar_account(&self.clock).1)
         } else if pubkey.eq(&EpochRewards::id()) {
@@ -127,7 +164,8 @@ impl Sysvars {
         self.sysvar_account(&self.stake_history)
     }
 
-    pub(crate) fn get_all_keyed_sysvar_accounts(&self) -> Vec<(Pubkey, Account)> {
+    /// Get the keys and accounts for every sysvar stored on `Sysvars`.
+    pub fn get_all_keyed_sysvar_accounts(&self) -> Vec<(Pubkey, Account)> {
         vec![
             self.keyed_account_for_clock_sysvar(),
             self.keyed_account_for_epoch_rewards_sysvar(),
@@ -218,6 +256,31 @@ impl Sysvars {
     }
 }
 
+/// A sysvar stored on `Sysvars`, which can be created as an account with
+/// `Sysvars::keyed_account`.
+pub trait StoredSysvar: SysvarSerialize {
+    /// Get the sysvar's value from `Sysvars`.
+    fn get(sysvars: &Sysvars) -> &Self;
+}
+
+macro_rules! impl_stored_sysvar {
+    ($sysvar:ty, $field:ident) => {
+        impl StoredSysvar for $sysvar {
+            fn get(sysvars: &Sysvars) -> &Self {
+                &sysvars.$field
+            }
+        }
+    };
+}
+
+impl_stored_sysvar!(Clock, clock);
+impl_stored_sysvar!(EpochRewards, epoch_rewards);
+impl_stored_sysvar!(EpochSchedule, epoch_schedule);
+impl_stored_sysvar!(LastRestartSlot, last_restart_slot);
+impl_stored_sysvar!(Rent, rent);
+impl_stored_sysvar!(SlotHashes, slot_hashes);
+impl_stored_sysvar!(StakeHistory, stake_history);
+
 impl From<&Sysvars> for SysvarCache {
     fn from(mollusk_cache: &Sysvars) -> Self {
         let mut sysvar_cache = SysvarCache::default();
@@ -0,0 +1,240 @@
+//! Deterministic test input generation.
+//!
+//! `Pubkey::new_unique()` hands out keys from a process-wide counter, so keys
+//! depend on test ordering and threading. That breaks fixture hashing and
+//! golden files, since re-running a suite produces different inputs.
+//!
+//! `KeyGen` produces the same sequence of keys for the same seed, every run.
+//! Tests that eject fixtures should prefer it over `Pubkey::new_unique()`.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::testgen::KeyGen;
+//!
+//! let mut keys = KeyGen::seeded(42);
+//! let program_id = keys.pubkey();
+//! let authority = keys.pubkey();
+//! ```
+//!
+//! `KeyFactory` builds on it with keys derived from a label, which don't
+//! shift when keys are added to or removed from a test, and
+//! `AccountFactory` hands out account builders at those keys:
+//!
+//! ```rust,ignore
+//! use mollusk_svm::testgen::AccountFactory;
+//!
+//! let mut accounts = AccountFactory::new(42, &mollusk.sysvars.rent);
+//! let payer = accounts.system_account(1_000_000_000);
+//! let vault = accounts.named("vault").owner(&program_id).space(64).build();
+//! ```
+
+use {
+    crate::account_builder::AccountBuilder, trezoa_account::Account, trezoa_pubkey::Pubkey,
+    trezoa_rent::Rent,
+};
+
+/// A seedable, deterministic generator of pubkeys.
+///
+/// Keys are not guaranteed to be off-curve, matching `Pubkey::new_unique()`.
+#[derive(Clone, Debug)]
+pub struct KeyGen {
+    state: u64,
+}
+
+impl Default for KeyGen {
+    fn default() -> Self {
+        Self::seeded(0)
+    }
+}
+
+impl KeyGen {
+    /// Create a new generator from the provided seed.
+    pub const fn seeded(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Generate the next raw value in the sequence, which pubkeys are built
+    /// from. SplitMix64: small, fast, and plenty for test input generation.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Generate the next pubkey in the sequence.
+    pub fn pubkey(&mut self) -> Pubkey {
+        let mut bytes = [0u8; 32];
+        for chunk in bytes.chunks_exact_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes());
+        }
+        Pubkey::new_from_array(bytes)
+    }
+
+    /// Generate the next `n` pubkeys in the sequence.
+    pub fn pubkeys(&mut self, n: usize) -> Vec<Pubkey> {
+        (0..n).map(|_| self.pubkey()).collect()
+    }
+}
+
+impl Iterator for KeyGen {
+    type Item = Pubkey;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.pubkey())
+    }
+}
+
+/// A seeded key factory, handing out keys in sequence or by label.
+///
+/// Sequential keys follow `KeyGen` for the same seed. Labeled keys only
+/// depend on the seed and the label.
+#[derive(Clone, Debug)]
+pub struct KeyFactory {
+    seed: u64,
+    sequence: KeyGen,
+}
+
+impl Default for KeyFactory {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl KeyFactory {
+    /// Create a new factory from the provided seed.
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            sequence: KeyGen::seeded(seed),
+        }
+    }
+
+    /// Generate the next pubkey in the sequence.
+    pub fn pubkey(&mut self) -> Pubkey {
+        self.sequence.pubkey()
+    }
+
+    /// Generate the next `n` pubkeys in the sequence.
+    pub fn pubkeys(&mut self, n: usize) -> Vec<Pubkey> {
+        self.sequence.pubkeys(n)
+    }
+
+    /// The pubkey for the provided label. The same label always yields the
+    /// same key for a given seed, regardless of any other keys generated.
+    pub fn named(&self, label: &str) -> Pubkey {
+        // FNV-1a, so the key is stable across platforms and releases.
+        let hash = label.bytes().fold(0xCBF29CE484222325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001B3)
+        });
+        KeyGen::seeded(self.seed ^ hash).pubkey()
+    }
+}
+
+/// A seeded account factory, placing accounts at keys from a `KeyFactory`.
+///
+/// Accounts are funded with the rent-exempt minimum for their data, from the
+/// provided rent configuration, unless overridden.
+pub struct AccountFactory<'a> {
+    keys: KeyFactory,
+    rent: &'a Rent,
+}
+
+impl<'a> AccountFactory<'a> {
+    /// Create a new factory from the provided seed, using the provided rent
+    /// configuration to compute rent-exempt balances.
+    pub fn new(seed: u64, rent: &'a Rent) -> Self {
+        Self {
+            keys: KeyFactory::new(seed),
+            rent,
+        }
+    }
+
+    /// The factory's keys, for generating keys which aren't accounts, such as
+    /// program IDs or authorities.
+    pub fn keys(&mut self) -> &mut KeyFactory {
+        &mut self.keys
+    }
+
+    /// A builder for an account at the next key in the sequence.
+    pub fn account(&mut self) -> AccountBuilder<'a> {
+        AccountBuilder::new(self.rent).address(&self.keys.pubkey())
+    }
+
+    /// A builder for an account at the key for the provided label.
+    pub fn named(&self, label: &str) -> AccountBuilder<'a> {
+        AccountBuilder::new(self.rent).address(&self.keys.named(label))
+    }
+
+    /// A system-owned account with the provided lamports, at the next key in
+    /// the sequence.
+    pub fn system_account(&mut self, lamports: u64) -> (Pubkey, Account) {
+        self.account().lamports(lamports).build()
+    }
+
+    /// A rent-exempt account owned by `owner`, holding the provided data, at
+    /// the next key in the sequence.
+    pub fn program_account(&mut self, owner: &Pubkey, data: &[u8]) -> (Pubkey, Account) {
+        self.account().owner(owner).data(data).build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::collections::HashSet};
+
+    #[test]
+    fn test_same_seed_same_keys() {
+        let a = KeyGen::seeded(42).pubkeys(16);
+        let b = KeyGen::seeded(42).pubkeys(16);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_different_seeds_different_keys() {
+        let a = KeyGen::seeded(42).pubkeys(16);
+        let b = KeyGen::seeded(43).pubkeys(16);
+        assert!(a.iter().all(|key| !b.contains(key)));
+    }
+
+    #[test]
+    fn test_keys_unique() {
+        let keys = KeyGen::seeded(7).take(1_000).collect::<HashSet<_>>();
+        assert_eq!(keys.len(), 1_000);
+    }
+
+    #[test]
+    fn test_named_keys_stable() {
+        let mut a = KeyFactory::new(42);
+        let b = KeyFactory::new(42);
+        let vault = a.named("vault");
+        a.pubkeys(8);
+        assert_eq!(a.named("vault"), vault);
+        assert_eq!(b.named("vault"), vault);
+        assert_ne!(b.named("mint"), vault);
+        assert_ne!(KeyFactory::new(43).named("vault"), vault);
+        assert_eq!(
+            KeyFactory::new(42).pubkeys(4),
+            KeyGen::seeded(42).pubkeys(4)
+        );
+    }
+
+    #[test]
+    fn test_account_factory_deterministic() {
+        let rent = Rent::default();
+        let build = || {
+            let mut accounts = AccountFactory::new(42, &rent);
+            let owner = accounts.keys().pubkey();
+            vec![
+                accounts.system_account(1_000),
+                accounts.program_account(&owner, &[1, 2, 3]),
+                accounts.named("vault").owner(&owner).space(64).build(),
+            ]
+        };
+        let accounts = build();
+        assert_eq!(accounts, build());
+        assert_eq!(accounts[0].1.lamports, 1_000);
+        assert_eq!(accounts[1].1.lamports, rent.minimum_balance(3));
+        assert_eq!(accounts[2].0, KeyFactory::new(42).named("vault"));
+    }
+}
@@ -0,0 +1,207 @@
+//! Time travel for sysvar-dependent tests.
+//!
+//! `Mollusk::warp_to_slot` only moves the clock slot and `SlotHashes`.
+//! `TimeTravel` moves every time-derived sysvar together, so programs gated
+//! on epoch boundaries or wall-clock time see a consistent view:
+//!
+//! * `Clock`: slot, epoch, leader schedule epoch, `unix_timestamp`, and
+//!   `epoch_start_timestamp`.
+//! * `SlotHashes`: the slots leading up to the current slot.
+//! * `StakeHistory`: an entry for every epoch crossed, carrying forward the
+//!   most recent entry.
+//! * `EpochRewards`: any active rewards distribution ends at an epoch boundary.
+//!
+//! The wall clock advances by `DEFAULT_MS_PER_SLOT` per slot, starting from
+//! the current `unix_timestamp`.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::Mollusk;
+//!
+//! let mut mollusk = Mollusk::new(&program_id, "my_program");
+//!
+//! mollusk
+//!     .time_travel()
+//!     .unix_timestamp(1_700_000_000)
+//!     .warp_to_epoch(10)
+//!     .advance_slots(100);
+//! ```
+
+use {
+    crate::sysvar::Sysvars,
+    trezoa_clock::{Epoch, Slot, UnixTimestamp, DEFAULT_MS_PER_SLOT},
+    trezoa_hash::Hash,
+    trezoa_slot_hashes::{SlotHashes, MAX_ENTRIES as SLOT_HASHES_MAX_ENTRIES},
+    std::ops::Range,
+};
+
+/// Moves the test environment through time by updating sysvars.
+pub struct TimeTravel<'a> {
+    sysvars: &'a mut Sysvars,
+}
+
+fn slots_to_seconds(slots: u64) -> i64 {
+    (slots.saturating_mul(DEFAULT_MS_PER_SLOT) / 1_000) as i64
+}
+
+impl<'a> TimeTravel<'a> {
+    /// Create a new time travel handle over the provided sysvars.
+    pub fn new(sysvars: &'a mut Sysvars) -> Self {
+        Self { sysvars }
+    }
+
+    /// Travel to the provided slot.
+    ///
+    /// Traveling backwards only updates `Clock` and `SlotHashes`, since
+    /// history can't be undone.
+    pub fn warp_to_slot(self, slot: Slot) -> Self {
+        let previous_clock = self.sysvars.clock.clone();
+
+        // Updates the clock's slot and epochs, and `SlotHashes`.
+        self.sysvars.warp_to_slot(slot);
+
+        let epoch_schedule = &self.sysvars.epoch_schedule;
+        let clock = &mut self.sysvars.clock;
+
+        let unix_timestamp = if slot >= previous_clock.slot {
+            previous_clock.unix_timestamp + slots_to_seconds(slot - previous_clock.slot)
+        } else {
+            previous_clock.unix_timestamp - slots_to_seconds(previous_clock.slot - slot)
+        };
+        clock.unix_timestamp = unix_timestamp;
+        clock.epoch_start_timestamp = if clock.epoch == previous_clock.epoch {
+            previous_clock.epoch_start_timestamp
+        } else {
+            let first_slot = epoch_schedule.get_first_slot_in_epoch(clock.epoch);
+            unix_timestamp - slots_to_seconds(slot.saturating_sub(first_slot))
+        };
+
+        if clock.epoch > previous_clock.epoch {
+            let entry = self
+                .sysvars
+                .stake_history
+                .iter()
+                .next()
+                .map(|(_, entry)| entry.clone())
+                .unwrap_or_default();
+            for epoch in previous_clock.epoch + 1..=self.sysvars.clock.epoch {
+                self.sysvars.stake_history.add(epoch, entry.clone());
+            }
+            self.sysvars.epoch_rewards.active = false;
+        }
+
+        self
+    }
+
+    /// Travel forward by the provided number of slots.
+    pub fn advance_slots(self, slots: u64) -> Self {
+        let slot = self.sysvars.clock.slot.saturating_add(slots);
+        self.warp_to_slot(slot)
+    }
+
+    /// Travel to the first slot of the provided epoch.
+    pub fn warp_to_epoch(self, epoch: Epoch) -> Self {
+        let slot = self.sysvars.epoch_schedule.get_first_slot_in_epoch(epoch);
+        self.warp_to_slot(slot)
+    }
+
+    /// Travel forward to the first slot of the epoch `epochs` after the
+    /// current one.
+    pub fn advance_epochs(self, epochs: u64) -> Self {
+        let epoch = self.sysvars.clock.epoch.saturating_add(epochs);
+        self.warp_to_epoch(epoch)
+    }
+
+    /// Set the wall-clock time at the current slot.
+    ///
+    /// `epoch_start_timestamp` is shifted by the same amount, so the elapsed
+    /// time within the epoch stays consistent.
+    pub fn unix_timestamp(self, unix_timestamp: UnixTimestamp) -> Self {
+        let clock = &mut self.sysvars.clock;
+        let delta = unix_timestamp - clock.unix_timestamp;
+        clock.unix_timestamp = unix_timestamp;
+        clock.epoch_start_timestamp += delta;
+        self
+    }
+
+    /// Replace `SlotHashes` with entries for the provided range of slots.
+    ///
+    /// Only the most recent `MAX_ENTRIES` slots of the range are kept.
+    pub fn slot_hashes(self, slots: Range<Slot>) -> Self {
+        let slot_hash_entries = slots
+            .rev()
+            .take(SLOT_HASHES_MAX_ENTRIES)
+            .map(|slot| (slot, Hash::default()))
+            .collect::<Vec<_>>();
+        self.sysvars.slot_hashes = SlotHashes::new(&slot_hash_entries);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, trezoa_stake_interface::stake_history::StakeHistoryEntry};
+
+    #[test]
+    fn test_warp_to_epoch() {
+        let mut sysvars = Sysvars::default();
+        sysvars.clock.unix_timestamp = 1_000;
+        sysvars.clock.epoch_start_timestamp = 1_000;
+        sysvars.stake_history.add(
+            0,
+            StakeHistoryEntry {
+                effective: 42,
+                ..Default::default()
+            },
+        );
+        sysvars.epoch_rewards.active = true;
+
+        TimeTravel::new(&mut sysvars)
+            .warp_to_epoch(3)
+            .advance_slots(10);
+
+        let first_slot = sysvars.epoch_schedule.get_first_slot_in_epoch(3);
+        assert_eq!(sysvars.clock.slot, first_slot + 10);
+        assert_eq!(sysvars.clock.epoch, 3);
+        assert_eq!(
+            sysvars.clock.unix_timestamp,
+            1_000 + slots_to_seconds(first_slot + 10),
+        );
+        assert_eq!(
+            sysvars.clock.epoch_start_timestamp,
+            1_000 + slots_to_seconds(first_slot),
+        );
+        for epoch in 1..=3 {
+            assert_eq!(sysvars.stake_history.get(epoch).unwrap().effective, 42);
+        }
+        assert!(!sysvars.epoch_rewards.active);
+        assert_eq!(
+            sysvars.slot_hashes.first(),
+            Some(&(first_slot + 9, Hash::default())),
+        );
+    }
+
+    #[test]
+    fn test_unix_timestamp() {
+        let mut sysvars = Sysvars::default();
+
+        TimeTravel::new(&mut sysvars)
+            .advance_slots(100)
+            .unix_timestamp(1_700_000_000);
+
+        assert_eq!(sysvars.clock.unix_timestamp, 1_700_000_000);
+        assert_eq!(
+            sysvars.clock.epoch_start_timestamp,
+            1_700_000_000 - slots_to_seconds(100),
+        );
+    }
+
+    #[test]
+    fn test_slot_hashes() {
+        let mut sysvars = Sysvars::default();
+
+        TimeTravel::new(&mut sysvars).slot_hashes(0..1_000);
+
+        assert_eq!(sysvars.slot_hashes.len(), SLOT_HASHES_MAX_ENTRIES);
+        assert_eq!(sysvars.slot_hashes.first(), Some(&(999, Hash::default())));
+    }
+}
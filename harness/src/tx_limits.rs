@@ -0,0 +1,175 @@
+//! Opt-in transaction limit enforcement.
+//!
+//! Mollusk compiles instructions into a message without a packet to fit it
+//! in, so by default a transaction can carry any number of accounts and any
+//! amount of data. Set `Mollusk::enforce_tx_limits` to have
+//! `process_transaction_instructions` reject transactions a validator
+//! wouldn't accept, before executing them.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::{tx_limits::TransactionLimits, Mollusk};
+//!
+//! let mut mollusk = Mollusk::default();
+//! mollusk.enforce_tx_limits = Some(TransactionLimits::default());
+//!
+//! let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+//! ```
+//!
+//! A transaction fails with:
+//!
+//! * `TransactionError::SanitizeFailure` if its serialized size, signatures
+//!   included, exceeds `max_transaction_size`.
+//! * `TransactionError::TooManyAccountLocks` if it references more than
+//!   `max_account_keys` accounts, including those loaded through lookup tables.
+//! * `TransactionError::MaxLoadedAccountsDataSizeExceeded` if the accounts it
+//!   loads exceed `max_loaded_accounts_data_size`, or the lower limit requested
+//!   by a `SetLoadedAccountsDataSizeLimit` instruction. Requesting a limit of
+//!   `0` fails with `TransactionError::InvalidLoadedAccountsDataSizeLimit`.
+
+use {
+    trezoa_account::{AccountSharedData, ReadableAccount},
+    trezoa_message::SanitizedMessage,
+    trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
+};
+
+/// The maximum size of a transaction packet.
+const PACKET_DATA_SIZE: usize = 1280 - 40 - 8;
+const MAX_TX_ACCOUNT_LOCKS: usize = 64;
+const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: u32 = 64 * 1024 * 1024;
+/// The size charged for each loaded account, on top of its data.
+const TRANSACTION_ACCOUNT_BASE_SIZE: usize = 64;
+const SIGNATURE_BYTES: usize = 64;
+const PUBKEY_BYTES: usize = 32;
+const HASH_BYTES: usize = 32;
+
+/// The limits a transaction must fit within.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionLimits {
+    /// The maximum size of the serialized transaction, signatures included.
+    /// Defaults to `1232`, the size of a packet.
+    pub max_transaction_size: usize,
+    /// The maximum number of account keys, including those loaded through
+    /// lookup tables. Defaults to `64`.
+    pub max_account_keys: usize,
+    /// The maximum size of the accounts loaded by the transaction, in bytes.
+    /// Each account is charged its data length plus `64` bytes. Defaults to
+    /// 64 MiB.
+    pub max_loaded_accounts_data_size: u32,
+}
+
+impl Default for TransactionLimits {
+    fn default() -> Self {
+        Self {
+            max_transaction_size: PACKET_DATA_SIZE,
+            max_account_keys: MAX_TX_ACCOUNT_LOCKS,
+            max_loaded_accounts_data_size: MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+        }
+    }
+}
+
+impl TransactionLimits {
+    /// Set the maximum serialized transaction size.
+    pub fn max_transaction_size(mut self, max_transaction_size: usize) -> Self {
+        self.max_transaction_size = max_transaction_size;
+        self
+    }
+
+    /// Set the maximum number of account keys.
+    pub fn max_account_keys(mut self, max_account_keys: usize) -> Self {
+        self.max_account_keys = max_account_keys;
+        self
+    }
+
+    /// Set the maximum size of the accounts loaded by the transaction.
+    pub fn max_loaded_accounts_data_size(mut self, max_loaded_accounts_data_size: u32) -> Self {
+        self.max_loaded_accounts_data_size = max_loaded_accounts_data_size;
+        self
+    }
+
+    /// Check a compiled message and its accounts against the limits, like a
+    /// validator does before executing a transaction.
+    pub(crate) fn check(
+        &self,
+        message: &SanitizedMessage,
+        transaction_accounts: &[(Pubkey, AccountSharedData)],
+        requested_loaded_accounts_data_size_limit: Option<u32>,
+    ) -> Result<(), TransactionError> {
+        if transaction_size(message) > self.max_transaction_size {
+            return Err(TransactionError::SanitizeFailure);
+        }
+
+        if message.account_keys().len() > self.max_account_keys {
+            return Err(TransactionError::TooManyAccountLocks);
+        }
+
+        let loaded_accounts_data_size_limit = match requested_loaded_accounts_data_size_limit {
+            Some(0) => return Err(TransactionError::InvalidLoadedAccountsDataSizeLimit),
+            Some(limit) => limit.min(self.max_loaded_accounts_data_size),
+            None => self.max_loaded_accounts_data_size,
+        };
+        let loaded_accounts_data_size = transaction_accounts
+            .iter()
+            .map(|(_, account)| account.data().len() + TRANSACTION_ACCOUNT_BASE_SIZE)
+            .sum::<usize>();
+        if loaded_accounts_data_size > loaded_accounts_data_size_limit as usize {
+            return Err(TransactionError::MaxLoadedAccountsDataSizeExceeded);
+        }
+
+        Ok(())
+    }
+}
+
+/// The number of bytes used to encode a length as a `short_vec` prefix.
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+/// The size of a sequence of `len` items of `item_size` bytes, with its
+/// `short_vec` length prefix.
+fn short_vec_size(len: usize, item_size: usize) -> usize {
+    short_vec_len(len) + len * item_size
+}
+
+/// The wire size of the transaction carrying the message, signatures
+/// included.
+fn transaction_size(message: &SanitizedMessage) -> usize {
+    let num_signatures = usize::from(message.header().num_required_signatures);
+    let signatures_size = short_vec_size(num_signatures, SIGNATURE_BYTES);
+
+    let instructions_size = short_vec_len(message.instructions().len())
+        + message
+            .instructions()
+            .iter()
+            .map(|ix| 1 + short_vec_size(ix.accounts.len(), 1) + short_vec_size(ix.data.len(), 1))
+            .sum::<usize>();
+
+    // Header, static account keys, and recent blockhash.
+    let message_size = |static_account_keys: usize| {
+        3 + short_vec_size(static_account_keys, PUBKEY_BYTES) + HASH_BYTES + instructions_size
+    };
+
+    let message_size = match message {
+        SanitizedMessage::Legacy(legacy) => message_size(legacy.message.account_keys.len()),
+        SanitizedMessage::V0(loaded) => {
+            let lookups = &loaded.message.address_table_lookups;
+            let lookups_size = short_vec_len(lookups.len())
+                + lookups
+                    .iter()
+                    .map(|lookup| {
+                        PUBKEY_BYTES
+                            + short_vec_size(lookup.writable_indexes.len(), 1)
+                            + short_vec_size(lookup.readonly_indexes.len(), 1)
+                    })
+                    .sum::<usize>();
+            // Versioned messages are prefixed with their version.
+            1 + message_size(loaded.message.account_keys.len()) + lookups_size
+        }
+    };
+
+    signatures_size + message_size
+}
@@ -0,0 +1,111 @@
+use {
+    mollusk_svm::{result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+};
+
+#[test]
+fn test_account_builder() {
+    let mollusk = Mollusk::default();
+    let rent = &mollusk.sysvars.rent;
+
+    let program_id = Pubkey::new_unique();
+
+    // Defaults: unique address, system-owned, rent-exempt.
+    let (key_a, account_a) = mollusk.account_builder().build();
+    let (key_b, _) = mollusk.account_builder().build();
+    assert_ne!(key_a, key_b);
+    assert_eq!(
+        account_a,
+        Account::new(
+            rent.minimum_balance(0),
+            0,
+            &trezoa_sdk_ids::system_program::id()
+        )
+    );
+
+    // Explicit address, owner, space.
+    let key = Pubkey::new_unique();
+    let (address, account) = mollusk
+        .account_builder()
+        .address(&key)
+        .owner(&program_id)
+        .space(64)
+        .build();
+    assert_eq!(address, key);
+    assert_eq!(
+        account,
+        Account::new(rent.minimum_balance(64), 64, &program_id)
+    );
+
+    // Lamports override.
+    let (_, account) = mollusk
+        .account_builder()
+        .data(&[1, 2, 3])
+        .lamports(42)
+        .build();
+    assert_eq!(account.lamports, 42);
+    assert_eq!(account.data, vec![1, 2, 3]);
+
+    // PDA.
+    let seeds: &[&[u8]] = &[b"vault", key.as_ref()];
+    let (expected_address, expected_bump) = Pubkey::find_program_address(seeds, &program_id);
+    let builder = mollusk.account_builder().pda(seeds, &program_id).space(8);
+    assert_eq!(builder.bump(), Some(expected_bump));
+    let (address, account) = builder.build();
+    assert_eq!(address, expected_address);
+    assert_eq!(account.owner, program_id);
+    assert_eq!(account.lamports, rent.minimum_balance(8));
+}
+
+#[test]
+fn test_account_builder_accounts_slice() {
+    let mollusk = Mollusk::default();
+
+    let (payer, payer_account) = mollusk
+        .account_builder()
+        .system()
+        .lamports(1_000_000_000)
+        .build();
+    let (recipient, recipient_account) = mollusk.account_builder().build();
+
+    mollusk.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::transfer(&payer, &recipient, 1_000),
+        &[
+            (payer, payer_account.clone()),
+            (recipient, recipient_account.clone()),
+        ],
+        &[
+            Check::success(),
+            Check::account(&payer)
+                .lamports(payer_account.lamports - 1_000)
+                .build(),
+            Check::account(&recipient)
+                .lamports(recipient_account.lamports + 1_000)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn test_rent_epoch_round_trip() {
+    let mollusk = Mollusk::default();
+
+    let (payer, payer_account) = mollusk
+        .account_builder()
+        .lamports(1_000_000_000)
+        .rent_epoch(u64::MAX)
+        .build();
+    let (recipient, recipient_account) = mollusk.account_builder().rent_epoch(42).build();
+    assert_eq!(payer_account.rent_epoch, u64::MAX);
+
+    mollusk.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::transfer(&payer, &recipient, 1_000),
+        &[(payer, payer_account), (recipient, recipient_account)],
+        &[
+            Check::success(),
+            Check::account(&payer).rent_epoch(u64::MAX).build(),
+            Check::account(&recipient).rent_epoch(42).build(),
+        ],
+    );
+}
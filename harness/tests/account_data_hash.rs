@@ -0,0 +1,104 @@
+use {
+    mollusk_svm::{
+        result::{check::account_data_hash, Check, Compare, Config},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    std::{cell::RefCell, rc::Rc},
+};
+
+fn config() -> Config {
+    Config::default().panic(false)
+}
+
+#[test]
+fn test_data_hash_check() {
+    let mollusk = Mollusk::default();
+    let config = config();
+
+    let key = Pubkey::new_unique();
+    let space = 10 * 1024 * 1024;
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+    let account = Account::new(lamports, 0, &trezoa_sdk_ids::system_program::id());
+    let hash = account_data_hash(&vec![0; space]);
+
+    let result = mollusk.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::allocate(&key, space as u64),
+        &[(key, account)],
+        &[
+            Check::success(),
+            Check::account(&key)
+                .data_hash(hash)
+                .lamports(lamports)
+                .build(),
+        ],
+    );
+    assert!(!result.run_checks(
+        &[Check::account(&key)
+            .data_hash(account_data_hash(&[0; 16]))
+            .build()],
+        &config,
+        &mollusk,
+    ));
+}
+
+#[test]
+fn test_compare_data_hash() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let key = Pubkey::new_unique();
+    let accounts = [(key, Account::new(1_000_000_000, 1024, &program_id))];
+    // Write the provided data to the account.
+    let write_data = |data: &[u8]| {
+        let mut instruction_data = vec![1];
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![AccountMeta::new(key, true)],
+        )
+    };
+    let expected = mollusk.process_instruction(&write_data(&[1; 1024]), &accounts);
+    assert!(expected.program_result.is_ok());
+    let same = mollusk.process_instruction(&write_data(&[1; 1024]), &accounts);
+    let mut changed = vec![1; 1024];
+    changed[1023] = 2;
+    let actual = mollusk.process_instruction(&write_data(&changed), &accounts);
+
+    let by_hash = [
+        Compare::AllResultingAccounts {
+            data: false,
+            executable: true,
+            lamports: true,
+            owner: true,
+            space: true,
+        },
+        Compare::ResultingAccountDataHashes,
+    ];
+    assert!(expected.compare_with_config(&same, &by_hash, &config()));
+
+    // A mismatch reports the hashes, rather than both buffers.
+    let failures = Rc::new(RefCell::new(Vec::new()));
+    let config = config().on_failure({
+        let failures = Rc::clone(&failures);
+        move |record| {
+            failures
+                .borrow_mut()
+                .push((record.check.clone(), record.expected.clone()))
+        }
+    });
+    assert!(!expected.compare_with_config(&actual, &by_hash, &config));
+    let expected_hash = account_data_hash(&expected.resulting_accounts[0].1.data);
+    assert_eq!(
+        *failures.borrow(),
+        [(
+            Some("resulting_account_data_hash".to_string()),
+            Some(format!("{:?}", expected_hash)),
+        )]
+    );
+}
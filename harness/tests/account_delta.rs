@@ -0,0 +1,139 @@
+use {
+    mollusk_svm::{
+        result::{Check, Config},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+};
+
+const BASE_LAMPORTS: u64 = 100_000_000;
+
+fn system_account() -> Account {
+    Account::new(BASE_LAMPORTS, 0, &trezoa_sdk_ids::system_program::id())
+}
+
+#[test]
+fn test_lamports_delta_and_unchanged() {
+    let mollusk = Mollusk::default();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let transfer_amount = 42_000u64;
+
+    let instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [(sender, system_account()), (recipient, system_account())];
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&sender)
+                .lamports_delta(-(transfer_amount as i128))
+                .data_unchanged()
+                .build(),
+            Check::account(&recipient)
+                .lamports_delta(transfer_amount as i128)
+                .build(),
+        ],
+    );
+
+    // A zero transfer leaves both accounts untouched.
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 0);
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&sender).unchanged().build(),
+            Check::account(&recipient).unchanged().build(),
+        ],
+    );
+}
+
+#[test]
+fn test_owner_changed_from_to() {
+    let mollusk = Mollusk::default();
+
+    let key = Pubkey::new_unique();
+    let new_owner = Pubkey::new_unique();
+    let system_program = trezoa_sdk_ids::system_program::id();
+
+    let instruction = trezoa_system_interface::instruction::assign(&key, &new_owner);
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, system_account())],
+        &[
+            Check::success(),
+            Check::account(&key)
+                .owner_changed_from_to(&system_program, &new_owner)
+                .executable_changed_from_to(false, false)
+                .lamports_delta(0)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+#[should_panic(expected = "CHECK FAILED: account_unchanged")]
+fn test_unchanged_fails_on_change() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 1);
+    Mollusk::default().process_and_validate_instruction(
+        &instruction,
+        &[(sender, system_account()), (recipient, system_account())],
+        &[Check::account(&recipient).unchanged().build()],
+    );
+}
+
+#[test]
+fn test_chain_deltas_against_previous_instruction() {
+    let mollusk = Mollusk::default();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let first = trezoa_system_interface::instruction::transfer(&sender, &recipient, 1_000);
+    let second = trezoa_system_interface::instruction::transfer(&sender, &recipient, 500);
+
+    // Each instruction's deltas are relative to the accounts it was given,
+    // which are the accounts resulting from the previous instruction.
+    mollusk.process_and_validate_instruction_chain(
+        &[
+            (
+                &first,
+                &[Check::account(&recipient).lamports_delta(1_000).build()],
+            ),
+            (
+                &second,
+                &[Check::account(&recipient).lamports_delta(500).build()],
+            ),
+        ],
+        &[(sender, system_account()), (recipient, system_account())],
+    );
+}
+
+#[test]
+fn test_delta_checks_need_input_accounts() {
+    let mollusk = Mollusk::default();
+    let config = Config::default().panic(false);
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 0);
+    let result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(sender, system_account()), (recipient, system_account())],
+        &[Check::success()],
+    );
+
+    // `Mollusk` alone doesn't provide the input accounts.
+    assert!(!result.run_checks(
+        &[Check::account(&sender).unchanged().build()],
+        &config,
+        &mollusk,
+    ));
+}
@@ -0,0 +1,182 @@
+use {
+    mollusk_svm::{
+        result::{diff::DataDiff, Check, Config},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+};
+
+#[test]
+fn test_diff_accounts() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let result = Mollusk::default().process_instruction(&instruction, &accounts);
+    let diffs = result.diff_accounts(&accounts);
+
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(diffs[0].pubkey, sender);
+    assert_eq!(
+        diffs[0].lamports,
+        Some((base_lamports, base_lamports - transfer_amount))
+    );
+    assert_eq!(diffs[0].lamports_delta(), Some(-42_000));
+    assert_eq!(diffs[1].pubkey, recipient);
+    assert_eq!(diffs[1].lamports_delta(), Some(42_000));
+    assert!(diffs
+        .iter()
+        .all(|diff| diff.owner.is_none() && diff.data.is_empty()));
+
+    // Unchanged accounts aren't reported.
+    assert!(result.diff_accounts(&result.resulting_accounts).is_empty());
+}
+
+#[test]
+fn test_account_diff_data() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let key = Pubkey::new_unique();
+    let accounts = [(
+        key,
+        Account {
+            lamports: 10,
+            data: vec![0, 1, 2, 3, 4, 5, 0, 0],
+            owner: program_id,
+            ..Default::default()
+        },
+    )];
+
+    // Write the data to the account.
+    let instruction = Instruction::new_with_bytes(
+        program_id,
+        &[1, 0, 9, 9, 3, 4, 7, 8, 8],
+        vec![AccountMeta::new(key, true)],
+    );
+    let result =
+        mollusk.process_and_validate_instruction(&instruction, &accounts, &[Check::success()]);
+
+    let diffs = result.diff_accounts(&accounts);
+    assert_eq!(diffs.len(), 1);
+    let diff = &diffs[0];
+    assert_eq!(diff.pubkey, key);
+    assert_eq!(diff.lamports, None);
+    assert_eq!(diff.owner, None);
+    assert_eq!(diff.data_len, None);
+    assert_eq!(
+        diff.data,
+        vec![
+            DataDiff {
+                offset: 1,
+                before: vec![1, 2],
+                after: vec![9, 9],
+            },
+            DataDiff {
+                offset: 5,
+                before: vec![5, 0, 0],
+                after: vec![7, 8, 8],
+            },
+        ]
+    );
+    assert!(diff
+        .to_string()
+        .contains("0x0001:\n      - 01 02\n      + 09 09"));
+}
+
+#[test]
+fn test_account_diff_owner_and_data_len() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+
+    let instructions = [
+        trezoa_system_interface::instruction::allocate(&key, 2),
+        trezoa_system_interface::instruction::assign(&key, &owner),
+    ];
+    let accounts = [(
+        key,
+        Account::new(100_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+    )];
+
+    let result = Mollusk::default().process_and_validate_instruction_chain(
+        &[
+            (&instructions[0], &[Check::success()]),
+            (&instructions[1], &[Check::success()]),
+        ],
+        &accounts,
+    );
+
+    let diffs = result.diff_accounts(&accounts);
+    assert_eq!(diffs.len(), 1);
+    let diff = &diffs[0];
+    assert_eq!(diff.lamports, None);
+    assert_eq!(
+        diff.owner,
+        Some((trezoa_sdk_ids::system_program::id(), owner))
+    );
+    assert_eq!(diff.data_len, Some((0, 2)));
+    // The new bytes are reported as a single trailing range.
+    assert_eq!(
+        diff.data,
+        vec![DataDiff {
+            offset: 0,
+            before: vec![],
+            after: vec![0, 0],
+        }]
+    );
+    assert!(diff.to_string().contains("data length: 0 -> 2"));
+}
+
+#[test]
+#[should_panic(expected = "lamports: 100000000 -> 99958000 (-42000)")]
+fn test_show_diffs() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let mut mollusk = Mollusk::default();
+    mollusk.config = Config::default().show_diffs(true);
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&recipient).lamports(base_lamports).build(),
+        ],
+    );
+}
@@ -0,0 +1,190 @@
+#![cfg(all(feature = "borsh", feature = "program-pack"))]
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    mollusk_svm::{
+        result::{Check, Config},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_program_error::ProgramError,
+    trezoa_program_pack::{IsInitialized, Pack, Sealed},
+    trezoa_pubkey::Pubkey,
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+struct Counter {
+    authority: [u8; 32],
+    count: u64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Vault {
+    is_initialized: bool,
+    balance: u64,
+}
+
+impl Sealed for Vault {}
+
+impl IsInitialized for Vault {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Vault {
+    const LEN: usize = 9;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1..9].copy_from_slice(&self.balance.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self {
+            is_initialized: src[0] == 1,
+            balance: u64::from_le_bytes(src[1..9].try_into().unwrap()),
+        })
+    }
+}
+
+fn config() -> Config {
+    Config::default().panic(false)
+}
+
+fn mollusk() -> (Mollusk, Pubkey) {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+    (mollusk, program_id)
+}
+
+// Write the provided data to the account, which must be the same length.
+fn write_data(program_id: &Pubkey, key: &Pubkey, data: &[u8]) -> Instruction {
+    let mut instruction_data = vec![1];
+    instruction_data.extend_from_slice(data);
+    Instruction::new_with_bytes(
+        *program_id,
+        &instruction_data,
+        vec![AccountMeta::new(*key, true)],
+    )
+}
+
+#[test]
+fn test_borsh_state_checks() {
+    let (mollusk, program_id) = mollusk();
+    let config = config();
+
+    let key = Pubkey::new_unique();
+    let counter = Counter {
+        authority: [7; 32],
+        count: 3,
+    };
+    // Trailing space after the state is ignored.
+    let mut data = borsh::to_vec(&counter).unwrap();
+    data.extend_from_slice(&[0; 16]);
+    let account = Account::new(1_000_000_000, data.len(), &program_id);
+
+    let result = mollusk.process_and_validate_instruction(
+        &write_data(&program_id, &key, &data),
+        &[(key, account)],
+        &[
+            Check::success(),
+            Check::account(&key).state_borsh(counter).build(),
+            Check::account(&key)
+                .owner(&program_id)
+                .state_with::<Counter>(|counter| counter.count == 3)
+                .build(),
+        ],
+    );
+
+    let failing_checks = [
+        Check::account(&key)
+            .state_borsh(Counter {
+                authority: [7; 32],
+                count: 4,
+            })
+            .build(),
+        Check::account(&key)
+            .state_with::<Counter>(|counter| counter.count > 3)
+            .build(),
+        // Not enough data for the state.
+        Check::account(&key).state_borsh([0u8; 64]).build(),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config, &mollusk));
+    }
+}
+
+#[test]
+fn test_packed_state_checks() {
+    let (mollusk, program_id) = mollusk();
+    let config = config();
+
+    let initialized = Pubkey::new_unique();
+    let uninitialized = Pubkey::new_unique();
+    let packed = |vault: Vault| {
+        let mut data = vec![0; Vault::LEN];
+        vault.pack_into_slice(&mut data);
+        data
+    };
+    let account = Account::new(1_000_000_000, Vault::LEN, &program_id);
+    let accounts = [(initialized, account.clone()), (uninitialized, account)];
+
+    let result = mollusk.process_and_validate_instruction_chain(
+        &[
+            (
+                &write_data(
+                    &program_id,
+                    &initialized,
+                    &packed(Vault {
+                        is_initialized: true,
+                        balance: 500,
+                    }),
+                ),
+                &[Check::success()],
+            ),
+            (
+                &write_data(
+                    &program_id,
+                    &uninitialized,
+                    &packed(Vault {
+                        is_initialized: false,
+                        balance: 0,
+                    }),
+                ),
+                &[
+                    Check::success(),
+                    Check::account(&initialized)
+                        .state_packed(Vault {
+                            is_initialized: true,
+                            balance: 500,
+                        })
+                        .build(),
+                ],
+            ),
+        ],
+        &accounts,
+    );
+
+    let failing_checks = [
+        Check::account(&initialized)
+            .state_packed(Vault {
+                is_initialized: true,
+                balance: 501,
+            })
+            .build(),
+        // Uninitialized state fails to unpack.
+        Check::account(&uninitialized)
+            .state_packed(Vault {
+                is_initialized: false,
+                balance: 0,
+            })
+            .build(),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config, &mollusk));
+    }
+}
@@ -1,12 +1,16 @@
 use {
-    mollusk_svm::{result::Check, Mollusk},
+    mollusk_svm::{
+        account_store::{AccountStore, LruAccountStore, MissingAccountHint},
+        result::Check,
+        Mollusk,
+    },
     trezoa_account::{Account, ReadableAccount},
     trezoa_instruction::{AccountMeta, Instruction},
     trezoa_program_error::ProgramError,
     trezoa_pubkey::Pubkey,
     trezoa_system_interface::error::SystemError,
     trezoa_system_program::system_processor::DEFAULT_COMPUTE_UNITS,
-    std::collections::HashMap,
+    std::{cell::RefCell, collections::HashMap, rc::Rc},
 };
 
 #[test]
@@ -56,6 +60,10 @@ fn test_transfer_with_context() {
         recipient_account.lamports(),
         base_lamports + transfer_amount
     );
+
+    // The result carries the same resulting accounts.
+    assert_eq!(result.get_account(&sender), Some(sender_account));
+    assert_eq!(result.get_account(&recipient), Some(recipient_account));
 }
 
 #[test]
@@ -217,3 +225,289 @@ fn test_account_store_default_account() {
         ))],
     );
 }
+
+#[test]
+fn test_airdrop_and_create_system_account() {
+    let context = Mollusk::default().with_context(HashMap::new());
+
+    let payer = Pubkey::new_unique();
+    let data_account = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+
+    // Airdrops create the account, then accumulate.
+    context.airdrop(&payer, 1_000);
+    context.airdrop(&payer, 500);
+    assert_eq!(
+        context.account_store.borrow().get(&payer),
+        Some(&Account::new(
+            1_500,
+            0,
+            &trezoa_sdk_ids::system_program::id()
+        )),
+    );
+
+    let rent = context.mollusk.sysvars.rent.minimum_balance(64);
+    context.create_system_account(&data_account, rent, 64, &owner);
+    assert_eq!(
+        context.account_store.borrow().get(&data_account),
+        Some(&Account::new(rent, 64, &owner)),
+    );
+
+    // Staged accounts are used by subsequent instructions.
+    let recipient = Pubkey::new_unique();
+    context.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::transfer(&payer, &recipient, 1_000),
+        &[
+            Check::success(),
+            Check::account(&payer).lamports(500).build(),
+            Check::account(&recipient).lamports(1_000).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_account_store_default_account_for() {
+    // Funds missing writable signers, like a fee payer would be.
+    #[derive(Default)]
+    struct FundedSignerStore(HashMap<Pubkey, Account>);
+
+    impl AccountStore for FundedSignerStore {
+        fn default_account_for(&self, _pubkey: &Pubkey, hint: MissingAccountHint) -> Account {
+            if hint.is_signer && hint.is_writable {
+                Account::new(1_000_000_000, 0, &trezoa_sdk_ids::system_program::id())
+            } else {
+                Account::default()
+            }
+        }
+
+        fn get_account(&self, pubkey: &Pubkey) -> Option<Account> {
+            self.0.get_account(pubkey)
+        }
+
+        fn store_account(&mut self, pubkey: Pubkey, account: Account) {
+            self.0.store_account(pubkey, account);
+        }
+    }
+
+    let context = Mollusk::default().with_context(FundedSignerStore::default());
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    context.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::transfer(&sender, &recipient, 1000),
+        &[
+            Check::success(),
+            Check::account(&sender)
+                .lamports(1_000_000_000 - 1000)
+                .build(),
+            Check::account(&recipient).lamports(1000).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_account_store_views_by_owner() {
+    let mollusk = Mollusk::default();
+
+    let program_a = Pubkey::new_unique();
+    let program_b = Pubkey::new_unique();
+
+    let mut account_store = HashMap::new();
+    let owned_by_a = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let owned_by_b = [Pubkey::new_unique()];
+    for key in owned_by_a.iter() {
+        account_store.insert(*key, Account::new(1_000, 8, &program_a));
+    }
+    for key in owned_by_b.iter() {
+        account_store.insert(*key, Account::new(1_000, 8, &program_b));
+    }
+
+    let context = mollusk.with_context(account_store);
+
+    let view_a = context.store_view(&program_a).unwrap();
+    assert_eq!(view_a.len(), 2);
+    assert!(view_a.iter().all(|(_, account)| account.owner == program_a));
+    assert!(view_a.windows(2).all(|w| w[0].0 < w[1].0));
+    for key in owned_by_a.iter() {
+        assert!(view_a.iter().any(|(k, _)| k == key));
+    }
+
+    let view_b = context.store_view(&program_b).unwrap();
+    assert_eq!(view_b.len(), 1);
+    assert_eq!(view_b[0].0, owned_by_b[0]);
+
+    assert!(context
+        .store_view(&Pubkey::new_unique())
+        .unwrap()
+        .is_empty());
+
+    let counts = context.count_by_owner().unwrap();
+    assert_eq!(counts.get(&program_a), Some(&2));
+    assert_eq!(counts.get(&program_b), Some(&1));
+
+    // Views reflect state changes made through the context.
+    let recipient = Pubkey::new_unique();
+    context.account_store.borrow_mut().insert(
+        recipient,
+        Account::new(0, 0, &trezoa_sdk_ids::system_program::id()),
+    );
+    context.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::assign(&recipient, &program_b),
+        &[Check::success()],
+    );
+    assert_eq!(context.store_view(&program_b).unwrap().len(), 2);
+}
+
+#[test]
+fn test_account_store_views_unsupported() {
+    use mollusk_svm::account_store::{AccountStore, IterationUnsupported};
+
+    // A store which can't enumerate its accounts.
+    #[derive(Default)]
+    struct LookupOnlyStore(HashMap<Pubkey, Account>);
+
+    impl AccountStore for LookupOnlyStore {
+        fn get_account(&self, pubkey: &Pubkey) -> Option<Account> {
+            self.0.get(pubkey).cloned()
+        }
+
+        fn store_account(&mut self, pubkey: Pubkey, account: Account) {
+            self.0.insert(pubkey, account);
+        }
+    }
+
+    let context = Mollusk::default().with_context(LookupOnlyStore::default());
+    context.airdrop(&Pubkey::new_unique(), 1_000);
+
+    assert_eq!(
+        context.store_view(&trezoa_sdk_ids::system_program::id()),
+        Err(IterationUnsupported),
+    );
+    assert_eq!(context.count_by_owner(), Err(IterationUnsupported));
+}
+
+#[test]
+fn test_file_account_store_resume() {
+    use mollusk_svm::account_store::{AccountStore, FileAccountStore};
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let path = std::env::temp_dir()
+        .join(format!("mollusk-file-store-{}", Pubkey::new_unique()))
+        .join("accounts.bin");
+
+    // First run: start from an empty store, and save the state.
+    {
+        let mut account_store = FileAccountStore::open(&path);
+        account_store.store_account(
+            sender,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        );
+        account_store.store_account(
+            recipient,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        );
+
+        let context = Mollusk::default().with_context(account_store);
+        context.process_and_validate_instruction(
+            &trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount),
+            &[Check::success()],
+        );
+        context.account_store.borrow().save();
+    }
+
+    // Second run: resume from the saved state.
+    let account_store = FileAccountStore::open(&path);
+    assert_eq!(
+        account_store.get_account(&sender).unwrap().lamports(),
+        base_lamports - transfer_amount,
+    );
+    assert_eq!(
+        account_store.get_account(&recipient).unwrap().lamports(),
+        base_lamports + transfer_amount,
+    );
+
+    let context = Mollusk::default().with_context(account_store);
+    context.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount),
+        &[
+            Check::success(),
+            Check::account(&recipient)
+                .lamports(base_lamports + 2 * transfer_amount)
+                .build(),
+        ],
+    );
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_lru_account_store_eviction() {
+    let owner = Pubkey::new_unique();
+    let account = |space: usize| Account::new(1_000, space, &owner);
+    let size = LruAccountStore::account_size(&account(100));
+    assert_eq!(size, LruAccountStore::ACCOUNT_OVERHEAD + 100);
+
+    let evicted = Rc::new(RefCell::new(Vec::new()));
+    let mut store = LruAccountStore::new(size * 3).with_eviction_callback({
+        let evicted = Rc::clone(&evicted);
+        move |pubkey, _| evicted.borrow_mut().push(pubkey)
+    });
+
+    let [a, b, c, d, e] = std::array::from_fn(|_| Pubkey::new_unique());
+    store.store_accounts([(a, account(100)), (b, account(100)), (c, account(100))]);
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.bytes(), size * 3);
+    assert!(evicted.borrow().is_empty());
+
+    // Reading `a` makes `b` the least recently used account.
+    assert!(store.get_account(&a).is_some());
+    store.store_account(d, account(100));
+    assert_eq!(*evicted.borrow(), vec![b]);
+    assert!(store.get_account(&b).is_none());
+
+    // Replacing an account accounts for its new size.
+    store.store_account(c, account(50));
+    assert_eq!(store.bytes(), size * 3 - 50);
+
+    // A batch is evicted from once it's stored.
+    store.store_accounts([(e, account(100)), (b, account(100))]);
+    assert_eq!(*evicted.borrow(), vec![b, a, d]);
+    assert!(store.contains(&c) && store.contains(&e) && store.contains(&b));
+    assert_eq!(store.evicted(), 3);
+    assert!(store.bytes() <= store.max_bytes());
+
+    // An account larger than the budget is kept alone.
+    store.store_account(a, account(size * 4));
+    assert_eq!(store.len(), 1);
+    assert!(store.contains(&a));
+}
+
+#[test]
+fn test_lru_account_store_with_context() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let system_program = trezoa_sdk_ids::system_program::id();
+
+    let mut store = LruAccountStore::new(1024 * 1024);
+    store.store_account(sender, Account::new(100_000_000, 0, &system_program));
+    let context = Mollusk::default().with_context(store);
+
+    context.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::transfer(&sender, &recipient, 42),
+        &[Check::success()],
+    );
+
+    let store = context.account_store.borrow();
+    assert_eq!(store.get_account(&recipient).unwrap().lamports(), 42);
+    assert_eq!(
+        store.get_account(&sender).unwrap().lamports(),
+        100_000_000 - 42
+    );
+    assert!(store.bytes() <= store.max_bytes());
+}
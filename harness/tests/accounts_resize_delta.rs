@@ -0,0 +1,99 @@
+use {
+    mollusk_svm::{program::Builtin, result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_instruction::{error::InstructionError, AccountMeta, Instruction},
+    trezoa_program_runtime::declare_process_instruction,
+    trezoa_pubkey::Pubkey,
+};
+
+// Resize the first account to the length in the instruction data.
+declare_process_instruction!(Resize, 100, |invoke_context| {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let data = instruction_context.get_instruction_data();
+    let len = u32::from_le_bytes(data.try_into().unwrap()) as usize;
+    let mut account = instruction_context.try_borrow_instruction_account(0)?;
+    account.set_data_length(len)?;
+    Ok(())
+});
+
+#[test]
+fn test_accounts_resize_delta() {
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::default();
+    mollusk
+        .program_cache
+        .add_builtin(Builtin::new(program_id, "resize", Resize::vm));
+
+    let key = Pubkey::new_unique();
+    let accounts = [(key, Account::new(1_000_000_000, 100, &program_id))];
+    let resize = |len: u32| {
+        Instruction::new_with_bytes(
+            program_id,
+            &len.to_le_bytes(),
+            vec![AccountMeta::new(key, false)],
+        )
+    };
+
+    let result =
+        mollusk.process_and_validate_instruction(&resize(1_100), &accounts, &[Check::success()]);
+    assert_eq!(result.accounts_resize_delta, 1_000);
+
+    let result =
+        mollusk.process_and_validate_instruction(&resize(40), &accounts, &[Check::success()]);
+    assert_eq!(result.accounts_resize_delta, -60);
+
+    // A chain accumulates the delta of each element.
+    let result = mollusk.process_instruction_chain(&[resize(600), resize(300)], &accounts);
+    assert!(result.program_result.is_ok());
+    assert_eq!(result.accounts_resize_delta, 200);
+
+    // A tighter limit fails the instruction exceeding it.
+    mollusk.accounts_resize_delta_limit = 500;
+    mollusk.process_and_validate_instruction(&resize(600), &accounts, &[Check::success()]);
+    mollusk.process_and_validate_instruction(
+        &resize(601),
+        &accounts,
+        &[
+            Check::instruction_err(InstructionError::MaxAccountsDataAllocationsExceeded),
+            Check::account(&key).space(100).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_accounts_resize_delta_limit_across_cpi() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let key = Pubkey::new_unique();
+    let lamports = mollusk.sysvars.rent.minimum_balance(100);
+    let accounts = [(key, Account::new(lamports, 100, &program_id))];
+    // Grow the account to the provided length for a CPI, then shrink it back.
+    let resize_over_cpi = |len: u32| {
+        let mut data = vec![7];
+        data.extend_from_slice(&len.to_le_bytes());
+        Instruction::new_with_bytes(program_id, &data, vec![AccountMeta::new(key, false)])
+    };
+
+    mollusk.accounts_resize_delta_limit = 500;
+
+    let result = mollusk.process_and_validate_instruction(
+        &resize_over_cpi(600),
+        &accounts,
+        &[Check::success(), Check::account(&key).space(100).build()],
+    );
+    assert_eq!(result.accounts_resize_delta, 0);
+
+    // The limit is exceeded during the CPI, even though the instruction's net
+    // growth is zero.
+    mollusk.process_and_validate_instruction(
+        &resize_over_cpi(601),
+        &accounts,
+        &[Check::instruction_err(
+            InstructionError::MaxAccountsDataAllocationsExceeded,
+        )],
+    );
+}
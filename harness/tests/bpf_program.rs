@@ -1,12 +1,16 @@
 use {
     mollusk_svm::{
-        program::{create_program_account_loader_v3, keyed_account_for_system_program},
-        result::{Check, CheckContext},
+        program::{
+            create_program_account_loader_v3, keyed_account_for_system_program, loader_keys,
+            SBPFVersion,
+        },
+        result::{types::ExecutionMode, Check, CheckContext, Compare},
         Mollusk,
     },
     trezoa_account::Account,
     trezoa_instruction::{error::InstructionError, AccountMeta, Instruction},
     trezoa_program_error::ProgramError,
+    trezoa_program_runtime::loaded_programs::ProgramCacheEntryType,
     trezoa_pubkey::Pubkey,
     trezoa_rent::Rent,
     trezoa_system_interface::error::SystemError,
@@ -365,7 +369,7 @@ fn test_cpi() {
     }
 
     // Success.
-    mollusk.process_and_validate_instruction(
+    let result = mollusk.process_and_validate_instruction(
         &instruction,
         &[
             (key, account.clone()),
@@ -385,6 +389,10 @@ fn test_cpi() {
                 .build(),
         ],
     );
+
+    // The top-level instruction and the CPI.
+    assert_eq!(result.instruction_trace_length, 2);
+    assert_eq!(result.max_stack_height, 2);
 }
 
 #[test]
@@ -524,6 +532,13 @@ fn test_inner_instructions_transfer() {
         &[
             Check::success(),
             Check::inner_instruction_count(1),
+            Check::cpi_to(&trezoa_sdk_ids::system_program::id()),
+            Check::inner_instruction(0)
+                .program_id(&trezoa_sdk_ids::system_program::id())
+                .accounts(&[payer, recipient])
+                // `SystemInstruction::Transfer`.
+                .data_prefix(&2u32.to_le_bytes())
+                .build(),
             Check::account(&payer)
                 .lamports(payer_lamports - transfer_amount)
                 .build(),
@@ -564,6 +579,64 @@ fn test_inner_instructions_transfer() {
         account_keys[recipient_index], recipient,
         "Inner instruction second account should be the recipient"
     );
+
+    let config = mollusk_svm::result::Config::default().panic(false);
+    let failing_checks = [
+        Check::cpi_to(&program_id),
+        Check::inner_instruction(0)
+            .accounts(&[recipient, payer])
+            .build(),
+        Check::inner_instruction(1).build(),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config, &mollusk));
+    }
+}
+
+#[test]
+fn test_direct_mapping_readonly_write() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, &program_id);
+
+    let instruction = {
+        let mut instruction_data = vec![6];
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![AccountMeta::new_readonly(key, false)],
+        )
+    };
+    let accounts = [(key, account)];
+
+    // The write faults during execution with direct mapping, and is caught
+    // after execution without it, but the outcome is the same.
+    let (without, with) = mollusk.process_and_compare_direct_mapping(
+        &instruction,
+        &accounts,
+        &Compare::everything_but_cus(),
+    );
+    let checks = [
+        Check::instruction_err(InstructionError::ReadonlyDataModified),
+        Check::account(&key).data(&[0; 5]).build(),
+    ];
+    without.run_checks(&checks, &mollusk.config, &mollusk);
+    with.run_checks(&checks, &mollusk.config, &mollusk);
+
+    // The feature set is restored afterwards.
+    assert!(mollusk
+        .feature_set
+        .is_active(&trezoa_feature_set::account_data_direct_mapping::id()));
 }
 
 #[test]
@@ -685,3 +758,558 @@ fn test_account_checks_rent_exemption() {
     // Success rent exempt.
     assert!(get_result(rent_exempt_lamports));
 }
+
+#[test]
+fn test_all_accounts_owned_by() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    mollusk.config.panic = false; // Don't panic, so we can evaluate failing checks.
+
+    let data_len = 8;
+    let rent_exempt_lamports = mollusk.sysvars.rent.minimum_balance(data_len);
+
+    let key = Pubkey::new_unique();
+    let owned_a = Pubkey::new_unique();
+    let owned_b = Pubkey::new_unique();
+    let not_owned = Pubkey::new_unique();
+    let not_rent_exempt = Pubkey::new_unique();
+
+    let result = mollusk.process_and_validate_instruction(
+        &Instruction::new_with_bytes(
+            program_id,
+            &{
+                let mut instruction_data = vec![1]; // `WriteData`
+                instruction_data.extend_from_slice(&[4; 8]);
+                instruction_data
+            },
+            vec![
+                AccountMeta::new(key, true),
+                AccountMeta::new_readonly(owned_a, false),
+                AccountMeta::new_readonly(owned_b, false),
+                AccountMeta::new_readonly(not_owned, false),
+                AccountMeta::new_readonly(not_rent_exempt, false),
+            ],
+        ),
+        &[
+            (
+                key,
+                Account::new(rent_exempt_lamports, data_len, &program_id),
+            ),
+            (
+                owned_a,
+                Account::new(rent_exempt_lamports, data_len, &program_id),
+            ),
+            (
+                owned_b,
+                Account::new(rent_exempt_lamports, data_len, &program_id),
+            ),
+            (
+                not_owned,
+                Account::new(
+                    rent_exempt_lamports,
+                    data_len,
+                    &trezoa_sdk_ids::system_program::id(),
+                ),
+            ),
+            (
+                not_rent_exempt,
+                Account::new(rent_exempt_lamports - 1, data_len, &program_id),
+            ),
+        ],
+        &[Check::success()],
+    );
+
+    let run = |accounts: &[Pubkey]| {
+        result.run_checks(
+            &[Check::all_accounts_owned_by(&program_id, accounts)],
+            &mollusk.config,
+            &mollusk,
+        )
+    };
+
+    // Success all owned and rent exempt.
+    assert!(run(&[key, owned_a, owned_b]));
+
+    // Fail wrong owner.
+    assert!(!run(&[owned_a, not_owned]));
+
+    // Fail not rent exempt.
+    assert!(!run(&[owned_a, not_rent_exempt]));
+
+    // Fail account not found.
+    assert!(!run(&[owned_a, Pubkey::new_unique()]));
+}
+
+#[test]
+fn test_program_load_timings() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let timings = mollusk
+        .program_cache
+        .get_program_load_timings(&program_id)
+        .unwrap();
+    assert!(timings.total_us >= timings.register_syscalls_us);
+    assert!(timings.total_us >= timings.load_elf_us + timings.verify_code_us);
+
+    // Builtins aren't loaded from an ELF.
+    assert!(mollusk
+        .program_cache
+        .get_program_load_timings(&trezoa_sdk_ids::system_program::id())
+        .is_none());
+}
+
+#[test]
+fn test_iter_program_cache_entries() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+    let entries = mollusk.program_cache.iter_entries();
+
+    assert_eq!(entries.len(), mollusk.program_cache.num_programs());
+    assert!(entries.is_sorted_by_key(|(program_id, _, _)| *program_id));
+
+    let elf = mollusk_svm::file::load_program_elf("test_program_primary");
+    assert!(entries.contains(&(
+        program_id,
+        trezoa_sdk_ids::bpf_loader_upgradeable::id(),
+        elf.len()
+    )));
+
+    // Builtins have no ELF.
+    assert!(entries.contains(&(
+        trezoa_sdk_ids::system_program::id(),
+        trezoa_sdk_ids::native_loader::id(),
+        0
+    )));
+}
+
+#[test]
+fn test_replace_and_remove_program() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    let program_ids = |mollusk: &Mollusk| {
+        mollusk
+            .program_cache
+            .iter_entries()
+            .into_iter()
+            .map(|(program_id, _, _)| program_id)
+            .collect::<Vec<_>>()
+    };
+    let cached = program_ids(&mollusk);
+    assert!(cached.contains(&program_id));
+
+    // The primary program treats this as a no-op.
+    let key = Pubkey::new_unique();
+    let data = [0, 1, 2];
+    let account = Account::new(
+        mollusk.sysvars.rent.minimum_balance(data.len()),
+        data.len(),
+        &program_id,
+    );
+    let instruction =
+        Instruction::new_with_bytes(program_id, &data, vec![AccountMeta::new(key, true)]);
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account.clone())],
+        &[Check::success(), Check::account(&key).data(&[0; 3]).build()],
+    );
+
+    // Upgrade the program to a different ELF.
+    let primary_elf = mollusk_svm::file::load_program_elf("test_program_primary");
+    let cpi_target_elf = mollusk_svm::file::load_program_elf("test_program_cpi_target");
+    let previous = mollusk
+        .program_cache
+        .replace_program(&program_id, &loader_keys::LOADER_V3, &cpi_target_elf)
+        .unwrap();
+    assert_eq!(previous.elf_bytes, Some(primary_elf));
+    assert_eq!(
+        mollusk.program_cache.get_program_elf_bytes(&program_id),
+        Some(cpi_target_elf)
+    );
+    assert_eq!(program_ids(&mollusk), cached);
+
+    // The upgraded program writes its whole input to the account instead.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account.clone())],
+        &[Check::success(), Check::account(&key).data(&data).build()],
+    );
+
+    // Close it.
+    let removed = mollusk.program_cache.remove_program(&program_id).unwrap();
+    assert_eq!(removed.loader_key, loader_keys::LOADER_V3);
+    assert!(!program_ids(&mollusk).contains(&program_id));
+    assert_eq!(
+        mollusk.program_cache.num_programs(),
+        mollusk.program_cache.iter_entries().len()
+    );
+    assert!(mollusk
+        .program_cache
+        .get_program_loader_key(&program_id)
+        .is_none());
+    assert!(mollusk
+        .process_instruction(&instruction, &[(key, account)])
+        .program_result
+        .is_err());
+    assert!(mollusk.program_cache.remove_program(&program_id).is_none());
+}
+
+#[test]
+fn test_execution_mode() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, &program_id);
+
+    let instruction = {
+        let mut instruction_data = vec![1];
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![AccountMeta::new(key, true)],
+        )
+    };
+
+    let checks = [
+        Check::success(),
+        Check::compute_units(384),
+        Check::account(&key).data(data).build(),
+    ];
+
+    let default_mode = if mollusk_svm::program::JIT_SUPPORTED {
+        ExecutionMode::Jit
+    } else {
+        ExecutionMode::Interpreted
+    };
+    assert_eq!(mollusk.execution_mode(), default_mode);
+    let result =
+        mollusk.process_and_validate_instruction(&instruction, &[(key, account.clone())], &checks);
+    assert_eq!(result.execution_mode, Some(default_mode));
+
+    let is_compiled = |mollusk: &Mollusk| match &mollusk
+        .program_cache
+        .load_program(&program_id)
+        .unwrap()
+        .program
+    {
+        ProgramCacheEntryType::Loaded(executable) => executable.get_compiled_program().is_some(),
+        _ => panic!("the program must be loaded"),
+    };
+    assert_eq!(is_compiled(&mollusk), mollusk_svm::program::JIT_SUPPORTED);
+
+    // Interpreted programs aren't compiled, and consume the same compute
+    // units.
+    mollusk.set_execution_mode(ExecutionMode::Interpreted);
+    assert_eq!(mollusk.execution_mode(), ExecutionMode::Interpreted);
+    assert!(!is_compiled(&mollusk));
+    let result =
+        mollusk.process_and_validate_instruction(&instruction, &[(key, account.clone())], &checks);
+    assert_eq!(result.execution_mode, Some(ExecutionMode::Interpreted));
+
+    // Switching back reloads programs with JIT, where supported.
+    mollusk.set_execution_mode(ExecutionMode::Jit);
+    assert_eq!(mollusk.execution_mode(), default_mode);
+    assert_eq!(is_compiled(&mollusk), mollusk_svm::program::JIT_SUPPORTED);
+    let result = mollusk.process_and_validate_instruction(&instruction, &[(key, account)], &checks);
+    assert_eq!(result.execution_mode, Some(default_mode));
+}
+
+#[test]
+fn test_add_program_with_sbpf_version() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let v0_id = Pubkey::new_unique();
+    let v3_id = Pubkey::new_unique();
+
+    // Load the v0 and v3 builds of the same program side by side.
+    let mut mollusk = Mollusk::default();
+    mollusk.add_program_with_sbpf_version(
+        &v0_id,
+        "test_program_primary",
+        &loader_keys::LOADER_V3,
+        SBPFVersion::V0,
+    );
+    let v3_elf = mollusk_svm::file::read_file("../target/deploy/v3/test_program_primary.so");
+    mollusk.program_cache.add_program_with_sbpf_version(
+        &v3_id,
+        &loader_keys::LOADER_V3,
+        &v3_elf,
+        SBPFVersion::V3,
+    );
+
+    let assert_versions = |mollusk: &Mollusk| {
+        assert_eq!(
+            mollusk.program_cache.get_program_sbpf_version(&v0_id),
+            Some(SBPFVersion::V0)
+        );
+        assert_eq!(
+            mollusk.program_cache.get_program_sbpf_version(&v3_id),
+            Some(SBPFVersion::V3)
+        );
+    };
+    assert_versions(&mollusk);
+    assert!(mollusk
+        .program_cache
+        .get_program_sbpf_version(&trezoa_sdk_ids::system_program::id())
+        .is_none());
+
+    let data = &[1, 2, 3];
+    let write = |program_id: &Pubkey| {
+        let key = Pubkey::new_unique();
+        let account = Account::new(
+            mollusk.sysvars.rent.minimum_balance(data.len()),
+            data.len(),
+            program_id,
+        );
+        let instruction = Instruction::new_with_bytes(
+            *program_id,
+            &[&[1], &data[..]].concat(),
+            vec![AccountMeta::new(key, true)],
+        );
+        (key, account, instruction)
+    };
+    let (v0_key, v0_account, v0_instruction) = write(&v0_id);
+    let (v3_key, v3_account, v3_instruction) = write(&v3_id);
+    let accounts = [(v0_key, v0_account), (v3_key, v3_account)];
+    let chain = [
+        (
+            &v0_instruction,
+            &[Check::success(), Check::account(&v0_key).data(data).build()][..],
+        ),
+        (
+            &v3_instruction,
+            &[Check::success(), Check::account(&v3_key).data(data).build()][..],
+        ),
+    ];
+    mollusk.process_and_validate_instruction_chain(&chain, &accounts);
+
+    // The pinned versions survive a reload.
+    mollusk.set_execution_mode(ExecutionMode::Interpreted);
+    assert_versions(&mollusk);
+    mollusk.process_and_validate_instruction_chain(&chain, &accounts);
+}
+
+#[test]
+#[should_panic(expected = "UnsupportedSBPFVersion")]
+fn test_add_program_with_mismatched_sbpf_version() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    let sbpf_version = match mollusk.program_cache.get_program_sbpf_version(&program_id) {
+        Some(SBPFVersion::V0) => SBPFVersion::V3,
+        _ => SBPFVersion::V0,
+    };
+
+    // The ELF doesn't target the pinned version, so it can't be loaded.
+    mollusk.add_program_with_sbpf_version(
+        &Pubkey::new_unique(),
+        "test_program_primary",
+        &loader_keys::LOADER_V3,
+        sbpf_version,
+    );
+}
+
+#[test]
+fn test_account_check_equals() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    mollusk.config.panic = false; // Don't panic, so we can evaluate failing checks.
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, &program_id);
+
+    let result = mollusk.process_and_validate_instruction(
+        &Instruction::new_with_bytes(
+            program_id,
+            &{
+                let mut instruction_data = vec![1]; // `WriteData`
+                instruction_data.extend_from_slice(data);
+                instruction_data
+            },
+            vec![AccountMeta::new(key, true)],
+        ),
+        &[(key, account.clone())],
+        &[Check::success()],
+    );
+
+    let expected = Account {
+        data: data.to_vec(),
+        ..account
+    };
+
+    let run = |expected: &Account| {
+        result.run_checks(
+            &[Check::account(&key).equals(expected).build()],
+            &mollusk.config,
+            &mollusk,
+        )
+    };
+
+    // Success exact match.
+    assert!(run(&expected));
+
+    // Fail any single field mismatch.
+    assert!(!run(&Account {
+        lamports: lamports + 1,
+        ..expected.clone()
+    }));
+    assert!(!run(&Account {
+        data: vec![0; space],
+        ..expected.clone()
+    }));
+    assert!(!run(&Account {
+        owner: Pubkey::new_unique(),
+        ..expected.clone()
+    }));
+    assert!(!run(&Account {
+        executable: true,
+        ..expected.clone()
+    }));
+    assert!(!run(&Account {
+        rent_epoch: 1,
+        ..expected.clone()
+    }));
+}
+
+#[test]
+fn test_custom_err_from() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    mollusk.config.panic = false; // Don't panic, so we can evaluate failing checks.
+
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    // The CPI to the system program overdraws the payer.
+    let instruction = {
+        let mut instruction_data = vec![2];
+        instruction_data.extend_from_slice(&2_000_000_u64.to_le_bytes());
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(trezoa_sdk_ids::system_program::id(), false),
+            ],
+        )
+    };
+
+    let result = mollusk.process_instruction(
+        &instruction,
+        &[
+            (payer, Account::default()),
+            (recipient, Account::default()),
+            keyed_account_for_system_program(),
+        ],
+    );
+
+    let system_program_id = trezoa_sdk_ids::system_program::id();
+    assert_eq!(result.failing_program_id, Some(system_program_id));
+
+    let code = SystemError::ResultWithNegativeLamports as u32;
+    let run = |check: Check| result.run_checks(&[check], &mollusk.config, &mollusk);
+
+    // The error is attributed to the system program, not the caller.
+    assert!(run(Check::custom_err_from(&system_program_id, code)));
+    assert!(!run(Check::custom_err_from(&program_id, code)));
+    assert!(!run(Check::custom_err_from(&system_program_id, code + 1)));
+}
+
+#[test]
+fn test_failing_program_id_without_logs() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    mollusk.collect_logs = false;
+
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let system_program_id = trezoa_sdk_ids::system_program::id();
+    let accounts = [
+        (payer, Account::default()),
+        (recipient, Account::default()),
+        keyed_account_for_system_program(),
+    ];
+
+    // Failures are attributed from the instruction trace, not the logs.
+    let result = mollusk.process_instruction(
+        &trezoa_system_interface::instruction::transfer(&payer, &recipient, 1),
+        &accounts,
+    );
+    assert!(result.program_result.is_err());
+    assert!(result.logs.is_empty());
+    assert_eq!(result.failing_program_id, Some(system_program_id));
+
+    // The failing CPI is the deepest failure, not its caller.
+    let instruction = {
+        let mut instruction_data = vec![2];
+        instruction_data.extend_from_slice(&2_000_000_u64.to_le_bytes());
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program_id, false),
+            ],
+        )
+    };
+    let result = mollusk.process_instruction(&instruction, &accounts);
+    assert!(result.program_result.is_err());
+    assert!(result.logs.is_empty());
+    assert_eq!(result.failing_program_id, Some(system_program_id));
+
+    // A program failing before its CPI is the deepest failure itself.
+    let instruction = {
+        let mut instruction_data = vec![2];
+        instruction_data.extend_from_slice(&1_000_u64.to_le_bytes());
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(payer, false),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program_id, false),
+            ],
+        )
+    };
+    let result = mollusk.process_instruction(&instruction, &accounts);
+    assert!(result.program_result.is_err());
+    assert_eq!(result.failing_program_id, Some(program_id));
+}
@@ -0,0 +1,72 @@
+use {
+    mollusk_svm::{result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_instruction::{error::InstructionError, AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    trezoa_svm_log_collector::LogCollector,
+};
+
+#[test]
+fn test_builder() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let cpi_target_program_id = Pubkey::new_unique();
+
+    let logger = LogCollector::new_ref();
+
+    let mollusk = Mollusk::builder()
+        .compute_budget(ComputeBudget {
+            compute_unit_limit: 10_000,
+            ..ComputeBudget::new_with_defaults(true, true)
+        })
+        .logger(logger.clone())
+        .programs(&[
+            (program_id, "test_program_primary"),
+            (cpi_target_program_id, "test_program_cpi_target"),
+        ])
+        .build();
+
+    assert_eq!(mollusk.compute_budget.compute_unit_limit, 10_000);
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+
+    let instruction = {
+        let mut instruction_data = vec![1];
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![AccountMeta::new(key, true)],
+        )
+    };
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, Account::new(lamports, space, &program_id))],
+        &[Check::success(), Check::account(&key).data(data).build()],
+    );
+    assert!(!logger.borrow().get_recorded_content().is_empty());
+
+    // The configured compute budget applies to the loaded programs.
+    let mollusk = Mollusk::builder()
+        .compute_budget(ComputeBudget {
+            compute_unit_limit: 10,
+            ..ComputeBudget::new_with_defaults(true, true)
+        })
+        .programs(&[(program_id, "test_program_primary")])
+        .build();
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, Account::new(lamports, space, &program_id))],
+        &[Check::instruction_err(
+            InstructionError::ComputationalBudgetExceeded,
+        )],
+    );
+}
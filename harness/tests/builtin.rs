@@ -0,0 +1,88 @@
+use {
+    mollusk_svm::{program::Builtin, result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_instruction_error::InstructionError,
+    trezoa_program_error::ProgramError,
+    trezoa_program_runtime::declare_process_instruction,
+    trezoa_pubkey::Pubkey,
+};
+
+const WRITE_DATA_COMPUTE_UNITS: u64 = 100;
+
+declare_process_instruction!(WriteData, WRITE_DATA_COMPUTE_UNITS, |invoke_context| {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let data = instruction_context.get_instruction_data().to_vec();
+    let mut account = instruction_context.try_borrow_instruction_account(0)?;
+    account.set_data_from_slice(&data)
+});
+
+declare_process_instruction!(RejectAll, 0, |_invoke_context| {
+    Err(InstructionError::Custom(42))
+});
+
+#[test]
+fn test_custom_builtin() {
+    let program_id = Pubkey::new_unique();
+    // Names don't need to be `'static`.
+    let name = format!("write_data_{}", program_id);
+    let builtin = Builtin::new(program_id, name.clone(), WriteData::vm);
+    assert_eq!(builtin.program_id(), &program_id);
+    assert_eq!(builtin.name(), name);
+
+    let (program_key, program_account) = builtin.keyed_account();
+    assert_eq!(program_key, program_id);
+    assert_eq!(program_account.data, name.as_bytes());
+    assert!(program_account.executable);
+
+    let mut mollusk = Mollusk::default();
+    mollusk.program_cache.add_builtin(builtin);
+
+    let key = Pubkey::new_unique();
+    let data = [1, 2, 3, 4, 5];
+    let account = Account::new(
+        mollusk.sysvars.rent.minimum_balance(data.len()),
+        data.len(),
+        &program_id,
+    );
+    let instruction =
+        Instruction::new_with_bytes(program_id, &data, vec![AccountMeta::new(key, false)]);
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account)],
+        &[
+            Check::success(),
+            Check::compute_units(WRITE_DATA_COMPUTE_UNITS),
+            Check::account(&key).data(&data).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_replace_builtin() {
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let accounts = [
+        (sender, Account::new(100_000_000, 0, &system_program)),
+        (recipient, Account::new(100_000_000, 0, &system_program)),
+    ];
+    let transfer = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+
+    let mut mollusk = Mollusk::default();
+    mollusk.process_and_validate_instruction(&transfer, &accounts, &[Check::success()]);
+
+    // A builtin with the System program's ID replaces it.
+    mollusk.program_cache.add_builtin(Builtin::new(
+        system_program,
+        "system_program",
+        RejectAll::vm,
+    ));
+    mollusk.process_and_validate_instruction(
+        &transfer,
+        &accounts,
+        &[Check::err(ProgramError::Custom(42))],
+    );
+}
@@ -0,0 +1,80 @@
+use {
+    mollusk_svm::{
+        result::{Check, Compare, Config},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::Instruction,
+    trezoa_pubkey::Pubkey,
+    trezoa_system_program::system_processor::DEFAULT_COMPUTE_UNITS,
+};
+
+fn config() -> Config {
+    Config::default().panic(false)
+}
+
+fn transfer() -> (Instruction, Vec<(Pubkey, Account)>) {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 1_000);
+    let accounts = vec![
+        (
+            sender,
+            Account::new(1_000_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (recipient, Account::default()),
+    ];
+    (instruction, accounts)
+}
+
+#[test]
+fn test_compute_units_tolerance_checks() {
+    let mollusk = Mollusk::default();
+    let config = config();
+
+    let (instruction, accounts) = transfer();
+    let units = DEFAULT_COMPUTE_UNITS;
+    let result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::compute_units_within(units, 0),
+            Check::compute_units_within(units + 50, 50),
+            Check::compute_units_within(units - 50, 50),
+            Check::compute_units_at_most(units),
+            Check::compute_units_at_most(units * 2),
+        ],
+    );
+
+    let failing_checks = [
+        Check::compute_units_within(units + 51, 50),
+        Check::compute_units_within(units - 51, 50),
+        Check::compute_units_at_most(units - 1),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config, &mollusk));
+    }
+}
+
+#[test]
+fn test_compare_compute_units_within() {
+    let mollusk = Mollusk::default();
+    let config = config();
+
+    let (instruction, accounts) = transfer();
+    let expected = mollusk.process_instruction(&instruction, &accounts);
+    // The same transfer, twice.
+    let slower = mollusk.process_instruction_chain(&[instruction.clone(), instruction], &accounts);
+    assert_eq!(
+        slower.compute_units_consumed,
+        expected.compute_units_consumed + DEFAULT_COMPUTE_UNITS
+    );
+
+    let tolerance = DEFAULT_COMPUTE_UNITS;
+    for (a, b) in [(&expected, &slower), (&slower, &expected)] {
+        assert!(a.compare_with_config(b, &[Compare::ComputeUnitsWithin(tolerance)], &config));
+        assert!(!a.compare_with_config(b, &[Compare::ComputeUnitsWithin(tolerance - 1)], &config));
+        assert!(!a.compare_with_config(b, &[Compare::ComputeUnits], &config));
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "fuzz")]
+
+use {
+    mollusk_svm::{fuzz::mollusk::build_fixture_from_mollusk_test, Mollusk},
+    mollusk_svm_fuzz_fixture::{proto::InstrFixture, Fixture},
+    mollusk_svm_fuzz_fs::{corpus::Corpus, FsHandler},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::path::{Path, PathBuf},
+};
+
+fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("mollusk-corpus-{}", Pubkey::new_unique()))
+}
+
+fn transfer_fixture(mollusk: &Mollusk, lamports: u64) -> Fixture {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, lamports);
+    let accounts = [
+        (sender, Account::new(100_000_000, 0, &system_program)),
+        (recipient, Account::new(100_000_000, 0, &system_program)),
+    ];
+    let result = mollusk.process_instruction(&instruction, &accounts);
+    build_fixture_from_mollusk_test(mollusk, &instruction, &accounts, &result)
+}
+
+fn dump(fixture: Fixture, dir: &Path) {
+    FsHandler::new(fixture).dump_to_blob_file(dir.to_str().unwrap());
+}
+
+fn count_files(dir: impl AsRef<Path>) -> usize {
+    std::fs::read_dir(dir).unwrap().count()
+}
+
+#[test]
+fn test_corpus_dedupe() {
+    let mollusk = Mollusk::default();
+    let dir = temp_dir();
+
+    let fixture = transfer_fixture(&mollusk, 42);
+    // Same context, different effects.
+    let mut duplicate = fixture.clone();
+    duplicate.output.compute_units_consumed += 1;
+
+    dump(fixture, &dir);
+    dump(duplicate, &dir);
+    dump(transfer_fixture(&mollusk, 43), &dir);
+
+    let mut corpus = Corpus::<InstrFixture>::load(&dir);
+    assert_eq!(corpus.len(), 3);
+    assert_eq!(corpus.duplicates().len(), 1);
+
+    let removed = corpus.dedupe();
+    assert_eq!(removed.len(), 1);
+    assert!(!removed[0].exists());
+    assert_eq!(corpus.len(), 2);
+    assert_eq!(count_files(&dir), 2);
+
+    let corpus = Corpus::<InstrFixture>::load(&dir);
+    assert!(corpus.duplicates().is_empty());
+}
+
+#[test]
+fn test_corpus_split_and_merge() {
+    let mollusk = Mollusk::default();
+    let dir_a = temp_dir();
+    let dir_b = temp_dir();
+
+    let shared = transfer_fixture(&mollusk, 42);
+    dump(shared.clone(), &dir_a);
+    dump(shared, &dir_b);
+    for lamports in 0..8 {
+        dump(transfer_fixture(&mollusk, 100 + lamports), &dir_a);
+    }
+    dump(transfer_fixture(&mollusk, 200), &dir_b);
+
+    let corpus = Corpus::<InstrFixture>::load_all([&dir_a, &dir_b]);
+    assert_eq!(corpus.len(), 11);
+
+    let merged = temp_dir();
+    assert_eq!(corpus.merge_into(&merged), 10);
+    assert_eq!(count_files(&merged), 10);
+
+    let shards = Corpus::<InstrFixture>::load(&merged).split(temp_dir(), 3);
+    assert_eq!(shards.len(), 3);
+    assert_eq!(shards.iter().map(count_files).sum::<usize>(), 10);
+
+    // Splitting again assigns each fixture to the same shard.
+    let again = Corpus::<InstrFixture>::load(&merged).split(temp_dir(), 3);
+    for (shard, shard_again) in shards.iter().zip(&again) {
+        let names = |dir: &Path| {
+            let mut names = std::fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(names(shard), names(shard_again));
+    }
+}
+
+#[test]
+fn test_corpus_file_name_collisions() {
+    let mollusk = Mollusk::default();
+    let dir_a = temp_dir();
+    let dir_b = temp_dir();
+
+    // Different fixtures, sharing a file name in each corpus.
+    for (dir, lamports) in [(&dir_a, 42), (&dir_b, 43)] {
+        dump(transfer_fixture(&mollusk, lamports), dir);
+        let entry = std::fs::read_dir(dir).unwrap().next().unwrap();
+        std::fs::rename(entry.unwrap().path(), dir.join("fixture.fix")).unwrap();
+    }
+
+    let corpus = Corpus::<InstrFixture>::load_all([&dir_a, &dir_b]);
+    assert_eq!(corpus.len(), 2);
+
+    let merged = temp_dir();
+    assert_eq!(corpus.merge_into(&merged), 2);
+    assert_eq!(count_files(&merged), 2);
+
+    let shards = corpus.split(temp_dir(), 1);
+    assert_eq!(count_files(&shards[0]), 2);
+}
@@ -66,3 +66,128 @@ fn test_custom_syscall() {
         );
     }
 }
+
+#[test]
+fn test_register_syscall() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::builder()
+        .syscall("sol_burn_cus", SyscallBurnCus::vm)
+        .programs(&[(program_id, "test_program_custom_syscall")])
+        .build();
+
+    let base_cus = mollusk
+        .process_and_validate_instruction(
+            &instruction_burn_cus(&program_id, 0),
+            &[],
+            &[Check::success()],
+        )
+        .compute_units_consumed;
+
+    // The syscall survives rebuilding the runtime environment.
+    mollusk
+        .program_cache
+        .set_feature_set(&mollusk.feature_set, &mollusk.compute_budget);
+    mollusk.process_and_validate_instruction(
+        &instruction_burn_cus(&program_id, 1_000),
+        &[],
+        &[Check::success(), Check::compute_units(base_cus + 1_000)],
+    );
+
+    // It's also registered on the environments used while processing
+    // messages.
+    let environments = mollusk.runtime_environments.get(
+        &mollusk.feature_set,
+        &mollusk.compute_budget,
+        false,
+        mollusk.program_cache.syscalls(),
+    );
+    assert!(environments
+        .program_runtime_v1
+        .get_function_registry()
+        .lookup_by_name(b"sol_burn_cus")
+        .is_some());
+}
+
+#[cfg(feature = "all-builtins")]
+#[test]
+fn test_register_syscall_loader_deploy() {
+    use {
+        mollusk_svm::program::{LoaderV4AccountBuilder, LoaderV4Status},
+        trezoa_account::Account,
+        trezoa_loader_v4_interface::instruction::deploy,
+    };
+
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let elf = mollusk_svm::file::load_program_elf("test_program_custom_syscall");
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let accounts = [
+        (
+            program_id,
+            LoaderV4AccountBuilder::new(&elf)
+                .authority(&authority)
+                .status(LoaderV4Status::Retracted)
+                .build(),
+        ),
+        (
+            authority,
+            Account::new(1_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    // The loader verifies the program against the environment it's given,
+    // so deploying fails without the syscall.
+    let mut mollusk = Mollusk::default();
+    mollusk.warp_to_slot(10);
+    let result = mollusk.process_instruction(&deploy(&program_id, &authority), &accounts);
+    assert!(result.program_result.is_err());
+
+    mollusk.register_syscall("sol_burn_cus", SyscallBurnCus::vm);
+    let result = mollusk.process_instruction(&deploy(&program_id, &authority), &accounts);
+    assert!(result.program_result.is_ok());
+
+    let base_cus = mollusk
+        .process_and_validate_instruction(
+            &instruction_burn_cus(&program_id, 0),
+            &result.resulting_accounts[..1],
+            &[Check::success()],
+        )
+        .compute_units_consumed;
+    mollusk.process_and_validate_instruction(
+        &instruction_burn_cus(&program_id, 100),
+        &result.resulting_accounts[..1],
+        &[Check::success(), Check::compute_units(base_cus + 100)],
+    );
+}
+
+#[test]
+fn test_set_feature_set_drops_gated_syscalls() {
+    let has_syscall = |mollusk: &Mollusk, name: &[u8]| {
+        mollusk
+            .program_cache
+            .program_runtime_environment
+            .get_function_registry()
+            .lookup_by_name(name)
+            .is_some()
+    };
+
+    let mut mollusk = Mollusk::default();
+    mollusk.register_syscall("sol_burn_cus", SyscallBurnCus::vm);
+    assert!(has_syscall(&mollusk, b"sol_curve_validate_point"));
+
+    mollusk
+        .feature_set
+        .deactivate(&trezoa_feature_set::curve25519_syscall_enabled::id());
+    mollusk
+        .program_cache
+        .set_feature_set(&mollusk.feature_set, &mollusk.compute_budget);
+
+    // The syscall gated off by the feature is dropped, while the custom
+    // syscall is carried over.
+    assert!(!has_syscall(&mollusk, b"sol_curve_validate_point"));
+    assert!(has_syscall(&mollusk, b"sol_burn_cus"));
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "invocation-inspect-callback")]
+
+use {
+    mollusk_svm::{debug::DebugSession, program::keyed_account_for_system_program, Mollusk},
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+};
+
+#[test]
+fn test_debug_session() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let accounts = [
+        (payer, Account::new(100_000_000, 0, &system_program)),
+        (recipient, Account::new(0, 0, &system_program)),
+        keyed_account_for_system_program(),
+    ];
+
+    // Transfer lamports from the payer to the recipient through CPI.
+    let transfer = |lamports: u64| {
+        Instruction::new_with_bytes(
+            program_id,
+            &[&[2], &lamports.to_le_bytes()[..]].concat(),
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program, false),
+            ],
+        )
+    };
+
+    let (result, session) =
+        DebugSession::record(|| mollusk.process_instruction(&transfer(42), &accounts));
+    assert!(result.program_result.is_ok());
+
+    let frames = session.frames();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].program_id, program_id);
+    assert_eq!(frames[0].stack_height, 1);
+    assert_eq!(frames[1].program_id, system_program);
+    assert_eq!(frames[1].stack_height, 2);
+    assert_eq!(session.frames_at_height(2).count(), 1);
+
+    // The top-level frame's consumption includes the CPI.
+    assert!(frames[0].compute_units_consumed() <= result.compute_units_consumed);
+    assert!(frames[0].compute_units_consumed() > frames[1].compute_units_consumed());
+
+    let before = session.state_before_cpi(0).unwrap();
+    let after = session.state_after_cpi(0).unwrap();
+    assert_eq!(before[0], (payer, accounts[0].1.clone()));
+    assert_eq!(after[0].1.lamports, 100_000_000 - 42);
+    assert_eq!(after[1].1.lamports, 42);
+    assert!(session.cpi(1).is_none());
+
+    // Failures are recorded at the level they happened.
+    let (_, session) =
+        DebugSession::record(|| mollusk.process_instruction(&transfer(200_000_000), &accounts));
+    let cpi = session.cpi(0).unwrap();
+    assert!(cpi.result.is_err());
+    assert_eq!(cpi.accounts_after, cpi.accounts_before);
+    assert!(session.frames()[0].result.is_err());
+}
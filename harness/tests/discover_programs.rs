@@ -0,0 +1,135 @@
+use {
+    mollusk_svm::{file, result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+const DEPLOY_DIR: &str = "../target/deploy";
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mollusk-programs-{}", Pubkey::new_unique()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn copy_elf(program_name: &str, dir: &Path, file_name: &str) {
+    fs::copy(
+        format!("{DEPLOY_DIR}/{program_name}.so"),
+        dir.join(format!("{file_name}.so")),
+    )
+    .unwrap();
+}
+
+fn write_keypair(dir: &Path, program_name: &str, program_id: &Pubkey) {
+    let bytes = [0; 32]
+        .iter()
+        .chain(program_id.as_ref())
+        .map(u8::to_string)
+        .collect::<Vec<_>>();
+    fs::write(
+        dir.join(format!("{program_name}-keypair.json")),
+        format!("[{}]", bytes.join(",")),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_discover_programs() {
+    let primary_id = Pubkey::new_unique();
+    let cpi_target_id = Pubkey::new_unique();
+    let shadowed_id = Pubkey::new_unique();
+
+    let dir_a = temp_dir();
+    copy_elf("test_program_primary", &dir_a, "primary");
+    copy_elf("test_program_cpi_target", &dir_a, "cpi_target");
+    copy_elf("test_program_cpi_target", &dir_a, "no_program_id");
+    fs::write(
+        dir_a.join(file::PROGRAM_IDS_MANIFEST),
+        format!("{{\n  \"primary\": \"{primary_id}\"\n}}\n"),
+    )
+    .unwrap();
+    write_keypair(&dir_a, "cpi_target", &cpi_target_id);
+
+    // Programs found in an earlier directory take precedence.
+    let dir_b = temp_dir();
+    copy_elf("test_program_primary", &dir_b, "primary");
+    write_keypair(&dir_b, "primary", &shadowed_id);
+
+    let programs = file::discover_programs_in([&dir_a, &dir_b, &dir_a.join("missing")]);
+    assert_eq!(
+        programs,
+        vec![
+            file::DiscoveredProgram {
+                name: "cpi_target".to_string(),
+                program_id: cpi_target_id,
+                path: dir_a.join("cpi_target.so"),
+            },
+            file::DiscoveredProgram {
+                name: "primary".to_string(),
+                program_id: primary_id,
+                path: dir_a.join("primary.so"),
+            },
+        ]
+    );
+
+    // Every discovered program is loaded.
+    std::env::set_var("SBF_OUT_DIR", &dir_a);
+    let mollusk = Mollusk::with_all_workspace_programs();
+
+    let key = Pubkey::new_unique();
+    let data = [1, 2, 3];
+    let instruction = Instruction::new_with_bytes(
+        primary_id,
+        &[&[1], &data[..]].concat(),
+        vec![AccountMeta::new(key, true)],
+    );
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, Account::new(1_000_000_000, data.len(), &primary_id))],
+        &[Check::success(), Check::account(&key).data(&data).build()],
+    );
+}
+
+#[test]
+fn test_program_ids_manifest_is_json() {
+    let primary_id = Pubkey::new_unique();
+    let other_id = Pubkey::new_unique();
+
+    // Escaped names don't throw off the names and IDs which follow them.
+    let dir = temp_dir();
+    copy_elf("test_program_primary", &dir, "primary");
+    fs::write(
+        dir.join(file::PROGRAM_IDS_MANIFEST),
+        format!("{{\"not \\\"deployed\\\"\": \"{other_id}\", \"primary\": \"{primary_id}\"}}"),
+    )
+    .unwrap();
+
+    let programs = file::discover_programs_in([&dir]);
+    assert_eq!(
+        programs,
+        vec![file::DiscoveredProgram {
+            name: "primary".to_string(),
+            program_id: primary_id,
+            path: dir.join("primary.so"),
+        }]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Invalid program ID file")]
+fn test_invalid_program_ids_manifest() {
+    let dir = temp_dir();
+    copy_elf("test_program_primary", &dir, "primary");
+    fs::write(
+        dir.join(file::PROGRAM_IDS_MANIFEST),
+        "{\"primary\": \"not a program ID\"}",
+    )
+    .unwrap();
+
+    file::discover_programs_in([&dir]);
+}
@@ -296,3 +296,36 @@ fn test_dump_firedancer() {
     std::env::remove_var("EJECT_FUZZ_FIXTURES_JSON_FD");
     clear(EJECT_FUZZ_FIXTURES_FD);
 }
+
+#[cfg(all(feature = "fuzz", feature = "fuzz-fd"))]
+#[test]
+#[serial]
+fn test_dump_paired() {
+    const EJECT_FUZZ_FIXTURES: &str = "./tests/paired-mollusk-fixtures";
+    const EJECT_FUZZ_FIXTURES_FD: &str = "./tests/paired-firedancer-fixtures";
+
+    clear(EJECT_FUZZ_FIXTURES);
+    clear(EJECT_FUZZ_FIXTURES_FD);
+    std::env::set_var("EJECT_FUZZ_FIXTURES", EJECT_FUZZ_FIXTURES);
+    std::env::set_var("EJECT_FUZZ_FIXTURES_FD", EJECT_FUZZ_FIXTURES_FD);
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let setup = TestSetup::new(&sender, &recipient);
+
+    setup.mollusk.process_and_validate_instruction(
+        &setup.instruction,
+        &setup.accounts,
+        &setup.checks,
+    );
+
+    // Both layouts of the same invocation share a name.
+    let fixture_path = find_fixture(EJECT_FUZZ_FIXTURES, &FileType::Blob).unwrap();
+    let fd_fixture_path = find_fixture(EJECT_FUZZ_FIXTURES_FD, &FileType::Blob).unwrap();
+    assert_filenames_match(&fixture_path, &fd_fixture_path);
+
+    std::env::remove_var("EJECT_FUZZ_FIXTURES");
+    std::env::remove_var("EJECT_FUZZ_FIXTURES_FD");
+    clear(EJECT_FUZZ_FIXTURES);
+    clear(EJECT_FUZZ_FIXTURES_FD);
+}
@@ -1,5 +1,10 @@
 use {
-    mollusk_svm::{result::Check, Mollusk},
+    mollusk_svm::{
+        epoch_stake::{EpochStakeBuilder, BOOTSTRAP_ACTIVATION_EPOCH, VOTE_STATE_SIZE},
+        result::Check,
+        stake::create_delegated_stake_account,
+        Mollusk,
+    },
     trezoa_account::Account,
     trezoa_instruction::{AccountMeta, Instruction},
     trezoa_pubkey::Pubkey,
@@ -48,3 +53,189 @@ fn test_epoch_stake() {
         );
     }
 }
+
+#[test]
+fn test_epoch_stake_vote_accounts() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::new(&program_id, "test_program_epoch_stake");
+    mollusk.epoch_stake = mollusk_svm::epoch_stake::create_mock_epoch_stake(350_000_000_000);
+
+    let rent_exempt = mollusk
+        .sysvars
+        .rent
+        .minimum_balance(mollusk_svm::epoch_stake::VOTE_STATE_SIZE);
+
+    let vote_accounts = mollusk.epoch_stake_vote_accounts();
+    assert_eq!(vote_accounts.len(), 3);
+    for (vote_address, account) in &vote_accounts {
+        assert_eq!(account.owner, trezoa_sdk_ids::vote::id());
+        assert_eq!(
+            account.lamports,
+            rent_exempt + mollusk.epoch_stake[vote_address]
+        );
+    }
+
+    // The vote accounts can be loaded into a context's store, and their
+    // balances line up with the epoch stake syscall.
+    let key = Pubkey::new_unique();
+    let (vote_address, vote_account) = vote_accounts[0].clone();
+    let stake = mollusk.epoch_stake[&vote_address];
+
+    let context = mollusk.with_context(std::collections::HashMap::new());
+    context.store_epoch_stake_vote_accounts();
+    context
+        .account_store
+        .borrow_mut()
+        .insert(key, Account::new(1_000, 16, &program_id));
+
+    context.process_and_validate_instruction(
+        &Instruction::new_with_bytes(
+            program_id,
+            &vote_address.to_bytes(),
+            vec![
+                AccountMeta::new(key, false),
+                AccountMeta::new_readonly(vote_address, false),
+            ],
+        ),
+        &[
+            Check::success(),
+            Check::account(&key)
+                .data(&{
+                    let mut data = vec![0; 16];
+                    data[0..8].copy_from_slice(&350_000_000_000u64.to_le_bytes());
+                    data[8..16].copy_from_slice(&stake.to_le_bytes());
+                    data
+                })
+                .build(),
+            Check::account(&vote_address)
+                .lamports(vote_account.lamports)
+                .data(&vote_account.data)
+                .build(),
+        ],
+    );
+}
+
+fn epoch_stake_data(total_stake: u64, stake: u64) -> Vec<u8> {
+    let mut data = vec![0; 16];
+    data[0..8].copy_from_slice(&total_stake.to_le_bytes());
+    data[8..16].copy_from_slice(&stake.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_epoch_stake_builder() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::new(&program_id, "test_program_epoch_stake");
+
+    let vote_a = Pubkey::new_unique();
+    let vote_b = Pubkey::new_unique();
+    let accounts = mollusk.set_epoch_stake(
+        EpochStakeBuilder::default()
+            .vote_account(vote_a, 1_000_000_000)
+            .vote_account(vote_b, 2_000_000_000)
+            .delegation(vote_b, 500_000_000),
+    );
+
+    assert_eq!(accounts.vote_accounts.len(), 2);
+    assert_eq!(accounts.stake_accounts.len(), 3);
+    assert_eq!(mollusk.epoch_stake[&vote_a], 1_000_000_000);
+    assert_eq!(mollusk.epoch_stake[&vote_b], 2_500_000_000);
+
+    let epoch = mollusk.sysvars.clock.epoch;
+    let entry = mollusk.sysvars.stake_history.get(epoch).unwrap();
+    assert_eq!(entry.effective, 3_500_000_000);
+    assert_eq!(entry.activating, 0);
+
+    let vote_rent_exempt = mollusk.sysvars.rent.minimum_balance(VOTE_STATE_SIZE);
+    let (_, vote_account_b) = &accounts.vote_accounts[1];
+    assert_eq!(vote_account_b.lamports, vote_rent_exempt + 2_500_000_000);
+
+    let key = Pubkey::new_unique();
+    let mut instruction_accounts = vec![(key, Account::new(1_000, 16, &program_id))];
+    instruction_accounts.extend(accounts.accounts());
+
+    mollusk.process_and_validate_instruction(
+        &Instruction::new_with_bytes(
+            program_id,
+            &vote_b.to_bytes(),
+            vec![AccountMeta::new(key, false)],
+        ),
+        &instruction_accounts,
+        &[
+            Check::success(),
+            Check::account(&key)
+                .data(&epoch_stake_data(3_500_000_000, 2_500_000_000))
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn test_sync_epoch_stake() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::new(&program_id, "test_program_epoch_stake");
+    mollusk.sync_epoch_stake = true;
+
+    let vote_a = Pubkey::new_unique();
+    let vote_b = Pubkey::new_unique();
+    mollusk.epoch_stake.insert(vote_a, 1_000_000_000);
+    mollusk.epoch_stake.insert(vote_b, 2_000_000_000);
+
+    // A test-supplied stake account delegating more to `vote_b` than
+    // configured.
+    let stake_address = Pubkey::new_unique();
+    let stake_account = create_delegated_stake_account(
+        &Pubkey::new_unique(),
+        &vote_b,
+        5_000_000_000,
+        &mollusk.sysvars.rent,
+        BOOTSTRAP_ACTIVATION_EPOCH,
+    );
+
+    let key = Pubkey::new_unique();
+    let instruction = Instruction::new_with_bytes(
+        program_id,
+        &vote_b.to_bytes(),
+        vec![
+            AccountMeta::new(key, false),
+            AccountMeta::new_readonly(stake_address, false),
+        ],
+    );
+    let accounts = [
+        (key, Account::new(1_000, 16, &program_id)),
+        (stake_address, stake_account),
+    ];
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&key)
+                .data(&epoch_stake_data(6_000_000_000, 5_000_000_000))
+                .build(),
+        ],
+    );
+
+    // The configured epoch stake is left untouched.
+    assert_eq!(mollusk.epoch_stake[&vote_b], 2_000_000_000);
+
+    // Without syncing, the configured epoch stake is used.
+    mollusk.sync_epoch_stake = false;
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&key)
+                .data(&epoch_stake_data(3_000_000_000, 2_000_000_000))
+                .build(),
+        ],
+    );
+}
@@ -0,0 +1,89 @@
+use {
+    mollusk_svm::{
+        result::{
+            events::{Event, EventSource},
+            Check, Config,
+        },
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::Instruction,
+    trezoa_pubkey::Pubkey,
+};
+
+const DISCRIMINATOR: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+const OTHER_DISCRIMINATOR: [u8; 8] = [8, 7, 6, 5, 4, 3, 2, 1];
+
+#[test]
+fn test_logs_captured() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    let accounts = [
+        (
+            sender,
+            Account::new(100_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(100_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let result = Mollusk::default().process_instruction(&instruction, &accounts);
+    assert_eq!(
+        result.logs.last().unwrap(),
+        &format!("Program {} success", trezoa_sdk_ids::system_program::id()),
+    );
+    assert!(result.events().is_empty());
+}
+
+#[test]
+fn test_event_checks() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+    let config = Config::default().panic(false);
+
+    // Log the discriminator, followed by a `u32` amount of 42.
+    let mut instruction_data = vec![9];
+    instruction_data.extend_from_slice(&DISCRIMINATOR);
+    instruction_data.extend_from_slice(&42u32.to_le_bytes());
+    let instruction = Instruction::new_with_bytes(program_id, &instruction_data, vec![]);
+
+    let result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &[],
+        &[
+            Check::success(),
+            Check::event_emitted(&DISCRIMINATOR),
+            Check::event_data(&DISCRIMINATOR, &42u32.to_le_bytes()),
+            Check::event_with(&DISCRIMINATOR, |data| data[0] == 42),
+        ],
+    );
+
+    assert_eq!(
+        result.events(),
+        vec![Event {
+            source: EventSource::Log,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 42, 0, 0, 0],
+        }]
+    );
+    assert_eq!(
+        result.decode_events(&DISCRIMINATOR, |data| {
+            Some(u32::from_le_bytes(data.try_into().ok()?))
+        }),
+        vec![42]
+    );
+
+    let failing_checks = [
+        Check::event_emitted(&OTHER_DISCRIMINATOR),
+        Check::event_data(&DISCRIMINATOR, &43u32.to_le_bytes()),
+        Check::event_with(&DISCRIMINATOR, |data| data.is_empty()),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config, &mollusk));
+    }
+}
@@ -0,0 +1,75 @@
+use {
+    mollusk_svm::{program::Builtin, result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_instruction::{error::InstructionError, AccountMeta, Instruction},
+    trezoa_program_error::ProgramError,
+    trezoa_program_runtime::declare_process_instruction,
+    trezoa_pubkey::Pubkey,
+};
+
+// Write to the first account, then fail if the instruction data says so.
+declare_process_instruction!(WriteThenFail, 100, |invoke_context| {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let mut account = instruction_context.try_borrow_instruction_account(0)?;
+    account.set_data_from_slice(&[1, 2, 3, 4])?;
+    if instruction_context.get_instruction_data() == [1] {
+        return Err(InstructionError::Custom(7));
+    }
+    Ok(())
+});
+
+#[test]
+fn test_failure_context() {
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::default();
+    mollusk.program_cache.add_builtin(Builtin::new(
+        program_id,
+        "write_then_fail",
+        WriteThenFail::vm,
+    ));
+
+    let key = Pubkey::new_unique();
+    let accounts = [(key, Account::new(1_000_000_000, 4, &program_id))];
+    let instruction = |data: &[u8]| {
+        Instruction::new_with_bytes(program_id, data, vec![AccountMeta::new(key, false)])
+    };
+
+    let result = mollusk.process_and_validate_instruction(
+        &instruction(&[0]),
+        &accounts,
+        &[Check::success()],
+    );
+    assert_eq!(result.failure_context, None);
+
+    let result = mollusk.process_and_validate_instruction(
+        &instruction(&[1]),
+        &accounts,
+        &[Check::err(ProgramError::Custom(7))],
+    );
+    // The resulting accounts revert to the inputs, but the failure context
+    // holds the state at the failure.
+    assert_eq!(result.resulting_accounts, accounts);
+    let failure_context = result.failure_context.unwrap();
+    assert_eq!(failure_context.accounts[0].0, key);
+    assert_eq!(failure_context.accounts[0].1.data, [1, 2, 3, 4]);
+    assert_eq!(failure_context.instruction_trace.len(), 1);
+    assert_eq!(failure_context.instruction_trace[0].program_id, program_id);
+    assert_eq!(failure_context.instruction_trace[0].stack_height, 1);
+    assert_eq!(
+        failure_context.compute_units_consumed,
+        result.compute_units_consumed
+    );
+    assert!(failure_context
+        .logs
+        .iter()
+        .any(|log| log.starts_with(&format!("Program {} failed", program_id))));
+
+    // A chain keeps the context of the failing element.
+    let result =
+        mollusk.process_instruction_chain(&[instruction(&[0]), instruction(&[1])], &accounts);
+    assert!(result.program_result.is_err());
+    let failure_context = result.failure_context.unwrap();
+    assert_eq!(failure_context.accounts[0].1.data, [1, 2, 3, 4]);
+    assert_eq!(failure_context.instruction_trace.len(), 1);
+}
@@ -0,0 +1,55 @@
+use {
+    mollusk_svm::{result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::{cell::RefCell, rc::Rc},
+};
+
+#[test]
+fn test_on_failure_sink() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let failures = Rc::new(RefCell::new(Vec::new()));
+
+    let mut mollusk = Mollusk::default();
+    mollusk.config.on_failure = Some(Box::new({
+        let failures = Rc::clone(&failures);
+        move |record| failures.borrow_mut().push(record.check.clone())
+    }));
+
+    // Failures are routed to the sink, rather than panicking.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&sender).lamports(base_lamports).build(),
+            Check::account(&recipient).lamports(base_lamports).build(),
+        ],
+    );
+
+    assert_eq!(
+        *failures.borrow(),
+        vec![
+            Some("account_lamports".to_string()),
+            Some("account_lamports".to_string()),
+        ]
+    );
+}
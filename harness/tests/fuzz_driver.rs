@@ -0,0 +1,166 @@
+#![cfg(feature = "fuzz")]
+
+use {
+    mollusk_svm::{
+        fuzz::driver::{run_context, run_random_instructions, ContextStrategy, Entropy},
+        Mollusk,
+    },
+    trezoa_pubkey::Pubkey,
+};
+
+fn system_program_strategy() -> ContextStrategy {
+    let mut strategy = ContextStrategy::new(&trezoa_sdk_ids::system_program::id());
+    strategy.num_accounts = 1..=3;
+    strategy.account_data_len = 0..=16;
+    strategy.owners = vec![trezoa_sdk_ids::system_program::id(), Pubkey::new_unique()];
+    strategy.instruction_data_len = 8..=8;
+    // `CreateAccount`, `Assign` and `Transfer`.
+    strategy.discriminators = vec![
+        0u32.to_le_bytes().to_vec(),
+        1u32.to_le_bytes().to_vec(),
+        2u32.to_le_bytes().to_vec(),
+    ];
+    strategy.compute_unit_limit = 100..=10_000;
+    strategy
+}
+
+#[test]
+fn test_run_random_instructions_deterministic() {
+    let mollusk = Mollusk::default();
+    let strategy = system_program_strategy();
+
+    // Execution time varies between runs, so only the inputs are compared.
+    let contexts = |seed| {
+        run_random_instructions(&mollusk, &strategy, seed, 32)
+            .into_iter()
+            .map(|fixture| fixture.input)
+            .collect::<Vec<_>>()
+    };
+
+    let first = contexts(42);
+    assert_eq!(first.len(), 32);
+    assert_eq!(contexts(42), first);
+    assert_ne!(contexts(43), first);
+}
+
+#[test]
+fn test_generated_contexts_within_bounds() {
+    let mollusk = Mollusk::default();
+    let strategy = system_program_strategy();
+
+    for fixture in run_random_instructions(&mollusk, &strategy, 7, 64) {
+        let context = fixture.input;
+        assert_eq!(context.program_id, strategy.program_id);
+        assert!(strategy.num_accounts.contains(&context.accounts.len()));
+        assert_eq!(context.instruction_accounts.len(), context.accounts.len());
+        for (key, account) in &context.accounts {
+            assert!(strategy.account_data_len.contains(&account.data.len()));
+            assert!(strategy.lamports.contains(&account.lamports));
+            assert!(strategy.owners.contains(&account.owner));
+            assert!(context
+                .instruction_accounts
+                .iter()
+                .any(|meta| meta.pubkey == *key));
+        }
+        assert_eq!(context.instruction_data.len(), 4 + 8);
+        assert!(strategy
+            .discriminators
+            .iter()
+            .any(|discriminator| context.instruction_data.starts_with(discriminator)));
+        assert!(strategy
+            .compute_unit_limit
+            .contains(&context.compute_budget.compute_unit_limit));
+        assert_eq!(context.feature_set, mollusk.feature_set);
+    }
+}
+
+#[test]
+fn test_entropy_from_bytes() {
+    let mollusk = Mollusk::default();
+    let strategy = system_program_strategy();
+
+    let data = (0..=255u8).cycle().take(1024).collect::<Vec<_>>();
+    let context = strategy.generate(&mollusk, &mut Entropy::from_bytes(&data));
+    assert_eq!(
+        strategy.generate(&mollusk, &mut Entropy::from_bytes(&data)),
+        context
+    );
+
+    // Exhausted input still yields a valid context.
+    let context = strategy.generate(&mollusk, &mut Entropy::from_bytes(&[]));
+    assert_eq!(context.accounts.len(), *strategy.num_accounts.start());
+    assert_eq!(
+        context.compute_budget.compute_unit_limit,
+        *strategy.compute_unit_limit.start()
+    );
+    run_context(&mollusk, context);
+}
+
+#[test]
+fn test_replay_against_reference() {
+    let mollusk = Mollusk::default();
+    let strategy = system_program_strategy();
+
+    // Replaying against an identical harness reproduces every effect.
+    let mut reference = Mollusk::default();
+    for fixture in run_random_instructions(&mollusk, &strategy, 1234, 32) {
+        reference.process_and_validate_fixture(&fixture);
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn test_entropy_arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let mollusk = Mollusk::default();
+    let strategy = system_program_strategy();
+
+    // Fuzzer input drives generation just like the raw bytes do.
+    let data = (0..=255u8).cycle().take(1024).collect::<Vec<_>>();
+    let mut entropy = Entropy::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+    assert_eq!(
+        strategy.generate(&mollusk, &mut entropy),
+        strategy.generate(&mollusk, &mut Entropy::from_bytes(&data))
+    );
+
+    let mut unstructured = Unstructured::new(&data);
+    let mut entropy = Entropy::arbitrary(&mut unstructured).unwrap();
+    assert!(unstructured.is_empty());
+    run_context(&mollusk, strategy.generate(&mollusk, &mut entropy));
+}
+
+#[cfg(feature = "proptest")]
+#[test]
+fn test_context_strategy_proptest() {
+    use proptest::{
+        arbitrary::any,
+        strategy::{Strategy, ValueTree},
+        test_runner::{Config, TestRunner},
+    };
+
+    let mollusk = Mollusk::default();
+    let strategy = system_program_strategy();
+
+    let mut runner = TestRunner::new(Config {
+        cases: 16,
+        ..Config::default()
+    });
+    runner
+        .run(&strategy.with_harness(&mollusk), |context| {
+            assert!(strategy.num_accounts.contains(&context.accounts.len()));
+            assert_eq!(context.feature_set, mollusk.feature_set);
+            run_context(&mollusk, context);
+            Ok(())
+        })
+        .unwrap();
+
+    // Entropy drawn by proptest is seeded, so it's reproducible.
+    let entropy = any::<Entropy>().new_tree(&mut runner).unwrap().current();
+    let mut first = entropy.clone();
+    let mut second = entropy;
+    assert_eq!(
+        strategy.generate(&mollusk, &mut first),
+        strategy.generate(&mollusk, &mut second)
+    );
+}
@@ -0,0 +1,261 @@
+use {
+    mollusk_svm::{result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_clock::Clock,
+    trezoa_epoch_rewards::EpochRewards,
+    trezoa_epoch_schedule::EpochSchedule,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_program_error::ProgramError,
+    trezoa_pubkey::Pubkey,
+    trezoa_rent::Rent,
+    trezoa_slot_hashes::SlotHashes,
+    trezoa_stake_interface::stake_history::{StakeHistory, StakeHistoryEntry},
+    trezoa_sysvar::last_restart_slot::LastRestartSlot,
+    trezoa_sysvar_id::SysvarId,
+};
+
+// Status codes returned by `sol_get_sysvar`, surfaced as custom errors by the
+// test program.
+const OFFSET_LENGTH_EXCEEDS_SYSVAR: u32 = 1;
+const SYSVAR_NOT_FOUND: u32 = 2;
+
+fn instruction_get_sysvar(
+    program_id: &Pubkey,
+    output: &Pubkey,
+    sysvar_id: &Pubkey,
+    offset: u64,
+    length: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(48);
+    data.extend_from_slice(sysvar_id.as_ref());
+    data.extend_from_slice(&offset.to_le_bytes());
+    data.extend_from_slice(&length.to_le_bytes());
+    Instruction::new_with_bytes(*program_id, &data, vec![AccountMeta::new(*output, false)])
+}
+
+fn setup() -> (Mollusk, Pubkey) {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new(&program_id, "test_program_get_sysvar");
+    (mollusk, program_id)
+}
+
+fn check_full_read(mollusk: &Mollusk, program_id: &Pubkey, sysvar_id: &Pubkey, expected: &[u8]) {
+    let output = Pubkey::new_unique();
+    let space = expected.len();
+
+    mollusk.process_and_validate_instruction(
+        &instruction_get_sysvar(program_id, &output, sysvar_id, 0, space as u64),
+        &[(output, Account::new(1_000_000_000, space, program_id))],
+        &[
+            Check::success(),
+            Check::account(&output).data(expected).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_get_sysvar_all_supported() {
+    let (mut mollusk, program_id) = setup();
+
+    mollusk.warp_to_slot(1_000);
+    mollusk.sysvars.epoch_rewards = EpochRewards {
+        total_rewards: 42,
+        ..Default::default()
+    };
+    mollusk.sysvars.last_restart_slot = LastRestartSlot {
+        last_restart_slot: 500,
+    };
+    mollusk
+        .sysvars
+        .stake_history
+        .add(1, StakeHistoryEntry::with_effective(1_000));
+
+    let sysvars = &mollusk.sysvars;
+    let cases = [
+        (Clock::id(), bincode::serialize(&sysvars.clock).unwrap()),
+        (
+            EpochRewards::id(),
+            bincode::serialize(&sysvars.epoch_rewards).unwrap(),
+        ),
+        (
+            EpochSchedule::id(),
+            bincode::serialize(&sysvars.epoch_schedule).unwrap(),
+        ),
+        (
+            LastRestartSlot::id(),
+            bincode::serialize(&sysvars.last_restart_slot).unwrap(),
+        ),
+        (Rent::id(), bincode::serialize(&sysvars.rent).unwrap()),
+        (
+            SlotHashes::id(),
+            bincode::serialize(&sysvars.slot_hashes).unwrap(),
+        ),
+        (
+            StakeHistory::id(),
+            bincode::serialize(&sysvars.stake_history).unwrap(),
+        ),
+    ];
+
+    for (sysvar_id, expected) in cases.iter() {
+        check_full_read(&mollusk, &program_id, sysvar_id, expected);
+    }
+}
+
+#[test]
+fn test_get_sysvar_slot_hashes_paged() {
+    let (mut mollusk, program_id) = setup();
+
+    mollusk.warp_to_slot(1_000);
+
+    let slot_hashes = bincode::serialize(&mollusk.sysvars.slot_hashes).unwrap();
+
+    // Skip the `u64` length prefix, then read entries one page at a time.
+    const ENTRY_SIZE: usize = 40; // slot (8) + hash (32)
+    const PAGE_ENTRIES: usize = 16;
+    const PAGE_SIZE: usize = ENTRY_SIZE * PAGE_ENTRIES;
+
+    for page in 0..4 {
+        let offset = 8 + page * PAGE_SIZE;
+        let expected = &slot_hashes[offset..offset + PAGE_SIZE];
+
+        let output = Pubkey::new_unique();
+        mollusk.process_and_validate_instruction(
+            &instruction_get_sysvar(
+                &program_id,
+                &output,
+                &SlotHashes::id(),
+                offset as u64,
+                PAGE_SIZE as u64,
+            ),
+            &[(output, Account::new(1_000_000_000, PAGE_SIZE, &program_id))],
+            &[
+                Check::success(),
+                Check::account(&output).data(expected).build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn test_get_sysvar_provided_account_overrides() {
+    let (mollusk, program_id) = setup();
+
+    // A sysvar account provided in the accounts list takes precedence over
+    // Mollusk's configured sysvars.
+    let clock = Clock {
+        slot: 77,
+        unix_timestamp: 1_700_000_000,
+        ..Default::default()
+    };
+    let clock_data = bincode::serialize(&clock).unwrap();
+
+    let output = Pubkey::new_unique();
+    mollusk.process_and_validate_instruction(
+        &instruction_get_sysvar(
+            &program_id,
+            &output,
+            &Clock::id(),
+            0,
+            clock_data.len() as u64,
+        ),
+        &[
+            (
+                output,
+                Account::new(1_000_000_000, clock_data.len(), &program_id),
+            ),
+            (
+                Clock::id(),
+                Account {
+                    lamports: 1,
+                    data: clock_data.clone(),
+                    owner: trezoa_sdk_ids::sysvar::id(),
+                    ..Default::default()
+                },
+            ),
+        ],
+        &[
+            Check::success(),
+            Check::account(&output).data(&clock_data).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_get_sysvar_errors() {
+    let (mollusk, program_id) = setup();
+
+    let output = Pubkey::new_unique();
+    let accounts = [(output, Account::new(1_000_000_000, 64, &program_id))];
+
+    // Reading beyond the end of the sysvar.
+    let clock_len = bincode::serialized_size(&mollusk.sysvars.clock).unwrap();
+    mollusk.process_and_validate_instruction(
+        &instruction_get_sysvar(&program_id, &output, &Clock::id(), clock_len, 8),
+        &accounts,
+        &[Check::err(ProgramError::Custom(
+            OFFSET_LENGTH_EXCEEDS_SYSVAR,
+        ))],
+    );
+
+    // Sysvars not served by the sysvar cache.
+    for sysvar_id in [
+        trezoa_sdk_ids::sysvar::fees::id(),
+        trezoa_sdk_ids::sysvar::recent_blockhashes::id(),
+        trezoa_sdk_ids::sysvar::instructions::id(),
+    ] {
+        mollusk.process_and_validate_instruction(
+            &instruction_get_sysvar(&program_id, &output, &sysvar_id, 0, 8),
+            &accounts,
+            &[Check::err(ProgramError::Custom(SYSVAR_NOT_FOUND))],
+        );
+    }
+}
+
+#[test]
+fn test_keyed_sysvar_accounts() {
+    let mut mollusk = Mollusk::default();
+    mollusk.warp_to_slot(1_000);
+    mollusk.sysvars.last_restart_slot = LastRestartSlot {
+        last_restart_slot: 500,
+    };
+    let sysvars = &mollusk.sysvars;
+
+    let (clock_id, clock_account) = sysvars.keyed_account::<Clock>();
+    assert_eq!(clock_id, Clock::id());
+    assert_eq!(clock_account.owner, trezoa_sdk_ids::sysvar::id());
+    assert_eq!(
+        bincode::deserialize::<Clock>(&clock_account.data).unwrap(),
+        sysvars.clock
+    );
+
+    let (last_restart_slot_id, last_restart_slot_account) =
+        sysvars.keyed_account::<LastRestartSlot>();
+    assert_eq!(last_restart_slot_id, LastRestartSlot::id());
+    assert_eq!(
+        bincode::deserialize::<LastRestartSlot>(&last_restart_slot_account.data).unwrap(),
+        sysvars.last_restart_slot
+    );
+
+    let all = sysvars.get_all_keyed_sysvar_accounts();
+    let expected = [
+        sysvars.keyed_account::<Clock>(),
+        sysvars.keyed_account::<EpochRewards>(),
+        sysvars.keyed_account::<EpochSchedule>(),
+        sysvars.keyed_account::<LastRestartSlot>(),
+        sysvars.keyed_account::<Rent>(),
+        sysvars.keyed_account::<SlotHashes>(),
+        sysvars.keyed_account::<StakeHistory>(),
+    ];
+    assert_eq!(all, expected);
+    for (key, account) in &all {
+        assert_eq!(
+            sysvars.maybe_create_sysvar_account(key).as_ref(),
+            Some(account)
+        );
+    }
+    assert!(sysvars
+        .maybe_create_sysvar_account(&Pubkey::new_unique())
+        .is_none());
+}
@@ -1,8 +1,19 @@
 use {
-    mollusk_svm::{program::keyed_account_for_system_program, result::Check, Mollusk},
+    mollusk_svm::{
+        chain::ChainElement,
+        program::{keyed_account_for_system_program, Builtin},
+        result::Check,
+        Mollusk,
+    },
     trezoa_account::Account,
-    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_clock::Clock,
+    trezoa_compute_budget::compute_budget::ComputeBudget,
+    trezoa_instruction::{error::InstructionError, AccountMeta, Instruction},
+    trezoa_program_error::ProgramError,
+    trezoa_program_runtime::declare_process_instruction,
     trezoa_pubkey::Pubkey,
+    trezoa_system_interface::error::SystemError,
+    std::collections::HashMap,
 };
 
 fn system_account_with_lamports(lamports: u64) -> Account {
@@ -185,3 +196,292 @@ fn test_mixed() {
         ],
     );
 }
+
+#[test]
+fn test_expect_failure_and_continue() {
+    let mollusk = Mollusk::default();
+
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let carol = Pubkey::new_unique();
+
+    let starting_lamports = 500_000_000;
+    let bob_to_carol = 50_000_000;
+
+    let result = mollusk.process_and_validate_instruction_chain(
+        &[
+            (
+                // 0: Alice overdraws, which fails without changing state.
+                &trezoa_system_interface::instruction::transfer(
+                    &alice,
+                    &bob,
+                    starting_lamports + 1,
+                ),
+                &[
+                    Check::expect_failure_and_continue(),
+                    Check::err(ProgramError::Custom(
+                        SystemError::ResultWithNegativeLamports as u32,
+                    )),
+                ],
+            ),
+            (
+                // 1: Bob to Carol still runs.
+                &trezoa_system_interface::instruction::transfer(&bob, &carol, bob_to_carol),
+                &[
+                    Check::success(),
+                    Check::account(&alice).lamports(starting_lamports).build(),
+                    Check::account(&bob)
+                        .lamports(starting_lamports - bob_to_carol)
+                        .build(),
+                    Check::account(&carol)
+                        .lamports(starting_lamports + bob_to_carol)
+                        .build(),
+                ],
+            ),
+        ],
+        &[
+            (alice, system_account_with_lamports(starting_lamports)),
+            (bob, system_account_with_lamports(starting_lamports)),
+            (carol, system_account_with_lamports(starting_lamports)),
+        ],
+    );
+
+    assert!(result.program_result.is_ok());
+}
+
+#[test]
+fn test_expect_failure_and_continue_with_context() {
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+
+    let starting_lamports = 500_000_000;
+    let transfer = 50_000_000;
+
+    let context = Mollusk::default().with_context(HashMap::from([
+        (alice, system_account_with_lamports(starting_lamports)),
+        (bob, system_account_with_lamports(starting_lamports)),
+    ]));
+
+    // The chain ends in an expected failure, so the transfer before it is
+    // still stored.
+    let result = context.process_and_validate_instruction_chain(&[
+        (
+            &trezoa_system_interface::instruction::transfer(&alice, &bob, transfer),
+            &[Check::success()],
+        ),
+        (
+            &trezoa_system_interface::instruction::transfer(&alice, &bob, starting_lamports),
+            &[
+                Check::expect_failure_and_continue(),
+                Check::err(ProgramError::Custom(
+                    SystemError::ResultWithNegativeLamports as u32,
+                )),
+            ],
+        ),
+    ]);
+    assert!(result.program_result.is_err());
+
+    let store = context.account_store.borrow();
+    assert_eq!(
+        store.get(&alice).unwrap().lamports,
+        starting_lamports - transfer
+    );
+    assert_eq!(
+        store.get(&bob).unwrap().lamports,
+        starting_lamports + transfer
+    );
+}
+
+#[test]
+fn test_chain_element_compute_budget() {
+    let mollusk = Mollusk::default();
+
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+
+    let starting_lamports = 500_000_000;
+    let transfer = trezoa_system_interface::instruction::transfer(&alice, &bob, 1_000);
+
+    // The system program consumes more than 100 compute units.
+    let small_budget = ComputeBudget {
+        compute_unit_limit: 100,
+        ..mollusk.compute_budget.clone()
+    };
+
+    let result = mollusk.process_and_validate_chain_elements(
+        &[
+            // 0: The harness's compute budget.
+            ChainElement::new(&transfer, &[Check::success()]),
+            // 1: The overridden compute budget.
+            ChainElement::new(
+                &transfer,
+                &[
+                    Check::instruction_err(InstructionError::ComputationalBudgetExceeded),
+                    Check::account(&alice)
+                        .lamports(starting_lamports - 1_000)
+                        .build(),
+                ],
+            )
+            .compute_budget(small_budget),
+        ],
+        &[
+            (alice, system_account_with_lamports(starting_lamports)),
+            (bob, system_account_with_lamports(starting_lamports)),
+        ],
+    );
+
+    assert!(result.program_result.is_err());
+}
+
+#[test]
+fn test_chain_with_hook() {
+    let mollusk = Mollusk::default();
+
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let carol = Pubkey::new_unique();
+
+    let starting_lamports = 500_000_000;
+    let airdrop = 2_000_000_000;
+
+    let accounts = [
+        (alice, system_account_with_lamports(starting_lamports)),
+        (bob, system_account_with_lamports(starting_lamports)),
+        (carol, system_account_with_lamports(starting_lamports)),
+    ];
+
+    // Bob can only afford the second transfer if he receives the airdrop
+    // between the two instructions.
+    let instructions = [
+        trezoa_system_interface::instruction::transfer(&alice, &bob, starting_lamports),
+        trezoa_system_interface::instruction::transfer(&bob, &carol, airdrop),
+    ];
+
+    let mut hook_calls = vec![];
+    let result =
+        mollusk.process_instruction_chain_with_hook(&instructions, &accounts, |index, accounts| {
+            hook_calls.push(index);
+            let (_, bob_account) = accounts.iter_mut().find(|(key, _)| key == &bob).unwrap();
+            bob_account.lamports += airdrop;
+        });
+
+    // The hook only runs between elements.
+    assert_eq!(hook_calls, vec![0]);
+
+    result.run_checks(
+        &[
+            Check::success(),
+            Check::account(&alice).lamports(0).build(),
+            Check::account(&bob).lamports(2 * starting_lamports).build(),
+            Check::account(&carol)
+                .lamports(starting_lamports + airdrop)
+                .build(),
+        ],
+        &mollusk.config,
+        &mollusk,
+    );
+
+    // Without the hook, Bob can't afford it.
+    let result = mollusk.process_instruction_chain(&instructions, &accounts);
+    assert!(result.program_result.is_err());
+}
+
+// Write the current slot to the first account.
+declare_process_instruction!(RecordSlot, 100, |invoke_context| {
+    let slot = invoke_context.get_sysvar_cache().get_clock()?.slot;
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let mut account = instruction_context.try_borrow_instruction_account(0)?;
+    account.set_data_from_slice(&slot.to_le_bytes())
+});
+
+#[test]
+fn test_chain_slot_advance() {
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::default();
+    mollusk
+        .program_cache
+        .add_builtin(Builtin::new(program_id, "record_slot", RecordSlot::vm));
+    mollusk.warp_to_slot(100);
+
+    let key = Pubkey::new_unique();
+    let accounts = [(key, Account::new(1_000_000_000, 8, &program_id))];
+    let instruction =
+        Instruction::new_with_bytes(program_id, &[], vec![AccountMeta::new(key, false)]);
+    let slot_check = |slot: u64| [Check::account(&key).data(&slot.to_le_bytes()).build()];
+
+    // Without advancing, the whole chain runs in the current slot.
+    let result =
+        mollusk.process_instruction_chain(&[instruction.clone(), instruction.clone()], &accounts);
+    assert_eq!(result.resulting_accounts[0].1.data, 100u64.to_le_bytes());
+
+    mollusk.slot_advance_per_instruction = Some(10);
+    let (first, second, third) = (slot_check(100), slot_check(110), slot_check(120));
+    mollusk.process_and_validate_instruction_chain(
+        &[
+            (&instruction, &first),
+            (&instruction, &second),
+            (&instruction, &third),
+        ],
+        &accounts,
+    );
+
+    let result = mollusk.process_instruction_chain(&[instruction.clone(), instruction], &accounts);
+    assert_eq!(result.resulting_accounts[0].1.data, 110u64.to_le_bytes());
+
+    // A clock account passed to the chain is moved forward too, rather than
+    // shadowing the advanced clock.
+    let (clock_key, clock_account) = mollusk.sysvars.keyed_account_for_clock_sysvar();
+    let instruction = Instruction::new_with_bytes(
+        program_id,
+        &[],
+        vec![
+            AccountMeta::new(key, false),
+            AccountMeta::new_readonly(clock_key, false),
+        ],
+    );
+    let result = mollusk.process_and_validate_instruction_chain(
+        &[(&instruction, &first), (&instruction, &second)],
+        &[accounts[0].clone(), (clock_key, clock_account)],
+    );
+    let clock: Clock = bincode::deserialize(&result.get_account(&clock_key).unwrap().data).unwrap();
+    assert_eq!(clock.slot, 110);
+
+    // The harness's own sysvars aren't moved.
+    assert_eq!(mollusk.sysvars.clock.slot, 100);
+}
+
+#[test]
+fn test_chain_element_compute_budget_limits() {
+    let mollusk = Mollusk::default();
+
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+
+    let starting_lamports = 500_000_000;
+    let transfer = trezoa_system_interface::instruction::transfer(&alice, &bob, 1_000);
+
+    // The overridden budget's instruction trace limit applies too, not just
+    // its compute unit limit.
+    let no_trace_budget = ComputeBudget {
+        max_instruction_trace_length: 0,
+        ..mollusk.compute_budget.clone()
+    };
+
+    mollusk.process_and_validate_chain_elements(
+        &[
+            ChainElement::new(&transfer, &[Check::success()]),
+            ChainElement::new(
+                &transfer,
+                &[Check::instruction_err(
+                    InstructionError::MaxInstructionTraceLengthExceeded,
+                )],
+            )
+            .compute_budget(no_trace_budget),
+        ],
+        &[
+            (alice, system_account_with_lamports(starting_lamports)),
+            (bob, system_account_with_lamports(starting_lamports)),
+        ],
+    );
+}
@@ -0,0 +1,144 @@
+use {
+    mollusk_svm::{
+        interface::{AccountInterface, InstructionInterface, InterfaceError, ProgramInterface},
+        result::Check,
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::AccountMeta,
+    trezoa_pubkey::Pubkey,
+};
+
+fn system_program_interface() -> ProgramInterface {
+    ProgramInterface::new(vec![InstructionInterface::new(
+        "transfer",
+        &[2, 0, 0, 0],
+        vec![
+            AccountInterface::new("from").signer().writable(),
+            AccountInterface::new("to").writable(),
+        ],
+    )])
+}
+
+#[test]
+fn test_validate_interface() {
+    let interface = system_program_interface();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let transfer = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    assert_eq!(interface.validate(&transfer), Ok(()));
+
+    // Extra accounts are left unchecked.
+    let mut instruction = transfer.clone();
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+    assert_eq!(interface.validate(&instruction), Ok(()));
+
+    let mut instruction = transfer.clone();
+    instruction.accounts[0].is_signer = false;
+    assert_eq!(
+        interface.validate(&instruction),
+        Err(InterfaceError::NotSigner {
+            instruction: "transfer".to_string(),
+            index: 0,
+            account: "from".to_string(),
+        })
+    );
+
+    let mut instruction = transfer.clone();
+    instruction.accounts[1].is_writable = false;
+    assert_eq!(
+        interface.validate(&instruction).unwrap_err().to_string(),
+        "transfer: account 1 (to) must be writable",
+    );
+
+    let instruction = trezoa_system_interface::instruction::assign(&sender, &Pubkey::new_unique());
+    assert!(matches!(
+        interface.validate(&instruction),
+        Err(InterfaceError::UnknownDiscriminator { .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "transfer: expected 2 accounts, got 1: missing to")]
+fn test_interface_validated_before_execution() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let accounts = [
+        (sender, Account::new(base_lamports, 0, &system_program)),
+        (recipient, Account::new(base_lamports, 0, &system_program)),
+    ];
+
+    let mut mollusk = Mollusk::default();
+    mollusk
+        .interfaces
+        .insert(system_program, system_program_interface());
+
+    let mut instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&recipient)
+                .lamports(base_lamports + transfer_amount)
+                .build(),
+        ],
+    );
+
+    // Without the recipient, the System program would fail with an opaque
+    // `NotEnoughAccountKeys`.
+    instruction.accounts.pop();
+    mollusk.process_instruction(&instruction, &accounts);
+}
+
+#[cfg(feature = "idl")]
+#[test]
+fn test_interface_from_anchor_idl() {
+    let idl = r#"{
+        "address": "11111111111111111111111111111111",
+        "metadata": { "name": "example", "version": "0.1.0", "spec": "0.1.0" },
+        "instructions": [
+            {
+                "name": "initialize",
+                "discriminator": [175, 175, 109, 31, 13, 152, 155, 237],
+                "accounts": [
+                    { "name": "payer", "writable": true, "signer": true },
+                    {
+                        "name": "common",
+                        "accounts": [{ "name": "state", "writable": true }]
+                    },
+                    {
+                        "name": "system_program",
+                        "address": "11111111111111111111111111111111"
+                    },
+                    { "name": "authority", "optional": true }
+                ],
+                "args": []
+            }
+        ]
+    }"#;
+
+    assert_eq!(
+        ProgramInterface::from_anchor_idl(idl).unwrap(),
+        ProgramInterface::new(vec![InstructionInterface::new(
+            "initialize",
+            &[175, 175, 109, 31, 13, 152, 155, 237],
+            vec![
+                AccountInterface::new("payer").signer().writable(),
+                AccountInterface::new("state").writable(),
+                AccountInterface::new("system_program")
+                    .address(trezoa_sdk_ids::system_program::id()),
+                AccountInterface::new("authority").optional(),
+            ],
+        )])
+    );
+}
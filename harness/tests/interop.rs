@@ -0,0 +1,55 @@
+// The interop conversions are field-by-field, so they can be exercised with
+// Mollusk's own SDK version standing in for another one.
+use {
+    mollusk_svm::{result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+};
+
+mollusk_svm::mollusk_interop! {
+    Pubkey = Pubkey,
+    Account = Account,
+    AccountMeta = AccountMeta,
+    Instruction = Instruction,
+}
+
+#[test]
+fn test_interop_conversions() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let converted: Instruction = instruction.to_mollusk();
+    assert_eq!(converted, instruction);
+
+    let result = Mollusk::default().process_and_validate_instruction(
+        &instruction.to_mollusk(),
+        &accounts.to_mollusk(),
+        &[
+            Check::success(),
+            Check::account(&recipient)
+                .lamports(base_lamports + transfer_amount)
+                .build(),
+        ],
+    );
+
+    let resulting_accounts: Vec<(Pubkey, Account)> =
+        FromMollusk::from_mollusk(&result.resulting_accounts);
+    assert_eq!(resulting_accounts, result.resulting_accounts);
+}
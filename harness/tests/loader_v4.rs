@@ -0,0 +1,138 @@
+#![cfg(feature = "all-builtins")]
+
+use {
+    mollusk_svm::{
+        program::{get_loader_v4_state, loader_keys, LoaderV4AccountBuilder, LoaderV4Status},
+        result::Check,
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::Instruction,
+    trezoa_loader_v4_interface::instruction::retract,
+    trezoa_pubkey::Pubkey,
+};
+
+#[test]
+fn test_loader_v4_account_builder() {
+    let elf = [7; 16];
+    let authority = Pubkey::new_unique();
+
+    for status in [
+        LoaderV4Status::Retracted,
+        LoaderV4Status::Deployed,
+        LoaderV4Status::Finalized,
+    ] {
+        let account = LoaderV4AccountBuilder::new(&elf)
+            .authority(&authority)
+            .status(status)
+            .slot(42)
+            .build();
+        assert_eq!(account.owner, loader_keys::LOADER_V4);
+        assert_eq!(account.executable, status != LoaderV4Status::Retracted);
+        assert!(account.data.ends_with(&elf));
+
+        let state = get_loader_v4_state(&account).unwrap();
+        assert_eq!(state.slot, 42);
+        assert_eq!(state.authority_address_or_next_version, authority);
+        assert_eq!(state.status, status);
+    }
+
+    assert!(get_loader_v4_state(&Account::new(1, 64, &Pubkey::new_unique())).is_none());
+}
+
+#[test]
+fn test_loader_v4_deploy_and_redeploy() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let mut mollusk = Mollusk::default();
+    let elf = mollusk_svm::file::load_program_elf("test_program_primary");
+    let other_elf = mollusk_svm::file::load_program_elf("test_program_cpi_target");
+
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let authority_account = Account::new(1_000_000, 0, &trezoa_sdk_ids::system_program::id());
+    let program_account = LoaderV4AccountBuilder::new(&[])
+        .authority(&authority)
+        .status(LoaderV4Status::Retracted)
+        .build();
+
+    // No-op for the primary program, while the CPI target requires an
+    // account.
+    let noop = Instruction::new_with_bytes(program_id, &[0], vec![]);
+    let state = |accounts: &[(Pubkey, Account)]| get_loader_v4_state(&accounts[0].1).unwrap();
+
+    // Deploy a new program.
+    mollusk.warp_to_slot(10);
+    let result = mollusk.deploy_loader_v4_program(
+        &program_id,
+        &authority,
+        &elf,
+        &[
+            (program_id, program_account),
+            (authority, authority_account),
+        ],
+    );
+    assert!(result.program_result.is_ok());
+    let accounts = result.resulting_accounts;
+    assert_eq!(state(&accounts).status, LoaderV4Status::Deployed);
+    assert_eq!(state(&accounts).slot, 10);
+    assert!(accounts[0].1.data.ends_with(&elf));
+    assert_eq!(
+        mollusk.program_cache.get_program_loader_key(&program_id),
+        Some(loader_keys::LOADER_V4)
+    );
+    mollusk.process_and_validate_instruction(&noop, &accounts[..1], &[Check::success()]);
+
+    // Retracting is rejected until the deployment cooldown has passed.
+    let result = mollusk.process_instruction(&retract(&program_id, &authority), &accounts);
+    assert!(result.program_result.is_err());
+
+    // Redeploying moves past the cooldown, retracts, resizes and writes the
+    // program.
+    let result = mollusk.deploy_loader_v4_program(&program_id, &authority, &other_elf, &accounts);
+    assert!(result.program_result.is_ok());
+    assert!(mollusk.sysvars.clock.slot > 10);
+    let accounts = result.resulting_accounts;
+    assert_eq!(state(&accounts).status, LoaderV4Status::Deployed);
+    assert_eq!(state(&accounts).slot, mollusk.sysvars.clock.slot);
+    assert!(accounts[0].1.data.ends_with(&other_elf));
+    assert!(mollusk
+        .process_instruction(&noop, &accounts[..1])
+        .program_result
+        .is_err());
+
+    // And back again, shrinking the program.
+    let result = mollusk.deploy_loader_v4_program(&program_id, &authority, &elf, &accounts);
+    assert!(result.program_result.is_ok());
+    let accounts = result.resulting_accounts;
+    mollusk.process_and_validate_instruction(&noop, &accounts[..1], &[Check::success()]);
+}
+
+#[test]
+fn test_loader_v4_deploy_finalized() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let mut mollusk = Mollusk::default();
+    let elf = mollusk_svm::file::load_program_elf("test_program_primary");
+
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let accounts = [
+        (
+            program_id,
+            LoaderV4AccountBuilder::new(&elf)
+                .authority(&authority)
+                .status(LoaderV4Status::Finalized)
+                .slot(1)
+                .build(),
+        ),
+        (
+            authority,
+            Account::new(1_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    // Finalized programs can't be redeployed.
+    let result = mollusk.deploy_loader_v4_program(&program_id, &authority, &elf, &accounts);
+    assert!(result.program_result.is_err());
+}
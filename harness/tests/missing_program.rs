@@ -0,0 +1,131 @@
+use {
+    mollusk_svm::{fees::FeeModel, result::Check, MissingProgramBehavior, Mollusk},
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_instruction_error::InstructionError,
+    trezoa_program_error::ProgramError,
+    trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
+};
+
+fn setup() -> (Instruction, [(Pubkey, Account); 1]) {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let account = Account::new(1_000_000, 8, &program_id);
+    let instruction =
+        Instruction::new_with_bytes(program_id, &[1, 2, 3], vec![AccountMeta::new(key, false)]);
+    (instruction, [(key, account)])
+}
+
+#[test]
+#[should_panic(expected = "Program targeted by the instruction is missing from the cache")]
+fn test_missing_program_panics() {
+    let mollusk = Mollusk::default();
+    let (instruction, accounts) = setup();
+    mollusk.process_instruction(&instruction, &accounts);
+}
+
+#[test]
+fn test_missing_program_stub() {
+    let mut mollusk = Mollusk::default();
+    mollusk.missing_program_behavior = MissingProgramBehavior::Stub;
+
+    let (instruction, accounts) = setup();
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::err(ProgramError::UnsupportedProgramId),
+            Check::account(&accounts[0].0).lamports(1_000_000).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_missing_program_error() {
+    let mut mollusk = Mollusk::default();
+    mollusk.missing_program_behavior = MissingProgramBehavior::Error;
+
+    let (instruction, accounts) = setup();
+    let result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::err(ProgramError::UnsupportedProgramId),
+            Check::compute_units(0),
+        ],
+    );
+    assert_eq!(result.failing_program_id, Some(instruction.program_id));
+    assert_eq!(result.resulting_accounts, accounts.to_vec());
+}
+
+fn with_transfer(
+    accounts: &[(Pubkey, Account)],
+) -> (Instruction, Pubkey, Pubkey, Vec<(Pubkey, Account)>) {
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let transfer = trezoa_system_interface::instruction::transfer(&payer, &recipient, 42);
+    let mut accounts = accounts.to_vec();
+    accounts.push((
+        payer,
+        Account::new(1_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+    ));
+    accounts.push((
+        recipient,
+        Account::new(0, 0, &trezoa_sdk_ids::system_program::id()),
+    ));
+    (transfer, payer, recipient, accounts)
+}
+
+#[test]
+fn test_missing_program_error_chain() {
+    let mut mollusk = Mollusk::default();
+    mollusk.missing_program_behavior = MissingProgramBehavior::Error;
+
+    let (instruction, accounts) = setup();
+    let (transfer, payer, recipient, accounts) = with_transfer(&accounts);
+
+    // The transfer before the instruction targeting the missing program is
+    // still executed.
+    let result = mollusk.process_instruction_chain(&[transfer, instruction.clone()], &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(InstructionError::UnsupportedProgramId)
+    );
+    assert_eq!(result.failing_program_id, Some(instruction.program_id));
+    assert_eq!(result.resulting_accounts[1].0, payer);
+    assert_eq!(result.resulting_accounts[1].1.lamports, 1_000_000 - 42);
+    assert_eq!(result.resulting_accounts[2].0, recipient);
+    assert_eq!(result.resulting_accounts[2].1.lamports, 42);
+}
+
+#[test]
+fn test_missing_program_error_transaction() {
+    let mut mollusk = Mollusk::default();
+    mollusk.missing_program_behavior = MissingProgramBehavior::Error;
+
+    let (instruction, accounts) = setup();
+    let (transfer, payer, _, accounts) = with_transfer(&accounts);
+
+    // Like the runtime, the transaction fails to load, so nothing is
+    // executed.
+    let instructions = [transfer, instruction];
+    let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::ProgramAccountNotFound)
+    );
+    assert_eq!(result.compute_units_consumed, 0);
+    assert_eq!(result.resulting_accounts, accounts);
+
+    // But the fee is still charged.
+    mollusk.fee_model = Some(FeeModel::default().fee_payer(payer));
+    let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::ProgramAccountNotFound)
+    );
+    assert_eq!(result.resulting_accounts[1].0, payer);
+    assert_eq!(result.resulting_accounts[1].1.lamports, 1_000_000 - 5_000);
+    assert_eq!(result.resulting_accounts[2..], accounts[2..]);
+}
@@ -0,0 +1,237 @@
+use {
+    mollusk_svm::{
+        fees::FeeModel,
+        nonce::{create_nonce_account, nonce_data},
+        result::{types::TransactionProgramResult, Check},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_hash::Hash,
+    trezoa_nonce::state::DurableNonce,
+    trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
+};
+
+struct Setup {
+    mollusk: Mollusk,
+    nonce: Pubkey,
+    authority: Pubkey,
+    sender: Pubkey,
+    recipient: Pubkey,
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+fn setup(nonce_blockhash: &Hash) -> Setup {
+    let mut mollusk = Mollusk::default();
+    mollusk.durable_nonce = true;
+    mollusk.blockhash = Hash::new_from_array([7; 32]);
+    // The transaction is signed over the stored nonce.
+    mollusk.recent_blockhash = *DurableNonce::from_blockhash(nonce_blockhash).as_hash();
+
+    let nonce = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    // Advancing a nonce requires a non-empty `RecentBlockhashes` sysvar,
+    // which isn't served by default.
+    let recent_blockhashes = {
+        let mut data = 1u64.to_le_bytes().to_vec();
+        data.extend_from_slice(mollusk.blockhash.as_ref());
+        data.extend_from_slice(&mollusk.lamports_per_signature.to_le_bytes());
+        let mut account = Account::new(1, 0, &trezoa_sdk_ids::sysvar::id());
+        account.data = data;
+        account
+    };
+
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let accounts = vec![
+        (
+            nonce,
+            create_nonce_account(&mollusk, &authority, nonce_blockhash),
+        ),
+        (authority, Account::new(1_000_000_000, 0, &system_program)),
+        (sender, Account::new(1_000_000_000, 0, &system_program)),
+        (recipient, Account::new(1_000_000_000, 0, &system_program)),
+        (
+            trezoa_sdk_ids::sysvar::recent_blockhashes::id(),
+            recent_blockhashes,
+        ),
+    ];
+
+    Setup {
+        mollusk,
+        nonce,
+        authority,
+        sender,
+        recipient,
+        accounts,
+    }
+}
+
+fn stored_durable_nonce(accounts: &[(Pubkey, Account)], nonce: &Pubkey) -> DurableNonce {
+    let (_, account) = accounts.iter().find(|(key, _)| key == nonce).unwrap();
+    nonce_data(account).unwrap().durable_nonce
+}
+
+#[test]
+fn test_durable_nonce_advanced() {
+    let previous_blockhash = Hash::new_from_array([1; 32]);
+    let Setup {
+        mollusk,
+        nonce,
+        authority,
+        sender,
+        recipient,
+        accounts,
+    } = setup(&previous_blockhash);
+
+    let instructions = [
+        trezoa_system_interface::instruction::advance_nonce_account(&nonce, &authority),
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, 42),
+    ];
+
+    let result = mollusk.process_and_validate_transaction_instructions(
+        &instructions,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&recipient).lamports(1_000_000_042).build(),
+        ],
+    );
+    assert_eq!(
+        stored_durable_nonce(&result.resulting_accounts, &nonce),
+        DurableNonce::from_blockhash(&mollusk.blockhash)
+    );
+
+    // Like a validator, the nonce is advanced even if execution fails.
+    let instructions = [
+        trezoa_system_interface::instruction::advance_nonce_account(&nonce, &authority),
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, 2_000_000_000),
+    ];
+
+    let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+    assert!(result.program_result.is_err());
+    assert_eq!(
+        stored_durable_nonce(&result.resulting_accounts, &nonce),
+        DurableNonce::from_blockhash(&mollusk.blockhash)
+    );
+    assert_eq!(
+        result.get_account(&recipient).unwrap().lamports,
+        1_000_000_000
+    );
+
+    // Without durable nonce handling, the failure discards the advance.
+    let mut mollusk = mollusk;
+    mollusk.durable_nonce = false;
+    let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+    assert!(result.program_result.is_err());
+    assert_eq!(
+        stored_durable_nonce(&result.resulting_accounts, &nonce),
+        DurableNonce::from_blockhash(&previous_blockhash)
+    );
+}
+
+#[test]
+fn test_durable_nonce_rejected() {
+    // A nonce stored under the current blockhash can't be advanced.
+    let Setup {
+        mollusk,
+        nonce,
+        authority,
+        sender,
+        recipient,
+        accounts,
+    } = setup(&Hash::new_from_array([7; 32]));
+
+    let instructions = [
+        trezoa_system_interface::instruction::advance_nonce_account(&nonce, &authority),
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, 42),
+    ];
+    let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+    assert_eq!(result.raw_result, Err(TransactionError::BlockhashNotFound));
+    assert_eq!(
+        result.program_result,
+        TransactionProgramResult::TransactionError(TransactionError::BlockhashNotFound)
+    );
+    assert_eq!(result.resulting_accounts, accounts);
+
+    // Nor can a nonce the transaction isn't signed over.
+    let Setup {
+        mut mollusk,
+        nonce,
+        authority,
+        sender,
+        recipient,
+        accounts,
+    } = setup(&Hash::new_from_array([1; 32]));
+
+    mollusk.recent_blockhash = mollusk.blockhash;
+    let instructions = [
+        trezoa_system_interface::instruction::advance_nonce_account(&nonce, &authority),
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, 42),
+    ];
+    mollusk.process_and_validate_transaction_instructions(
+        &instructions,
+        &accounts,
+        &[Check::transaction_err(TransactionError::BlockhashNotFound)],
+    );
+
+    // Neither can a nonce whose authority didn't sign.
+    let Setup {
+        mollusk,
+        nonce,
+        sender,
+        recipient,
+        accounts,
+        ..
+    } = setup(&Hash::new_from_array([1; 32]));
+
+    let mut advance =
+        trezoa_system_interface::instruction::advance_nonce_account(&nonce, &Pubkey::new_unique());
+    advance.accounts[2].is_signer = false;
+    let instructions = [
+        advance,
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, 42),
+    ];
+    mollusk.process_and_validate_transaction_instructions(
+        &instructions,
+        &accounts,
+        &[Check::transaction_err(TransactionError::BlockhashNotFound)],
+    );
+}
+
+#[test]
+fn test_durable_nonce_fee_model_lamports_per_signature() {
+    let previous_blockhash = Hash::new_from_array([1; 32]);
+    let Setup {
+        mut mollusk,
+        nonce,
+        authority,
+        sender,
+        recipient,
+        accounts,
+    } = setup(&previous_blockhash);
+    mollusk.fee_model = Some(FeeModel::default().lamports_per_signature(10_000));
+
+    // The nonce is advanced with the fee model's lamports per signature, on
+    // success and on failure alike.
+    for lamports in [42, 2_000_000_000] {
+        let instructions = [
+            trezoa_system_interface::instruction::advance_nonce_account(&nonce, &authority),
+            trezoa_system_interface::instruction::transfer(&sender, &recipient, lamports),
+        ];
+        let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+        let (_, account) = result
+            .resulting_accounts
+            .iter()
+            .find(|(key, _)| *key == nonce)
+            .unwrap();
+        let data = nonce_data(account).unwrap();
+        assert_eq!(
+            data.durable_nonce,
+            DurableNonce::from_blockhash(&mollusk.blockhash)
+        );
+        assert_eq!(data.get_lamports_per_signature(), 10_000);
+    }
+}
@@ -2,6 +2,7 @@ use {
     mollusk_svm::{result::Check, Mollusk},
     rand0_7::thread_rng,
     trezoa_account::{Account, WritableAccount},
+    trezoa_precompile_error::PrecompileError,
     trezoa_pubkey::Pubkey,
 };
 
@@ -114,3 +115,44 @@ fn test_secp256r1() {
         &[Check::success()],
     );
 }
+
+#[test]
+fn test_precompile_errors() {
+    use ed25519_dalek::Signer;
+    let mollusk = Mollusk::default();
+    let keypair = ed25519_dalek::Keypair::generate(&mut thread_rng());
+
+    let msg = b"hello";
+    let signature = keypair.sign(msg).to_bytes();
+    let pubkey_bytes = keypair.public.to_bytes();
+
+    let mut instr = trezoa_ed25519_program::new_ed25519_instruction_with_signature(
+        msg,
+        <&[u8; trezoa_ed25519_program::SIGNATURE_SERIALIZED_SIZE]>::try_from(&signature[..])
+            .unwrap(),
+        <&[u8; trezoa_ed25519_program::PUBKEY_SERIALIZED_SIZE]>::try_from(&pubkey_bytes[..])
+            .unwrap(),
+    );
+    let accounts = [
+        (Pubkey::new_unique(), Account::default()),
+        (trezoa_sdk_ids::ed25519_program::id(), precompile_account()),
+    ];
+
+    // The message is at the end of the instruction data.
+    *instr.data.last_mut().unwrap() ^= 1;
+    let result = mollusk.process_and_validate_instruction(
+        &instr,
+        &accounts,
+        &[Check::precompile_err(PrecompileError::InvalidSignature)],
+    );
+    assert!(result.program_result.is_err());
+
+    instr.data.truncate(1);
+    mollusk.process_and_validate_instruction(
+        &instr,
+        &accounts,
+        &[Check::precompile_err(
+            PrecompileError::InvalidInstructionDataSize,
+        )],
+    );
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "precompile-fixtures")]
+
+use mollusk_svm::{
+    precompile::{self, Corruption, PrecompileFixture},
+    testgen::KeyGen,
+    Mollusk,
+};
+
+fn run(mollusk: &Mollusk, fixture: &PrecompileFixture) {
+    let result = mollusk.process_and_validate_instruction(
+        &fixture.instruction,
+        &[fixture.keyed_account()],
+        &[fixture.check()],
+    );
+    assert_eq!(
+        result.program_result.is_err(),
+        fixture.expected_error.is_some()
+    );
+}
+
+#[test]
+fn test_ed25519_fixtures() {
+    let mollusk = Mollusk::default();
+    let mut keys = KeyGen::seeded(42);
+
+    let fixture = precompile::ed25519(&mut keys, b"hello");
+    assert_eq!(fixture.program_id, trezoa_sdk_ids::ed25519_program::id());
+    assert_eq!(fixture.expected_error, None);
+    run(&mollusk, &fixture);
+
+    for corruption in Corruption::ALL {
+        let fixture = precompile::ed25519_with_corruption(&mut keys, b"hello", corruption);
+        assert_eq!(fixture.expected_error, Some(corruption.expected_error()));
+        run(&mollusk, &fixture);
+    }
+}
+
+#[test]
+fn test_secp256r1_fixtures() {
+    let mollusk = Mollusk::default();
+    let mut keys = KeyGen::seeded(42);
+
+    // Enough signatures for both high and low S values to come up.
+    for _ in 0..16 {
+        let fixture = precompile::secp256r1(&mut keys, b"hello");
+        assert_eq!(fixture.program_id, trezoa_sdk_ids::secp256r1_program::id());
+        run(&mollusk, &fixture);
+    }
+
+    for corruption in Corruption::ALL {
+        run(
+            &mollusk,
+            &precompile::secp256r1_with_corruption(&mut keys, b"hello", corruption),
+        );
+    }
+}
+
+#[test]
+fn test_fixtures_reproducible() {
+    for generate in [precompile::ed25519, precompile::secp256r1] {
+        let fixture = generate(&mut KeyGen::seeded(7), b"hello");
+        assert_eq!(fixture, generate(&mut KeyGen::seeded(7), b"hello"));
+        assert_ne!(fixture, generate(&mut KeyGen::seeded(8), b"hello"));
+    }
+}
@@ -54,6 +54,56 @@ fn test_process_mollusk() {
     mollusk.process_and_validate_fixture(&fixture);
 }
 
+#[cfg(feature = "fuzz")]
+#[test]
+fn test_process_fixture_batch() {
+    let mut mollusk = Mollusk::default();
+    mollusk.config.panic = true;
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let accounts = vec![
+        (
+            sender,
+            Account::new(BASE_LAMPORTS, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(BASE_LAMPORTS, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let fixture = |mollusk: &Mollusk, lamports: u64| {
+        let instruction =
+            trezoa_system_interface::instruction::transfer(&sender, &recipient, lamports);
+        let result = mollusk.process_instruction(&instruction, &accounts);
+        mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+            mollusk,
+            &instruction,
+            &accounts,
+            &result,
+        )
+    };
+
+    // Two contexts, interleaved, with one fixture whose effects don't match.
+    let first = fixture(&mollusk, 1_000);
+    let too_much = fixture(&mollusk, BASE_LAMPORTS + 1);
+    mollusk.compute_budget.compute_unit_limit /= 2;
+    let second = fixture(&mollusk, 2_000);
+    let mut mismatched = fixture(&mollusk, 3_000);
+    mismatched.output.compute_units_consumed += 1;
+
+    let report = mollusk.process_fixture_batch([first, second, too_much, mismatched]);
+    assert_eq!(report.total, 4);
+    assert_eq!(report.passed, 3);
+    assert_eq!(report.failed, vec![3]);
+    assert_eq!(report.contexts, 2);
+    assert!(!report.all_passed());
+
+    // The harness's config is restored.
+    assert!(mollusk.config.panic);
+}
+
 #[cfg(feature = "fuzz-fd")]
 #[test]
 fn test_process_firedancer() {
@@ -103,3 +153,219 @@ fn test_process_firedancer() {
 
     mollusk.process_and_validate_firedancer_fixture(&fixture);
 }
+
+#[cfg(feature = "fuzz-fd")]
+#[test]
+fn test_process_firedancer_txn() {
+    use mollusk_svm_fuzz_fixture_firedancer::{
+        txn::{CompiledInstruction, MessageHeader, TxnContext, TxnEffects},
+        TxnFixture,
+    };
+
+    const FEE: u64 = 5_000;
+
+    let mut mollusk = Mollusk::default();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let system_program = trezoa_sdk_ids::system_program::id();
+
+    let accounts = vec![
+        (sender, Account::new(BASE_LAMPORTS, 0, &system_program)),
+        (recipient, Account::new(BASE_LAMPORTS, 0, &system_program)),
+    ];
+
+    let instructions = [
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, 42_000),
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, 58_000),
+    ];
+    let result = mollusk.process_transaction_instructions(&instructions, &accounts);
+
+    // Account keys: the fee payer, then the recipient, then the program.
+    let input = TxnContext {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        },
+        account_keys: vec![sender, recipient, system_program],
+        accounts: accounts
+            .iter()
+            .map(|(key, account)| (*key, account.clone(), None))
+            .collect(),
+        instructions: instructions
+            .iter()
+            .map(|ix| CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1],
+                data: ix.data.clone(),
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    // The fee payer is charged a fee, which Mollusk refunds before comparing.
+    let resulting_accounts = result
+        .resulting_accounts
+        .iter()
+        .map(|(key, account)| {
+            let mut account = account.clone();
+            if *key == sender {
+                account.lamports -= FEE;
+            }
+            (*key, account, None)
+        })
+        .collect();
+
+    let output = TxnEffects {
+        executed: true,
+        is_ok: true,
+        resulting_accounts,
+        executed_units: result.compute_units_consumed,
+        transaction_fee: FEE,
+        ..Default::default()
+    };
+
+    let fixture = TxnFixture {
+        metadata: None,
+        input,
+        output,
+    };
+
+    let processed = mollusk.process_and_validate_firedancer_txn_fixture(&fixture);
+    assert_eq!(
+        processed.resulting_accounts[0].1.lamports,
+        BASE_LAMPORTS - 100_000,
+    );
+}
+
+#[cfg(all(feature = "fuzz-fd", feature = "all-builtins"))]
+#[test]
+fn test_process_firedancer_core_bpf_migrated_builtins() {
+    use {
+        mollusk_svm::{
+            program::loader_keys, result::Check, stake::create_delegated_stake_account,
+            MissingProgramBehavior,
+        },
+        trezoa_program_error::ProgramError,
+    };
+
+    let fixture_program_account = |fixture: &mollusk_svm_fuzz_fixture_firedancer::Fixture| {
+        fixture
+            .input
+            .accounts
+            .iter()
+            .find(|(key, _, _)| *key == fixture.input.program_id)
+            .map(|(_, account, _)| account.clone())
+            .unwrap()
+    };
+
+    let mut mollusk = Mollusk::default();
+    let stake_migration = trezoa_feature_set::migrate_stake_program_to_core_bpf::id();
+    let config_migration = trezoa_feature_set::migrate_config_program_to_core_bpf::id();
+    assert!(mollusk.feature_set.is_active(&stake_migration));
+
+    // The harness executes the stake builtin, even though its migration is
+    // active, so the fixture stubs a builtin and records the migration as
+    // inactive.
+    let authority = Pubkey::new_unique();
+    let stake_address = Pubkey::new_unique();
+    let accounts = vec![
+        (
+            stake_address,
+            create_delegated_stake_account(
+                &authority,
+                &Pubkey::new_unique(),
+                BASE_LAMPORTS,
+                &mollusk.sysvars.rent,
+                mollusk.sysvars.clock.epoch,
+            ),
+        ),
+        (authority, Account::default()),
+    ];
+    let instruction = trezoa_instruction::Instruction::new_with_bytes(
+        trezoa_sdk_ids::stake::id(),
+        &5u32.to_le_bytes(), // `StakeInstruction::Deactivate`
+        vec![
+            trezoa_instruction::AccountMeta::new(stake_address, false),
+            trezoa_instruction::AccountMeta::new_readonly(
+                trezoa_sdk_ids::sysvar::clock::id(),
+                false,
+            ),
+            trezoa_instruction::AccountMeta::new_readonly(authority, true),
+        ],
+    );
+    let result = mollusk.process_instruction(&instruction, &accounts);
+    assert!(result.program_result.is_ok());
+
+    let fixture = mollusk_svm::fuzz::firedancer::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &accounts,
+        &result,
+    );
+    assert_eq!(
+        fixture_program_account(&fixture).owner,
+        loader_keys::NATIVE_LOADER,
+    );
+    assert!(!fixture
+        .input
+        .epoch_context
+        .feature_set
+        .is_active(&stake_migration));
+
+    mollusk.process_and_validate_firedancer_fixture(&fixture);
+
+    // The config program isn't in the cache, so it's stubbed according to
+    // its migration, along with its program data account. Neither the harness
+    // nor the fixture can execute it.
+    mollusk.missing_program_behavior = MissingProgramBehavior::Stub;
+    let config_program = trezoa_sdk_ids::config::id();
+    let programdata_address =
+        Pubkey::find_program_address(&[config_program.as_ref()], &loader_keys::LOADER_V3).0;
+    let instruction = trezoa_instruction::Instruction::new_with_bytes(config_program, &[], vec![]);
+    let result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &[],
+        &[Check::err(ProgramError::UnsupportedProgramId)],
+    );
+
+    let fixture = mollusk_svm::fuzz::firedancer::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &[],
+        &result,
+    );
+    assert_eq!(
+        fixture_program_account(&fixture).owner,
+        loader_keys::LOADER_V3,
+    );
+    assert!(fixture
+        .input
+        .accounts
+        .iter()
+        .any(|(key, account, _)| *key == programdata_address
+            && account.owner == loader_keys::LOADER_V3));
+
+    mollusk.process_and_validate_firedancer_fixture(&fixture);
+
+    mollusk.feature_set.deactivate(&config_migration);
+    let result = mollusk.process_instruction(&instruction, &[]);
+    let fixture = mollusk_svm::fuzz::firedancer::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &[],
+        &result,
+    );
+    assert_eq!(
+        fixture_program_account(&fixture).owner,
+        loader_keys::NATIVE_LOADER,
+    );
+    assert!(!fixture
+        .input
+        .accounts
+        .iter()
+        .any(|(key, _, _)| *key == programdata_address));
+
+    mollusk.process_and_validate_firedancer_fixture(&fixture);
+}
@@ -0,0 +1,201 @@
+use {
+    mollusk_svm::{
+        program::create_program_account_loader_v3,
+        result::profile::{ExecutionProfile, InstructionProfile},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    trezoa_system_program::system_processor::DEFAULT_COMPUTE_UNITS,
+};
+
+#[test]
+fn test_profile_builtin() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let accounts = [
+        (sender, Account::new(100_000_000, 0, &system_program)),
+        (recipient, Account::new(100_000_000, 0, &system_program)),
+    ];
+
+    let mollusk = Mollusk::default();
+    let result = mollusk.process_instruction_chain(
+        &[
+            trezoa_system_interface::instruction::transfer(&sender, &recipient, 42),
+            trezoa_system_interface::instruction::transfer(&recipient, &sender, 42),
+        ],
+        &accounts,
+    );
+
+    let transfer_profile = InstructionProfile {
+        program_id: system_program,
+        stack_height: 1,
+        compute_units_consumed: Some(DEFAULT_COMPUTE_UNITS),
+    };
+    assert_eq!(
+        result.profile,
+        ExecutionProfile {
+            instructions: vec![transfer_profile.clone(), transfer_profile],
+            syscall_counts: Default::default(),
+            vm_instruction_count: None,
+        }
+    );
+}
+
+#[test]
+fn test_profile_cpi() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let cpi_target_program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    mollusk.add_program_with_loader(
+        &cpi_target_program_id,
+        "test_program_cpi_target",
+        &mollusk_svm::program::loader_keys::LOADER_V3,
+    );
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, &cpi_target_program_id);
+
+    let instruction = {
+        let mut instruction_data = vec![4];
+        instruction_data.extend_from_slice(cpi_target_program_id.as_ref());
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(key, true),
+                AccountMeta::new_readonly(cpi_target_program_id, false),
+            ],
+        )
+    };
+    let accounts = [
+        (key, account),
+        (
+            cpi_target_program_id,
+            create_program_account_loader_v3(&cpi_target_program_id),
+        ),
+    ];
+
+    let result = mollusk.process_instruction(&instruction, &accounts);
+    assert!(result.program_result.is_ok());
+
+    let profile = &result.profile;
+    assert_eq!(profile.instructions.len(), 2);
+
+    let top_level = &profile.instructions[0];
+    assert_eq!(top_level.program_id, program_id);
+    assert_eq!(top_level.stack_height, 1);
+    assert_eq!(
+        top_level.compute_units_consumed,
+        Some(result.compute_units_consumed)
+    );
+
+    let cpi = &profile.instructions[1];
+    assert_eq!(cpi.program_id, cpi_target_program_id);
+    assert_eq!(cpi.stack_height, 2);
+    let cpi_compute_units = cpi.compute_units_consumed.unwrap();
+    assert!(cpi_compute_units > 0);
+    assert!(cpi_compute_units < result.compute_units_consumed);
+
+    // Unlike `execution_time`, the profile is stable across runs.
+    let rerun = mollusk.process_instruction(&instruction, &accounts);
+    assert_eq!(rerun.profile, result.profile);
+}
+
+#[cfg(feature = "register-tracing")]
+#[test]
+fn test_profile_register_tracing() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new_debuggable(
+        &program_id,
+        "test_program_primary",
+        /* enable_register_tracing */ true,
+    );
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, &program_id);
+
+    let mut instruction_data = vec![1];
+    instruction_data.extend_from_slice(data);
+    let instruction = Instruction::new_with_bytes(
+        program_id,
+        &instruction_data,
+        vec![AccountMeta::new(key, true)],
+    );
+
+    let result = mollusk.process_instruction(&instruction, &[(key, account)]);
+    assert!(result.program_result.is_ok());
+
+    let profile = &result.profile;
+    assert!(profile.vm_instruction_count.unwrap() > 0);
+    assert!(profile.syscall_counts.values().all(|count| *count > 0));
+}
+
+#[cfg(feature = "register-tracing")]
+#[test]
+fn test_profile_syscalls_sbpf_v3() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let v3_program_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new_debuggable(
+        &program_id,
+        "test_program_primary",
+        /* enable_register_tracing */ true,
+    );
+    // Built for SBPF v3 by `make build-test-programs`, which invokes
+    // syscalls with the `syscall` instruction rather than `call imm`.
+    let v3_elf = std::fs::read("../target/deploy/v3/test_program_primary.so").unwrap();
+    mollusk.add_program_with_loader_and_elf(
+        &v3_program_id,
+        &mollusk_svm::program::loader_keys::LOADER_V3,
+        &v3_elf,
+    );
+
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let system_program = trezoa_sdk_ids::system_program::id();
+
+    for id in [program_id, v3_program_id] {
+        // Transfer with a CPI to the System program.
+        let instruction = Instruction::new_with_bytes(
+            id,
+            &[&[2], &1_000u64.to_le_bytes()[..]].concat(),
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program, false),
+            ],
+        );
+        let accounts = [
+            (payer, Account::new(100_000_000, 0, &system_program)),
+            (recipient, Account::new(100_000_000, 0, &system_program)),
+            mollusk_svm::program::keyed_account_for_system_program(),
+        ];
+
+        let result = mollusk.process_instruction(&instruction, &accounts);
+        assert!(result.program_result.is_ok());
+        assert_eq!(
+            result.profile.syscall_counts.get("sol_invoke_signed_rust"),
+            Some(&1)
+        );
+    }
+}
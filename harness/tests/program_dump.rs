@@ -0,0 +1,115 @@
+use {
+    mollusk_svm::{
+        file::{load_program_dump, ProgramDump},
+        program::{
+            create_program_account_loader_v3, create_program_data_account_loader_v3_with_header,
+            loader_keys,
+        },
+        result::Check,
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    std::path::PathBuf,
+};
+
+fn write_dump(name: &str, data: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mollusk-dump-{}", Pubkey::new_unique()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+fn check_write_data(mollusk: &Mollusk, program_id: &Pubkey) {
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, program_id);
+
+    let mut instruction_data = vec![1];
+    instruction_data.extend_from_slice(data);
+    let instruction = Instruction::new_with_bytes(
+        *program_id,
+        &instruction_data,
+        vec![AccountMeta::new(key, true)],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account)],
+        &[Check::success(), Check::account(&key).data(data).build()],
+    );
+}
+
+#[test]
+fn test_add_program_from_elf_dump() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let elf = mollusk_svm::file::load_program_elf("test_program_primary");
+    let path = write_dump("program.so", &elf);
+    assert_eq!(load_program_dump(&path), ProgramDump::Elf(elf.clone()));
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::default();
+    let [(program_key, program_account), (_, programdata_account)] =
+        mollusk.add_program_from_dump(&program_id, &path);
+
+    assert_eq!(program_key, program_id);
+    assert_eq!(
+        program_account,
+        create_program_account_loader_v3(&program_id)
+    );
+    assert_eq!(
+        programdata_account,
+        create_program_data_account_loader_v3_with_header(&elf, 0, None)
+    );
+    assert_eq!(
+        mollusk.program_cache.get_program_loader_key(&program_id),
+        Some(loader_keys::LOADER_V3)
+    );
+
+    check_write_data(&mollusk, &program_id);
+}
+
+#[test]
+fn test_add_program_from_programdata_dump() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let elf = mollusk_svm::file::load_program_elf("test_program_primary");
+    let authority = Pubkey::new_unique();
+    let programdata = create_program_data_account_loader_v3_with_header(&elf, 42, Some(authority));
+    let path = write_dump("programdata.bin", &programdata.data);
+    assert_eq!(
+        load_program_dump(&path),
+        ProgramDump::ProgramData {
+            slot: 42,
+            upgrade_authority_address: Some(authority),
+            elf: elf.clone(),
+        }
+    );
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::default();
+    let [_, (programdata_key, programdata_account)] =
+        mollusk.add_program_from_dump(&program_id, &path);
+
+    // The header is preserved.
+    assert_eq!(
+        programdata_key,
+        Pubkey::find_program_address(&[program_id.as_ref()], &loader_keys::LOADER_V3).0
+    );
+    assert_eq!(programdata_account, programdata);
+
+    check_write_data(&mollusk, &program_id);
+}
+
+#[test]
+#[should_panic(expected = "Program dump is neither an ELF nor loader-v3 program data")]
+fn test_invalid_program_dump() {
+    let path = write_dump("garbage.bin", &[1, 2, 3, 4]);
+    load_program_dump(path);
+}
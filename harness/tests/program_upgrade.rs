@@ -0,0 +1,197 @@
+use {
+    mollusk_svm::{
+        program::{
+            create_program_account_loader_v3, create_program_data_account_loader_v3_with_header,
+            loader_keys,
+        },
+        result::Check,
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::Instruction,
+    trezoa_loader_v3_interface::{
+        instruction::{set_upgrade_authority, upgrade},
+        state::UpgradeableLoaderState,
+    },
+    trezoa_pubkey::Pubkey,
+};
+
+fn buffer_account(mollusk: &Mollusk, elf: &[u8], authority: &Pubkey) -> Account {
+    let metadata_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    let mut data = vec![0; metadata_len + elf.len()];
+    bincode::serialize_into(
+        &mut data[..metadata_len],
+        &UpgradeableLoaderState::Buffer {
+            authority_address: Some(*authority),
+        },
+    )
+    .unwrap();
+    data[metadata_len..].copy_from_slice(elf);
+    Account {
+        lamports: mollusk.sysvars.rent.minimum_balance(data.len()),
+        data,
+        owner: loader_keys::LOADER_V3,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_upgrade_mid_chain() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let original_elf = mollusk_svm::file::load_program_elf("test_program_cpi_target");
+    let upgraded_elf = mollusk_svm::file::load_program_elf("test_program_primary");
+
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let buffer = Pubkey::new_unique();
+    let spill = Pubkey::new_unique();
+    let programdata_address =
+        Pubkey::find_program_address(&[program_id.as_ref()], &loader_keys::LOADER_V3).0;
+
+    let mut mollusk = Mollusk::default();
+    mollusk.add_program_with_loader_and_elf(&program_id, &loader_keys::LOADER_V3, &original_elf);
+    mollusk.warp_to_slot(10);
+
+    // Leave room in the programdata for the upgraded ELF.
+    let mut programdata_elf = original_elf.clone();
+    programdata_elf.resize(original_elf.len().max(upgraded_elf.len()), 0);
+    let mut programdata_account =
+        create_program_data_account_loader_v3_with_header(&programdata_elf, 0, Some(authority));
+    programdata_account.lamports = mollusk
+        .sysvars
+        .rent
+        .minimum_balance(programdata_account.data.len());
+
+    let system_account = Account::new(1_000_000_000, 0, &trezoa_sdk_ids::system_program::id());
+    let accounts = [
+        (programdata_address, programdata_account),
+        (program_id, create_program_account_loader_v3(&program_id)),
+        (buffer, buffer_account(&mollusk, &upgraded_elf, &authority)),
+        (spill, system_account.clone()),
+        mollusk.sysvars.keyed_account_for_rent_sysvar(),
+        mollusk.sysvars.keyed_account_for_clock_sysvar(),
+        (authority, system_account),
+    ];
+
+    // The original program expects an account, so the no-op of the
+    // upgraded program fails against it.
+    let noop = Instruction::new_with_bytes(program_id, &[0], vec![]);
+    assert!(mollusk
+        .process_instruction(&noop, &accounts)
+        .program_result
+        .is_err());
+
+    // The upgraded program is invoked by the elements which follow the
+    // upgrade.
+    mollusk.process_and_validate_instruction_chain(
+        &[
+            (
+                &upgrade(&program_id, &buffer, &authority, &spill),
+                &[Check::success()],
+            ),
+            (&noop, &[Check::success()]),
+        ],
+        &accounts,
+    );
+
+    let cached_elf = mollusk
+        .program_cache
+        .get_program_elf_bytes(&program_id)
+        .unwrap();
+    assert!(cached_elf.starts_with(&upgraded_elf));
+    assert_eq!(
+        mollusk.program_cache.get_program_loader_key(&program_id),
+        Some(loader_keys::LOADER_V3)
+    );
+}
+
+#[test]
+fn test_set_authority_keeps_program() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let elf = mollusk_svm::file::load_program_elf("test_program_primary");
+
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let new_authority = Pubkey::new_unique();
+    let programdata_address =
+        Pubkey::find_program_address(&[program_id.as_ref()], &loader_keys::LOADER_V3).0;
+
+    let mut mollusk = Mollusk::default();
+    mollusk.add_program_with_loader_and_elf(&program_id, &loader_keys::LOADER_V3, &elf);
+
+    let accounts = [
+        (
+            programdata_address,
+            create_program_data_account_loader_v3_with_header(&elf, 0, Some(authority)),
+        ),
+        (program_id, create_program_account_loader_v3(&program_id)),
+        (
+            authority,
+            Account::new(1_000_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (new_authority, Account::default()),
+    ];
+
+    // Changing the authority modifies the programdata, but not the ELF, so
+    // the program is left as is.
+    let result = mollusk.process_and_validate_instruction(
+        &set_upgrade_authority(&program_id, &authority, Some(&new_authority)),
+        &accounts,
+        &[Check::success()],
+    );
+    assert_eq!(
+        mollusk.program_cache.get_program_elf_bytes(&program_id),
+        Some(elf)
+    );
+
+    let noop = Instruction::new_with_bytes(program_id, &[0], vec![]);
+    mollusk.process_and_validate_instruction(
+        &noop,
+        &result.resulting_accounts,
+        &[Check::success()],
+    );
+}
+
+#[test]
+fn test_set_authority_placeholder_programdata() {
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let new_authority = Pubkey::new_unique();
+    let programdata_address =
+        Pubkey::find_program_address(&[program_id.as_ref()], &loader_keys::LOADER_V3).0;
+
+    let mollusk = Mollusk::default();
+
+    let accounts = [
+        (
+            programdata_address,
+            create_program_data_account_loader_v3_with_header(&[1; 64], 0, Some(authority)),
+        ),
+        (program_id, create_program_account_loader_v3(&program_id)),
+        (
+            authority,
+            Account::new(1_000_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (new_authority, Account::default()),
+    ];
+
+    // The programdata doesn't hold a valid ELF, so the program is marked as
+    // failing verification, rather than the sync panicking.
+    let result = mollusk.process_and_validate_instruction(
+        &set_upgrade_authority(&program_id, &authority, Some(&new_authority)),
+        &accounts,
+        &[Check::success()],
+    );
+    assert!(mollusk
+        .program_cache
+        .get_program_elf_bytes(&program_id)
+        .is_none());
+
+    let noop = Instruction::new_with_bytes(program_id, &[0], vec![]);
+    assert!(mollusk
+        .process_instruction(&noop, &result.resulting_accounts)
+        .program_result
+        .is_err());
+}
@@ -0,0 +1,66 @@
+use {
+    mollusk_svm::{result::Check, Mollusk},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::collections::HashMap,
+};
+
+#[test]
+fn test_referenced_accounts() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let unused = Pubkey::new_unique();
+
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    let accounts = [
+        (sender, Account::new(100_000_000, 0, &system_program)),
+        (recipient, Account::new(100_000_000, 0, &system_program)),
+        (unused, Account::new(100_000_000, 0, &system_program)),
+    ];
+
+    let result = Mollusk::default().process_instruction(&instruction, &accounts);
+    assert_eq!(
+        result.referenced_accounts,
+        vec![system_program, sender, recipient]
+    );
+
+    let result = Mollusk::default().process_instruction_chain(
+        &[
+            instruction.clone(),
+            trezoa_system_interface::instruction::transfer(&recipient, &unused, 42),
+        ],
+        &accounts,
+    );
+    assert_eq!(
+        result.referenced_accounts,
+        vec![system_program, sender, recipient, unused]
+    );
+}
+
+#[test]
+fn test_hydrate_referenced_only() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let mut account_store = HashMap::new();
+    account_store.insert(sender, Account::new(100_000_000, 0, &system_program));
+    account_store.insert(recipient, Account::new(100_000_000, 0, &system_program));
+
+    let mut context = Mollusk::default().with_context(account_store);
+    context.hydrate_referenced_only = true;
+
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    context.process_and_validate_instruction(&instruction, &[Check::success()]);
+
+    assert!(context.referenced_accounts.borrow().contains(&sender));
+    assert!(context
+        .referenced_accounts
+        .borrow()
+        .contains(&system_program));
+
+    // Sysvars the instruction never touched aren't hydrated.
+    let store = context.account_store.borrow();
+    assert!(!store.contains_key(&trezoa_sdk_ids::sysvar::clock::id()));
+}
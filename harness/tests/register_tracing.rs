@@ -209,3 +209,172 @@ fn test_custom_register_tracing_callback() {
         );
     }
 }
+
+#[cfg(feature = "register-tracing")]
+#[test]
+fn test_json_trace_callback() {
+    use {
+        mollusk_svm::{
+            register_tracing::{JsonTraceCallback, JSON_TRACE_SCHEMA_VERSION},
+            Mollusk,
+        },
+        trezoa_account::Account,
+        trezoa_instruction::{AccountMeta, Instruction},
+        trezoa_pubkey::Pubkey,
+        std::{cell::RefCell, io::Write, rc::Rc},
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::new_debuggable(
+        &program_id,
+        "test_program_primary",
+        /* enable_register_tracing */ true,
+    );
+    let buffer = SharedBuffer::default();
+    mollusk.invocation_inspect_callback = Box::new(JsonTraceCallback::new(buffer.clone()));
+
+    let data = &[1, 2, 3, 4, 5];
+    let key = Pubkey::new_unique();
+    let account = Account::new(
+        mollusk.sysvars.rent.minimum_balance(data.len()),
+        data.len(),
+        &program_id,
+    );
+    let instruction = Instruction::new_with_bytes(
+        program_id,
+        &[&[1], &data[..]].concat(),
+        vec![AccountMeta::new(key, true)],
+    );
+
+    // Each instruction of a chain is exported once.
+    let result =
+        mollusk.process_instruction_chain(&[instruction.clone(), instruction], &[(key, account)]);
+    assert!(result.program_result.is_ok());
+
+    let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines.len() as u64,
+        result.profile.vm_instruction_count.unwrap()
+    );
+
+    let prefix = format!(
+        "{{\"version\":{},\"trace\":0,\"program_id\":\"{}\",\"step\":0,\"pc\":",
+        JSON_TRACE_SCHEMA_VERSION, program_id
+    );
+    assert!(lines[0].starts_with(&prefix));
+
+    let records = lines
+        .iter()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let (first, second) = records.split_at(records.len() / 2);
+    for (trace, records) in [first, second].into_iter().enumerate() {
+        assert!(records.iter().all(
+            |record| record["trace"] == trace && record["program_id"] == program_id.to_string()
+        ));
+
+        // The remaining compute units are read from the compute meter, so
+        // the last instruction leaves what the instruction didn't consume.
+        let cu = records
+            .iter()
+            .map(|record| record["cu"].as_u64().unwrap())
+            .collect::<Vec<_>>();
+        assert!(cu.windows(2).all(|pair| pair[0] > pair[1]));
+        assert_eq!(
+            *cu.last().unwrap(),
+            mollusk.compute_budget.compute_unit_limit - result.compute_units_consumed / 2 + 1
+        );
+    }
+}
+
+#[cfg(feature = "register-tracing")]
+#[test]
+fn test_syscall_profile_callback() {
+    use {
+        mollusk_svm::{register_tracing::SyscallProfileCallback, result::Check, Mollusk},
+        trezoa_account::Account,
+        trezoa_instruction::{AccountMeta, Instruction},
+        trezoa_pubkey::Pubkey,
+        trezoa_system_program::system_processor::DEFAULT_COMPUTE_UNITS,
+    };
+
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mut mollusk = Mollusk::new_debuggable(
+        &program_id,
+        "test_program_primary",
+        /* enable_register_tracing */ true,
+    );
+    let profiler = SyscallProfileCallback::new();
+    let report = profiler.report();
+    mollusk.invocation_inspect_callback = Box::new(profiler);
+
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let (system_program_id, system_account) =
+        mollusk_svm::program::keyed_account_for_system_program();
+    let instruction = Instruction::new_with_bytes(
+        program_id,
+        &[&[2], &42u64.to_le_bytes()[..]].concat(),
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new_readonly(system_program_id, false),
+        ],
+    );
+    let accounts = [
+        (payer, Account::new(1_000_000, 0, &system_program_id)),
+        (recipient, Account::new(0, 0, &system_program_id)),
+        (system_program_id, system_account),
+    ];
+
+    let result =
+        mollusk.process_and_validate_instruction(&instruction, &accounts, &[Check::success()]);
+
+    {
+        let report = report.borrow();
+        assert_eq!(report.compute_units_consumed, result.compute_units_consumed);
+        assert_eq!(
+            report.vm_instruction_count,
+            result.profile.vm_instruction_count.unwrap()
+        );
+
+        // The transfer is a single CPI, measured with the compute meter, so it
+        // includes the System program it invokes, leaving nothing unattributed.
+        let cpi = &report.syscalls["sol_invoke_signed_rust"];
+        assert_eq!(cpi.calls, 1);
+        assert_eq!(
+            cpi.compute_units,
+            mollusk.compute_budget.invoke_units + DEFAULT_COMPUTE_UNITS
+        );
+        assert_eq!(report.unattributed_compute_units(), 0);
+        assert!(report.to_string().contains("sol_invoke_signed_rust"));
+    }
+
+    // Measurements follow the compute budget the instruction runs with.
+    let invoke_units = 2 * mollusk.compute_budget.invoke_units;
+    mollusk.compute_budget.invoke_units = invoke_units;
+    *report.borrow_mut() = Default::default();
+    mollusk.process_and_validate_instruction(&instruction, &accounts, &[Check::success()]);
+    assert_eq!(
+        report.borrow().syscalls["sol_invoke_signed_rust"].compute_units,
+        invoke_units + DEFAULT_COMPUTE_UNITS
+    );
+}
@@ -0,0 +1,110 @@
+use {
+    mollusk_svm::{
+        rent_collection::{collect_rent, RENT_EXEMPT_RENT_EPOCH},
+        result::Check,
+        Mollusk,
+    },
+    trezoa_account::{Account, AccountSharedData, ReadableAccount},
+    trezoa_instruction::AccountMeta,
+    trezoa_pubkey::Pubkey,
+};
+
+#[test]
+fn test_collect_rent() {
+    let mollusk = Mollusk::default();
+    let rent = &mollusk.sysvars.rent;
+    let epoch_schedule = &mollusk.sysvars.epoch_schedule;
+
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+
+    // One epoch of rent for an account with no data.
+    let mut account = AccountSharedData::new(10_000, 0, &owner);
+    assert_eq!(
+        collect_rent(rent, epoch_schedule, 0, &key, &mut account),
+        2439
+    );
+    assert_eq!(account.lamports(), 10_000 - 2439);
+    assert_eq!(account.rent_epoch(), 1);
+
+    // Already paid up for this epoch.
+    assert_eq!(collect_rent(rent, epoch_schedule, 0, &key, &mut account), 0);
+
+    // An account which can't pay is deleted.
+    let mut account = AccountSharedData::new(100, 0, &owner);
+    assert_eq!(
+        collect_rent(rent, epoch_schedule, 0, &key, &mut account),
+        100
+    );
+    assert_eq!(account, AccountSharedData::default());
+
+    // Rent-exempt accounts are never charged.
+    let mut account = AccountSharedData::new(rent.minimum_balance(0), 0, &owner);
+    assert_eq!(collect_rent(rent, epoch_schedule, 0, &key, &mut account), 0);
+    assert_eq!(account.rent_epoch(), RENT_EXEMPT_RENT_EPOCH);
+}
+
+#[test]
+fn test_rent_collection_before_execution() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let rent_payer = Pubkey::new_unique();
+    let insolvent = Pubkey::new_unique();
+    let readonly = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let mut instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    // The System program ignores any extra accounts.
+    instruction.accounts.extend([
+        AccountMeta::new(rent_payer, false),
+        AccountMeta::new(insolvent, false),
+        AccountMeta::new_readonly(readonly, false),
+    ]);
+
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let accounts = [
+        (sender, Account::new(base_lamports, 0, &system_program)),
+        (recipient, Account::new(base_lamports, 0, &system_program)),
+        (rent_payer, Account::new(10_000, 0, &system_program)),
+        (insolvent, Account::new(100, 0, &system_program)),
+        (readonly, Account::new(10_000, 0, &system_program)),
+    ];
+
+    // Without rent collection, accounts are left alone.
+    let mut mollusk = Mollusk::default();
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&rent_payer).lamports(10_000).build(),
+            Check::account(&insolvent).lamports(100).build(),
+        ],
+    );
+
+    mollusk.collect_rent = true;
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&sender)
+                .lamports(base_lamports - transfer_amount)
+                .rent_epoch(RENT_EXEMPT_RENT_EPOCH)
+                .build(),
+            Check::account(&rent_payer)
+                .lamports(10_000 - 2439)
+                .rent_epoch(1)
+                .build(),
+            Check::account(&insolvent).lamports(0).build(),
+            // Readonly accounts aren't charged.
+            Check::account(&readonly)
+                .lamports(10_000)
+                .rent_epoch(0)
+                .build(),
+        ],
+    );
+}
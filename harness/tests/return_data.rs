@@ -0,0 +1,139 @@
+use {
+    mollusk_svm::{
+        result::{return_data::InnerReturnData, Check, Config},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    trezoa_svm_log_collector::LogCollector,
+};
+
+fn config() -> Config {
+    Config::default().panic(false)
+}
+
+fn mollusk() -> (Mollusk, Pubkey) {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+    (mollusk, program_id)
+}
+
+// Return the provided data.
+fn return_data(program_id: &Pubkey, key: &Pubkey, data: &[u8]) -> Instruction {
+    let mut instruction_data = vec![8];
+    instruction_data.extend_from_slice(data);
+    Instruction::new_with_bytes(
+        *program_id,
+        &instruction_data,
+        vec![AccountMeta::new(*key, true)],
+    )
+}
+
+#[test]
+fn test_return_data_checks() {
+    let (mollusk, program_id) = mollusk();
+    let config = config();
+
+    let key = Pubkey::new_unique();
+    // A `u32` tag, followed by a `u64` count.
+    let mut data = 7u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&3u64.to_le_bytes());
+
+    let result = mollusk.process_and_validate_instruction(
+        &return_data(&program_id, &key, &data),
+        &[(key, Account::default())],
+        &[
+            Check::success(),
+            Check::return_data_len(12),
+            Check::return_data_slice(0, &7u32.to_le_bytes()),
+            Check::return_data_slice(4, &3u64.to_le_bytes()),
+            Check::return_data_with(|data| {
+                let count = u64::from_le_bytes(data[4..12].try_into().unwrap());
+                count == 3
+            }),
+        ],
+    );
+
+    let failing_checks = [
+        Check::return_data_len(8),
+        Check::return_data_slice(0, &8u32.to_le_bytes()),
+        // Out of bounds.
+        Check::return_data_slice(8, &3u64.to_le_bytes()),
+        Check::return_data_with(|data| data.is_empty()),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config, &mollusk));
+    }
+}
+
+#[test]
+fn test_inner_return_data() {
+    let (mut mollusk, program_id) = mollusk();
+
+    let key = Pubkey::new_unique();
+    let accounts = [(key, Account::default())];
+    let return_data = |data: &[u8]| return_data(&program_id, &key, data);
+    // The program invokes itself, which returns the data.
+    let return_data_via_cpi = |data: &[u8]| {
+        let mut instruction_data = vec![4];
+        instruction_data.extend_from_slice(program_id.as_ref());
+        instruction_data.push(8);
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![AccountMeta::new(key, true)],
+        )
+    };
+
+    let result = mollusk.process_and_validate_instruction(
+        &return_data_via_cpi(&[1, 2]),
+        &accounts,
+        &[
+            Check::success(),
+            Check::return_data(&[1, 2]),
+            Check::inner_return_data(&program_id, &[1, 2]),
+        ],
+    );
+    assert_eq!(
+        result.inner_return_data,
+        [InnerReturnData {
+            program_id,
+            stack_height: 2,
+            data: vec![1, 2],
+        }]
+    );
+
+    // Return data is recorded even once the logs are truncated.
+    mollusk.logger = Some(LogCollector::new_ref_with_limit(Some(0)));
+    let result = mollusk.process_instruction(&return_data(&[3]), &accounts);
+    assert_eq!(
+        result.inner_return_data,
+        [InnerReturnData {
+            program_id,
+            stack_height: 1,
+            data: vec![3],
+        }]
+    );
+    mollusk.logger = None;
+
+    // Instructions returning the same data are each attributed their own.
+    let result = mollusk
+        .process_transaction_instructions(&[return_data(&[4]), return_data(&[4])], &accounts);
+    assert!(result.program_result.is_ok());
+    assert_eq!(result.per_instruction[0].return_data, [4]);
+    assert_eq!(result.per_instruction[1].return_data, [4]);
+    assert_eq!(result.inner_return_data.len(), 2);
+
+    let result = mollusk.process_instruction(&return_data_via_cpi(&[5]), &accounts);
+    let failing_checks = [
+        Check::inner_return_data(&program_id, &[6]),
+        Check::inner_return_data(&Pubkey::new_unique(), &[5]),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config(), &mollusk));
+    }
+}
@@ -0,0 +1,120 @@
+use {
+    mollusk_svm::{result::Check, runtime_environment::RuntimeEnvironmentCache, Mollusk},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+fn transfer(mollusk: &Mollusk) {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    let accounts = [
+        (
+            sender,
+            Account::new(1_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(0, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+    mollusk.process_and_validate_instruction(&instruction, &accounts, &[Check::success()]);
+}
+
+#[test]
+fn test_runtime_environments_reused() {
+    let mut mollusk = Mollusk::default();
+    assert!(mollusk.runtime_environments.is_empty());
+
+    transfer(&mollusk);
+    transfer(&mollusk);
+    transfer(&mollusk);
+    assert_eq!(mollusk.runtime_environments.len(), 1);
+
+    // Built once, then reused.
+    let environments =
+        mollusk
+            .runtime_environments
+            .get(&mollusk.feature_set, &mollusk.compute_budget, false, &[]);
+    let reused =
+        mollusk
+            .runtime_environments
+            .get(&mollusk.feature_set, &mollusk.compute_budget, false, &[]);
+    assert!(Arc::ptr_eq(
+        &environments.program_runtime_v1,
+        &reused.program_runtime_v1
+    ));
+    assert!(Arc::ptr_eq(
+        &environments.program_runtime_v2,
+        &reused.program_runtime_v2
+    ));
+
+    // The compute unit limit doesn't affect the environments.
+    mollusk.compute_budget.compute_unit_limit /= 2;
+    transfer(&mollusk);
+    assert_eq!(mollusk.runtime_environments.len(), 1);
+
+    // A different call depth gets its own environments.
+    mollusk.compute_budget.max_call_depth /= 2;
+    transfer(&mollusk);
+    assert_eq!(mollusk.runtime_environments.len(), 2);
+
+    mollusk.runtime_environments.clear();
+    assert!(mollusk.runtime_environments.is_empty());
+}
+
+#[test]
+fn test_runtime_environments_shared() {
+    let first = Mollusk::default();
+    let mut second = Mollusk::default();
+    second.runtime_environments = first.runtime_environments.clone();
+
+    transfer(&first);
+    transfer(&second);
+    assert_eq!(first.runtime_environments.len(), 1);
+    assert_eq!(second.runtime_environments.len(), 1);
+}
+
+#[test]
+fn test_runtime_environments_evicted() {
+    let mut mollusk = Mollusk::default();
+    mollusk.runtime_environments = RuntimeEnvironmentCache::with_capacity(2);
+
+    let default_budget = mollusk.compute_budget;
+    let environments =
+        mollusk
+            .runtime_environments
+            .get(&mollusk.feature_set, &default_budget, false, &[]);
+
+    // Each call depth gets its own environments, evicting the least recently
+    // used ones once the cache is full.
+    for depth in 1..=4 {
+        mollusk.compute_budget.max_call_depth = depth;
+        transfer(&mollusk);
+        assert!(mollusk.runtime_environments.len() <= 2);
+    }
+    assert_eq!(mollusk.runtime_environments.len(), 2);
+
+    // The default budget's environments were evicted, so they're rebuilt.
+    let rebuilt =
+        mollusk
+            .runtime_environments
+            .get(&mollusk.feature_set, &default_budget, false, &[]);
+    assert!(!Arc::ptr_eq(
+        &environments.program_runtime_v1,
+        &rebuilt.program_runtime_v1
+    ));
+
+    // Using an entry keeps it from being evicted.
+    mollusk.compute_budget.max_call_depth = 5;
+    transfer(&mollusk);
+    let reused =
+        mollusk
+            .runtime_environments
+            .get(&mollusk.feature_set, &default_budget, false, &[]);
+    assert!(Arc::ptr_eq(
+        &rebuilt.program_runtime_v1,
+        &reused.program_runtime_v1
+    ));
+}
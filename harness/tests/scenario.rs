@@ -0,0 +1,108 @@
+use {
+    mollusk_svm::{result::Check, scenario::Scenario, testgen::KeyGen, Mollusk},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+};
+
+fn system_account(lamports: u64) -> Account {
+    Account::new(lamports, 0, &trezoa_sdk_ids::system_program::id())
+}
+
+#[test]
+fn test_scenario_transfer() {
+    let mollusk = Mollusk::default();
+
+    let payer = Scenario::new("payer").account("payer", |_| system_account(1_000_000));
+    let recipient = Scenario::new("recipient").account("recipient", |_| system_account(0));
+    let transfer = payer.merge(recipient);
+    assert_eq!(transfer.name(), "payer + recipient");
+    assert_eq!(
+        transfer.account_names().collect::<Vec<_>>(),
+        ["payer", "recipient"]
+    );
+
+    let first = transfer.instantiate();
+    let second = transfer.instantiate();
+    assert_ne!(first.key("payer"), second.key("payer"));
+    assert_ne!(first.key("recipient"), second.key("recipient"));
+
+    for instance in [first, second] {
+        let instruction = trezoa_system_interface::instruction::transfer(
+            &instance.key("payer"),
+            &instance.key("recipient"),
+            42,
+        );
+        mollusk.process_and_validate_instruction(
+            &instruction,
+            &instance,
+            &[
+                Check::success(),
+                Check::account(&instance.key("payer"))
+                    .lamports(1_000_000 - 42)
+                    .build(),
+                Check::account(&instance.key("recipient"))
+                    .lamports(42)
+                    .build(),
+            ],
+        );
+    }
+}
+
+#[test]
+fn test_scenario_derived_accounts() {
+    let program_id = Pubkey::new_unique();
+
+    let authority = Scenario::new("authority").account("authority", |_| system_account(1_000));
+    let vault = Scenario::new("vault")
+        .derived_account(
+            "vault",
+            move |keys| {
+                Pubkey::find_program_address(&[b"vault", keys["authority"].as_ref()], &program_id).0
+            },
+            move |keys| Account {
+                lamports: 1_000,
+                data: keys["authority"].to_bytes().to_vec(),
+                owner: program_id,
+                ..Default::default()
+            },
+        )
+        // Accounts can refer to derived keys.
+        .account("config", |keys| Account {
+            data: keys["vault"].to_bytes().to_vec(),
+            ..Default::default()
+        });
+    let scenario = authority.merge(vault);
+
+    let instance = scenario.instantiate_with(&mut KeyGen::seeded(42));
+    let authority_key = instance.key("authority");
+    let vault_key = instance.key("vault");
+    assert_eq!(
+        vault_key,
+        Pubkey::find_program_address(&[b"vault", authority_key.as_ref()], &program_id).0
+    );
+    assert_eq!(instance.account("vault").data, authority_key.to_bytes());
+    assert_eq!(instance.account("config").data, vault_key.to_bytes());
+    assert_eq!(instance.len(), 3);
+
+    // The same seed yields the same instance.
+    assert_eq!(scenario.instantiate_with(&mut KeyGen::seeded(42)), instance);
+
+    // Merging replaces accounts with the same name.
+    let rich = scenario
+        .merge(Scenario::new("rich authority").account("authority", |_| system_account(1_000_000)));
+    let instance = rich.instantiate();
+    assert_eq!(instance.len(), 3);
+    assert_eq!(instance.accounts()[0].0, instance.key("authority"));
+    assert_eq!(instance.account("authority").lamports, 1_000_000);
+}
+
+#[test]
+#[should_panic(expected = "Scenario has no account named `payer`")]
+fn test_scenario_missing_account() {
+    Scenario::new("mint")
+        .account("mint", |keys| Account {
+            data: keys["payer"].to_bytes().to_vec(),
+            ..Default::default()
+        })
+        .instantiate();
+}
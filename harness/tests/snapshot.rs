@@ -0,0 +1,98 @@
+use {
+    mollusk_svm::{
+        mollusk_snapshot,
+        result::{Check, Config},
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+};
+
+#[test]
+fn test_snapshot() {
+    let mollusk = Mollusk::default();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = [
+        (
+            sender,
+            Account::new(100_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(100_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let path = std::env::temp_dir()
+        .join(format!("mollusk-snapshot-{}", Pubkey::new_unique()))
+        .join("transfer.snap");
+
+    let result = mollusk.process_instruction(
+        &trezoa_system_interface::instruction::transfer(&sender, &recipient, 42_000),
+        &accounts,
+    );
+    result.save_snapshot(&path).unwrap();
+
+    // Re-running the same instruction matches the snapshot.
+    mollusk.process_and_validate_instruction(
+        &trezoa_system_interface::instruction::transfer(&sender, &recipient, 42_000),
+        &accounts,
+        &[Check::success(), Check::matches_snapshot(&path)],
+    );
+
+    // A different transfer amount does not.
+    let result = mollusk.process_instruction(
+        &trezoa_system_interface::instruction::transfer(&sender, &recipient, 43_000),
+        &accounts,
+    );
+    let config = Config::default().panic(false).verbose(true);
+    assert!(!result.run_checks(&[Check::matches_snapshot(&path)], &config, &mollusk));
+
+    // Neither does a missing snapshot.
+    let missing = path.with_file_name("missing.snap");
+    assert!(!result.run_checks(&[Check::matches_snapshot(&missing)], &config, &mollusk));
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_snapshot_macro() {
+    let mollusk = Mollusk::default();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = [
+        (
+            sender,
+            Account::new(100_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(100_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+    let instruction = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42_000);
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots/snapshot__test_snapshot_macro__transfer.snap");
+    let _ = std::fs::remove_file(&path);
+
+    // The first run creates the snapshot.
+    mollusk_snapshot!(mollusk, &instruction, &accounts, "transfer");
+    assert!(path.exists());
+
+    // Later runs compare against it.
+    mollusk_snapshot!(mollusk, &instruction, &accounts, "transfer");
+
+    let different = trezoa_system_interface::instruction::transfer(&sender, &recipient, 43_000);
+    let mismatch = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        mollusk_snapshot!(mollusk, &different, &accounts, "transfer");
+    }));
+    assert!(mismatch.is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "all-builtins")]
+
+use {
+    mollusk_svm::{
+        epoch_stake::create_vote_account_with_authorities,
+        program::{keyed_account_for_stake_program, keyed_account_for_vote_program},
+        result::Check,
+        stake::{create_delegated_stake_account, create_initialized_stake_account},
+        Mollusk,
+    },
+    trezoa_instruction::{error::InstructionError, AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+};
+
+#[test]
+fn test_stake_program() {
+    let mollusk = Mollusk::default();
+
+    let authority = Pubkey::new_unique();
+    let stake_address = Pubkey::new_unique();
+    let vote_address = Pubkey::new_unique();
+
+    let epoch = mollusk.sysvars.clock.epoch;
+    let stake_account = create_delegated_stake_account(
+        &authority,
+        &vote_address,
+        1_000_000_000,
+        &mollusk.sysvars.rent,
+        epoch,
+    );
+
+    // `StakeInstruction::Deactivate`.
+    let instruction = Instruction::new_with_bytes(
+        trezoa_sdk_ids::stake::id(),
+        &5u32.to_le_bytes(),
+        vec![
+            AccountMeta::new(stake_address, false),
+            AccountMeta::new_readonly(trezoa_sdk_ids::sysvar::clock::id(), false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+    );
+
+    // The delegation's deactivation epoch follows the tag (4 bytes), the
+    // `Meta` (120 bytes), and the voter, stake, and activation epoch.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[
+            (stake_address, stake_account),
+            (authority, Default::default()),
+            keyed_account_for_stake_program(),
+        ],
+        &[
+            Check::success(),
+            Check::account(&stake_address)
+                .data_slice(172, &epoch.to_le_bytes())
+                .build(),
+        ],
+    );
+
+    // An undelegated stake account can't be deactivated.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[
+            (
+                stake_address,
+                create_initialized_stake_account(&authority, &mollusk.sysvars.rent),
+            ),
+            (authority, Default::default()),
+            keyed_account_for_stake_program(),
+        ],
+        &[Check::instruction_err(InstructionError::InvalidAccountData)],
+    );
+}
+
+#[test]
+fn test_vote_program() {
+    let mollusk = Mollusk::default();
+
+    let node = Pubkey::new_unique();
+    let voter = Pubkey::new_unique();
+    let withdrawer = Pubkey::new_unique();
+    let vote_address = Pubkey::new_unique();
+
+    let vote_account = create_vote_account_with_authorities(
+        &node,
+        &voter,
+        &withdrawer,
+        10,
+        &mollusk.sysvars.rent,
+        mollusk.sysvars.clock.epoch,
+    );
+
+    // `VoteInstruction::UpdateCommission`, lowering it to 5%.
+    let instruction = Instruction::new_with_bytes(
+        trezoa_sdk_ids::vote::id(),
+        &[5, 0, 0, 0, 5],
+        vec![
+            AccountMeta::new(vote_address, false),
+            AccountMeta::new_readonly(withdrawer, true),
+        ],
+    );
+
+    // The commission follows the tag, node pubkey, and withdrawer.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[
+            (vote_address, vote_account),
+            (withdrawer, Default::default()),
+            keyed_account_for_vote_program(),
+        ],
+        &[
+            Check::success(),
+            Check::account(&vote_address).data_slice(68, &[5]).build(),
+        ],
+    );
+}
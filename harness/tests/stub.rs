@@ -0,0 +1,136 @@
+use {
+    mollusk_svm::{result::Check, stub::STUB_COMPUTE_UNITS, Mollusk},
+    trezoa_account::Account,
+    trezoa_account_info::MAX_PERMITTED_DATA_INCREASE,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_program_error::ProgramError,
+    trezoa_pubkey::Pubkey,
+    std::{cell::Cell, rc::Rc},
+};
+
+#[test]
+fn test_stub_program() {
+    let stub_id = Pubkey::new_unique();
+    let invocations = Rc::new(Cell::new(0));
+
+    let mut mollusk = Mollusk::default();
+    mollusk.stub_program(&stub_id, {
+        let invocations = invocations.clone();
+        move |data, accounts| {
+            invocations.set(invocations.get() + 1);
+            if data.is_empty() {
+                return Err(ProgramError::Custom(7));
+            }
+            let account = &accounts[0];
+            account.try_borrow_mut_data()?.copy_from_slice(data);
+            Ok(())
+        }
+    });
+
+    let key = Pubkey::new_unique();
+    let data = [1, 2, 3];
+    let account = Account::new(100_000_000, data.len(), &stub_id);
+    let instruction =
+        Instruction::new_with_bytes(stub_id, &data, vec![AccountMeta::new(key, true)]);
+
+    // The stub's account changes are written back.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account.clone())],
+        &[
+            Check::success(),
+            Check::compute_units(STUB_COMPUTE_UNITS),
+            Check::account(&key).data(&data).build(),
+        ],
+    );
+
+    let instruction = Instruction::new_with_bytes(stub_id, &[], vec![AccountMeta::new(key, true)]);
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account)],
+        &[Check::err(ProgramError::Custom(7))],
+    );
+
+    assert_eq!(invocations.get(), 2);
+}
+
+#[test]
+fn test_stub_program_cpi() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+    let stub_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::new(&program_id, "test_program_primary");
+    let stub_account = mollusk.stub_program(&stub_id, |data, accounts| {
+        accounts[0].try_borrow_mut_data()?.copy_from_slice(data);
+        Ok(())
+    });
+
+    let data = &[1, 2, 3, 4, 5];
+    let key = Pubkey::new_unique();
+    let account = Account::new(
+        mollusk.sysvars.rent.minimum_balance(data.len()),
+        data.len(),
+        &stub_id,
+    );
+
+    // Have the primary program CPI into the stub.
+    let mut instruction_data = vec![4];
+    instruction_data.extend_from_slice(stub_id.as_ref());
+    instruction_data.extend_from_slice(data);
+    let instruction = Instruction::new_with_bytes(
+        program_id,
+        &instruction_data,
+        vec![
+            AccountMeta::new(key, true),
+            AccountMeta::new_readonly(stub_id, false),
+        ],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account), stub_account],
+        &[Check::success(), Check::account(&key).data(data).build()],
+    );
+}
+
+#[test]
+fn test_stub_program_resize_and_duplicates() {
+    let stub_id = Pubkey::new_unique();
+
+    let mut mollusk = Mollusk::default();
+    mollusk.stub_program(&stub_id, |data, accounts| {
+        // Both infos are the same account, so growing one grows the other.
+        accounts[0].resize(data.len())?;
+        accounts[1].try_borrow_mut_data()?.copy_from_slice(data);
+        Ok(())
+    });
+
+    let key = Pubkey::new_unique();
+    let data = [1, 2, 3];
+    let instruction = Instruction::new_with_bytes(
+        stub_id,
+        &data,
+        vec![AccountMeta::new(key, true), AccountMeta::new(key, true)],
+    );
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, Account::new(100_000_000, 0, &stub_id))],
+        &[Check::success(), Check::account(&key).data(&data).build()],
+    );
+
+    // Data can't grow past the usual limit.
+    let too_long = vec![1; MAX_PERMITTED_DATA_INCREASE + 1];
+    let instruction = Instruction::new_with_bytes(
+        stub_id,
+        &too_long,
+        vec![AccountMeta::new(key, true), AccountMeta::new(key, true)],
+    );
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, Account::new(100_000_000, 0, &stub_id))],
+        &[Check::err(ProgramError::InvalidRealloc)],
+    );
+}
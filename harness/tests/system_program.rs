@@ -1,8 +1,11 @@
 use {
     mollusk_svm::{result::Check, Mollusk},
     trezoa_account::{Account, WritableAccount},
+    trezoa_hash::Hash,
     trezoa_instruction::error::InstructionError,
+    trezoa_nonce::state::{DurableNonce, State},
     trezoa_pubkey::Pubkey,
+    trezoa_system_interface::error::SystemError,
     trezoa_system_program::system_processor::DEFAULT_COMPUTE_UNITS,
 };
 
@@ -73,7 +76,15 @@ fn test_transfer_account_ordering() {
             .build(),
     ];
 
-    Mollusk::default().process_and_validate_instruction(&instruction, &accounts, &checks);
+    let result =
+        Mollusk::default().process_and_validate_instruction(&instruction, &accounts, &checks);
+
+    // The compiled message orders accounts by privilege, regardless.
+    let message = result.message.unwrap();
+    assert_eq!(
+        message.account_keys().iter().copied().collect::<Vec<_>>(),
+        vec![sender, recipient, trezoa_sdk_ids::system_program::id()],
+    );
 }
 
 #[test]
@@ -136,3 +147,198 @@ fn test_transfer_swap_program_account() {
     // provided one. Now it uses the provided account if available.
     let _result = Mollusk::default().process_instruction(&instruction, &accounts);
 }
+
+#[test]
+fn test_write_set() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let bystander = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let mut instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    // Writable, but untouched by the transfer.
+    instruction
+        .accounts
+        .push(trezoa_instruction::AccountMeta::new(bystander, false));
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            bystander,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let mollusk = Mollusk::default();
+    let mut result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[Check::success(), Check::no_undeclared_writes()],
+    );
+
+    for key in [sender, recipient, bystander] {
+        assert!(result.declared_writable.contains(&key));
+    }
+    assert!(!result
+        .declared_writable
+        .contains(&trezoa_sdk_ids::system_program::id()));
+    assert_eq!(result.actually_modified, vec![sender, recipient]);
+
+    // A write outside of the declared set fails the check.
+    result.declared_writable.retain(|key| key != &recipient);
+    let config = mollusk_svm::result::Config::default().panic(false);
+    assert!(!result.run_checks(&[Check::no_undeclared_writes()], &config, &mollusk));
+}
+
+#[test]
+fn test_write_set_absorbed() {
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let carol = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let accounts = [alice, bob, carol].map(|key| {
+        (
+            key,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        )
+    });
+
+    let mollusk = Mollusk::default();
+    let config = mollusk_svm::result::Config::default().panic(false);
+
+    let first = mollusk.process_instruction(
+        &trezoa_system_interface::instruction::transfer(&alice, &bob, 1_000),
+        &accounts,
+    );
+    let mut second = mollusk.process_instruction(
+        &trezoa_system_interface::instruction::transfer(&bob, &carol, 1_000),
+        &first.resulting_accounts,
+    );
+
+    let mut result = first.clone();
+    result.absorb(second.clone());
+    assert!(result.run_checks(&[Check::no_undeclared_writes()], &config, &mollusk));
+
+    // The first instruction declaring Bob writable doesn't cover the second
+    // instruction writing Bob without declaring it.
+    second.declared_writable.retain(|key| key != &bob);
+    let mut result = first;
+    result.absorb(second);
+    assert!(!result.run_checks(&[Check::no_undeclared_writes()], &config, &mollusk));
+}
+
+#[test]
+fn test_pre_execution_transform() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction =
+        trezoa_system_interface::instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+    ];
+
+    let mut mollusk = Mollusk::default();
+    mollusk.process_and_validate_instruction(&instruction, &accounts, &[Check::success()]);
+
+    // Drain the sender before execution, without touching the provided
+    // accounts.
+    mollusk.set_pre_execution_transform(move |transaction_accounts| {
+        for (pubkey, account) in transaction_accounts.iter_mut() {
+            if *pubkey == sender {
+                account.set_lamports(0);
+            }
+        }
+    });
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[Check::instruction_err(InstructionError::Custom(
+            SystemError::ResultWithNegativeLamports as u32,
+        ))],
+    );
+    assert_eq!(accounts[0].1.lamports, base_lamports);
+}
+
+#[test]
+fn test_blockhash_and_lamports_per_signature() {
+    let mut mollusk = Mollusk::default();
+    mollusk.blockhash = Hash::new_from_array([7; 32]);
+    mollusk.lamports_per_signature = 1_234;
+
+    let payer = Pubkey::new_unique();
+    let nonce = Pubkey::new_unique();
+    let nonce_lamports = mollusk.sysvars.rent.minimum_balance(State::size());
+
+    // Initializing a nonce account requires a non-empty `RecentBlockhashes`
+    // sysvar, which isn't served by default.
+    let recent_blockhashes = {
+        let mut data = 1u64.to_le_bytes().to_vec();
+        data.extend_from_slice(mollusk.blockhash.as_ref());
+        data.extend_from_slice(&mollusk.lamports_per_signature.to_le_bytes());
+        let mut account = Account::new(1, 0, &trezoa_sdk_ids::sysvar::id());
+        account.data = data;
+        account
+    };
+
+    let instructions = trezoa_system_interface::instruction::create_nonce_account(
+        &payer,
+        &nonce,
+        &payer,
+        nonce_lamports,
+    );
+    let accounts = [
+        (
+            payer,
+            Account::new(10_000_000_000, 0, &trezoa_sdk_ids::system_program::id()),
+        ),
+        (nonce, Account::default()),
+        (
+            trezoa_sdk_ids::sysvar::recent_blockhashes::id(),
+            recent_blockhashes,
+        ),
+        mollusk.sysvars.keyed_account_for_rent_sysvar(),
+    ];
+
+    // The nonce account stores the durable nonce derived from the blockhash,
+    // followed by the lamports per signature.
+    let durable_nonce = DurableNonce::from_blockhash(&mollusk.blockhash);
+    let lamports_per_signature = mollusk.lamports_per_signature.to_le_bytes();
+    let create_checks = [Check::success()];
+    let initialize_checks = [
+        Check::success(),
+        Check::account(&nonce)
+            .data_slice(40, durable_nonce.as_hash().as_ref())
+            .build(),
+        Check::account(&nonce)
+            .data_slice(72, &lamports_per_signature)
+            .build(),
+    ];
+    mollusk.process_and_validate_instruction_chain(
+        &[
+            (&instructions[0], &create_checks),
+            (&instructions[1], &initialize_checks),
+        ],
+        &accounts,
+    );
+}
@@ -1,8 +1,15 @@
 use {
-    mollusk_svm::{program::keyed_account_for_system_program, result::Check, Mollusk},
+    mollusk_svm::{
+        fees::FeeModel,
+        program::keyed_account_for_system_program,
+        result::{Check, Config},
+        Mollusk,
+    },
     trezoa_account::Account,
     trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_instruction_error::InstructionError,
     trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
 };
 
 fn system_account_with_lamports(lamports: u64) -> Account {
@@ -229,3 +236,333 @@ fn test_many_instructions_in_transaction() {
         Some(initial_balance - (transfer_amount * 10))
     );
 }
+
+fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![2];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction::new_with_bytes(trezoa_sdk_ids::compute_budget::id(), &data, vec![])
+}
+
+fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![3];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    Instruction::new_with_bytes(trezoa_sdk_ids::compute_budget::id(), &data, vec![])
+}
+
+#[test]
+fn test_compute_budget_instructions() {
+    let mollusk = Mollusk::default();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let transfer = trezoa_system_interface::instruction::transfer(&sender, &recipient, 100);
+    let accounts = [
+        (sender, system_account_with_lamports(1000)),
+        (recipient, system_account_with_lamports(0)),
+    ];
+
+    // A sufficient limit is applied, and the compute budget instruction
+    // itself is not executed.
+    let result = mollusk.process_transaction_instructions(
+        &[set_compute_unit_limit(10_000), transfer.clone()],
+        &accounts,
+    );
+    assert!(result.program_result.is_ok());
+    assert_eq!(
+        result.compute_units_consumed,
+        mollusk
+            .process_transaction_instructions(&[transfer.clone()], &accounts)
+            .compute_units_consumed,
+    );
+
+    // An insufficient limit fails the transfer.
+    let result = mollusk.process_transaction_instructions(
+        &[set_compute_unit_limit(1), transfer.clone()],
+        &accounts,
+    );
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::InstructionError(
+            1,
+            InstructionError::ComputationalBudgetExceeded,
+        )),
+    );
+
+    // Duplicate compute budget instructions fail the transaction before
+    // anything executes.
+    let result = mollusk.process_transaction_instructions(
+        &[
+            set_compute_unit_limit(10_000),
+            set_compute_unit_limit(10_000),
+            transfer,
+        ],
+        &accounts,
+    );
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::DuplicateInstruction(1)),
+    );
+    assert_eq!(result.compute_units_consumed, 0);
+    assert_eq!(result.resulting_accounts, accounts.to_vec());
+}
+
+#[test]
+fn test_address_lookup_tables() {
+    let mollusk = Mollusk::default();
+
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let carol = Pubkey::new_unique();
+
+    let transfer_amount = 1_000u64;
+
+    // Bob and Carol are only referenced through the lookup table.
+    let address_lookup_table = trezoa_message::AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: vec![Pubkey::new_unique(), bob, carol],
+    };
+
+    let result = mollusk.process_and_validate_transaction_instructions_with_lookup_tables(
+        &[
+            trezoa_system_interface::instruction::transfer(&alice, &bob, transfer_amount),
+            trezoa_system_interface::instruction::transfer(&alice, &carol, transfer_amount),
+        ],
+        &[
+            (alice, system_account_with_lamports(10_000)),
+            (bob, system_account_with_lamports(0)),
+            (carol, system_account_with_lamports(0)),
+        ],
+        &[address_lookup_table],
+        &[
+            Check::success(),
+            Check::account(&alice)
+                .lamports(10_000 - 2 * transfer_amount)
+                .build(),
+            Check::account(&bob).lamports(transfer_amount).build(),
+            Check::account(&carol).lamports(transfer_amount).build(),
+        ],
+    );
+
+    // The transfers reached Bob and Carol through the lookup table, rather
+    // than the message's static account keys.
+    let Some(trezoa_message::SanitizedMessage::V0(message)) = result.message else {
+        panic!("expected a v0 message");
+    };
+    assert!(!message.message.account_keys.contains(&bob));
+    assert!(!message.message.account_keys.contains(&carol));
+    assert_eq!(message.loaded_addresses.writable, vec![bob, carol]);
+    assert!(message.loaded_addresses.readonly.is_empty());
+}
+
+#[test]
+fn test_fee_payer_deduction() {
+    let mut mollusk = Mollusk::default();
+
+    let payer = Pubkey::new_unique();
+    mollusk.fee_model = Some(FeeModel::default().fee_payer(payer));
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let transfer = trezoa_system_interface::instruction::transfer(&sender, &recipient, 1_000);
+    let payer_lamports = 10_000_000;
+
+    // Both the payer and the sender sign.
+    let fee = 2 * 5_000;
+
+    mollusk.process_and_validate_transaction_instructions(
+        &[transfer.clone()],
+        &[
+            (payer, system_account_with_lamports(payer_lamports)),
+            (sender, system_account_with_lamports(1_000)),
+            (recipient, system_account_with_lamports(0)),
+        ],
+        &[
+            Check::success(),
+            Check::account(&payer)
+                .lamports(payer_lamports - fee)
+                .build(),
+            Check::account(&sender).lamports(0).build(),
+            Check::account(&recipient).lamports(1_000).build(),
+        ],
+    );
+
+    // The fee is charged even if execution fails.
+    let accounts = [
+        (payer, system_account_with_lamports(payer_lamports)),
+        (sender, system_account_with_lamports(0)),
+        (recipient, system_account_with_lamports(0)),
+    ];
+    let result = mollusk.process_transaction_instructions(&[transfer.clone()], &accounts);
+    assert!(result.program_result.is_err());
+    assert_eq!(
+        result.resulting_accounts[0],
+        (payer, system_account_with_lamports(payer_lamports - fee)),
+    );
+    assert_eq!(result.resulting_accounts[1..], accounts[1..]);
+
+    // A fee payer which can't pay fails the transaction before anything
+    // executes.
+    let accounts = [
+        (payer, system_account_with_lamports(fee - 1)),
+        (sender, system_account_with_lamports(1_000)),
+        (recipient, system_account_with_lamports(0)),
+    ];
+    let result = mollusk.process_transaction_instructions(&[transfer.clone()], &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::InsufficientFundsForFee)
+    );
+    assert_eq!(result.resulting_accounts, accounts.to_vec());
+
+    // The error is surfaced as is, rather than as an instruction error no
+    // program raised.
+    let config = Config::default().panic(false);
+    assert!(result.run_checks(
+        &[Check::transaction_err(
+            TransactionError::InsufficientFundsForFee
+        )],
+        &config,
+        &mollusk,
+    ));
+    assert!(!result.run_checks(
+        &[Check::instruction_err(InstructionError::InsufficientFunds)],
+        &config,
+        &mollusk,
+    ));
+
+    // So does one which isn't owned by the System program.
+    let accounts = [
+        (
+            payer,
+            Account::new(payer_lamports, 0, &Pubkey::new_unique()),
+        ),
+        (sender, system_account_with_lamports(1_000)),
+        (recipient, system_account_with_lamports(0)),
+    ];
+    let result = mollusk.process_transaction_instructions(&[transfer], &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::InvalidAccountForFee)
+    );
+}
+
+#[test]
+fn test_prioritization_fee() {
+    let mut mollusk = Mollusk::default();
+
+    let payer = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    mollusk.fee_model = Some(FeeModel::default().fee_payer(payer));
+
+    let instructions = [
+        set_compute_unit_limit(10_000),
+        set_compute_unit_price(1_500_000),
+        trezoa_system_interface::instruction::transfer(&payer, &recipient, 1_000),
+    ];
+    let accounts = [
+        (payer, system_account_with_lamports(10_000_000)),
+        (recipient, system_account_with_lamports(0)),
+    ];
+
+    // The requested limit of 10,000 compute units is charged at 1.5
+    // lamports each, on top of the signature fee.
+    let signature_fee = 5_000;
+    let prioritization_fee = 15_000;
+    mollusk.process_and_validate_transaction_instructions(
+        &instructions,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&payer)
+                .lamports(10_000_000 - 1_000 - signature_fee - prioritization_fee)
+                .build(),
+        ],
+    );
+
+    mollusk.fee_model = Some(
+        FeeModel::default()
+            .fee_payer(payer)
+            .prioritization_fee(false),
+    );
+    mollusk.process_and_validate_transaction_instructions(
+        &instructions,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&payer)
+                .lamports(10_000_000 - 1_000 - signature_fee)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn test_per_instruction_summaries() {
+    let mollusk = Mollusk::default();
+
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let carol = Pubkey::new_unique();
+
+    let system_program = trezoa_sdk_ids::system_program::id();
+    let result = mollusk.process_transaction_instructions(
+        &[
+            set_compute_unit_limit(10_000),
+            trezoa_system_interface::instruction::transfer(&alice, &bob, 100),
+            trezoa_system_interface::instruction::transfer(&bob, &carol, 999_999),
+            trezoa_system_interface::instruction::transfer(&alice, &carol, 50),
+        ],
+        &[
+            (alice, system_account_with_lamports(1_000_000)),
+            (bob, system_account_with_lamports(0)),
+            (carol, system_account_with_lamports(0)),
+        ],
+    );
+    assert!(result.program_result.is_err());
+
+    // The last transfer isn't executed, so it isn't summarized.
+    let [compute_budget, transfer, failed_transfer] = result.per_instruction.as_slice() else {
+        panic!("expected 3 summaries, got {}", result.per_instruction.len());
+    };
+
+    assert_eq!(
+        compute_budget.program_id,
+        trezoa_sdk_ids::compute_budget::id()
+    );
+    assert!(compute_budget.program_result.is_ok());
+    assert_eq!(compute_budget.compute_units_consumed, 0);
+    assert!(compute_budget.logs.is_empty());
+
+    assert_eq!(transfer.program_id, system_program);
+    assert!(transfer.program_result.is_ok());
+    assert!(transfer.compute_units_consumed > 0);
+    assert_eq!(
+        transfer.logs.first(),
+        Some(&format!("Program {} invoke [1]", system_program))
+    );
+
+    assert_eq!(failed_transfer.program_id, system_program);
+    assert!(failed_transfer.program_result.is_err());
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::InstructionError(
+            2,
+            failed_transfer.raw_result.clone().unwrap_err(),
+        )),
+    );
+    assert!(failed_transfer
+        .logs
+        .last()
+        .is_some_and(|log| log.contains("failed")));
+
+    assert_eq!(
+        transfer.compute_units_consumed + failed_transfer.compute_units_consumed,
+        result.compute_units_consumed
+    );
+    assert_eq!(
+        [transfer.logs.clone(), failed_transfer.logs.clone()].concat(),
+        result.logs
+    );
+}
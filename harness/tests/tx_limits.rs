@@ -0,0 +1,129 @@
+use {
+    mollusk_svm::{
+        result::{types::TransactionProgramResult, Check},
+        tx_limits::TransactionLimits,
+        Mollusk,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
+};
+
+fn transfer_accounts(sender: &Pubkey, recipient: &Pubkey) -> Vec<(Pubkey, Account)> {
+    let system_program = trezoa_sdk_ids::system_program::id();
+    vec![
+        (*sender, Account::new(100_000_000, 0, &system_program)),
+        (*recipient, Account::new(100_000_000, 0, &system_program)),
+    ]
+}
+
+#[test]
+fn test_tx_limits_transaction_size() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    // Far more data than fits in a packet.
+    let mut transfer = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    transfer.data.resize(4096, 0);
+
+    let mollusk = Mollusk::default();
+    let result = mollusk.process_transaction_instructions(
+        &[transfer.clone()],
+        &transfer_accounts(&sender, &recipient),
+    );
+    assert!(result.compute_units_consumed > 0);
+
+    let mut mollusk = mollusk;
+    mollusk.enforce_tx_limits = Some(TransactionLimits::default());
+    let result = mollusk
+        .process_transaction_instructions(&[transfer], &transfer_accounts(&sender, &recipient));
+    assert_eq!(result.raw_result, Err(TransactionError::SanitizeFailure));
+    assert_eq!(
+        result.program_result,
+        TransactionProgramResult::TransactionError(TransactionError::SanitizeFailure)
+    );
+    assert_eq!(result.compute_units_consumed, 0);
+}
+
+#[test]
+fn test_tx_limits_account_keys() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let mut accounts = transfer_accounts(&sender, &recipient);
+    let mut transfer = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    for _ in 0..8 {
+        let key = Pubkey::new_unique();
+        transfer
+            .accounts
+            .push(AccountMeta::new_readonly(key, false));
+        accounts.push((key, Account::default()));
+    }
+
+    let mut mollusk = Mollusk::default();
+    mollusk.enforce_tx_limits = Some(TransactionLimits::default());
+    let result = mollusk.process_transaction_instructions(&[transfer.clone()], &accounts);
+    assert!(result.program_result.is_ok());
+
+    mollusk.enforce_tx_limits = Some(TransactionLimits::default().max_account_keys(8));
+    let result = mollusk.process_and_validate_transaction_instructions(
+        &[transfer],
+        &accounts,
+        &[Check::transaction_err(
+            TransactionError::TooManyAccountLocks,
+        )],
+    );
+    assert_eq!(result.resulting_accounts, accounts);
+}
+
+#[test]
+fn test_tx_limits_loaded_accounts_data_size() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let large = Pubkey::new_unique();
+
+    let mut accounts = transfer_accounts(&sender, &recipient);
+    accounts.push((
+        large,
+        Account::new(100_000_000, 10_000, &Pubkey::new_unique()),
+    ));
+
+    let mut transfer = trezoa_system_interface::instruction::transfer(&sender, &recipient, 42);
+    transfer
+        .accounts
+        .push(AccountMeta::new_readonly(large, false));
+
+    let mut mollusk = Mollusk::default();
+    mollusk.enforce_tx_limits =
+        Some(TransactionLimits::default().max_loaded_accounts_data_size(5_000));
+    let result = mollusk.process_transaction_instructions(&[transfer.clone()], &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::MaxLoadedAccountsDataSizeExceeded)
+    );
+
+    // A transaction can request a lower limit than the configured one.
+    let request_limit = |bytes: u32| {
+        let mut data = vec![4];
+        data.extend_from_slice(&bytes.to_le_bytes());
+        Instruction::new_with_bytes(trezoa_sdk_ids::compute_budget::id(), &data, vec![])
+    };
+
+    mollusk.enforce_tx_limits = Some(TransactionLimits::default());
+    let result = mollusk.process_transaction_instructions(&[transfer.clone()], &accounts);
+    assert!(result.program_result.is_ok());
+
+    let result = mollusk
+        .process_transaction_instructions(&[request_limit(5_000), transfer.clone()], &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::MaxLoadedAccountsDataSizeExceeded)
+    );
+
+    let result = mollusk.process_transaction_instructions(&[request_limit(0), transfer], &accounts);
+    assert_eq!(
+        result.raw_result,
+        Err(TransactionError::InvalidLoadedAccountsDataSizeLimit)
+    );
+}
@@ -0,0 +1,112 @@
+//! Checks on the state of TPL Token and Token-2022 accounts.
+//!
+//! Each check unpacks the resulting account and compares a single field,
+//! reporting mismatches like any other `Check`.
+//!
+//! ```rust,ignore
+//! use mollusk_svm_programs_token::checks::TokenCheck;
+//!
+//! mollusk.process_and_validate_instruction(
+//!     &transfer,
+//!     &accounts,
+//!     &[
+//!         Check::success(),
+//!         TokenCheck::balance(&destination, 1_000),
+//!         TokenCheck::mint_supply(&mint, 1_000_000),
+//!     ],
+//! );
+//! ```
+//!
+//! Token-2022 accounts are checked on their base state, so extensions are
+//! ignored.
+
+use {
+    mollusk_svm::result::{
+        primitives::{compare, fail},
+        Check, Config,
+    },
+    trezoa_account::Account,
+    trezoa_program_pack::{IsInitialized, Pack},
+    trezoa_pubkey::Pubkey,
+    tpl_token_interface::state::{Account as TokenAccount, AccountState, Mint},
+};
+
+/// Unpack the base state of a token account or mint, reporting a failure if
+/// the account isn't an initialized one.
+fn unpack<T: Pack + IsInitialized>(config: &Config, check: &str, account: &Account) -> Option<T> {
+    let state = account
+        .data
+        .get(..T::LEN)
+        .and_then(|data| T::unpack(data).ok());
+    if state.is_none() {
+        fail(
+            config,
+            format!(
+                "CHECK FAILED: {}\n  Account data isn't initialized token state",
+                check
+            ),
+        );
+    }
+    state
+}
+
+/// Checks on the state of token accounts and mints.
+pub struct TokenCheck;
+
+impl TokenCheck {
+    /// Check the token balance of a token account.
+    pub fn balance<'a>(token_account: &Pubkey, amount: u64) -> Check<'a> {
+        Check::account_with(token_account, move |config, account| {
+            unpack::<TokenAccount>(config, "token_balance", account)
+                .is_some_and(|state| compare(config, "token_balance", amount, state.amount))
+        })
+    }
+
+    /// Check the total supply of a mint.
+    pub fn mint_supply<'a>(mint: &Pubkey, supply: u64) -> Check<'a> {
+        Check::account_with(mint, move |config, account| {
+            unpack::<Mint>(config, "mint_supply", account)
+                .is_some_and(|state| compare(config, "mint_supply", supply, state.supply))
+        })
+    }
+
+    /// Check the owner of a token account.
+    pub fn owner<'a>(token_account: &Pubkey, owner: &Pubkey) -> Check<'a> {
+        let owner = *owner;
+        Check::account_with(token_account, move |config, account| {
+            unpack::<TokenAccount>(config, "token_owner", account)
+                .is_some_and(|state| compare(config, "token_owner", owner, state.owner))
+        })
+    }
+
+    /// Check the delegate of a token account, and the amount delegated to
+    /// it. Pass `None` to check that the account has no delegate.
+    pub fn delegate<'a>(
+        token_account: &Pubkey,
+        delegate: Option<&Pubkey>,
+        delegated_amount: u64,
+    ) -> Check<'a> {
+        let delegate = delegate.copied();
+        Check::account_with(token_account, move |config, account| {
+            unpack::<TokenAccount>(config, "token_delegate", account).is_some_and(|state| {
+                let actual_delegate: Option<Pubkey> = state.delegate.into();
+                let actual_delegated_amount = state.delegated_amount;
+                compare(config, "token_delegate", delegate, actual_delegate)
+                    && compare(
+                        config,
+                        "token_delegated_amount",
+                        delegated_amount,
+                        actual_delegated_amount,
+                    )
+            })
+        })
+    }
+
+    /// Check the state of a token account: initialized or frozen.
+    pub fn state<'a>(token_account: &Pubkey, state: AccountState) -> Check<'a> {
+        Check::account_with(token_account, move |config, account| {
+            unpack::<TokenAccount>(config, "token_state", account)
+                .is_some_and(|actual| compare(config, "token_state", state, actual.state))
+        })
+    }
+}
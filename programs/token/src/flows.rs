@@ -0,0 +1,229 @@
+//! Instruction-building helpers for common TPL Token flows.
+//!
+//! Each helper builds the instructions for a flow against a `MolluskContext`,
+//! storing any accounts the flow needs which the store doesn't have yet, and
+//! returns them as a `TokenFlow`, ready to process as a chain. Token
+//! accounts are created at the owner's associated token address, and
+//! initialized directly with the Token program, so the Associated Token
+//! program isn't needed.
+//!
+//! ```rust,ignore
+//! use mollusk_svm_programs_token::{flows, token};
+//!
+//! let mut mollusk = Mollusk::default();
+//! token::add_program(&mut mollusk);
+//! let context = mollusk.with_context(HashMap::new());
+//!
+//! let mint = flows::setup_mint(&context, 6, &authority).process(&context);
+//! flows::mint_to(&context, &mint, &alice, 1_000, &authority).process(&context);
+//! flows::transfer_checked(&context, &mint, &alice, &bob, 400, 6).process(&context);
+//! ```
+//!
+//! The accounts a flow initializes are only stored when it's processed, so
+//! flows which are built but never processed leave the store untouched.
+//! Flows can be built ahead of time, and combined with `TokenFlow::then`, as
+//! long as they're processed in order.
+
+use {
+    crate::token::ID,
+    mollusk_svm::{account_store::AccountStore, result::InstructionResult, MolluskContext},
+    trezoa_account::Account,
+    trezoa_instruction::Instruction,
+    trezoa_program_pack::Pack,
+    trezoa_pubkey::Pubkey,
+    tpl_token_interface::{
+        instruction::{self, TokenInstruction},
+        state::{Account as TokenAccount, Mint},
+    },
+};
+
+const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    trezoa_pubkey::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// The instructions for a token flow, along with the address of the account
+/// it targets: the mint, or the destination token account.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenFlow {
+    /// The mint, or the destination token account.
+    pub address: Pubkey,
+    /// The empty accounts the flow initializes, stored when the flow is
+    /// processed, unless the store already has them.
+    pub accounts: Vec<(Pubkey, Account)>,
+    /// The instructions to process, in order.
+    pub instructions: Vec<Instruction>,
+}
+
+impl TokenFlow {
+    /// Append the instructions of another flow. The address of the other
+    /// flow is kept.
+    ///
+    /// Accounts already initialized by this flow aren't initialized again,
+    /// so flows built ahead of time for the same new token account can be
+    /// combined.
+    pub fn then(mut self, other: TokenFlow) -> Self {
+        let initialized = self
+            .accounts
+            .iter()
+            .map(|(address, _)| *address)
+            .collect::<Vec<_>>();
+        self.accounts.extend(
+            other
+                .accounts
+                .into_iter()
+                .filter(|(address, _)| !initialized.contains(address)),
+        );
+        self.instructions.extend(
+            other
+                .instructions
+                .into_iter()
+                .filter(|ix| !initializes_any(ix, &initialized)),
+        );
+        self.address = other.address;
+        self
+    }
+
+    /// Process the flow as an instruction chain, panicking if it fails.
+    /// Returns the address of the account the flow targets.
+    pub fn process<AS: AccountStore>(&self, context: &MolluskContext<AS>) -> Pubkey {
+        let result = self.try_process(context);
+        if let Err(err) = &result.raw_result {
+            panic!("Token flow for {} failed: {:?}", self.address, err);
+        }
+        self.address
+    }
+
+    /// Process the flow as an instruction chain, returning the result.
+    pub fn try_process<AS: AccountStore>(&self, context: &MolluskContext<AS>) -> InstructionResult {
+        {
+            let mut store = context.account_store.borrow_mut();
+            for (address, account) in &self.accounts {
+                if store.get_account(address).is_none() {
+                    store.store_account(*address, account.clone());
+                }
+            }
+        }
+        context.process_instruction_chain(&self.instructions)
+    }
+}
+
+/// The token account of `owner` for `mint`, at the owner's associated token
+/// address.
+pub fn token_account_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Whether the instruction initializes a mint or token account at one of the
+/// provided addresses.
+fn initializes_any(instruction: &Instruction, addresses: &[Pubkey]) -> bool {
+    instruction.program_id == ID
+        && matches!(
+            TokenInstruction::unpack(&instruction.data),
+            Ok(TokenInstruction::InitializeMint2 { .. }
+                | TokenInstruction::InitializeAccount3 { .. })
+        )
+        && instruction
+            .accounts
+            .first()
+            .is_some_and(|meta| addresses.contains(&meta.pubkey))
+}
+
+/// An empty, rent-exempt account owned by the Token program, to be
+/// initialized by a flow.
+fn uninitialized_account<AS: AccountStore>(context: &MolluskContext<AS>, len: usize) -> Account {
+    let lamports = context.mollusk.sysvars.rent.minimum_balance(len);
+    Account::new(lamports, len, &ID)
+}
+
+/// Find the token account of `owner` for `mint`, returning its address, along
+/// with the account and the instruction initializing it if it isn't
+/// initialized yet.
+fn ensure_token_account<AS: AccountStore>(
+    context: &MolluskContext<AS>,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, Option<((Pubkey, Account), Instruction)>) {
+    let address = token_account_address(owner, mint);
+    let initialized = context
+        .account_store
+        .borrow()
+        .get_account(&address)
+        .is_some_and(|account| account.owner == ID && TokenAccount::unpack(&account.data).is_ok());
+    if initialized {
+        return (address, None);
+    }
+    let account = uninitialized_account(context, TokenAccount::LEN);
+    let initialize = instruction::initialize_account3(&ID, &address, mint, owner).unwrap();
+    (address, Some(((address, account), initialize)))
+}
+
+/// Create a mint with the provided decimals and mint authority, at a new
+/// address. The flow's address is the mint.
+pub fn setup_mint<AS: AccountStore>(
+    context: &MolluskContext<AS>,
+    decimals: u8,
+    authority: &Pubkey,
+) -> TokenFlow {
+    let mint = Pubkey::new_unique();
+    let account = uninitialized_account(context, Mint::LEN);
+    let initialize = instruction::initialize_mint2(&ID, &mint, authority, None, decimals).unwrap();
+    TokenFlow {
+        address: mint,
+        accounts: vec![(mint, account)],
+        instructions: vec![initialize],
+    }
+}
+
+/// Mint `amount` tokens to the token account of `owner`, creating it if it
+/// doesn't exist. The flow's address is the token account.
+pub fn mint_to<AS: AccountStore>(
+    context: &MolluskContext<AS>,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    authority: &Pubkey,
+) -> TokenFlow {
+    let (destination, initialize) = ensure_token_account(context, mint, owner);
+    let mint_to = instruction::mint_to(&ID, mint, &destination, authority, &[], amount).unwrap();
+    let (accounts, initialize) = initialize.into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+    TokenFlow {
+        address: destination,
+        accounts,
+        instructions: initialize.into_iter().chain([mint_to]).collect(),
+    }
+}
+
+/// Transfer `amount` tokens from the token account of `source_owner` to the
+/// token account of `destination_owner`, creating the latter if it doesn't
+/// exist. The flow's address is the destination token account.
+pub fn transfer_checked<AS: AccountStore>(
+    context: &MolluskContext<AS>,
+    mint: &Pubkey,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> TokenFlow {
+    let source = token_account_address(source_owner, mint);
+    let (destination, initialize) = ensure_token_account(context, mint, destination_owner);
+    let transfer = instruction::transfer_checked(
+        &ID,
+        &source,
+        mint,
+        &destination,
+        source_owner,
+        &[],
+        amount,
+        decimals,
+    )
+    .unwrap();
+    let (accounts, initialize) = initialize.into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+    TokenFlow {
+        address: destination,
+        accounts,
+        instructions: initialize.into_iter().chain([transfer]).collect(),
+    }
+}
@@ -1,6 +1,9 @@
 //! Last updated at mainnet-beta slot height: 347196212
 #[cfg(feature = "associated-token")]
 pub mod associated_token;
+pub mod checks;
+#[cfg(feature = "token")]
+pub mod flows;
 #[cfg(feature = "token")]
 pub mod token;
 #[cfg(feature = "token-2022")]
@@ -1,3 +1,6 @@
+mod builder;
+
+pub use builder::{Extension, MintBuilder, TokenAccountBuilder, TransferFee};
 use {
     mollusk_svm::Mollusk,
     trezoa_account::Account,
@@ -0,0 +1,362 @@
+//! Builders for Token-2022 mint and token accounts with extensions.
+//!
+//! Token-2022 accounts with extensions use the following layout:
+//!
+//! * The base state (`Mint` or `Account`), padded to `Account::LEN` bytes.
+//! * A single `AccountType` byte.
+//! * A TLV entry for each extension: a `u16` extension type, a `u16` length,
+//!   and the extension's value.
+//!
+//! Accounts without extensions are just the base state.
+
+use {
+    super::ID,
+    trezoa_account::Account,
+    trezoa_program_pack::Pack,
+    trezoa_pubkey::Pubkey,
+    trezoa_rent::Rent,
+    tpl_token_interface::state::{Account as TokenAccount, AccountState, Mint, Multisig},
+};
+
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+// `ExtensionType` discriminators.
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_TRANSFER_FEE_AMOUNT: u16 = 2;
+const EXTENSION_MINT_CLOSE_AUTHORITY: u16 = 3;
+const EXTENSION_DEFAULT_ACCOUNT_STATE: u16 = 6;
+const EXTENSION_IMMUTABLE_OWNER: u16 = 7;
+const EXTENSION_MEMO_TRANSFER: u16 = 8;
+const EXTENSION_NON_TRANSFERABLE: u16 = 9;
+const EXTENSION_PERMANENT_DELEGATE: u16 = 12;
+const EXTENSION_NON_TRANSFERABLE_ACCOUNT: u16 = 13;
+const EXTENSION_METADATA_POINTER: u16 = 18;
+
+const TLV_HEADER_LEN: usize = 4;
+
+/// A transfer fee schedule, as stored in the `TransferFeeConfig` extension.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferFee {
+    /// First epoch where the transfer fee takes effect.
+    pub epoch: u64,
+    /// Maximum fee assessed on transfers, in token amount.
+    pub maximum_fee: u64,
+    /// Amount of transfer collected as fees, in basis points.
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    fn pack_into(&self, data: &mut Vec<u8>) {
+        data.extend_from_slice(&self.epoch.to_le_bytes());
+        data.extend_from_slice(&self.maximum_fee.to_le_bytes());
+        data.extend_from_slice(&self.transfer_fee_basis_points.to_le_bytes());
+    }
+}
+
+/// A Token-2022 extension.
+///
+/// Mint extensions should only be added to mints, and account extensions
+/// only to token accounts. The builders don't validate this, so tests can
+/// construct malformed accounts on purpose.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Extension {
+    /// Mint: transfer fee configuration.
+    TransferFeeConfig {
+        transfer_fee_config_authority: Option<Pubkey>,
+        withdraw_withheld_authority: Option<Pubkey>,
+        withheld_amount: u64,
+        older_transfer_fee: TransferFee,
+        newer_transfer_fee: TransferFee,
+    },
+    /// Account: withheld transfer fees. Required on token accounts for mints
+    /// with `TransferFeeConfig`.
+    TransferFeeAmount { withheld_amount: u64 },
+    /// Mint: authority allowed to close the mint.
+    MintCloseAuthority { close_authority: Option<Pubkey> },
+    /// Mint: the state of newly initialized token accounts.
+    DefaultAccountState { state: AccountState },
+    /// Account: the owner can't be reassigned.
+    ImmutableOwner,
+    /// Account: incoming transfers must be preceded by a memo.
+    MemoTransfer {
+        require_incoming_transfer_memos: bool,
+    },
+    /// Mint: tokens can't be transferred.
+    NonTransferable,
+    /// Mint: delegate with unlimited authority over all token accounts.
+    PermanentDelegate { delegate: Option<Pubkey> },
+    /// Account: holds tokens of a `NonTransferable` mint.
+    NonTransferableAccount,
+    /// Mint: pointer to the mint's metadata.
+    MetadataPointer {
+        authority: Option<Pubkey>,
+        metadata_address: Option<Pubkey>,
+    },
+    /// Any other extension, as a raw extension type and value.
+    Other { extension_type: u16, value: Vec<u8> },
+}
+
+fn pack_optional_pubkey(pubkey: &Option<Pubkey>, data: &mut Vec<u8>) {
+    // `OptionalNonZeroPubkey`: the zero pubkey means `None`.
+    data.extend_from_slice(pubkey.unwrap_or_default().as_ref());
+}
+
+impl Extension {
+    /// The extension's `ExtensionType` discriminator.
+    pub fn extension_type(&self) -> u16 {
+        match self {
+            Self::TransferFeeConfig { .. } => EXTENSION_TRANSFER_FEE_CONFIG,
+            Self::TransferFeeAmount { .. } => EXTENSION_TRANSFER_FEE_AMOUNT,
+            Self::MintCloseAuthority { .. } => EXTENSION_MINT_CLOSE_AUTHORITY,
+            Self::DefaultAccountState { .. } => EXTENSION_DEFAULT_ACCOUNT_STATE,
+            Self::ImmutableOwner => EXTENSION_IMMUTABLE_OWNER,
+            Self::MemoTransfer { .. } => EXTENSION_MEMO_TRANSFER,
+            Self::NonTransferable => EXTENSION_NON_TRANSFERABLE,
+            Self::PermanentDelegate { .. } => EXTENSION_PERMANENT_DELEGATE,
+            Self::NonTransferableAccount => EXTENSION_NON_TRANSFERABLE_ACCOUNT,
+            Self::MetadataPointer { .. } => EXTENSION_METADATA_POINTER,
+            Self::Other { extension_type, .. } => *extension_type,
+        }
+    }
+
+    /// The length of the extension's value, excluding the TLV header.
+    pub fn value_len(&self) -> usize {
+        match self {
+            Self::TransferFeeConfig { .. } => 108,
+            Self::TransferFeeAmount { .. } => 8,
+            Self::MintCloseAuthority { .. } => 32,
+            Self::DefaultAccountState { .. } => 1,
+            Self::ImmutableOwner => 0,
+            Self::MemoTransfer { .. } => 1,
+            Self::NonTransferable => 0,
+            Self::PermanentDelegate { .. } => 32,
+            Self::NonTransferableAccount => 0,
+            Self::MetadataPointer { .. } => 64,
+            Self::Other { value, .. } => value.len(),
+        }
+    }
+
+    fn pack_value_into(&self, data: &mut Vec<u8>) {
+        match self {
+            Self::TransferFeeConfig {
+                transfer_fee_config_authority,
+                withdraw_withheld_authority,
+                withheld_amount,
+                older_transfer_fee,
+                newer_transfer_fee,
+            } => {
+                pack_optional_pubkey(transfer_fee_config_authority, data);
+                pack_optional_pubkey(withdraw_withheld_authority, data);
+                data.extend_from_slice(&withheld_amount.to_le_bytes());
+                older_transfer_fee.pack_into(data);
+                newer_transfer_fee.pack_into(data);
+            }
+            Self::TransferFeeAmount { withheld_amount } => {
+                data.extend_from_slice(&withheld_amount.to_le_bytes());
+            }
+            Self::MintCloseAuthority { close_authority } => {
+                pack_optional_pubkey(close_authority, data);
+            }
+            Self::DefaultAccountState { state } => data.push(*state as u8),
+            Self::ImmutableOwner | Self::NonTransferable | Self::NonTransferableAccount => {}
+            Self::MemoTransfer {
+                require_incoming_transfer_memos,
+            } => data.push(u8::from(*require_incoming_transfer_memos)),
+            Self::PermanentDelegate { delegate } => pack_optional_pubkey(delegate, data),
+            Self::MetadataPointer {
+                authority,
+                metadata_address,
+            } => {
+                pack_optional_pubkey(authority, data);
+                pack_optional_pubkey(metadata_address, data);
+            }
+            Self::Other { value, .. } => data.extend_from_slice(value),
+        }
+    }
+}
+
+/// Compute the length of a Token-2022 account with the provided base state
+/// length and extensions.
+fn account_len(base_len: usize, extensions: &[Extension]) -> usize {
+    if extensions.is_empty() {
+        return base_len;
+    }
+    let len = TokenAccount::LEN
+        + 1
+        + extensions
+            .iter()
+            .map(|extension| TLV_HEADER_LEN + extension.value_len())
+            .sum::<usize>();
+    // Token-2022 pads accounts which would otherwise be mistaken for a
+    // multisig with an uninitialized extension type.
+    if len == Multisig::LEN {
+        len + std::mem::size_of::<u16>()
+    } else {
+        len
+    }
+}
+
+fn pack_extensions(data: &mut Vec<u8>, account_type: u8, extensions: &[Extension], len: usize) {
+    if extensions.is_empty() {
+        return;
+    }
+    data.resize(TokenAccount::LEN, 0);
+    data.push(account_type);
+    for extension in extensions {
+        data.extend_from_slice(&extension.extension_type().to_le_bytes());
+        data.extend_from_slice(&(extension.value_len() as u16).to_le_bytes());
+        extension.pack_value_into(data);
+    }
+    // Any remaining padding is read as `ExtensionType::Uninitialized`.
+    data.resize(len, 0);
+}
+
+fn rent_exempt_account(data: Vec<u8>, rent: &Rent) -> Account {
+    Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        owner: ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Builder for Token-2022 mint accounts.
+///
+/// ```rust,ignore
+/// use mollusk_svm_programs_token::token2022::{Extension, MintBuilder};
+///
+/// let (mint, mint_account) = MintBuilder::new(Mint {
+///     mint_authority: COption::Some(authority),
+///     decimals: 6,
+///     is_initialized: true,
+///     ..Default::default()
+/// })
+/// .extension(Extension::MetadataPointer {
+///     authority: Some(authority),
+///     metadata_address: Some(metadata),
+/// })
+/// .build();
+/// ```
+pub struct MintBuilder {
+    address: Option<Pubkey>,
+    mint: Mint,
+    extensions: Vec<Extension>,
+    rent: Rent,
+}
+
+impl MintBuilder {
+    /// Create a new builder for the provided base mint state.
+    pub fn new(mint: Mint) -> Self {
+        Self {
+            address: None,
+            mint,
+            extensions: Vec::new(),
+            rent: Rent::default(),
+        }
+    }
+
+    /// Set the mint's address. Defaults to a unique pubkey.
+    pub fn address(mut self, address: &Pubkey) -> Self {
+        self.address = Some(*address);
+        self
+    }
+
+    /// Add an extension to the mint.
+    pub fn extension(mut self, extension: Extension) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// Set the rent used to compute the rent-exempt balance.
+    pub fn rent(mut self, rent: Rent) -> Self {
+        self.rent = rent;
+        self
+    }
+
+    /// The length of the mint account's data.
+    pub fn data_len(&self) -> usize {
+        account_len(Mint::LEN, &self.extensions)
+    }
+
+    /// Build the rent-exempt mint account.
+    pub fn build(self) -> (Pubkey, Account) {
+        let len = self.data_len();
+        let mut data = vec![0u8; Mint::LEN];
+        Mint::pack(self.mint, &mut data).unwrap();
+        pack_extensions(&mut data, ACCOUNT_TYPE_MINT, &self.extensions, len);
+
+        let address = self.address.unwrap_or_else(Pubkey::new_unique);
+        (address, rent_exempt_account(data, &self.rent))
+    }
+}
+
+/// Builder for Token-2022 token accounts.
+///
+/// ```rust,ignore
+/// use mollusk_svm_programs_token::token2022::{Extension, TokenAccountBuilder};
+///
+/// let (token_account, account) = TokenAccountBuilder::new(TokenAccount {
+///     mint,
+///     owner,
+///     amount: 1_000,
+///     state: AccountState::Initialized,
+///     ..Default::default()
+/// })
+/// .extension(Extension::ImmutableOwner)
+/// .extension(Extension::TransferFeeAmount { withheld_amount: 0 })
+/// .build();
+/// ```
+pub struct TokenAccountBuilder {
+    address: Option<Pubkey>,
+    token_account: TokenAccount,
+    extensions: Vec<Extension>,
+    rent: Rent,
+}
+
+impl TokenAccountBuilder {
+    /// Create a new builder for the provided base token account state.
+    pub fn new(token_account: TokenAccount) -> Self {
+        Self {
+            address: None,
+            token_account,
+            extensions: Vec::new(),
+            rent: Rent::default(),
+        }
+    }
+
+    /// Set the token account's address. Defaults to a unique pubkey.
+    pub fn address(mut self, address: &Pubkey) -> Self {
+        self.address = Some(*address);
+        self
+    }
+
+    /// Add an extension to the token account.
+    pub fn extension(mut self, extension: Extension) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// Set the rent used to compute the rent-exempt balance.
+    pub fn rent(mut self, rent: Rent) -> Self {
+        self.rent = rent;
+        self
+    }
+
+    /// The length of the token account's data.
+    pub fn data_len(&self) -> usize {
+        account_len(TokenAccount::LEN, &self.extensions)
+    }
+
+    /// Build the rent-exempt token account.
+    pub fn build(self) -> (Pubkey, Account) {
+        let len = self.data_len();
+        let mut data = vec![0u8; TokenAccount::LEN];
+        TokenAccount::pack(self.token_account, &mut data).unwrap();
+        pack_extensions(&mut data, ACCOUNT_TYPE_ACCOUNT, &self.extensions, len);
+
+        let address = self.address.unwrap_or_else(Pubkey::new_unique);
+        (address, rent_exempt_account(data, &self.rent))
+    }
+}
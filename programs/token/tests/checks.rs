@@ -0,0 +1,168 @@
+#![cfg(feature = "token")]
+
+use {
+    mollusk_svm::{
+        result::{Check, Config, InstructionResult},
+        Mollusk,
+    },
+    mollusk_svm_programs_token::{checks::TokenCheck, token},
+    trezoa_account::Account,
+    trezoa_program_option::COption,
+    trezoa_pubkey::Pubkey,
+    tpl_token_interface::{
+        instruction,
+        state::{Account as TokenAccount, AccountState, Mint},
+    },
+};
+
+struct Keys {
+    mint: Pubkey,
+    authority: Pubkey,
+    token_account: Pubkey,
+    owner: Pubkey,
+    delegate: Pubkey,
+}
+
+fn mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::default();
+    token::add_program(&mut mollusk);
+    mollusk
+}
+
+// Mint 1,000 tokens to a token account, delegate 300 of them, then freeze
+// the account, checking each step along the way.
+fn mint_approve_and_freeze(mollusk: &Mollusk) -> (Keys, InstructionResult) {
+    let keys = Keys {
+        mint: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        token_account: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        delegate: Pubkey::new_unique(),
+    };
+
+    let mint_to = instruction::mint_to(
+        &token::ID,
+        &keys.mint,
+        &keys.token_account,
+        &keys.authority,
+        &[],
+        1_000,
+    )
+    .unwrap();
+    let approve = instruction::approve(
+        &token::ID,
+        &keys.token_account,
+        &keys.delegate,
+        &keys.owner,
+        &[],
+        300,
+    )
+    .unwrap();
+    let freeze = instruction::freeze_account(
+        &token::ID,
+        &keys.token_account,
+        &keys.mint,
+        &keys.authority,
+        &[],
+    )
+    .unwrap();
+
+    let accounts = [
+        (
+            keys.mint,
+            token::create_account_for_mint(Mint {
+                mint_authority: COption::Some(keys.authority),
+                supply: 0,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::Some(keys.authority),
+            }),
+        ),
+        (
+            keys.token_account,
+            token::create_account_for_token_account(TokenAccount {
+                mint: keys.mint,
+                owner: keys.owner,
+                state: AccountState::Initialized,
+                ..Default::default()
+            }),
+        ),
+        (keys.authority, Account::default()),
+        (keys.owner, Account::default()),
+        (keys.delegate, Account::default()),
+    ];
+
+    let result = mollusk.process_and_validate_instruction_chain(
+        &[
+            (
+                &mint_to,
+                &[
+                    Check::success(),
+                    TokenCheck::balance(&keys.token_account, 1_000),
+                    TokenCheck::mint_supply(&keys.mint, 1_000),
+                    TokenCheck::owner(&keys.token_account, &keys.owner),
+                    TokenCheck::delegate(&keys.token_account, None, 0),
+                    TokenCheck::state(&keys.token_account, AccountState::Initialized),
+                ],
+            ),
+            (
+                &approve,
+                &[
+                    Check::success(),
+                    TokenCheck::delegate(&keys.token_account, Some(&keys.delegate), 300),
+                ],
+            ),
+            (
+                &freeze,
+                &[
+                    Check::success(),
+                    TokenCheck::state(&keys.token_account, AccountState::Frozen),
+                ],
+            ),
+        ],
+        &accounts,
+    );
+    (keys, result)
+}
+
+#[test]
+fn test_token_checks() {
+    let mollusk = mollusk();
+    let (keys, result) = mint_approve_and_freeze(&mollusk);
+
+    // The final state still passes every check.
+    assert!(result.run_checks(
+        &[
+            TokenCheck::balance(&keys.token_account, 1_000),
+            TokenCheck::mint_supply(&keys.mint, 1_000),
+            TokenCheck::owner(&keys.token_account, &keys.owner),
+            TokenCheck::delegate(&keys.token_account, Some(&keys.delegate), 300),
+            TokenCheck::state(&keys.token_account, AccountState::Frozen),
+        ],
+        &Config::default(),
+        &mollusk,
+    ));
+}
+
+#[test]
+fn test_failing_token_checks() {
+    let mollusk = mollusk();
+    let config = Config::default().panic(false);
+    let (keys, result) = mint_approve_and_freeze(&mollusk);
+
+    let failing_checks = [
+        TokenCheck::balance(&keys.token_account, 999),
+        TokenCheck::mint_supply(&keys.mint, 1_001),
+        TokenCheck::owner(&keys.token_account, &keys.delegate),
+        TokenCheck::delegate(&keys.token_account, None, 0),
+        TokenCheck::delegate(&keys.token_account, Some(&keys.delegate), 299),
+        TokenCheck::delegate(&keys.token_account, Some(&keys.owner), 300),
+        TokenCheck::state(&keys.token_account, AccountState::Initialized),
+        // Not token state at all.
+        TokenCheck::balance(&keys.mint, 1_000),
+        TokenCheck::mint_supply(&keys.owner, 1_000),
+    ];
+    for check in failing_checks {
+        assert!(!result.run_checks(&[check], &config, &mollusk));
+    }
+}
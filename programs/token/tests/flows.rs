@@ -0,0 +1,131 @@
+#![cfg(feature = "token")]
+
+use {
+    mollusk_svm::{Mollusk, MolluskContext},
+    mollusk_svm_programs_token::{flows, token},
+    trezoa_account::Account,
+    trezoa_program_option::COption,
+    trezoa_program_pack::Pack,
+    trezoa_pubkey::Pubkey,
+    tpl_token_interface::state::{Account as TokenAccount, Mint},
+    std::collections::HashMap,
+};
+
+const DECIMALS: u8 = 6;
+
+fn context() -> MolluskContext<HashMap<Pubkey, Account>> {
+    let mut mollusk = Mollusk::default();
+    token::add_program(&mut mollusk);
+    mollusk.with_context(HashMap::new())
+}
+
+fn token_balance(context: &MolluskContext<HashMap<Pubkey, Account>>, address: &Pubkey) -> u64 {
+    let store = context.account_store.borrow();
+    TokenAccount::unpack(&store.get(address).unwrap().data)
+        .unwrap()
+        .amount
+}
+
+#[test]
+fn test_flows() {
+    let context = context();
+
+    let authority = Pubkey::new_unique();
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+
+    let mint = flows::setup_mint(&context, DECIMALS, &authority).process(&context);
+    {
+        let store = context.account_store.borrow();
+        let mint = Mint::unpack(&store.get(&mint).unwrap().data).unwrap();
+        assert_eq!(mint.decimals, DECIMALS);
+        assert_eq!(mint.mint_authority, COption::Some(authority));
+    }
+
+    let alice_ata = flows::mint_to(&context, &mint, &alice, 1_000, &authority).process(&context);
+    assert_eq!(alice_ata, flows::token_account_address(&alice, &mint));
+    assert_eq!(token_balance(&context, &alice_ata), 1_000);
+
+    // Alice's token account exists now, so it isn't initialized again.
+    let flow = flows::mint_to(&context, &mint, &alice, 500, &authority);
+    assert_eq!(flow.instructions.len(), 1);
+    assert!(flow.accounts.is_empty());
+    flow.process(&context);
+    assert_eq!(token_balance(&context, &alice_ata), 1_500);
+
+    let bob_ata =
+        flows::transfer_checked(&context, &mint, &alice, &bob, 400, DECIMALS).process(&context);
+    assert_eq!(token_balance(&context, &alice_ata), 1_100);
+    assert_eq!(token_balance(&context, &bob_ata), 400);
+}
+
+#[test]
+fn test_unprocessed_flow_leaves_store_untouched() {
+    let context = context();
+
+    let authority = Pubkey::new_unique();
+    let alice = Pubkey::new_unique();
+
+    let mint = flows::setup_mint(&context, DECIMALS, &authority).process(&context);
+
+    // Building a flow doesn't store the account it initializes.
+    let flow = flows::mint_to(&context, &mint, &alice, 1_000, &authority);
+    let alice_ata = flow.address;
+    assert!(!context.account_store.borrow().contains_key(&alice_ata));
+
+    // So a flow built later still initializes it.
+    flows::mint_to(&context, &mint, &alice, 1_000, &authority).process(&context);
+    assert_eq!(token_balance(&context, &alice_ata), 1_000);
+}
+
+#[test]
+fn test_flow_initializes_empty_account() {
+    let context = context();
+
+    let authority = Pubkey::new_unique();
+    let alice = Pubkey::new_unique();
+
+    let mint = flows::setup_mint(&context, DECIMALS, &authority).process(&context);
+
+    // An empty account at the token account's address, such as one left
+    // behind by a failed flow, is still initialized.
+    let alice_ata = flows::token_account_address(&alice, &mint);
+    let lamports = context
+        .mollusk
+        .sysvars
+        .rent
+        .minimum_balance(TokenAccount::LEN);
+    context.account_store.borrow_mut().insert(
+        alice_ata,
+        Account::new(lamports, TokenAccount::LEN, &token::ID),
+    );
+
+    let flow = flows::mint_to(&context, &mint, &alice, 1_000, &authority);
+    assert_eq!(flow.instructions.len(), 2);
+    flow.process(&context);
+    assert_eq!(token_balance(&context, &alice_ata), 1_000);
+}
+
+#[test]
+fn test_flows_built_ahead_of_time() {
+    let context = context();
+
+    let authority = Pubkey::new_unique();
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+
+    let mint = flows::setup_mint(&context, DECIMALS, &authority).process(&context);
+
+    // Both flows initialize Alice's token account, but combined, it's only
+    // initialized once.
+    let bob_ata = flows::mint_to(&context, &mint, &alice, 1_000, &authority)
+        .then(flows::mint_to(&context, &mint, &alice, 500, &authority))
+        .then(flows::transfer_checked(
+            &context, &mint, &alice, &bob, 300, DECIMALS,
+        ))
+        .process(&context);
+
+    let alice_ata = flows::token_account_address(&alice, &mint);
+    assert_eq!(token_balance(&context, &alice_ata), 1_200);
+    assert_eq!(token_balance(&context, &bob_ata), 300);
+}
@@ -0,0 +1,442 @@
+use {
+    mollusk_svm::{result::Check, Mollusk},
+    mollusk_svm_programs_memo::memo,
+    mollusk_svm_programs_token::token2022::{
+        self, Extension, MintBuilder, TokenAccountBuilder, TransferFee, ID,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::{AccountMeta, Instruction},
+    trezoa_program_option::COption,
+    trezoa_program_pack::Pack,
+    trezoa_pubkey::Pubkey,
+    tpl_token_interface::{
+        instruction::{self, AuthorityType},
+        state::{Account as TokenAccount, AccountState, Mint},
+    },
+};
+
+const DECIMALS: u8 = 6;
+
+// `TokenError` codes of Token-2022.
+const ERROR_IMMUTABLE_OWNER: u32 = 34;
+const ERROR_NO_MEMO: u32 = 36;
+const ERROR_NON_TRANSFERABLE: u32 = 37;
+
+fn mollusk() -> Mollusk {
+    let mut mollusk = Mollusk::default();
+    token2022::add_program(&mut mollusk);
+    mollusk
+}
+
+fn base_mint(supply: u64) -> Mint {
+    Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        supply,
+        decimals: DECIMALS,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+}
+
+fn base_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> TokenAccount {
+    TokenAccount {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }
+}
+
+fn transfer_checked(
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction::transfer_checked(
+        &ID,
+        source,
+        mint,
+        destination,
+        authority,
+        &[],
+        amount,
+        DECIMALS,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_transfer_checked_with_transfer_fee() {
+    let mollusk = mollusk();
+
+    let fee = TransferFee {
+        epoch: 0,
+        maximum_fee: 1_000_000,
+        transfer_fee_basis_points: 100,
+    };
+    let (mint, mint_account) = MintBuilder::new(base_mint(1_000_000))
+        .extension(Extension::TransferFeeConfig {
+            transfer_fee_config_authority: Some(Pubkey::new_unique()),
+            withdraw_withheld_authority: Some(Pubkey::new_unique()),
+            withheld_amount: 0,
+            older_transfer_fee: fee,
+            newer_transfer_fee: fee,
+        })
+        .build();
+
+    let owner = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let build_account = |address: &Pubkey, owner: &Pubkey, amount: u64, withheld_amount: u64| {
+        TokenAccountBuilder::new(base_token_account(&mint, owner, amount))
+            .address(address)
+            .extension(Extension::TransferFeeAmount { withheld_amount })
+            .build()
+            .1
+    };
+    let source = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+
+    // 1% of 10,000 is withheld in the destination account.
+    let expected_source = build_account(&source, &owner, 990_000, 0);
+    let expected_destination = build_account(&destination, &recipient, 9_900, 100);
+
+    mollusk.process_and_validate_instruction(
+        &transfer_checked(&source, &mint, &destination, &owner, 10_000),
+        &[
+            (source, build_account(&source, &owner, 1_000_000, 0)),
+            (mint, mint_account.clone()),
+            (destination, build_account(&destination, &recipient, 0, 0)),
+            (owner, Account::default()),
+        ],
+        &[
+            Check::success(),
+            Check::account(&source).data(&expected_source.data).build(),
+            Check::account(&destination)
+                .data(&expected_destination.data)
+                .build(),
+            Check::account(&mint).data(&mint_account.data).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_transfer_checked_with_memo_transfer() {
+    let mut mollusk = mollusk();
+    memo::add_program(&mut mollusk);
+
+    let (mint, mint_account) = MintBuilder::new(base_mint(1_000)).build();
+    let owner = Pubkey::new_unique();
+    let (source, source_account) =
+        TokenAccountBuilder::new(base_token_account(&mint, &owner, 1_000)).build();
+    let destination_with_memos = |require_incoming_transfer_memos: bool| {
+        TokenAccountBuilder::new(base_token_account(&mint, &Pubkey::new_unique(), 0))
+            .extension(Extension::ImmutableOwner)
+            .extension(Extension::MemoTransfer {
+                require_incoming_transfer_memos,
+            })
+            .build()
+    };
+
+    // Memos are required, but none precedes the transfer.
+    let (destination, destination_account) = destination_with_memos(true);
+    let accounts = [
+        (source, source_account.clone()),
+        (mint, mint_account.clone()),
+        (destination, destination_account),
+        (owner, Account::default()),
+    ];
+    let transfer = transfer_checked(&source, &mint, &destination, &owner, 400);
+    mollusk.process_and_validate_instruction(
+        &transfer,
+        &accounts,
+        &[Check::custom_err_from(&ID, ERROR_NO_MEMO)],
+    );
+
+    // A memo preceding the transfer satisfies the requirement.
+    let memo = Instruction::new_with_bytes(memo::ID, b"invoice #1", vec![]);
+    mollusk.process_and_validate_transaction_instructions(
+        &[memo, transfer],
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&destination)
+                .data_slice(64, &400u64.to_le_bytes())
+                .build(),
+        ],
+    );
+
+    // Memos aren't required.
+    let (destination, destination_account) = destination_with_memos(false);
+    mollusk.process_and_validate_instruction(
+        &transfer_checked(&source, &mint, &destination, &owner, 400),
+        &[
+            (source, source_account),
+            (mint, mint_account),
+            (destination, destination_account),
+            (owner, Account::default()),
+        ],
+        &[
+            Check::success(),
+            Check::account(&destination)
+                .data_slice(64, &400u64.to_le_bytes())
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn test_transfer_checked_non_transferable() {
+    let mollusk = mollusk();
+
+    let (mint, mint_account) = MintBuilder::new(base_mint(1_000))
+        .extension(Extension::NonTransferable)
+        .build();
+    let owner = Pubkey::new_unique();
+    let build_account = |owner: &Pubkey, amount: u64| {
+        TokenAccountBuilder::new(base_token_account(&mint, owner, amount))
+            .extension(Extension::ImmutableOwner)
+            .extension(Extension::NonTransferableAccount)
+            .build()
+    };
+    let (source, source_account) = build_account(&owner, 1_000);
+    let (destination, destination_account) = build_account(&Pubkey::new_unique(), 0);
+
+    mollusk.process_and_validate_instruction(
+        &transfer_checked(&source, &mint, &destination, &owner, 400),
+        &[
+            (source, source_account),
+            (mint, mint_account),
+            (destination, destination_account),
+            (owner, Account::default()),
+        ],
+        &[Check::custom_err_from(&ID, ERROR_NON_TRANSFERABLE)],
+    );
+}
+
+#[test]
+fn test_transfer_checked_permanent_delegate() {
+    let mollusk = mollusk();
+
+    let delegate = Pubkey::new_unique();
+    let (mint, mint_account) = MintBuilder::new(base_mint(1_000))
+        .extension(Extension::PermanentDelegate {
+            delegate: Some(delegate),
+        })
+        .build();
+    let (source, source_account) =
+        TokenAccountBuilder::new(base_token_account(&mint, &Pubkey::new_unique(), 1_000)).build();
+    let (destination, destination_account) =
+        TokenAccountBuilder::new(base_token_account(&mint, &Pubkey::new_unique(), 0)).build();
+
+    // The delegate can move tokens out of any account of the mint.
+    mollusk.process_and_validate_instruction(
+        &transfer_checked(&source, &mint, &destination, &delegate, 400),
+        &[
+            (source, source_account),
+            (mint, mint_account),
+            (destination, destination_account),
+            (delegate, Account::default()),
+        ],
+        &[
+            Check::success(),
+            Check::account(&source)
+                .data_slice(64, &600u64.to_le_bytes())
+                .build(),
+            Check::account(&destination)
+                .data_slice(64, &400u64.to_le_bytes())
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn test_set_authority_immutable_owner() {
+    let mollusk = mollusk();
+
+    let (mint, mint_account) = MintBuilder::new(base_mint(0)).build();
+    let owner = Pubkey::new_unique();
+    let (account, token_account) = TokenAccountBuilder::new(base_token_account(&mint, &owner, 0))
+        .extension(Extension::ImmutableOwner)
+        .build();
+
+    mollusk.process_and_validate_instruction(
+        &instruction::set_authority(
+            &ID,
+            &account,
+            Some(&Pubkey::new_unique()),
+            AuthorityType::AccountOwner,
+            &owner,
+            &[],
+        )
+        .unwrap(),
+        &[
+            (account, token_account),
+            (mint, mint_account),
+            (owner, Account::default()),
+        ],
+        &[Check::custom_err_from(&ID, ERROR_IMMUTABLE_OWNER)],
+    );
+}
+
+#[test]
+fn test_close_mint_with_close_authority() {
+    let mollusk = mollusk();
+
+    let close_authority = Pubkey::new_unique();
+    let (mint, mint_account) = MintBuilder::new(base_mint(0))
+        .extension(Extension::MintCloseAuthority {
+            close_authority: Some(close_authority),
+        })
+        .build();
+    let destination = Pubkey::new_unique();
+    let lamports = mint_account.lamports;
+
+    mollusk.process_and_validate_instruction(
+        &instruction::close_account(&ID, &mint, &destination, &close_authority, &[]).unwrap(),
+        &[
+            (mint, mint_account),
+            (destination, Account::default()),
+            (close_authority, Account::default()),
+        ],
+        &[
+            Check::success(),
+            Check::account(&mint).lamports(0).build(),
+            Check::account(&destination).lamports(lamports).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_initialize_account_default_account_state() {
+    let mollusk = mollusk();
+
+    let (mint, mint_account) = MintBuilder::new(base_mint(0))
+        .extension(Extension::DefaultAccountState {
+            state: AccountState::Frozen,
+        })
+        .build();
+    let owner = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let uninitialized = Account {
+        lamports: mollusk.sysvars.rent.minimum_balance(TokenAccount::LEN),
+        data: vec![0; TokenAccount::LEN],
+        owner: ID,
+        ..Default::default()
+    };
+
+    // New accounts are frozen.
+    let (_, expected) = TokenAccountBuilder::new(TokenAccount {
+        state: AccountState::Frozen,
+        ..base_token_account(&mint, &owner, 0)
+    })
+    .build();
+
+    mollusk.process_and_validate_instruction(
+        &instruction::initialize_account3(&ID, &account, &mint, &owner).unwrap(),
+        &[(account, uninitialized), (mint, mint_account)],
+        &[
+            Check::success(),
+            Check::account(&account).data(&expected.data).build(),
+        ],
+    );
+}
+
+#[test]
+fn test_get_account_data_size() {
+    let mollusk = mollusk();
+
+    let authority = Some(Pubkey::new_unique());
+    let fee = TransferFee {
+        epoch: 0,
+        maximum_fee: 1_000,
+        transfer_fee_basis_points: 50,
+    };
+    let transfer_fee_config = Extension::TransferFeeConfig {
+        transfer_fee_config_authority: authority,
+        withdraw_withheld_authority: authority,
+        withheld_amount: 0,
+        older_transfer_fee: fee,
+        newer_transfer_fee: fee,
+    };
+    let other_mint_extensions = [
+        Extension::MintCloseAuthority {
+            close_authority: authority,
+        },
+        Extension::DefaultAccountState {
+            state: AccountState::Initialized,
+        },
+        Extension::PermanentDelegate {
+            delegate: authority,
+        },
+        Extension::MetadataPointer {
+            authority,
+            metadata_address: Some(Pubkey::new_unique()),
+        },
+    ];
+
+    // Token-2022 parses the mint's extensions to find those new token
+    // accounts need, and the builder must agree on the resulting length.
+    let cases = [
+        (vec![], vec![]),
+        (
+            vec![transfer_fee_config.clone()],
+            vec![Extension::TransferFeeAmount { withheld_amount: 0 }],
+        ),
+        (other_mint_extensions.to_vec(), vec![]),
+        (
+            [vec![transfer_fee_config], other_mint_extensions.to_vec()].concat(),
+            vec![Extension::TransferFeeAmount { withheld_amount: 0 }],
+        ),
+    ];
+    for (mint_extensions, account_extensions) in cases {
+        let (mint, mint_account) = mint_extensions
+            .into_iter()
+            .fold(MintBuilder::new(base_mint(0)), MintBuilder::extension)
+            .build();
+        let expected_len = account_extensions
+            .into_iter()
+            .fold(
+                TokenAccountBuilder::new(TokenAccount::default()),
+                TokenAccountBuilder::extension,
+            )
+            .data_len() as u64;
+
+        mollusk.process_and_validate_instruction(
+            &instruction::get_account_data_size(&ID, &mint).unwrap(),
+            &[(mint, mint_account)],
+            &[
+                Check::success(),
+                Check::return_data(&expected_len.to_le_bytes()),
+            ],
+        );
+    }
+
+    // Extensions requested on top of the mint's.
+    let (mint, mint_account) = MintBuilder::new(base_mint(0)).build();
+    let expected_len = TokenAccountBuilder::new(TokenAccount::default())
+        .extension(Extension::ImmutableOwner)
+        .extension(Extension::MemoTransfer {
+            require_incoming_transfer_memos: true,
+        })
+        .data_len() as u64;
+    let mut data = vec![21];
+    data.extend_from_slice(&Extension::ImmutableOwner.extension_type().to_le_bytes());
+    data.extend_from_slice(
+        &Extension::MemoTransfer {
+            require_incoming_transfer_memos: true,
+        }
+        .extension_type()
+        .to_le_bytes(),
+    );
+    mollusk.process_and_validate_instruction(
+        &Instruction::new_with_bytes(ID, &data, vec![AccountMeta::new_readonly(mint, false)]),
+        &[(mint, mint_account)],
+        &[
+            Check::success(),
+            Check::return_data(&expected_len.to_le_bytes()),
+        ],
+    );
+}
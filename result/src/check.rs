@@ -1,34 +1,95 @@
 //! Check system for validating individual instruction results.
 
+#[cfg(feature = "borsh")]
+use borsh::BorshDeserialize;
 #[cfg(feature = "inner-instructions")]
-use trezoa_transaction_status_client_types::InnerInstruction;
+use {trezoa_message::SanitizedMessage, trezoa_transaction_status_client_types::InnerInstruction};
+#[cfg(feature = "program-pack")]
+use trezoa_program_pack::{IsInitialized, Pack};
+#[cfg(any(feature = "borsh", feature = "program-pack"))]
+use std::fmt::Debug;
+#[cfg(feature = "fs")]
+use {crate::snapshot, std::path::Path};
 use {
     crate::{
         config::{compare, throw, CheckContext, Config},
+        events::Event,
+        primitives::{self, AccountExpectation},
+        return_data::InnerReturnData,
         types::{InstructionResult, ProgramResult, TransactionProgramResult, TransactionResult},
     },
     trezoa_account::{Account, ReadableAccount},
     trezoa_instruction::error::InstructionError,
+    trezoa_precompile_error::PrecompileError,
     trezoa_program_error::ProgramError,
+    sha2::{Digest, Sha256},
     trezoa_pubkey::Pubkey,
+    trezoa_transaction_error::TransactionError,
 };
 
 enum CheckType<'a> {
     /// Check the number of compute units consumed by the instruction.
     ComputeUnitsConsumed(u64),
+    /// Check that the compute units consumed are within a tolerance of the
+    /// expected number.
+    ComputeUnitsWithin(u64, u64),
+    /// Check that the compute units consumed don't exceed a maximum.
+    ComputeUnitsAtMost(u64),
     /// Check the time taken to execute the instruction.
     ExecutionTime(u64),
     /// Check the result code of the program's execution.
     ProgramResult(ProgramResult),
+    /// Check that a custom error was returned by a specific program.
+    CustomErrorFrom(&'a Pubkey, u32),
+    /// Check the error returned by a failing precompile.
+    PrecompileError(PrecompileError),
+    /// Check the error of a transaction which failed before any program was
+    /// invoked.
+    TransactionError(TransactionError),
     /// Check the return data produced by executing the instruction.
     ReturnData(&'a [u8]),
+    /// Check a slice of the return data, at an offset.
+    ReturnDataSlice(usize, &'a [u8]),
+    /// Check the length of the return data.
+    ReturnDataLen(usize),
+    /// Check the return data with a predicate.
+    ReturnDataWith(Box<dyn Fn(&[u8]) -> bool + 'a>),
+    /// Check that a program returned the data, at any level of the
+    /// invocation stack.
+    InnerReturnData(&'a Pubkey, &'a [u8]),
+    /// Check that an event with the discriminator was emitted.
+    EventEmitted(&'a [u8]),
+    /// Check that an event with the discriminator and payload was emitted.
+    EventData(&'a [u8], &'a [u8]),
+    /// Check that an event with the discriminator was emitted, whose payload
+    /// satisfies a predicate.
+    EventWith(&'a [u8], Box<dyn Fn(&[u8]) -> bool + 'a>),
     /// Check a resulting account after executing the instruction.
     ResultingAccount(AccountCheck<'a>),
+    /// Check a resulting account with a function.
+    ResultingAccountWith(Pubkey, Box<dyn Fn(&Config, &Account) -> bool + 'a>),
     /// Check that all accounts are rent exempt
     AllRentExempt,
+    /// Check that all of the provided accounts are owned by the program and
+    /// rent exempt.
+    AllAccountsOwnedBy(&'a Pubkey, &'a [Pubkey]),
+    /// Check the result against a snapshot file.
+    #[cfg(feature = "fs")]
+    Snapshot(&'a Path),
+    /// Check that the instruction failed, and allow an instruction chain to
+    /// continue past it.
+    ExpectFailureAndContinue,
+    /// Check that only accounts declared writable were modified.
+    NoUndeclaredWrites,
     /// Check the number of inner instructions (CPIs) invoked.
     #[cfg(feature = "inner-instructions")]
     InnerInstructionCount(usize),
+    /// Check an inner instruction (CPI) invoked during execution.
+    #[cfg(feature = "inner-instructions")]
+    InnerInstruction(InnerInstructionCheck<'a>),
+    /// Check that at least one inner instruction (CPI) invoked the program.
+    #[cfg(feature = "inner-instructions")]
+    CpiTo(&'a Pubkey),
 }
 
 pub struct Check<'a> {
@@ -45,6 +106,21 @@ impl<'a> Check<'a> {
         Check::new(CheckType::ComputeUnitsConsumed(units))
     }
 
+    /// Check that the number of compute units consumed by the instruction is
+    /// within `tolerance` of `expected`, in either direction.
+    ///
+    /// Unlike `compute_units`, this survives minor codegen changes, such as
+    /// compiler upgrades, while still catching real regressions.
+    pub const fn compute_units_within(expected: u64, tolerance: u64) -> Self {
+        Check::new(CheckType::ComputeUnitsWithin(expected, tolerance))
+    }
+
+    /// Check that the number of compute units consumed by the instruction
+    /// doesn't exceed `max`.
+    pub const fn compute_units_at_most(max: u64) -> Self {
+        Check::new(CheckType::ComputeUnitsAtMost(max))
+    }
+
     /// Check the time taken to execute the instruction.
     pub const fn time(time: u64) -> Self {
         Check::new(CheckType::ExecutionTime(time))
@@ -60,11 +136,38 @@ impl<'a> Check<'a> {
         Check::new(CheckType::ProgramResult(ProgramResult::Failure(error)))
     }
 
+    /// Assert that the provided program returned the custom error code.
+    ///
+    /// Unlike `Check::err(ProgramError::Custom(code))`, this also checks
+    /// which program failed. When a CPI fails, the error is attributed to
+    /// the deepest failing program, so identical codes from different
+    /// programs can be told apart.
+    pub const fn custom_err_from(program_id: &'a Pubkey, code: u32) -> Self {
+        Check::new(CheckType::CustomErrorFrom(program_id, code))
+    }
+
     /// Assert that the instruction returned an error.
     pub const fn instruction_err(error: InstructionError) -> Self {
         Check::new(CheckType::ProgramResult(ProgramResult::UnknownError(error)))
     }
 
+    /// Assert that a precompile failed with the provided error.
+    ///
+    /// Precompile failures surface as a generic instruction error, so
+    /// `Check::err` can't tell them apart. This checks the precompile's own
+    /// error instead.
+    pub const fn precompile_err(error: PrecompileError) -> Self {
+        Check::new(CheckType::PrecompileError(error))
+    }
+
+    /// Assert that the transaction failed with the provided error before
+    /// any program was invoked, such as when it exceeds a transaction limit.
+    ///
+    /// Only transaction results can fail this way.
+    pub const fn transaction_err(error: TransactionError) -> Self {
+        Check::new(CheckType::TransactionError(error))
+    }
+
     /// Assert that the instruction returned the provided result.
     pub const fn program_result(result: ProgramResult) -> Self {
         Check::new(CheckType::ProgramResult(result))
@@ -75,21 +178,164 @@ impl<'a> Check<'a> {
         Check::new(CheckType::ReturnData(return_data))
     }
 
+    /// Check a slice of the return data, starting at `offset`.
+    pub const fn return_data_slice(offset: usize, data: &'a [u8]) -> Self {
+        Check::new(CheckType::ReturnDataSlice(offset, data))
+    }
+
+    /// Check the length of the return data.
+    pub const fn return_data_len(len: usize) -> Self {
+        Check::new(CheckType::ReturnDataLen(len))
+    }
+
+    /// Check the return data with a predicate, which should return `true` if
+    /// the data is as expected.
+    ///
+    /// Useful for structured return data, which can be decoded and checked
+    /// field by field instead of reconstructing the exact bytes.
+    ///
+    /// ```rust,ignore
+    /// Check::return_data_with(|data| {
+    ///     State::try_from_slice(data).is_ok_and(|state| state.count == 3)
+    /// })
+    /// ```
+    pub fn return_data_with<F>(predicate: F) -> Self
+    where
+        F: Fn(&[u8]) -> bool + 'a,
+    {
+        Check::new(CheckType::ReturnDataWith(Box::new(predicate)))
+    }
+
+    /// Check that the provided program returned the provided data, at any
+    /// level of the invocation stack, such as a program invoked via CPI. See
+    /// the `return_data` module.
+    pub const fn inner_return_data(program_id: &'a Pubkey, data: &'a [u8]) -> Self {
+        Check::new(CheckType::InnerReturnData(program_id, data))
+    }
+
+    /// Check that an event with the provided discriminator was emitted,
+    /// either via logs or a self-CPI. See the `events` module.
+    pub const fn event_emitted(discriminator: &'a [u8]) -> Self {
+        Check::new(CheckType::EventEmitted(discriminator))
+    }
+
+    /// Check that an event with the provided discriminator and payload (the
+    /// serialized event, following the discriminator) was emitted.
+    pub const fn event_data(discriminator: &'a [u8], payload: &'a [u8]) -> Self {
+        Check::new(CheckType::EventData(discriminator, payload))
+    }
+
+    /// Check that an event with the provided discriminator was emitted, whose
+    /// payload satisfies a predicate.
+    ///
+    /// ```rust,ignore
+    /// Check::event_with(&TRANSFER_EVENT_DISCRIMINATOR, |data| {
+    ///     TransferEvent::try_from_slice(data).is_ok_and(|event| event.amount == 42)
+    /// })
+    /// ```
+    pub fn event_with<F>(discriminator: &'a [u8], predicate: F) -> Self
+    where
+        F: Fn(&[u8]) -> bool + 'a,
+    {
+        Check::new(CheckType::EventWith(discriminator, Box::new(predicate)))
+    }
+
     /// Check a resulting account after executing the instruction.
     pub const fn account(pubkey: &Pubkey) -> AccountCheckBuilder<'_> {
         AccountCheckBuilder::new(pubkey)
     }
 
+    /// Check a resulting account with a function, which should return
+    /// `true` if the account is as expected.
+    ///
+    /// The function receives the check config, so it can report mismatches
+    /// with `primitives::compare` like the built-in checks do. Useful for
+    /// building checks on top of an account's decoded state.
+    ///
+    /// ```rust,ignore
+    /// Check::account_with(&counter, |config, account| {
+    ///     let count = u64::from_le_bytes(account.data[..8].try_into().unwrap());
+    ///     primitives::compare(config, "counter_count", 3, count)
+    /// })
+    /// ```
+    pub fn account_with<F>(pubkey: &Pubkey, check: F) -> Self
+    where
+        F: Fn(&Config, &Account) -> bool + 'a,
+    {
+        Check::new(CheckType::ResultingAccountWith(*pubkey, Box::new(check)))
+    }
+
     /// Check that all resulting accounts are rent exempt
     pub const fn all_rent_exempt() -> Self {
         Check::new(CheckType::AllRentExempt)
     }
 
+    /// Check that every one of the provided resulting accounts is owned by
+    /// `program_id` and rent exempt.
+    ///
+    /// Useful for init-style instructions which create several accounts.
+    /// Each account that fails the check is reported individually.
+    pub const fn all_accounts_owned_by(program_id: &'a Pubkey, accounts: &'a [Pubkey]) -> Self {
+        Check::new(CheckType::AllAccountsOwnedBy(program_id, accounts))
+    }
+
+    /// Check the result against the snapshot file at `path`.
+    ///
+    /// Compute units, program result, return data, and resulting accounts
+    /// must all match the snapshot. If `MOLLUSK_UPDATE_SNAPSHOTS` is set, the
+    /// snapshot is overwritten instead. See the `snapshot` module.
+    #[cfg(feature = "fs")]
+    pub fn matches_snapshot<P: AsRef<Path> + ?Sized>(path: &'a P) -> Self {
+        Check::new(CheckType::Snapshot(path.as_ref()))
+    }
+
+    /// Assert that the instruction failed, and continue processing the rest
+    /// of the chain.
+    ///
+    /// By default, instruction chains stop at the first failing instruction.
+    /// With this check, the chain continues, and the next instruction runs
+    /// against the unchanged state from before the failure. Combine it with
+    /// `Check::err` or `Check::instruction_err` to assert the specific error.
+    pub const fn expect_failure_and_continue() -> Self {
+        Check::new(CheckType::ExpectFailureAndContinue)
+    }
+
+    /// Check that every account modified during execution was declared
+    /// writable by the compiled message.
+    ///
+    /// The runtime rejects writes to readonly accounts, so this catches
+    /// programs which only pass in the harness because of how their accounts
+    /// were provided.
+    pub const fn no_undeclared_writes() -> Self {
+        Check::new(CheckType::NoUndeclaredWrites)
+    }
+
+    /// Returns `true` if this is an `expect_failure_and_continue` check.
+    pub const fn is_expect_failure_and_continue(&self) -> bool {
+        matches!(self.check, CheckType::ExpectFailureAndContinue)
+    }
+
     /// Check the number of inner instructions (CPIs) invoked during execution.
     #[cfg(feature = "inner-instructions")]
     pub const fn inner_instruction_count(count: usize) -> Self {
         Check::new(CheckType::InnerInstructionCount(count))
     }
+
+    /// Check the inner instruction (CPI) at `index`, in invocation order.
+    ///
+    /// Program ids and account keys are resolved through the compiled
+    /// message, so they can't be checked on results loaded from fuzz
+    /// fixtures.
+    #[cfg(feature = "inner-instructions")]
+    pub const fn inner_instruction(index: usize) -> InnerInstructionCheckBuilder<'a> {
+        InnerInstructionCheckBuilder::new(index)
+    }
+
+    /// Check that at least one inner instruction (CPI) invoked the program.
+    #[cfg(feature = "inner-instructions")]
+    pub const fn cpi_to(program_id: &'a Pubkey) -> Self {
+        Check::new(CheckType::CpiTo(program_id))
+    }
 }
 
 enum AccountStateCheck {
@@ -97,32 +343,106 @@ enum AccountStateCheck {
     RentExempt,
 }
 
+/// A check comparing a resulting account against the same account as it was
+/// provided to the instruction.
+enum AccountDeltaCheck<'a> {
+    Unchanged,
+    DataUnchanged,
+    LamportsDelta(i128),
+    OwnerChangedFromTo(&'a Pubkey, &'a Pubkey),
+    ExecutableChangedFromTo(bool, bool),
+}
+
+impl AccountDeltaCheck<'_> {
+    fn check(&self, config: &Config, input: &Account, resulting: &Account) -> bool {
+        let c = config;
+        match self {
+            AccountDeltaCheck::Unchanged => compare!(c, "account_unchanged", input, resulting),
+            AccountDeltaCheck::DataUnchanged => {
+                compare!(c, "account_data_unchanged", &input.data, &resulting.data)
+            }
+            AccountDeltaCheck::LamportsDelta(delta) => {
+                let check_delta = *delta;
+                let actual_delta = resulting.lamports as i128 - input.lamports as i128;
+                compare!(c, "account_lamports_delta", check_delta, actual_delta)
+            }
+            AccountDeltaCheck::OwnerChangedFromTo(from, to) => {
+                let check_owners = (**from, **to);
+                let actual_owners = (input.owner, resulting.owner);
+                compare!(c, "account_owner_change", check_owners, actual_owners)
+            }
+            AccountDeltaCheck::ExecutableChangedFromTo(from, to) => {
+                let check_executable = (*from, *to);
+                let actual_executable = (input.executable, resulting.executable);
+                compare!(
+                    c,
+                    "account_executable_change",
+                    check_executable,
+                    actual_executable
+                )
+            }
+        }
+    }
+}
+
 struct AccountCheck<'a> {
     pubkey: Pubkey,
-    check_data: Option<&'a [u8]>,
-    check_executable: Option<bool>,
-    check_lamports: Option<u64>,
-    check_owner: Option<&'a Pubkey>,
-    check_space: Option<usize>,
+    check_fields: AccountExpectation<'a, Pubkey>,
     check_state: Option<AccountStateCheck>,
-    check_data_slice: Option<(usize, &'a [u8])>,
+    check_data_with: Vec<Box<dyn Fn(&Config, &[u8]) -> bool + 'a>>,
+    check_deltas: Vec<AccountDeltaCheck<'a>>,
 }
 
 impl AccountCheck<'_> {
     const fn new(pubkey: &Pubkey) -> Self {
         Self {
             pubkey: *pubkey,
-            check_data: None,
-            check_executable: None,
-            check_lamports: None,
-            check_owner: None,
-            check_space: None,
+            check_fields: AccountExpectation::new(),
             check_state: None,
-            check_data_slice: None,
+            check_data_with: Vec::new(),
+            check_deltas: Vec::new(),
         }
     }
 }
 
+/// Deserialize Borsh account state, reporting a failure if the account data
+/// doesn't hold a `T`. Trailing bytes, such as unused space, are ignored.
+#[cfg(feature = "borsh")]
+fn deserialize_borsh<T: BorshDeserialize>(config: &Config, data: &[u8]) -> Option<T> {
+    let state = T::deserialize(&mut &data[..]);
+    if let Err(err) = &state {
+        throw!(
+            config,
+            "CHECK FAILED: account_state\n  Failed to deserialize account data as {}: {}",
+            std::any::type_name::<T>(),
+            err
+        );
+    }
+    state.ok()
+}
+
+/// Unpack account state, reporting a failure if the account data doesn't
+/// hold an initialized `T`.
+#[cfg(feature = "program-pack")]
+fn unpack<T: Pack + IsInitialized>(config: &Config, data: &[u8]) -> Option<T> {
+    let state = data.get(..T::LEN).and_then(|data| T::unpack(data).ok());
+    if state.is_none() {
+        throw!(
+            config,
+            "CHECK FAILED: account_state\n  Account data isn't an initialized {}",
+            std::any::type_name::<T>()
+        );
+    }
+    state
+}
+
+/// The SHA-256 hash of account data, as checked by
+/// `AccountCheckBuilder::data_hash` and compared by
+/// `Compare::ResultingAccountDataHashes`.
+pub fn account_data_hash(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
 pub struct AccountCheckBuilder<'a> {
     check: AccountCheck<'a>,
 }
@@ -140,22 +460,40 @@ impl<'a> AccountCheckBuilder<'a> {
     }
 
     pub const fn data(mut self, data: &'a [u8]) -> Self {
-        self.check.check_data = Some(data);
+        self.check.check_fields.data = Some(data);
         self
     }
 
     pub const fn executable(mut self, executable: bool) -> Self {
-        self.check.check_executable = Some(executable);
+        self.check.check_fields.executable = Some(executable);
         self
     }
 
     pub const fn lamports(mut self, lamports: u64) -> Self {
-        self.check.check_lamports = Some(lamports);
+        self.check.check_fields.lamports = Some(lamports);
         self
     }
 
     pub const fn owner(mut self, owner: &'a Pubkey) -> Self {
-        self.check.check_owner = Some(owner);
+        self.check.check_fields.owner = Some(owner);
+        self
+    }
+
+    pub const fn rent_epoch(mut self, rent_epoch: u64) -> Self {
+        self.check.check_fields.rent_epoch = Some(rent_epoch);
+        self
+    }
+
+    /// Check that the resulting account matches the provided account
+    /// exactly: lamports, data, owner, executable, and rent epoch.
+    ///
+    /// Each mismatched field is reported individually.
+    pub fn equals(mut self, account: &'a Account) -> Self {
+        self.check.check_fields.data = Some(account.data.as_slice());
+        self.check.check_fields.executable = Some(account.executable);
+        self.check.check_fields.lamports = Some(account.lamports);
+        self.check.check_fields.owner = Some(&account.owner);
+        self.check.check_fields.rent_epoch = Some(account.rent_epoch);
         self
     }
 
@@ -165,12 +503,141 @@ impl<'a> AccountCheckBuilder<'a> {
     }
 
     pub const fn space(mut self, space: usize) -> Self {
-        self.check.check_space = Some(space);
+        self.check.check_fields.space = Some(space);
         self
     }
 
     pub const fn data_slice(mut self, offset: usize, data: &'a [u8]) -> Self {
-        self.check.check_data_slice = Some((offset, data));
+        self.check.check_fields.data_slice = Some((offset, data));
+        self
+    }
+
+    /// Check the SHA-256 hash of the resulting account's data, as computed by
+    /// `account_data_hash`.
+    ///
+    /// Useful for large accounts, since the expected data doesn't need to be
+    /// held by the test, and a mismatch reports the hashes rather than both
+    /// buffers.
+    ///
+    /// ```rust,ignore
+    /// Check::account(&large_account)
+    ///     .data_hash(account_data_hash(&expected_data))
+    ///     .build()
+    /// ```
+    pub fn data_hash(self, hash: [u8; 32]) -> Self {
+        self.state_data_with(move |config, data| {
+            primitives::compare(config, "account_data_hash", hash, account_data_hash(data))
+        })
+    }
+
+    /// Check the resulting account's state, deserialized with Borsh.
+    ///
+    /// ```rust,ignore
+    /// Check::account(&counter)
+    ///     .state_borsh(Counter { authority, count: 3 })
+    ///     .build()
+    /// ```
+    #[cfg(feature = "borsh")]
+    pub fn state_borsh<T>(self, expected: T) -> Self
+    where
+        T: BorshDeserialize + PartialEq + Debug + 'a,
+    {
+        self.state_data_with(move |config, data| {
+            deserialize_borsh::<T>(config, data).is_some_and(|state| {
+                primitives::compare(config, "account_state", &expected, &state)
+            })
+        })
+    }
+
+    /// Check the resulting account's state, unpacked with `Pack`. The state
+    /// must be initialized.
+    #[cfg(feature = "program-pack")]
+    pub fn state_packed<T>(self, expected: T) -> Self
+    where
+        T: Pack + IsInitialized + PartialEq + Debug + 'a,
+    {
+        self.state_data_with(move |config, data| {
+            unpack::<T>(config, data).is_some_and(|state| {
+                primitives::compare(config, "account_state", &expected, &state)
+            })
+        })
+    }
+
+    /// Check the resulting account's state, deserialized with Borsh, with a
+    /// function, which should return `true` if the state is as expected.
+    ///
+    /// ```rust,ignore
+    /// Check::account(&counter)
+    ///     .state_with::<Counter>(|counter| counter.count > 0)
+    ///     .build()
+    /// ```
+    #[cfg(feature = "borsh")]
+    pub fn state_with<T>(self, check: impl Fn(&T) -> bool + 'a) -> Self
+    where
+        T: BorshDeserialize,
+    {
+        self.state_data_with(move |config, data| {
+            deserialize_borsh::<T>(config, data).is_some_and(|state| {
+                check(&state)
+                    || throw!(
+                        config,
+                        "CHECK FAILED: account_state\n  {} didn't satisfy the predicate",
+                        std::any::type_name::<T>()
+                    )
+            })
+        })
+    }
+
+    fn state_data_with(mut self, check: impl Fn(&Config, &[u8]) -> bool + 'a) -> Self {
+        self.check.check_data_with.push(Box::new(check));
+        self
+    }
+
+    /// Check that the resulting account is identical to the account provided
+    /// to the instruction: lamports, data, owner, executable, and rent epoch.
+    ///
+    /// This and the other delta checks compare against the input accounts,
+    /// which Mollusk supplies through `CheckContext::input_account`.
+    pub fn unchanged(self) -> Self {
+        self.delta(AccountDeltaCheck::Unchanged)
+    }
+
+    /// Check that the resulting account's data is identical to the data of
+    /// the account provided to the instruction.
+    pub fn data_unchanged(self) -> Self {
+        self.delta(AccountDeltaCheck::DataUnchanged)
+    }
+
+    /// Check the change in the account's lamports, from the account provided
+    /// to the instruction to the resulting account. Negative for debits.
+    ///
+    /// ```rust,ignore
+    /// Check::account(&payer).lamports_delta(-(rent + fee as i128)).build()
+    /// ```
+    pub fn lamports_delta(self, delta: i128) -> Self {
+        self.delta(AccountDeltaCheck::LamportsDelta(delta))
+    }
+
+    /// Check that the account was owned by `from` when provided to the
+    /// instruction, and is owned by `to` after it.
+    ///
+    /// ```rust,ignore
+    /// Check::account(&account)
+    ///     .owner_changed_from_to(&system_program::id(), &program_id)
+    ///     .build()
+    /// ```
+    pub fn owner_changed_from_to(self, from: &'a Pubkey, to: &'a Pubkey) -> Self {
+        self.delta(AccountDeltaCheck::OwnerChangedFromTo(from, to))
+    }
+
+    /// Check the account's executable flag when provided to the instruction,
+    /// and after it.
+    pub fn executable_changed_from_to(self, from: bool, to: bool) -> Self {
+        self.delta(AccountDeltaCheck::ExecutableChangedFromTo(from, to))
+    }
+
+    fn delta(mut self, check: AccountDeltaCheck<'a>) -> Self {
+        self.check.check_deltas.push(check);
         self
     }
 
@@ -179,6 +646,134 @@ impl<'a> AccountCheckBuilder<'a> {
     }
 }
 
+#[cfg(feature = "inner-instructions")]
+struct InnerInstructionCheck<'a> {
+    index: usize,
+    program_id: Option<&'a Pubkey>,
+    data: Option<&'a [u8]>,
+    data_prefix: Option<&'a [u8]>,
+    accounts: Option<&'a [Pubkey]>,
+}
+
+#[cfg(feature = "inner-instructions")]
+impl InnerInstructionCheck<'_> {
+    fn check(
+        &self,
+        config: &Config,
+        inner_instruction: &InnerInstruction,
+        message: Option<&SanitizedMessage>,
+    ) -> bool {
+        let c = config;
+        let instruction = &inner_instruction.instruction;
+        let mut pass = true;
+        if self.program_id.is_some() || self.accounts.is_some() {
+            let Some(message) = message else {
+                return throw!(
+                    c,
+                    "CHECK FAILED: inner instruction {}: no compiled message to resolve account \
+                     keys",
+                    self.index
+                );
+            };
+            let account_keys = message.account_keys();
+            let resolve = |index: &u8| account_keys.get(*index as usize).copied();
+            if let Some(check_program_id) = self.program_id {
+                let actual_program_id = resolve(&instruction.program_id_index);
+                pass &= compare!(
+                    c,
+                    "inner_instruction_program_id",
+                    Some(*check_program_id),
+                    actual_program_id,
+                );
+            }
+            if let Some(check_accounts) = self.accounts {
+                let check_accounts = check_accounts.iter().copied().map(Some).collect::<Vec<_>>();
+                let actual_accounts = instruction.accounts.iter().map(resolve).collect::<Vec<_>>();
+                pass &= compare!(
+                    c,
+                    "inner_instruction_accounts",
+                    check_accounts,
+                    actual_accounts,
+                );
+            }
+        }
+        if let Some(check_data) = self.data {
+            let actual_data = instruction.data.as_slice();
+            pass &= compare!(c, "inner_instruction_data", check_data, actual_data);
+        }
+        if let Some(check_data_prefix) = self.data_prefix {
+            if !instruction.data.starts_with(check_data_prefix) {
+                pass &= throw!(
+                    c,
+                    "CHECK FAILED: inner instruction {}: data doesn't start with prefix\n  \
+                     Expected prefix: `{:?}`,\n Got: `{:?}`",
+                    self.index,
+                    check_data_prefix,
+                    instruction.data,
+                );
+            }
+        }
+        pass
+    }
+}
+
+#[cfg(feature = "inner-instructions")]
+pub struct InnerInstructionCheckBuilder<'a> {
+    check: InnerInstructionCheck<'a>,
+}
+
+#[cfg(feature = "inner-instructions")]
+impl<'a> InnerInstructionCheckBuilder<'a> {
+    const fn new(index: usize) -> Self {
+        Self {
+            check: InnerInstructionCheck {
+                index,
+                program_id: None,
+                data: None,
+                data_prefix: None,
+                accounts: None,
+            },
+        }
+    }
+
+    pub const fn program_id(mut self, program_id: &'a Pubkey) -> Self {
+        self.check.program_id = Some(program_id);
+        self
+    }
+
+    pub const fn data(mut self, data: &'a [u8]) -> Self {
+        self.check.data = Some(data);
+        self
+    }
+
+    pub const fn data_prefix(mut self, data_prefix: &'a [u8]) -> Self {
+        self.check.data_prefix = Some(data_prefix);
+        self
+    }
+
+    /// Check the keys of the accounts passed to the inner instruction, in
+    /// order.
+    pub const fn accounts(mut self, accounts: &'a [Pubkey]) -> Self {
+        self.check.accounts = Some(accounts);
+        self
+    }
+
+    pub const fn build(self) -> Check<'a> {
+        Check::new(CheckType::InnerInstruction(self.check))
+    }
+}
+
+/// Fail a check of the program's execution, for a transaction which failed
+/// before any program was invoked.
+fn not_executed(config: &Config, check: &str, error: &TransactionError) -> bool {
+    throw!(
+        config,
+        "CHECK FAILED: {}\n  The transaction failed before any program was invoked: {:?}",
+        check,
+        error
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 fn run_checks<C: CheckContext>(
     checks: &[Check],
@@ -186,10 +781,17 @@ fn run_checks<C: CheckContext>(
     context: &C,
     compute_units_consumed: u64,
     execution_time: u64,
-    program_result: &ProgramResult,
+    program_result: Result<&ProgramResult, &TransactionError>,
     return_data: &[u8],
     resulting_accounts: &[(Pubkey, Account)],
+    failing_program_id: Option<&Pubkey>,
+    precompile_error: Option<&PrecompileError>,
+    declared_writable: &[Pubkey],
+    actually_modified: &[Pubkey],
+    inner_return_data: &[InnerReturnData],
+    events: &dyn Fn() -> Vec<Event>,
     #[cfg(feature = "inner-instructions")] inner_instructions: &[InnerInstruction],
+    #[cfg(feature = "inner-instructions")] message: Option<&SanitizedMessage>,
 ) -> bool {
     let c = config;
     let mut pass = true;
@@ -200,6 +802,29 @@ fn run_checks<C: CheckContext>(
                 let actual_units = compute_units_consumed;
                 pass &= compare!(c, "compute_units", check_units, actual_units);
             }
+            CheckType::ComputeUnitsWithin(units, tolerance) => {
+                let actual_units = compute_units_consumed;
+                if actual_units.abs_diff(*units) > *tolerance {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: compute_units\n  Expected: `{} ± {}`,\n Got: `{}`",
+                        units,
+                        tolerance,
+                        actual_units
+                    );
+                }
+            }
+            CheckType::ComputeUnitsAtMost(max_units) => {
+                let actual_units = compute_units_consumed;
+                if actual_units > *max_units {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: compute_units\n  Expected at most: `{}`,\n Got: `{}`",
+                        max_units,
+                        actual_units
+                    );
+                }
+            }
             CheckType::ExecutionTime(time) => {
                 let check_time = *time;
                 let actual_time = execution_time;
@@ -207,13 +832,136 @@ fn run_checks<C: CheckContext>(
             }
             CheckType::ProgramResult(check_program_result) => {
                 let check_result = check_program_result;
-                let actual_result = program_result;
+                let actual_result = match program_result {
+                    Ok(actual_result) => actual_result,
+                    Err(error) => {
+                        pass &= not_executed(c, "program_result", error);
+                        continue;
+                    }
+                };
                 pass &= compare!(c, "program_result", check_result, actual_result);
             }
+            CheckType::CustomErrorFrom(check_program_id, check_code) => {
+                let check_result = ProgramResult::Failure(ProgramError::Custom(*check_code));
+                let actual_result = match program_result {
+                    Ok(actual_result) => actual_result,
+                    Err(error) => {
+                        pass &= not_executed(c, "program_result", error);
+                        continue;
+                    }
+                };
+                pass &= compare!(c, "program_result", &check_result, actual_result);
+                let check_program_id = Some(*check_program_id);
+                let actual_program_id = failing_program_id;
+                pass &= compare!(c, "failing_program_id", check_program_id, actual_program_id);
+            }
+            CheckType::PrecompileError(check_error) => {
+                let check_error = Some(check_error);
+                let actual_error = precompile_error;
+                pass &= compare!(c, "precompile_error", check_error, actual_error);
+            }
+            CheckType::TransactionError(check_error) => {
+                let check_error = Some(check_error);
+                let actual_error = program_result.err();
+                pass &= compare!(c, "transaction_error", check_error, actual_error);
+            }
             CheckType::ReturnData(check_return_data) => {
                 let actual_return_data = return_data;
                 pass &= compare!(c, "return_data", *check_return_data, actual_return_data);
             }
+            CheckType::ReturnDataSlice(offset, check_data_slice) => {
+                let Some(actual_data_slice) =
+                    return_data.get(*offset..*offset + check_data_slice.len())
+                else {
+                    pass &= throw!(
+                        c,
+                        "Return data slice: offset {} + slice length {} exceeds return data \
+                         length {}",
+                        offset,
+                        check_data_slice.len(),
+                        return_data.len(),
+                    );
+                    continue;
+                };
+                pass &= compare!(c, "return_data_slice", *check_data_slice, actual_data_slice);
+            }
+            CheckType::ReturnDataLen(len) => {
+                let check_len = *len;
+                let actual_len = return_data.len();
+                pass &= compare!(c, "return_data_len", check_len, actual_len);
+            }
+            CheckType::ReturnDataWith(predicate) => {
+                if !predicate(return_data) {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: return_data_with\n  Predicate rejected return data: `{:?}`",
+                        return_data
+                    );
+                }
+            }
+            CheckType::InnerReturnData(program_id, data) => {
+                if !inner_return_data
+                    .iter()
+                    .any(|returned| returned.program_id == **program_id && returned.data == *data)
+                {
+                    let returned_by_program = inner_return_data
+                        .iter()
+                        .filter(|returned| returned.program_id == **program_id)
+                        .map(|returned| &returned.data)
+                        .collect::<Vec<_>>();
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: inner_return_data\n  Expected program `{}` to return \
+                         `{:?}`,\n Got: `{:?}`",
+                        program_id,
+                        data,
+                        returned_by_program
+                    );
+                }
+            }
+            CheckType::EventEmitted(discriminator) => {
+                let events = events();
+                if !events.iter().any(|event| event.is(discriminator)) {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: event_emitted\n  No event with discriminator `{:?}` among \
+                         {} events",
+                        discriminator,
+                        events.len()
+                    );
+                }
+            }
+            CheckType::EventData(discriminator, payload) => {
+                let events = events();
+                let payloads = events
+                    .iter()
+                    .filter(|event| event.is(discriminator))
+                    .map(|event| &event.data[discriminator.len()..])
+                    .collect::<Vec<_>>();
+                if !payloads.contains(payload) {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: event_data\n  Expected an event with discriminator `{:?}` \
+                         and payload `{:?}`,\n Got payloads: `{:?}`",
+                        discriminator,
+                        payload,
+                        payloads
+                    );
+                }
+            }
+            CheckType::EventWith(discriminator, predicate) => {
+                let events = events();
+                if !events.iter().any(|event| {
+                    event.is(discriminator) && predicate(&event.data[discriminator.len()..])
+                }) {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: event_with\n  No event with discriminator `{:?}` satisfied \
+                         the predicate",
+                        discriminator
+                    );
+                }
+            }
             CheckType::ResultingAccount(account) => {
                 let pubkey = account.pubkey;
                 let Some(resulting_account) = resulting_accounts
@@ -224,25 +972,9 @@ fn run_checks<C: CheckContext>(
                     pass &= throw!(c, "Account not found in resulting accounts: {}", pubkey);
                     continue;
                 };
-                if let Some(check_data) = account.check_data {
-                    let actual_data = resulting_account.data();
-                    pass &= compare!(c, "account_data", check_data, actual_data);
-                }
-                if let Some(check_executable) = account.check_executable {
-                    let actual_executable = resulting_account.executable();
-                    pass &= compare!(c, "account_executable", check_executable, actual_executable);
-                }
-                if let Some(check_lamports) = account.check_lamports {
-                    let actual_lamports = resulting_account.lamports();
-                    pass &= compare!(c, "account_lamports", check_lamports, actual_lamports);
-                }
-                if let Some(check_owner) = account.check_owner {
-                    let actual_owner = resulting_account.owner();
-                    pass &= compare!(c, "account_owner", check_owner, actual_owner);
-                }
-                if let Some(check_space) = account.check_space {
-                    let actual_space = resulting_account.data().len();
-                    pass &= compare!(c, "account_space", check_space, actual_space);
+                pass &= account.check_fields.check(c, resulting_account);
+                for check_data in &account.check_data_with {
+                    pass &= check_data(c, &resulting_account.data);
                 }
                 if let Some(check_state) = &account.check_state {
                     match check_state {
@@ -268,25 +1000,71 @@ fn run_checks<C: CheckContext>(
                         }
                     }
                 }
-                if let Some((offset, check_data_slice)) = account.check_data_slice {
-                    let actual_data = resulting_account.data();
-                    if offset + check_data_slice.len() > actual_data.len() {
+                if account.check_deltas.is_empty() {
+                    continue;
+                }
+                let Some(input_account) = context.input_account(&pubkey) else {
+                    pass &= throw!(
+                        c,
+                        "Account not found in input accounts: {} (delta checks need the check \
+                         context to provide them)",
+                        pubkey
+                    );
+                    continue;
+                };
+                for check_delta in &account.check_deltas {
+                    pass &= check_delta.check(c, input_account, resulting_account);
+                }
+            }
+            CheckType::ResultingAccountWith(pubkey, check) => {
+                let Some(resulting_account) = resulting_accounts
+                    .iter()
+                    .find(|(k, _)| k == pubkey)
+                    .map(|(_, a)| a)
+                else {
+                    pass &= throw!(c, "Account not found in resulting accounts: {}", pubkey);
+                    continue;
+                };
+                pass &= check(c, resulting_account);
+            }
+            CheckType::AllRentExempt => {
+                for (pubkey, account) in resulting_accounts {
+                    let is_rent_exempt = context.is_rent_exempt(
+                        account.lamports(),
+                        account.data().len(),
+                        account.owner,
+                    );
+                    if !is_rent_exempt {
                         pass &= throw!(
                             c,
-                            "Account data slice: offset {} + slice length {} exceeds account data \
-                             length {}",
-                            offset,
-                            check_data_slice.len(),
-                            actual_data.len(),
+                            "Account {} is not rent exempt after execution (lamports: {}, \
+                             data_len: {})",
+                            pubkey,
+                            account.lamports(),
+                            account.data().len()
                         );
-                        continue;
                     }
-                    let actual_data_slice = &actual_data[offset..offset + check_data_slice.len()];
-                    pass &= compare!(c, "account_data_slice", check_data_slice, actual_data_slice,);
                 }
             }
-            CheckType::AllRentExempt => {
-                for (pubkey, account) in resulting_accounts {
+            CheckType::AllAccountsOwnedBy(program_id, accounts) => {
+                for pubkey in accounts.iter() {
+                    let Some(account) = resulting_accounts
+                        .iter()
+                        .find(|(k, _)| k == pubkey)
+                        .map(|(_, a)| a)
+                    else {
+                        pass &= throw!(c, "Account not found in resulting accounts: {}", pubkey);
+                        continue;
+                    };
+                    if account.owner() != *program_id {
+                        pass &= throw!(
+                            c,
+                            "Account {} is not owned by {} after execution (owner: {})",
+                            pubkey,
+                            program_id,
+                            account.owner(),
+                        );
+                    }
                     let is_rent_exempt = context.is_rent_exempt(
                         account.lamports(),
                         account.data().len(),
@@ -304,12 +1082,78 @@ fn run_checks<C: CheckContext>(
                     }
                 }
             }
+            #[cfg(feature = "fs")]
+            CheckType::Snapshot(path) => {
+                let program_result = match program_result {
+                    Ok(program_result) => program_result,
+                    Err(error) => {
+                        pass &= not_executed(c, "snapshot", error);
+                        continue;
+                    }
+                };
+                let actual = snapshot::render(
+                    compute_units_consumed,
+                    program_result,
+                    return_data,
+                    resulting_accounts,
+                );
+                if let Some(mismatch) = snapshot::compare_snapshot(path, &actual) {
+                    pass &= throw!(c, "CHECK FAILED: snapshot\n  {}", mismatch);
+                }
+            }
+            CheckType::ExpectFailureAndContinue => {
+                if program_result.is_ok_and(ProgramResult::is_ok) {
+                    pass &= throw!(c, "CHECK FAILED: expected failure, got success");
+                }
+            }
+            CheckType::NoUndeclaredWrites => {
+                for pubkey in actually_modified {
+                    if !declared_writable.contains(pubkey) {
+                        pass &= throw!(
+                            c,
+                            "CHECK FAILED: account {} was modified but not declared writable",
+                            pubkey
+                        );
+                    }
+                }
+            }
             #[cfg(feature = "inner-instructions")]
             CheckType::InnerInstructionCount(count) => {
                 let check_count = *count;
                 let actual_count = inner_instructions.len();
                 pass &= compare!(c, "inner_instruction_count", check_count, actual_count);
             }
+            #[cfg(feature = "inner-instructions")]
+            CheckType::InnerInstruction(check) => {
+                let Some(inner_instruction) = inner_instructions.get(check.index) else {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: inner instruction {} not found ({} invoked)",
+                        check.index,
+                        inner_instructions.len(),
+                    );
+                    continue;
+                };
+                pass &= check.check(c, inner_instruction, message);
+            }
+            #[cfg(feature = "inner-instructions")]
+            CheckType::CpiTo(program_id) => {
+                let Some(message) = message else {
+                    pass &= throw!(
+                        c,
+                        "CHECK FAILED: cpi_to: no compiled message to resolve account keys"
+                    );
+                    continue;
+                };
+                let account_keys = message.account_keys();
+                let invoked = inner_instructions.iter().any(|inner_instruction| {
+                    let index = inner_instruction.instruction.program_id_index as usize;
+                    account_keys.get(index) == Some(*program_id)
+                });
+                if !invoked {
+                    pass &= throw!(c, "CHECK FAILED: no CPI to program {}", program_id);
+                }
+            }
         }
     }
     pass
@@ -333,11 +1177,19 @@ impl InstructionResult {
             context,
             self.compute_units_consumed,
             self.execution_time,
-            &self.program_result,
+            Ok(&self.program_result),
             &self.return_data,
             &self.resulting_accounts,
+            self.failing_program_id.as_ref(),
+            self.precompile_error.as_ref(),
+            &self.declared_writable,
+            &self.actually_modified,
+            &self.inner_return_data,
+            &|| self.events(),
             #[cfg(feature = "inner-instructions")]
             &self.inner_instructions,
+            #[cfg(feature = "inner-instructions")]
+            self.message.as_ref(),
         )
     }
 }
@@ -355,11 +1207,12 @@ impl TransactionResult {
         context: &C,
     ) -> bool {
         let program_result = match &self.program_result {
-            TransactionProgramResult::Success => ProgramResult::Success,
-            TransactionProgramResult::Failure(_idx, err) => ProgramResult::Failure(err.clone()),
+            TransactionProgramResult::Success => Ok(ProgramResult::Success),
+            TransactionProgramResult::Failure(_idx, err) => Ok(ProgramResult::Failure(err.clone())),
             TransactionProgramResult::UnknownError(_idx, err) => {
-                ProgramResult::UnknownError(err.clone())
+                Ok(ProgramResult::UnknownError(err.clone()))
             }
+            TransactionProgramResult::TransactionError(err) => Err(err),
         };
         run_checks(
             checks,
@@ -367,14 +1220,22 @@ impl TransactionResult {
             context,
             self.compute_units_consumed,
             self.execution_time,
-            &program_result,
+            program_result.as_ref().map_err(|err| *err),
             &self.return_data,
             &self.resulting_accounts,
+            self.failing_program_id.as_ref(),
+            self.precompile_error.as_ref(),
+            &self.declared_writable,
+            &self.actually_modified,
+            &self.inner_return_data,
+            &|| self.events(),
             #[cfg(feature = "inner-instructions")]
             self.inner_instructions
                 .first()
                 .map(Vec::as_slice)
                 .unwrap_or(&[]),
+            #[cfg(feature = "inner-instructions")]
+            self.message.as_ref(),
         )
     }
 }
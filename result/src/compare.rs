@@ -1,15 +1,18 @@
-//! Compare system for comparing two instruction results.
+//! Compare system for comparing two instruction or transaction results.
 
 use {
     crate::{
-        config::{compare, Config},
-        types::InstructionResult,
+        check::account_data_hash,
+        config::{compare, throw, Config},
+        types::{InstructionResult, ProgramResult, TransactionProgramResult, TransactionResult},
     },
-    trezoa_account::ReadableAccount,
+    trezoa_account::{Account, ReadableAccount},
     trezoa_pubkey::Pubkey,
+    std::fmt::Debug,
 };
 
-/// Checks to run between two `InstructionResult` instances.
+/// Checks to run between two `InstructionResult` or `TransactionResult`
+/// instances.
 ///
 /// Similar to `Check`, this allows a developer to dictate the type of checks
 /// to run on two results. This is useful for comparing the results of two
@@ -23,6 +26,9 @@ use {
 pub enum Compare {
     /// Validate compute units consumed.
     ComputeUnits,
+    /// Validate compute units consumed, allowing them to differ by up to the
+    /// provided tolerance, in either direction.
+    ComputeUnitsWithin(u64),
     /// Validate execution time.
     ExecutionTime,
     /// Validate the program result.
@@ -77,6 +83,11 @@ pub enum Compare {
         /// space.
         space: bool,
     },
+    /// Validate the data of all resulting accounts by its SHA-256 hash,
+    /// rather than byte for byte, so a mismatch reports the hashes instead
+    /// of both buffers. Combine with `AllResultingAccounts { data: false, ..
+    /// }` to validate the other fields.
+    ResultingAccountDataHashes,
 }
 
 impl Compare {
@@ -156,7 +167,17 @@ struct CompareAccountFields {
     space: bool,
 }
 
-impl InstructionResult {
+/// The fields shared by instruction and transaction results which can be
+/// compared.
+struct ComparableResult<'a, R> {
+    compute_units_consumed: u64,
+    execution_time: u64,
+    program_result: &'a R,
+    return_data: &'a [u8],
+    resulting_accounts: &'a [(Pubkey, Account)],
+}
+
+impl<R: PartialEq + Debug> ComparableResult<'_, R> {
     fn compare_resulting_accounts(
         &self,
         b: &Self,
@@ -208,8 +229,7 @@ impl InstructionResult {
         pass
     }
 
-    /// Compare an `InstructionResult` against another `InstructionResult`.
-    pub fn compare_with_config(&self, b: &Self, checks: &[Compare], config: &Config) -> bool {
+    fn compare_with_config(&self, b: &Self, checks: &[Compare], config: &Config) -> bool {
         let c = config;
         let mut pass = true;
         for check in checks {
@@ -222,6 +242,21 @@ impl InstructionResult {
                         b.compute_units_consumed
                     );
                 }
+                Compare::ComputeUnitsWithin(tolerance) => {
+                    let difference = self
+                        .compute_units_consumed
+                        .abs_diff(b.compute_units_consumed);
+                    if difference > *tolerance {
+                        pass &= throw!(
+                            c,
+                            "CHECK FAILED: compute_units_consumed\n  Expected: `{} ± {}`,\n Got: \
+                             `{}`",
+                            self.compute_units_consumed,
+                            tolerance,
+                            b.compute_units_consumed
+                        );
+                    }
+                }
                 Compare::ExecutionTime => {
                     pass &= compare!(c, "execution_time", self.execution_time, b.execution_time);
                 }
@@ -231,6 +266,26 @@ impl InstructionResult {
                 Compare::ReturnData => {
                     pass &= compare!(c, "return_data", self.return_data, b.return_data);
                 }
+                Compare::ResultingAccountDataHashes => {
+                    pass &= compare!(
+                        c,
+                        "resulting_accounts_length",
+                        self.resulting_accounts.len(),
+                        b.resulting_accounts.len()
+                    );
+                    for (a, b) in self
+                        .resulting_accounts
+                        .iter()
+                        .zip(b.resulting_accounts.iter())
+                    {
+                        pass &= compare!(
+                            c,
+                            "resulting_account_data_hash",
+                            account_data_hash(a.1.data()),
+                            account_data_hash(b.1.data())
+                        );
+                    }
+                }
                 Compare::AllResultingAccounts {
                     data,
                     executable,
@@ -316,6 +371,24 @@ impl InstructionResult {
         }
         pass
     }
+}
+
+impl InstructionResult {
+    fn comparable(&self) -> ComparableResult<'_, ProgramResult> {
+        ComparableResult {
+            compute_units_consumed: self.compute_units_consumed,
+            execution_time: self.execution_time,
+            program_result: &self.program_result,
+            return_data: &self.return_data,
+            resulting_accounts: &self.resulting_accounts,
+        }
+    }
+
+    /// Compare an `InstructionResult` against another `InstructionResult`.
+    pub fn compare_with_config(&self, b: &Self, checks: &[Compare], config: &Config) -> bool {
+        self.comparable()
+            .compare_with_config(&b.comparable(), checks, config)
+    }
 
     /// Compare an `InstructionResult` against another `InstructionResult`,
     /// panicking on any mismatches.
@@ -326,6 +399,44 @@ impl InstructionResult {
             &Config {
                 panic: true,
                 verbose: true,
+                show_diffs: false,
+                on_failure: None,
+            },
+        );
+    }
+}
+
+impl TransactionResult {
+    fn comparable(&self) -> ComparableResult<'_, TransactionProgramResult> {
+        ComparableResult {
+            compute_units_consumed: self.compute_units_consumed,
+            execution_time: self.execution_time,
+            program_result: &self.program_result,
+            return_data: &self.return_data,
+            resulting_accounts: &self.resulting_accounts,
+        }
+    }
+
+    /// Compare a `TransactionResult` against another `TransactionResult`.
+    ///
+    /// `Compare::ProgramResult` also compares the index of the failing
+    /// instruction, if any.
+    pub fn compare_with_config(&self, b: &Self, checks: &[Compare], config: &Config) -> bool {
+        self.comparable()
+            .compare_with_config(&b.comparable(), checks, config)
+    }
+
+    /// Compare a `TransactionResult` against another `TransactionResult`,
+    /// panicking on any mismatches.
+    pub fn compare(&self, b: &Self) {
+        self.compare_with_config(
+            b,
+            &Compare::everything(),
+            &Config {
+                panic: true,
+                verbose: true,
+                show_diffs: false,
+                on_failure: None,
             },
         );
     }
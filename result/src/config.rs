@@ -1,20 +1,7 @@
 //! Configuration and context for result validation.
 
-use {trezoa_pubkey::Pubkey, trezoa_rent::Rent};
-
-pub struct Config {
-    pub panic: bool,
-    pub verbose: bool,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            panic: true,
-            verbose: false,
-        }
-    }
-}
+pub use crate::primitives::Config;
+use {trezoa_account::Account, trezoa_pubkey::Pubkey, trezoa_rent::Rent};
 
 /// A trait for providing context to the checks.
 ///
@@ -27,40 +14,31 @@ pub trait CheckContext {
     fn is_rent_exempt(&self, lamports: u64, space: usize, owner: Pubkey) -> bool {
         owner.eq(&Pubkey::default()) && lamports == 0 || Rent::default().is_exempt(lamports, space)
     }
+
+    /// The account as it was provided to the instruction, before execution.
+    ///
+    /// Delta checks, such as `AccountCheckBuilder::unchanged`, compare the
+    /// resulting account against it. Mollusk supplies the input accounts
+    /// when validating results itself. A custom context that doesn't provide
+    /// them fails any delta checks.
+    fn input_account(&self, _pubkey: &Pubkey) -> Option<&Account> {
+        None
+    }
 }
 
 macro_rules! compare {
     ($c:expr, $check:expr, $left:expr, $right:expr $(,)?) => {{
-        if $left != $right {
-            let msg = format!(
-                "CHECK FAILED: {}\n  Expected: `{:?}`,\n Got: `{:?}`",
-                $check, $left, $right
-            );
-            if $c.panic {
-                panic!("{}", msg);
-            } else {
-                if $c.verbose {
-                    println!("{}", msg);
-                }
-                return false;
-            }
+        if !$crate::primitives::compare($c, $check, &$left, &$right) {
+            return false;
         }
         true
     }};
 }
 
 macro_rules! throw {
-    ($c:expr, $($arg:tt)+) => {{
-        let msg = format!($($arg)+);
-        if $c.panic {
-            panic!("{}", msg);
-        } else {
-            if $c.verbose {
-                eprintln!("{}", msg);
-            }
-        }
-        false
-    }};
+    ($c:expr, $($arg:tt)+) => {
+        $crate::primitives::fail($c, format!($($arg)+))
+    };
 }
 
 pub(crate) use {compare, throw};
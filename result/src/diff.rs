@@ -0,0 +1,225 @@
+//! Structured diffs between input and resulting accounts.
+//!
+//! When a check fails, the failing field alone rarely explains what the
+//! instruction did. An `AccountDiff` describes every change made to an
+//! account: its lamports, owner, executable flag, and the byte ranges of its
+//! data which changed.
+//!
+//! ```rust,ignore
+//! let result = mollusk.process_instruction(&instruction, &accounts);
+//! for diff in result.diff_accounts(&accounts) {
+//!     println!("{}", diff);
+//! }
+//! ```
+//!
+//! Set `Config::show_diffs` to have Mollusk include these diffs in the panic
+//! message of a failed check.
+
+use {
+    crate::types::{InstructionResult, TransactionResult},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::fmt,
+};
+
+/// The number of bytes rendered per line of a data hexdump.
+const HEXDUMP_WIDTH: usize = 16;
+
+/// A contiguous range of account data which changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DataDiff {
+    /// The offset of the first changed byte.
+    pub offset: usize,
+    /// The bytes in the range before execution. Empty if the account grew.
+    pub before: Vec<u8>,
+    /// The bytes in the range after execution. Empty if the account shrank.
+    pub after: Vec<u8>,
+}
+
+/// The changes made to a single account.
+///
+/// Each field is `None` (or empty) if it didn't change.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountDiff {
+    /// The account's address.
+    pub pubkey: Pubkey,
+    /// The account's lamports, before and after.
+    pub lamports: Option<(u64, u64)>,
+    /// The account's owner, before and after.
+    pub owner: Option<(Pubkey, Pubkey)>,
+    /// The account's executable flag, before and after.
+    pub executable: Option<(bool, bool)>,
+    /// The account's data length, before and after.
+    pub data_len: Option<(usize, usize)>,
+    /// The changed ranges of the account's data.
+    pub data: Vec<DataDiff>,
+}
+
+impl AccountDiff {
+    /// Diff an account's state before and after execution. Returns `None` if
+    /// nothing changed.
+    pub fn new(pubkey: &Pubkey, before: &Account, after: &Account) -> Option<Self> {
+        let diff = Self {
+            pubkey: *pubkey,
+            lamports: changed(before.lamports, after.lamports),
+            owner: changed(before.owner, after.owner),
+            executable: changed(before.executable, after.executable),
+            data_len: changed(before.data.len(), after.data.len()),
+            data: diff_data(&before.data, &after.data),
+        };
+        (diff != Self::default_for(pubkey)).then_some(diff)
+    }
+
+    /// The change in the account's lamports, if any.
+    pub fn lamports_delta(&self) -> Option<i128> {
+        self.lamports
+            .map(|(before, after)| i128::from(after) - i128::from(before))
+    }
+
+    fn default_for(pubkey: &Pubkey) -> Self {
+        Self {
+            pubkey: *pubkey,
+            ..Default::default()
+        }
+    }
+}
+
+fn changed<T: PartialEq>(before: T, after: T) -> Option<(T, T)> {
+    (before != after).then_some((before, after))
+}
+
+/// Collect the contiguous ranges where `before` and `after` differ. Bytes past
+/// the end of the shorter buffer are reported as a single trailing range.
+fn diff_data(before: &[u8], after: &[u8]) -> Vec<DataDiff> {
+    let common = before.len().min(after.len());
+    let mut diffs = Vec::new();
+
+    let mut offset = 0;
+    while offset < common {
+        if before[offset] == after[offset] {
+            offset += 1;
+            continue;
+        }
+        let start = offset;
+        while offset < common && before[offset] != after[offset] {
+            offset += 1;
+        }
+        diffs.push(DataDiff {
+            offset: start,
+            before: before[start..offset].to_vec(),
+            after: after[start..offset].to_vec(),
+        });
+    }
+
+    if before.len() != after.len() {
+        diffs.push(DataDiff {
+            offset: common,
+            before: before[common..].to_vec(),
+            after: after[common..].to_vec(),
+        });
+    }
+
+    diffs
+}
+
+/// Diff each account in `after` against its state in `before`.
+///
+/// Accounts missing from `before` are treated as default (empty) accounts.
+/// Only accounts which changed are returned, in the order of `after`.
+pub fn diff_accounts(
+    before: &[(Pubkey, Account)],
+    after: &[(Pubkey, Account)],
+) -> Vec<AccountDiff> {
+    let empty = Account::default();
+    after
+        .iter()
+        .filter_map(|(pubkey, resulting)| {
+            let input = before
+                .iter()
+                .find(|(k, _)| k == pubkey)
+                .map(|(_, a)| a)
+                .unwrap_or(&empty);
+            AccountDiff::new(pubkey, input, resulting)
+        })
+        .collect()
+}
+
+/// Render a list of diffs, one account after another.
+pub fn format_diffs(diffs: &[AccountDiff]) -> String {
+    if diffs.is_empty() {
+        return "No accounts changed.\n".to_string();
+    }
+    diffs.iter().map(|diff| diff.to_string()).collect()
+}
+
+fn write_hex(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for DataDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.before.len().max(self.after.len());
+        let mut line = 0;
+        while line < len {
+            let end = (line + HEXDUMP_WIDTH).min(len);
+            let before = &self.before[line.min(self.before.len())..end.min(self.before.len())];
+            let after = &self.after[line.min(self.after.len())..end.min(self.after.len())];
+            writeln!(f, "    {:#06x}:", self.offset + line)?;
+            write!(f, "      - ")?;
+            write_hex(f, before)?;
+            writeln!(f)?;
+            write!(f, "      + ")?;
+            write_hex(f, after)?;
+            writeln!(f)?;
+            line = end;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AccountDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Account {}:", self.pubkey)?;
+        if let (Some((before, after)), Some(delta)) = (self.lamports, self.lamports_delta()) {
+            writeln!(f, "  lamports: {} -> {} ({:+})", before, after, delta)?;
+        }
+        if let Some((before, after)) = self.owner {
+            writeln!(f, "  owner: {} -> {}", before, after)?;
+        }
+        if let Some((before, after)) = self.executable {
+            writeln!(f, "  executable: {} -> {}", before, after)?;
+        }
+        if let Some((before, after)) = self.data_len {
+            writeln!(f, "  data length: {} -> {}", before, after)?;
+        }
+        if !self.data.is_empty() {
+            writeln!(f, "  data:")?;
+            for data in &self.data {
+                write!(f, "{}", data)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InstructionResult {
+    /// Diff the resulting accounts against the accounts the instruction was
+    /// processed with.
+    pub fn diff_accounts(&self, input_accounts: &[(Pubkey, Account)]) -> Vec<AccountDiff> {
+        diff_accounts(input_accounts, &self.resulting_accounts)
+    }
+}
+
+impl TransactionResult {
+    /// Diff the resulting accounts against the accounts the transaction was
+    /// processed with.
+    pub fn diff_accounts(&self, input_accounts: &[(Pubkey, Account)]) -> Vec<AccountDiff> {
+        diff_accounts(input_accounts, &self.resulting_accounts)
+    }
+}
@@ -0,0 +1,177 @@
+//! Anchor-style program events.
+//!
+//! Anchor programs emit events in one of two ways:
+//!
+//! * `emit!` logs the event as base64, in a `Program data: ` log.
+//! * `emit_cpi!` invokes the program itself (a self-CPI), with the event as
+//!   instruction data, prefixed by `EVENT_IX_TAG_LE`.
+//!
+//! Either way, the event is an 8-byte discriminator, followed by the
+//! serialized event. `InstructionResult::events` collects the events from
+//! both sources, and `Check::event_emitted` and friends assert on them.
+//!
+//! ```rust,ignore
+//! use mollusk_svm_result::Check;
+//!
+//! mollusk.process_and_validate_instruction(
+//!     &instruction,
+//!     &accounts,
+//!     &[
+//!         Check::success(),
+//!         Check::event_emitted(&MY_EVENT_DISCRIMINATOR),
+//!     ],
+//! );
+//!
+//! let result = mollusk.process_instruction(&instruction, &accounts);
+//! let events: Vec<MyEvent> = result.decode_events(&MY_EVENT_DISCRIMINATOR, |data| {
+//!     MyEvent::try_from_slice(data).ok()
+//! });
+//! ```
+//!
+//! Self-CPI events require the `inner-instructions` feature.
+
+#[cfg(feature = "inner-instructions")]
+use trezoa_transaction_status_client_types::InnerInstruction;
+use {
+    crate::types::{InstructionResult, TransactionResult},
+    base64::{prelude::BASE64_STANDARD, Engine},
+};
+
+/// The length of an event discriminator.
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// The prefix of the instruction data of a self-CPI event, as emitted by
+/// Anchor's `emit_cpi!`.
+pub const EVENT_IX_TAG_LE: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+
+const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// How an event was emitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventSource {
+    /// A `Program data: ` log.
+    Log,
+    /// A self-CPI.
+    SelfCpi,
+}
+
+/// An event emitted by a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// How the event was emitted.
+    pub source: EventSource,
+    /// The event's discriminator, followed by the serialized event.
+    pub data: Vec<u8>,
+}
+
+impl Event {
+    /// The event's discriminator.
+    pub fn discriminator(&self) -> &[u8] {
+        &self.data[..DISCRIMINATOR_LEN.min(self.data.len())]
+    }
+
+    /// The serialized event, following the discriminator.
+    pub fn payload(&self) -> &[u8] {
+        &self.data[DISCRIMINATOR_LEN.min(self.data.len())..]
+    }
+
+    /// Whether the event has the provided discriminator.
+    pub fn is(&self, discriminator: &[u8]) -> bool {
+        self.data.starts_with(discriminator)
+    }
+
+    /// Decode the event with the provided function, if it has the provided
+    /// discriminator. The function is passed the payload, without the
+    /// discriminator.
+    pub fn decode<T>(
+        &self,
+        discriminator: &[u8],
+        decode: impl FnOnce(&[u8]) -> Option<T>,
+    ) -> Option<T> {
+        self.is(discriminator)
+            .then(|| decode(&self.data[discriminator.len()..]))
+            .flatten()
+    }
+}
+
+/// Extract the events emitted via `Program data: ` logs. Logs which aren't
+/// valid base64 are skipped.
+pub fn events_from_logs(logs: &[String]) -> Vec<Event> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix(PROGRAM_DATA_LOG_PREFIX))
+        // A single log may hold several space-separated payloads, but
+        // Anchor always emits one.
+        .filter_map(|data| data.split(' ').next())
+        .filter_map(|data| BASE64_STANDARD.decode(data).ok())
+        .map(|data| Event {
+            source: EventSource::Log,
+            data,
+        })
+        .collect()
+}
+
+/// Extract the events emitted via self-CPIs, identified by instruction data
+/// starting with `EVENT_IX_TAG_LE`.
+#[cfg(feature = "inner-instructions")]
+pub fn events_from_inner_instructions(inner_instructions: &[InnerInstruction]) -> Vec<Event> {
+    inner_instructions
+        .iter()
+        .filter_map(|inner| inner.instruction.data.strip_prefix(&EVENT_IX_TAG_LE))
+        .map(|data| Event {
+            source: EventSource::SelfCpi,
+            data: data.to_vec(),
+        })
+        .collect()
+}
+
+impl InstructionResult {
+    /// The events emitted during execution. Events emitted via logs come
+    /// first, followed by those emitted via self-CPIs.
+    pub fn events(&self) -> Vec<Event> {
+        #[allow(unused_mut)]
+        let mut events = events_from_logs(&self.logs);
+        #[cfg(feature = "inner-instructions")]
+        events.extend(events_from_inner_instructions(&self.inner_instructions));
+        events
+    }
+
+    /// Decode every event with the provided discriminator. See
+    /// `Event::decode`.
+    pub fn decode_events<T>(
+        &self,
+        discriminator: &[u8],
+        decode: impl Fn(&[u8]) -> Option<T>,
+    ) -> Vec<T> {
+        self.events()
+            .iter()
+            .filter_map(|event| event.decode(discriminator, &decode))
+            .collect()
+    }
+}
+
+impl TransactionResult {
+    /// The events emitted during execution, across all instructions. Events
+    /// emitted via logs come first, followed by those emitted via self-CPIs.
+    pub fn events(&self) -> Vec<Event> {
+        #[allow(unused_mut)]
+        let mut events = events_from_logs(&self.logs);
+        #[cfg(feature = "inner-instructions")]
+        for inner_instructions in &self.inner_instructions {
+            events.extend(events_from_inner_instructions(inner_instructions));
+        }
+        events
+    }
+
+    /// Decode every event with the provided discriminator. See
+    /// `Event::decode`.
+    pub fn decode_events<T>(
+        &self,
+        discriminator: &[u8],
+        decode: impl Fn(&[u8]) -> Option<T>,
+    ) -> Vec<T> {
+        self.events()
+            .iter()
+            .filter_map(|event| event.decode(discriminator, &decode))
+            .collect()
+    }
+}
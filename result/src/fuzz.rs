@@ -50,11 +50,23 @@ impl From<&mollusk_svm_fuzz_fixture::effects::Effects> for InstructionResult {
             program_result,
             raw_result,
             return_data,
+            inner_return_data: vec![],
             resulting_accounts,
+            instruction_trace_length: 0,
+            max_stack_height: 0,
+            accounts_resize_delta: 0,
+            failing_program_id: None,
+            precompile_error: None,
+            declared_writable: vec![],
+            actually_modified: vec![],
+            referenced_accounts: vec![],
+            profile: Default::default(),
+            execution_mode: None,
+            logs: vec![],
             #[cfg(feature = "inner-instructions")]
             inner_instructions: vec![],
-            #[cfg(feature = "inner-instructions")]
             message: None,
+            failure_context: None,
         }
     }
 }
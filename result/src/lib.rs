@@ -7,7 +7,6 @@
 //!
 //! * [`InstructionResult`] - The main result type containing execution details
 //! * [`ProgramResult`] - The program's execution outcome (success/failure)
-//! * [`ContextResult`] - Result type for use with `MolluskContext`
 //!
 //! # Validation
 //!
@@ -16,6 +15,31 @@
 //! * [`Config`] - Configuration for validation behavior
 //! * [`CheckContext`] - Context trait for custom validation logic
 //!
+//! Changes made to accounts can be inspected with
+//! [`InstructionResult::diff_accounts`]. See the [`diff`] module.
+//!
+//! Anchor-style events emitted by programs can be extracted and checked. See
+//! the [`events`] module.
+//!
+//! Return data set by programs invoked via CPI, not only the outermost one,
+//! can be extracted and checked. See the [`return_data`] module.
+//!
+//! Compute unit consumption per instruction, CPIs included, along with
+//! syscall and VM instruction counts, is recorded in a deterministic
+//! execution profile. See the [`profile`] module.
+//!
+//! With the `fs` feature, results can also be validated against golden
+//! files. See the [`snapshot`] module.
+//!
+//! # Features
+//!
+//! * `sdk` (default) - Everything above, built on the Trezoa SDK types.
+//! * `std` (default, implied by `sdk`) - Printing verbose check failures.
+//! * `fs` - Snapshot files.
+//!
+//! With default features disabled, only the SDK-independent [`primitives`]
+//! are available, and the crate is `no_std`.
+//!
 //! # Example
 //!
 //! ```rust,ignore
@@ -28,18 +52,41 @@
 //! result.run_checks(&checks, &config, &mollusk);
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "sdk")]
 pub mod check;
+#[cfg(feature = "sdk")]
 pub mod compare;
+#[cfg(feature = "sdk")]
 pub mod config;
+#[cfg(feature = "sdk")]
+pub mod diff;
+#[cfg(feature = "sdk")]
+pub mod events;
 #[cfg(feature = "fuzz")]
 pub mod fuzz;
+pub mod primitives;
+#[cfg(feature = "sdk")]
+pub mod profile;
+#[cfg(feature = "sdk")]
+pub mod return_data;
+#[cfg(feature = "fs")]
+pub mod snapshot;
+#[cfg(feature = "sdk")]
 pub mod types;
 
+#[cfg(feature = "inner-instructions")]
+pub use check::InnerInstructionCheckBuilder;
+pub use primitives::{CheckFailureRecord, Config};
 // Re-export the main types and traits for convenience, and for backwards
 // compatibility.
+#[cfg(feature = "sdk")]
 pub use {
     check::{AccountCheckBuilder, Check},
     compare::Compare,
-    config::{CheckContext, Config},
-    types::{InstructionResult, ProgramResult},
+    config::CheckContext,
+    types::{ExecutionMode, InstructionResult, ProgramResult},
 };
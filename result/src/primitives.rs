@@ -0,0 +1,394 @@
+//! Comparison primitives, independent of the Trezoa SDK.
+//!
+//! This module is always available, including with default features
+//! disabled, where the crate is `no_std` and pulls in no Trezoa crates. Tools
+//! which embed Mollusk's validation logic - for example, in wasm - can check
+//! their own account types by implementing `AccountFields`.
+//!
+//! ```toml
+//! mollusk-svm-result = { version = "*", default-features = false }
+//! ```
+
+use {
+    alloc::{boxed::Box, format, string::String},
+    core::fmt::Debug,
+};
+
+/// A failed check, as reported to `Config::on_failure`.
+#[derive(Debug)]
+pub struct CheckFailureRecord {
+    /// The check which failed (ie. `account_lamports`), for checks which
+    /// compare an expected value against an actual one.
+    pub check: Option<String>,
+    /// The expected value, formatted with `Debug`.
+    pub expected: Option<String>,
+    /// The actual value, formatted with `Debug`.
+    pub actual: Option<String>,
+    /// The message Mollusk would otherwise panic with or print.
+    pub message: String,
+}
+
+/// Configuration for how failed checks are reported.
+///
+/// New options may be added, so configs are built from `Config::default()`
+/// with the setters below.
+///
+/// ```rust,ignore
+/// let config = Config::default().panic(false).verbose(true);
+/// ```
+#[non_exhaustive]
+pub struct Config {
+    /// Panic on the first failed check.
+    pub panic: bool,
+    /// Print failed checks when not panicking. Requires `std`.
+    pub verbose: bool,
+    /// Include a diff of the input and resulting accounts when a check
+    /// fails. Only honored by harnesses which know the input accounts, such
+    /// as Mollusk.
+    pub show_diffs: bool,
+    /// Route failed checks to a callback, rather than panicking or printing.
+    /// Useful for test frameworks with their own reporting.
+    pub on_failure: Option<Box<dyn Fn(&CheckFailureRecord)>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            panic: true,
+            verbose: false,
+            show_diffs: false,
+            on_failure: None,
+        }
+    }
+}
+
+impl Config {
+    /// Panic on the first failed check.
+    pub fn panic(mut self, panic: bool) -> Self {
+        self.panic = panic;
+        self
+    }
+
+    /// Print failed checks when not panicking. Requires `std`.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Include a diff of the input and resulting accounts when a check
+    /// fails.
+    pub fn show_diffs(mut self, show_diffs: bool) -> Self {
+        self.show_diffs = show_diffs;
+        self
+    }
+
+    /// Route failed checks to the provided callback, rather than panicking
+    /// or printing.
+    pub fn on_failure(mut self, on_failure: impl Fn(&CheckFailureRecord) + 'static) -> Self {
+        self.on_failure = Some(Box::new(on_failure));
+        self
+    }
+}
+
+/// Report a failed check according to the provided config.
+///
+/// Always returns `false`, so the result can be folded into a pass flag.
+pub fn fail(config: &Config, msg: String) -> bool {
+    let record = CheckFailureRecord {
+        check: None,
+        expected: None,
+        actual: None,
+        message: msg,
+    };
+    if !report(config, &record) {
+        #[cfg(feature = "std")]
+        std::eprintln!("{}", record.message);
+    }
+    false
+}
+
+/// Compare an expected value against an actual value, reporting a mismatch
+/// according to the provided config.
+pub fn compare<L, R>(config: &Config, check: &str, expected: L, actual: R) -> bool
+where
+    L: PartialEq<R> + Debug,
+    R: Debug,
+{
+    if expected == actual {
+        return true;
+    }
+    let expected = format!("{:?}", expected);
+    let actual = format!("{:?}", actual);
+    let message = format!(
+        "CHECK FAILED: {}\n  Expected: `{}`,\n Got: `{}`",
+        check, expected, actual
+    );
+    let record = CheckFailureRecord {
+        check: Some(check.into()),
+        expected: Some(expected),
+        actual: Some(actual),
+        message,
+    };
+    if !report(config, &record) {
+        #[cfg(feature = "std")]
+        std::println!("{}", record.message);
+    }
+    false
+}
+
+// Hand a failure to the config's sink, or panic. Returns `true` unless the
+// caller should print the failure.
+fn report(config: &Config, record: &CheckFailureRecord) -> bool {
+    if let Some(on_failure) = &config.on_failure {
+        on_failure(record);
+        return true;
+    }
+    if config.panic {
+        panic!("{}", record.message);
+    }
+    !config.verbose
+}
+
+/// Read access to the fields of an account.
+pub trait AccountFields {
+    /// The type of the account's owner.
+    type Owner: PartialEq + Debug + ?Sized;
+
+    fn lamports(&self) -> u64;
+    fn data(&self) -> &[u8];
+    fn owner(&self) -> &Self::Owner;
+    fn executable(&self) -> bool;
+    fn rent_epoch(&self) -> u64;
+}
+
+/// Expected values for the fields of an account. Unset fields aren't
+/// checked.
+pub struct AccountExpectation<'a, O: ?Sized> {
+    pub data: Option<&'a [u8]>,
+    /// An offset into the account's data, and the bytes expected there.
+    pub data_slice: Option<(usize, &'a [u8])>,
+    pub executable: Option<bool>,
+    pub lamports: Option<u64>,
+    pub owner: Option<&'a O>,
+    pub rent_epoch: Option<u64>,
+    pub space: Option<usize>,
+}
+
+impl<O: ?Sized> Default for AccountExpectation<'_, O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: ?Sized> AccountExpectation<'_, O> {
+    /// Create an expectation which checks nothing.
+    pub const fn new() -> Self {
+        Self {
+            data: None,
+            data_slice: None,
+            executable: None,
+            lamports: None,
+            owner: None,
+            rent_epoch: None,
+            space: None,
+        }
+    }
+}
+
+impl<O: PartialEq + Debug + ?Sized> AccountExpectation<'_, O> {
+    /// Check the provided account against the expectation.
+    pub fn check<A: AccountFields<Owner = O> + ?Sized>(
+        &self,
+        config: &Config,
+        account: &A,
+    ) -> bool {
+        let mut pass = true;
+        if let Some(check_data) = self.data {
+            pass &= compare(config, "account_data", check_data, account.data());
+        }
+        if let Some(check_executable) = self.executable {
+            pass &= compare(
+                config,
+                "account_executable",
+                check_executable,
+                account.executable(),
+            );
+        }
+        if let Some(check_lamports) = self.lamports {
+            pass &= compare(
+                config,
+                "account_lamports",
+                check_lamports,
+                account.lamports(),
+            );
+        }
+        if let Some(check_owner) = self.owner {
+            pass &= compare(config, "account_owner", check_owner, account.owner());
+        }
+        if let Some(check_rent_epoch) = self.rent_epoch {
+            pass &= compare(
+                config,
+                "account_rent_epoch",
+                check_rent_epoch,
+                account.rent_epoch(),
+            );
+        }
+        if let Some(check_space) = self.space {
+            pass &= compare(config, "account_space", check_space, account.data().len());
+        }
+        if let Some((offset, check_data_slice)) = self.data_slice {
+            let actual_data = account.data();
+            let Some(actual_data_slice) = actual_data.get(offset..offset + check_data_slice.len())
+            else {
+                return fail(
+                    config,
+                    format!(
+                        "Account data slice: offset {} + slice length {} exceeds account data \
+                         length {}",
+                        offset,
+                        check_data_slice.len(),
+                        actual_data.len(),
+                    ),
+                );
+            };
+            pass &= compare(
+                config,
+                "account_data_slice",
+                check_data_slice,
+                actual_data_slice,
+            );
+        }
+        pass
+    }
+}
+
+#[cfg(feature = "sdk")]
+impl AccountFields for trezoa_account::Account {
+    type Owner = trezoa_pubkey::Pubkey;
+
+    fn lamports(&self) -> u64 {
+        self.lamports
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn owner(&self) -> &Self::Owner {
+        &self.owner
+    }
+
+    fn executable(&self) -> bool {
+        self.executable
+    }
+
+    fn rent_epoch(&self) -> u64 {
+        self.rent_epoch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        alloc::{rc::Rc, vec::Vec},
+        core::cell::RefCell,
+    };
+
+    // An account type outside the Trezoa SDK, with a string owner.
+    struct TestAccount {
+        lamports: u64,
+        data: Vec<u8>,
+        owner: &'static str,
+    }
+
+    impl AccountFields for TestAccount {
+        type Owner = str;
+
+        fn lamports(&self) -> u64 {
+            self.lamports
+        }
+
+        fn data(&self) -> &[u8] {
+            &self.data
+        }
+
+        fn owner(&self) -> &str {
+            self.owner
+        }
+
+        fn executable(&self) -> bool {
+            false
+        }
+
+        fn rent_epoch(&self) -> u64 {
+            0
+        }
+    }
+
+    fn account() -> TestAccount {
+        TestAccount {
+            lamports: 42,
+            data: [1, 2, 3, 4].to_vec(),
+            owner: "owner",
+        }
+    }
+
+    #[test]
+    fn test_account_expectation() {
+        let config = Config::default();
+        let expectation = AccountExpectation {
+            data: Some(&[1, 2, 3, 4]),
+            data_slice: Some((1, &[2, 3])),
+            executable: Some(false),
+            lamports: Some(42),
+            owner: Some("owner"),
+            rent_epoch: Some(0),
+            space: Some(4),
+        };
+        assert!(expectation.check(&config, &account()));
+        assert!(AccountExpectation::new().check(&config, &account()));
+    }
+
+    #[test]
+    fn test_failing_account_expectation() {
+        let failures = Rc::new(RefCell::new(Vec::new()));
+        let config = Config::default().on_failure({
+            let failures = Rc::clone(&failures);
+            move |record| failures.borrow_mut().push(record.check.clone())
+        });
+
+        let expectation = AccountExpectation {
+            lamports: Some(43),
+            owner: Some("other"),
+            ..AccountExpectation::new()
+        };
+        assert!(!expectation.check(&config, &account()));
+        assert_eq!(
+            *failures.borrow(),
+            [
+                Some(String::from("account_lamports")),
+                Some(String::from("account_owner")),
+            ]
+        );
+
+        // A slice past the end of the data fails without a comparison.
+        failures.borrow_mut().clear();
+        let expectation = AccountExpectation::<str> {
+            data_slice: Some((3, &[4, 5])),
+            ..AccountExpectation::new()
+        };
+        assert!(!expectation.check(&config, &account()));
+        assert_eq!(*failures.borrow(), [None::<String>]);
+    }
+
+    #[test]
+    #[should_panic(expected = "CHECK FAILED: account_space")]
+    fn test_failing_account_expectation_panics() {
+        let expectation = AccountExpectation::<str> {
+            space: Some(5),
+            ..AccountExpectation::new()
+        };
+        expectation.check(&Config::default(), &account());
+    }
+}
@@ -0,0 +1,126 @@
+//! Deterministic execution profiles.
+//!
+//! `execution_time` is measured with a wall clock, so it varies from run to
+//! run. An `ExecutionProfile` only records values which are stable across
+//! runs, so a test can assert on them to catch regressions:
+//!
+//! ```rust,ignore
+//! let result = mollusk.process_instruction(&instruction, &accounts);
+//! let profile = &result.profile;
+//!
+//! // Compute units consumed by the CPI to the Token program.
+//! let cpi = profile
+//!     .instructions
+//!     .iter()
+//!     .find(|ix| ix.stack_height == 2 && ix.program_id == spl_token::id())
+//!     .unwrap();
+//! assert_eq!(cpi.compute_units_consumed, Some(4_645));
+//! ```
+//!
+//! Syscall and VM instruction counts require register tracing, which is
+//! enabled with the `register-tracing` feature and `SBF_TRACE_DIR`, or with
+//! `Mollusk::new_debuggable`.
+
+use {trezoa_pubkey::Pubkey, std::collections::BTreeMap};
+
+/// The profile of a single instruction, top-level or CPI.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstructionProfile {
+    /// The program invoked.
+    pub program_id: Pubkey,
+    /// The stack height of the invocation. Top-level instructions have a
+    /// stack height of 1.
+    pub stack_height: usize,
+    /// The compute units consumed by the instruction, including those
+    /// consumed by its CPIs.
+    ///
+    /// Always recorded for top-level instructions. For CPIs, it's taken from
+    /// the program's logs, so it's `None` for programs which don't log their
+    /// consumption, such as builtins.
+    pub compute_units_consumed: Option<u64>,
+}
+
+/// A deterministic profile of an execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionProfile {
+    /// Every instruction invoked, in invocation order, with each CPI
+    /// following the instruction which invoked it.
+    pub instructions: Vec<InstructionProfile>,
+    /// The number of times each syscall was invoked, by name.
+    ///
+    /// Only recorded when register tracing is enabled.
+    pub syscall_counts: BTreeMap<String, u64>,
+    /// The number of VM instructions executed across all SBF programs.
+    ///
+    /// `None` unless register tracing is enabled.
+    pub vm_instruction_count: Option<u64>,
+}
+
+impl ExecutionProfile {
+    /// The profiles of the top-level instructions.
+    pub fn top_level_instructions(&self) -> impl Iterator<Item = &InstructionProfile> {
+        self.instructions.iter().filter(|ix| ix.stack_height == 1)
+    }
+
+    /// Mutable access to the profiles of the top-level instructions.
+    pub fn top_level_instructions_mut(&mut self) -> impl Iterator<Item = &mut InstructionProfile> {
+        self.instructions
+            .iter_mut()
+            .filter(|ix| ix.stack_height == 1)
+    }
+
+    /// Merge the profile of a subsequent execution into this one.
+    pub fn absorb(&mut self, other: Self) {
+        self.instructions.extend(other.instructions);
+        for (name, count) in other.syscall_counts {
+            *self.syscall_counts.entry(name).or_default() += count;
+        }
+        self.vm_instruction_count = match (self.vm_instruction_count, other.vm_instruction_count) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Fill in the compute units consumed by each instruction from the
+    /// `Program <id> consumed <n> of <m> compute units` logs, which are
+    /// matched against the instructions in invocation order.
+    pub fn record_logged_compute_units(&mut self, logs: &[String]) {
+        let mut next = 0;
+        let mut stack = Vec::new();
+        for log in logs {
+            let Some(rest) = log.strip_prefix("Program ") else {
+                continue;
+            };
+            let Some((program_id, event)) = rest.split_once(' ') else {
+                continue;
+            };
+            let Ok(program_id) = program_id.parse::<Pubkey>() else {
+                continue;
+            };
+
+            if event.starts_with("invoke [") {
+                // Instructions which don't log their invocation, such as
+                // precompiles, are skipped.
+                let Some(offset) = self.instructions[next..]
+                    .iter()
+                    .position(|ix| ix.program_id == program_id)
+                else {
+                    return;
+                };
+                stack.push(next + offset);
+                next += offset + 1;
+            } else if let Some(consumed) = event.strip_prefix("consumed ") {
+                let consumed = consumed
+                    .split_once(" of ")
+                    .and_then(|(consumed, _)| consumed.parse().ok());
+                if let (Some(&index), Some(consumed)) = (stack.last(), consumed) {
+                    self.instructions[index]
+                        .compute_units_consumed
+                        .get_or_insert(consumed);
+                }
+            } else if event == "success" || event.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+}
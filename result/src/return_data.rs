@@ -0,0 +1,40 @@
+//! Return data set at each level of the invocation stack.
+//!
+//! A result's `return_data` is whatever return data was left when execution
+//! finished, so data returned by an invoked program is lost once its caller
+//! sets its own. Mollusk records the return data each time a program sets
+//! it, CPIs included, in the result's `inner_return_data`.
+//!
+//! ```rust,ignore
+//! use mollusk_svm_result::Check;
+//!
+//! let result = mollusk.process_and_validate_instruction(
+//!     &instruction,
+//!     &accounts,
+//!     &[
+//!         Check::success(),
+//!         Check::inner_return_data(&callee_program_id, &42u64.to_le_bytes()),
+//!     ],
+//! );
+//!
+//! for returned in &result.inner_return_data {
+//!     println!("{} @ {}: {:?}", returned.program_id, returned.stack_height, returned.data);
+//! }
+//! ```
+//!
+//! Return data is recorded as SBF programs set it through the
+//! `sol_set_return_data` syscall, so builtin programs aren't covered.
+
+use trezoa_pubkey::Pubkey;
+
+/// Return data set by a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InnerReturnData {
+    /// The program which set the data.
+    pub program_id: Pubkey,
+    /// The stack height of the invocation which set the data. Top-level
+    /// instructions have a stack height of 1, so anything greater is a CPI.
+    pub stack_height: usize,
+    /// The returned data.
+    pub data: Vec<u8>,
+}
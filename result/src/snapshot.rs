@@ -0,0 +1,131 @@
+//! Snapshot (golden file) testing for execution results.
+//!
+//! A snapshot is a deterministic, human-readable rendering of a result's
+//! compute units, program result, return data, and resulting accounts.
+//! Execution time is omitted, since it varies between runs.
+//!
+//! Save a snapshot with `InstructionResult::save_snapshot`, then validate
+//! later runs against it with `Check::matches_snapshot`. To regenerate
+//! snapshots instead of validating them, set `MOLLUSK_UPDATE_SNAPSHOTS`:
+//!
+//! ```ignore
+//! MOLLUSK_UPDATE_SNAPSHOTS=1 cargo test-sbf ...
+//! ```
+
+use {
+    crate::types::{InstructionResult, ProgramResult},
+    trezoa_account::Account,
+    trezoa_pubkey::Pubkey,
+    std::{fmt::Write, path::Path},
+};
+
+/// Environment variable which, when set, causes snapshot checks to
+/// overwrite their snapshot files rather than compare against them.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "MOLLUSK_UPDATE_SNAPSHOTS";
+
+const BYTES_PER_LINE: usize = 32;
+
+/// Returns `true` if snapshots should be regenerated.
+pub fn update_snapshots() -> bool {
+    std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some()
+}
+
+fn write_bytes(out: &mut String, label: &str, bytes: &[u8]) {
+    writeln!(out, "{}: {} bytes", label, bytes.len()).unwrap();
+    for chunk in bytes.chunks(BYTES_PER_LINE) {
+        out.push_str("  ");
+        for byte in chunk {
+            write!(out, "{:02x}", byte).unwrap();
+        }
+        out.push('\n');
+    }
+}
+
+/// Render the snapshot of a result.
+pub fn render(
+    compute_units_consumed: u64,
+    program_result: &ProgramResult,
+    return_data: &[u8],
+    resulting_accounts: &[(Pubkey, Account)],
+) -> String {
+    let mut out = String::new();
+    writeln!(out, "compute_units_consumed: {}", compute_units_consumed).unwrap();
+    writeln!(out, "program_result: {:?}", program_result).unwrap();
+    write_bytes(&mut out, "return_data", return_data);
+    for (pubkey, account) in resulting_accounts {
+        writeln!(out, "account {}", pubkey).unwrap();
+        writeln!(out, "lamports: {}", account.lamports).unwrap();
+        writeln!(out, "owner: {}", account.owner).unwrap();
+        writeln!(out, "executable: {}", account.executable).unwrap();
+        writeln!(out, "rent_epoch: {}", account.rent_epoch).unwrap();
+        write_bytes(&mut out, "data", &account.data);
+    }
+    out
+}
+
+/// Compare a rendered snapshot against the snapshot file at `path`.
+///
+/// Returns a description of the first mismatch, or `None` if the snapshots
+/// match. If `MOLLUSK_UPDATE_SNAPSHOTS` is set, the file is overwritten
+/// instead.
+pub(crate) fn compare_snapshot(path: &Path, actual: &str) -> Option<String> {
+    if update_snapshots() {
+        write_snapshot(path, actual)
+            .unwrap_or_else(|err| panic!("Failed to write snapshot {}: {}", path.display(), err));
+        return None;
+    }
+
+    let Ok(expected) = std::fs::read_to_string(path) else {
+        return Some(format!(
+            "Snapshot not found: {}. Set {} to create it.",
+            path.display(),
+            UPDATE_SNAPSHOTS_ENV,
+        ));
+    };
+
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return None,
+            (expected, actual) if expected == actual => line += 1,
+            (expected, actual) => {
+                return Some(format!(
+                    "Snapshot mismatch: {} (line {})\n  Expected: `{}`,\n Got: `{}`\nSet {} to \
+                     update it.",
+                    path.display(),
+                    line,
+                    expected.unwrap_or("<end of snapshot>"),
+                    actual.unwrap_or("<end of snapshot>"),
+                    UPDATE_SNAPSHOTS_ENV,
+                ));
+            }
+        }
+    }
+}
+
+fn write_snapshot(path: &Path, snapshot: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, snapshot)
+}
+
+impl InstructionResult {
+    /// Render the result's snapshot. See the `snapshot` module.
+    pub fn snapshot(&self) -> String {
+        render(
+            self.compute_units_consumed,
+            &self.program_result,
+            &self.return_data,
+            &self.resulting_accounts,
+        )
+    }
+
+    /// Write the result's snapshot to the provided path, creating any
+    /// missing parent directories.
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        write_snapshot(path.as_ref(), &self.snapshot())
+    }
+}
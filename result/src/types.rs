@@ -1,12 +1,20 @@
 //! Core result types for SVM program execution.
 
 use {
-    trezoa_account::Account, trezoa_instruction::error::InstructionError,
-    trezoa_program_error::ProgramError, trezoa_pubkey::Pubkey,
+    crate::{
+        profile::{ExecutionProfile, InstructionProfile},
+        return_data::InnerReturnData,
+    },
+    trezoa_account::Account,
+    trezoa_instruction::error::InstructionError,
+    trezoa_message::SanitizedMessage,
+    trezoa_precompile_error::PrecompileError,
+    trezoa_program_error::ProgramError,
+    trezoa_pubkey::Pubkey,
     trezoa_transaction_error::TransactionError,
 };
 #[cfg(feature = "inner-instructions")]
-use {trezoa_message::SanitizedMessage, trezoa_transaction_status_client_types::InnerInstruction};
+use trezoa_transaction_status_client_types::InnerInstruction;
 
 /// The result code of the program's execution.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -46,6 +54,32 @@ impl From<Result<(), InstructionError>> for ProgramResult {
     }
 }
 
+/// How SBF programs were executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Programs were JIT-compiled to native code.
+    Jit,
+    /// Programs were executed by the SBPF interpreter.
+    Interpreted,
+}
+
+/// The state of execution when an instruction failed, before its changes
+/// were discarded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FailureContext {
+    /// Every instruction invoked up to the failure, CPIs included, in
+    /// invocation order, with its stack height and compute units consumed.
+    pub instruction_trace: Vec<InstructionProfile>,
+    /// The program logs emitted up to the failure.
+    pub logs: Vec<String>,
+    /// The compute units consumed up to the failure.
+    pub compute_units_consumed: u64,
+    /// The accounts as they were when the instruction failed, in the order
+    /// they were provided. Unlike `resulting_accounts`, which reverts to the
+    /// input accounts, these hold any changes made before the failure.
+    pub accounts: Vec<(Pubkey, Account)>,
+}
+
 /// The overall result of the instruction.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InstructionResult {
@@ -59,12 +93,87 @@ pub struct InstructionResult {
     pub raw_result: Result<(), InstructionError>,
     /// The return data produced by the instruction, if any.
     pub return_data: Vec<u8>,
+    /// The return data set by each program invocation, CPIs included, in
+    /// the order it was set. See the [`return_data`](crate::return_data)
+    /// module.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub inner_return_data: Vec<InnerReturnData>,
     /// The resulting accounts after executing the instruction.
     ///
     /// This includes all accounts provided to the processor, in the order
     /// they were provided. Any accounts that were modified will maintain
     /// their original position in this list, but with updated state.
     pub resulting_accounts: Vec<(Pubkey, Account)>,
+    /// The number of instructions in the instruction trace, including CPIs.
+    ///
+    /// This is `0` when the result is loaded from a fuzz fixture.
+    pub instruction_trace_length: usize,
+    /// The maximum stack height reached during execution. Top-level
+    /// instructions have a stack height of 1, so anything greater indicates
+    /// a CPI.
+    ///
+    /// This is `0` when the result is loaded from a fuzz fixture.
+    pub max_stack_height: usize,
+    /// The net change in account data size, in bytes, accumulated during
+    /// execution, CPIs included. This is what the runtime limits with
+    /// `MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION`, and
+    /// Mollusk with `accounts_resize_delta_limit`.
+    ///
+    /// This is `0` when the result is loaded from a fuzz fixture.
+    pub accounts_resize_delta: i64,
+    /// The deepest program which failed, if any. When a CPI fails, this is
+    /// the callee rather than the top-level program, which disambiguates
+    /// custom error codes shared by several programs.
+    ///
+    /// The program is found from the instruction trace, so it doesn't
+    /// depend on the logs. It's `None` when the result is loaded from a
+    /// fuzz fixture.
+    pub failing_program_id: Option<Pubkey>,
+    /// The specific error returned by a failing precompile, such as a
+    /// secp256k1 signature verification failure. The runtime reports
+    /// precompile failures with a generic instruction error, so this is the
+    /// only record of the exact failure.
+    ///
+    /// This is `None` when the result is loaded from a fuzz fixture.
+    pub precompile_error: Option<PrecompileError>,
+    /// The accounts the compiled message declared writable, computed before
+    /// execution.
+    ///
+    /// For instruction chains, this is the union across the chain, less any
+    /// account an instruction modified without declaring it writable.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub declared_writable: Vec<Pubkey>,
+    /// The accounts whose state actually changed during execution. Empty if
+    /// the execution failed, since its changes are discarded.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub actually_modified: Vec<Pubkey>,
+    /// The accounts referenced by the instruction trace, in the order they
+    /// were first referenced: the accounts of every instruction, CPIs
+    /// included, along with the programs invoked.
+    ///
+    /// An account is listed when it's passed to an instruction, whether or
+    /// not the program reads it. Sysvars read through syscalls are served by
+    /// the sysvar cache rather than an account, so they're only listed if
+    /// passed as an account.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub referenced_accounts: Vec<Pubkey>,
+    /// A deterministic profile of the execution, for regression checks. See
+    /// the [`profile`](crate::profile) module.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub profile: ExecutionProfile,
+    /// How SBF programs were executed.
+    ///
+    /// This is `None` when the result is loaded from a fuzz fixture.
+    pub execution_mode: Option<ExecutionMode>,
+    /// The program logs emitted during execution.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub logs: Vec<String>,
     /// Inner instructions (CPIs) invoked during the instruction execution.
     ///
     /// Each entry represents a cross-program invocation made by the program,
@@ -74,13 +183,19 @@ pub struct InstructionResult {
     pub inner_instructions: Vec<InnerInstruction>,
     /// The compiled message used to execute the instruction.
     ///
-    /// This can be used to map account indices in inner instructions back to
-    /// their corresponding pubkeys via `message.account_keys()`.
+    /// This can be used to verify the compiled account ordering, or to map
+    /// account indices (ie. in inner instructions) back to their
+    /// corresponding pubkeys via `message.account_keys()`.
     ///
     /// This is `None` when the result is loaded from a fuzz fixture, since
     /// fixtures don't contain the compiled message.
-    #[cfg(feature = "inner-instructions")]
     pub message: Option<SanitizedMessage>,
+    /// The state of execution when the instruction failed, for diagnosing
+    /// failures without rerunning them under a debugger. `None` if the
+    /// instruction succeeded, or failed before anything was executed.
+    ///
+    /// This is `None` when the result is loaded from a fuzz fixture.
+    pub failure_context: Option<FailureContext>,
 }
 
 impl Default for InstructionResult {
@@ -91,11 +206,23 @@ impl Default for InstructionResult {
             program_result: ProgramResult::Success,
             raw_result: Ok(()),
             return_data: vec![],
+            inner_return_data: vec![],
             resulting_accounts: vec![],
+            instruction_trace_length: 0,
+            max_stack_height: 0,
+            accounts_resize_delta: 0,
+            failing_program_id: None,
+            precompile_error: None,
+            declared_writable: vec![],
+            actually_modified: vec![],
+            referenced_accounts: vec![],
+            profile: ExecutionProfile::default(),
+            execution_mode: None,
+            logs: vec![],
             #[cfg(feature = "inner-instructions")]
             inner_instructions: vec![],
-            #[cfg(feature = "inner-instructions")]
             message: None,
+            failure_context: None,
         }
     }
 }
@@ -115,12 +242,54 @@ impl InstructionResult {
         self.program_result = other.program_result;
         self.raw_result = other.raw_result;
         self.return_data = other.return_data;
+        self.inner_return_data.extend(other.inner_return_data);
         self.resulting_accounts = other.resulting_accounts;
+        self.instruction_trace_length += other.instruction_trace_length;
+        self.max_stack_height = self.max_stack_height.max(other.max_stack_height);
+        self.accounts_resize_delta += other.accounts_resize_delta;
+        self.failing_program_id = other.failing_program_id;
+        self.precompile_error = other.precompile_error;
+        // Writes are checked against each instruction's own declarations, so
+        // an account written without being declared writable by one
+        // instruction isn't covered by another instruction declaring it.
+        let undeclared = |declared: &[Pubkey], modified: &[Pubkey]| {
+            modified
+                .iter()
+                .filter(|key| !declared.contains(key))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let mut undeclared_writes = undeclared(&self.declared_writable, &self.actually_modified);
+        undeclared_writes.extend(undeclared(
+            &other.declared_writable,
+            &other.actually_modified,
+        ));
+        for key in other.declared_writable {
+            if !self.declared_writable.contains(&key) {
+                self.declared_writable.push(key);
+            }
+        }
+        self.declared_writable
+            .retain(|key| !undeclared_writes.contains(key));
+        for key in other.actually_modified {
+            if !self.actually_modified.contains(&key) {
+                self.actually_modified.push(key);
+            }
+        }
+        for key in other.referenced_accounts {
+            if !self.referenced_accounts.contains(&key) {
+                self.referenced_accounts.push(key);
+            }
+        }
+        self.profile.absorb(other.profile);
+        self.execution_mode = other.execution_mode;
+        self.logs.extend(other.logs);
         #[cfg(feature = "inner-instructions")]
         {
             self.inner_instructions = other.inner_instructions;
-            self.message = other.message;
         }
+        self.message = other.message;
+        self.failure_context = other.failure_context;
     }
 }
 
@@ -133,6 +302,9 @@ pub enum TransactionProgramResult {
     Failure(usize, ProgramError),
     /// Mollusk encountered an error while executing the program.
     UnknownError(usize, InstructionError),
+    /// The transaction failed before any program was invoked, such as when
+    /// it exceeds a transaction limit or its fee payer can't pay the fee.
+    TransactionError(TransactionError),
 }
 
 impl TransactionProgramResult {
@@ -147,6 +319,24 @@ impl TransactionProgramResult {
     }
 }
 
+/// The outcome of a single top-level instruction within a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstructionExecutionSummary {
+    /// The program invoked by the instruction.
+    pub program_id: Pubkey,
+    /// The result code of the instruction's execution.
+    pub program_result: ProgramResult,
+    /// The raw result of the instruction's execution.
+    pub raw_result: Result<(), InstructionError>,
+    /// The number of compute units consumed by the instruction, including
+    /// any CPIs it made.
+    pub compute_units_consumed: u64,
+    /// The return data set during the instruction, if any.
+    pub return_data: Vec<u8>,
+    /// The program logs emitted during the instruction.
+    pub logs: Vec<String>,
+}
+
 /// The overall result of the transaction.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TransactionResult {
@@ -160,12 +350,93 @@ pub struct TransactionResult {
     pub raw_result: Result<(), TransactionError>,
     /// The return data produced by the transaction, if any.
     pub return_data: Vec<u8>,
+    /// The return data set by each program invocation, across all
+    /// instructions, in the order it was set. See the
+    /// [`return_data`](crate::return_data) module.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub inner_return_data: Vec<InnerReturnData>,
     /// The resulting accounts after executing the transaction.
     ///
     /// This includes all accounts provided to the processor, in the order
     /// they were provided. Any accounts that were modified will maintain
     /// their original position in this list, but with updated state.
     pub resulting_accounts: Vec<(Pubkey, Account)>,
+    /// The number of instructions in the instruction trace, including CPIs.
+    ///
+    /// This is `0` when the result is loaded from a fuzz fixture.
+    pub instruction_trace_length: usize,
+    /// The maximum stack height reached during execution. Top-level
+    /// instructions have a stack height of 1, so anything greater indicates
+    /// a CPI.
+    ///
+    /// This is `0` when the result is loaded from a fuzz fixture.
+    pub max_stack_height: usize,
+    /// The net change in account data size, in bytes, accumulated during
+    /// execution, CPIs included. This is what the runtime limits with
+    /// `MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION`, and
+    /// Mollusk with `accounts_resize_delta_limit`.
+    ///
+    /// This is `0` when the result is loaded from a fuzz fixture.
+    pub accounts_resize_delta: i64,
+    /// The deepest program which failed, if any. When a CPI fails, this is
+    /// the callee rather than the top-level program, which disambiguates
+    /// custom error codes shared by several programs.
+    ///
+    /// The program is found from the instruction trace, so it doesn't
+    /// depend on the logs. It's `None` when the result is loaded from a
+    /// fuzz fixture.
+    pub failing_program_id: Option<Pubkey>,
+    /// The specific error returned by a failing precompile, such as a
+    /// secp256k1 signature verification failure. The runtime reports
+    /// precompile failures with a generic instruction error, so this is the
+    /// only record of the exact failure.
+    ///
+    /// This is `None` when the result is loaded from a fuzz fixture.
+    pub precompile_error: Option<PrecompileError>,
+    /// The accounts the compiled message declared writable, computed before
+    /// execution.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub declared_writable: Vec<Pubkey>,
+    /// The accounts whose state actually changed during execution. Empty if
+    /// the execution failed, since its changes are discarded.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub actually_modified: Vec<Pubkey>,
+    /// The accounts made available to a program during execution, in the
+    /// order they were first used: the accounts of every instruction, CPIs
+    /// included, along with the programs invoked. Accounts which aren't
+    /// listed can be pruned from the test setup.
+    ///
+    /// Sysvars read through syscalls are served by the sysvar cache rather
+    /// than an account, so they're only listed if passed as an account.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub referenced_accounts: Vec<Pubkey>,
+    /// A deterministic profile of the execution, for regression checks. See
+    /// the [`profile`](crate::profile) module.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub profile: ExecutionProfile,
+    /// How SBF programs were executed.
+    ///
+    /// This is `None` when the result is loaded from a fuzz fixture.
+    pub execution_mode: Option<ExecutionMode>,
+    /// The program logs emitted during execution.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture.
+    pub logs: Vec<String>,
+    /// A summary of each top-level instruction processed, in message order,
+    /// up to and including the one which failed, if any. Instructions after
+    /// a failure aren't executed, so they aren't listed.
+    ///
+    /// Compute budget program instructions are listed as succeeding without
+    /// consuming compute units, since they aren't executed.
+    ///
+    /// This is empty when the result is loaded from a fuzz fixture, or when
+    /// the transaction failed before execution.
+    pub per_instruction: Vec<InstructionExecutionSummary>,
     /// Inner instructions (CPIs) invoked during the transaction execution.
     ///
     /// Each entry represents a cross-program invocation made by the program,
@@ -175,11 +446,11 @@ pub struct TransactionResult {
     pub inner_instructions: Vec<Vec<InnerInstruction>>,
     /// The compiled message used to execute the transaction.
     ///
-    /// This can be used to map account indices in inner instructions back to
-    /// their corresponding pubkeys via `message.account_keys()`.
+    /// This can be used to verify the compiled account ordering, or to map
+    /// account indices (ie. in inner instructions) back to their
+    /// corresponding pubkeys via `message.account_keys()`.
     ///
     /// This is `None` when the result is loaded from a fuzz fixture, since
     /// fixtures don't contain the compiled message.
-    #[cfg(feature = "inner-instructions")]
     pub message: Option<SanitizedMessage>,
 }
@@ -0,0 +1,51 @@
+#![cfg(target_os = "trezoa")]
+
+use {trezoa_account_info::AccountInfo, trezoa_program_error::ProgramError, trezoa_pubkey::Pubkey};
+
+extern "C" {
+    fn sol_get_sysvar(
+        sysvar_id_addr: *const u8,
+        var_addr: *mut u8,
+        offset: u64,
+        length: u64,
+    ) -> u64;
+}
+
+trezoa_program_entrypoint::entrypoint!(process_instruction);
+
+fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> Result<(), ProgramError> {
+    // Input: sysvar_id (32) | offset (8) | length (8)
+    if input.len() != 48 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let sysvar_id = Pubkey::new_from_array(input[0..32].try_into().unwrap());
+    let offset = u64::from_le_bytes(input[32..40].try_into().unwrap());
+    let length = u64::from_le_bytes(input[40..48].try_into().unwrap());
+
+    // Read the requested range directly into the output account.
+    let mut data = accounts[0].try_borrow_mut_data()?;
+    if (length as usize) > data.len() {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    let result = unsafe {
+        sol_get_sysvar(
+            sysvar_id.as_ref().as_ptr(),
+            data.as_mut_ptr(),
+            offset,
+            length,
+        )
+    };
+
+    // Surface the syscall's status code as a custom error.
+    // 1: Offset + length exceeds the sysvar's size.
+    // 2: Sysvar not found.
+    match result {
+        0 => Ok(()),
+        code => Err(ProgramError::Custom(code as u32)),
+    }
+}
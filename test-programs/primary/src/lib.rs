@@ -1,6 +1,6 @@
 use {
     trezoa_account_info::{next_account_info, AccountInfo},
-    trezoa_cpi::invoke,
+    trezoa_cpi::{invoke, set_return_data},
     trezoa_instruction::{AccountMeta, Instruction},
     trezoa_program_error::{ProgramError, ProgramResult},
     trezoa_pubkey::{Pubkey, PUBKEY_BYTES},
@@ -11,7 +11,7 @@ trezoa_pubkey::declare_id!("239vxAL9Q7e3uLoinJpJ873r3bvT9sPFxH7yekwPppNF");
 trezoa_program_entrypoint::entrypoint!(process_instruction);
 
 fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
@@ -115,6 +115,54 @@ fn process_instruction(
                 return Err(ProgramError::MissingRequiredSignature);
             }
         }
+        Some((6, rest)) => {
+            // Write the remaining data to the first account, without checking
+            // whether it's writable.
+            let account_info = next_account_info(accounts_iter)?;
+
+            if rest.len() > account_info.data_len() {
+                return Err(ProgramError::AccountDataTooSmall);
+            }
+
+            account_info.try_borrow_mut_data()?[..rest.len()].copy_from_slice(rest);
+        }
+        Some((7, rest)) if rest.len() == 4 => {
+            // Resize the first account to the provided length, invoke this
+            // program with the account, then restore its original length.
+            let account_info = next_account_info(accounts_iter)?;
+
+            let original_len = account_info.data_len();
+            let len = u32::from_le_bytes(rest.try_into().unwrap()) as usize;
+
+            account_info.resize(len)?;
+
+            let instruction = Instruction::new_with_bytes(
+                *program_id,
+                &[0],
+                vec![AccountMeta::new(*account_info.key, false)],
+            );
+
+            invoke(&instruction, std::slice::from_ref(account_info))?;
+
+            account_info.resize(original_len)?;
+        }
+        Some((8, rest)) => {
+            // Return the remaining data.
+            set_return_data(rest);
+        }
+        Some((9, rest)) => {
+            // Log the remaining data, as programs emitting events do.
+            #[cfg(target_os = "trezoa")]
+            unsafe {
+                let fields: &[&[u8]] = &[rest];
+                trezoa_define_syscall::definitions::sol_log_data(
+                    fields.as_ptr() as *const u8,
+                    fields.len() as u64,
+                );
+            }
+            #[cfg(not(target_os = "trezoa"))]
+            let _ = rest;
+        }
         _ => return Err(ProgramError::InvalidInstructionData),
     }
 